@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+use bio_rust::universe::Universe;
+
+// Compares the scalar per-cell tick against the bit-packed one, packed and unpacked every
+// generation (`tick`) or kept packed over the whole run (`tick_many`), and kept packed while
+// finding the cells each generation changed, as the simulation thread does.
+// Run with `cargo bench --bench tick`.
+fn pseudo_dna(len: usize) -> Vec<u8> {
+    let mut state: u64 = 0x9E3779B97F4A7C15;
+    (0..len)
+        .map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            b"ACGT"[(state >> 62) as usize]
+        })
+        .collect()
+}
+
+fn time_ticks(universe: &mut Universe, generations: u32, tick: fn(&mut Universe)) -> Duration {
+    let start = Instant::now();
+    for _ in 0..generations {
+        tick(universe);
+    }
+    start.elapsed()
+}
+
+fn main() {
+    for &(rows, cols, generations) in &[(64, 64, 500), (256, 256, 100), (1024, 1024, 10)] {
+        let dna = pseudo_dna((rows * cols) as usize);
+
+        let mut scalar = Universe::new(rows, cols, &dna);
+        let mut packed = Universe::new(rows, cols, &dna);
+        let mut run = Universe::new(rows, cols, &dna);
+
+        let scalar_time = time_ticks(&mut scalar, generations, Universe::tick_scalar);
+        let packed_time = time_ticks(&mut packed, generations, Universe::tick);
+        let start = Instant::now();
+        run.tick_many(u64::from(generations));
+        let run_time = start.elapsed();
+        let mut worker = Universe::new(rows, cols, &dna).packed();
+        let start = Instant::now();
+        for _ in 0..generations {
+            let previous = worker.step();
+            std::hint::black_box(worker.grid.changed(&previous));
+        }
+        let worker_time = start.elapsed();
+        let mut stepped = vec![false; scalar.cells.len()];
+        worker.grid.write_cells(&mut stepped);
+        assert_eq!(scalar.cells, packed.cells, "backends diverged");
+        assert_eq!(scalar.cells, run.cells, "backends diverged");
+        assert_eq!(scalar.cells, stepped, "backends diverged");
+
+        let speedup = |time: Duration| scalar_time.as_secs_f64() / time.as_secs_f64();
+        println!(
+            "{rows}x{cols} x{generations}: scalar {:?}/gen, packed {:?}/gen ({:.1}x), kept packed {:?}/gen ({:.1}x), with changes {:?}/gen ({:.1}x)",
+            scalar_time / generations,
+            packed_time / generations,
+            speedup(packed_time),
+            run_time / generations,
+            speedup(run_time),
+            worker_time / generations,
+            speedup(worker_time),
+        );
+    }
+}
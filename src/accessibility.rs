@@ -0,0 +1,78 @@
+use std::process::Command;
+
+use tracing::warn;
+
+// Settings for people who need more contrast, bigger text or the state read out: a
+// high-contrast palette with thick outlines around live cells, a whole multiple of the
+// overlay text size, and announcements of the generation and population on stdout (which
+// screen readers follow in a terminal), optionally passed on to a notification command
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accessibility {
+    pub high_contrast: bool,
+    pub text_scale: u32,
+    // Announce the generation and population every this many generations, and extinction
+    // or revival as it happens; None stays quiet
+    pub announce_every: Option<u64>,
+    // Program and arguments run with each announcement appended, e.g. `notify-send`
+    pub announce_command: Option<Vec<String>>,
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Self { high_contrast: false, text_scale: 1, announce_every: None, announce_command: None }
+    }
+}
+
+// Overlay text sizes, as multiples of the normal one, that still leave room for the grid
+pub const MAX_TEXT_SCALE: u32 = 3;
+
+// Keeps track of what was announced, so each change is announced once
+#[derive(Default)]
+pub struct Announcer {
+    next: u64,
+    extinct: bool,
+}
+
+impl Announcer {
+    // Announcements due after stepping to `generation` with `population` live cells
+    pub fn update(&mut self, every: u64, generation: u64, population: usize) -> Vec<String> {
+        let mut announcements = Vec::new();
+        if (population == 0) != self.extinct {
+            self.extinct = population == 0;
+            announcements.push(match self.extinct {
+                true => format!("All cells died at generation {generation}"),
+                false => format!("Cells came back at generation {generation}, population {population}"),
+            });
+        }
+        if generation >= self.next {
+            announcements.push(format!("Generation {generation}, population {population}"));
+            self.next = (generation / every + 1) * every;
+        }
+        announcements
+    }
+}
+
+pub fn announce(settings: &Accessibility, text: &str) {
+    println!("{text}");
+    if let Some((program, args)) = settings.announce_command.as_ref().and_then(|command| command.split_first())
+        && let Err(err) = Command::new(program).args(args).arg(text).spawn()
+    {
+        warn!("Failed to run announcement command {program}: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn announces_periodically_and_on_extinction() {
+        let mut announcer = Announcer::default();
+        assert_eq!(announcer.update(100, 0, 5), ["Generation 0, population 5"]);
+        assert!(announcer.update(100, 99, 5).is_empty());
+        assert_eq!(announcer.update(100, 150, 0), ["All cells died at generation 150", "Generation 150, population 0"]);
+        assert!(announcer.update(100, 151, 0).is_empty());
+        assert_eq!(announcer.update(100, 160, 2), ["Cells came back at generation 160, population 2"]);
+        assert_eq!(announcer.update(100, 200, 2).len(), 1);
+    }
+}
@@ -0,0 +1,527 @@
+use std::path::PathBuf;
+
+use crate::automaton::lenia::KernelSource;
+use crate::export::sheet::SheetSettings;
+use crate::export::table::Column;
+use crate::formats::bam::GenomicRegion;
+use crate::formats::vcf::VariantFilter;
+use crate::layers::{Layer, Layers};
+use crate::mutation::{MutationParams, MutationTarget};
+use crate::presets::{Preset, Slot};
+use crate::render::post::Effect;
+use crate::rule::Rule;
+use crate::selection::Region;
+use crate::tools::Tool;
+use crate::universe::Anchor;
+use crate::viewport::Fit;
+use crate::seq_analysis::codon_usage::{CdsSource, CodonSort};
+use crate::seq_analysis::complexity::ComplexityParams;
+use crate::seq_analysis::difference::Comparison;
+use crate::seq_analysis::frames::ReadingFrame;
+use crate::seq_analysis::signals::SignalFilter;
+use crate::seq_analysis::repeats::RepeatParams;
+use crate::seq_analysis::motif::Motif;
+use crate::seq_analysis::pcr::PrimerPair;
+use crate::seq_analysis::primers::PrimerConstraints;
+use crate::seq_analysis::pwm::Background;
+use crate::seq_analysis::shuffle::ShuffleKind;
+
+// Everything the user can ask the app to do. Keyboard, mouse and console input are all
+// translated into actions and executed by `App::apply`, so every input path behaves the same.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    ToggleCell { row: u32, col: u32 },
+    // Bring cells to life or kill them, as one edit (a paint or erase stroke)
+    Paint { alive: bool, cells: Vec<(u32, u32)> },
+    // Log a cell's state, neighbors and the base it was seeded from
+    InspectCell { row: u32, col: u32 },
+    // Pick what a left click or drag on the grid does
+    SetTool(Tool),
+    Jump(u64),
+    // Reseed the selected region, or the whole grid without a selection, from a sequence offset
+    Seed { offset: usize },
+    // Reseed the selection (or the whole grid) from the read depth over a BAM/CRAM region:
+    // bases covered by at least `min_depth` reads start alive. CRAM needs its reference.
+    SeedDepth { path: PathBuf, region: GenomicRegion, min_depth: u32, reference: Option<PathBuf> },
+    // Replace the sequence with the first record of a FASTA file, read in the background, and
+    // reseed the whole grid from it once loaded
+    Open(PathBuf),
+    // Like Open, with another record of a multi-FASTA than the first (from 0, shown from 1)
+    OpenRecord { path: PathBuf, record: usize },
+    // Like Open, with a sequence downloaded from NCBI by accession (`--features net`)
+    Fetch(String),
+    // Like Open, but with every record of a multi-FASTA seeding a row of its own, labeled with
+    // the record's id
+    OpenRows(PathBuf),
+    // Clear the selection (or the whole grid) and put a Life 1.06 or plaintext pattern in its
+    // middle
+    LoadPattern(PathBuf),
+    // Save the live cells of the selection (or the whole grid) as a Life 1.06 or plaintext pattern
+    SavePattern(PathBuf),
+    // Compare the sequence with the first record of a FASTA file and reseed the selection
+    // (or the whole grid) so only differing positions start alive; None goes back to G/C seeding
+    Compare(Option<(PathBuf, Comparison)>),
+    // Read an aligned FASTA or Clustal alignment whose first sequence is the one shown and
+    // chart the conservation of its columns as a track behind the cells; with a threshold,
+    // also reseed the selection (or the whole grid) so only positions conserved less than it
+    // start alive. None drops the track and goes back to seeding from the sequence.
+    LoadAlignment(Option<(PathBuf, Option<f64>)>),
+    // Reseed the selection (or the whole grid) from a shuffle of the sequence with this RNG
+    // seed, as a control for order-dependent dynamics; None goes back to the original
+    Shuffle(Option<(ShuffleKind, u64)>),
+    // Reseed the grid from the offset it was last seeded from as a whole, with the cells whose
+    // codon in this reading frame codes for a hydrophobic amino acid alive, and color the cells
+    // by amino acid; None goes back to seeding from the sequence
+    ReadFrame(Option<ReadingFrame>),
+    SetRule(Rule),
+    // Use a rule of the rule library by name
+    UseNamedRule(String),
+    // Show or hide the rule library panel
+    ShowRuleLibrary(bool),
+    // Add the current rule to the rule library and save it to the config file
+    SaveNamedRule { name: String, description: String },
+    // Define preset A or B
+    SetPreset(Slot, Preset),
+    // Keep the current rule, seeding and theme as preset A or B
+    SavePreset(Slot),
+    // Switch to the preset's rule, seeding and theme and reseed the grid from the offset it was
+    // last seeded from as a whole, so both presets start from the same cells
+    UsePreset(Slot),
+    // Cells in GC-poor and GC-rich stretches of the sequence follow these rules instead of
+    // the current one; None gives every cell the current rule again
+    GcRules(Option<(Rule, Rule)>),
+    // Flip one bit of the rule every this many generations, chosen by the next codon of the
+    // sequence; None stops the walk and keeps the current rule
+    WalkRules(Option<u64>),
+    // Mark the walk's current rule as interesting, for the export
+    MarkRule,
+    ShowRuleHistory,
+    ExportRuleHistory(PathBuf),
+    // Generations per second
+    SetSpeed(f64),
+    // Step this many generations every frame whatever the speed, or go back to the speed
+    Turbo(Option<u32>),
+    // Stop or resume stepping the simulation
+    Pause(bool),
+    ExportFrame(PathBuf),
+    // Capture a frame every so many generations from now on into a contact sheet, written
+    // once it is full; None writes the thumbnails taken so far
+    ContactSheet(Option<SheetSettings>),
+    // Write these columns for every generation stepped from now on to an Arrow IPC or
+    // Parquet file; None finishes the file
+    RecordTable(Option<(PathBuf, Vec<Column>)>),
+    // Write a hash of the cells for every generation stepped from now on, to verify another
+    // run against; None finishes the file
+    RecordHashes(Option<PathBuf>),
+    // Compare every generation stepped from now on with the hashes of a recorded run, pausing
+    // at the first that differs; None stops comparing
+    Verify(Option<PathBuf>),
+    // Draw the cells with a WGSL shader from disk, recompiled whenever the file is saved;
+    // None goes back to the built-in shader
+    LoadShader(Option<PathBuf>),
+    // Load a Rhai script providing next() and/or seed(); None unloads the current script
+    LoadScript(Option<PathBuf>),
+    // Per-cell signal from a bedGraph/wig file, for one chromosome (the first when None);
+    // None clears the signal
+    LoadSignal(Option<(PathBuf, Option<String>)>),
+    // Mark the variants of a VCF file on one chromosome (the first when None) that pass
+    // the filter; None clears them
+    LoadVariants(Option<(PathBuf, Option<String>, VariantFilter)>),
+    // Minimum signal a dead cell needs to be born; None lets every cell be born
+    SetBirthThreshold(Option<f32>),
+    // Highlight motif matches on the grid; None clears the highlights
+    SearchMotif(Option<Motif>),
+    // Mark start and stop codons and ribosome-binding motifs on the grid with glyphs, those
+    // of some kinds in some frames; None clears them
+    ShowSignals(Option<SignalFilter>),
+    // Find tandem repeats, shade them on the grid and list them in a panel; None clears them
+    FindRepeats(Option<RepeatParams>),
+    // Find where a primer pair binds with up to this many mismatches and highlight the
+    // products it would amplify; None clears the highlights
+    Pcr(Option<(PrimerPair, usize)>),
+    // Propose primers within the constraints from the bases seeded into the selection, listed
+    // in a panel; None closes the panel
+    DesignPrimers(Option<PrimerConstraints>),
+    // Copy the proposed primer with this number, or all of them, to the clipboard
+    CopyPrimers(Option<usize>),
+    // Score every position against a PWM file, highlighting sites whose relative score
+    // (0..1) reaches the threshold; None clears the highlights
+    ScanPwm(Option<(PathBuf, f64)>),
+    SetPwmBackground(Background),
+    // Find homopolymer runs and low-complexity regions and underline them; when the flag is
+    // set they also stay dead when seeding. None clears the mask.
+    MaskLowComplexity(Option<(ComplexityParams, bool)>),
+    // Count codon usage over the coding sequences and color their cells by how preferred
+    // each codon is; None clears the table and the coloring
+    CodonUsage(Option<CdsSource>),
+    // Reorder and print the codon usage table
+    SortCodons(CodonSort),
+    ExportCodons(PathBuf),
+    // Turn a post-processing effect of the grid window on or off
+    SetEffect(Effect, bool),
+    // Color cells by how many generations they have been alive instead of their state
+    ShowHeatmap(bool),
+    // Remember the current cells, for the diff view
+    MarkGeneration,
+    // Color cells by whether they were born, died or stayed alive since the marked generation
+    ShowDiff(bool),
+    ResetHeatmap,
+    // Save the heat map as a PNG (one pixel per cell) or, for .csv paths, the raw counts
+    ExportHeatmap(PathBuf),
+    // Draw the trail of the population centroid and show its drift in the title
+    ShowTrail(bool),
+    // Label the connected groups of live cells every generation and follow the largest
+    TrackBlobs(bool),
+    // Color the cells by the group they belong to
+    ColorBlobs(bool),
+    // Log the number and sizes of the groups of live cells
+    ShowBlobStats,
+    // Show or hide the coordinate ruler along the grid
+    ShowRuler(bool),
+    // Outline the connected live regions
+    ShowContours(bool),
+    // Fade born cells in and shrink dying ones out over the tick interval, or step crisply
+    SmoothTransitions(bool),
+    // Draw each cell's base letter inside it once the cells are big enough
+    ShowLetters(bool),
+    // Draw the cells on a 3D torus instead of the flat grid
+    ShowTorus(bool),
+    // Draw the cells as columns as tall as they are old, at an isometric angle
+    ShowHeightfield(bool),
+    // Save a SimulationReport (population, drift, centroid trajectory) as TSV
+    ExportReport(PathBuf),
+    // Save the sequence, cells, generation, rule and speed as a compressed session, or put a
+    // saved one back
+    SaveSession(PathBuf),
+    LoadSession(PathBuf),
+    // Replace the Life-like rule with ants (up to this many) walking the grid, or go back
+    Ants(Option<usize>),
+    // Replace the Life-like rule with the cyclic A > C > G > T > A automaton at this
+    // takeover threshold, or go back
+    Cyclic(Option<u8>),
+    // Replace the Life-like rule with the protein automaton of amino-acid property classes,
+    // or go back
+    Protein(bool),
+    // Replace the Life-like rule with Lenia, its kernel standard or derived from the
+    // sequence, or go back
+    Lenia(Option<KernelSource>),
+    // Print the ants' step count, visited cells and positions
+    ShowAntStats,
+    // Show or hide the sequence strip along the bottom of the window
+    ShowStrip(bool),
+    // Scroll the sequence strip to start at this position
+    ScrollStrip(usize),
+    // Chart the named sequence-analysis track below the grid (and behind the cells when the
+    // track layer is on), or the next one when None
+    SelectTrack(Option<String>),
+    ShowTrackLayer(bool),
+    // Show or hide one layer of the stack the grid is composited from, set how opaque it is
+    // (0 to 1), or move it one place up or down the stack
+    ShowLayer(Layer, bool),
+    SetLayerOpacity(Layer, f32),
+    MoveLayer { layer: Layer, up: bool },
+    // Replace the whole stack, bottom layer first
+    SetLayers(Layers),
+    // Show or hide the panel with a checkbox for each layer
+    ShowLayerPanel(bool),
+    // Print the tracks that can be selected
+    ListTracks,
+    // Save the view, the seeding windows and the cells at this generation in slot 1..=9,
+    // named after the slot unless a name is given
+    SaveBookmark { slot: usize, name: Option<String> },
+    // Go back to the bookmark with this slot number or name
+    GotoBookmark(String),
+    ListBookmarks,
+    // Attach a note to the base a cell was seeded from, or remove its note with None
+    Annotate { row: u32, col: u32, text: Option<String> },
+    ListNotes,
+    // Save the notes as BED, on the given chromosome name or "sequence"
+    ExportNotes { path: PathBuf, chrom: Option<String> },
+    // Save the bases of the live cells as merged BED intervals, on the given chromosome name
+    // or "sequence"
+    ExportAlive { path: PathBuf, chrom: Option<String> },
+    // Open or close the second window with the analysis charts
+    ShowAnalysis(bool),
+    // Render the analysis charts alone to a PNG, whether or not their window is open
+    ExportAnalysis(PathBuf),
+    // Show or hide the overlay listing the key bindings, mode and rule
+    ShowHelp(bool),
+    // Show or hide the overlay timing each GPU pass and the CPU side of the frame
+    ShowProfiler(bool),
+    // Revert or reapply the last manual edit (toggle or reseed)
+    Undo,
+    Redo,
+    ToggleBackground,
+    // How cells are scaled into the window when their size is automatic
+    SetFit(Fit),
+    // Gap between cells as a share of their pitch, 0 to 0.9
+    SetPadding(f32),
+    // Fixed cell side in logical pixels, or None to fit the cells to the window
+    SetCellSize(Option<u32>),
+    // How far the grid is moved from the middle of its area, in logical pixels right and up
+    SetPan([i32; 2]),
+    // Draw blocks of cells once they are smaller than a pixel (on by default), or every cell
+    SetLevelOfDetail(bool),
+    // Print the cell size, padding, fit and pan
+    ShowLayout,
+    // Give the grid a new size, keeping the live cells where `anchor` puts them
+    ResizeGrid { rows: u32, cols: u32, anchor: Anchor },
+    // Switch the accessibility palette with outlined live cells on or off
+    HighContrast(bool),
+    // Announce the generation and population on stdout every this many generations, and
+    // extinction or revival as it happens; None stops announcing
+    Announce(Option<u64>),
+    // Mutate cells or bases at random every generation; None stops mutating
+    Mutate(Option<MutationParams>),
+    Select(Region),
+    ClearSelection,
+}
+
+impl Action {
+    // Actions that edit cells directly and can be undone
+    pub fn is_edit(&self) -> bool {
+        matches!(self, Action::ToggleCell { .. } | Action::Paint { .. } | Action::LoadPattern(_) | Action::Seed { .. } | Action::SeedDepth { .. } | Action::Compare(_) | Action::LoadAlignment(_) | Action::UsePreset(_) | Action::Shuffle(_) | Action::ReadFrame(_) | Action::GotoBookmark(_))
+    }
+
+    // Actions that change what the simulation thread steps (the cells, the rule, the signal,
+    // the thresholds or the automaton) or whether it should, so it is reloaded after them;
+    // loading a sequence in the background reloads it once the sequence is in
+    pub fn changes_simulation(&self) -> bool {
+        self.is_edit()
+            || matches!(
+                self,
+                Action::Jump(_)
+                    | Action::OpenRows(_)
+                    | Action::SetRule(_)
+                    | Action::UseNamedRule(_)
+                    | Action::GcRules(_)
+                    | Action::WalkRules(_)
+                    | Action::Turbo(_)
+                    | Action::Pause(_)
+                    | Action::LoadScript(_)
+                    | Action::LoadSignal(_)
+                    | Action::SetBirthThreshold(_)
+                    | Action::LoadSession(_)
+                    | Action::Ants(_)
+                    | Action::Cyclic(_)
+                    | Action::Protein(_)
+                    | Action::Lenia(_)
+                    | Action::Undo
+                    | Action::Redo
+                    | Action::ResizeGrid { .. }
+                    | Action::Mutate(_)
+            )
+    }
+}
+
+// The console command that produces the action, so actions can be written to replay files
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Action::ToggleCell { row, col } => write!(f, "toggle {row} {col}"),
+            Action::Paint { alive, cells } => {
+                write!(f, "paint {}", if *alive { "on" } else { "off" })?;
+                for (row, col) in cells {
+                    write!(f, " {row} {col}")?;
+                }
+                Ok(())
+            }
+            Action::InspectCell { row, col } => write!(f, "inspect {row} {col}"),
+            Action::SetTool(tool) => write!(f, "tool {tool}"),
+            Action::Jump(generations) => write!(f, "jump {generations}"),
+            Action::Seed { offset } => write!(f, "seed offset {offset}"),
+            Action::SeedDepth { path, region, min_depth, reference } => {
+                write!(f, "bam {} {region} {min_depth}", path.display())?;
+                if let Some(reference) = reference {
+                    write!(f, " {}", reference.display())?;
+                }
+                Ok(())
+            }
+            Action::Open(path) => write!(f, "open {}", path.display()),
+            Action::OpenRecord { path, record } => write!(f, "open {} {}", path.display(), record + 1),
+            Action::Fetch(accession) => write!(f, "fetch {accession}"),
+            Action::OpenRows(path) => write!(f, "rows {}", path.display()),
+            Action::LoadPattern(path) => write!(f, "pattern load {}", path.display()),
+            Action::SavePattern(path) => write!(f, "pattern save {}", path.display()),
+            Action::Compare(Some((path, comparison))) => write!(f, "compare {} {comparison}", path.display()),
+            Action::Compare(None) => write!(f, "compare off"),
+            Action::ReadFrame(Some(frame)) => write!(f, "frame {frame}"),
+            Action::ReadFrame(None) => write!(f, "frame off"),
+            Action::SetPreset(slot, preset) => write!(f, "preset {slot} {preset}"),
+            Action::SavePreset(slot) => write!(f, "preset save {slot}"),
+            Action::UsePreset(slot) => write!(f, "preset use {slot}"),
+            Action::LoadAlignment(Some((path, None))) => write!(f, "msa {}", path.display()),
+            Action::LoadAlignment(Some((path, Some(threshold)))) => write!(f, "msa {} seed {threshold}", path.display()),
+            Action::LoadAlignment(None) => write!(f, "msa off"),
+            Action::Shuffle(Some((kind, seed))) => write!(f, "shuffle {kind} {seed}"),
+            Action::Shuffle(None) => write!(f, "shuffle off"),
+            Action::SetRule(rule) => write!(f, "rule {rule}"),
+            Action::UseNamedRule(name) => write!(f, "rule {name}"),
+            Action::ShowRuleLibrary(true) => write!(f, "rules on"),
+            Action::ShowRuleLibrary(false) => write!(f, "rules off"),
+            Action::SaveNamedRule { name, description } if description.is_empty() => write!(f, "rules save {name}"),
+            Action::SaveNamedRule { name, description } => write!(f, "rules save {name} {description}"),
+            Action::GcRules(Some((poor, rich))) => write!(f, "gcrule {poor} {rich}"),
+            Action::GcRules(None) => write!(f, "gcrule off"),
+            Action::WalkRules(Some(interval)) => write!(f, "walk {interval}"),
+            Action::WalkRules(None) => write!(f, "walk off"),
+            Action::MarkRule => write!(f, "walk mark"),
+            Action::ShowRuleHistory => write!(f, "walk history"),
+            Action::ExportRuleHistory(path) => write!(f, "walk export {}", path.display()),
+            Action::SetSpeed(speed) => write!(f, "speed {speed}"),
+            Action::Turbo(Some(steps)) => write!(f, "turbo {steps}"),
+            Action::Turbo(None) => write!(f, "turbo off"),
+            Action::Pause(true) => write!(f, "pause on"),
+            Action::Pause(false) => write!(f, "pause off"),
+            Action::ExportFrame(path) => write!(f, "export {}", path.display()),
+            Action::ContactSheet(Some(sheet)) => {
+                write!(f, "sheet {} {} {}x{} {}", sheet.path.display(), sheet.every, sheet.rows, sheet.cols, sheet.thumbnail_width)
+            }
+            Action::ContactSheet(None) => write!(f, "sheet off"),
+            Action::RecordTable(Some((path, columns))) => {
+                let columns: Vec<String> = columns.iter().map(Column::to_string).collect();
+                write!(f, "table {} {}", path.display(), columns.join(","))
+            }
+            Action::RecordTable(None) => write!(f, "table off"),
+            Action::RecordHashes(Some(path)) => write!(f, "hashes {}", path.display()),
+            Action::RecordHashes(None) => write!(f, "hashes off"),
+            Action::Verify(Some(path)) => write!(f, "verify {}", path.display()),
+            Action::Verify(None) => write!(f, "verify off"),
+            Action::LoadShader(Some(path)) => write!(f, "shader {}", path.display()),
+            Action::LoadShader(None) => write!(f, "shader off"),
+            Action::LoadScript(Some(path)) => write!(f, "script {}", path.display()),
+            Action::LoadScript(None) => write!(f, "script off"),
+            Action::LoadSignal(Some((path, None))) => write!(f, "signal {}", path.display()),
+            Action::LoadSignal(Some((path, Some(chrom)))) => write!(f, "signal {} {chrom}", path.display()),
+            Action::LoadSignal(None) => write!(f, "signal off"),
+            Action::LoadVariants(Some((path, chrom, filter))) => {
+                write!(f, "vcf {}", path.display())?;
+                if let Some(chrom) = chrom {
+                    write!(f, " {chrom}")?;
+                }
+                write!(f, " {filter}")
+            }
+            Action::LoadVariants(None) => write!(f, "vcf off"),
+            Action::SetBirthThreshold(Some(threshold)) => write!(f, "fertility {threshold}"),
+            Action::SetBirthThreshold(None) => write!(f, "fertility off"),
+            Action::SearchMotif(Some(motif)) => write!(f, "motif {motif}"),
+            Action::SearchMotif(None) => write!(f, "motif off"),
+            Action::ShowSignals(Some(filter)) => write!(f, "signals {filter}"),
+            Action::ShowSignals(None) => write!(f, "signals off"),
+            Action::FindRepeats(Some(params)) => write!(f, "repeats {params}"),
+            Action::FindRepeats(None) => write!(f, "repeats off"),
+            Action::Pcr(Some((primers, max_mismatches))) => write!(f, "pcr {} {} {max_mismatches}", primers.forward, primers.reverse),
+            Action::Pcr(None) => write!(f, "pcr off"),
+            Action::DesignPrimers(Some(constraints)) => write!(f, "primers {constraints}"),
+            Action::DesignPrimers(None) => write!(f, "primers off"),
+            Action::CopyPrimers(Some(number)) => write!(f, "primers copy {number}"),
+            Action::CopyPrimers(None) => write!(f, "primers copy"),
+            Action::ScanPwm(Some((path, threshold))) => write!(f, "pwm {} {threshold}", path.display()),
+            Action::ScanPwm(None) => write!(f, "pwm off"),
+            Action::SetPwmBackground(background) => write!(f, "pwm background {background}"),
+            Action::MaskLowComplexity(Some((params, seeding))) => {
+                let mode = if *seeding { "seed" } else { "show" };
+                write!(f, "mask {mode} {} {} {}", params.min_run, params.window, params.threshold)
+            }
+            Action::MaskLowComplexity(None) => write!(f, "mask off"),
+            Action::CodonUsage(Some(source)) => write!(f, "codons {source}"),
+            Action::CodonUsage(None) => write!(f, "codons off"),
+            Action::SortCodons(sort) => write!(f, "codons sort {sort}"),
+            Action::ExportCodons(path) => write!(f, "codons export {}", path.display()),
+            Action::SetEffect(effect, enabled) => write!(f, "effect {effect} {}", if *enabled { "on" } else { "off" }),
+            Action::ShowHeatmap(true) => write!(f, "heatmap on"),
+            Action::ShowHeatmap(false) => write!(f, "heatmap off"),
+            Action::MarkGeneration => write!(f, "diff mark"),
+            Action::ShowDiff(true) => write!(f, "diff on"),
+            Action::ShowDiff(false) => write!(f, "diff off"),
+            Action::ResetHeatmap => write!(f, "heatmap reset"),
+            Action::ExportHeatmap(path) => write!(f, "heatmap export {}", path.display()),
+            Action::ShowTrail(true) => write!(f, "trail on"),
+            Action::ShowTrail(false) => write!(f, "trail off"),
+            Action::TrackBlobs(true) => write!(f, "blobs on"),
+            Action::TrackBlobs(false) => write!(f, "blobs off"),
+            Action::ColorBlobs(true) => write!(f, "blobs color on"),
+            Action::ColorBlobs(false) => write!(f, "blobs color off"),
+            Action::ShowBlobStats => write!(f, "blobs stats"),
+            Action::ShowRuler(true) => write!(f, "ruler on"),
+            Action::ShowRuler(false) => write!(f, "ruler off"),
+            Action::ShowContours(true) => write!(f, "contour on"),
+            Action::ShowContours(false) => write!(f, "contour off"),
+            Action::SmoothTransitions(true) => write!(f, "smooth on"),
+            Action::SmoothTransitions(false) => write!(f, "smooth off"),
+            Action::ShowLetters(true) => write!(f, "letters on"),
+            Action::ShowLetters(false) => write!(f, "letters off"),
+            Action::ShowTorus(true) => write!(f, "torus on"),
+            Action::ShowTorus(false) => write!(f, "torus off"),
+            Action::ShowHeightfield(true) => write!(f, "heightfield on"),
+            Action::ShowHeightfield(false) => write!(f, "heightfield off"),
+            Action::ExportReport(path) => write!(f, "report {}", path.display()),
+            Action::SaveSession(path) => write!(f, "session save {}", path.display()),
+            Action::LoadSession(path) => write!(f, "session load {}", path.display()),
+            Action::Ants(Some(count)) => write!(f, "ant on {count}"),
+            Action::Ants(None) => write!(f, "ant off"),
+            Action::ShowAntStats => write!(f, "ant stats"),
+            Action::Cyclic(Some(threshold)) => write!(f, "cyclic on {threshold}"),
+            Action::Cyclic(None) => write!(f, "cyclic off"),
+            Action::Protein(true) => write!(f, "protein on"),
+            Action::Protein(false) => write!(f, "protein off"),
+            Action::Lenia(Some(source)) => write!(f, "lenia on {source}"),
+            Action::Lenia(None) => write!(f, "lenia off"),
+            Action::ShowStrip(true) => write!(f, "strip on"),
+            Action::ShowStrip(false) => write!(f, "strip off"),
+            Action::ScrollStrip(position) => write!(f, "strip goto {position}"),
+            Action::SelectTrack(Some(name)) => write!(f, "track {name}"),
+            Action::SelectTrack(None) => write!(f, "track next"),
+            Action::ShowTrackLayer(true) => write!(f, "track layer on"),
+            Action::ShowTrackLayer(false) => write!(f, "track layer off"),
+            Action::ShowLayer(layer, true) => write!(f, "layer {} on", layer.name()),
+            Action::ShowLayer(layer, false) => write!(f, "layer {} off", layer.name()),
+            Action::SetLayerOpacity(layer, opacity) => write!(f, "layer {} {opacity}", layer.name()),
+            Action::MoveLayer { layer, up: true } => write!(f, "layer {} up", layer.name()),
+            Action::MoveLayer { layer, up: false } => write!(f, "layer {} down", layer.name()),
+            Action::SetLayers(layers) => write!(f, "layers {layers}"),
+            Action::ShowLayerPanel(true) => write!(f, "layers on"),
+            Action::ShowLayerPanel(false) => write!(f, "layers off"),
+            Action::ListTracks => write!(f, "track list"),
+            Action::SaveBookmark { slot, name: Some(name) } => write!(f, "bookmark save {slot} {name}"),
+            Action::SaveBookmark { slot, name: None } => write!(f, "bookmark save {slot}"),
+            Action::GotoBookmark(key) => write!(f, "bookmark goto {key}"),
+            Action::ListBookmarks => write!(f, "bookmark list"),
+            Action::Annotate { row, col, text: Some(text) } => write!(f, "note {row} {col} {text}"),
+            Action::Annotate { row, col, text: None } => write!(f, "note remove {row} {col}"),
+            Action::ListNotes => write!(f, "note list"),
+            Action::ExportNotes { path, chrom: Some(chrom) } => write!(f, "note export {} {chrom}", path.display()),
+            Action::ExportNotes { path, chrom: None } => write!(f, "note export {}", path.display()),
+            Action::ExportAlive { path, chrom: Some(chrom) } => write!(f, "alive export {} {chrom}", path.display()),
+            Action::ExportAlive { path, chrom: None } => write!(f, "alive export {}", path.display()),
+            Action::ShowAnalysis(true) => write!(f, "analysis on"),
+            Action::ShowAnalysis(false) => write!(f, "analysis off"),
+            Action::ExportAnalysis(path) => write!(f, "analysis export {}", path.display()),
+            Action::ShowHelp(true) => write!(f, "help on"),
+            Action::ShowHelp(false) => write!(f, "help off"),
+            Action::ShowProfiler(true) => write!(f, "profile on"),
+            Action::ShowProfiler(false) => write!(f, "profile off"),
+            Action::Undo => write!(f, "undo"),
+            Action::Redo => write!(f, "redo"),
+            Action::ToggleBackground => write!(f, "background"),
+            Action::SetFit(fit) => write!(f, "layout fit {fit}"),
+            Action::SetPadding(padding) => write!(f, "layout padding {padding}"),
+            Action::SetCellSize(Some(size)) => write!(f, "layout cell {size}"),
+            Action::SetCellSize(None) => write!(f, "layout cell auto"),
+            Action::SetPan([x, y]) => write!(f, "layout pan {x} {y}"),
+            Action::SetLevelOfDetail(true) => write!(f, "layout lod auto"),
+            Action::SetLevelOfDetail(false) => write!(f, "layout lod off"),
+            Action::ShowLayout => write!(f, "layout"),
+            Action::ResizeGrid { rows, cols, anchor } => write!(f, "grid {rows}x{cols} {anchor}"),
+            Action::HighContrast(true) => write!(f, "contrast on"),
+            Action::HighContrast(false) => write!(f, "contrast off"),
+            Action::Announce(Some(every)) => write!(f, "announce {every}"),
+            Action::Announce(None) => write!(f, "announce off"),
+            Action::Mutate(Some(MutationParams { target: MutationTarget::Cells, rate, seed, .. })) => write!(f, "mutate cells {rate} seed {seed}"),
+            Action::Mutate(Some(MutationParams { target: MutationTarget::Bases, rate, kappa, seed })) => write!(f, "mutate bases {rate} kappa {kappa} seed {seed}"),
+            Action::Mutate(None) => write!(f, "mutate off"),
+            Action::Select(region) => write!(f, "select {} {} {} {}", region.row, region.col, region.rows, region.cols),
+            Action::ClearSelection => write!(f, "select none"),
+        }
+    }
+}
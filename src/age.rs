@@ -0,0 +1,61 @@
+// How many generations in a row each cell has been alive, for the heightfield view, where
+// long-lived cells rise as columns. Unlike the heat map's counts, a cell's age drops back to
+// zero as soon as it dies, so only structures that stay put grow tall. HashLife jumps count
+// as one generation.
+pub struct Ages {
+    ages: Vec<u32>,
+}
+
+// Age at which a column reaches its full height
+pub const MAX_AGE: u32 = 500;
+
+impl Ages {
+    pub fn new(cells: usize) -> Self {
+        Self { ages: vec![0; cells] }
+    }
+
+    pub fn update(&mut self, cells: &[bool]) {
+        if self.ages.len() != cells.len() {
+            *self = Self::new(cells.len());
+        }
+        for (age, &alive) in self.ages.iter_mut().zip(cells) {
+            *age = if alive { age.saturating_add(1) } else { 0 };
+        }
+    }
+
+    pub fn ages(&self) -> &[u32] {
+        &self.ages
+    }
+
+    // Age per cell in 0..1 on a log scale up to `MAX_AGE`, so the first generations of a
+    // still life already show while oscillators stay low
+    pub fn levels(&self) -> Vec<f32> {
+        let scale = (MAX_AGE as f32).ln_1p();
+        self.ages.iter().map(|&age| ((age as f32).ln_1p() / scale).min(1.0)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ages_reset_when_a_cell_dies() {
+        let mut ages = Ages::new(3);
+        for _ in 0..3 {
+            ages.update(&[true, true, false]);
+        }
+        ages.update(&[true, false, true]);
+        assert_eq!(ages.ages(), [4, 0, 1]);
+
+        let levels = ages.levels();
+        assert_eq!(levels[1], 0.0);
+        assert!(levels[0] > levels[2]);
+        ages.ages[0] = MAX_AGE * 2;
+        assert_eq!(ages.levels()[0], 1.0);
+
+        // A new grid size starts over
+        ages.update(&[true; 2]);
+        assert_eq!(ages.ages(), [1, 1]);
+    }
+}
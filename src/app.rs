@@ -0,0 +1,4012 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bio::seq_analysis::gc::gc_content;
+use tracing::{debug, info, warn};
+use wgpu::{Color, Instance};
+use winit::dpi::PhysicalPosition;
+use winit::event::Touch;
+use winit::window::{CursorIcon, Window, WindowId};
+
+use crate::accessibility::{self, Accessibility, Announcer};
+use crate::autopause::{AutoPause, EndDetector};
+use crate::action::Action;
+use crate::age::Ages;
+use crate::automaton::ant::Colony;
+use crate::automaton::cyclic::{self, Cyclic};
+use crate::automaton::protein::{self, PropertyClass, Protein};
+use crate::automaton::lenia::{self, KernelSource, Lenia};
+use crate::clipboard;
+use crate::bookmarks::{Bookmark, Bookmarks};
+use crate::centroid::{Trajectory, centroid};
+use crate::clock::{MAX_SPEED, MIN_SPEED, TickClock};
+use crate::components::Components;
+use crate::console::Console;
+use crate::context_menu::{ContextMenu, MenuItem};
+use crate::contour::{self, Contours};
+use crate::export;
+#[cfg(feature = "arrow")]
+use crate::export::table::TableRecorder;
+use crate::export::sheet::ContactSheet;
+use crate::export::video::{VideoRecorder, VideoSettings};
+use crate::formats::{bedgraph, fasta, gff, msa};
+use crate::gc_rules::{self, GcRules};
+use crate::formats::vcf::{self, VariantKind};
+use crate::gpu::{Gpu, Offscreen, WindowSurface};
+use crate::grid_texture::GridLayout;
+use crate::heatmap::Heatmap;
+use crate::history::{Change, History, Snapshot};
+use crate::keymap;
+use crate::kiosk::{self, Kiosk, SCENE_CROSSFADE};
+use crate::layers::{Layer, LayerPanel, Layers};
+use crate::loader::{self, LoadedSequence, SequenceLoader};
+use crate::lod;
+use crate::logging::FrameTimes;
+use crate::msa::RowRecords;
+use crate::mutation::{MutationTarget, Mutator};
+use crate::notes::Notes;
+use crate::presets::{Parameters, Preset, Presets, Seeding, Theme};
+use crate::provenance::Provenance;
+use crate::quality::{Quality, QualityManager};
+use crate::render::chart::{Chart, ChartStyle, Frame, create_stacked_charts, decimate};
+use crate::render::panel::{create_panel_vertices, create_popup_vertices, panel_line_at};
+use crate::render::profiler::Timings;
+use crate::render::text::font_pixel;
+use crate::render::grid_resources::CellView;
+use crate::render::heightfield;
+use crate::render::letters::{self, Letter, LetterView};
+use crate::render::post::{Effects, PostProcess};
+use crate::render::torus::Arcball;
+use crate::replay::{Recorder, Replay};
+use crate::report::SimulationReport;
+use crate::rule_library::{self, NamedRule, RuleLibrary};
+use crate::rule_walk::RuleWalk;
+use crate::ruler::create_ruler_vertices;
+#[cfg(feature = "scripting")]
+use crate::scripting::Script;
+use crate::selection::Region;
+use crate::session::{self, Autosave, Session};
+#[cfg(feature = "server")]
+use crate::server::{Query, Reply, Server};
+use crate::shader_file::ShaderFile;
+use crate::sim::Simulation;
+use crate::strip::{self, SeedMap, StripLayout, create_strip_vertices};
+use crate::tools::{Gesture, Tool, ToolbarLayout};
+use crate::touch::{TouchGesture, TouchTracker};
+use crate::tracks::{Track, TrackRegistry};
+use crate::transition::Transition;
+use crate::turbo::{self, Turbo};
+use crate::seq_analysis::codon_usage::{self, CdsSource, CodonSort, CodonTable};
+use crate::seq_analysis::complexity::{is_masked, low_complexity};
+use crate::seq_analysis::difference;
+use crate::seq_analysis::frames::{self, FrameStats, RANDOM_STOP_DENSITY, ReadingFrame};
+use crate::seq_analysis::motif::Strand;
+use crate::seq_analysis::pcr::{self, Primer};
+use crate::seq_analysis::primers::{self, Candidate};
+use crate::seq_analysis::pwm::{Background, Pwm};
+use crate::seq_analysis::repeats::{self, RepeatParams, TandemRepeat};
+use crate::seq_analysis::shuffle;
+use crate::seq_analysis::signals::{self, Signal, SignalFilter, SignalKind};
+use crate::seq_analysis::cache::{StatsCache, sequence_id};
+use crate::seq_analysis::skew::Skew;
+use crate::universe::io::{self, Pattern};
+use crate::universe::{Anchor, MAX_CELLS, SeedStrategy, Universe};
+use crate::viewport::{CellLayout, GridGeometry, MAX_CELL_SIZE, MAX_PADDING, Viewport};
+use crate::verify::{HashRecorder, Reference, Verifier};
+use crate::vertex::{ALIVE_COLOR, DEAD_COLOR, Glyph, Motion, NOTE_COLOR, Overlay, RegionColors, Vertex, cell_color, create_grid_vertices, create_lod_vertices, create_overlay_vertices, create_smooth_grid_vertices};
+
+// How the cells are drawn: one quad per cell, or one quad sampling a cell texture
+// (`--renderer texture`), which keeps the vertex count fixed for very large grids
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Renderer {
+    Geometry,
+    Texture,
+}
+
+impl std::str::FromStr for Renderer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "geometry" => Ok(Renderer::Geometry),
+            "texture" => Ok(Renderer::Texture),
+            _ => Err(format!("Unknown renderer '{s}', expected 'geometry' or 'texture'")),
+        }
+    }
+}
+
+// What advances the grid each generation: the Life-like rule (or a script), or one of the
+// alternate automata, which keep their own state and write alive flags into the universe
+enum Mode {
+    Rule,
+    Ants(Colony),
+    // Four competing base states instead of alive and dead
+    Cyclic(Cyclic),
+    // Continuous states, stepped by a compute shader when the device allows
+    Lenia(Lenia),
+    // Amino-acid property classes, from a protein sequence or the translated DNA
+    Protein(Protein),
+}
+
+// A two-finger pinch over the flat grid, previewed until the fingers lift: the layout and
+// cell size (in logical pixels) it started from, the grid's center and the fingers' midpoint
+// then, in clip space, and the layout it has reached
+struct Pinch {
+    layout: CellLayout,
+    cell_size: u32,
+    center: [f32; 2],
+    midpoint: [f32; 2],
+    preview: CellLayout,
+}
+
+// All application state shared by the event loop handlers
+pub struct App {
+    instance: Instance,
+    gpu: Gpu,
+    // The main window with the grid
+    surface: WindowSurface,
+    // Optional second window with the analysis charts, sharing the device
+    analysis: Option<WindowSurface>,
+    analysis_requested: bool,
+    analysis_dirty: bool,
+    // Cursor over the analysis window, in its clip space
+    analysis_hover: Option<[f32; 2]>,
+    // Sequence analyses, the selected one charted below the grid and, with the heat layer,
+    // drawn behind the cells. `track_fade` is the track switched away from and when, while
+    // it crossfades into the new one.
+    tracks: TrackRegistry,
+    track_fade: Option<(&'static str, Instant)>,
+    // Live cells after each of the most recent generations
+    population: Vec<f64>,
+    // Generations each cell has been alive, shown instead of the cells when `heatmap_view` is set
+    heatmap: Heatmap,
+    heatmap_view: bool,
+    // Generations in a row each cell has been alive, the column heights of `heightfield_view`
+    ages: Ages,
+    heightfield_view: bool,
+    // Cells at the generation marked with B, and whether they are compared with the current
+    // ones instead of showing the cells
+    mark: Option<Snapshot>,
+    diff_view: bool,
+    // Population centroid of the recent generations, drawn as a trail when `trail_view` is set
+    trajectory: Trajectory,
+    trail_view: bool,
+    // Largest connected group of live cells tracked every generation, with its centroid
+    // trail and the number of groups and the largest's size for the title
+    blob_tracking: bool,
+    blob_trail: Trajectory,
+    blob_summary: Option<(usize, u32)>,
+    // Cells colored by the group they belong to
+    blob_colors: bool,
+    // Coordinate ruler along the grid edges
+    ruler_view: bool,
+    // Outlines of the live regions drawn over the cells, and those last traced
+    contour_view: bool,
+    contours: Contours,
+    // Smooth transitions between generations; None steps crisply
+    transition: Option<Transition>,
+    // Base letters inside the cells once they are MIN_CELL_PIXELS across
+    letter_view: bool,
+    // Checkboxes for the layers of `overlay.layers`, in the top-left corner
+    layer_panel: bool,
+    // The right-click menu of a cell, and its item under the cursor
+    context_menu: Option<ContextMenu>,
+    menu_hover: Option<MenuItem>,
+    // Cells drawn on a torus instead of the flat grid, seen through `torus_camera`; the
+    // last cursor position of a drag rotating it, in clip space
+    torus_view: bool,
+    torus_camera: Arcball,
+    torus_drag: Option<[f32; 2]>,
+    // Touch gestures in progress, a pinch zooming the grid, and on the torus view the scale
+    // of the pinch it last zoomed to
+    touch: TouchTracker,
+    pinch: Option<Pinch>,
+    torus_pinch: f64,
+    // Anchor of the last grid resize, reused by the resize keys
+    resize_anchor: Anchor,
+    pub universe: Universe,
+    pub dna: Vec<u8>,
+    // Content hash of `dna`, the key of its cached analyses
+    dna_id: u64,
+    // File or accession `dna` was read from, and the seed of the last mutations drawn from
+    // it, for the provenance of exports
+    input: String,
+    rng_seed: Option<u64>,
+    stats: StatsCache,
+    // Sequence being read in the background (`open`), swapped in when it is ready
+    loader: Option<SequenceLoader>,
+    // Record each row was seeded from, when `dna` holds a multi-FASTA stacked one record per row
+    row_records: Option<RowRecords>,
+    // Second sequence loaded with `compare`, which `dna` is seeded against
+    pub other_dna: Option<Vec<u8>>,
+    // The sequence as loaded while `dna` holds a shuffled copy of it (`shuffle`)
+    unshuffled: Option<Vec<u8>>,
+    // Point mutations applied every generation (`mutate`)
+    mutator: Option<Mutator>,
+    seed_strategy: SeedStrategy,
+    // Sequence position behind every cell, for the strip. Annotations (motif matches, variants,
+    // a bedGraph signal) go the other way, putting position i on cell i in row-major order
+    // like the seeding.
+    seed_map: SeedMap,
+    // Sequence strip along the bottom, starting at `strip_start`, with the base under the
+    // cursor (over the strip or over a cell) highlighted
+    strip_view: bool,
+    strip_start: usize,
+    // Key bindings, mode and rule drawn over the top-left of the grid
+    help_view: bool,
+    // GPU pass and CPU frame timings drawn over the top-left of the grid while the help is closed
+    profile_view: bool,
+    // Primers proposed for the selection (`primers`) and the bases they were picked from,
+    // listed in a panel while set
+    primers: Option<(Range<usize>, Vec<Candidate>)>,
+    // Codons and stop codons of the six reading frames, listed in a panel while the grid is
+    // seeded from one of them
+    frame_stats: Vec<FrameStats>,
+    // Translation signals marked on the grid (`signals`), those the filter lets through,
+    // sorted by position
+    signals: Option<(SignalFilter, Vec<Signal>)>,
+    // Tandem repeats shaded on the grid and listed in a panel (`repeats`), sorted by position
+    repeats: Option<(RepeatParams, Vec<TandemRepeat>)>,
+    cpu_timings: Timings,
+    profile_refreshed: Instant,
+    bookmarks: Bookmarks,
+    // Notes on sequence positions, and the cell under the cursor when its base has one
+    notes: Notes,
+    note_hover: Option<(u32, u32)>,
+    hovered_base: Option<usize>,
+    // Cursor x over the track chart below the grid, in clip space
+    track_hover: Option<f32>,
+    // Mouse tool, the drag it is in the middle of, and the cursor shown over the window
+    tool: Tool,
+    gesture: Option<Gesture>,
+    cursor_icon: CursorIcon,
+    // Low-complexity intervals, and whether they are also kept dead when seeding
+    mask: Option<(Vec<Range<usize>>, bool)>,
+    // Codon usage of the last `codons` command, kept in `codon_sort` order
+    codons: Option<CodonTable>,
+    codon_sort: CodonSort,
+    // Sequence-driven rule mutation, with the rules visited so far
+    rule_walk: Option<RuleWalk>,
+    mode: Mode,
+    layout: CellLayout,
+    renderer: Renderer,
+    // Whether cells smaller than a pixel are drawn in blocks, and the block side drawn with
+    // last, which the next size is picked around (lod.rs); 1 while single cells are drawn
+    level_of_detail: bool,
+    lod_block: u32,
+    // Post-processing effects for the grid window, kept here so they survive GPU recreation
+    effects: Effects,
+    pub overlay: Overlay,
+    pub console: Console,
+    history: History,
+    pub presets: Presets,
+    // Named rules for `rule <name>`, the built-ins plus the config file's, and that file,
+    // where `rules save` adds to them
+    pub rule_library: RuleLibrary,
+    pub config_path: PathBuf,
+    // The rule library listed in the top-left corner, a click on a rule using it
+    rule_panel: bool,
+    // Every applied action is appended here when recording (`--record`)
+    pub recorder: Option<Recorder>,
+    // Actions from a replay file (`--replay`), applied when their generation comes up
+    pub replay: Option<Replay>,
+    // Loaded PWM with its highlight threshold, rescanned when the background changes
+    pwm: Option<(Pwm, f64)>,
+    pwm_background: Background,
+    grid_data: Vec<Vertex>,
+    // Smooth transitions of the first vertices of `grid_data`, empty while they are off
+    grid_motions: Vec<Motion>,
+    grid_dirty: bool,
+    color_toggle: bool,
+    tick_interval: Duration,
+    // Time ticks are due by, the wall clock unless deterministic (`--deterministic`)
+    clock: TickClock,
+    paused: bool,
+    // A fixed number of generations a frame instead of the speed (`turbo`)
+    turbo: Option<Turbo>,
+    // Worker thread stepping the rule, when it could be started, and whether it holds a copy
+    // of the current universe
+    simulation: Option<Simulation>,
+    simulation_synced: bool,
+    // Contrast, text size and announcements, from the config file
+    pub accessibility: Accessibility,
+    announcer: Announcer,
+    // Pausing on extinction or a short cycle, from the config file, and when it last did
+    pub auto_pause: AutoPause,
+    ending: EndDetector,
+    flash: Option<Instant>,
+    title: String,
+    // Per-generation table being written (`table`)
+    #[cfg(feature = "arrow")]
+    table: Option<TableRecorder>,
+    // Per-generation hashes being written (`hashes`), and a recorded run being compared
+    // with this one (`verify`)
+    hashes: Option<HashRecorder>,
+    verifier: Option<Verifier>,
+    // ffmpeg process the grid window is streamed to (`--record-video`)
+    video: Option<VideoRecorder>,
+    // Thumbnails of every Nth generation being collected (`sheet`)
+    sheet: Option<ContactSheet>,
+    // Time between grid window frames, summarized on exit
+    pub frame_times: FrameTimes,
+    // Quality level the grid window is drawn at, dropped while updates run over the frame
+    // budget, and the generations stepped since the grid was last rebuilt
+    pub quality: QualityManager,
+    generations_unshown: bool,
+    frames_since_upload: u32,
+    // Scene scheduler of `--kiosk`, and the cell colors of the scene before while crossfading
+    // out of them, with when the fade started once the new scene is in place
+    pub kiosk: Option<Kiosk>,
+    // Saving the session for crash recovery, from the config file, when it last did, and the
+    // thread still writing it
+    pub autosave: Autosave,
+    last_autosave: Instant,
+    autosaving: Option<std::thread::JoinHandle<()>>,
+    scene_fade: Option<(Vec<[f32; 3]>, Option<Instant>)>,
+    last_frame: Option<Instant>,
+    // Custom cell shader, recompiled when saved
+    shader: Option<ShaderFile>,
+    last_shader_check: Instant,
+    #[cfg(feature = "scripting")]
+    script: Option<Script>,
+    #[cfg(feature = "scripting")]
+    last_script_check: Instant,
+    // HTTP/WebSocket API (`--serve`)
+    #[cfg(feature = "server")]
+    pub server: Option<Server>,
+}
+
+const MAX_TICKS_PER_FRAME: u32 = 1000;
+// How long switching tracks takes to crossfade
+const TRACK_CROSSFADE: Duration = Duration::from_millis(400);
+// How often the profiler overlay's numbers are redrawn
+const PROFILE_REFRESH: Duration = Duration::from_millis(250);
+// How long the window flashes when the run pauses by itself
+const AUTO_PAUSE_FLASH: Duration = Duration::from_millis(500);
+const MAX_LISTED_MATCHES: usize = 20;
+const POPULATION_HISTORY: usize = 1000;
+// Component sizes listed by `blobs stats` and the server's state, largest first
+const LISTED_COMPONENTS: usize = 10;
+// Primers proposed per strand
+const PRIMER_CANDIDATES: usize = 5;
+// Dead cells on a stop codon of the reading frame the grid is seeded from
+const STOP_CODON_COLOR: [f32; 3] = [0.45, 0.08, 0.08];
+// Stripes of a tandem repeat's even and odd copies, so the units can be told apart
+const REPEAT_COLORS: [[f32; 3]; 2] = [[0.35, 0.8, 1.0], [0.15, 0.45, 0.6]];
+// Tandem repeats listed in the panel, longest first
+const LISTED_REPEATS: usize = 15;
+// Size of the analysis charts exported on their own, in pixels
+const ANALYSIS_EXPORT_SIZE: (u32, u32) = (1200, 1000);
+// Jumps without HashLife step every generation on the main thread, so long ones would
+// freeze the window
+const MAX_STEPPED_JUMP: u64 = turbo::MAX_STEPPED as u64;
+
+impl App {
+    pub fn new(window: Arc<Window>, dna: Vec<u8>, skew: &Skew, renderer: Renderer, layout: CellLayout, (rows, cols): (u32, u32)) -> Self {
+        let instance = Instance::default();
+
+        let universe = Universe::new(rows, cols, &dna);
+        let mut overlay = Overlay::default();
+        // Cell seeded from the base at the skew minimum
+        overlay.markers.push((skew.min_position().saturating_sub(1), [0.9, 0.2, 0.9]));
+
+        // The texture renderer gets its cells uploaded on the first update
+        let geometry = Viewport::of(&window).grid_geometry(rows, cols, &layout);
+        let grid_data = match renderer {
+            Renderer::Geometry => create_grid_vertices(&universe, &geometry, &overlay),
+            Renderer::Texture => create_overlay_vertices(&universe, &geometry, &overlay),
+        };
+
+        let (gpu, surface) = Gpu::new(&instance, window, &grid_data);
+
+        let dna_id = sequence_id(&dna);
+        let mut stats = StatsCache::new();
+        let tracks = TrackRegistry::new(&stats.window_stats(&dna, dna_id, skew.window));
+        let simulation = Simulation::start().inspect_err(|err| warn!("{err}; ticking on the main thread")).ok();
+        let population = vec![universe.cells.iter().filter(|&&alive| alive).count() as f64];
+
+        Self {
+            instance,
+            gpu,
+            surface,
+            analysis: None,
+            analysis_requested: false,
+            analysis_dirty: false,
+            analysis_hover: None,
+            tracks,
+            track_fade: None,
+            population,
+            heatmap: Heatmap::new(universe.cells.len()),
+            heatmap_view: false,
+            ages: Ages::new(universe.cells.len()),
+            heightfield_view: false,
+            mark: None,
+            diff_view: false,
+            trajectory: Trajectory::new(universe.rows, universe.cols, POPULATION_HISTORY),
+            trail_view: false,
+            blob_tracking: false,
+            blob_trail: Trajectory::new(universe.rows, universe.cols, POPULATION_HISTORY),
+            blob_summary: None,
+            blob_colors: false,
+            ruler_view: false,
+            contour_view: false,
+            contours: Contours::default(),
+            transition: None,
+            letter_view: true,
+            layer_panel: false,
+            context_menu: None,
+            menu_hover: None,
+            torus_view: false,
+            torus_camera: Arcball::default(),
+            torus_drag: None,
+            touch: TouchTracker::default(),
+            pinch: None,
+            torus_pinch: 1.0,
+            resize_anchor: Anchor::default(),
+            universe,
+            dna,
+            dna_id,
+            input: "built-in".to_string(),
+            rng_seed: None,
+            stats,
+            loader: None,
+            row_records: None,
+            other_dna: None,
+            unshuffled: None,
+            mutator: None,
+            seed_strategy: SeedStrategy::default(),
+            seed_map: SeedMap::new(rows, cols),
+            strip_view: true,
+            strip_start: 0,
+            help_view: false,
+            profile_view: false,
+            primers: None,
+            frame_stats: Vec::new(),
+            signals: None,
+            repeats: None,
+            cpu_timings: Timings::default(),
+            profile_refreshed: Instant::now(),
+            bookmarks: Bookmarks::default(),
+            notes: Notes::default(),
+            note_hover: None,
+            hovered_base: None,
+            track_hover: None,
+            tool: Tool::default(),
+            gesture: None,
+            cursor_icon: CursorIcon::Default,
+            mask: None,
+            codons: None,
+            codon_sort: CodonSort::Codon,
+            rule_walk: None,
+            mode: Mode::Rule,
+            layout,
+            renderer,
+            level_of_detail: true,
+            lod_block: 1,
+            effects: Effects::default(),
+            overlay,
+            console: Console::default(),
+            history: History::default(),
+            presets: Presets::default(),
+            rule_library: RuleLibrary::default(),
+            config_path: crate::config::DEFAULT_PATH.into(),
+            rule_panel: false,
+            recorder: None,
+            replay: None,
+            pwm: None,
+            pwm_background: Background::Uniform,
+            grid_data,
+            grid_motions: Vec::new(),
+            // The skew track, the sequence strip and the texture renderer's cells are added
+            // on the first update
+            grid_dirty: true,
+            color_toggle: false,
+            tick_interval: Duration::from_millis(1000),
+            paused: false,
+            turbo: None,
+            simulation,
+            simulation_synced: false,
+            accessibility: Accessibility::default(),
+            announcer: Announcer::default(),
+            auto_pause: AutoPause::default(),
+            ending: EndDetector::default(),
+            flash: None,
+            clock: TickClock::wall(),
+            title: String::new(),
+            #[cfg(feature = "arrow")]
+            table: None,
+            hashes: None,
+            verifier: None,
+            video: None,
+            sheet: None,
+            frame_times: FrameTimes::default(),
+            quality: QualityManager::default(),
+            kiosk: None,
+            autosave: Autosave::default(),
+            last_autosave: Instant::now(),
+            autosaving: None,
+            scene_fade: None,
+            generations_unshown: false,
+            frames_since_upload: 0,
+            last_frame: None,
+            shader: None,
+            last_shader_check: Instant::now(),
+            #[cfg(feature = "scripting")]
+            script: None,
+            #[cfg(feature = "scripting")]
+            last_script_check: Instant::now(),
+            #[cfg(feature = "server")]
+            server: None,
+        }
+    }
+
+    pub fn apply(&mut self, action: Action) {
+        let _span = tracing::debug_span!("apply", %action).entered();
+        if action.changes_simulation() {
+            self.simulation_synced = false;
+        }
+        if let Some(recorder) = self.recorder.as_mut()
+            && let Err(err) = recorder.record(self.universe.generation, &action)
+        {
+            warn!("Failed to record action, recording stopped: {err}");
+            self.recorder = None;
+        }
+
+        // Only the cells an edit changes are kept, but finding them needs the previous grid
+        let before = action.is_edit().then(|| (self.universe.cells.clone(), self.parameters()));
+
+        match action {
+            Action::ToggleCell { row, col } => {
+                if row < self.universe.rows && col < self.universe.cols {
+                    self.universe.toggle(row, col);
+                } else {
+                    warn!("Cell ({row}, {col}) is outside the {}x{} grid", self.universe.rows, self.universe.cols);
+                }
+            }
+            Action::Paint { alive, cells } => {
+                let (rows, cols) = (self.universe.rows, self.universe.cols);
+                for (row, col) in cells {
+                    if row < rows && col < cols {
+                        self.universe.cells[(row * cols + col) as usize] = alive;
+                    } else {
+                        warn!("Cell ({row}, {col}) is outside the {rows}x{cols} grid");
+                    }
+                }
+            }
+            Action::InspectCell { row, col } => self.inspect(row, col),
+            Action::SetTool(tool) => {
+                self.tool = tool;
+                self.set_cursor_icon(tool.cursor());
+                info!("{tool} tool: {}", tool.status());
+            }
+            Action::Jump(generations) => {
+                if self.universe.generation.checked_add(generations).is_none() {
+                    warn!("Jumping {generations} generations from generation {} would count past {}", self.universe.generation, u64::MAX);
+                    return;
+                }
+                #[cfg(feature = "scripting")]
+                let scripted = self.script.as_ref().is_some_and(Script::has_next);
+                #[cfg(not(feature = "scripting"))]
+                let scripted = false;
+                // Script rules, the alternate automata and rules HashLife can't jump have no
+                // shortcut through time
+                let stepped = scripted || !matches!(self.mode, Mode::Rule);
+                if (stepped || !self.universe.jumps()) && generations > MAX_STEPPED_JUMP {
+                    warn!("Without HashLife jumps are limited to {MAX_STEPPED_JUMP} generations");
+                    return;
+                }
+                if stepped {
+                    for _ in 0..generations {
+                        self.step();
+                    }
+                    info!("Jumped to generation {}", self.universe.generation);
+                    self.grid_dirty = true;
+                    return;
+                }
+
+                self.jump_rule(generations);
+                info!("Jumped to generation {}", self.universe.generation);
+            }
+            Action::Seed { offset } => {
+                let region = self.overlay.selection.unwrap_or(Region::full(self.universe.rows, self.universe.cols));
+                if offset >= self.dna.len() {
+                    warn!("Offset {offset} is past the end of the sequence ({} bases)", self.dna.len());
+                }
+                self.seed(&region, offset);
+                info!("Reseeded {}x{} cells from offset {offset}", region.rows, region.cols);
+            }
+            #[cfg(feature = "bam")]
+            Action::SeedDepth { path, region: genomic_region, min_depth, reference } => {
+                match crate::formats::bam::read_depth(&path, &genomic_region, reference.as_deref()) {
+                    Ok(depth) => {
+                        let region = self.overlay.selection.unwrap_or(Region::full(self.universe.rows, self.universe.cols));
+                        self.universe.fill_region(&region, |i| depth.get(i).is_some_and(|&depth| depth >= min_depth));
+                        let covered = depth.iter().filter(|&&depth| depth >= min_depth).count();
+                        info!("Seeded {}x{} cells from {genomic_region}: {covered} of {} bases have depth {min_depth} or more", region.rows, region.cols, depth.len());
+                    }
+                    Err(err) => warn!("{err}"),
+                }
+            }
+            #[cfg(not(feature = "bam"))]
+            Action::SeedDepth { .. } => {
+                warn!("BAM/CRAM support is not available in this build, rebuild with `--features bam`");
+            }
+            Action::Open(path) => match SequenceLoader::start(&path) {
+                Ok(loader) => {
+                    info!("Loading {} in the background", path.display());
+                    self.loader = Some(loader);
+                }
+                Err(err) => warn!("{err}"),
+            },
+            Action::OpenRecord { path, record } => match SequenceLoader::start_record(&path, record) {
+                Ok(loader) => {
+                    info!("Loading record {} of {} in the background", record + 1, path.display());
+                    self.loader = Some(loader);
+                }
+                Err(err) => warn!("{err}"),
+            },
+            #[cfg(feature = "net")]
+            Action::Fetch(accession) => match SequenceLoader::start_accession(&accession) {
+                Ok(loader) => {
+                    info!("Fetching {accession} in the background");
+                    self.loader = Some(loader);
+                }
+                Err(err) => warn!("{err}"),
+            },
+            #[cfg(not(feature = "net"))]
+            Action::Fetch(_) => {
+                warn!("Downloading from NCBI is not available in this build, rebuild with `--features net`");
+            }
+            Action::OpenRows(path) => match SequenceLoader::start_rows(&path, self.universe.rows, self.universe.cols) {
+                Ok(loader) => {
+                    info!("Loading the records of {} in the background, one per row", path.display());
+                    self.loader = Some(loader);
+                }
+                Err(err) => warn!("{err}"),
+            },
+            Action::LoadPattern(path) => {
+                let region = self.overlay.selection.unwrap_or(Region::full(self.universe.rows, self.universe.cols));
+                match io::load(&path).and_then(|pattern| pattern.place(&mut self.universe, &region).map(|()| pattern)) {
+                    Ok(pattern) => info!("Loaded a {}x{} pattern of {} cells from {}", pattern.rows, pattern.cols, pattern.population(), path.display()),
+                    Err(err) => warn!("{err}"),
+                }
+            }
+            Action::SavePattern(path) => {
+                let region = self.overlay.selection.unwrap_or(Region::full(self.universe.rows, self.universe.cols));
+                let pattern = Pattern::from_region(&self.universe, &region);
+                match io::save(&path, &pattern) {
+                    Ok(()) => info!("Saved a {}x{} pattern of {} cells to {}", pattern.rows, pattern.cols, pattern.population(), path.display()),
+                    Err(err) => warn!("{err}"),
+                }
+            }
+            Action::Compare(Some((path, comparison))) => match fasta::load_first(&path) {
+                Ok(other) => {
+                    let requested = comparison;
+                    let comparison = comparison.for_lengths(self.dna.len(), other.len());
+                    if comparison != requested {
+                        warn!("The sequences are too long to align ({} and {} bases), comparing them by position", self.dna.len(), other.len());
+                    }
+                    let mismatches = difference::mismatches(&self.dna, &other, comparison);
+                    let differing = mismatches.iter().filter(|&&mismatch| mismatch).count();
+                    info!(
+                        "{differing} of {} positions differ from {} ({} bases, compared by {comparison})",
+                        self.dna.len(),
+                        path.display(),
+                        other.len(),
+                    );
+                    self.other_dna = Some(other);
+                    self.seed_strategy = SeedStrategy::Difference(mismatches);
+                    let region = self.overlay.selection.unwrap_or(Region::full(self.universe.rows, self.universe.cols));
+                    self.seed(&region, 0);
+                }
+                Err(err) => warn!("{err}"),
+            },
+            Action::Compare(None) => {
+                self.other_dna = None;
+                self.seed_strategy = sequence_seed_strategy(&self.dna);
+                let region = self.overlay.selection.unwrap_or(Region::full(self.universe.rows, self.universe.cols));
+                self.seed(&region, 0);
+                info!("Seeding from the sequence again");
+            }
+            Action::ReadFrame(Some(frame)) => {
+                if protein::is_protein(&self.dna) {
+                    warn!("Reading frames need a nucleotide sequence, this one is protein");
+                } else {
+                    self.frame_stats = frames::stats(&self.dna);
+                    self.seed_strategy = translated_seed_strategy(&self.dna, frame);
+                    self.seed(&Region::full(self.universe.rows, self.universe.cols), self.seed_offset());
+                    let stats = self.frame_stats[frame.number() - 1];
+                    info!("Reading frame {frame}: {} codons, {:.1}% stops", stats.codons, stats.stop_density() * 100.0);
+                }
+            }
+            Action::ReadFrame(None) => {
+                if self.frames_shown() {
+                    self.seed_strategy = sequence_seed_strategy(&self.dna);
+                    self.seed(&Region::full(self.universe.rows, self.universe.cols), self.seed_offset());
+                    info!("Seeding from the sequence again");
+                }
+            }
+            Action::LoadAlignment(Some((path, threshold))) => match msa::load(&path) {
+                Ok(alignment) => {
+                    let conservation = alignment.reference_conservation();
+                    info!(
+                        "Loaded an alignment of {} sequences and {} columns from {}, mean conservation {:.2}",
+                        alignment.sequences.len(),
+                        alignment.columns(),
+                        path.display(),
+                        conservation.iter().sum::<f64>() / conservation.len().max(1) as f64,
+                    );
+                    if conservation.len() != self.dna.len() {
+                        warn!("{} has {} residues, the sequence {} bases; positions are matched in order", alignment.ids[0], conservation.len(), self.dna.len());
+                    }
+                    let previous = self.tracks.selected().name;
+                    self.tracks.register(Track { name: "conservation", label: "alignment conservation", values: conservation.clone(), window: 1, color: [0.9, 0.8, 0.3], mark: None });
+                    let _ = self.tracks.select("conservation");
+                    if previous != "conservation" {
+                        self.track_fade = Some((previous, Instant::now()));
+                    }
+                    self.overlay.layers.show(Layer::Heat, true);
+                    if let Some(threshold) = threshold {
+                        let unconserved: Vec<bool> = conservation.iter().map(|&score| score < threshold).collect();
+                        let count = unconserved.iter().filter(|&&flag| flag).count();
+                        self.seed_strategy = SeedStrategy::Unconserved(unconserved);
+                        let region = self.overlay.selection.unwrap_or(Region::full(self.universe.rows, self.universe.cols));
+                        self.seed(&region, 0);
+                        info!("Reseeded from the {count} positions conserved less than {threshold}");
+                    }
+                    self.analysis_dirty = true;
+                }
+                Err(err) => warn!("{err}"),
+            },
+            Action::LoadAlignment(None) => {
+                self.tracks.remove("conservation");
+                if matches!(self.seed_strategy, SeedStrategy::Unconserved(_)) {
+                    self.seed_strategy = sequence_seed_strategy(&self.dna);
+                    let region = self.overlay.selection.unwrap_or(Region::full(self.universe.rows, self.universe.cols));
+                    self.seed(&region, 0);
+                    info!("Seeding from the sequence again");
+                }
+                self.analysis_dirty = true;
+            }
+            Action::Shuffle(Some((kind, seed))) => {
+                let original = self.unshuffled.take().unwrap_or_else(|| std::mem::take(&mut self.dna));
+                let shuffled = shuffle::shuffle(&original, kind, seed);
+                self.unshuffled = Some(original);
+                self.set_sequence(shuffled);
+                let region = self.overlay.selection.unwrap_or(Region::full(self.universe.rows, self.universe.cols));
+                self.seed(&region, 0);
+                info!("Reseeded {}x{} cells from a {kind} shuffle of the sequence (seed {seed})", region.rows, region.cols);
+            }
+            Action::Shuffle(None) => match self.unshuffled.take() {
+                Some(original) => {
+                    self.set_sequence(original);
+                    let region = self.overlay.selection.unwrap_or(Region::full(self.universe.rows, self.universe.cols));
+                    self.seed(&region, 0);
+                    info!("Seeding from the original sequence again");
+                }
+                None => info!("The sequence is not shuffled"),
+            },
+            Action::SetRule(rule) => {
+                self.universe.rule = rule;
+                info!("Rule: {rule}");
+                self.grid_dirty = true;
+            }
+            Action::UseNamedRule(name) => match self.rule_library.get(&name).cloned() {
+                Some(named) => {
+                    self.apply(Action::SetRule(named.rule));
+                    if !named.description.is_empty() {
+                        info!("{}: {}", named.name, named.description);
+                    }
+                }
+                None => warn!("Unknown rule name '{name}', `rules` lists them"),
+            },
+            Action::ShowRuleLibrary(show) => {
+                self.rule_panel = show;
+                self.grid_dirty = true;
+            }
+            Action::SaveNamedRule { name, description } => {
+                let named = NamedRule { name: name.to_ascii_lowercase(), rule: self.universe.rule, description };
+                match rule_library::save(&self.config_path, &named) {
+                    Ok(()) => {
+                        info!("Saved rule {} ({}) to {}", named.name, named.rule, self.config_path.display());
+                        self.rule_library.add(named);
+                        self.grid_dirty = true;
+                    }
+                    Err(err) => warn!("{err}"),
+                }
+            }
+            Action::SetPreset(slot, preset) => {
+                self.presets.set(slot, preset);
+                info!("Preset {slot}: {preset}");
+            }
+            Action::SavePreset(slot) => {
+                let Parameters { rule, seed_strategy, theme } = self.parameters();
+                let seeding = if seed_strategy == SeedStrategy::Hydrophobic { Seeding::Hydrophobic } else { Seeding::Gc };
+                if !matches!(seed_strategy, SeedStrategy::GcContent | SeedStrategy::Hydrophobic) {
+                    warn!("Presets can't hold a comparison, alignment or reading frame seeding; preset {slot} seeds from G/C");
+                }
+                let preset = Preset { rule, seeding, theme };
+                self.presets.set(slot, preset);
+                info!("Preset {slot}: {preset}");
+            }
+            Action::UsePreset(slot) => match self.presets.get(slot).copied() {
+                Some(preset) => {
+                    self.set_parameters(preset.parameters());
+                    self.presets.active = Some(slot);
+                    self.seed(&Region::full(self.universe.rows, self.universe.cols), self.seed_offset());
+                    info!("Preset {slot}: {preset}");
+                }
+                None => warn!("Preset {slot} is not set, define it with 'preset {slot} <B/S> <gc|hydrophobic> <red|blue|contrast>'"),
+            },
+            Action::GcRules(Some((poor, rich))) => {
+                let gc_rules = GcRules::new(poor, rich, &self.dna, &self.seed_map, self.universe.rows, self.universe.cols);
+                let [poor_cells, average, rich_cells] = gc_rules.counts();
+                info!(
+                    "GC rules: {poor_cells} cells below {:.0}% GC follow {poor}, {rich_cells} above {:.0}% follow {rich}, {average} follow {}",
+                    gc_rules::POOR_BELOW * 100.0,
+                    gc_rules::RICH_ABOVE * 100.0,
+                    self.universe.rule,
+                );
+                self.universe.gc_rules = Some(gc_rules);
+            }
+            Action::GcRules(None) => {
+                self.universe.gc_rules = None;
+                info!("Every cell follows {} again", self.universe.rule);
+            }
+            Action::WalkRules(Some(interval)) => {
+                let population = self.universe.cells.iter().filter(|&&alive| alive).count();
+                self.rule_walk = Some(RuleWalk::new(interval, self.universe.rule, self.universe.generation, population));
+                info!("Walking from {} every {interval} generations", self.universe.rule);
+            }
+            Action::WalkRules(None) => {
+                if self.rule_walk.take().is_some() {
+                    info!("Rule walk stopped at {}", self.universe.rule);
+                }
+            }
+            Action::MarkRule => match self.rule_walk.as_mut() {
+                Some(walk) => {
+                    walk.mark_current();
+                    info!("Marked {}", walk.current().rule);
+                }
+                None => info!("No rule walk running, start one with 'walk <generations>'"),
+            },
+            Action::ShowRuleHistory => match &self.rule_walk {
+                Some(walk) => {
+                    info!("Rules visited, every {} generations:", walk.interval);
+                    for step in &walk.history {
+                        let mark = if step.marked { " *" } else { "" };
+                        info!("  {:>10} {:<20} {:>8} alive{mark}", step.generation, step.rule.to_string(), step.population);
+                    }
+                }
+                None => info!("No rule walk running, start one with 'walk <generations>'"),
+            },
+            Action::ExportRuleHistory(path) => match &self.rule_walk {
+                Some(walk) => match std::fs::write(&path, self.provenance().commented(&walk.to_csv())) {
+                    Ok(()) => info!("Exported {} rules to {}", walk.history.len(), path.display()),
+                    Err(err) => warn!("Failed to export {}: {err}", path.display()),
+                },
+                None => info!("No rule walk running, start one with 'walk <generations>'"),
+            },
+            Action::SetSpeed(generations_per_second) => {
+                // Sessions, replays and the gamepad's halving and doubling aren't checked by the console
+                let generations_per_second = if generations_per_second.is_nan() { 1.0 } else { generations_per_second.clamp(MIN_SPEED, MAX_SPEED) };
+                self.tick_interval = Duration::from_secs_f64(1.0 / generations_per_second);
+                info!("Speed: {generations_per_second} generations/s");
+            }
+            Action::Turbo(steps) => match (steps, self.turbo.as_mut()) {
+                (Some(steps), Some(turbo)) => {
+                    turbo.steps = steps;
+                    info!("Turbo: {steps} generations a frame");
+                }
+                (Some(steps), None) => {
+                    self.turbo = Some(Turbo::new(steps, Instant::now()));
+                    info!("Turbo: {steps} generations a frame");
+                }
+                (None, _) => {
+                    self.turbo = None;
+                    info!("Turbo off, back to {} generations/s", self.speed());
+                }
+            },
+            Action::SetFit(fit) => {
+                self.layout.fit = fit;
+                info!("Fit: {fit}{}", if self.layout.cell_size.is_some() { " (once the cell size is back to auto)" } else { "" });
+            }
+            Action::SetPadding(padding) => {
+                self.layout.padding = padding.clamp(0.0, MAX_PADDING);
+                info!("Padding: {:.2} of the cell pitch", self.layout.padding);
+            }
+            Action::SetCellSize(size) => {
+                self.layout.cell_size = size.map(|size| size.clamp(1, MAX_CELL_SIZE));
+                match self.layout.cell_size {
+                    Some(size) => info!("Cell size: {size} px"),
+                    None => info!("Cell size: auto ({})", self.layout.fit),
+                }
+            }
+            Action::SetPan(pan) => {
+                self.layout.pan = pan;
+                info!("Pan: {} px right, {} px up", pan[0], pan[1]);
+            }
+            Action::SetLevelOfDetail(on) => {
+                self.level_of_detail = on;
+            }
+            Action::ShowLayout => {
+                let size = self.layout.cell_size.map_or(format!("auto, {} px now", self.cell_size()), |size| format!("{size} px"));
+                let [x, y] = self.layout.pan;
+                info!("Cell size {size}, padding {:.2}, fit {}, pan {x} {y}", self.layout.padding, self.layout.fit);
+                if self.lod_block > 1 {
+                    info!("Drawing blocks of {0}x{0} cells", self.lod_block);
+                }
+            }
+            Action::ResizeGrid { rows, cols, anchor } => self.resize_grid(rows, cols, anchor),
+            Action::HighContrast(on) => {
+                self.accessibility.high_contrast = on;
+            }
+            Action::Announce(every) => {
+                self.accessibility.announce_every = every;
+                self.announcer = Announcer::default();
+                self.announce();
+            }
+            Action::Mutate(params) => {
+                if let Some(mutator) = self.mutator.take() {
+                    info!("{} mutations", mutator.count);
+                    // The tracks follow the mutated sequence once it holds still
+                    if mutator.params.target == MutationTarget::Bases && mutator.count > 0 {
+                        let dna = std::mem::take(&mut self.dna);
+                        self.set_sequence(dna);
+                    }
+                }
+                match params {
+                    Some(params) => info!("Mutating {} at {} per cell per generation (seed {})", params.target, params.rate, params.seed),
+                    None => info!("Stopped mutating"),
+                }
+                if let Some(params) = params {
+                    self.rng_seed = Some(params.seed);
+                }
+                self.mutator = params.map(Mutator::new);
+                // Mutations are stepped here rather than on the simulation thread
+                self.simulation_synced = false;
+            }
+            Action::Pause(paused) => {
+                self.paused = paused;
+                info!("{} at generation {}", if paused { "Paused" } else { "Resumed" }, self.universe.generation);
+            }
+            #[cfg(feature = "arrow")]
+            Action::RecordTable(Some((path, columns))) => {
+                self.finish_table();
+                match TableRecorder::create(&path, columns, self.universe.rows, self.universe.cols, &self.provenance()) {
+                    Ok(table) => {
+                        info!("Recording every generation to {}", path.display());
+                        self.table = Some(table);
+                    }
+                    Err(err) => warn!("{err}"),
+                }
+            }
+            #[cfg(feature = "arrow")]
+            Action::RecordTable(None) => self.finish_table(),
+            #[cfg(not(feature = "arrow"))]
+            Action::RecordTable(_) => {
+                warn!("Arrow and Parquet export are not available in this build, rebuild with `--features arrow`");
+            }
+            Action::RecordHashes(path) => {
+                self.finish_hashes();
+                if let Some(path) = path {
+                    match HashRecorder::create(&path, &self.provenance()) {
+                        Ok(hashes) => {
+                            info!("Recording a hash of every generation from {} to {}", self.universe.generation, path.display());
+                            self.hashes = Some(hashes);
+                            self.record_hash();
+                        }
+                        Err(err) => warn!("{err}"),
+                    }
+                }
+            }
+            Action::Verify(Some(path)) => match Reference::load(&path) {
+                Ok(reference) => {
+                    info!("Verifying against {} generations of {}, through generation {}", reference.hashes.len(), path.display(), reference.last());
+                    self.verifier = Some(Verifier::new(path, reference));
+                    self.verify_generation();
+                }
+                Err(err) => warn!("{err}"),
+            },
+            Action::Verify(None) => {
+                if let Some(verifier) = self.verifier.take() {
+                    info!("Stopped verifying, {} generations of {} matched", verifier.checked.len(), verifier.path.display());
+                }
+            }
+            Action::ExportFrame(path) if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("svg")) => {
+                let geometry = self.geometry();
+                let provenance = self.provenance();
+                let Color { r, g, b, .. } = self.clear_color();
+                let figure = export::svg::Figure {
+                    universe: &self.universe,
+                    overlay: &self.overlay,
+                    rows: geometry.visible_range(self.universe.rows, 1),
+                    cols: geometry.visible_range(self.universe.cols, 0),
+                    padding: self.layout.padding,
+                    track: Some(self.tracks.selected()),
+                    background: [r as f32, g as f32, b as f32],
+                    provenance: Some(&provenance),
+                };
+                match figure.write(&path) {
+                    Ok(()) => info!("Exported frame to {}", path.display()),
+                    Err(err) => warn!("Failed to export {}: {err}", path.display()),
+                }
+            }
+            Action::ExportFrame(path) => {
+                let clear_color = self.clear_color();
+                let (width, height, pixels) = self.surface.capture(&self.gpu, clear_color);
+                match export::png::write_rgba(&path, width, height, &pixels, Some(&self.provenance())) {
+                    Ok(()) => info!("Exported frame to {}", path.display()),
+                    Err(err) => warn!("Failed to export {}: {err}", path.display()),
+                }
+            }
+            Action::ContactSheet(Some(settings)) => {
+                self.finish_sheet();
+                info!("Capturing every {} generations into a {}x{} contact sheet {}", settings.every, settings.rows, settings.cols, settings.path.display());
+                self.sheet = Some(ContactSheet::new(settings, self.universe.generation));
+            }
+            Action::ContactSheet(None) => {
+                if self.sheet.is_none() {
+                    info!("No contact sheet is being captured");
+                }
+                self.finish_sheet();
+            }
+            #[cfg(feature = "scripting")]
+            Action::LoadScript(Some(path)) => match Script::load(&path) {
+                Ok(script) => {
+                    info!("Loaded script {} (next: {}, seed: {})", path.display(), script.has_next(), script.has_seed());
+                    self.script = Some(script);
+                }
+                Err(err) => warn!("{err}"),
+            },
+            #[cfg(feature = "scripting")]
+            Action::LoadScript(None) => {
+                if self.script.take().is_some() {
+                    info!("Script unloaded, using rule {}", self.universe.rule);
+                }
+            }
+            #[cfg(not(feature = "scripting"))]
+            Action::LoadScript(_) => {
+                warn!("Scripting is not available in this build, rebuild with `--features scripting`");
+            }
+            Action::LoadSignal(Some((path, chrom))) => match bedgraph::load(&path) {
+                Ok(intervals) => {
+                    let signal = bedgraph::signal_per_position(&intervals, chrom.as_deref(), self.universe.cells.len());
+                    let covered = signal.iter().filter(|&&value| value > 0.0).count();
+                    info!("Loaded {} intervals from {}, {covered} cells covered", intervals.len(), path.display());
+                    self.universe.signal = Some(signal);
+                }
+                Err(err) => warn!("{err}"),
+            },
+            Action::LoadSignal(None) => {
+                self.universe.signal = None;
+            }
+            Action::LoadVariants(Some((path, chrom, filter))) => match vcf::load(&path) {
+                Ok(variants) => {
+                    let chrom = chrom.or_else(|| variants.first().map(|variant| variant.chrom.clone()));
+                    let shown: Vec<_> = variants
+                        .iter()
+                        .filter(|variant| Some(&variant.chrom) == chrom.as_ref() && filter.accepts(variant))
+                        .collect();
+                    self.overlay.variants = shown
+                        .iter()
+                        .filter(|variant| variant.position < self.universe.cells.len())
+                        .map(|variant| {
+                            let color = match variant.kind {
+                                VariantKind::Snv => [0.95, 0.25, 0.25],
+                                VariantKind::Indel => [0.3, 0.5, 1.0],
+                                VariantKind::Other => [0.85, 0.85, 0.85],
+                            };
+                            (variant.position, color)
+                        })
+                        .collect();
+                    info!(
+                        "{} of {} variants on {} pass the filter ({filter}), {} of them on the grid",
+                        shown.len(),
+                        variants.len(),
+                        chrom.as_deref().unwrap_or("-"),
+                        self.overlay.variants.len(),
+                    );
+                }
+                Err(err) => warn!("{err}"),
+            },
+            Action::LoadVariants(None) => {
+                self.overlay.variants.clear();
+            }
+            Action::SetBirthThreshold(threshold) => {
+                self.universe.birth_threshold = threshold;
+                match threshold {
+                    Some(threshold) => info!("Births need a signal of at least {threshold}"),
+                    None => info!("Births no longer depend on the signal"),
+                }
+            }
+            Action::SearchMotif(Some(motif)) => match motif.search(&self.dna) {
+                Ok(matches) => {
+                    info!("Motif {motif}: {} matches", matches.len());
+                    for m in matches.iter().take(MAX_LISTED_MATCHES) {
+                        let strand = if m.strand == Strand::Forward { '+' } else { '-' };
+                        let bases = String::from_utf8_lossy(&self.dna[m.start..m.end]);
+                        info!("  {}..{} ({strand}) {bases}", m.start, m.end);
+                    }
+                    if matches.len() > MAX_LISTED_MATCHES {
+                        info!("  ... and {} more", matches.len() - MAX_LISTED_MATCHES);
+                    }
+
+                    self.overlay.highlights = matches
+                        .iter()
+                        .flat_map(|m| {
+                            let color = if m.strand == Strand::Forward { [1.0, 0.6, 0.1] } else { [0.2, 0.8, 0.9] };
+                            (m.start..m.end).map(move |position| (position, color))
+                        })
+                        .collect();
+                }
+                Err(err) => warn!("{err}"),
+            },
+            Action::SearchMotif(None) => {
+                self.overlay.highlights.clear();
+            }
+            Action::ShowSignals(Some(filter)) => {
+                let found: Vec<Signal> = signals::find(&self.dna).into_iter().filter(|signal| filter.accepts(signal)).collect();
+                let count = |kind| found.iter().filter(|signal| signal.kind == kind).count();
+                info!(
+                    "Signals ({filter}): {} starts, {} stops, {} Kozak and {} Shine-Dalgarno sites",
+                    count(SignalKind::Start),
+                    count(SignalKind::Stop),
+                    count(SignalKind::Kozak),
+                    count(SignalKind::ShineDalgarno),
+                );
+                self.signals = Some((filter, found));
+            }
+            Action::ShowSignals(None) => {
+                self.signals = None;
+            }
+            Action::FindRepeats(Some(params)) => {
+                let found = repeats::find(&self.dna, &params);
+                info!("Tandem repeats ({params}): {} covering {} bases", found.len(), found.iter().map(|repeat| repeat.end - repeat.start).sum::<usize>());
+                self.repeats = Some((params, found));
+            }
+            Action::FindRepeats(None) => {
+                self.repeats = None;
+            }
+            Action::Pcr(Some((primers, max_mismatches))) => match pcr::amplify(&self.dna, &primers, max_mismatches, pcr::MAX_PRODUCT) {
+                Ok((sites, amplicons)) => {
+                    info!("Primers {} / {}: {} binding sites with up to {max_mismatches} mismatches, {} products", primers.forward, primers.reverse, sites.len(), amplicons.len());
+                    for amplicon in amplicons.iter().take(MAX_LISTED_MATCHES) {
+                        let primer = |site: &pcr::Site| if site.primer == Primer::Forward { "forward" } else { "reverse" };
+                        let (left, right) = (&amplicon.left, &amplicon.right);
+                        info!(
+                            "  {}..{}: {} bp, {} primer at {} ({} mismatches) to {} primer at {} ({} mismatches)",
+                            amplicon.start, amplicon.end, amplicon.len(), primer(left), left.start, left.mismatches, primer(right), right.start, right.mismatches,
+                        );
+                    }
+                    if amplicons.len() > MAX_LISTED_MATCHES {
+                        info!("  ... and {} more", amplicons.len() - MAX_LISTED_MATCHES);
+                    }
+
+                    // Products in a dim color, with the primer sites over them colored by
+                    // strand as motif matches are
+                    let mut colors = std::collections::BTreeMap::new();
+                    for amplicon in &amplicons {
+                        colors.extend((amplicon.start..amplicon.end).map(|position| (position, [0.55, 0.45, 0.85])));
+                    }
+                    for site in &sites {
+                        let color = if site.strand == Strand::Forward { [1.0, 0.6, 0.1] } else { [0.2, 0.8, 0.9] };
+                        colors.extend((site.start..site.end).map(|position| (position, color)));
+                    }
+                    self.overlay.highlights = colors.into_iter().collect();
+                }
+                Err(err) => warn!("{err}"),
+            },
+            Action::Pcr(None) => {
+                self.overlay.highlights.clear();
+            }
+            Action::DesignPrimers(Some(constraints)) => {
+                let Some(region) = self.overlay.selection else {
+                    warn!("Select a region to design primers for first");
+                    return;
+                };
+                // The bases from the first to the last one seeded into the selection
+                let region = region.within(self.universe.rows, self.universe.cols);
+                let positions: Vec<usize> = (region.row..region.row + region.rows)
+                    .flat_map(|row| (region.col..region.col + region.cols).map(move |col| (row, col)))
+                    .filter_map(|(row, col)| self.seed_map.position(row, col))
+                    .collect();
+                let (Some(&first), Some(&last)) = (positions.iter().min(), positions.iter().max()) else {
+                    warn!("The selection holds no bases");
+                    return;
+                };
+                let range = first.min(self.dna.len())..(last + 1).min(self.dna.len());
+                let candidates = match primers::design(&self.dna, range.clone(), &constraints, PRIMER_CANDIDATES) {
+                    Ok(candidates) => candidates,
+                    Err(err) => {
+                        warn!("{err}");
+                        return;
+                    }
+                };
+                info!("{} primers for bases {}..{} ({constraints})", candidates.len(), range.start, range.end);
+                for (i, candidate) in candidates.iter().enumerate() {
+                    info!("  {} {}", i + 1, primer_line(candidate));
+                }
+                self.primers = Some((range, candidates));
+            }
+            Action::DesignPrimers(None) => {
+                self.primers = None;
+            }
+            Action::CopyPrimers(number) => {
+                let Some((_, candidates)) = &self.primers else {
+                    warn!("No primers to copy; propose some with 'primers'");
+                    return;
+                };
+                // One primer as it is, or all of them as FASTA
+                let text = match number {
+                    Some(number) => match candidates.get(number - 1) {
+                        Some(candidate) => candidate.sequence.clone(),
+                        None => {
+                            warn!("There is no primer {number}, only {}", candidates.len());
+                            return;
+                        }
+                    },
+                    None => candidates.iter().enumerate().map(|(i, candidate)| format!(">primer_{} {}..{}\n{}\n", i + 1, candidate.start, candidate.end, candidate.sequence)).collect(),
+                };
+                match clipboard::copy(&text) {
+                    Ok(()) => info!("Copied {} to the clipboard", if number.is_some() { "the primer" } else { "the primers" }),
+                    Err(err) => warn!("{err}"),
+                }
+            }
+            Action::ScanPwm(Some((path, threshold))) => match Pwm::load(&path) {
+                Ok(pwm) => {
+                    self.pwm = Some((pwm, threshold));
+                    self.highlight_pwm_sites();
+                }
+                Err(err) => warn!("{err}"),
+            },
+            Action::ScanPwm(None) => {
+                self.pwm = None;
+                self.overlay.highlights.clear();
+            }
+            Action::SetPwmBackground(background) => {
+                self.pwm_background = background;
+                info!("PWM background: {background}");
+                self.highlight_pwm_sites();
+            }
+            Action::MaskLowComplexity(Some((params, seeding))) => {
+                let intervals = low_complexity(&self.dna, &params);
+                let bases: usize = intervals.iter().map(|interval| interval.len()).sum();
+                info!("{} low-complexity intervals covering {bases} bases", intervals.len());
+                for interval in intervals.iter().take(MAX_LISTED_MATCHES) {
+                    info!("  {}..{} {}", interval.start, interval.end, String::from_utf8_lossy(&self.dna[interval.clone()]));
+                }
+                if intervals.len() > MAX_LISTED_MATCHES {
+                    info!("  ... and {} more", intervals.len() - MAX_LISTED_MATCHES);
+                }
+                if seeding {
+                    info!("Masked positions stay dead from the next reseed on");
+                }
+
+                self.overlay.masked = intervals.iter().flat_map(|interval| interval.clone()).collect();
+                self.mask = Some((intervals, seeding));
+            }
+            Action::MaskLowComplexity(None) => {
+                self.mask = None;
+                self.overlay.masked.clear();
+            }
+            Action::CodonUsage(Some(source)) => {
+                let cds = match source {
+                    CdsSource::Gff(path, seqid) => match gff::load(&path) {
+                        Ok(features) => gff::coding_sequences(&features, seqid.as_deref()),
+                        Err(err) => {
+                            warn!("{err}");
+                            return;
+                        }
+                    },
+                    CdsSource::Orfs(min_codons) => codon_usage::find_orfs(&self.dna, min_codons),
+                };
+                let mut table = CodonTable::from_cds(&self.dna, &cds);
+                table.sort(self.codon_sort);
+                info!("{} coding sequences, {} codons", cds.len(), table.total());
+                print_codon_table(&table);
+
+                self.overlay.optimality = codon_usage::optimality(&self.dna, &cds, &table)
+                    .into_iter()
+                    .map(|optimality| optimality.map(|optimality| optimality as f32))
+                    .collect();
+                self.codons = Some(table);
+            }
+            Action::CodonUsage(None) => {
+                self.codons = None;
+                self.overlay.optimality.clear();
+            }
+            Action::SortCodons(sort) => {
+                self.codon_sort = sort;
+                match self.codons.as_mut() {
+                    Some(table) => {
+                        table.sort(sort);
+                        print_codon_table(table);
+                    }
+                    None => info!("No codon usage yet, run 'codons gff <file>' or 'codons orf' first"),
+                }
+            }
+            Action::ExportCodons(path) => match &self.codons {
+                Some(table) => match std::fs::write(&path, self.provenance().commented(&table.to_csv())) {
+                    Ok(()) => info!("Exported codon usage to {}", path.display()),
+                    Err(err) => warn!("Failed to export {}: {err}", path.display()),
+                },
+                None => info!("No codon usage yet, run 'codons gff <file>' or 'codons orf' first"),
+            },
+            Action::LoadShader(Some(path)) => {
+                let mut shader = ShaderFile::open(&path);
+                match shader.read() {
+                    Ok(source) => {
+                        // A broken shader is still watched, so saving a fix picks it up
+                        self.compile_shader(&mut shader, &source);
+                        self.shader = Some(shader);
+                    }
+                    Err(err) => warn!("{err}"),
+                }
+            }
+            Action::LoadShader(None) => {
+                if self.shader.take().is_some() {
+                    self.gpu.set_shader(None);
+                    self.rebuild_pipelines();
+                    info!("Using the built-in shader");
+                }
+            }
+            Action::SetEffect(effect, enabled) => {
+                self.effects.set(effect, enabled);
+                self.update_post_process();
+                info!("{effect} {}", if enabled { "on" } else { "off" });
+            }
+            Action::ShowHeatmap(show) => {
+                self.heatmap_view = show;
+                if show {
+                    info!("Heat map of {} generations", self.heatmap.generations);
+                }
+            }
+            Action::MarkGeneration => {
+                self.mark = Some(Snapshot { generation: self.universe.generation, cells: self.universe.cells.clone() });
+                info!("Marked generation {}", self.universe.generation);
+            }
+            Action::ShowDiff(true) => match &self.mark {
+                Some(mark) => {
+                    let [born, died, stayed] = mark.counts(&self.universe.cells);
+                    info!("Since generation {}: {born} cells born, {died} died, {stayed} alive throughout", mark.generation);
+                    self.diff_view = true;
+                }
+                None => warn!("No generation marked, mark one with B or 'diff mark'"),
+            },
+            Action::ShowDiff(false) => self.diff_view = false,
+            Action::ResetHeatmap => {
+                self.heatmap.reset();
+                info!("Heat map reset");
+            }
+            Action::ExportHeatmap(path) => {
+                let (rows, cols) = (self.universe.rows, self.universe.cols);
+                let result = if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("csv")) {
+                    std::fs::write(&path, self.provenance().commented(&self.heatmap.to_csv(cols)))
+                } else {
+                    export::png::write_rgba(&path, cols, rows, &self.heatmap.to_rgba(cols), Some(&self.provenance()))
+                };
+                match result {
+                    Ok(()) => info!("Exported heat map of {} generations to {}", self.heatmap.generations, path.display()),
+                    Err(err) => warn!("Failed to export {}: {err}", path.display()),
+                }
+            }
+            Action::ShowRuler(show) => {
+                self.ruler_view = show;
+            }
+            Action::ShowContours(show) => {
+                self.contour_view = show;
+                if !show {
+                    self.contours = Contours::default();
+                } else if self.universe.cells.len() > contour::MAX_TRACED {
+                    info!("Outlines are drawn once no more than {} cells are in view", contour::MAX_TRACED);
+                }
+            }
+            Action::SmoothTransitions(smooth) => {
+                self.transition = smooth.then(|| Transition::new(&self.universe, self.clock.now()));
+            }
+            Action::ShowLetters(show) => {
+                self.letter_view = show;
+            }
+            // The next rebuild builds the view's GPU objects and drops the other one's
+            Action::ShowTorus(show) => {
+                self.torus_view = show;
+                if show {
+                    self.heightfield_view = false;
+                } else {
+                    self.torus_drag = None;
+                }
+            }
+            Action::ShowHeightfield(show) => {
+                self.heightfield_view = show;
+                if show {
+                    self.torus_view = false;
+                    self.torus_drag = None;
+                }
+            }
+            Action::ShowTrail(show) => {
+                self.trail_view = show;
+                if show && let Some([dx, dy]) = self.trajectory.drift() {
+                    info!("Drift: {dx:+.3} columns, {dy:+.3} rows per generation");
+                }
+            }
+            Action::TrackBlobs(track) => {
+                self.blob_tracking = track;
+                self.blob_trail.clear();
+                self.blob_summary = None;
+                if track {
+                    self.track_blobs();
+                }
+            }
+            Action::ColorBlobs(show) => {
+                self.blob_colors = show;
+            }
+            Action::ShowBlobStats => {
+                let components = Components::label(&self.universe.cells, self.universe.rows, self.universe.cols);
+                let sizes = components.sorted_sizes();
+                info!("Generation {}: {} connected groups of live cells", self.universe.generation, components.count());
+                if let Some(largest) = components.largest().and_then(|id| components.centroid(id)) {
+                    let shown: Vec<String> = sizes.iter().take(LISTED_COMPONENTS).map(u32::to_string).collect();
+                    info!("Sizes, largest first: {}{}", shown.join(", "), if sizes.len() > LISTED_COMPONENTS { ", ..." } else { "" });
+                    info!("Largest at column {:.1}, row {:.1}", largest[0], largest[1]);
+                }
+                if let Some([dx, dy]) = self.blob_trail.drift() {
+                    info!("Largest drift: {dx:+.3} columns, {dy:+.3} rows per generation");
+                }
+            }
+            Action::ExportReport(path) => {
+                let report = self.report();
+                match std::fs::write(&path, self.provenance().commented(&report.to_tsv())) {
+                    Ok(()) => info!("Exported report of generation {} to {}", report.generation, path.display()),
+                    Err(err) => warn!("Failed to export {}: {err}", path.display()),
+                }
+            }
+            Action::SaveSession(path) => match self.session().save(&path) {
+                Ok(()) => info!("Saved the session at generation {} to {}", self.universe.generation, path.display()),
+                Err(err) => warn!("{err}"),
+            },
+            Action::LoadSession(path) => match Session::load(&path) {
+                Ok(session) => self.restore(&path, session),
+                Err(err) => warn!("{err}"),
+            },
+            Action::Ants(Some(count)) => {
+                let colony = Colony::new(&self.dna, self.universe.rows, self.universe.cols, count);
+                info!("{} ants walking, turning right on purines and left on pyrimidines (reversed on live cells)", colony.ants.len());
+                self.mode = Mode::Ants(colony);
+            }
+            Action::Ants(None) => {
+                if matches!(self.mode, Mode::Ants(_)) {
+                    self.mode = Mode::Rule;
+                    info!("Ants stopped, using rule {}", self.universe.rule);
+                }
+            }
+            Action::ShowAntStats => match &self.mode {
+                Mode::Ants(colony) => {
+                    let cells = self.universe.cells.len();
+                    info!("{} ants, {} steps", colony.ants.len(), colony.steps);
+                    info!("Visited {} of {cells} cells ({:.1}%)", colony.visited_count, colony.visited_count as f64 / cells as f64 * 100.0);
+                    for ant in &colony.ants {
+                        info!("  ({}, {}) heading {:?}", ant.row, ant.col, ant.heading);
+                    }
+                }
+                _ => info!("No ants running, start them with 'ant on'"),
+            },
+            Action::Cyclic(Some(threshold)) => {
+                let automaton = Cyclic::new(&self.dna, self.universe.rows, self.universe.cols, threshold);
+                info!("Cyclic automaton, A > C > G > T > A, taking over at {threshold} neighbors: {}", composition(&automaton));
+                self.mode = Mode::Cyclic(automaton);
+            }
+            Action::Cyclic(None) => {
+                if matches!(self.mode, Mode::Cyclic(_)) {
+                    self.mode = Mode::Rule;
+                    info!("Cyclic automaton stopped, using rule {}", self.universe.rule);
+                }
+            }
+            Action::Protein(true) => {
+                let automaton = Protein::new(&self.dna, self.universe.rows, self.universe.cols);
+                let source = if protein::is_protein(&self.dna) { "protein sequence" } else { "translated sequence" };
+                info!("Protein automaton from the {source}: {}", class_shares(&automaton));
+                self.mode = Mode::Protein(automaton);
+            }
+            Action::Protein(false) => {
+                if matches!(self.mode, Mode::Protein(_)) {
+                    self.mode = Mode::Rule;
+                    info!("Protein automaton stopped, using rule {}", self.universe.rule);
+                }
+            }
+            Action::Lenia(Some(source)) => {
+                let params = match source {
+                    KernelSource::Standard => lenia::Params::STANDARD,
+                    KernelSource::Sequence => lenia::Params::from_sequence(&self.dna),
+                };
+                let mut automaton = Lenia::new(&self.dna, self.universe.rows, self.universe.cols, params);
+                // GPUs round differently, so deterministic runs stay on the CPU
+                if self.clock.is_fixed() {
+                    info!("Lenia ({source} kernel: {params}) on the CPU");
+                } else {
+                    match automaton.attach(&self.gpu) {
+                        Ok(()) => info!("Lenia ({source} kernel: {params}) on the GPU"),
+                        Err(err) => warn!("Lenia compute shader unavailable, stepping on the CPU: {err}"),
+                    }
+                }
+                self.mode = Mode::Lenia(automaton);
+            }
+            Action::Lenia(None) => {
+                if matches!(self.mode, Mode::Lenia(_)) {
+                    self.mode = Mode::Rule;
+                    info!("Lenia stopped, using rule {}", self.universe.rule);
+                }
+            }
+            Action::ShowStrip(show) => {
+                self.strip_view = show;
+            }
+            Action::ShowHelp(show) => {
+                self.help_view = show;
+            }
+            Action::ShowProfiler(show) => {
+                self.profile_view = show;
+                self.cpu_timings.clear();
+                match &self.gpu.profiler {
+                    Some(profiler) => profiler.set_enabled(show),
+                    None if show => warn!("{} has no timestamp queries; only CPU timings are shown", self.gpu.adapter.get_info().name),
+                    None => {}
+                }
+            }
+            Action::SelectTrack(name) => {
+                let previous = self.tracks.selected().name;
+                let selected = match name {
+                    Some(name) => self.tracks.select(&name),
+                    None => Ok(self.tracks.select_next()),
+                };
+                match selected {
+                    Ok(track) => {
+                        let (min, max) = track.range();
+                        info!("Track: {}, {min:.2} to {max:.2}", track.label);
+                        if track.name != previous {
+                            self.track_fade = Some((previous, Instant::now()));
+                        }
+                    }
+                    Err(err) => warn!("{err}"),
+                }
+            }
+            Action::ShowTrackLayer(show) => {
+                self.overlay.layers.show(Layer::Heat, show);
+            }
+            Action::ShowLayer(layer, show) => {
+                self.overlay.layers.show(layer, show);
+            }
+            Action::SetLayerOpacity(layer, opacity) => {
+                self.overlay.layers.set_opacity(layer, opacity);
+            }
+            Action::MoveLayer { layer, up } => {
+                self.overlay.layers.raise(layer, up);
+                info!("Layers: {}", self.overlay.layers);
+            }
+            Action::SetLayers(layers) => {
+                self.overlay.layers = layers;
+            }
+            Action::ShowLayerPanel(show) => {
+                self.layer_panel = show;
+            }
+            Action::ListTracks => {
+                let selected = self.tracks.selected().name;
+                for name in self.tracks.names() {
+                    let track = self.tracks.get(name).unwrap();
+                    let marker = if name == selected { "*" } else { " " };
+                    info!("{marker} {name}: {}", track.label);
+                }
+            }
+            Action::ScrollStrip(position) => {
+                self.strip_start = position.min(self.dna.len().saturating_sub(1));
+            }
+            Action::SaveBookmark { slot, name } => {
+                let bookmark = Bookmark {
+                    name: name.unwrap_or_else(|| slot.to_string()),
+                    generation: self.universe.generation,
+                    cells: self.universe.cells.clone(),
+                    seed_map: self.seed_map.clone(),
+                    strip_start: self.strip_start,
+                    selection: self.overlay.selection,
+                    cell_size: self.layout.cell_size,
+                    pan: self.layout.pan,
+                };
+                let (name, generation) = (bookmark.name.clone(), bookmark.generation);
+                match self.bookmarks.save(slot, bookmark) {
+                    Ok(()) => info!("Saved bookmark {slot} '{name}' at generation {generation}"),
+                    Err(err) => warn!("{err}"),
+                }
+            }
+            Action::GotoBookmark(key) => match self.bookmarks.find(&key) {
+                Some((_, bookmark)) if bookmark.cells.len() != self.universe.cells.len() => {
+                    warn!("Bookmark '{}' was saved on a grid of another size", bookmark.name);
+                }
+                Some((slot, bookmark)) => {
+                    self.universe.cells.clone_from(&bookmark.cells);
+                    self.universe.generation = bookmark.generation;
+                    self.seed_map = bookmark.seed_map.clone();
+                    if let Some(gc_rules) = self.universe.gc_rules.as_mut() {
+                        let counts = self.stats.gc_counts.for_sequence(self.dna_id);
+                        gc_rules.classify_with(&self.dna, &self.seed_map, self.universe.rows, self.universe.cols, counts);
+                    }
+                    self.strip_start = bookmark.strip_start;
+                    self.overlay.selection = bookmark.selection;
+                    (self.layout.cell_size, self.layout.pan) = (bookmark.cell_size, bookmark.pan);
+                    self.analysis_dirty = true;
+                    info!("Went to bookmark {slot} '{}' at generation {}", bookmark.name, bookmark.generation);
+                }
+                None => warn!("No bookmark '{key}'"),
+            },
+            Action::ListBookmarks => {
+                if self.bookmarks.is_empty() {
+                    info!("No bookmarks, save one with Ctrl+1..9 or 'bookmark save <slot> [name]'");
+                }
+                for (slot, bookmark) in self.bookmarks.iter() {
+                    info!("{slot} {}: generation {}, strip at base {}", bookmark.name, bookmark.generation, bookmark.strip_start);
+                }
+            }
+            Action::Annotate { row, col, text } => {
+                let Some(position) = self.seed_map.position(row, col).filter(|&position| position < self.dna.len()) else {
+                    warn!("Cell ({row}, {col}) wasn't seeded from the sequence, so a note has nothing to attach to");
+                    return;
+                };
+                match text {
+                    Some(text) => {
+                        self.notes.set(position, &text);
+                        info!("Noted base {position}: {text}");
+                    }
+                    None => match self.notes.remove(position) {
+                        Some(_) => info!("Removed the note on base {position}"),
+                        None => warn!("Base {position} has no note"),
+                    },
+                }
+            }
+            Action::ListNotes => {
+                if self.notes.is_empty() {
+                    info!("No notes, right-click a cell or use 'note <row> <col> <text>' to add one");
+                }
+                for note in self.notes.iter() {
+                    info!("Base {}: {}", note.position, note.text);
+                }
+            }
+            Action::ExportNotes { path, chrom } => {
+                let notes = self.notes.iter().map(|note| (note.position..note.position + 1, note.text.as_str()));
+                let bed = self.provenance().commented(&export::bed::to_bed(notes, chrom.as_deref()));
+                match std::fs::write(&path, bed) {
+                    Ok(()) => info!("Exported {} notes to {}", self.notes.len(), path.display()),
+                    Err(err) => warn!("Failed to export {}: {err}", path.display()),
+                }
+            }
+            Action::ExportAlive { path, chrom } => {
+                let intervals = export::bed::alive_intervals(&self.universe.cells, self.universe.cols, &self.seed_map, self.dna.len());
+                let bed = self.provenance().commented(&export::bed::to_bed(intervals.iter().map(|interval| (interval.clone(), "alive")), chrom.as_deref()));
+                match std::fs::write(&path, bed) {
+                    Ok(()) => {
+                        let bases: usize = intervals.iter().map(|interval| interval.len()).sum();
+                        info!("Exported {} intervals covering {bases} bases to {}", intervals.len(), path.display());
+                    }
+                    Err(err) => warn!("Failed to export {}: {err}", path.display()),
+                }
+            }
+            Action::ShowAnalysis(true) => {
+                // The event loop owns window creation; it picks this up in `wants_analysis_window`
+                self.analysis_requested = self.analysis.is_none();
+            }
+            Action::ShowAnalysis(false) => {
+                self.analysis_requested = false;
+                // Dropping the surface releases the last reference to the window, closing it
+                self.analysis = None;
+                self.analysis_hover = None;
+            }
+            Action::ExportAnalysis(path) => {
+                let (width, height) = ANALYSIS_EXPORT_SIZE;
+                let viewport = Viewport { width, height, scale_factor: 1.0 };
+                let pixels = Offscreen::new(&self.gpu, width, height, &self.analysis_vertices(&viewport, None)).capture(&self.gpu, self.clear_color());
+                match export::png::write_rgba(&path, width, height, &pixels, Some(&self.provenance())) {
+                    Ok(()) => info!("Exported the analysis charts to {}", path.display()),
+                    Err(err) => warn!("Failed to export {}: {err}", path.display()),
+                }
+            }
+            Action::Undo => match self.history.undo(&mut self.universe.cells) {
+                Some((cells, parameters)) => {
+                    info!("Undid an edit of {cells} cells");
+                    if let Some(parameters) = parameters {
+                        self.set_parameters(parameters);
+                    }
+                }
+                None => info!("Nothing to undo"),
+            },
+            Action::Redo => match self.history.redo(&mut self.universe.cells) {
+                Some((cells, parameters)) => {
+                    info!("Redid an edit of {cells} cells");
+                    if let Some(parameters) = parameters {
+                        self.set_parameters(parameters);
+                    }
+                }
+                None => info!("Nothing to redo"),
+            },
+            Action::ToggleBackground => {
+                self.color_toggle = !self.color_toggle;
+
+                if self.color_toggle {
+                    info!("Background: Dim Red");
+                } else {
+                    info!("Background: Dim Blue");
+                }
+            }
+            Action::Select(region) => {
+                let region = region.within(self.universe.rows, self.universe.cols);
+                if region.is_empty() {
+                    warn!("The selection is outside the {}x{} grid", self.universe.rows, self.universe.cols);
+                    return;
+                }
+                self.overlay.selection = Some(region);
+            }
+            Action::ClearSelection => {
+                self.overlay.selection = None;
+            }
+        }
+
+        if let Some((before, parameters)) = before {
+            self.history.record_with(&before, &self.universe.cells, Some([parameters, self.parameters()]));
+            // Edits move the centroid without any drift, so the trail starts over
+            self.trajectory.clear();
+            self.blob_trail.clear();
+        }
+        self.grid_dirty = true;
+    }
+
+    // Outline the start of every site scoring at least the threshold against the loaded PWM,
+    // brighter for better scores
+    fn highlight_pwm_sites(&mut self) {
+        let Some((pwm, threshold)) = &self.pwm else {
+            return;
+        };
+        let scores = pwm.scan(&self.dna, self.pwm_background);
+        let sites: Vec<(usize, f64)> = scores
+            .iter()
+            .enumerate()
+            .filter_map(|(position, score)| score.filter(|&score| score >= *threshold).map(|score| (position, score)))
+            .collect();
+        info!("PWM {} ({} positions): {} sites at relative score {threshold} or above", pwm.name, pwm.len(), sites.len());
+
+        let range = (1.0 - threshold).max(f64::EPSILON);
+        self.overlay.highlights = sites
+            .iter()
+            .map(|&(position, score)| {
+                let intensity = (0.25 + 0.75 * (score - threshold) / range) as f32;
+                (position, [0.9 * intensity, 0.3 * intensity, 0.6 * intensity])
+            })
+            .collect();
+    }
+
+    // Advance one generation, through the script's next() when one is loaded
+    fn step(&mut self) {
+        let _span = tracing::trace_span!("tick", generation = self.universe.generation).entered();
+        match &mut self.mode {
+            Mode::Rule => {}
+            Mode::Ants(colony) => colony.step(&mut self.universe),
+            Mode::Cyclic(automaton) => automaton.step(&mut self.universe),
+            Mode::Lenia(automaton) => automaton.step(&mut self.universe, &self.gpu),
+            Mode::Protein(automaton) => automaton.step(&mut self.universe),
+        }
+        if !matches!(self.mode, Mode::Rule) {
+            self.record_population();
+            return;
+        }
+
+        #[cfg(feature = "scripting")]
+        if let Some(script) = self.script.as_ref().filter(|script| script.has_next()) {
+            let dna = &self.dna;
+            let mut error = None;
+            self.universe.tick_with(|idx, alive, neighbors| {
+                if error.is_some() {
+                    return alive;
+                }
+                let base = dna.get(idx).map_or('N', |&base| base as char);
+                script.next(alive, neighbors, base).unwrap_or_else(|err| {
+                    error = Some(err);
+                    alive
+                })
+            });
+
+            if let Some(err) = error {
+                warn!("{err}; script disabled");
+                self.script = None;
+            }
+            self.mutate();
+            self.record_population();
+            self.advance_rule_walk();
+            return;
+        }
+
+        self.universe.tick();
+        self.mutate();
+        self.record_population();
+        self.advance_rule_walk();
+    }
+
+    // This generation's point mutations: flipped cells, or substituted bases with the cells
+    // seeded from them derived again
+    fn mutate(&mut self) {
+        let Some(mutator) = self.mutator.as_mut() else { return };
+        let cols = self.universe.cols as usize;
+        for idx in mutator.draw(self.universe.cells.len()) {
+            if mutator.params.target == MutationTarget::Cells {
+                self.universe.cells[idx] = !self.universe.cells[idx];
+                continue;
+            }
+            let Some(position) = self.seed_map.position((idx / cols) as u32, (idx % cols) as u32).filter(|&position| position < self.dna.len()) else {
+                continue;
+            };
+            let base = mutator.substitute(self.dna[position]);
+            self.dna[position] = base;
+            for cell in self.seed_map.cells(position) {
+                self.universe.cells[cell] = self.seed_strategy.alive(position, base);
+            }
+        }
+    }
+
+    // Jump the rule ahead. A rule walk changes the rule partway, so the jump stops at every
+    // step of it.
+    fn jump_rule(&mut self, generations: u64) {
+        let mut remaining = generations;
+        while remaining > 0 {
+            let generation = self.universe.generation;
+            let segment = self.rule_walk.as_ref().map_or(remaining, |walk| walk.remaining(generation).clamp(1, remaining));
+            self.universe.jump(segment);
+            remaining -= segment;
+            self.record_population();
+            self.advance_rule_walk();
+        }
+    }
+
+    // Step the turbo's generations for this frame, returning how many were stepped. The plain
+    // rule jumps; Lenia, script rules, mutations, the other automata and rules HashLife can't
+    // jump are stepped for at most half the frame budget (except on a fixed clock, which
+    // steps the same whatever the time), the rest of the batch left to the next frames.
+    fn tick_turbo(&mut self) -> u32 {
+        // The speed's clock starts over once turbo is off rather than catching up
+        self.clock.skip();
+        #[cfg(feature = "scripting")]
+        let scripted = self.script.as_ref().is_some_and(Script::has_next);
+        #[cfg(not(feature = "scripting"))]
+        let scripted = false;
+        let jumps = matches!(self.mode, Mode::Rule) && !scripted && self.mutator.is_none() && self.universe.jumps();
+        let Some(steps) = self.turbo.as_mut().map(|turbo| turbo.due(if jumps { turbo::MAX_STEPS } else { turbo::MAX_STEPPED })) else {
+            return 0;
+        };
+        let deadline = (!self.clock.is_fixed()).then(|| Instant::now() + self.quality.budget.limit / 2);
+        let from = self.universe.generation;
+        match &mut self.mode {
+            _ if jumps => self.jump_rule(u64::from(steps)),
+            Mode::Lenia(automaton) => {
+                automaton.advance(&mut self.universe, &self.gpu, steps, deadline);
+                self.record_population();
+            }
+            _ => {
+                for stepped in 0..steps {
+                    if stepped > 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        break;
+                    }
+                    self.step();
+                }
+            }
+        }
+        // The simulation thread's copy is behind now
+        self.simulation_synced = false;
+        self.apply_replayed();
+        self.generations_unshown = true;
+        let stepped = self.universe.generation - from;
+        if let Some(turbo) = self.turbo.as_mut() {
+            turbo.record(stepped, Instant::now());
+        }
+        stepped as u32
+    }
+
+    // Plain rule steps go to the simulation thread; the alternate automata, script rules and
+    // mutations are stepped here
+    fn ticks_on_thread(&self) -> bool {
+        #[cfg(feature = "scripting")]
+        if self.script.as_ref().is_some_and(Script::has_next) {
+            return false;
+        }
+        // Generations from the thread arrive whenever they are ready, while a contact sheet
+        // needs to stop on each generation it captures
+        self.sheet.is_none()
+            && self.simulation.is_some() && matches!(self.mode, Mode::Rule) && !self.clock.is_fixed() && self.mutator.is_none()
+    }
+
+    // Step every tick that is due on this thread, returning how many were stepped
+    fn tick_inline(&mut self) -> u32 {
+        // Generations asked of the simulation thread before switching automaton are abandoned
+        if let Some(simulation) = self.simulation.as_mut().filter(|simulation| simulation.pending() > 0) {
+            simulation.load(&self.universe);
+        }
+        // Catch up on every tick that is due, so speeds above the refresh rate still work
+        let mut ticks = 0;
+        while self.clock.is_due(self.tick_interval) && !self.paused {
+            self.step();
+            self.apply_replayed();
+            self.generations_unshown = true;
+            self.clock.tick(self.tick_interval);
+
+            ticks += 1;
+            if ticks == MAX_TICKS_PER_FRAME {
+                // Can't keep up; drop the backlog instead of falling further behind
+                self.clock.skip();
+                break;
+            }
+            // Show the generation the contact sheet captures next, dropping the backlog so
+            // the run doesn't race ahead once the sheet is done
+            if self.sheet.as_ref().is_some_and(|sheet| sheet.due(self.universe.generation)) {
+                self.clock.skip();
+                break;
+            }
+        }
+        ticks
+    }
+
+    // Ask the simulation thread for the ticks that are due, and apply the generations that
+    // have arrived since the last frame, returning how many were applied
+    fn tick_on_thread(&mut self) -> u32 {
+        if !self.simulation_synced {
+            self.sync_simulation();
+        }
+        let Some(simulation) = self.simulation.as_mut() else {
+            return 0;
+        };
+        let mut due = 0;
+        while self.clock.is_due(self.tick_interval) {
+            due += 1;
+            self.clock.tick(self.tick_interval);
+            if simulation.pending() + due >= MAX_TICKS_PER_FRAME {
+                // Can't keep up; drop the backlog instead of falling further behind
+                self.clock.skip();
+                break;
+            }
+        }
+        simulation.step(due);
+
+        let mut ticks = 0;
+        while ticks < MAX_TICKS_PER_FRAME
+            && let Some(update) = self.simulation.as_mut().and_then(Simulation::next_update)
+        {
+            update.apply(&mut self.universe);
+            self.record_population();
+            self.advance_rule_walk();
+            self.apply_replayed();
+            self.generations_unshown = true;
+            ticks += 1;
+            // A replayed action or the rule walk changed the universe; the generations still
+            // queued were stepped from the old one
+            if !self.simulation_synced {
+                self.sync_simulation();
+                break;
+            }
+        }
+        ticks
+    }
+
+    // Reload the simulation thread with the current universe, asking again for the ticks
+    // that were in flight unless paused
+    fn sync_simulation(&mut self) {
+        if let Some(simulation) = self.simulation.as_mut() {
+            let pending = if self.paused { 0 } else { simulation.pending() };
+            simulation.load(&self.universe);
+            simulation.step(pending);
+        }
+        self.simulation_synced = true;
+    }
+
+    fn advance_rule_walk(&mut self) {
+        let population = self.population.last().copied().unwrap_or_default() as usize;
+        let generation = self.universe.generation;
+        if let Some(rule) = self.rule_walk.as_mut().and_then(|walk| walk.advance(self.universe.rule, &self.dna, generation, population)) {
+            info!("Generation {generation}: rule walk to {rule}");
+            self.universe.rule = rule;
+            self.simulation_synced = false;
+        }
+    }
+
+    fn record_population(&mut self) {
+        if self.population.len() == POPULATION_HISTORY {
+            self.population.remove(0);
+        }
+        self.population.push(self.universe.cells.iter().filter(|&&alive| alive).count() as f64);
+        self.analysis_dirty = true;
+        self.heatmap.accumulate(&self.universe.cells);
+        self.ages.update(&self.universe.cells);
+        let centroid = centroid(&self.universe.cells, self.universe.rows, self.universe.cols);
+        self.trajectory.record(self.universe.generation, centroid);
+        if self.blob_tracking {
+            self.track_blobs();
+        }
+
+        #[cfg(feature = "arrow")]
+        if let Some(table) = self.table.as_mut()
+            && let Err(err) = table.record(self.universe.generation, &self.universe.cells, centroid, &self.universe.rule.to_string())
+        {
+            warn!("Failed to write {}, recording stopped: {err}", table.path.display());
+            self.table = None;
+        }
+        self.record_hash();
+        self.verify_generation();
+        self.detect_ending();
+    }
+
+    fn record_hash(&mut self) {
+        if let Some(hashes) = self.hashes.as_mut()
+            && let Err(err) = hashes.record(self.universe.generation, &self.universe.cells)
+        {
+            warn!("Failed to write {}, recording stopped: {err}", hashes.path.display());
+            self.hashes = None;
+        }
+    }
+
+    // Compare this generation with the reference run: the first that differs pauses the run,
+    // flashing the window like an automatic pause
+    fn verify_generation(&mut self) {
+        let Some(verifier) = self.verifier.as_mut() else { return };
+        if let Some(divergence) = verifier.check(self.universe.generation, &self.universe.cells) {
+            warn!(
+                "Generation {} diverges from {}: hash {:016x}, expected {:016x} ({} generations matched before it)",
+                divergence.generation,
+                verifier.path.display(),
+                divergence.actual,
+                divergence.expected,
+                verifier.checked.len() - 1,
+            );
+            self.paused = true;
+            self.simulation_synced = false;
+            self.flash = Some(Instant::now());
+        } else if verifier.is_complete() && verifier.reference.last() == self.universe.generation {
+            info!("All {} generations of {} match", verifier.checked.len(), verifier.path.display());
+        }
+    }
+
+    // Label the groups of live cells and follow the largest
+    fn track_blobs(&mut self) {
+        let components = Components::label(&self.universe.cells, self.universe.rows, self.universe.cols);
+        let largest = components.largest();
+        self.blob_trail.record(self.universe.generation, largest.and_then(|id| components.centroid(id)));
+        self.blob_summary = largest.map(|id| (components.count(), components.sizes[id as usize]));
+    }
+
+    // Pause once the cells died out or settled, if the config asks to. Lenia's cells are
+    // thresholded states that keep drifting underneath, so they never count as settled.
+    fn detect_ending(&mut self) {
+        if matches!(self.mode, Mode::Lenia(_)) {
+            return;
+        }
+        if let Some(ending) = self.ending.observe(&self.auto_pause, &self.universe.cells) {
+            info!("{ending} at generation {}, pausing", self.universe.generation);
+            self.paused = true;
+            // Generations stepped past this one on the simulation thread are dropped
+            self.simulation_synced = false;
+            self.flash = Some(Instant::now());
+        }
+    }
+
+    // Kiosk mode: once the scene's sequence is in place its generations are counted and the
+    // crossfade into it starts; once they have run, or the run paused by itself, the next
+    // scene is put up from the colors the cells are showing
+    fn next_scene(&mut self) {
+        if self.loader.is_some() {
+            return;
+        }
+        let Some(mut kiosk) = self.kiosk.take() else {
+            return;
+        };
+        if kiosk.start(self.universe.generation) {
+            info!("Kiosk scene {}", kiosk.scene());
+            if let Some((_, started)) = &mut self.scene_fade {
+                *started = Some(Instant::now());
+            }
+        } else if self.scene_fade.is_none() && (kiosk.due(self.universe.generation) || self.paused) {
+            self.scene_fade = Some((self.cell_colors(), None));
+            for action in kiosk.next_scene(self.theme(), self.dna.len(), self.universe.cells.len()) {
+                self.apply(action);
+            }
+        }
+        self.kiosk = Some(kiosk);
+    }
+
+    // Fill color of every cell as last drawn
+    fn cell_colors(&self) -> Vec<[f32; 3]> {
+        let (rows, cols) = (self.universe.rows, self.universe.cols);
+        (0..rows).flat_map(|row| (0..cols).map(move |col| (row, col))).map(|(row, col)| cell_color(&self.universe, &self.overlay, row, col)).collect()
+    }
+
+    fn session(&self) -> Session {
+        Session {
+            dna: self.dna.clone(),
+            rows: self.universe.rows,
+            cols: self.universe.cols,
+            generation: self.universe.generation,
+            cells: self.universe.cells.clone(),
+            rule: self.universe.rule,
+            speed: self.speed(),
+            paused: self.paused,
+            seed_offset: self.seed_offset(),
+            strip_start: self.strip_start,
+            notes: self.notes.clone(),
+            bookmarks: self.bookmarks.clone(),
+            input: Some(self.input.clone()),
+            rng_seed: self.rng_seed,
+        }
+    }
+
+    // Where the current grid came from, written into every export
+    pub fn provenance(&self) -> Provenance {
+        Provenance::new(&self.input, self.dna_id, self.seed_offset(), self.universe.rule.to_string(), self.rng_seed, self.universe.generation)
+    }
+
+    // Put a saved session back: its sequence replaces the current one as if opened, then the
+    // grid takes its size, cells and generation, and the run its rule and speed
+    fn restore(&mut self, path: &Path, session: Session) {
+        if !matches!(self.mode, Mode::Rule) {
+            warn!("Sessions are only restored with the Life-like rule, the {} is running", self.mode_name());
+            return;
+        }
+        self.loader = None;
+        self.resize_grid(session.rows, session.cols, Anchor::Center);
+        self.open_sequence(loader::analyze(path.to_path_buf(), session.dna));
+        if session.seed_offset > 0 {
+            self.seed(&Region::full(self.universe.rows, self.universe.cols), session.seed_offset);
+        }
+        self.universe.cells = session.cells;
+        self.universe.generation = session.generation;
+        // Undo and the diff mark would reach back into the run before
+        self.history = History::default();
+        self.mark = None;
+        self.diff_view = false;
+        self.strip_start = session.strip_start.min(self.dna.len().saturating_sub(1));
+        self.notes = session.notes;
+        self.bookmarks = session.bookmarks;
+        // The session's provenance goes back to the sequence's first input, not the session
+        if let Some(input) = session.input {
+            self.input = input;
+        }
+        self.rng_seed = session.rng_seed;
+        self.apply(Action::SetRule(session.rule));
+        self.apply(Action::SetSpeed(session.speed));
+        self.apply(Action::Pause(session.paused));
+        info!("Restored the session at generation {} from {}", session.generation, path.display());
+    }
+
+    // Crash recovery: the session is saved every `autosave.interval`, compressed and written
+    // on a thread of its own, one save at a time. After a panic on another thread it is saved
+    // at once, before whatever broke takes the app down.
+    fn autosave(&mut self) {
+        if !self.autosave.enabled {
+            return;
+        }
+        if session::take_panicked() {
+            self.finish_autosave();
+            let path = session::recovery_path();
+            match self.session().save(&path) {
+                Ok(()) => tracing::error!("Saved the session to {} after a panic", path.display()),
+                Err(err) => tracing::error!("{err}"),
+            }
+            self.last_autosave = Instant::now();
+            return;
+        }
+        if self.last_autosave.elapsed() < self.autosave.interval || self.autosaving.as_ref().is_some_and(|saving| !saving.is_finished()) {
+            return;
+        }
+        self.last_autosave = Instant::now();
+        let session = self.session();
+        let saving = std::thread::Builder::new().name("autosave".into()).spawn(move || {
+            if let Err(err) = session.save(&session::recovery_path()) {
+                warn!("{err}");
+            }
+        });
+        match saving {
+            Ok(saving) => self.autosaving = Some(saving),
+            Err(err) => warn!("Failed to start autosaving: {err}"),
+        }
+    }
+
+    // Wait for a save in progress, so nothing writes the recovery file behind our back
+    fn finish_autosave(&mut self) {
+        if let Some(saving) = self.autosaving.take() {
+            let _ = saving.join();
+        }
+    }
+
+    // Swap in a sequence read by the loader: everything derived from the old one is dropped,
+    // and the whole grid is reseeded from the start of the new one
+    fn open_sequence(&mut self, mut loaded: LoadedSequence) {
+        info!("Opened {} ({} bases, GC {:.2}%)", loaded.path.display(), loaded.dna.len(), gc_content(&loaded.dna) * 100.0);
+        let _ = loaded.tracks.select(self.tracks.selected().name);
+        self.tracks = loaded.tracks;
+        self.dna = loaded.dna;
+        self.dna_id = loaded.id;
+        self.input = loaded.path.display().to_string();
+        self.rng_seed = None;
+        self.stats.insert(loaded.id, loaded.stats.skew.window, loaded.stats);
+        self.row_records = loaded.rows;
+        self.other_dna = None;
+        self.unshuffled = None;
+        self.seed_strategy = sequence_seed_strategy(&self.dna);
+        self.mask = None;
+        self.codons = None;
+        self.signals = None;
+        self.repeats = None;
+        self.universe.signal = None;
+        self.overlay.markers = vec![(loaded.origin.saturating_sub(1), [0.9, 0.2, 0.9])];
+        self.overlay.highlights.clear();
+        self.overlay.variants.clear();
+        self.overlay.masked.clear();
+        self.overlay.optimality.clear();
+        self.notes = Notes::default();
+        self.highlight_pwm_sites();
+        self.seed(&Region::full(self.universe.rows, self.universe.cols), 0);
+        self.analysis_dirty = true;
+        self.grid_dirty = true;
+        self.simulation_synced = false;
+    }
+
+    // Replace the sequence the grid is seeded from, recomputing the tracks of the new one
+    fn set_sequence(&mut self, dna: Vec<u8>) {
+        let (selected, window) = (self.tracks.selected().name, self.tracks.selected().window);
+        self.dna_id = sequence_id(&dna);
+        self.tracks = TrackRegistry::new(&self.stats.window_stats(&dna, self.dna_id, window));
+        let _ = self.tracks.select(selected);
+        self.dna = dna;
+        if let Some((filter, found)) = self.signals.as_mut() {
+            *found = signals::find(&self.dna).into_iter().filter(|signal| filter.accepts(signal)).collect();
+        }
+        if let Some((params, found)) = self.repeats.as_mut() {
+            *found = repeats::find(&self.dna, params);
+        }
+        // A shuffled sequence is read in the same frame
+        if let SeedStrategy::Translated(frame, _) = self.seed_strategy {
+            self.frame_stats = frames::stats(&self.dna);
+            self.seed_strategy = translated_seed_strategy(&self.dna, frame);
+        }
+        self.analysis_dirty = true;
+    }
+
+    fn seed(&mut self, region: &Region, offset: usize) {
+        // The strip follows the seeding window
+        self.seed_map.record(region, offset);
+        self.strip_start = offset.min(self.dna.len().saturating_sub(1));
+        self.classify_gc();
+
+        // Masked low-complexity positions stay dead when masking is used for seeding
+        let mask = self.mask.as_ref().filter(|(_, seeding)| *seeding).map(|(intervals, _)| intervals.as_slice());
+        let unmasked = |position| mask.is_none_or(|intervals| !is_masked(intervals, position));
+
+        #[cfg(feature = "scripting")]
+        if let Some(script) = self.script.as_ref().filter(|script| script.has_seed()) {
+            let mut error = None;
+            self.universe.seed_region_with(region, &self.dna, offset, |position, base| {
+                unmasked(position) && error.is_none() && script.seed(position, base as char).unwrap_or_else(|err| {
+                    error = Some(err);
+                    false
+                })
+            });
+
+            if let Some(err) = error {
+                warn!("{err}; script disabled");
+                self.script = None;
+            }
+            return;
+        }
+
+        let strategy = &self.seed_strategy;
+        self.universe.seed_region_with(region, &self.dna, offset, |position, base| unmasked(position) && strategy.alive(position, base));
+    }
+
+    // Give the grid a new size with its live cells kept around `anchor`. Everything recorded
+    // per cell for the old size (history, mark, heat map, ages, trail, table) starts over.
+    fn resize_grid(&mut self, rows: u32, cols: u32, anchor: Anchor) {
+        if !matches!(self.mode, Mode::Rule) {
+            warn!("Only the Life-like rule's grid can be resized, the {} is running", self.mode_name());
+            return;
+        }
+        if rows == 0 || cols == 0 || rows as u64 * cols as u64 > MAX_CELLS {
+            warn!("Invalid grid size {rows}x{cols}");
+            return;
+        }
+        self.resize_anchor = anchor;
+        let old = (self.universe.rows, self.universe.cols);
+        if old == (rows, cols) {
+            return;
+        }
+        let shift = self.universe.resize(rows, cols, anchor);
+        self.seed_map.resize(rows, cols, shift);
+        self.classify_gc();
+        // Records no longer line up with the rows once they move
+        if rows != old.0 {
+            self.row_records = None;
+        }
+
+        let cells = self.universe.cells.len();
+        self.history = History::default();
+        self.mark = None;
+        self.diff_view = false;
+        self.heatmap = Heatmap::new(cells);
+        self.ages = Ages::new(cells);
+        self.trajectory = Trajectory::new(rows, cols, POPULATION_HISTORY);
+        self.blob_trail = Trajectory::new(rows, cols, POPULATION_HISTORY);
+        self.overlay.selection = None;
+        self.overlay.hovered.clear();
+        self.gesture = None;
+        #[cfg(feature = "arrow")]
+        if self.table.is_some() {
+            warn!("Stopped the table, its rows hold {}x{} grids", old.0, old.1);
+            self.finish_table();
+        }
+        info!("Grid resized from {}x{} to {rows}x{cols} ({anchor}), {} cells alive", old.0, old.1, self.universe.cells.iter().filter(|&&alive| alive).count());
+        self.grid_dirty = true;
+        self.analysis_dirty = true;
+        self.simulation_synced = false;
+    }
+
+    // Cells seeded from other bases may fall in a different GC class
+    fn classify_gc(&mut self) {
+        if let Some(gc_rules) = self.universe.gc_rules.as_mut() {
+            let counts = self.stats.gc_counts.for_sequence(self.dna_id);
+            gc_rules.classify_with(&self.dna, &self.seed_map, self.universe.rows, self.universe.cols, counts);
+        }
+    }
+
+    // Switch to the shader source if it compiles; otherwise keep the current one and remember
+    // the error for the title bar
+    fn compile_shader(&mut self, shader: &mut ShaderFile, source: &str) {
+        match self.gpu.compile_shader(source, self.surface.config.format) {
+            Ok(module) => {
+                self.gpu.set_shader(Some(module));
+                self.rebuild_pipelines();
+                shader.error = None;
+                info!("Loaded shader {}", shader.path().display());
+            }
+            Err(err) => {
+                warn!("Shader {} failed to compile, keeping the previous one:\n{err}", shader.path().display());
+                shader.error = Some(err);
+            }
+        }
+    }
+
+    // Create or drop the grid window's post-processing chain to match `effects`
+    fn update_post_process(&mut self) {
+        if !self.effects.any() {
+            self.surface.post = None;
+        } else if let Some(post) = self.surface.post.as_mut() {
+            post.effects = self.effects;
+        } else {
+            let config = &self.surface.config;
+            self.surface.post = Some(PostProcess::new(&self.gpu, config.format, config.width, config.height, self.effects));
+        }
+    }
+
+    fn rebuild_pipelines(&mut self) {
+        self.surface.rebuild_pipeline(&self.gpu);
+        if let Some(analysis) = self.analysis.as_mut() {
+            analysis.rebuild_pipeline(&self.gpu);
+        }
+    }
+
+    // Low-complexity intervals found by the last `mask` command, sorted and non-overlapping
+    pub fn masked_intervals(&self) -> &[Range<usize>] {
+        self.mask.as_ref().map_or(&[], |(intervals, _)| intervals)
+    }
+
+    // Apply replayed actions recorded for the current generation, before it is stepped
+    fn apply_replayed(&mut self) {
+        while let Some(action) = self.replay.as_mut().and_then(|replay| replay.next_due(self.universe.generation)) {
+            self.apply(action);
+            if self.replay.as_ref().is_some_and(Replay::is_finished) {
+                info!("Replay finished at generation {}", self.universe.generation);
+                self.replay = None;
+            }
+        }
+    }
+
+    // Mark the vertex data as stale after changing the universe or overlay directly
+    pub fn invalidate(&mut self) {
+        self.grid_dirty = true;
+    }
+
+    // Cells under the sequence strip can't be reached through it, nor cells on the torus
+    pub fn cell_at_cursor(&self, cursor_pos: PhysicalPosition<f64>) -> Option<(u32, u32)> {
+        if self.scene_view() || self.base_at_cursor(cursor_pos).is_some() {
+            return None;
+        }
+        let [x, y] = Viewport::of(&self.surface.window).to_clip(cursor_pos);
+        self.geometry().cell_at(self.universe.rows, self.universe.cols, x, y)
+    }
+
+    // The cell a selection dragged to the cursor ends at, even over padding or off the grid
+    pub fn cell_near_cursor(&self, cursor_pos: PhysicalPosition<f64>) -> (u32, u32) {
+        let [x, y] = Viewport::of(&self.surface.window).to_clip(cursor_pos);
+        self.geometry().nearest_cell(self.universe.rows, self.universe.cols, x, y)
+    }
+
+    // Sequence position of the base under the cursor in the strip
+    pub fn base_at_cursor(&self, cursor_pos: PhysicalPosition<f64>) -> Option<usize> {
+        if !self.strip_view || self.scene_view() {
+            return None;
+        }
+        let point = Viewport::of(&self.surface.window).to_clip(cursor_pos);
+        let base = self.strip_start + self.strip_layout().base_at(point)?;
+        (base < self.dna.len()).then_some(base)
+    }
+
+    // Highlight the base under the cursor: in the strip, along with the cells seeded from it,
+    // or the base a cell was seeded from, scrolling the strip to it when it is out of view
+    pub fn hover(&mut self, cursor_pos: PhysicalPosition<f64>) {
+        let menu_hover = self.menu_item_at_cursor(cursor_pos);
+        if menu_hover != self.menu_hover {
+            self.menu_hover = menu_hover;
+            self.grid_dirty = true;
+        }
+        let icon = match self.tool_at_cursor(cursor_pos) {
+            Some(_) => CursorIcon::Pointer,
+            None if menu_hover.is_some() => CursorIcon::Pointer,
+            None if self.torus_view => CursorIcon::Grab,
+            None => self.tool.cursor(),
+        };
+        self.set_cursor_icon(icon);
+
+        let position = Viewport::of(&self.surface.window).to_clip(cursor_pos);
+        let track_hover = Some(position[0]).filter(|_| !self.scene_view() && self.track_frame(&self.geometry()).contains(position));
+        if track_hover != self.track_hover {
+            self.track_hover = track_hover;
+            self.grid_dirty = true;
+        }
+
+        let note_hover = self.cell_at_cursor(cursor_pos).filter(|&(row, col)| self.seed_map.position(row, col).is_some_and(|position| self.notes.get(position).is_some()));
+        if note_hover != self.note_hover {
+            self.note_hover = note_hover;
+            self.grid_dirty = true;
+        }
+
+        let (base, cells) = match self.base_at_cursor(cursor_pos) {
+            Some(base) => (Some(base), self.seed_map.cells(base)),
+            None => {
+                let base = self.cell_at_cursor(cursor_pos).and_then(|(row, col)| self.seed_map.position(row, col));
+                (base.filter(|&base| base < self.dna.len()), Vec::new())
+            }
+        };
+        if base == self.hovered_base {
+            return;
+        }
+
+        let bases = self.strip_layout().bases;
+        if cells.is_empty() && let Some(base) = base
+            && !(self.strip_start..self.strip_start + bases).contains(&base)
+        {
+            self.strip_start = base.saturating_sub(bases / 2);
+        }
+        self.hovered_base = base;
+        self.overlay.hovered = cells;
+        self.grid_dirty = true;
+    }
+
+    // Console command that adds a note to a cell's base, or edits the one it has
+    fn note_prompt(&self, row: u32, col: u32) -> String {
+        let note = self.seed_map.position(row, col).and_then(|position| self.notes.get(position));
+        format!("note {row} {col} {}", note.map_or("", |note| note.text.as_str()))
+    }
+
+    // Right button pressed: the menu of things to do with the cell under the cursor, or with
+    // the selection when the cell is in it
+    pub fn open_context_menu(&mut self, cursor_pos: PhysicalPosition<f64>) {
+        let Some((row, col)) = self.cell_at_cursor(cursor_pos) else {
+            self.close_context_menu();
+            return;
+        };
+        let region = self.overlay.selection.filter(|region| region.contains(row, col));
+        let anchor = Viewport::of(&self.surface.window).to_clip(cursor_pos);
+        self.context_menu = Some(ContextMenu { row, col, region, anchor });
+        self.menu_hover = None;
+        self.grid_dirty = true;
+    }
+
+    pub fn context_menu_open(&self) -> bool {
+        self.context_menu.is_some()
+    }
+
+    pub fn close_context_menu(&mut self) {
+        if self.context_menu.take().is_some() {
+            self.menu_hover = None;
+            self.grid_dirty = true;
+        }
+    }
+
+    fn menu_item_at_cursor(&self, cursor_pos: PhysicalPosition<f64>) -> Option<MenuItem> {
+        // Only drawn over the flat grid
+        if self.scene_view() {
+            return None;
+        }
+        let viewport = Viewport::of(&self.surface.window);
+        self.context_menu.as_ref()?.item_at(&viewport, self.accessibility.text_scale, viewport.to_clip(cursor_pos))
+    }
+
+    // An item of the context menu, done as the actions and commands it stands for
+    fn pick_menu_item(&mut self, menu: ContextMenu, item: MenuItem) {
+        let (row, col) = (menu.row, menu.col);
+        match item {
+            MenuItem::Inspect => self.apply(Action::InspectCell { row, col }),
+            MenuItem::Annotate => {
+                let prefill = self.note_prompt(row, col);
+                self.console.open(&prefill);
+                info!("Type the note and press Enter, or Escape to cancel");
+            }
+            MenuItem::Reseed => match self.seed_map.position(row, col) {
+                Some(offset) => self.apply(Action::Seed { offset }),
+                None => warn!("Cell {row}, {col} wasn't seeded from a base"),
+            },
+            MenuItem::Center => {
+                // The pan that puts the grid's center where the cell's is now leaves the cell
+                // at the center of the grid area
+                let geometry = self.geometry();
+                let viewport = Viewport::of(&self.surface.window);
+                let (rows, cols) = (self.universe.rows, self.universe.cols);
+                let center = [(geometry.origin[0] + geometry.end(cols, 0)) / 2.0, (geometry.origin[1] + geometry.end(rows, 1)) / 2.0];
+                let origin = geometry.cell_origin(row, col);
+                let pixel = viewport.pixel();
+                let pan = [0, 1].map(|axis| {
+                    let cell = origin[axis] + geometry.cell[axis] / 2.0;
+                    ((center[axis] - cell) / pixel[axis] / viewport.scale_factor as f32).round() as i32
+                });
+                self.apply(Action::SetPan(pan));
+            }
+            MenuItem::Copy => {
+                let coordinates = menu.coordinates();
+                match clipboard::copy(&coordinates) {
+                    Ok(()) => info!("Copied {coordinates}"),
+                    Err(err) => warn!("{err}"),
+                }
+            }
+        }
+    }
+
+    // The note of the hovered cell's base, next to the cell
+    fn note_popup(&self, geometry: &GridGeometry) -> Option<Vec<Vertex>> {
+        let (row, col) = self.note_hover?;
+        let position = self.seed_map.position(row, col)?;
+        let note = self.notes.get(position)?;
+        let mut lines = vec![(format!("Base {position}"), [0.6, 0.6, 0.7])];
+        let mut line = String::new();
+        // Wrapped at 40 columns
+        for word in note.text.split(' ') {
+            if !line.is_empty() && line.len() + 1 + word.len() > 40 {
+                lines.push((std::mem::take(&mut line), NOTE_COLOR));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push((line, NOTE_COLOR));
+        let [x, y] = geometry.cell_origin(row, col);
+        let anchor = [x + geometry.cell[0], y];
+        Some(create_popup_vertices(&lines, anchor, &Viewport::of(&self.surface.window), self.accessibility.text_scale))
+    }
+
+    fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        if icon != self.cursor_icon {
+            self.cursor_icon = icon;
+            self.surface.window.set_cursor_icon(icon);
+        }
+    }
+
+    fn toolbar_layout(&self) -> ToolbarLayout {
+        ToolbarLayout::new(&Viewport::of(&self.surface.window), self.accessibility.text_scale)
+    }
+
+    // Toolbar button under the cursor
+    pub fn tool_at_cursor(&self, cursor_pos: PhysicalPosition<f64>) -> Option<Tool> {
+        if self.scene_view() {
+            return None;
+        }
+        self.toolbar_layout().tool_at(Viewport::of(&self.surface.window).to_clip(cursor_pos))
+    }
+
+    pub fn tool(&self) -> Tool {
+        self.tool
+    }
+
+    // The layer panel, while no other panel takes its corner
+    fn layer_panel_layout(&self) -> Option<LayerPanel> {
+        let covered = self.help_view || self.profile_view || self.primers.is_some() || self.frames_shown() || self.repeats.is_some() || self.rule_panel || self.scene_view();
+        (self.layer_panel && !covered).then(|| LayerPanel::new(&Viewport::of(&self.surface.window), self.accessibility.text_scale))
+    }
+
+    // Left button pressed, or a finger put down: an open context menu does the item picked
+    // and closes, a toolbar button picks its tool, a layer's row in the layer panel shows or
+    // hides it, a base in the sequence strip reseeds from it, and the current tool acts on a
+    // cell; on the torus view dragging turns the torus
+    pub fn click(&mut self, cursor_pos: PhysicalPosition<f64>, select: bool) {
+        if let Some(menu) = self.context_menu.clone() {
+            let item = self.menu_item_at_cursor(cursor_pos);
+            self.close_context_menu();
+            if let Some(item) = item {
+                self.pick_menu_item(menu, item);
+            }
+            return;
+        }
+        if let Some(named) = self.rule_at_cursor(cursor_pos) {
+            self.apply(Action::UseNamedRule(named));
+            return;
+        }
+        let layer = self.layer_panel_layout().and_then(|panel| panel.layer_at(&self.overlay.layers, Viewport::of(&self.surface.window).to_clip(cursor_pos)));
+        if self.torus_shown() {
+            self.press_torus(cursor_pos);
+        } else if let Some(layer) = layer {
+            self.apply(Action::ShowLayer(layer, !self.overlay.layers.shown(layer)));
+        } else if let Some(tool) = self.tool_at_cursor(cursor_pos) {
+            self.apply(Action::SetTool(tool));
+        } else if let Some(offset) = self.base_at_cursor(cursor_pos) {
+            self.apply(Action::Seed { offset });
+        } else if let Some(cell) = self.cell_at_cursor(cursor_pos) {
+            self.press(cell, select);
+        }
+    }
+
+    // A touch event on the grid window, turned into the mouse's presses and drags or a zoom
+    pub fn touch(&mut self, touch: Touch) {
+        for gesture in self.touch.input(touch.id, touch.phase, touch.location, Instant::now()) {
+            self.touch_gesture(gesture);
+        }
+    }
+
+    fn touch_gesture(&mut self, gesture: TouchGesture) {
+        match gesture {
+            TouchGesture::Tap(position) => {
+                self.click(position, false);
+                self.release();
+            }
+            TouchGesture::DragStart(position) => self.click(position, false),
+            TouchGesture::Drag(position) => self.drag(position),
+            TouchGesture::DragEnd => self.release(),
+            // The inspector, whichever tool is picked
+            TouchGesture::LongPress(position) => {
+                if let Some((row, col)) = self.cell_at_cursor(position) {
+                    self.apply(Action::InspectCell { row, col });
+                }
+            }
+            TouchGesture::PinchStart(midpoint) => {
+                self.torus_pinch = 1.0;
+                if self.scene_view() {
+                    return;
+                }
+                let geometry = self.geometry();
+                let (rows, cols) = (self.universe.rows, self.universe.cols);
+                let center = [(geometry.origin[0] + geometry.end(cols, 0)) / 2.0, (geometry.origin[1] + geometry.end(rows, 1)) / 2.0];
+                let midpoint = Viewport::of(&self.surface.window).to_clip(midpoint);
+                self.pinch = Some(Pinch { layout: self.layout, cell_size: self.cell_size(), center, midpoint, preview: self.layout });
+            }
+            TouchGesture::Pinch { scale, .. } if self.torus_shown() => {
+                // Spreading the fingers brings the camera closer, a wheel line per 10%
+                self.zoom_torus(-((scale / self.torus_pinch).ln() / 1.1f64.ln()) as f32);
+                self.torus_pinch = scale;
+            }
+            TouchGesture::Pinch { scale, midpoint } => {
+                let viewport = Viewport::of(&self.surface.window);
+                let Some(pinch) = self.pinch.as_mut() else { return };
+                let size = ((pinch.cell_size as f64 * scale).round() as u32).clamp(1, MAX_CELL_SIZE);
+                let zoom = size as f32 / pinch.cell_size.max(1) as f32;
+                // The point of the grid under the fingers when they landed follows their midpoint
+                let midpoint = viewport.to_clip(midpoint);
+                let pixel = viewport.pixel();
+                let pan = [0, 1].map(|axis| {
+                    let center = midpoint[axis] - (pinch.midpoint[axis] - pinch.center[axis]) * zoom;
+                    pinch.layout.pan[axis] + ((center - pinch.center[axis]) / pixel[axis] / viewport.scale_factor as f32).round() as i32
+                });
+                pinch.preview = CellLayout { cell_size: Some(size), pan, ..pinch.layout };
+                self.grid_dirty = true;
+            }
+            // The zoom and pan the pinch reached become actions, as if typed
+            TouchGesture::PinchEnd => {
+                let Some(pinch) = self.pinch.take() else { return };
+                if pinch.preview.cell_size != pinch.layout.cell_size {
+                    self.apply(Action::SetCellSize(pinch.preview.cell_size));
+                }
+                if pinch.preview.pan != pinch.layout.pan {
+                    self.apply(Action::SetPan(pinch.preview.pan));
+                }
+                self.grid_dirty = true;
+            }
+        }
+    }
+
+    // Left button pressed on a cell: the current tool acts on it, or Shift starts a selection
+    pub fn press(&mut self, cell: (u32, u32), select: bool) {
+        let tool = if select { Tool::Select } else { self.tool };
+        let (gesture, actions) = tool.press(cell);
+        for action in actions {
+            self.apply(action);
+        }
+        self.gesture = gesture;
+        self.grid_dirty = true;
+    }
+
+    // Left button pressed on the torus view: start turning it
+    pub fn press_torus(&mut self, cursor_pos: PhysicalPosition<f64>) {
+        self.torus_drag = Some(Viewport::of(&self.surface.window).to_clip(cursor_pos));
+        self.set_cursor_icon(CursorIcon::Grabbing);
+    }
+
+    // Move the torus camera closer or further by a number of wheel lines
+    pub fn zoom_torus(&mut self, lines: f32) {
+        self.torus_camera.zoom(lines);
+        self.grid_dirty = true;
+    }
+
+    // The cursor moved with the left button held
+    pub fn drag(&mut self, cursor_pos: PhysicalPosition<f64>) {
+        if let Some(from) = self.torus_drag {
+            let to = Viewport::of(&self.surface.window).to_clip(cursor_pos);
+            self.torus_camera.drag(from, to);
+            self.torus_drag = Some(to);
+            self.grid_dirty = true;
+        }
+        if self.gesture.is_none() {
+            return;
+        }
+        let (exact, nearest) = (self.cell_at_cursor(cursor_pos), self.cell_near_cursor(cursor_pos));
+        if let Some(gesture) = self.gesture.as_mut()
+            && gesture.drag(exact, nearest)
+        {
+            self.grid_dirty = true;
+        }
+    }
+
+    // Left button released: a stroke or selection in progress becomes its action
+    pub fn release(&mut self) {
+        if self.torus_drag.take().is_some() {
+            self.set_cursor_icon(CursorIcon::Grab);
+        }
+        let Some(gesture) = self.gesture.take() else {
+            return;
+        };
+        for action in gesture.finish() {
+            let selected = if let Action::Select(region) = &action { Some(*region) } else { None };
+            self.apply(action);
+            if let Some(region) = selected {
+                info!("Selected {}x{} cells, press R to reseed", region.rows, region.cols);
+            }
+        }
+    }
+
+    // Log what is known about a cell: its state, its live neighbors and the base it was
+    // seeded from
+    fn inspect(&self, row: u32, col: u32) {
+        let (rows, cols) = (self.universe.rows, self.universe.cols);
+        if row >= rows || col >= cols {
+            warn!("Cell ({row}, {col}) is outside the {rows}x{cols} grid");
+            return;
+        }
+        let idx = (row * cols + col) as usize;
+        let neighbors = (0..9)
+            .filter(|&i| i != 4)
+            .filter(|i| self.universe.cells[((row + rows + i / 3 - 1) % rows * cols + (col + cols + i % 3 - 1) % cols) as usize])
+            .count();
+        let base = match self.seed_map.position(row, col).filter(|&position| position < self.dna.len()) {
+            Some(position) => format!("base {position} ({})", self.dna[position] as char),
+            None => "past the end of the sequence".to_string(),
+        };
+        let state = if self.universe.cells[idx] { "alive" } else { "dead" };
+        let mut details = format!("Cell ({row}, {col}): {state}, {neighbors} live neighbors, seeded from {base}");
+        if let Some(id) = self.row_records.as_ref().and_then(|records| records.id(row)) {
+            details.push_str(&format!(" of record {id}"));
+        }
+        if let Some(class) = self.universe.gc_rules.as_ref().and_then(|gc_rules| gc_rules.classes.get(idx)) {
+            details.push_str(&format!(", GC class {}", format!("{class:?}").to_lowercase()));
+        }
+        info!("{details}");
+    }
+
+    // Scroll the strip by a number of bases, e.g. from the mouse wheel
+    pub fn scroll_strip(&mut self, bases: i64) {
+        let position = self.strip_start.saturating_add_signed(bases as isize);
+        if self.strip_view && position != self.strip_start {
+            self.apply(Action::ScrollStrip(position));
+        }
+    }
+
+    fn strip_layout(&self) -> StripLayout {
+        StripLayout::new(&Viewport::of(&self.surface.window), self.accessibility.text_scale)
+    }
+
+    // Cell layout snapped to the main window's current pixels
+    // Laid out as a pinch in progress would leave it
+    fn geometry(&self) -> GridGeometry {
+        let layout = self.pinch.as_ref().map_or(&self.layout, |pinch| &pinch.preview);
+        Viewport::of(&self.surface.window).grid_geometry(self.universe.rows, self.universe.cols, layout)
+    }
+
+    // The selected track at full strength, or during a crossfade both tracks with the
+    // share of the fade each has reached
+    fn track_weights(&self) -> Vec<(&Track, f32)> {
+        let selected = self.tracks.selected();
+        match self.track_fade.and_then(|(name, started)| Some((self.tracks.get(name)?, started))) {
+            Some((previous, started)) => {
+                let faded = (started.elapsed().as_secs_f32() / TRACK_CROSSFADE.as_secs_f32()).min(1.0);
+                vec![(previous, 1.0 - faded), (selected, faded)]
+            }
+            None => vec![(selected, 1.0)],
+        }
+    }
+
+    // Where the selected track is charted below the grid, across its visible width
+    fn track_frame(&self, geometry: &GridGeometry) -> Frame {
+        Frame { min: [geometry.origin[0].max(-1.0), -0.86], max: [geometry.end(self.universe.cols, 0).min(1.0), -0.66] }
+    }
+
+    // Background of every visible cell from the track value at the base it was seeded from
+    fn track_background(&self, geometry: &GridGeometry) -> RegionColors {
+        let weights: Vec<_> = self.track_weights().into_iter().map(|(track, weight)| (track.backgrounds(), weight)).collect();
+        let (rows, cols) = (geometry.visible_range(self.universe.rows, 1), geometry.visible_range(self.universe.cols, 0));
+        let region = Region { row: rows.start, col: cols.start, rows: rows.len() as u32, cols: cols.len() as u32 };
+        let colors = rows
+            .flat_map(|row| cols.clone().map(move |col| (row, col)))
+            .map(|(row, col)| {
+                let mut color = [0.0; 3];
+                let Some(position) = self.seed_map.position(row, col) else { return Some(color) };
+                for (background, weight) in &weights {
+                    let tint = background(position).unwrap_or([0.0; 3]);
+                    for channel in 0..3 {
+                        color[channel] += tint[channel] * weight;
+                    }
+                }
+                Some(color)
+            })
+            .collect();
+        RegionColors { region, colors }
+    }
+
+    // Stripes of the visible cells seeded from a base in a repeat, looked up cell by cell so
+    // that a genome full of repeats costs no more than the screen
+    fn repeat_stripes(&self, found: &[TandemRepeat], geometry: &GridGeometry) -> Vec<(usize, [f32; 3])> {
+        let (rows, cols) = (geometry.visible_range(self.universe.rows, 1), geometry.visible_range(self.universe.cols, 0));
+        rows.flat_map(|row| cols.clone().map(move |col| (row, col)))
+            .filter_map(|(row, col)| {
+                let position = self.seed_map.position(row, col)?;
+                let repeat = repeats::covering(found, position)?;
+                Some(((row * self.universe.cols + col) as usize, REPEAT_COLORS[repeat.copy_of(position) % 2]))
+            })
+            .collect()
+    }
+
+    // Letters of the visible cells' bases, each standing out on its cell; none unless the
+    // cells are big enough to read them in
+    fn base_letters(&self, geometry: &GridGeometry) -> Vec<Letter> {
+        let (rows, cols) = (geometry.visible_range(self.universe.rows, 1), geometry.visible_range(self.universe.cols, 0));
+        letters::layout(geometry, rows, cols, Viewport::of(&self.surface.window).pixel(), |row, col| {
+            let base = *self.seed_map.position(row, col).and_then(|position| self.dna.get(position))?;
+            Some((base, cell_color(&self.universe, &self.overlay, row, col)))
+        })
+    }
+
+    // Colors of the visible cells' bases, for the bases layer
+    fn base_colors(&self, geometry: &GridGeometry) -> RegionColors {
+        let (rows, cols) = (geometry.visible_range(self.universe.rows, 1), geometry.visible_range(self.universe.cols, 0));
+        let region = Region { row: rows.start, col: cols.start, rows: rows.len() as u32, cols: cols.len() as u32 };
+        let colors = rows
+            .flat_map(|row| cols.clone().map(move |col| (row, col)))
+            .map(|(row, col)| self.seed_map.position(row, col).and_then(|position| self.dna.get(position)).map(|&base| strip::base_color(base)))
+            .collect();
+        RegionColors { region, colors }
+    }
+
+    pub fn is_analysis_window(&self, id: WindowId) -> bool {
+        self.analysis.as_ref().is_some_and(|analysis| analysis.window.id() == id)
+    }
+
+    pub fn analysis_open(&self) -> bool {
+        self.analysis.is_some()
+    }
+
+    pub fn has_mark(&self) -> bool {
+        self.mark.is_some()
+    }
+
+    pub fn diff_shown(&self) -> bool {
+        self.diff_view
+    }
+
+    pub fn heatmap_shown(&self) -> bool {
+        self.heatmap_view
+    }
+
+    pub fn trail_shown(&self) -> bool {
+        self.trail_view
+    }
+
+    pub fn ruler_shown(&self) -> bool {
+        self.ruler_view
+    }
+
+    pub fn contours_shown(&self) -> bool {
+        self.contour_view
+    }
+
+    pub fn smooth_transitions(&self) -> bool {
+        self.transition.is_some()
+    }
+
+    pub fn rule_library_shown(&self) -> bool {
+        self.rule_panel
+    }
+
+    pub fn layer_panel_shown(&self) -> bool {
+        self.layer_panel
+    }
+
+    // Whether the grid is seeded from a reading frame, with the frames listed in a panel
+    pub fn frames_shown(&self) -> bool {
+        matches!(self.seed_strategy, SeedStrategy::Translated(..))
+    }
+
+    pub fn torus_shown(&self) -> bool {
+        self.torus_view
+    }
+
+    pub fn heightfield_shown(&self) -> bool {
+        self.heightfield_view
+    }
+
+    // Whether the cells are drawn in 3D, where the flat grid's cells, strip and toolbar
+    // aren't drawn and can't be clicked
+    fn scene_view(&self) -> bool {
+        self.torus_view || self.heightfield_view
+    }
+
+    pub fn strip_shown(&self) -> bool {
+        self.strip_view
+    }
+
+    // Read out the generation and population when due, if announcements are on
+    fn announce(&mut self) {
+        let Some(every) = self.accessibility.announce_every else {
+            return;
+        };
+        let population = self.universe.cells.iter().filter(|&&alive| alive).count();
+        for text in self.announcer.update(every, self.universe.generation, population) {
+            accessibility::announce(&self.accessibility, &text);
+        }
+    }
+
+    pub fn layout(&self) -> CellLayout {
+        self.layout
+    }
+
+    pub fn grid_size(&self) -> (u32, u32) {
+        (self.universe.rows, self.universe.cols)
+    }
+
+    pub fn resize_anchor(&self) -> Anchor {
+        self.resize_anchor
+    }
+
+    // Side of the cells as currently drawn, in logical pixels
+    pub fn cell_size(&self) -> u32 {
+        let viewport = Viewport::of(&self.surface.window);
+        let physical = self.geometry().cell[0] / viewport.pixel()[0];
+        (physical as f64 / viewport.scale_factor).round().max(1.0) as u32
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    // Generations a frame while in turbo
+    pub fn turbo_steps(&self) -> Option<u32> {
+        self.turbo.as_ref().map(|turbo| turbo.steps)
+    }
+
+    // Generations per second
+    pub fn speed(&self) -> f64 {
+        1.0 / self.tick_interval.as_secs_f64()
+    }
+
+    // Offset of the last reseed of the whole grid, which every later one only covers part of
+    pub fn seed_offset(&self) -> usize {
+        self.seed_map.windows().first().map_or(0, |window| window.start)
+    }
+
+    fn mode_name(&self) -> &'static str {
+        match self.mode {
+            Mode::Rule => "Life-like rule",
+            Mode::Ants(_) => "Langton's ants",
+            Mode::Cyclic(_) => "cyclic automaton",
+            Mode::Lenia(_) => "Lenia",
+            Mode::Protein(_) => "protein automaton",
+        }
+    }
+
+    pub fn help_shown(&self) -> bool {
+        self.help_view
+    }
+
+    pub fn profiler_shown(&self) -> bool {
+        self.profile_view
+    }
+
+    pub fn has_bookmarks(&self) -> bool {
+        !self.bookmarks.is_empty()
+    }
+
+    // The help overlay: every key binding that applies right now, then what the grid is running
+    fn help_lines(&self) -> Vec<(String, [f32; 3])> {
+        let (heading, key, text) = match self.accessibility.high_contrast {
+            true => ([1.0, 1.0, 0.0], [1.0; 3], [1.0; 3]),
+            false => ([1.0, 0.85, 0.4], [0.5, 0.8, 1.0], [0.85, 0.85, 0.85]),
+        };
+        let mut lines = vec![("Keys".to_string(), heading)];
+        for binding in keymap::active(self) {
+            lines.push((format!("{:<13}{}", binding.label, binding.description), key));
+        }
+        lines.push((String::new(), text));
+        lines.push((format!("Mode: {}", self.mode_name()), text));
+        lines.push((format!("Rule: {}", self.universe.rule), text));
+        lines
+    }
+
+    // The profiler overlay: the GPU time of each timed pass, then where the CPU frame goes
+    fn profile_lines(&self) -> Vec<(String, [f32; 3])> {
+        let (heading, text) = match self.accessibility.high_contrast {
+            true => ([1.0, 1.0, 0.0], [1.0; 3]),
+            false => ([1.0, 0.85, 0.4], [0.85, 0.85, 0.85]),
+        };
+        let row = |label: &str, ms: f64| (format!("{label:<12}{ms:>8.3} ms"), text);
+        let mut lines = vec![("GPU".to_string(), heading)];
+        match &self.gpu.profiler {
+            Some(profiler) => {
+                let timings = profiler.timings();
+                if timings.is_empty() {
+                    lines.push(("Waiting for timestamps".to_string(), text));
+                }
+                lines.extend(timings.iter().map(|&(label, ms)| row(label, ms)));
+                if timings.len() > 1 {
+                    lines.push(row("total", timings.iter().map(|(_, ms)| ms).sum()));
+                }
+            }
+            None => lines.push(("No timestamp queries".to_string(), text)),
+        }
+        lines.push((String::new(), text));
+        lines.push(("CPU".to_string(), heading));
+        lines.extend(self.cpu_timings.entries().iter().map(|&(label, ms)| row(label, ms)));
+        lines.push((String::new(), text));
+        lines.push((format!("Quality: {}", self.quality.level()), heading));
+        lines.push(row("update", self.quality.average_ms()));
+        if self.quality.budget.enabled {
+            lines.push(row("budget", self.quality.budget.limit.as_secs_f64() * 1000.0));
+        }
+        lines
+    }
+
+    fn primer_lines(&self) -> Vec<(String, [f32; 3])> {
+        let (heading, text) = match self.accessibility.high_contrast {
+            true => ([1.0, 1.0, 0.0], [1.0; 3]),
+            false => ([1.0, 0.85, 0.4], [0.85, 0.85, 0.85]),
+        };
+        let Some((range, candidates)) = &self.primers else { return Vec::new() };
+        let mut lines = vec![(format!("Primers for bases {}..{}", range.start, range.end), heading)];
+        if candidates.is_empty() {
+            lines.push(("None fit the constraints".to_string(), text));
+        }
+        lines.extend(candidates.iter().enumerate().map(|(i, candidate)| (format!("{:>2} {}", i + 1, primer_line(candidate)), text)));
+        lines.push((String::new(), text));
+        lines.push(("primers copy N: copy one, primers copy: all".to_string(), text));
+        lines
+    }
+
+    // The six frames' stop codons over the whole sequence, the one seeding the grid marked
+    fn frame_lines(&self) -> Vec<(String, [f32; 3])> {
+        let (heading, text) = match self.accessibility.high_contrast {
+            true => ([1.0, 1.0, 0.0], [1.0; 3]),
+            false => ([1.0, 0.85, 0.4], [0.85, 0.85, 0.85]),
+        };
+        let SeedStrategy::Translated(current, _) = &self.seed_strategy else { return Vec::new() };
+        let mut lines = vec![("Reading frames: stop codons".to_string(), heading)];
+        lines.extend(self.frame_stats.iter().map(|stats| {
+            let marker = if stats.frame == *current { '>' } else { ' ' };
+            let line = format!("{marker}{} {:>2} {:>5.1}% {:>9}", stats.frame.number(), stats.frame, stats.stop_density() * 100.0, stats.stops);
+            (line, if stats.frame == *current { heading } else { text })
+        }));
+        lines.push((format!("Random codons: {:.1}%", RANDOM_STOP_DENSITY * 100.0), text));
+        lines.push((String::new(), text));
+        lines.push(("Live: hydrophobic residues".to_string(), text));
+        lines.push(("Dark red: stop codons".to_string(), STOP_CODON_COLOR));
+        lines.push(("1-6: pick a frame, F: close".to_string(), text));
+        lines
+    }
+
+    // The longest tandem repeats with their copy counts
+    fn repeat_lines(&self) -> Vec<(String, [f32; 3])> {
+        let (heading, text) = match self.accessibility.high_contrast {
+            true => ([1.0, 1.0, 0.0], [1.0; 3]),
+            false => ([1.0, 0.85, 0.4], [0.85, 0.85, 0.85]),
+        };
+        let Some((params, found)) = &self.repeats else { return Vec::new() };
+        let mut lines = vec![(format!("Tandem repeats: {}", found.len()), heading)];
+        lines.push((format!("Units of {} to {} bases, {}+ copies", params.unit.0, params.unit.1, params.min_copies), text));
+        let mut longest: Vec<&TandemRepeat> = found.iter().collect();
+        longest.sort_by_key(|repeat| std::cmp::Reverse(repeat.end - repeat.start));
+        lines.extend(longest.iter().take(LISTED_REPEATS).map(|repeat| {
+            let unit = if repeat.unit.len() > 12 { format!("{}...", &repeat.unit[..12]) } else { repeat.unit.clone() };
+            (format!("{:>9}..{:<9} ({unit}) x{:.1}", repeat.start, repeat.end, repeat.copies()), text)
+        }));
+        if found.len() > LISTED_REPEATS {
+            lines.push((format!("... and {} more", found.len() - LISTED_REPEATS), text));
+        }
+        lines.push((String::new(), text));
+        lines.push(("Striped: alternate copies".to_string(), REPEAT_COLORS[0]));
+        lines.push(("repeats off: close".to_string(), text));
+        lines
+    }
+
+    // The rule library, the rule in use marked, one line per rule after the heading
+    fn rule_lines(&self) -> Vec<(String, [f32; 3])> {
+        let (heading, text) = match self.accessibility.high_contrast {
+            true => ([1.0, 1.0, 0.0], [1.0; 3]),
+            false => ([1.0, 0.85, 0.4], [0.85, 0.85, 0.85]),
+        };
+        let width = self.rule_library.rules.iter().map(|named| named.name.len()).max().unwrap_or(0);
+        let mut lines = vec![("Rule library".to_string(), heading)];
+        lines.extend(self.rule_library.rules.iter().map(|named| {
+            let current = named.rule == self.universe.rule;
+            let marker = if current { '>' } else { ' ' };
+            let rule = named.rule.to_string();
+            (format!("{marker}{:<width$} {rule:<13} {}", named.name, named.description), if current { heading } else { text })
+        }));
+        lines.push((String::new(), text));
+        lines.push(("Click or rule <name>: use one".to_string(), text));
+        lines.push(("rules save <name> [description]: add the current rule".to_string(), text));
+        lines.push(("rules off: close".to_string(), text));
+        lines
+    }
+
+    // Name of the rule under the cursor in the rule library panel, when it is the one shown
+    fn rule_at_cursor(&self, cursor_pos: PhysicalPosition<f64>) -> Option<String> {
+        let shown = self.rule_panel && !(self.help_view || self.profile_view || self.primers.is_some() || self.frames_shown() || self.repeats.is_some());
+        if !shown {
+            return None;
+        }
+        let viewport = Viewport::of(&self.surface.window);
+        let line = panel_line_at(&self.rule_lines(), &viewport, self.accessibility.text_scale, viewport.to_clip(cursor_pos))?;
+        self.rule_library.rules.get(line.checked_sub(1)?).map(|named| named.name.clone())
+    }
+
+    // Cells colored by the amino acid their base codes for in the reading frame: live cells
+    // by property class, dead cells on a stop codon dark red
+    fn residue_colors(&self, residues: &[u8]) -> Vec<[f32; 3]> {
+        let (rows, cols) = (self.universe.rows, self.universe.cols);
+        let (alive, dead) = if self.accessibility.high_contrast { ([1.0; 3], [0.0; 3]) } else { (ALIVE_COLOR, DEAD_COLOR) };
+        (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (row, col)))
+            .map(|(row, col)| {
+                let residue = self.seed_map.position(row, col).and_then(|position| residues.get(position).copied()).unwrap_or(0);
+                match self.universe.cells[(row * cols + col) as usize] {
+                    true => PropertyClass::of(residue).map_or(alive, PropertyClass::color),
+                    false if residue == b'*' => STOP_CODON_COLOR,
+                    false => dead,
+                }
+            })
+            .collect()
+    }
+
+    fn record_cpu_time(&mut self, label: &'static str, started: Instant) {
+        if self.profile_view {
+            self.cpu_timings.record(label, started.elapsed());
+        }
+    }
+
+    pub fn report(&self) -> SimulationReport {
+        SimulationReport {
+            generation: self.universe.generation,
+            rule: self.universe.rule,
+            population: self.universe.cells.iter().filter(|&&alive| alive).count(),
+            max_population: self.population.iter().copied().fold(0.0, f64::max) as usize,
+            trajectory: self.trajectory.points().copied().collect(),
+            drift: self.trajectory.drift(),
+        }
+    }
+
+    pub fn wants_analysis_window(&self) -> bool {
+        self.analysis_requested
+    }
+
+    pub fn open_analysis_window(&mut self, window: Arc<Window>) {
+        self.analysis_requested = false;
+        let vertices = self.analysis_vertices(&Viewport::of(&window), None);
+        match self.gpu.create_surface(&self.instance, window, &vertices) {
+            Ok(analysis) => self.analysis = Some(analysis),
+            Err(err) => warn!("{err}"),
+        }
+    }
+
+    // GC content per window, cumulative skew, base entropy and population, top to bottom
+    fn analysis_charts(&self) -> Vec<Chart<'_>> {
+        let gc = self.tracks.get("gc").unwrap().chart();
+        let population = Chart { label: "population", values: &self.population, color: [0.9, 0.7, 0.2], style: ChartStyle::Area, mark: None, x_step: 1, x_unit: "" };
+        let mut charts = vec![Chart { mark: None, style: ChartStyle::Area, ..gc }, self.tracks.get("skew").unwrap().chart()];
+        charts.extend(self.tracks.get("complexity").map(Track::chart));
+        charts.push(population);
+        charts
+    }
+
+    // The analysis charts drawn for `viewport`, reading out the point under `hover`
+    fn analysis_vertices(&self, viewport: &Viewport, hover: Option<[f32; 2]>) -> Vec<Vertex> {
+        create_stacked_charts(&self.analysis_charts(), Some(font_pixel(viewport, self.accessibility.text_scale)), hover)
+    }
+
+    // The cursor moved over the analysis window, or left it
+    pub fn hover_analysis(&mut self, cursor_pos: Option<PhysicalPosition<f64>>) {
+        let Some(analysis) = &self.analysis else { return };
+        let hover = cursor_pos.map(|position| Viewport::of(&analysis.window).to_clip(position));
+        if hover != self.analysis_hover {
+            self.analysis_hover = hover;
+            self.analysis_dirty = true;
+        }
+    }
+
+    pub fn resize(&mut self, id: WindowId, width: u32, height: u32) {
+        if let Some(analysis) = self.analysis.as_mut().filter(|analysis| analysis.window.id() == id) {
+            analysis.resize(&self.gpu, width, height);
+            // The charts' text is sized in the window's pixels
+            self.analysis_dirty = true;
+        } else if id == self.surface.window.id() {
+            self.surface.resize(&self.gpu, width, height);
+            // Cells are snapped to the window's pixels
+            self.grid_dirty = true;
+        }
+    }
+
+    // The window moved to a monitor with a different DPI. Its physical size changes with the
+    // scale factor, so the surface and the snapped grid are rebuilt for the new size.
+    pub fn rescale(&mut self, id: WindowId, scale_factor: f64) {
+        let window = match self.analysis.as_ref().filter(|analysis| analysis.window.id() == id) {
+            Some(analysis) => analysis.window.clone(),
+            None => self.surface.window.clone(),
+        };
+        if id == self.surface.window.id() {
+            debug!("Scale factor {scale_factor}");
+        }
+        let size = window.inner_size();
+        self.resize(id, size.width, size.height);
+    }
+
+    // Called once per event loop iteration: recover the GPU, tick, and upload changes
+    pub fn update(&mut self) {
+        let frame_started = Instant::now();
+        if self.gpu.is_lost() {
+            warn!("Recreating GPU state");
+            let (gpu, surface) = Gpu::new(&self.instance, self.surface.window.clone(), &self.grid_data);
+            self.gpu = gpu;
+            self.surface = surface;
+            // The cell texture belonged to the old device too
+            self.grid_dirty = true;
+            if let Some(analysis) = self.analysis.take() {
+                self.open_analysis_window(analysis.window.clone());
+            }
+            self.update_post_process();
+            if let Some(profiler) = &self.gpu.profiler {
+                profiler.set_enabled(self.profile_view);
+            }
+            if let Mode::Lenia(automaton) = &mut self.mode
+                && !self.clock.is_fixed()
+                && let Err(err) = automaton.attach(&self.gpu)
+            {
+                warn!("Lenia compute shader unavailable, stepping on the CPU: {err}");
+            }
+            // The new device starts with the built-in shader
+            if let Some(mut shader) = self.shader.take() {
+                match shader.read() {
+                    Ok(source) => self.compile_shader(&mut shader, &source),
+                    Err(err) => warn!("{err}"),
+                }
+                self.shader = Some(shader);
+            }
+        }
+
+        if self.last_shader_check.elapsed() >= Duration::from_millis(500) {
+            self.last_shader_check = Instant::now();
+            if let Some(mut shader) = self.shader.take() {
+                match shader.changed() {
+                    Some(Ok(source)) => self.compile_shader(&mut shader, &source),
+                    Some(Err(err)) => warn!("{err}"),
+                    None => {}
+                }
+                self.shader = Some(shader);
+            }
+        }
+
+        #[cfg(feature = "scripting")]
+        if self.last_script_check.elapsed() >= Duration::from_millis(500) {
+            self.last_script_check = Instant::now();
+            if let Some(script) = self.script.as_mut() {
+                match script.reload_if_changed() {
+                    Some(Ok(())) => info!("Reloaded script {}", script.path().display()),
+                    Some(Err(err)) => warn!("{err}"),
+                    None => {}
+                }
+            }
+        }
+
+        self.apply_replayed();
+        if let Some(gesture) = self.touch.poll(Instant::now()) {
+            self.touch_gesture(gesture);
+        }
+
+        #[cfg(feature = "server")]
+        self.answer_queries();
+
+        // Deterministic runs wait for the sequence rather than switching to it at whichever
+        // generation it happens to arrive
+        let loaded = if self.clock.is_fixed() { self.loader.as_ref().map(SequenceLoader::wait) } else { self.loader.as_ref().and_then(SequenceLoader::poll) };
+        if let Some(result) = loaded {
+            self.loader = None;
+            match result {
+                Ok(loaded) => self.open_sequence(loaded),
+                Err(err) => warn!("{err}"),
+            }
+        }
+
+        self.next_scene();
+        self.autosave();
+
+        self.clock.next_frame();
+        if self.paused {
+            self.clock.skip();
+        }
+        let started = Instant::now();
+        let ticks = if self.turbo.is_some() && !self.paused {
+            self.tick_turbo()
+        } else if self.ticks_on_thread() {
+            self.tick_on_thread()
+        } else {
+            self.tick_inline()
+        };
+        self.record_cpu_time("simulation", started);
+        // Below full quality, new generations can wait a few frames to be shown together
+        self.frames_since_upload += 1;
+        if self.generations_unshown && self.frames_since_upload >= self.quality.frames_per_upload() {
+            self.grid_dirty = true;
+        }
+
+        if self.profile_view {
+            if let Some(profiler) = &self.gpu.profiler {
+                profiler.collect(&self.gpu.device);
+            }
+            if self.profile_refreshed.elapsed() >= PROFILE_REFRESH {
+                self.profile_refreshed = Instant::now();
+                self.grid_dirty = true;
+            }
+        }
+
+        if ticks > 0 {
+            self.announce();
+        }
+
+        #[cfg(feature = "server")]
+        if ticks > 0 && let Some(server) = self.server.as_ref().filter(|server| server.has_subscribers()) {
+            server.publish(&self.state_json(false));
+        }
+
+        if self.flash.is_some_and(|started| started.elapsed() >= AUTO_PAUSE_FLASH) {
+            self.flash = None;
+        }
+
+        if let Some((_, started)) = self.track_fade {
+            self.grid_dirty = true;
+            if started.elapsed() >= TRACK_CROSSFADE {
+                self.track_fade = None;
+            }
+        }
+
+        if let Some((_, Some(started))) = self.scene_fade {
+            self.grid_dirty = true;
+            if started.elapsed() >= SCENE_CROSSFADE {
+                self.scene_fade = None;
+            }
+        }
+
+        let started = Instant::now();
+        if self.grid_dirty {
+            let geometry = self.geometry();
+            self.overlay.heat = self.heatmap_view.then(|| self.heatmap.levels());
+            (self.overlay.colors, self.overlay.ants) = match &self.mode {
+                Mode::Rule => (None, Vec::new()),
+                Mode::Ants(colony) => (None, colony.positions(self.universe.cols)),
+                Mode::Cyclic(automaton) => (Some(automaton.colors()), Vec::new()),
+                Mode::Lenia(automaton) => (Some(automaton.colors()), Vec::new()),
+                Mode::Protein(automaton) => (Some(automaton.colors()), Vec::new()),
+            };
+            if let SeedStrategy::Translated(_, residues) = &self.seed_strategy
+                && matches!(self.mode, Mode::Rule)
+            {
+                self.overlay.colors = Some(self.residue_colors(residues));
+            }
+            self.overlay.stroke = self.gesture.as_ref().map(|gesture| gesture.stroke(self.universe.cols)).unwrap_or_default();
+            if let Some(region) = self.gesture.as_ref().and_then(Gesture::selection) {
+                self.overlay.selection = Some(region);
+            }
+            if let Some(mark) = self.mark.as_ref().filter(|_| self.diff_view) {
+                self.overlay.colors = Some(mark.changes(&self.universe.cells).into_iter().map(Change::color).collect());
+            }
+            self.overlay.high_contrast = self.accessibility.high_contrast;
+            self.overlay.notes = self.notes.iter().flat_map(|note| self.seed_map.cells(note.position)).collect();
+            self.overlay.glyphs = match &self.signals {
+                Some((_, found)) => self.seed_map.cells_of_sorted(found, |signal| signal.position).into_iter().map(|(cell, i)| signal_glyph(cell, &found[i])).collect(),
+                None => Vec::new(),
+            };
+            self.overlay.stripes = match &self.repeats {
+                Some((_, found)) => self.repeat_stripes(found, &geometry),
+                None => Vec::new(),
+            };
+            if let Some((from, Some(started))) = &self.scene_fade {
+                let to = self.cell_colors();
+                if from.len() == to.len() {
+                    self.overlay.colors = Some(kiosk::crossfade(from, &to, started.elapsed().as_secs_f32() / SCENE_CROSSFADE.as_secs_f32()));
+                }
+            }
+            self.overlay.fill_dead = !self.quality.grid_lines();
+            if let Some(transition) = self.transition.as_mut() {
+                transition.show(&self.universe, self.clock.now());
+            }
+            if self.blob_colors {
+                let dead = if self.overlay.high_contrast { [0.0; 3] } else { DEAD_COLOR };
+                self.overlay.colors = Some(Components::label(&self.universe.cells, self.universe.rows, self.universe.cols).colors(dead));
+            }
+            self.overlay.trail = if self.trail_view { self.trajectory.wrapped() } else { Vec::new() };
+            self.overlay.blob_trail = if self.blob_tracking { self.blob_trail.wrapped() } else { Vec::new() };
+            self.overlay.contours = if self.contour_view {
+                let (rows, cols) = (geometry.visible_range(self.universe.rows, 1), geometry.visible_range(self.universe.cols, 0));
+                self.contours.trace(&self.universe.cells, self.universe.cols, rows, cols).to_vec()
+            } else {
+                Vec::new()
+            };
+            self.overlay.background = if self.overlay.layers.shown(Layer::Heat) { self.track_background(&geometry) } else { RegionColors::default() };
+            self.overlay.bases = if self.overlay.layers.shown(Layer::Bases) { self.base_colors(&geometry) } else { RegionColors::default() };
+            let backdrop = self.clear_color();
+            self.overlay.backdrop = [backdrop.r as f32, backdrop.g as f32, backdrop.b as f32];
+            self.grid_motions.clear();
+            // In 3D only the cells and the panels are drawn: the flat grid's overlays, tracks
+            // and strip have nothing to line up with
+            let scene = (self.torus_view && self.upload_torus()) || (self.heightfield_view && self.upload_heightfield());
+            if scene {
+                self.grid_data = Vec::new();
+                self.surface.letters = None;
+            } else {
+                // The cell texture only holds alive and dead, so the heat map, multi-state and
+                // continuous automata, the diff view, codon optimality, a changed layer stack, the
+                // high-contrast palette and smooth transitions are always drawn as geometry
+                let pixel = Viewport::of(&self.surface.window).pixel();
+                let cell_pixels = (geometry.pitch[0] / pixel[0]).min(geometry.pitch[1] / pixel[1]);
+                self.lod_block = if self.level_of_detail { lod::block_size(self.lod_block, cell_pixels) } else { 1 };
+                let view = match self.renderer {
+                    // Blocks replace both renderers: far fewer quads than cells, and a texture
+                    // sampled at less than a pixel a cell would alias
+                    _ if self.lod_block > 1 => CellView::Geometry,
+                    Renderer::Geometry => CellView::Geometry,
+                    Renderer::Texture if self.heatmap_view || self.overlay.colors.is_some() || !self.overlay.optimality.is_empty() || self.overlay.layers != Layers::default() || self.overlay.high_contrast || self.transition.is_some() => {
+                        CellView::Geometry
+                    }
+                    Renderer::Texture => CellView::Texture(GridLayout::new(&geometry, self.universe.rows, self.universe.cols)),
+                };
+                if let Err(err) = self.surface.grid.rebuild(&self.gpu, &self.universe, view) {
+                    warn!("{err}");
+                }
+                self.grid_data = match view {
+                    CellView::Texture(_) => {
+                        if let Some(texture) = &self.surface.grid.texture {
+                            texture.upload(&self.gpu, &self.universe, self.overlay.selection);
+                        }
+                        create_overlay_vertices(&self.universe, &geometry, &self.overlay)
+                    }
+                    _ if self.lod_block > 1 => create_lod_vertices(&self.universe, &geometry, &self.overlay, self.lod_block),
+                    _ if let Some(transition) = &self.transition => {
+                        let (vertices, motions) = create_smooth_grid_vertices(&self.universe, &geometry, &self.overlay, &transition.previous);
+                        self.grid_motions = motions;
+                        vertices
+                    }
+                    _ => create_grid_vertices(&self.universe, &geometry, &self.overlay),
+                };
+                let letters = if self.letter_view { self.base_letters(&geometry) } else { Vec::new() };
+                if letters.is_empty() {
+                    self.surface.letters = None;
+                } else {
+                    let view = self.surface.letters.get_or_insert_with(|| LetterView::new(&self.gpu, self.surface.config.format));
+                    view.upload(&self.gpu, &letters, self.grid_data.len() as u32);
+                }
+                // The selected track below the grid, spanning its visible width, above the sequence
+                // strip; while crossfading, the previous one fades out over it
+                let (frame, pixel) = (self.track_frame(&geometry), font_pixel(&Viewport::of(&self.surface.window), self.accessibility.text_scale));
+                let charts: Vec<Vertex> = self
+                    .track_weights()
+                    .into_iter()
+                    .flat_map(|(track, weight)| {
+                        let color = track.color.map(|channel| channel * weight);
+                        let chart = track.chart();
+                        let (values, stride) = decimate(chart.values, self.quality.track_points());
+                        let mark = chart.mark.map(|i| i / stride);
+                        Chart { color, values: &values, mark, x_step: chart.x_step * stride, ..chart }.vertices(&frame, Some(pixel), self.track_hover)
+                    })
+                    .collect();
+                self.grid_data.extend(charts);
+                if let Some(records) = &self.row_records {
+                    let rows = geometry.visible_range(self.universe.rows, 1);
+                    self.grid_data.extend(records.vertices(&geometry, rows, &Viewport::of(&self.surface.window), self.accessibility.text_scale));
+                }
+                // Record labels take the place of the row axis
+                if self.ruler_view {
+                    let visible = (geometry.visible_range(self.universe.rows, 1), geometry.visible_range(self.universe.cols, 0));
+                    let viewport = Viewport::of(&self.surface.window);
+                    self.grid_data.extend(create_ruler_vertices(&geometry, &self.seed_map, visible, &viewport, self.accessibility.text_scale, self.row_records.is_none()));
+                }
+                if self.strip_view {
+                    let windows = self.seed_map.windows();
+                    self.grid_data.extend(create_strip_vertices(&self.dna, self.strip_start, &self.strip_layout(), &windows, self.hovered_base));
+                }
+            }
+            // The help covers the same corner, and takes it while open
+            if self.help_view {
+                self.grid_data.extend(create_panel_vertices(&self.help_lines(), &Viewport::of(&self.surface.window), self.accessibility.text_scale));
+            } else if self.profile_view {
+                self.grid_data.extend(create_panel_vertices(&self.profile_lines(), &Viewport::of(&self.surface.window), self.accessibility.text_scale));
+            } else if self.primers.is_some() {
+                self.grid_data.extend(create_panel_vertices(&self.primer_lines(), &Viewport::of(&self.surface.window), self.accessibility.text_scale));
+            } else if self.frames_shown() {
+                self.grid_data.extend(create_panel_vertices(&self.frame_lines(), &Viewport::of(&self.surface.window), self.accessibility.text_scale));
+            } else if self.repeats.is_some() {
+                self.grid_data.extend(create_panel_vertices(&self.repeat_lines(), &Viewport::of(&self.surface.window), self.accessibility.text_scale));
+            } else if self.rule_panel {
+                self.grid_data.extend(create_panel_vertices(&self.rule_lines(), &Viewport::of(&self.surface.window), self.accessibility.text_scale));
+            } else if let Some(panel) = self.layer_panel_layout() {
+                self.grid_data.extend(panel.vertices(&self.overlay.layers));
+            }
+            if !scene {
+                self.grid_data.extend(self.toolbar_layout().vertices(self.tool));
+                if let Some(popup) = self.note_popup(&geometry) {
+                    self.grid_data.extend(popup);
+                }
+                if let Some(menu) = &self.context_menu {
+                    self.grid_data.extend(menu.vertices(&Viewport::of(&self.surface.window), self.accessibility.text_scale, self.menu_hover));
+                }
+            }
+            self.surface.upload(&self.gpu, &self.grid_data);
+            self.surface.upload_motions(&self.gpu, &self.grid_motions);
+            self.grid_dirty = false;
+            self.generations_unshown = false;
+            self.frames_since_upload = 0;
+            self.record_cpu_time("grid upload", started);
+        }
+        // The geometry only changes with the generation; the shader plays the transition into it
+        let progress = self.transition.as_ref().map_or(1.0, |transition| transition.progress(self.clock.now(), self.tick_interval));
+        self.surface.set_progress(&self.gpu, progress);
+
+        if self.analysis_dirty && let Some(viewport) = self.analysis.as_ref().map(|analysis| Viewport::of(&analysis.window)) {
+            let vertices = self.analysis_vertices(&viewport, self.analysis_hover);
+            if let Some(analysis) = self.analysis.as_mut() {
+                analysis.upload(&self.gpu, &vertices);
+            }
+            self.analysis_dirty = false;
+        }
+        if let Some(analysis) = &self.analysis {
+            analysis.window.request_redraw();
+        }
+
+        // A fixed clock renders the same frames however long they take
+        if !self.clock.is_fixed() && let Some(level) = self.quality.record(frame_started.elapsed()) {
+            let budget = self.quality.budget.limit.as_secs_f64() * 1000.0;
+            info!("Updates take {:.1} ms against a budget of {budget:.1} ms, switching to {level} quality", self.quality.average_ms());
+            self.grid_dirty = true;
+        }
+
+        let mut title = String::from("Bio Rust");
+        if self.gpu.software {
+            title.push_str(" (software rendering)");
+        }
+        if self.quality.level() != Quality::Full {
+            title.push_str(&format!(" | {} quality", self.quality.level()));
+        }
+        if self.rule_walk.is_some() {
+            title.push_str(&format!(" | walk {}", self.universe.rule));
+        }
+        if let Some(turbo) = &self.turbo {
+            title.push_str(&format!(" | turbo {}/frame, generation {}", turbo.steps, self.universe.generation));
+            if let Some(rate) = turbo.rate() {
+                title.push_str(&format!(" at {rate:.0}/s"));
+            }
+        }
+        match &self.mode {
+            Mode::Rule => {}
+            Mode::Ants(colony) => title.push_str(&format!(" | {} ants, {} cells visited", colony.ants.len(), colony.visited_count)),
+            Mode::Cyclic(automaton) => title.push_str(&format!(" | cyclic {}", composition(automaton))),
+            Mode::Lenia(automaton) => {
+                let device = if automaton.on_gpu() { "GPU" } else { "CPU" };
+                title.push_str(&format!(" | Lenia mass {:.0} ({device})", automaton.mass()));
+            }
+            Mode::Protein(automaton) => title.push_str(&format!(" | protein {}", class_shares(automaton))),
+        }
+        // Only while nothing the preset sets has been changed since
+        if let Some(slot) = self.presets.active
+            && self.presets.get(slot).is_some_and(|preset| preset.parameters() == self.parameters())
+        {
+            title.push_str(&format!(" | preset {slot}"));
+        }
+        if let Some(mark) = self.mark.as_ref().filter(|_| self.diff_view) {
+            title.push_str(&format!(" | changes since generation {}", mark.generation));
+        }
+        if self.trail_view && let Some([dx, dy]) = self.trajectory.drift() {
+            title.push_str(&format!(" | drift {dx:+.2}, {dy:+.2} cells/gen"));
+        }
+        if let Some((count, largest)) = self.blob_summary.filter(|_| self.blob_tracking) {
+            title.push_str(&format!(" | {count} blobs, largest {largest}"));
+        }
+        if let Some(verifier) = &self.verifier {
+            match verifier.divergence {
+                Some(divergence) => title.push_str(&format!(" | diverged at generation {}", divergence.generation)),
+                None => title.push_str(&format!(" | verified {}/{}", verifier.checked.len(), verifier.reference.hashes.len())),
+            }
+        }
+        if let Some(loader) = &self.loader {
+            let name = loader.path.file_name().unwrap_or(loader.path.as_os_str()).to_string_lossy();
+            title.push_str(&format!(" | loading {name} {:.0}%", loader.progress() * 100.0));
+        }
+        if let Some(prompt) = self.console.prompt() {
+            title.push(' ');
+            title.push_str(&prompt);
+        } else if let Some(err) = self.shader.as_ref().and_then(|shader| shader.error.as_ref()) {
+            // Full error in the terminal; the first line is enough to notice it
+            title.push_str(" | shader error: ");
+            title.push_str(err.lines().find(|line| !line.trim().is_empty()).unwrap_or_default().trim());
+        }
+        if title != self.title {
+            self.surface.window.set_title(&title);
+            self.title = title;
+        }
+        self.surface.window.request_redraw();
+    }
+
+    // Build the torus view when it is missing or the grid changed size, then upload the cells
+    // and the camera. Grids too large for one texture turn the view off again.
+    fn upload_torus(&mut self) -> bool {
+        if let Err(err) = self.surface.grid.rebuild(&self.gpu, &self.universe, CellView::Torus) {
+            warn!("{err}");
+            self.torus_view = false;
+            return false;
+        }
+        if let Some(torus) = &self.surface.grid.torus {
+            let aspect = self.surface.config.width as f32 / self.surface.config.height as f32;
+            torus.upload(&self.gpu, &self.universe, self.overlay.selection, &self.torus_camera, aspect);
+        }
+        true
+    }
+
+    // The same for the heightfield view, with the columns built from the cells' ages
+    fn upload_heightfield(&mut self) -> bool {
+        if let Err(err) = self.surface.grid.rebuild(&self.gpu, &self.universe, CellView::Heightfield) {
+            warn!("{err}");
+            self.heightfield_view = false;
+            return false;
+        }
+        if let Some(view) = &self.surface.grid.heightfield {
+            let columns = heightfield::columns(&self.universe, &self.ages.levels(), self.overlay.selection);
+            view.upload(&self.gpu, &columns, self.surface.config.width as f32 / self.surface.config.height as f32);
+        }
+        true
+    }
+
+    // The simulation's state for the server's /state endpoint and WebSocket stream, with the
+    // connected groups of live cells only when asked for
+    #[cfg(feature = "server")]
+    fn state_json(&self, with_components: bool) -> String {
+        let population = self.universe.cells.iter().filter(|&&alive| alive).count();
+        let components = if with_components {
+            let components = Components::label(&self.universe.cells, self.universe.rows, self.universe.cols);
+            let sizes: Vec<String> = components.sorted_sizes().iter().take(LISTED_COMPONENTS).map(u32::to_string).collect();
+            let largest = match components.largest().and_then(|id| components.centroid(id)) {
+                Some([x, y]) => format!("[{x:.2},{y:.2}]"),
+                None => "null".to_string(),
+            };
+            format!(",\"components\":{{\"count\":{},\"sizes\":[{}],\"largest_centroid\":{largest}}}", components.count(), sizes.join(","))
+        } else {
+            String::new()
+        };
+        format!(
+            "{{\"generation\":{},\"rows\":{},\"cols\":{},\"population\":{population},\"rule\":\"{}\",\"mode\":\"{}\",\"speed\":{},\"paused\":{}{components},\"provenance\":{}}}",
+            self.universe.generation,
+            self.universe.rows,
+            self.universe.cols,
+            self.universe.rule,
+            self.mode_name(),
+            self.speed(),
+            self.paused,
+            self.provenance().to_json(),
+        )
+    }
+
+    // Answer what the server's clients asked since the last update
+    #[cfg(feature = "server")]
+    fn answer_queries(&mut self) {
+        while let Some((query, reply)) = self.server.as_ref().and_then(Server::next_query) {
+            let answer = match query {
+                Query::State { components } => Reply::Json(self.state_json(components)),
+                Query::Grid => {
+                    let rows = self.universe.cells.chunks(self.universe.cols as usize);
+                    Reply::Text(rows.map(|row| row.iter().map(|&alive| if alive { 'O' } else { '.' }).chain(['\n']).collect::<String>()).collect())
+                }
+                Query::Frame => {
+                    let (width, height, pixels) = self.surface.capture(&self.gpu, self.clear_color());
+                    let mut png = Vec::new();
+                    match export::png::encode_rgba(&mut png, width, height, &pixels, Some(&self.provenance())) {
+                        Ok(()) => Reply::Png(png),
+                        Err(err) => Reply::Error(500, format!("Failed to encode the frame: {err}")),
+                    }
+                }
+                Query::Apply(action) => {
+                    info!("Remote command: {action}");
+                    self.apply(action);
+                    Reply::Json(self.state_json(false))
+                }
+            };
+            // The client may have given up waiting
+            let _ = reply.send(answer);
+        }
+    }
+
+    // Stream the grid window to a video file from now on
+    pub fn record_video(&mut self, settings: VideoSettings) -> Result<(), String> {
+        let (width, height) = (self.surface.config.width, self.surface.config.height);
+        let path = settings.path.clone();
+        self.video = Some(VideoRecorder::start(settings, width, height)?);
+        info!("Recording video to {}", path.display());
+        Ok(())
+    }
+
+    // Write the rest of the per-generation table and its footer, if recording one
+    pub fn finish_table(&mut self) {
+        #[cfg(feature = "arrow")]
+        if let Some(table) = self.table.take() {
+            let path = table.path.clone();
+            match table.finish() {
+                Ok(rows) => info!("Wrote {rows} generations to {}", path.display()),
+                Err(err) => warn!("Table {}: {err}", path.display()),
+            }
+        }
+    }
+
+    // Flush the per-generation hashes, if recording them
+    pub fn finish_hashes(&mut self) {
+        if let Some(hashes) = self.hashes.take() {
+            let path = hashes.path.clone();
+            match hashes.finish() {
+                Ok(count) => info!("Wrote the hashes of {count} generations to {}", path.display()),
+                Err(err) => warn!("Hashes {}: {err}", path.display()),
+            }
+        }
+    }
+
+    // Wait for ffmpeg to finish the file, if recording
+    pub fn finish_video(&mut self) {
+        let Some(video) = self.video.take() else {
+            return;
+        };
+        let (path, fps) = (video.settings.path.clone(), video.settings.fps);
+        match video.finish() {
+            Ok(frames) => info!("Wrote {frames} frames ({:.1} s) to {}", frames as f64 / fps as f64, path.display()),
+            Err(err) => warn!("Video {}: {err}", path.display()),
+        }
+    }
+
+    // Write the contact sheet with the thumbnails taken so far, if capturing one
+    pub fn finish_sheet(&mut self) {
+        let Some(sheet) = self.sheet.take() else {
+            return;
+        };
+        // The simulation thread takes the stepping back, from the generations stepped here
+        self.simulation_synced = false;
+        if sheet.is_empty() {
+            return;
+        }
+        match sheet.write(&self.provenance()) {
+            Ok(()) => info!("Wrote a contact sheet of {} generations to {}", sheet.len(), sheet.settings.path.display()),
+            Err(err) => warn!("{err}"),
+        }
+    }
+
+    // Capture the frame for the contact sheet when its generation is due, writing the sheet
+    // once the last thumbnail is in
+    fn capture_sheet_frame(&mut self) {
+        let generation = self.universe.generation;
+        if !self.sheet.as_ref().is_some_and(|sheet| sheet.due(generation)) {
+            return;
+        }
+        let (width, height, pixels) = self.surface.capture(&self.gpu, self.clear_color());
+        let Some(sheet) = self.sheet.as_mut() else {
+            return;
+        };
+        sheet.push(generation, width, height, &pixels);
+        if sheet.is_complete() {
+            self.finish_sheet();
+        }
+    }
+
+    // Capture the frame for the video when one is due, repeating it to catch up when the
+    // window renders slower than the video's frame rate
+    fn capture_video_frame(&mut self) {
+        // A fixed clock runs at the video's frame rate, one frame per frame
+        let due = |video: &VideoRecorder| if self.clock.is_fixed() { u64::from(!video.is_complete()) } else { video.frames_due() };
+        let Some(copies) = self.video.as_ref().map(due).filter(|&copies| copies > 0) else {
+            return;
+        };
+        let clear_color = self.clear_color();
+        let (width, height, pixels) = self.surface.capture(&self.gpu, clear_color);
+        let Some(video) = self.video.as_mut() else {
+            return;
+        };
+        // ffmpeg's own error comes out of `finish`
+        let failed = video.push(width, height, pixels, copies).is_err();
+        if failed || video.is_complete() {
+            self.finish_video();
+        }
+    }
+
+    // Step on a fixed clock of `fps` frames per second instead of the wall clock, so the same
+    // inputs step the same generations on the same frames on any machine (`--deterministic`)
+    pub fn make_deterministic(&mut self, fps: u32) {
+        self.clock = TickClock::fixed(fps);
+    }
+
+    pub fn render(&mut self, id: WindowId) {
+        let _span = tracing::trace_span!("render").entered();
+        let clear_color = self.clear_color();
+        if let Some(analysis) = self.analysis.as_mut().filter(|analysis| analysis.window.id() == id) {
+            analysis.render(&self.gpu, clear_color);
+        } else if id == self.surface.window.id() {
+            let started = Instant::now();
+            self.surface.render(&self.gpu, self.flashed(clear_color));
+            self.record_cpu_time("render", started);
+            let now = Instant::now();
+            if let Some(last_frame) = self.last_frame.replace(now) {
+                self.frame_times.record(now - last_frame);
+                if self.profile_view {
+                    self.cpu_timings.record("frame", now - last_frame);
+                }
+            }
+            self.capture_video_frame();
+            self.capture_sheet_frame();
+        }
+    }
+
+    // The clear color brightened while the auto-pause flash fades out
+    fn flashed(&self, color: Color) -> Color {
+        let Some(started) = self.flash else {
+            return color;
+        };
+        let strength = 0.6 * (1.0 - (started.elapsed().as_secs_f64() / AUTO_PAUSE_FLASH.as_secs_f64()).min(1.0));
+        let blend = |channel: f64| channel + (1.0 - channel) * strength;
+        Color { r: blend(color.r), g: blend(color.g), b: blend(color.b), a: color.a }
+    }
+
+    // What a preset sets, as it is now
+    fn parameters(&self) -> Parameters {
+        Parameters { rule: self.universe.rule, seed_strategy: self.seed_strategy.clone(), theme: self.theme() }
+    }
+
+    pub fn theme(&self) -> Theme {
+        match (self.accessibility.high_contrast, self.color_toggle) {
+            (true, _) => Theme::Contrast,
+            (false, true) => Theme::Red,
+            (false, false) => Theme::Blue,
+        }
+    }
+
+    fn set_parameters(&mut self, Parameters { rule, seed_strategy, theme }: Parameters) {
+        self.universe.rule = rule;
+        self.seed_strategy = seed_strategy;
+        self.accessibility.high_contrast = theme == Theme::Contrast;
+        if theme != Theme::Contrast {
+            self.color_toggle = theme == Theme::Red;
+        }
+    }
+
+    fn clear_color(&self) -> Color {
+        if self.accessibility.high_contrast {
+            return Color::BLACK;
+        }
+        Color {
+            r: if self.color_toggle { 0.15 } else { 0.05 },
+            g: 0.05,
+            b: if !self.color_toggle { 0.15 } else { 0.05 },
+            a: 1.0
+        }
+    }
+}
+
+// Start codons point the way they are read, stops are bars, Kozak contexts diamonds and
+// Shine-Dalgarno sites pins
+fn signal_glyph(cell: usize, signal: &Signal) -> (usize, Glyph, [f32; 3]) {
+    match signal.kind {
+        SignalKind::Start => (cell, Glyph::Arrow { right: signal.frame.strand == Strand::Forward }, [0.3, 1.0, 0.45]),
+        SignalKind::Stop => (cell, Glyph::Bar, [1.0, 0.3, 0.3]),
+        SignalKind::Kozak => (cell, Glyph::Diamond, [0.8, 0.55, 1.0]),
+        SignalKind::ShineDalgarno => (cell, Glyph::Pin, [0.3, 0.85, 1.0]),
+    }
+}
+
+// Protein sequences are seeded from their hydrophobic residues, nucleotides from G/C
+fn sequence_seed_strategy(dna: &[u8]) -> SeedStrategy {
+    if protein::is_protein(dna) { SeedStrategy::Hydrophobic } else { SeedStrategy::GcContent }
+}
+
+fn translated_seed_strategy(dna: &[u8], frame: ReadingFrame) -> SeedStrategy {
+    SeedStrategy::Translated(frame, frames::residues(dna, frame).into())
+}
+
+// Share of the cells in each state, e.g. "A 31% C 18% G 22% T 29%"
+fn composition(automaton: &Cyclic) -> String {
+    let counts = automaton.counts();
+    let total = counts.iter().sum::<usize>().max(1) as f64;
+    let shares: Vec<String> = cyclic::BASES
+        .iter()
+        .zip(counts)
+        .map(|(&base, count)| format!("{} {:.0}%", base as char, count as f64 / total * 100.0))
+        .collect();
+    shares.join(" ")
+}
+
+// Share of the occupied cells in each property class, e.g. "hydrophobic 41% polar 22% ..."
+fn class_shares(automaton: &Protein) -> String {
+    let counts = automaton.counts();
+    let total = counts.iter().sum::<usize>().max(1) as f64;
+    let shares: Vec<String> = PropertyClass::ALL
+        .iter()
+        .zip(counts)
+        .map(|(class, count)| format!("{class} {:.0}%", count as f64 / total * 100.0))
+        .collect();
+    shares.join(" ")
+}
+
+// A proposed primer for the log and the panel, e.g. "+ 1210..1230 ACGT... Tm 58.2 GC 50 self 3".
+// The panel's font has no percent sign.
+fn primer_line(candidate: &Candidate) -> String {
+    let strand = if candidate.strand == Strand::Forward { '+' } else { '-' };
+    format!(
+        "{strand} {}..{} {:<25} Tm {:.1} GC {:.0} self {}",
+        candidate.start, candidate.end, candidate.sequence, candidate.tm, candidate.gc, candidate.self_complementarity
+    )
+}
+
+// Codon usage table, four codons per line in the table's current order
+fn print_codon_table(table: &CodonTable) {
+    for row in table.codons.chunks(4) {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|usage| format!("{} {} {:>6} {:>5.2}", usage.codon, usage.amino_acid, usage.count, usage.rscu))
+            .collect();
+        info!("  {}", cells.join("   "));
+    }
+}
+
+// A panic unwinding out of the event loop drops the app, which saves the session for the
+// next launch to offer back. Closing the window drops it too, and then nothing needs
+// recovering.
+impl Drop for App {
+    fn drop(&mut self) {
+        if !self.autosave.enabled {
+            return;
+        }
+        self.finish_autosave();
+        let path = session::recovery_path();
+        if std::thread::panicking() {
+            match self.session().save(&path) {
+                Ok(()) => tracing::error!("Saved the session to {} before exiting", path.display()),
+                Err(err) => tracing::error!("{err}"),
+            }
+        } else if path.exists() && let Err(err) = std::fs::remove_file(&path) {
+            warn!("Failed to remove {}: {err}", path.display());
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::universe::Universe;
+
+// Langton's ant with its turns read from the sequence: an ant on a dead cell turns right if
+// the cell was seeded from a purine (A, G) and left on a pyrimidine (C, T), turns the other
+// way on a live cell, then flips the cell and steps forward. An all-purine sequence is the
+// classic ant. Cells map to sequence positions in row-major order, like the seeding.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Heading {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Heading {
+    fn turn(self, right: bool) -> Self {
+        match (self, right) {
+            (Heading::Up, true) | (Heading::Down, false) => Heading::Right,
+            (Heading::Right, true) | (Heading::Left, false) => Heading::Down,
+            (Heading::Down, true) | (Heading::Up, false) => Heading::Left,
+            (Heading::Left, true) | (Heading::Right, false) => Heading::Up,
+        }
+    }
+
+    // Heading named by a base: A up, C right, G down, T left
+    fn from_base(base: u8) -> Self {
+        match base.to_ascii_uppercase() {
+            b'C' => Heading::Right,
+            b'G' => Heading::Down,
+            b'T' => Heading::Left,
+            _ => Heading::Up,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Ant {
+    pub row: u32,
+    pub col: u32,
+    pub heading: Heading,
+}
+
+// Purines and the purine ambiguity code turn right; pyrimidines and Y turn left. Anything
+// else (N, gaps, cells past the end of the sequence) behaves like the classic ant.
+pub fn turns_right(base: Option<u8>) -> bool {
+    !matches!(base.map(|base| base.to_ascii_uppercase()), Some(b'C' | b'T' | b'U' | b'Y'))
+}
+
+pub struct Colony {
+    pub ants: Vec<Ant>,
+    // Per cell, whether an ant on it turns right while it is dead
+    turns: Vec<bool>,
+    visited: Vec<bool>,
+    // Distinct cells any ant has stood on
+    pub visited_count: usize,
+    pub steps: u64,
+}
+
+impl Colony {
+    // Up to `count` ants, one on each ATG start codon of the sequence that falls on the grid,
+    // facing the way named by the base after it. Without any, one ant starts in the middle.
+    pub fn new(dna: &[u8], rows: u32, cols: u32, count: usize) -> Self {
+        let cells = (rows * cols) as usize;
+        let mut ants: Vec<Ant> = dna
+            .windows(4)
+            .enumerate()
+            .filter(|&(position, window)| position < cells && window[..3].eq_ignore_ascii_case(b"ATG"))
+            .take(count)
+            .map(|(position, window)| Ant { row: position as u32 / cols, col: position as u32 % cols, heading: Heading::from_base(window[3]) })
+            .collect();
+        if ants.is_empty() && count > 0 {
+            ants.push(Ant { row: rows / 2, col: cols / 2, heading: Heading::Up });
+        }
+
+        let mut visited = vec![false; cells];
+        for ant in &ants {
+            visited[(ant.row * cols + ant.col) as usize] = true;
+        }
+        Self {
+            turns: (0..cells).map(|position| turns_right(dna.get(position).copied())).collect(),
+            visited_count: visited.iter().filter(|&&visited| visited).count(),
+            visited,
+            ants,
+            steps: 0,
+        }
+    }
+
+    // Move every ant once, in order, and count it as a generation
+    pub fn step(&mut self, universe: &mut Universe) {
+        let (rows, cols) = (universe.rows, universe.cols);
+        for ant in &mut self.ants {
+            let idx = (ant.row * cols + ant.col) as usize;
+            let alive = universe.cells[idx];
+            ant.heading = ant.heading.turn(self.turns[idx] != alive);
+            universe.cells[idx] = !alive;
+
+            // Row 0 is at the bottom; the grid wraps around
+            match ant.heading {
+                Heading::Up => ant.row = (ant.row + 1) % rows,
+                Heading::Down => ant.row = (ant.row + rows - 1) % rows,
+                Heading::Right => ant.col = (ant.col + 1) % cols,
+                Heading::Left => ant.col = (ant.col + cols - 1) % cols,
+            }
+            let idx = (ant.row * cols + ant.col) as usize;
+            if !self.visited[idx] {
+                self.visited[idx] = true;
+                self.visited_count += 1;
+            }
+        }
+        self.steps += 1;
+        universe.generation += 1;
+    }
+
+    // Cells the ants are on, row-major
+    pub fn positions(&self, cols: u32) -> Vec<usize> {
+        self.ants.iter().map(|ant| (ant.row * cols + ant.col) as usize).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classic_ant_builds_its_highway() {
+        // By 11000 steps the classic ant is on its highway: every 104 steps it moves two
+        // cells diagonally and leaves 12 more black cells. 100x100 keeps it from wrapping.
+        let mut universe = Universe::new(100, 100, b"");
+        let mut colony = Colony::new(b"", 100, 100, 1);
+        assert_eq!(colony.ants[0], Ant { row: 50, col: 50, heading: Heading::Up });
+        for _ in 0..11000 {
+            colony.step(&mut universe);
+        }
+        let population = |universe: &Universe| universe.cells.iter().filter(|&&alive| alive).count();
+        let (before, ant) = (population(&universe), colony.ants[0]);
+        for _ in 0..104 {
+            colony.step(&mut universe);
+        }
+        assert_eq!(population(&universe), before + 12);
+        assert_eq!((colony.ants[0].row.abs_diff(ant.row), colony.ants[0].col.abs_diff(ant.col)), (2, 2));
+        assert_eq!(universe.generation, 11104);
+        assert!(colony.visited_count > before);
+    }
+
+    #[test]
+    fn ants_start_on_start_codons() {
+        // ATG at 2 facing C (right), and at 9 facing A (up); the third is past the grid
+        let colony = Colony::new(b"CCATGCCCCATGACCCCCCCCCATGT", 4, 5, 3);
+        assert_eq!(colony.ants, vec![
+            Ant { row: 0, col: 2, heading: Heading::Right },
+            Ant { row: 1, col: 4, heading: Heading::Up },
+        ]);
+        assert!(turns_right(Some(b'g')) && !turns_right(Some(b'C')) && turns_right(None));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::universe::Universe;
+
+// Cyclic cellular automaton with the four bases as competing states: A beats C beats G beats
+// T beats A. A cell is taken over by the state that beats it when at least `threshold` of
+// its eight neighbors hold that state. Seeded straight from the sequence, the takeover
+// fronts organize into spiral waves shaped by the base composition.
+
+// States in the order A, C, G, T
+pub const BASES: [u8; 4] = *b"ACGT";
+
+// The state that beats `state`
+fn predator(state: u8) -> u8 {
+    (state + 3) % 4
+}
+
+pub struct Cyclic {
+    // One state per cell, 0..4 as in BASES
+    pub states: Vec<u8>,
+    rows: u32,
+    cols: u32,
+    pub threshold: u8,
+}
+
+impl Cyclic {
+    // Every cell takes the state of its base, reading the sequence in row-major order and
+    // starting over at its end; bases other than ACGT go round the four states
+    pub fn new(dna: &[u8], rows: u32, cols: u32, threshold: u8) -> Self {
+        let states = (0..(rows * cols) as usize)
+            .map(|idx| {
+                let base = if dna.is_empty() { b'N' } else { dna[idx % dna.len()].to_ascii_uppercase() };
+                BASES.iter().position(|&b| b == base).unwrap_or(idx % 4) as u8
+            })
+            .collect();
+        Self { states, rows, cols, threshold }
+    }
+
+    // One synchronous generation. The universe's live cells become the ones just taken over,
+    // so population, heat map and centroid follow the wave fronts.
+    pub fn step(&mut self, universe: &mut Universe) {
+        let (rows, cols) = (self.rows as usize, self.cols as usize);
+        let mut next = self.states.clone();
+        for row in 0..rows {
+            for col in 0..cols {
+                let idx = row * cols + col;
+                let predator = predator(self.states[idx]);
+                let mut count = 0;
+                for (dr, dc) in [(rows - 1, cols - 1), (rows - 1, 0), (rows - 1, 1), (0, cols - 1), (0, 1), (1, cols - 1), (1, 0), (1, 1)] {
+                    let neighbor = (row + dr) % rows * cols + (col + dc) % cols;
+                    count += u8::from(self.states[neighbor] == predator);
+                }
+                if count >= self.threshold {
+                    next[idx] = predator;
+                }
+            }
+        }
+
+        for (alive, (&before, &after)) in universe.cells.iter_mut().zip(self.states.iter().zip(&next)) {
+            *alive = before != after;
+        }
+        self.states = next;
+        universe.generation += 1;
+    }
+
+    // Cells in each state, A, C, G, T
+    pub fn counts(&self) -> [usize; 4] {
+        let mut counts = [0; 4];
+        for &state in &self.states {
+            counts[state as usize] += 1;
+        }
+        counts
+    }
+
+    pub fn colors(&self) -> Vec<[f32; 3]> {
+        self.states.iter().map(|&state| color(state)).collect()
+    }
+}
+
+// A green, C blue, G orange, T red, also used for the bases in the sequence strip
+pub fn color(state: u8) -> [f32; 3] {
+    match state {
+        0 => [0.35, 0.85, 0.35],
+        1 => [0.35, 0.6, 1.0],
+        2 => [1.0, 0.75, 0.2],
+        _ => [1.0, 0.35, 0.35],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predators_take_over_at_the_threshold() {
+        assert_eq!([0, 1, 2, 3].map(predator), [3, 0, 1, 2]);
+
+        // A C in the middle of As is eaten; the As around it have no T to fear
+        let mut cyclic = Cyclic::new(b"AAAAACAAAAAAAAAAAAAAAAAAA", 5, 5, 3);
+        let mut universe = Universe::new(5, 5, b"");
+        assert_eq!(cyclic.counts(), [24, 1, 0, 0]);
+        cyclic.step(&mut universe);
+        assert_eq!(cyclic.counts(), [25, 0, 0, 0]);
+        assert_eq!(universe.cells.iter().position(|&alive| alive), Some(5));
+        assert_eq!(universe.generation, 1);
+
+        // Two Ts next to an A are below a threshold of three
+        let mut universe = Universe::new(3, 3, b"");
+        let mut cyclic = Cyclic::new(b"TTAAAAAAA", 3, 3, 3);
+        cyclic.step(&mut universe);
+        assert_eq!(cyclic.counts(), [7, 0, 0, 2]);
+        let mut cyclic = Cyclic::new(b"TTAAAAAAA", 3, 3, 2);
+        cyclic.step(&mut universe);
+        assert_eq!(cyclic.counts(), [0, 0, 0, 9]);
+    }
+}
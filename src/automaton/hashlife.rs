@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+
+use crate::rule::Rule;
+use crate::universe::Universe;
+
+// HashLife: the plane is stored as a hash-consed quadtree and the future of every
+// node is memoized, so periodic or repetitive patterns can be advanced by huge
+// powers of two in a handful of lookups.
+//
+// The universe is a torus, which HashLife has no notion of. Instead the grid is tiled
+// across the plane: a node of level k contains a full tile in its centre half, and
+// anything that happens within 2^(k-2) generations cannot reach the centre from
+// outside the node, so the centre half of the result is exactly the next torus state.
+
+type NodeId = u32;
+
+const DEAD: NodeId = 0;
+const ALIVE: NodeId = 1;
+
+#[derive(Copy, Clone)]
+struct Node {
+    level: u8,
+    nw: NodeId,
+    ne: NodeId,
+    sw: NodeId,
+    se: NodeId,
+    population: u64,
+}
+
+// Births with zero neighbours would make the empty plane fill up, breaking the
+// "empty stays empty" shortcut the algorithm relies on.
+pub fn supports(rule: &Rule) -> bool {
+    rule.birth & 1 == 0
+}
+
+pub fn jump(universe: &mut Universe, generations: u64) {
+    let mut engine = HashLife::new(universe.rule);
+    let tile_level = (universe.rows.max(universe.cols) as u64).next_power_of_two().trailing_zeros() as u8;
+
+    for step_level in 0..64u8 {
+        if generations & (1 << step_level) == 0 {
+            continue;
+        }
+
+        // Centre half must hold a whole tile and the step must be at most a quarter of the node
+        let level = (step_level + 2).max(tile_level + 1).max(2);
+        let root = engine.tile(universe, level);
+        let result = engine.step(root, step_level);
+        engine.write_cells(result, universe);
+    }
+
+    universe.generation += generations;
+}
+
+struct HashLife {
+    rule: Rule,
+    nodes: Vec<Node>,
+    lookup: HashMap<(NodeId, NodeId, NodeId, NodeId), NodeId>,
+    steps: HashMap<(NodeId, u8), NodeId>,
+    empty: Vec<NodeId>,
+}
+
+impl HashLife {
+    fn new(rule: Rule) -> Self {
+        let leaf = |population| Node { level: 0, nw: DEAD, ne: DEAD, sw: DEAD, se: DEAD, population };
+        Self {
+            rule,
+            nodes: vec![leaf(0), leaf(1)],
+            lookup: HashMap::new(),
+            steps: HashMap::new(),
+            empty: vec![DEAD],
+        }
+    }
+
+    fn join(&mut self, nw: NodeId, ne: NodeId, sw: NodeId, se: NodeId) -> NodeId {
+        if let Some(&id) = self.lookup.get(&(nw, ne, sw, se)) {
+            return id;
+        }
+
+        let level = self.nodes[nw as usize].level + 1;
+        let population = [nw, ne, sw, se]
+            .iter()
+            .fold(0u64, |acc, &child| acc.saturating_add(self.nodes[child as usize].population));
+
+        let id = self.nodes.len() as NodeId;
+        self.nodes.push(Node { level, nw, ne, sw, se, population });
+        self.lookup.insert((nw, ne, sw, se), id);
+        id
+    }
+
+    fn empty(&mut self, level: u8) -> NodeId {
+        while self.empty.len() <= level as usize {
+            let below = *self.empty.last().unwrap();
+            let node = self.join(below, below, below, below);
+            self.empty.push(node);
+        }
+        self.empty[level as usize]
+    }
+
+    fn centre(&mut self, id: NodeId) -> NodeId {
+        let n = self.nodes[id as usize];
+        let (nw, ne, sw, se) = (self.node(n.nw), self.node(n.ne), self.node(n.sw), self.node(n.se));
+        self.join(nw.se, ne.sw, sw.ne, se.nw)
+    }
+
+    fn node(&self, id: NodeId) -> Node {
+        self.nodes[id as usize]
+    }
+
+    fn cell(&self, mut id: NodeId, mut x: u64, mut y: u64) -> bool {
+        loop {
+            let node = self.node(id);
+            if node.level == 0 {
+                return id == ALIVE;
+            }
+            let half = 1 << (node.level - 1);
+            id = match (x >= half, y >= half) {
+                (false, false) => node.nw,
+                (true, false) => node.ne,
+                (false, true) => node.sw,
+                (true, true) => node.se,
+            };
+            x %= half;
+            y %= half;
+        }
+    }
+
+    // Advance the centre of a level-k node by 2^step_level generations (step_level <= k - 2),
+    // returning a level k-1 node.
+    fn step(&mut self, id: NodeId, step_level: u8) -> NodeId {
+        let node = self.node(id);
+        if node.population == 0 {
+            return self.empty(node.level - 1);
+        }
+        if node.level == 2 {
+            return self.step_base(id);
+        }
+        if let Some(&result) = self.steps.get(&(id, step_level)) {
+            return result;
+        }
+
+        let (nw, ne, sw, se) = (self.node(node.nw), self.node(node.ne), self.node(node.sw), self.node(node.se));
+
+        // Nine overlapping sub-squares of the level k-1 grandchildren
+        let n00 = node.nw;
+        let n01 = self.join(nw.ne, ne.nw, nw.se, ne.sw);
+        let n02 = node.ne;
+        let n10 = self.join(nw.sw, nw.se, sw.nw, sw.ne);
+        let n11 = self.join(nw.se, ne.sw, sw.ne, se.nw);
+        let n12 = self.join(ne.sw, ne.se, se.nw, se.ne);
+        let n20 = node.sw;
+        let n21 = self.join(sw.ne, se.nw, sw.se, se.sw);
+        let n22 = node.se;
+
+        let full_speed = step_level == node.level - 2;
+        let mut first = [0; 9];
+        for (slot, sub) in first.iter_mut().zip([n00, n01, n02, n10, n11, n12, n20, n21, n22]) {
+            // At full speed both halves advance; otherwise only the second half does
+            *slot = if full_speed { self.step(sub, step_level - 1) } else { self.centre(sub) };
+        }
+        let [r00, r01, r02, r10, r11, r12, r20, r21, r22] = first;
+
+        let second_level = if full_speed { step_level - 1 } else { step_level };
+        let q_nw = self.join(r00, r01, r10, r11);
+        let q_ne = self.join(r01, r02, r11, r12);
+        let q_sw = self.join(r10, r11, r20, r21);
+        let q_se = self.join(r11, r12, r21, r22);
+
+        let s_nw = self.step(q_nw, second_level);
+        let s_ne = self.step(q_ne, second_level);
+        let s_sw = self.step(q_sw, second_level);
+        let s_se = self.step(q_se, second_level);
+
+        let result = self.join(s_nw, s_ne, s_sw, s_se);
+        self.steps.insert((id, step_level), result);
+        result
+    }
+
+    // One generation of a 4x4 node, returning its 2x2 centre
+    fn step_base(&mut self, id: NodeId) -> NodeId {
+        let mut next = [DEAD; 4];
+        for (i, (x, y)) in [(1, 1), (2, 1), (1, 2), (2, 2)].into_iter().enumerate() {
+            let mut neighbors = 0;
+            for dy in 0..3 {
+                for dx in 0..3 {
+                    if (dx, dy) != (1, 1) && self.cell(id, x + dx - 1, y + dy - 1) {
+                        neighbors += 1;
+                    }
+                }
+            }
+            if self.rule.next_state(self.cell(id, x, y), neighbors) {
+                next[i] = ALIVE;
+            }
+        }
+        self.join(next[0], next[1], next[2], next[3])
+    }
+
+    // Build a level-`level` node whose centre half starts with torus cell (0, 0)
+    fn tile(&mut self, universe: &Universe, level: u8) -> NodeId {
+        let (rows, cols) = (universe.rows as u64, universe.cols as u64);
+        let quarter = level - 2;
+        let x = (cols - pow2_mod(quarter, cols)) % cols;
+        let y = (rows - pow2_mod(quarter, rows)) % rows;
+
+        let mut cache = HashMap::new();
+        self.build(universe, level, x, y, &mut cache)
+    }
+
+    // (x, y) are the torus coordinates of the node's top-left cell; the tiling is periodic,
+    // so nodes at the same level and torus offset are identical and only built once.
+    fn build(
+        &mut self,
+        universe: &Universe,
+        level: u8,
+        x: u64,
+        y: u64,
+        cache: &mut HashMap<(u8, u64, u64), NodeId>,
+    ) -> NodeId {
+        if level == 0 {
+            let idx = (y * universe.cols as u64 + x) as usize;
+            return if universe.cells[idx] { ALIVE } else { DEAD };
+        }
+        if let Some(&id) = cache.get(&(level, x, y)) {
+            return id;
+        }
+
+        let (rows, cols) = (universe.rows as u64, universe.cols as u64);
+        let x2 = (x + pow2_mod(level - 1, cols)) % cols;
+        let y2 = (y + pow2_mod(level - 1, rows)) % rows;
+
+        let nw = self.build(universe, level - 1, x, y, cache);
+        let ne = self.build(universe, level - 1, x2, y, cache);
+        let sw = self.build(universe, level - 1, x, y2, cache);
+        let se = self.build(universe, level - 1, x2, y2, cache);
+
+        let id = self.join(nw, ne, sw, se);
+        cache.insert((level, x, y), id);
+        id
+    }
+
+    fn write_cells(&self, result: NodeId, universe: &mut Universe) {
+        universe.cells.iter_mut().for_each(|cell| *cell = false);
+        self.write_node(result, 0, 0, universe);
+    }
+
+    fn write_node(&self, id: NodeId, x: u64, y: u64, universe: &mut Universe) {
+        let node = self.node(id);
+        if node.population == 0 || x >= universe.cols as u64 || y >= universe.rows as u64 {
+            return;
+        }
+        if node.level == 0 {
+            let idx = (y * universe.cols as u64 + x) as usize;
+            universe.cells[idx] = true;
+            return;
+        }
+
+        let half = 1u64 << (node.level - 1);
+        self.write_node(node.nw, x, y, universe);
+        self.write_node(node.ne, x + half, y, universe);
+        self.write_node(node.sw, x, y + half, universe);
+        self.write_node(node.se, x + half, y + half, universe);
+    }
+}
+
+// 2^exp mod modulus, without overflowing for the very large levels used by long jumps
+fn pow2_mod(exp: u8, modulus: u64) -> u64 {
+    (0..exp).fold(1 % modulus, |acc, _| acc * 2 % modulus)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jump_matches_repeated_ticks() {
+        let dna = b"GATCCAGATCGATCCGATCGATCGGCATTACGCCGATAGCTTAGCGCATGCAAT";
+        for &(rows, cols) in &[(10, 10), (7, 12), (16, 5)] {
+            for generations in [1, 2, 3, 7, 64, 100] {
+                let mut jumped = Universe::new(rows, cols, dna);
+                let mut ticked = Universe::new(rows, cols, dna);
+
+                jumped.jump(generations);
+                for _ in 0..generations {
+                    ticked.tick();
+                }
+
+                assert_eq!(jumped.cells, ticked.cells, "{rows}x{cols} after {generations} generations");
+                assert_eq!(jumped.generation, ticked.generation);
+            }
+        }
+    }
+}
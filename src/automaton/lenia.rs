@@ -0,0 +1,452 @@
+#[cfg(feature = "gui")]
+use std::time::Instant;
+
+#[cfg(feature = "gui")]
+use wgpu::*;
+#[cfg(feature = "gui")]
+use wgpu::util::DeviceExt;
+
+#[cfg(feature = "gui")]
+use crate::gpu::Gpu;
+#[cfg(feature = "gui")]
+use crate::universe::Universe;
+
+// Lenia: a continuous cellular automaton. Every cell holds a state in 0..1; each step the
+// states are convolved with a ring-shaped kernel, the resulting neighborhood potential goes
+// through a bell-shaped growth function, and a small fraction `dt` of the growth is added.
+// Smooth, moving "creatures" emerge for a narrow band of growth parameters.
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Params {
+    // Kernel radius in cells
+    pub radius: u32,
+    // Potential with the highest growth, and the width of the growth bell
+    pub mu: f32,
+    pub sigma: f32,
+    // Time step: share of the growth added per generation
+    pub dt: f32,
+}
+
+impl Params {
+    // The classic Orbium parameters
+    pub const STANDARD: Self = Self { radius: 13, mu: 0.15, sigma: 0.015, dt: 0.1 };
+
+    // Parameters from the sequence's composition: GC content moves the growth peak (0.12 to
+    // 0.18), the purine share widens the bell (0.01 to 0.03), and the average homopolymer run
+    // length sets the radius (8 to 16 cells). Balanced composition peaks where STANDARD does.
+    pub fn from_sequence(dna: &[u8]) -> Self {
+        let bases = dna.iter().filter(|base| b"ACGTacgt".contains(base)).count().max(1) as f32;
+        let share = |set: &[u8]| dna.iter().filter(|base| set.contains(&base.to_ascii_uppercase())).count() as f32 / bases;
+        let runs = dna.chunk_by(|a, b| a.eq_ignore_ascii_case(b)).count().max(1);
+        let mean_run = dna.len() as f32 / runs as f32;
+        Self {
+            radius: (8.0 + (mean_run - 1.0) * 8.0).clamp(8.0, 16.0).round() as u32,
+            mu: 0.12 + 0.06 * share(b"GC"),
+            sigma: 0.01 + 0.02 * share(b"AG"),
+            dt: 0.1,
+        }
+    }
+}
+
+impl std::fmt::Display for Params {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "R {} mu {:.3} sigma {:.4} dt {}", self.radius, self.mu, self.sigma, self.dt)
+    }
+}
+
+// Where the kernel parameters come from
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum KernelSource {
+    Standard,
+    Sequence,
+}
+
+impl std::str::FromStr for KernelSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "standard" => Ok(KernelSource::Standard),
+            "sequence" => Ok(KernelSource::Sequence),
+            _ => Err(format!("Unknown Lenia kernel '{s}', expected 'standard' or 'sequence'")),
+        }
+    }
+}
+
+impl std::fmt::Display for KernelSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            KernelSource::Standard => write!(f, "standard"),
+            KernelSource::Sequence => write!(f, "sequence"),
+        }
+    }
+}
+
+// Kernel weights over the (2R + 1)^2 square, row-major and summing to 1: a smooth bump
+// peaking halfway out from the center, zero at the center and beyond the radius
+pub fn kernel(radius: u32) -> Vec<f32> {
+    let r = radius as i32;
+    let mut weights: Vec<f32> = (-r..=r)
+        .flat_map(|dy| (-r..=r).map(move |dx| ((dx * dx + dy * dy) as f32).sqrt() / radius as f32))
+        .map(|distance| if distance > 0.0 && distance < 1.0 { (4.0 - 1.0 / (distance * (1.0 - distance))).exp() } else { 0.0 })
+        .collect();
+    let sum: f32 = weights.iter().sum();
+    weights.iter_mut().for_each(|weight| *weight /= sum);
+    weights
+}
+
+// Growth for a neighborhood potential, from -1 far from `mu` to 1 at it
+pub fn growth(potential: f32, mu: f32, sigma: f32) -> f32 {
+    2.0 * (-(potential - mu).powi(2) / (2.0 * sigma * sigma)).exp() - 1.0
+}
+
+// States at or above this count as alive for the population, heat map and centroid
+#[cfg(feature = "gui")]
+const ALIVE: f32 = 0.5;
+
+pub struct Lenia {
+    pub states: Vec<f32>,
+    rows: u32,
+    cols: u32,
+    pub params: Params,
+    kernel: Vec<f32>,
+    // Compute pipeline stepping the states on the GPU; without it they are stepped here
+    #[cfg(feature = "gui")]
+    gpu: Option<LeniaGpu>,
+}
+
+impl Lenia {
+    // Each cell starts at the GC share of the 8 bases from its position, reading the sequence
+    // in row-major order; cells past the end of the sequence start empty
+    pub fn new(dna: &[u8], rows: u32, cols: u32, params: Params) -> Self {
+        let states = (0..(rows * cols) as usize)
+            .map(|position| match dna.get(position..(position + 8).min(dna.len())) {
+                Some(window) if !window.is_empty() => {
+                    window.iter().filter(|base| b"GCgc".contains(base)).count() as f32 / window.len() as f32
+                }
+                _ => 0.0,
+            })
+            .collect();
+        Self {
+            states,
+            rows,
+            cols,
+            params,
+            kernel: kernel(params.radius),
+            #[cfg(feature = "gui")]
+            gpu: None,
+        }
+    }
+
+    // Step on this device from now on, called again after the device is recreated. On error
+    // (e.g. a grid too large for one storage buffer) the states keep being stepped on the CPU.
+    #[cfg(feature = "gui")]
+    pub fn attach(&mut self, gpu: &Gpu) -> Result<(), String> {
+        self.gpu = None;
+        gpu.device.push_error_scope(ErrorFilter::Validation);
+        let compute = LeniaGpu::new(gpu, self);
+        match pollster::block_on(gpu.device.pop_error_scope()) {
+            Some(err) => Err(err.to_string()),
+            None => {
+                self.gpu = Some(compute);
+                Ok(())
+            }
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn on_gpu(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn step(&mut self, universe: &mut Universe, gpu: &Gpu) {
+        self.advance(universe, gpu, 1, None);
+    }
+
+    // Step up to `generations` at once (turbo), stopping early once `deadline` has passed,
+    // and return how many were stepped. On the GPU they are dispatched in submits of at most
+    // MAX_DISPATCHES, each waited for, and the states are read back only after the last.
+    #[cfg(feature = "gui")]
+    pub fn advance(&mut self, universe: &mut Universe, gpu: &Gpu, generations: u32, deadline: Option<Instant>) -> u32 {
+        // At least one generation, however late
+        let in_time = |stepped: u32| stepped == 0 || deadline.is_none_or(|deadline| Instant::now() < deadline);
+        let mut stepped = 0;
+        match self.gpu.as_mut() {
+            Some(compute) => {
+                while stepped < generations && in_time(stepped) {
+                    let batch = (generations - stepped).min(MAX_DISPATCHES);
+                    compute.dispatch(gpu, batch, stepped == 0);
+                    stepped += batch;
+                }
+                self.states = compute.read(gpu, self.states.len());
+            }
+            None => {
+                while stepped < generations && in_time(stepped) {
+                    self.step_cpu();
+                    stepped += 1;
+                }
+            }
+        }
+        for (alive, &state) in universe.cells.iter_mut().zip(&self.states) {
+            *alive = state >= ALIVE;
+        }
+        universe.generation += u64::from(stepped);
+        stepped
+    }
+
+    // Reference implementation of the compute shader
+    pub fn step_cpu(&mut self) {
+        let (rows, cols, r) = (self.rows as i64, self.cols as i64, self.params.radius as i64);
+        let size = 2 * r + 1;
+        let mut next = vec![0.0; self.states.len()];
+        for row in 0..rows {
+            for col in 0..cols {
+                let mut potential = 0.0;
+                for dy in -r..=r {
+                    let y = (row + dy).rem_euclid(rows);
+                    for dx in -r..=r {
+                        let weight = self.kernel[((dy + r) * size + dx + r) as usize];
+                        if weight > 0.0 {
+                            potential += weight * self.states[(y * cols + (col + dx).rem_euclid(cols)) as usize];
+                        }
+                    }
+                }
+                let idx = (row * cols + col) as usize;
+                let growth = growth(potential, self.params.mu, self.params.sigma);
+                next[idx] = (self.states[idx] + self.params.dt * growth).clamp(0.0, 1.0);
+            }
+        }
+        self.states = next;
+    }
+
+    pub fn mass(&self) -> f32 {
+        self.states.iter().sum()
+    }
+
+    pub fn colors(&self) -> Vec<[f32; 3]> {
+        self.states.iter().map(|&state| color(state)).collect()
+    }
+}
+
+// Near black through purple and teal to yellow
+const PALETTE: [[f32; 3]; 5] = [
+    [0.03, 0.02, 0.06],
+    [0.28, 0.1, 0.45],
+    [0.12, 0.5, 0.56],
+    [0.45, 0.8, 0.35],
+    [0.99, 0.91, 0.25],
+];
+
+pub fn color(state: f32) -> [f32; 3] {
+    let position = state.clamp(0.0, 1.0) * (PALETTE.len() - 1) as f32;
+    let i = (position as usize).min(PALETTE.len() - 2);
+    let t = position - i as f32;
+    let (from, to) = (PALETTE[i], PALETTE[i + 1]);
+    [0, 1, 2].map(|c| from[c] + (to[c] - from[c]) * t)
+}
+
+#[cfg(feature = "gui")]
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Uniforms {
+    rows: u32,
+    cols: u32,
+    radius: u32,
+    mu: f32,
+    sigma: f32,
+    dt: f32,
+    _padding: [u32; 2],
+}
+
+// Dispatches in one submit, so a long turbo batch doesn't hold the GPU long enough to trip
+// the driver's watchdog
+#[cfg(feature = "gui")]
+const MAX_DISPATCHES: u32 = 256;
+
+// Cells per side of a compute workgroup, matching lenia.wgsl
+#[cfg(feature = "gui")]
+const WORKGROUP: u32 = 8;
+
+// The states live in two storage buffers, read from one and written to the other in turn;
+// after each step the new states are copied to a mappable buffer and read back.
+#[cfg(feature = "gui")]
+struct LeniaGpu {
+    pipeline: ComputePipeline,
+    bind_groups: [BindGroup; 2],
+    states: [Buffer; 2],
+    readback: Buffer,
+    // Buffer holding the latest states
+    current: usize,
+    rows: u32,
+    cols: u32,
+}
+
+#[cfg(feature = "gui")]
+impl LeniaGpu {
+    fn new(gpu: &Gpu, lenia: &Lenia) -> Self {
+        let device = &gpu.device;
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Lenia Shader"),
+            source: ShaderSource::Wgsl(include_str!("lenia.wgsl").into()),
+        });
+        let pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: Some("Lenia Pipeline"),
+            layout: None,
+            module: &shader,
+            entry_point: Some("step"),
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        let params = lenia.params;
+        let uniforms = Uniforms { rows: lenia.rows, cols: lenia.cols, radius: params.radius, mu: params.mu, sigma: params.sigma, dt: params.dt, _padding: [0; 2] };
+        let uniforms = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Lenia Uniforms"),
+            contents: bytemuck::bytes_of(&uniforms),
+            usage: BufferUsages::UNIFORM,
+        });
+        let kernel = device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Lenia Kernel"),
+            contents: bytemuck::cast_slice(&lenia.kernel),
+            usage: BufferUsages::STORAGE,
+        });
+        let states = [0, 1].map(|_| {
+            device.create_buffer_init(&util::BufferInitDescriptor {
+                label: Some("Lenia States"),
+                contents: bytemuck::cast_slice(&lenia.states),
+                usage: BufferUsages::STORAGE | BufferUsages::COPY_SRC,
+            })
+        });
+        let readback = device.create_buffer(&BufferDescriptor {
+            label: Some("Lenia Readback"),
+            size: (lenia.states.len() * size_of::<f32>()) as BufferAddress,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let layout = pipeline.get_bind_group_layout(0);
+        let bind_groups = [0, 1].map(|from| {
+            device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Lenia Bind Group"),
+                layout: &layout,
+                entries: &[
+                    BindGroupEntry { binding: 0, resource: uniforms.as_entire_binding() },
+                    BindGroupEntry { binding: 1, resource: kernel.as_entire_binding() },
+                    BindGroupEntry { binding: 2, resource: states[from].as_entire_binding() },
+                    BindGroupEntry { binding: 3, resource: states[1 - from].as_entire_binding() },
+                ],
+            })
+        });
+
+        Self { pipeline, bind_groups, states, readback, current: 0, rows: lenia.rows, cols: lenia.cols }
+    }
+
+    // One dispatch per generation, each reading the buffer the one before it wrote, submitted
+    // and waited for. Only a frame's first submit is timed by the profiler.
+    fn dispatch(&mut self, gpu: &Gpu, generations: u32, timed: bool) {
+        let mut encoder = gpu.device.create_command_encoder(&CommandEncoderDescriptor { label: Some("Lenia Encoder") });
+        {
+            let mut pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+                label: Some("Lenia Pass"),
+                timestamp_writes: gpu.profiler.as_ref().filter(|_| timed).and_then(|profiler| profiler.compute_writes("Lenia")),
+            });
+            pass.set_pipeline(&self.pipeline);
+            for _ in 0..generations {
+                pass.set_bind_group(0, &self.bind_groups[self.current], &[]);
+                pass.dispatch_workgroups(self.cols.div_ceil(WORKGROUP), self.rows.div_ceil(WORKGROUP), 1);
+                self.current = 1 - self.current;
+            }
+        }
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        gpu.device.poll(Maintain::Wait);
+    }
+
+    // The latest states
+    fn read(&mut self, gpu: &Gpu, cells: usize) -> Vec<f32> {
+        let mut encoder = gpu.device.create_command_encoder(&CommandEncoderDescriptor { label: Some("Lenia Readback Encoder") });
+        encoder.copy_buffer_to_buffer(&self.states[self.current], 0, &self.readback, 0, self.readback.size());
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = self.readback.slice(..);
+        slice.map_async(MapMode::Read, |result| {
+            if let Err(err) = result {
+                tracing::warn!("Failed to read back Lenia states: {err}");
+            }
+        });
+        gpu.device.poll(Maintain::Wait);
+        let mut states: Vec<f32> = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+        self.readback.unmap();
+        states.resize(cells, 0.0);
+        states
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernel_and_growth() {
+        let weights = kernel(13);
+        assert_eq!(weights.len(), 27 * 27);
+        assert!((weights.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        // Nothing at the center or the corners; the ring peaks halfway out
+        assert_eq!(weights[13 * 27 + 13], 0.0);
+        assert_eq!(weights[0], 0.0);
+        assert!(weights[13 * 27 + 13 + 6] > weights[13 * 27 + 13 + 2]);
+
+        assert_eq!(growth(0.15, 0.15, 0.015), 1.0);
+        assert!(growth(0.0, 0.15, 0.015) < -0.99);
+    }
+
+    #[test]
+    fn empty_grid_stays_empty_and_full_grid_decays() {
+        let mut lenia = Lenia::new(b"", 20, 20, Params::STANDARD);
+        lenia.step_cpu();
+        assert_eq!(lenia.mass(), 0.0);
+
+        // A potential of 1 is far above mu, so everything shrinks by dt
+        lenia.states.iter_mut().for_each(|state| *state = 1.0);
+        lenia.step_cpu();
+        assert!(lenia.states.iter().all(|&state| (state - 0.9).abs() < 1e-5));
+    }
+
+    #[test]
+    fn parameters_follow_the_sequence() {
+        let params = Params::from_sequence(b"ACGTACGTACGT");
+        assert_eq!(params.radius, 8);
+        assert!((params.mu - 0.15).abs() < 1e-6 && (params.sigma - 0.02).abs() < 1e-6);
+        assert_eq!(Params::from_sequence(b"GGGGCCCC").radius, 16);
+        assert_eq!("sequence".parse(), Ok(KernelSource::Sequence));
+    }
+
+    #[test]
+    #[cfg(feature = "gui")]
+    fn chained_dispatches_match_the_cpu() {
+        let Ok(gpu) = Gpu::headless(&Instance::default()).inspect_err(|err| eprintln!("Skipping Lenia GPU test: {err}")) else {
+            return;
+        };
+        let dna = b"GATTACAGGCCGCGATATCGCGGCCATTAGCA".repeat(40);
+        let (mut cpu, mut chained) = (Lenia::new(&dna, 16, 16, Params::STANDARD), Lenia::new(&dna, 16, 16, Params::STANDARD));
+        chained.attach(&gpu).unwrap();
+        let mut universe = Universe::new(16, 16, &dna);
+        // More generations than fit one submit
+        let generations = MAX_DISPATCHES + 3;
+        assert_eq!(chained.advance(&mut universe, &gpu, generations, None), generations);
+        for _ in 0..generations {
+            cpu.step_cpu();
+        }
+        assert_eq!(universe.generation, u64::from(generations));
+        assert!(cpu.states.iter().zip(&chained.states).all(|(a, b)| (a - b).abs() < 1e-4));
+        assert_eq!(universe.cells, cpu.states.iter().map(|&state| state >= ALIVE).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn shader_validates() {
+        let module = naga::front::wgsl::parse_str(include_str!("lenia.wgsl")).unwrap();
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+            .validate(&module)
+            .unwrap();
+    }
+}
@@ -0,0 +1,48 @@
+// One Lenia step per invocation and cell: convolve the states with the ring kernel on the
+// torus, then add dt times the growth of the resulting potential. Mirrors Lenia::step_cpu.
+
+struct Uniforms {
+    rows: u32,
+    cols: u32,
+    radius: u32,
+    mu: f32,
+    sigma: f32,
+    dt: f32,
+}
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+// (2R + 1)^2 weights, row-major
+@group(0) @binding(1) var<storage, read> kernel: array<f32>;
+@group(0) @binding(2) var<storage, read> source: array<f32>;
+@group(0) @binding(3) var<storage, read_write> destination: array<f32>;
+
+@compute @workgroup_size(8, 8)
+fn step(@builtin(global_invocation_id) id: vec3<u32>) {
+    let col = id.x;
+    let row = id.y;
+    if (col >= uniforms.cols || row >= uniforms.rows) {
+        return;
+    }
+
+    let r = i32(uniforms.radius);
+    let size = 2 * r + 1;
+    let rows = i32(uniforms.rows);
+    let cols = i32(uniforms.cols);
+    var potential = 0.0;
+    for (var dy = -r; dy <= r; dy++) {
+        // Adding a multiple of the size keeps the operand of % non-negative
+        let y = (i32(row) + dy + rows * (r / rows + 1)) % rows;
+        for (var dx = -r; dx <= r; dx++) {
+            let weight = kernel[(dy + r) * size + dx + r];
+            if (weight > 0.0) {
+                let x = (i32(col) + dx + cols * (r / cols + 1)) % cols;
+                potential += weight * source[y * cols + x];
+            }
+        }
+    }
+
+    let idx = row * uniforms.cols + col;
+    let distance = potential - uniforms.mu;
+    let growth = 2.0 * exp(-distance * distance / (2.0 * uniforms.sigma * uniforms.sigma)) - 1.0;
+    destination[idx] = clamp(source[idx] + uniforms.dt * growth, 0.0, 1.0);
+}
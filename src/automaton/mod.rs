@@ -0,0 +1,5 @@
+pub mod ant;
+pub mod cyclic;
+pub mod hashlife;
+pub mod lenia;
+pub mod protein;
@@ -0,0 +1,184 @@
+use crate::seq_analysis::codon_usage::translate;
+use crate::universe::Universe;
+
+// Protein automaton: every cell holds an amino acid residue's property class, or nothing.
+// Residues come from a protein sequence, or from translating a DNA one in the first frame.
+// Hydrophobic clustering drives survival, as in a folding core:
+//   hydrophobic  survives with 2 to 5 hydrophobic neighbors; exposed or crowded, it leaves
+//   charged      survives with 2 or 3 neighbors, or 1 to 4 with an opposite charge among
+//                them (a salt bridge)
+//   polar and    survive with 2 or 3 neighbors
+//   special
+//   empty        gains a residue with exactly 3 neighbors, of their most common class
+// The universe's live cells are the occupied ones, so population, heat map and centroid
+// follow the residues.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PropertyClass {
+    Hydrophobic,
+    Polar,
+    Positive,
+    Negative,
+    // Glycine, proline and cysteine, which bend or bond the backbone
+    Special,
+}
+
+impl PropertyClass {
+    pub const ALL: [PropertyClass; 5] = [PropertyClass::Hydrophobic, PropertyClass::Polar, PropertyClass::Positive, PropertyClass::Negative, PropertyClass::Special];
+
+    // Class of a one-letter amino acid code; None for stops and ambiguous codes
+    pub fn of(residue: u8) -> Option<Self> {
+        match residue.to_ascii_uppercase() {
+            b'A' | b'V' | b'I' | b'L' | b'M' | b'F' | b'W' => Some(PropertyClass::Hydrophobic),
+            b'S' | b'T' | b'N' | b'Q' | b'Y' => Some(PropertyClass::Polar),
+            b'K' | b'R' | b'H' => Some(PropertyClass::Positive),
+            b'D' | b'E' => Some(PropertyClass::Negative),
+            b'G' | b'P' | b'C' => Some(PropertyClass::Special),
+            _ => None,
+        }
+    }
+
+    // Hydrophobic amber, polar green, positive blue, negative red, special violet
+    pub fn color(self) -> [f32; 3] {
+        match self {
+            PropertyClass::Hydrophobic => [0.95, 0.75, 0.2],
+            PropertyClass::Polar => [0.3, 0.8, 0.45],
+            PropertyClass::Positive => [0.3, 0.5, 1.0],
+            PropertyClass::Negative => [1.0, 0.35, 0.35],
+            PropertyClass::Special => [0.75, 0.45, 0.9],
+        }
+    }
+}
+
+impl std::fmt::Display for PropertyClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PropertyClass::Hydrophobic => write!(f, "hydrophobic"),
+            PropertyClass::Polar => write!(f, "polar"),
+            PropertyClass::Positive => write!(f, "positive"),
+            PropertyClass::Negative => write!(f, "negative"),
+            PropertyClass::Special => write!(f, "special"),
+        }
+    }
+}
+
+// Whether a sequence is protein rather than nucleotides: it has letters no nucleotide
+// IUPAC code uses
+pub fn is_protein(seq: &[u8]) -> bool {
+    seq.iter().any(|&residue| b"EFIJLOPQZ*".contains(&residue.to_ascii_uppercase()))
+}
+
+// The sequence as amino acids: protein as is, nucleotides translated codon by codon
+pub fn residues(seq: &[u8]) -> Vec<u8> {
+    if is_protein(seq) {
+        return seq.to_vec();
+    }
+    seq.chunks_exact(3).map(|codon| translate(codon).map_or(b'X', |amino_acid| amino_acid as u8)).collect()
+}
+
+// Empty cells are drawn like dead ones
+const EMPTY_COLOR: [f32; 3] = [0.1, 0.1, 0.1];
+
+pub struct Protein {
+    // One class per cell, None when empty
+    pub states: Vec<Option<PropertyClass>>,
+    rows: u32,
+    cols: u32,
+}
+
+impl Protein {
+    // Residues in row-major order from the start of the sequence; cells past its end are empty
+    pub fn new(seq: &[u8], rows: u32, cols: u32) -> Self {
+        let residues = residues(seq);
+        let states = (0..(rows * cols) as usize).map(|idx| residues.get(idx).copied().and_then(PropertyClass::of)).collect();
+        Self { states, rows, cols }
+    }
+
+    pub fn step(&mut self, universe: &mut Universe) {
+        let (rows, cols) = (self.rows as usize, self.cols as usize);
+        let mut next = self.states.clone();
+        for row in 0..rows {
+            for col in 0..cols {
+                let idx = row * cols + col;
+                let mut counts = [0u8; 5];
+                for (dr, dc) in [(rows - 1, cols - 1), (rows - 1, 0), (rows - 1, 1), (0, cols - 1), (0, 1), (1, cols - 1), (1, 0), (1, 1)] {
+                    if let Some(class) = self.states[(row + dr) % rows * cols + (col + dc) % cols] {
+                        counts[class as usize] += 1;
+                    }
+                }
+                let occupied: u8 = counts.iter().sum();
+                let count = |class: PropertyClass| counts[class as usize];
+
+                next[idx] = match self.states[idx] {
+                    Some(PropertyClass::Hydrophobic) => (2..=5).contains(&count(PropertyClass::Hydrophobic)).then_some(PropertyClass::Hydrophobic),
+                    Some(class @ (PropertyClass::Positive | PropertyClass::Negative)) => {
+                        let opposite = if class == PropertyClass::Positive { PropertyClass::Negative } else { PropertyClass::Positive };
+                        let bridged = count(opposite) > 0 && occupied <= 4;
+                        ((2..=3).contains(&occupied) || bridged).then_some(class)
+                    }
+                    Some(class) => (2..=3).contains(&occupied).then_some(class),
+                    // Ties go to the class listed first
+                    None if occupied == 3 => PropertyClass::ALL.into_iter().rev().max_by_key(|&class| count(class)),
+                    None => None,
+                };
+            }
+        }
+
+        for (alive, state) in universe.cells.iter_mut().zip(&next) {
+            *alive = state.is_some();
+        }
+        self.states = next;
+        universe.generation += 1;
+    }
+
+    // Cells of each class, in PropertyClass::ALL order
+    pub fn counts(&self) -> [usize; 5] {
+        let mut counts = [0; 5];
+        for class in self.states.iter().flatten() {
+            counts[*class as usize] += 1;
+        }
+        counts
+    }
+
+    pub fn colors(&self) -> Vec<[f32; 3]> {
+        self.states.iter().map(|state| state.map_or(EMPTY_COLOR, PropertyClass::color)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_and_translates_sequences() {
+        assert!(is_protein(b"MKTAYIAKQR"));
+        assert!(!is_protein(b"ACGTNRYKM"));
+        assert_eq!(residues(b"ATGAAATAAGC"), b"MK*");
+        assert_eq!(residues(b"MEEP"), b"MEEP");
+        assert_eq!(PropertyClass::of(b'w'), Some(PropertyClass::Hydrophobic));
+        assert_eq!(PropertyClass::of(b'*'), None);
+    }
+
+    #[test]
+    fn hydrophobic_cores_hold_and_exposed_residues_leave() {
+        // A 2x2 hydrophobic block: every residue has three hydrophobic neighbors
+        let block = b"LL...LL..........................";
+        let mut protein = Protein::new(block, 5, 5);
+        let mut universe = Universe::new(5, 5, b"");
+        assert_eq!(protein.counts(), [4, 0, 0, 0, 0]);
+        protein.step(&mut universe);
+        assert_eq!(protein.counts(), [4, 0, 0, 0, 0]);
+        assert_eq!(universe.cells.iter().filter(|&&alive| alive).count(), 4);
+
+        // A lone hydrophobic residue is exposed; a charged pair holds by its salt bridge
+        let mut protein = Protein::new(b"KE................L", 5, 5);
+        protein.step(&mut universe);
+        assert_eq!(protein.counts(), [0, 0, 1, 1, 0]);
+        assert_eq!(universe.generation, 2);
+
+        // Three polar neighbors (glutamine) give an empty cell a polar residue
+        let mut protein = Protein::new(b"QQ...Q...", 3, 3);
+        protein.step(&mut Universe::new(3, 3, b""));
+        assert_eq!(protein.states[4], Some(PropertyClass::Polar));
+    }
+}
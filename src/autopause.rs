@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+
+use crate::fnv::cells_hash;
+
+// Pausing by itself once there is nothing left to watch (`auto_pause` in the config): the
+// cells died out, stopped changing or fell into a cycle of at most `max_period` generations.
+// Generations are compared by the hash of their cells, as in the period detection of batch
+// runs, over a window of the last `max_period` of them.
+
+// Long enough for the common oscillators, up to the pentadecathlon
+pub const DEFAULT_MAX_PERIOD: usize = 15;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AutoPause {
+    pub enabled: bool,
+    // Longest cycle that counts as settled
+    pub max_period: usize,
+}
+
+impl Default for AutoPause {
+    fn default() -> Self {
+        Self { enabled: false, max_period: DEFAULT_MAX_PERIOD }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Ending {
+    Extinction,
+    StillLife,
+    // A cycle of this many generations
+    Cycle(usize),
+}
+
+impl std::fmt::Display for Ending {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Ending::Extinction => write!(f, "All cells died"),
+            Ending::StillLife => write!(f, "The cells stopped changing"),
+            Ending::Cycle(period) => write!(f, "The cells settled into a period {period} cycle"),
+        }
+    }
+}
+
+// Hashes of the latest generations, and whether the run they show has already ended. An
+// ending is reported once: resuming a still life or a cycle keeps revisiting states in the
+// window, and only a state outside it (after an edit or a rule change) starts a new run.
+#[derive(Default)]
+pub struct EndDetector {
+    recent: VecDeque<u64>,
+    ended: bool,
+}
+
+impl EndDetector {
+    // How the run ended, if the generation just stepped to ends it
+    pub fn observe(&mut self, settings: &AutoPause, cells: &[bool]) -> Option<Ending> {
+        if !settings.enabled {
+            return None;
+        }
+        let hash = cells_hash(cells);
+        let period = self.recent.iter().rev().position(|&seen| seen == hash).map(|back| back + 1);
+        self.recent.push_back(hash);
+        while self.recent.len() > settings.max_period {
+            self.recent.pop_front();
+        }
+
+        let ending = match period {
+            _ if !cells.contains(&true) => Some(Ending::Extinction),
+            Some(1) => Some(Ending::StillLife),
+            Some(period) => Some(Ending::Cycle(period)),
+            None => None,
+        };
+        let already_ended = self.ended;
+        self.ended = ending.is_some() || (already_ended && period.is_some());
+        ending.filter(|_| !already_ended)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_each_ending_once() {
+        let settings = AutoPause { enabled: true, max_period: 4 };
+        let mut detector = EndDetector::default();
+        let (blinker, rotated) = ([false, true, true], [true, false, true]);
+        assert_eq!(detector.observe(&settings, &blinker), None);
+        assert_eq!(detector.observe(&settings, &rotated), None);
+        assert_eq!(detector.observe(&settings, &blinker), Some(Ending::Cycle(2)));
+        // Resumed, the cycle goes on without pausing again
+        assert_eq!(detector.observe(&settings, &rotated), None);
+        assert_eq!(detector.observe(&settings, &blinker), None);
+
+        // An edit starts a new run
+        assert_eq!(detector.observe(&settings, &[true, true, true]), None);
+        assert_eq!(detector.observe(&settings, &[true, true, true]), Some(Ending::StillLife));
+        assert_eq!(detector.observe(&settings, &[true, false, false]), None);
+        assert_eq!(detector.observe(&settings, &[false; 3]), Some(Ending::Extinction));
+        assert_eq!(detector.observe(&settings, &[false; 3]), None);
+
+        // A cycle longer than the window isn't one
+        let mut detector = EndDetector::default();
+        let short = AutoPause { enabled: true, max_period: 1 };
+        assert_eq!(detector.observe(&short, &blinker), None);
+        assert_eq!(detector.observe(&short, &rotated), None);
+        assert_eq!(detector.observe(&short, &blinker), None);
+        assert_eq!(detector.observe(&AutoPause::default(), &[false]), None);
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use bio::seq_analysis::gc::gc_content;
+
+use crate::fnv::cells_hash;
+use crate::rule::Rule;
+use crate::universe::Universe;
+
+// Headless runs of many sequences, one universe per FASTA record, summarized for screening
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BatchSettings {
+    pub generations: u64,
+    pub rows: u32,
+    pub cols: u32,
+    pub rule: Rule,
+}
+
+impl Default for BatchSettings {
+    fn default() -> Self {
+        Self { generations: 500, rows: 64, cols: 64, rule: Rule::default() }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordSummary {
+    pub id: String,
+    pub length: usize,
+    pub gc: f32,
+    // First generation of the cycle the universe settled into, if it did within the run
+    pub stabilized_at: Option<u64>,
+    // Length of that cycle; 1 for still lifes and extinction
+    pub period: Option<u64>,
+    pub max_population: usize,
+    pub final_population: usize,
+}
+
+const COLUMNS: &str = "id\tlength\tgc_percent\tstabilized_at\tperiod\tmax_population\tfinal_population";
+
+impl RecordSummary {
+    // The record's TSV row, without a newline
+    pub fn to_row(&self) -> String {
+        let or_na = |value: Option<u64>| value.map_or("NA".to_string(), |value| value.to_string());
+        format!(
+            "{}\t{}\t{:.2}\t{}\t{}\t{}\t{}",
+            self.id, self.length, self.gc * 100.0, or_na(self.stabilized_at), or_na(self.period),
+            self.max_population, self.final_population
+        )
+    }
+
+    pub fn from_row(row: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid summary row '{row}'");
+        let fields: Vec<&str> = row.split('\t').collect();
+        let [id, length, gc, stabilized_at, period, max_population, final_population] = fields[..] else {
+            return Err(invalid());
+        };
+        let or_na = |field: &str| match field {
+            "NA" => Ok(None),
+            _ => field.parse().map(Some).map_err(|_| invalid()),
+        };
+        Ok(Self {
+            id: id.to_string(),
+            length: length.parse().map_err(|_| invalid())?,
+            gc: gc.parse::<f32>().map_err(|_| invalid())? / 100.0,
+            stabilized_at: or_na(stabilized_at)?,
+            period: or_na(period)?,
+            max_population: max_population.parse().map_err(|_| invalid())?,
+            final_population: final_population.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+// How a run ended: the cycle it settled into, if it did, and its populations
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outcome {
+    // First generation of the cycle and its length; 1 for still lifes and extinction
+    pub stabilized_at: Option<u64>,
+    pub period: Option<u64>,
+    pub max_population: usize,
+    pub final_population: usize,
+}
+
+// Step `universe` until it repeats an earlier state or reaches `generations`, calling
+// `observe` with every generation from the first. States are compared by hash, so a repeat
+// is found in one pass without keeping every generation's cells. None when `cancel` is set
+// before the end.
+pub fn run_universe(universe: &mut Universe, generations: u64, cancel: &AtomicBool, mut observe: impl FnMut(&Universe)) -> Option<Outcome> {
+    let mut seen = HashMap::new();
+    let mut max_population = 0;
+    let mut cycle = None;
+    loop {
+        observe(universe);
+        max_population = max_population.max(population(universe));
+        let hash = cells_hash(&universe.cells);
+        if let Some(&first) = seen.get(&hash) {
+            cycle = Some((first, universe.generation - first));
+            break;
+        }
+        seen.insert(hash, universe.generation);
+        if universe.generation >= generations {
+            break;
+        }
+        if cancel.load(Ordering::Relaxed) {
+            return None;
+        }
+        universe.tick();
+    }
+    Some(Outcome {
+        stabilized_at: cycle.map(|(first, _)| first),
+        period: cycle.map(|(_, period)| period),
+        max_population,
+        final_population: population(universe),
+    })
+}
+
+// Seed a universe from `dna` and run it. None when `cancel` is set before the end.
+pub fn run_record(id: &str, dna: &[u8], settings: &BatchSettings, cancel: &AtomicBool) -> Option<RecordSummary> {
+    let mut universe = Universe::new(settings.rows, settings.cols, dna);
+    universe.rule = settings.rule;
+    let outcome = run_universe(&mut universe, settings.generations, cancel, |_| {})?;
+    Some(RecordSummary {
+        id: id.to_string(),
+        length: dna.len(),
+        gc: gc_content(dna),
+        stabilized_at: outcome.stabilized_at,
+        period: outcome.period,
+        max_population: outcome.max_population,
+        final_population: outcome.final_population,
+    })
+}
+
+// Run every record on up to `threads` threads, calling `finished` as each one completes.
+// Setting `cancel` abandons the records still running. The completed summaries come back
+// in the records' order.
+pub fn run(
+    records: &[(String, Vec<u8>)],
+    settings: &BatchSettings,
+    threads: usize,
+    cancel: &AtomicBool,
+    finished: impl Fn(&RecordSummary) + Sync,
+) -> Vec<RecordSummary> {
+    let next = AtomicUsize::new(0);
+    let summaries = Mutex::new(vec![None; records.len()]);
+    std::thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, records.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((id, dna)) = records.get(index) else { break };
+                    let _span = tracing::debug_span!("record", id = %id).entered();
+                    let Some(summary) = run_record(id, dna, settings, cancel) else { break };
+                    tracing::debug!("Finished at generation {} with period {:?}", summary.stabilized_at.unwrap_or(settings.generations), summary.period);
+                    finished(&summary);
+                    summaries.lock().unwrap()[index] = Some(summary);
+                }
+            });
+        }
+    });
+    summaries.into_inner().unwrap().into_iter().flatten().collect()
+}
+
+// The first id shared by two records. Rows and checkpoints are matched to records by id, so
+// a batch needs them to be unique.
+pub fn duplicate_id(records: &[(String, Vec<u8>)]) -> Option<&str> {
+    let mut seen = HashSet::new();
+    records.iter().map(|(id, _)| id.as_str()).find(|id| !seen.insert(*id))
+}
+
+// One row per record under a header, NA where a record never stabilized
+pub fn to_tsv(summaries: &[RecordSummary]) -> String {
+    let mut tsv = format!("{COLUMNS}\n");
+    for summary in summaries {
+        tsv.push_str(&summary.to_row());
+        tsv.push('\n');
+    }
+    tsv
+}
+
+// Rows of the records finished so far, appended as they complete so an interrupted (or
+// killed) batch can be resumed. The first line records the settings, which a resumed run
+// must share.
+pub struct Checkpoint {
+    pub path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl Checkpoint {
+    fn settings_line(settings: &BatchSettings) -> String {
+        format!("# generations={} grid={}x{} rule={}", settings.generations, settings.rows, settings.cols, settings.rule)
+    }
+
+    // The summaries in an existing checkpoint for the same settings
+    pub fn load(path: &Path, settings: &BatchSettings) -> Result<Vec<RecordSummary>, String> {
+        let contents = std::fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        let mut lines = contents.lines();
+        let expected = Self::settings_line(settings);
+        match lines.next() {
+            Some(line) if line == expected => {}
+            Some(line) if line.starts_with('#') => {
+                return Err(format!("{} was written with other settings ({}), expected {}", path.display(), &line[2..], &expected[2..]));
+            }
+            _ => return Err(format!("{} is not a batch checkpoint", path.display())),
+        }
+        lines.filter(|line| !line.is_empty() && *line != COLUMNS).map(RecordSummary::from_row).collect()
+    }
+
+    // Start a checkpoint holding `done`, replacing any file at `path`
+    pub fn create(path: &Path, settings: &BatchSettings, done: &[RecordSummary]) -> Result<Self, String> {
+        let contents = format!("{}\n{}", Self::settings_line(settings), to_tsv(done));
+        std::fs::write(path, contents).map_err(|err| format!("Failed to write {}: {err}", path.display()))?;
+        let file = OpenOptions::new().append(true).open(path).map_err(|err| format!("Failed to open {}: {err}", path.display()))?;
+        Ok(Self { path: path.to_path_buf(), file: Mutex::new(file) })
+    }
+
+    pub fn append(&self, summary: &RecordSummary) -> Result<(), String> {
+        writeln!(self.file.lock().unwrap(), "{}", summary.to_row()).map_err(|err| format!("Failed to write {}: {err}", self.path.display()))
+    }
+
+    // Delete the checkpoint once the batch is complete
+    pub fn remove(self) -> Result<(), String> {
+        drop(self.file);
+        std::fs::remove_file(&self.path).map_err(|err| format!("Failed to remove {}: {err}", self.path.display()))
+    }
+}
+
+pub fn population(universe: &Universe) -> usize {
+    universe.cells.iter().filter(|&&alive| alive).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::tests::BLINKER;
+
+    #[test]
+    fn finds_the_period_a_record_settles_into() {
+        let settings = BatchSettings { generations: 50, rows: 5, cols: 5, ..Default::default() };
+        let cancel = AtomicBool::new(false);
+        let summary = run_record("blinker", BLINKER, &settings, &cancel).unwrap();
+        assert_eq!((summary.stabilized_at, summary.period), (Some(0), Some(2)));
+        assert_eq!(summary.max_population, 3);
+        assert_eq!(summary.gc, 0.12);
+
+        let records = vec![("empty".to_string(), b"AAAA".to_vec()), ("blinker".to_string(), BLINKER.to_vec())];
+        let finished = AtomicUsize::new(0);
+        let summaries = run(&records, &settings, 4, &cancel, |_| {
+            finished.fetch_add(1, Ordering::Relaxed);
+        });
+        assert_eq!(finished.into_inner(), 2);
+        assert_eq!(summaries.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), ["empty", "blinker"]);
+        assert_eq!(summaries[0].period, Some(1));
+        assert_eq!(
+            to_tsv(&summaries).lines().nth(2),
+            Some("blinker\t25\t12.00\t0\t2\t3\t3")
+        );
+
+        assert_eq!(duplicate_id(&records), None);
+        let twice = [records.clone(), records.clone()].concat();
+        assert_eq!(duplicate_id(&twice), Some("empty"));
+
+        cancel.store(true, Ordering::Relaxed);
+        assert!(run(&records, &settings, 1, &cancel, |_| {}).is_empty());
+    }
+
+    #[test]
+    fn resumes_from_a_checkpoint() {
+        let settings = BatchSettings::default();
+        let path = std::env::temp_dir().join(format!("bio-rust-checkpoint-{}.tsv", std::process::id()));
+        let done = RecordSummary {
+            id: "chr1".into(), length: 100, gc: 0.4, stabilized_at: None, period: None, max_population: 50, final_population: 20,
+        };
+        let checkpoint = Checkpoint::create(&path, &settings, std::slice::from_ref(&done)).unwrap();
+        let next = RecordSummary { id: "chr2".into(), stabilized_at: Some(12), period: Some(1), ..done.clone() };
+        checkpoint.append(&next).unwrap();
+
+        assert_eq!(Checkpoint::load(&path, &settings).unwrap(), [done, next]);
+        let other = BatchSettings { generations: 10, ..settings };
+        assert!(Checkpoint::load(&path, &other).unwrap_err().contains("generations=10"));
+        checkpoint.remove().unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,189 @@
+use crate::rule::Rule;
+
+// Bit-packed copy of the universe: one bit per cell, each row padded to whole u64 words.
+// Stepping works on 64 cells at once using shifted copies of the neighbouring rows
+// and a bit-sliced adder for the neighbour counts.
+pub struct BitGrid {
+    pub rows: u32,
+    pub cols: u32,
+    stride: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn from_cells(rows: u32, cols: u32, cells: &[bool]) -> Self {
+        let stride = (cols as usize).div_ceil(64);
+        let mut words = vec![0u64; stride * rows as usize];
+
+        for (row, cells) in cells.chunks_exact(cols as usize).enumerate() {
+            for (word, chunk) in words[row * stride..].iter_mut().zip(cells.chunks(64)) {
+                *word = chunk.iter().rev().fold(0, |acc, &alive| (acc << 1) | alive as u64);
+            }
+        }
+
+        Self { rows, cols, stride, words }
+    }
+
+    // A grid of the cells `alive(idx)` says are set, idx row-major
+    pub fn from_fn(rows: u32, cols: u32, alive: impl Fn(usize) -> bool) -> Self {
+        let stride = (cols as usize).div_ceil(64);
+        let mut words = vec![0u64; stride * rows as usize];
+        for row in 0..rows as usize {
+            for col in 0..cols as usize {
+                words[row * stride + col / 64] |= (alive(row * cols as usize + col) as u64) << (col % 64);
+            }
+        }
+        Self { rows, cols, stride, words }
+    }
+
+    pub fn write_cells(&self, cells: &mut [bool]) {
+        for (row, cells) in cells.chunks_exact_mut(self.cols as usize).enumerate() {
+            for (&word, chunk) in self.row(row).iter().zip(cells.chunks_mut(64)) {
+                for (bit, cell) in chunk.iter_mut().enumerate() {
+                    *cell = (word >> bit) & 1 == 1;
+                }
+            }
+        }
+    }
+
+    // Take `other`'s cells where `mask` is set
+    pub fn select(&mut self, other: &BitGrid, mask: &BitGrid) {
+        for ((word, &other), &mask) in self.words.iter_mut().zip(&other.words).zip(&mask.words) {
+            *word = (*word & !mask) | (other & mask);
+        }
+    }
+
+    // Undo the births of this grid, stepped from `previous`, outside the `fertile` cells
+    pub fn limit_births(&mut self, previous: &BitGrid, fertile: &BitGrid) {
+        for ((word, &previous), &fertile) in self.words.iter_mut().zip(&previous.words).zip(&fertile.words) {
+            *word &= previous | fertile;
+        }
+    }
+
+    // Indices of the cells that differ from `other`'s, row-major, found a word at a time
+    pub fn changed(&self, other: &BitGrid) -> Vec<usize> {
+        let mut changed = Vec::new();
+        for (i, (&word, &other)) in self.words.iter().zip(&other.words).enumerate() {
+            let mut diff = word ^ other;
+            let start = i / self.stride * self.cols as usize + i % self.stride * 64;
+            while diff != 0 {
+                changed.push(start + diff.trailing_zeros() as usize);
+                diff &= diff - 1;
+            }
+        }
+        changed
+    }
+
+    fn row(&self, row: usize) -> &[u64] {
+        &self.words[row * self.stride..(row + 1) * self.stride]
+    }
+
+    // Mask of the valid bits in the last word of a row
+    fn last_mask(&self) -> u64 {
+        match self.cols % 64 {
+            0 => !0,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    // dst[col] = src[col - 1], wrapping around the row
+    fn shift_west(&self, src: &[u64], dst: &mut [u64]) {
+        let last = self.cols as usize - 1;
+        let mut carry = (src[last / 64] >> (last % 64)) & 1;
+        for (d, &w) in dst.iter_mut().zip(src) {
+            *d = (w << 1) | carry;
+            carry = w >> 63;
+        }
+        dst[self.stride - 1] &= self.last_mask();
+    }
+
+    // dst[col] = src[col + 1], wrapping around the row
+    fn shift_east(&self, src: &[u64], dst: &mut [u64]) {
+        let mut carry = 0;
+        for (d, &w) in dst.iter_mut().zip(src).rev() {
+            *d = (w >> 1) | (carry << 63);
+            carry = w & 1;
+        }
+        let last = self.cols as usize - 1;
+        dst[last / 64] |= (src[0] & 1) << (last % 64);
+        dst[self.stride - 1] &= self.last_mask();
+    }
+
+    pub fn step(&self, rule: &Rule) -> BitGrid {
+        let rows = self.rows as usize;
+        let stride = self.stride;
+
+        // Horizontally shifted copies of every row, computed once and reused by the rows above and below
+        let mut west = vec![0u64; self.words.len()];
+        let mut east = vec![0u64; self.words.len()];
+        for row in 0..rows {
+            let range = row * stride..(row + 1) * stride;
+            self.shift_west(self.row(row), &mut west[range.clone()]);
+            self.shift_east(self.row(row), &mut east[range]);
+        }
+
+        let mut next = vec![0u64; self.words.len()];
+        for row in 0..rows {
+            let up = (row + rows - 1) % rows * stride;
+            let mid = row * stride;
+            let down = (row + 1) % rows * stride;
+
+            for i in 0..stride {
+                let (top_sum, top_carry) = full_add(west[up + i], self.words[up + i], east[up + i]);
+                let (mid_sum, mid_carry) = half_add(west[mid + i], east[mid + i]);
+                let (bot_sum, bot_carry) = full_add(west[down + i], self.words[down + i], east[down + i]);
+
+                // Neighbour count as four bit planes: count = b0 + 2*b1 + 4*b2 + 8*b3
+                let (b0, ones_carry) = full_add(top_sum, mid_sum, bot_sum);
+                let (twos, fours) = full_add(top_carry, mid_carry, bot_carry);
+                let (b1, twos_carry) = half_add(twos, ones_carry);
+                let (b2, b3) = half_add(fours, twos_carry);
+
+                let alive = self.words[mid + i];
+                next[mid + i] = if rule.is_conway() {
+                    // Alive next generation with exactly 3 neighbours, or 2 if already alive
+                    b1 & !b2 & !b3 & (b0 | alive)
+                } else {
+                    apply_rule(rule, alive, [b0, b1, b2, b3])
+                };
+            }
+            // Rules with B0 set the padding bits, which would shift into the row next step
+            next[mid + stride - 1] &= self.last_mask();
+        }
+
+        BitGrid { rows: self.rows, cols: self.cols, stride, words: next }
+    }
+}
+
+// Generic rule: select the cells whose neighbour count matches a birth/survive entry
+fn apply_rule(rule: &Rule, alive: u64, planes: [u64; 4]) -> u64 {
+    let mut next = 0;
+    for count in 0..=8 {
+        let (born, kept) = (rule.birth & (1 << count) != 0, rule.survive & (1 << count) != 0);
+        if !born && !kept {
+            continue;
+        }
+
+        let matches = planes
+            .iter()
+            .enumerate()
+            .fold(!0, |acc, (bit, &plane)| acc & if count & (1 << bit) != 0 { plane } else { !plane });
+
+        if born {
+            next |= matches & !alive;
+        }
+        if kept {
+            next |= matches & alive;
+        }
+    }
+    next
+}
+
+fn half_add(a: u64, b: u64) -> (u64, u64) {
+    (a ^ b, a & b)
+}
+
+fn full_add(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let partial = a ^ b;
+    (partial ^ c, (a & b) | (partial & c))
+}
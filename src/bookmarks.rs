@@ -0,0 +1,161 @@
+use crate::selection::Region;
+use crate::strip::SeedMap;
+
+// Places to come back to while exploring a genome: what was on screen and where the camera
+// was, the sequence windows the grid was seeded from, and the cells at that generation. Saving one is an action like
+// any other, so a replay file recreates its bookmarks as it plays, and sessions keep them.
+pub const SLOTS: usize = 9;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bookmark {
+    pub name: String,
+    pub generation: u64,
+    pub cells: Vec<bool>,
+    pub seed_map: SeedMap,
+    // First base shown in the strip
+    pub strip_start: usize,
+    pub selection: Option<Region>,
+    // The camera: cell size in logical pixels (None when fitted) and pan, as `layout` sets them
+    pub cell_size: Option<u32>,
+    pub pan: [i32; 2],
+}
+
+impl Bookmark {
+    // Two lines of a session file:
+    //
+    //   bookmark 3 120 45 64x64 2,3,10x10 0,0,64x64+0;2,3,10x10+500 8 -40,12 origin
+    //   .O..O...
+    //
+    // the slot, generation, first base in the strip, grid size, selection (`-` for none),
+    // the seeds as region+offset oldest first, cell size (`auto` when fitted), pan and name,
+    // then all the cells on one line
+    pub fn encode(&self, slot: usize) -> String {
+        let (rows, cols) = self.seed_map.size();
+        let selection = self.selection.map_or("-".to_string(), |region| region.to_string());
+        let seeds: Vec<String> = self.seed_map.seeds().iter().map(|(region, offset)| format!("{region}+{offset}")).collect();
+        let cells: String = self.cells.iter().map(|&alive| if alive { 'O' } else { '.' }).collect();
+        let cell_size = self.cell_size.map_or("auto".to_string(), |size| size.to_string());
+        let [x, y] = self.pan;
+        format!("bookmark {slot} {} {} {rows}x{cols} {selection} {} {cell_size} {x},{y} {}\n{cells}\n", self.generation, self.strip_start, seeds.join(";"), self.name)
+    }
+
+    // A bookmark and its slot from the two lines `encode` writes, `bookmark ` left off
+    pub fn decode(line: &str, cells: &str) -> Result<(usize, Self), String> {
+        let invalid = |what: &str| format!("invalid bookmark {what} in '{line}'");
+        let fields: Vec<&str> = line.splitn(9, ' ').collect();
+        let &[slot, generation, strip_start, size, selection, seeds, cell_size, pan, name] = fields.as_slice() else {
+            return Err(format!("expected 9 bookmark fields in '{line}'"));
+        };
+        let (rows, cols) = crate::universe::parse_grid_size(size).ok_or_else(|| invalid("grid"))?;
+        let selection = match selection {
+            "-" => None,
+            region => Some(region.parse()?),
+        };
+        let seeds = seeds
+            .split(';')
+            .map(|seed| {
+                let (region, offset) = seed.split_once('+').ok_or_else(|| invalid("seed"))?;
+                Ok((region.parse()?, offset.parse().map_err(|_| invalid("seed"))?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        let cell_size = match cell_size {
+            "auto" => None,
+            size => Some(size.parse().map_err(|_| invalid("cell size"))?),
+        };
+        let (x, y) = pan.split_once(',').ok_or_else(|| invalid("pan"))?;
+        let pan = [x.parse().map_err(|_| invalid("pan"))?, y.parse().map_err(|_| invalid("pan"))?];
+        if cells.len() != rows as usize * cols as usize {
+            return Err(format!("bookmark '{name}' has {} cells, expected {}", cells.len(), rows as usize * cols as usize));
+        }
+        let bookmark = Bookmark {
+            name: name.to_string(),
+            generation: generation.parse().map_err(|_| invalid("generation"))?,
+            cells: cells.bytes().map(|cell| cell == b'O').collect(),
+            seed_map: SeedMap::from_seeds(rows, cols, seeds),
+            strip_start: strip_start.parse().map_err(|_| invalid("strip"))?,
+            selection,
+            cell_size,
+            pan,
+        };
+        Ok((slot.parse().map_err(|_| invalid("slot"))?, bookmark))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bookmarks {
+    slots: [Option<Bookmark>; SLOTS],
+}
+
+impl Bookmarks {
+    // Store a bookmark in slot 1..=9, replacing what was there
+    pub fn save(&mut self, slot: usize, bookmark: Bookmark) -> Result<(), String> {
+        match slot.checked_sub(1).and_then(|index| self.slots.get_mut(index)) {
+            Some(stored) => {
+                *stored = Some(bookmark);
+                Ok(())
+            }
+            None => Err(format!("Bookmark slot {slot} is out of range, expected 1 to {SLOTS}")),
+        }
+    }
+
+    // A bookmark by slot number or name
+    pub fn find(&self, key: &str) -> Option<(usize, &Bookmark)> {
+        match key.parse::<usize>() {
+            Ok(slot) => self.iter().find(|(other, _)| *other == slot),
+            Err(_) => self.iter().find(|(_, bookmark)| bookmark.name == key),
+        }
+    }
+
+    // Saved bookmarks with their slot numbers
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Bookmark)> {
+        self.slots.iter().enumerate().filter_map(|(index, bookmark)| Some((index + 1, bookmark.as_ref()?)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(name: &str, generation: u64) -> Bookmark {
+        Bookmark { name: name.to_string(), generation, cells: vec![false; 4], seed_map: SeedMap::new(2, 2), strip_start: 0, selection: None, cell_size: None, pan: [0, 0] }
+    }
+
+    #[test]
+    fn finds_bookmarks_by_slot_or_name() {
+        let mut bookmarks = Bookmarks::default();
+        assert!(bookmarks.is_empty());
+        bookmarks.save(3, bookmark("origin", 10)).unwrap();
+        bookmarks.save(9, bookmark("9", 20)).unwrap();
+        assert!(bookmarks.save(0, bookmark("x", 0)).is_err());
+        assert!(bookmarks.save(10, bookmark("x", 0)).is_err());
+
+        assert_eq!(bookmarks.find("origin").map(|(slot, bookmark)| (slot, bookmark.generation)), Some((3, 10)));
+        assert_eq!(bookmarks.find("3").map(|(_, bookmark)| bookmark.name.as_str()), Some("origin"));
+        assert!(bookmarks.find("4").is_none());
+        bookmarks.save(3, bookmark("terminus", 30)).unwrap();
+        assert!(bookmarks.find("origin").is_none());
+        assert_eq!(bookmarks.iter().map(|(slot, _)| slot).collect::<Vec<_>>(), [3, 9]);
+    }
+
+    #[test]
+    fn round_trips_through_session_lines() {
+        let mut seed_map = SeedMap::new(2, 2);
+        seed_map.record(&Region { row: 1, col: 0, rows: 1, cols: 2 }, 500);
+        let bookmark = Bookmark { cells: vec![true, false, false, true], seed_map, selection: Some(Region::full(1, 1)), cell_size: Some(8), pan: [-40, 12], ..bookmark("oriC region", 7) };
+        let encoded = bookmark.encode(4);
+        assert_eq!(encoded, "bookmark 4 7 0 2x2 0,0,1x1 0,0,2x2+0;1,0,1x2+500 8 -40,12 oriC region\nO..O\n");
+        let (line, cells) = encoded.strip_prefix("bookmark ").unwrap().split_once('\n').unwrap();
+        assert_eq!(Bookmark::decode(line, cells.trim_end()), Ok((4, bookmark.clone())));
+        assert!(Bookmark::decode(line, "O..").is_err());
+        assert!(Bookmark::decode("4 7 0 2x2 - 0,0,2x2+0", "O..O").is_err());
+        let fitted = Bookmark { cell_size: None, ..bookmark.clone() };
+        let encoded = fitted.encode(4);
+        let (line, cells) = encoded.strip_prefix("bookmark ").unwrap().split_once('\n').unwrap();
+        assert_eq!(Bookmark::decode(line, cells.trim_end()), Ok((4, fitted)));
+        assert!(Bookmark::decode(&line.replace("-40,12", "-40"), cells.trim_end()).is_err());
+    }
+}
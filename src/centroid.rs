@@ -0,0 +1,142 @@
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+
+// Population centroid as (column, row) in cell units, or None when nothing is alive. The
+// grid wraps around, so each axis uses the circular mean: a glider crossing an edge keeps
+// a centroid next to it instead of one jumping to the middle of the grid.
+pub fn centroid(cells: &[bool], rows: u32, cols: u32) -> Option<[f64; 2]> {
+    let direction = |i: u32, n: u32| {
+        let angle = TAU * (i as f64 + 0.5) / n as f64;
+        [angle.cos(), angle.sin()]
+    };
+    let col_directions: Vec<[f64; 2]> = (0..cols).map(|col| direction(col, cols)).collect();
+
+    let (mut x, mut y, mut count) = ([0.0; 2], [0.0; 2], 0);
+    for (row, line) in cells.chunks(cols as usize).enumerate() {
+        let mut alive = 0;
+        for (col, _) in line.iter().enumerate().filter(|&(_, &alive)| alive) {
+            x[0] += col_directions[col][0];
+            x[1] += col_directions[col][1];
+            alive += 1;
+        }
+        let row_direction = direction(row as u32, rows);
+        y[0] += row_direction[0] * alive as f64;
+        y[1] += row_direction[1] * alive as f64;
+        count += alive;
+    }
+    if count == 0 {
+        return None;
+    }
+
+    let position = |[cos, sin]: [f64; 2], n: u32| (sin.atan2(cos) / TAU * n as f64 - 0.5).rem_euclid(n as f64);
+    Some([position(x, cols), position(y, rows)])
+}
+
+// Centroids of the most recent generations. Points are unwrapped across the grid edges
+// (each step is taken the short way around), so the trail is continuous and drift is not
+// undone by wrapping.
+pub struct Trajectory {
+    points: VecDeque<(u64, [f64; 2])>,
+    capacity: usize,
+    // Grid columns and rows
+    size: [f64; 2],
+}
+
+impl Trajectory {
+    pub fn new(rows: u32, cols: u32, capacity: usize) -> Self {
+        Self { points: VecDeque::new(), capacity, size: [cols as f64, rows as f64] }
+    }
+
+    // Add the centroid of `generation`. An empty grid has no centroid and ends the trail.
+    pub fn record(&mut self, generation: u64, centroid: Option<[f64; 2]>) {
+        let Some(centroid) = centroid else {
+            self.points.clear();
+            return;
+        };
+        let point = match self.points.back() {
+            Some(&(_, last)) => [0, 1].map(|axis| {
+                let size = self.size[axis];
+                let step = centroid[axis] - last[axis].rem_euclid(size);
+                last[axis] + step - size * (step / size).round()
+            }),
+            None => centroid,
+        };
+        if self.points.len() == self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back((generation, point));
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    // Unwrapped points, oldest first
+    pub fn points(&self) -> impl Iterator<Item = &(u64, [f64; 2])> {
+        self.points.iter()
+    }
+
+    // Points folded back onto the grid, for drawing
+    pub fn wrapped(&self) -> Vec<[f32; 2]> {
+        self.points.iter().map(|(_, point)| [0, 1].map(|axis| point[axis].rem_euclid(self.size[axis]) as f32)).collect()
+    }
+
+    // Net drift velocity in cells per generation along (columns, rows): the least-squares
+    // slope of the trail against the generation. None until two generations are recorded.
+    pub fn drift(&self) -> Option<[f64; 2]> {
+        let n = self.points.len() as f64;
+        let mean_generation = self.points.iter().map(|&(generation, _)| generation as f64).sum::<f64>() / n;
+        let variance: f64 = self.points.iter().map(|&(generation, _)| (generation as f64 - mean_generation).powi(2)).sum();
+        if self.points.len() < 2 || variance == 0.0 {
+            return None;
+        }
+        Some([0, 1].map(|axis| {
+            let mean = self.points.iter().map(|(_, point)| point[axis]).sum::<f64>() / n;
+            let covariance: f64 = self.points.iter().map(|&(generation, point)| (generation as f64 - mean_generation) * (point[axis] - mean)).sum();
+            covariance / variance
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::Universe;
+
+    #[test]
+    fn centroid_wraps_around_edges() {
+        let mut universe = Universe::new(10, 10, b"");
+        assert_eq!(centroid(&universe.cells, 10, 10), None);
+        universe.toggle(4, 2);
+        universe.toggle(4, 4);
+        let [x, y] = centroid(&universe.cells, 10, 10).unwrap();
+        assert!((x - 3.0).abs() < 1e-9 && (y - 4.0).abs() < 1e-9);
+
+        // Columns 9 and 1 straddle the edge: the centroid is column 0, not 5
+        let mut universe = Universe::new(10, 10, b"");
+        universe.toggle(0, 9);
+        universe.toggle(0, 1);
+        let [x, _] = centroid(&universe.cells, 10, 10).unwrap();
+        assert!(x.min(10.0 - x) < 1e-9);
+    }
+
+    #[test]
+    fn glider_drifts_a_quarter_cell_per_generation() {
+        let mut universe = Universe::new(20, 20, b"");
+        for (row, col) in [(2, 1), (1, 2), (0, 0), (0, 1), (0, 2)] {
+            universe.toggle(row, col);
+        }
+        let mut trajectory = Trajectory::new(20, 20, 1000);
+        // Long enough to cross the edges
+        for _ in 0..120 {
+            trajectory.record(universe.generation, centroid(&universe.cells, 20, 20));
+            universe.tick();
+        }
+        let [dx, dy] = trajectory.drift().unwrap();
+        assert!((dx.abs() - 0.25).abs() < 0.01 && (dy.abs() - 0.25).abs() < 0.01, "drift {dx}, {dy}");
+        assert!(trajectory.wrapped().iter().all(|point| point.iter().all(|&v| (0.0..20.0).contains(&v))));
+
+        trajectory.record(120, None);
+        assert_eq!(trajectory.drift(), None);
+    }
+}
@@ -0,0 +1,347 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use tracing::warn;
+
+use crate::fnv::fnv1a;
+use crate::mutation::Mutator;
+use crate::rule::Rule;
+use crate::universe::Universe;
+
+// Checkpoints of long headless runs (`bio-rust run`): every N generations the grid, its rule
+// and generation, and the state of the mutation RNG are written to a numbered file in a
+// directory, so a run cut short by a crash or a reboot goes on from the last one with
+// `--resume`. Each file is gzip-compressed, a text header and then the cells packed eight to
+// a byte, row by row:
+//
+//   bio-rust checkpoint 2
+//   checksum 3f2a9c0e5d7b1a64
+//   run 8c1d0e4f2b7a9356
+//   generation 2000000
+//   grid 512x512
+//   rule B3/S23
+//   mutate 0.0001 9e3779b97f4a7c15 4211
+//   cells
+//   <packed cells>
+//
+// The checksum is FNV-1a over everything after its line, so a torn write or a bad disk is
+// skipped rather than resumed from. `run` tells runs apart: a hash of the sequence, the grid,
+// the rule and the mutations it started with, also in the file name, so runs sharing a
+// directory neither prune nor resume each other's files. `mutate` (the rate, the RNG state
+// and the mutations drawn so far) is only there when mutations are on. Only the newest few
+// files of a run are kept.
+
+const HEADER: &str = "bio-rust checkpoint 2";
+pub const DEFAULT_EVERY: u64 = 100_000;
+pub const DEFAULT_KEEP: usize = 3;
+pub const DEFAULT_DIR: &str = "checkpoints";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MutationState {
+    pub rate: f64,
+    pub rng: u64,
+    pub count: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunState {
+    pub run: u64,
+    pub rows: u32,
+    pub cols: u32,
+    pub generation: u64,
+    pub rule: Rule,
+    pub cells: Vec<bool>,
+    pub mutation: Option<MutationState>,
+}
+
+impl MutationState {
+    pub fn of(mutator: &Mutator) -> Self {
+        Self { rate: mutator.params.rate, rng: mutator.rng_state(), count: mutator.count }
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u64 {
+    fnv1a(bytes.iter().copied())
+}
+
+// Identity of a run started from `dna` on a grid of this size, under this rule, with these
+// mutations
+pub fn run_id(dna: &[u8], (rows, cols): (u32, u32), rule: Rule, mutation: Option<(f64, u64)>) -> u64 {
+    let settings = format!("{rows}x{cols} {rule} {mutation:?}");
+    fnv1a(dna.iter().copied().chain([0]).chain(settings.bytes()))
+}
+
+// Step a run `generations` further: with mutations one generation at a time, each followed
+// by its draws, otherwise with HashLife where the rule allows. A resumed run steps the same
+// way, so it comes out as if it had never stopped.
+pub fn advance(universe: &mut Universe, mutator: Option<&mut Mutator>, generations: u64) {
+    match mutator {
+        Some(mutator) => {
+            for _ in 0..generations {
+                universe.tick();
+                for idx in mutator.draw(universe.cells.len()) {
+                    universe.cells[idx] = !universe.cells[idx];
+                }
+            }
+        }
+        None => universe.jump(generations),
+    }
+}
+
+impl RunState {
+    pub fn of(run: u64, universe: &Universe, mutation: Option<MutationState>) -> Self {
+        Self { run, rows: universe.rows, cols: universe.cols, generation: universe.generation, rule: universe.rule, cells: universe.cells.clone(), mutation }
+    }
+
+    // A universe in this state, with no sequence behind it: a resumed run only needs the cells
+    pub fn universe(&self) -> Universe {
+        let mut universe = Universe::new(self.rows, self.cols, b"");
+        universe.cells.clone_from(&self.cells);
+        universe.rule = self.rule;
+        universe.generation = self.generation;
+        universe
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut body = format!("run {:016x}\ngeneration {}\ngrid {}x{}\nrule {}\n", self.run, self.generation, self.rows, self.cols, self.rule);
+        if let Some(mutation) = self.mutation {
+            body.push_str(&format!("mutate {} {:016x} {}\n", mutation.rate, mutation.rng, mutation.count));
+        }
+        body.push_str("cells\n");
+        let mut body = body.into_bytes();
+        body.extend(self.cells.chunks(8).map(|byte| byte.iter().enumerate().fold(0u8, |packed, (bit, &alive)| packed | (alive as u8) << bit)));
+        let mut bytes = format!("{HEADER}\nchecksum {:016x}\n", checksum(&body)).into_bytes();
+        bytes.extend(body);
+        bytes
+    }
+
+    fn decode(bytes: &[u8]) -> Result<Self, String> {
+        // The text lines, then the packed cells after the `cells` line
+        let mut lines = Vec::new();
+        let mut rest = bytes;
+        while let Some(end) = rest.iter().position(|&byte| byte == b'\n') {
+            let line = std::str::from_utf8(&rest[..end]).map_err(|_| "Invalid checkpoint header".to_string())?;
+            rest = &rest[end + 1..];
+            if line == "cells" {
+                break;
+            }
+            lines.push(line);
+        }
+        let (Some(&HEADER), Some(expected)) = (lines.first(), lines.get(1).and_then(|line| line.strip_prefix("checksum "))) else {
+            return Err("Not a bio-rust checkpoint".to_string());
+        };
+        let body = &bytes[HEADER.len() + 1 + lines[1].len() + 1..];
+        if u64::from_str_radix(expected, 16) != Ok(checksum(body)) {
+            return Err("Checksum mismatch, the checkpoint is corrupt".to_string());
+        }
+
+        let (mut run, mut generation, mut grid, mut rule, mut mutation) = (None, None, None, None, None);
+        for line in &lines[2..] {
+            let (key, value) = line.split_once(' ').ok_or_else(|| format!("Invalid checkpoint line '{line}'"))?;
+            let invalid = || format!("Invalid checkpoint {key} '{value}'");
+            match key {
+                "run" => run = Some(u64::from_str_radix(value, 16).map_err(|_| invalid())?),
+                "generation" => generation = Some(value.parse::<u64>().map_err(|_| invalid())?),
+                "grid" => grid = Some(crate::universe::parse_grid_size(value).ok_or_else(invalid)?),
+                "rule" => rule = Some(value.parse::<Rule>()?),
+                "mutate" => {
+                    let [rate, rng, count] = value.split(' ').collect::<Vec<_>>()[..] else { return Err(invalid()) };
+                    mutation = Some(MutationState {
+                        rate: rate.parse().map_err(|_| invalid())?,
+                        rng: u64::from_str_radix(rng, 16).map_err(|_| invalid())?,
+                        count: count.parse().map_err(|_| invalid())?,
+                    });
+                }
+                _ => return Err(format!("Unknown checkpoint line '{line}'")),
+            }
+        }
+        let missing = |what: &str| format!("Checkpoint without a {what}");
+        let (rows, cols) = grid.ok_or_else(|| missing("grid"))?;
+        let len = rows as usize * cols as usize;
+        if rest.len() != len.div_ceil(8) {
+            return Err(format!("Checkpoint has {} bytes of cells, a {rows}x{cols} grid needs {}", rest.len(), len.div_ceil(8)));
+        }
+        let cells = (0..len).map(|idx| (rest[idx / 8] >> (idx % 8)) & 1 == 1).collect();
+        Ok(Self { run: run.ok_or_else(|| missing("run"))?, rows, cols, generation: generation.ok_or_else(|| missing("generation"))?, rule: rule.ok_or_else(|| missing("rule"))?, cells, mutation })
+    }
+}
+
+// Named by run and generation, zero-padded so a run's names sort in the order they were
+// written
+pub fn file_name(run: u64, generation: u64) -> String {
+    format!("checkpoint-{run:016x}-{generation:020}.bin")
+}
+
+// The run a checkpoint file name is of
+fn run_of(path: &Path) -> Option<u64> {
+    let name = path.file_name()?.to_str()?.strip_prefix("checkpoint-")?.strip_suffix(".bin")?;
+    let (run, generation) = name.split_once('-')?;
+    generation.parse::<u64>().ok()?;
+    u64::from_str_radix(run, 16).ok()
+}
+
+// The checkpoints in `dir`, of one run or of all, sorted by run and then generation
+pub fn checkpoints(dir: &Path, run: Option<u64>) -> Result<Vec<PathBuf>, String> {
+    let entries = std::fs::read_dir(dir).map_err(|err| format!("Failed to list {}: {err}", dir.display()))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| run_of(path).is_some_and(|of| run.is_none_or(|run| run == of)))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+// Write a checkpoint into `dir` (next to its final name first, then moved there, so a crash
+// while writing leaves the previous ones intact), then delete all but the newest `keep` of
+// its run, never the one just written
+pub fn save(dir: &Path, state: &RunState, keep: usize) -> Result<PathBuf, String> {
+    let path = dir.join(file_name(state.run, state.generation));
+    let partial = dir.join(format!("{}.part", file_name(state.run, state.generation)));
+    let error = |err: std::io::Error| format!("Failed to write the checkpoint {}: {err}", path.display());
+    std::fs::create_dir_all(dir).map_err(error)?;
+    let write = || -> std::io::Result<()> {
+        let mut encoder = GzEncoder::new(File::create(&partial)?, Compression::default());
+        encoder.write_all(&state.encode())?;
+        encoder.finish()?.sync_all()
+    };
+    write().and_then(|()| std::fs::rename(&partial, &path)).map_err(|err| {
+        let _ = std::fs::remove_file(&partial);
+        error(err)
+    })?;
+
+    let all = checkpoints(dir, Some(state.run))?;
+    for old in all[..all.len().saturating_sub(keep.max(1))].iter().filter(|&old| *old != path) {
+        std::fs::remove_file(old).map_err(|err| format!("Failed to remove the old checkpoint {}: {err}", old.display()))?;
+    }
+    Ok(path)
+}
+
+fn load_file(path: &Path) -> Result<RunState, String> {
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|file| GzDecoder::new(file).read_to_end(&mut bytes))
+        .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    RunState::decode(&bytes).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+// A checkpoint file, or the newest one in a directory that reads back intact; broken newer
+// ones are skipped with a warning. A directory holding the checkpoints of several runs is
+// refused, as which one to go on with is for the user to say.
+pub fn load(path: &Path) -> Result<(PathBuf, RunState), String> {
+    if !path.is_dir() {
+        return load_file(path).map(|state| (path.to_path_buf(), state));
+    }
+    let all = checkpoints(path, None)?;
+    let mut runs: Vec<u64> = all.iter().filter_map(|path| run_of(path)).collect();
+    runs.dedup();
+    if runs.len() > 1 {
+        return Err(format!("{} holds the checkpoints of {} runs, pass the file to resume from", path.display(), runs.len()));
+    }
+    for candidate in all.into_iter().rev() {
+        match load_file(&candidate) {
+            Ok(state) => return Ok((candidate, state)),
+            Err(err) => warn!("Skipping {err}"),
+        }
+    }
+    Err(format!("No valid checkpoints in {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mutation::{DEFAULT_KAPPA, MutationParams, MutationTarget};
+
+    #[test]
+    fn keeps_the_newest_valid_checkpoints() {
+        let dir = std::env::temp_dir().join(format!("bio-rust-checkpoints-{}", std::process::id()));
+        let mut state = RunState {
+            run: 0xabc,
+            rows: 3,
+            cols: 5,
+            generation: 0,
+            rule: Rule::CONWAY,
+            cells: (0..15).map(|idx| idx % 3 == 0).collect(),
+            mutation: Some(MutationState { rate: 0.001, rng: 0x9e37_79b9_7f4a_7c15, count: 42 }),
+        };
+        for generation in [100, 200, 300, 400] {
+            state.generation = generation;
+            save(&dir, &state, 2).unwrap();
+        }
+        let file = |generation| dir.join(file_name(0xabc, generation));
+        assert_eq!(checkpoints(&dir, None).unwrap(), vec![file(300), file(400)]);
+        assert_eq!(load(&dir), Ok((file(400), state.clone())));
+        assert_eq!(load(&file(300)).map(|(_, state)| state.generation), Ok(300));
+        assert_eq!(state.universe().cells, state.cells);
+
+        // A flipped bit is caught, and so is a cut-off file
+        let mut bytes = state.encode();
+        *bytes.last_mut().unwrap() ^= 1;
+        assert!(RunState::decode(&bytes).unwrap_err().contains("Checksum"));
+        let bytes = state.encode();
+        assert!(RunState::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert_eq!(RunState::decode(&RunState { mutation: None, ..state.clone() }.encode()).map(|state| state.mutation), Ok(None));
+
+        // A torn newest file falls back to the one before it
+        std::fs::write(file(400), b"torn").unwrap();
+        assert_eq!(load(&dir).map(|(_, state)| state.generation), Ok(300));
+        std::fs::write(file(300), b"torn").unwrap();
+        assert!(load(&dir).unwrap_err().starts_with("No valid checkpoints"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resumed_runs_end_like_uninterrupted_ones() {
+        let dir = std::env::temp_dir().join(format!("bio-rust-resume-{}", std::process::id()));
+        let dna: Vec<u8> = (0..1024u32).map(|idx| b"ACGT"[(idx * 7 % 13 % 4) as usize]).collect();
+        let params = MutationParams { target: MutationTarget::Cells, rate: 0.01, kappa: DEFAULT_KAPPA, seed: 7 };
+
+        let mut whole = Universe::new(32, 32, &dna);
+        let mut mutator = Mutator::new(params);
+        advance(&mut whole, Some(&mut mutator), 200);
+
+        let mut half = Universe::new(32, 32, &dna);
+        let mut first = Mutator::new(params);
+        advance(&mut half, Some(&mut first), 100);
+        save(&dir, &RunState::of(1, &half, Some(MutationState::of(&first))), 1).unwrap();
+        let (_, state) = load(&dir).unwrap();
+        let saved = state.mutation.unwrap();
+        let (mut resumed, mut second) = (state.universe(), Mutator::resume(params, saved.rng, saved.count));
+        advance(&mut resumed, Some(&mut second), 100);
+
+        assert_eq!((resumed.generation, &resumed.cells), (200, &whole.cells));
+        assert_eq!(MutationState::of(&second), MutationState::of(&mutator));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn runs_sharing_a_directory_keep_to_their_own_files() {
+        let dir = std::env::temp_dir().join(format!("bio-rust-two-runs-{}", std::process::id()));
+        let dna = b"ACGTACGGTTCA";
+        let (long, short) = (run_id(dna, (4, 4), Rule::CONWAY, None), run_id(dna, (4, 4), Rule::CONWAY, Some((0.01, 1))));
+        assert_ne!(long, short);
+        let universe = Universe::new(4, 4, dna);
+        let state = |run, generation| RunState { generation, ..RunState::of(run, &universe, None) };
+        for generation in [1000, 2000, 3000] {
+            save(&dir, &state(long, generation), 3).unwrap();
+        }
+
+        // A second, shorter run prunes only its own files, and never the one just written
+        for generation in [100, 200] {
+            let path = save(&dir, &state(short, generation), 1).unwrap();
+            assert!(path.exists());
+        }
+        assert_eq!(checkpoints(&dir, Some(long)).unwrap().len(), 3);
+        assert_eq!(checkpoints(&dir, Some(short)).unwrap(), vec![dir.join(file_name(short, 200))]);
+        save(&dir, &state(long, 500), 3).unwrap();
+        assert!(dir.join(file_name(long, 500)).exists());
+
+        // Which run to resume is then up to the user
+        assert!(load(&dir).unwrap_err().contains("2 runs"));
+        assert_eq!(load(&dir.join(file_name(short, 200))).map(|(_, state)| (state.run, state.generation)), Ok((short, 200)));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
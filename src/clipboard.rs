@@ -0,0 +1,19 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+// The system clipboard, through whichever of the usual command-line tools is installed
+// (Wayland, X11, macOS, Windows), so no clipboard crate is needed
+const PROGRAMS: &[&[&str]] = &[&["wl-copy"], &["xclip", "-selection", "clipboard"], &["xsel", "--clipboard", "--input"], &["pbcopy"], &["clip.exe"]];
+
+pub fn copy(text: &str) -> Result<(), String> {
+    for program in PROGRAMS {
+        let Ok(mut child) = Command::new(program[0]).args(&program[1..]).stdin(Stdio::piped()).spawn() else {
+            continue;
+        };
+        let written = child.stdin.take().is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+        if written && child.wait().is_ok_and(|status| status.success()) {
+            return Ok(());
+        }
+    }
+    Err("Failed to copy: none of wl-copy, xclip, xsel, pbcopy or clip.exe worked".to_string())
+}
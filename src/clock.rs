@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+
+// Speeds the tick interval is kept to, in generations per second
+pub const MIN_SPEED: f64 = 0.01;
+pub const MAX_SPEED: f64 = 10_000.0;
+
+// Time generations are stepped by. It normally follows the wall clock, so how many generations
+// a frame steps depends on how long the frame took. A fixed clock (`--deterministic`) instead
+// moves on by exactly one frame per update, so the same run steps the same generations on
+// every frame, however fast or slow the machine draws.
+pub struct TickClock {
+    started: Instant,
+    // Time per frame, or None to follow the wall clock
+    frame: Option<Duration>,
+    now: Duration,
+    // Time of the last tick
+    last_tick: Duration,
+}
+
+impl TickClock {
+    pub fn wall() -> Self {
+        Self { started: Instant::now(), frame: None, now: Duration::ZERO, last_tick: Duration::ZERO }
+    }
+
+    pub fn fixed(fps: u32) -> Self {
+        Self { frame: Some(Duration::from_secs(1) / fps.max(1)), ..Self::wall() }
+    }
+
+    pub fn is_fixed(&self) -> bool {
+        self.frame.is_some()
+    }
+
+    // Time of the current frame
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    // Move on to the current frame
+    pub fn next_frame(&mut self) {
+        self.now = match self.frame {
+            Some(frame) => self.now + frame,
+            None => self.started.elapsed(),
+        };
+    }
+
+    // Whether a tick `interval` after the last one is due
+    pub fn is_due(&self, interval: Duration) -> bool {
+        self.now.saturating_sub(self.last_tick) >= interval
+    }
+
+    pub fn tick(&mut self, interval: Duration) {
+        self.last_tick += interval;
+    }
+
+    // Forget the ticks that are due, e.g. while paused or when they can't be kept up with
+    pub fn skip(&mut self) {
+        self.last_tick = self.now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_clock_ticks_the_same_every_run() {
+        // 25 generations/s at 60 frames/s: ticks on the frames where another 40 ms have passed
+        let ticks = || {
+            let mut clock = TickClock::fixed(60);
+            let interval = Duration::from_millis(40);
+            let mut per_frame = Vec::new();
+            for _ in 0..12 {
+                std::thread::sleep(Duration::from_millis(1));
+                clock.next_frame();
+                let mut ticks = 0;
+                while clock.is_due(interval) {
+                    clock.tick(interval);
+                    ticks += 1;
+                }
+                per_frame.push(ticks);
+            }
+            per_frame
+        };
+        assert_eq!(ticks(), vec![0, 0, 1, 0, 1, 0, 0, 1, 0, 1, 0, 0]);
+        assert_eq!(ticks(), ticks());
+
+        let mut clock = TickClock::fixed(10);
+        clock.next_frame();
+        clock.next_frame();
+        clock.skip();
+        assert!(!clock.is_due(Duration::from_millis(1)));
+    }
+}
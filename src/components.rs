@@ -0,0 +1,159 @@
+use crate::centroid::centroid;
+
+// Connected groups of live cells ("blobs", `blobs`): cells are in the same group when they
+// touch, diagonally included, as neighbors do for the rule, and across the wrapping edges.
+// Labeling is a union-find pass over the grid joining each live cell with its live
+// neighbors above and to the left, then a second pass numbering the groups.
+
+// Label of dead cells
+pub const NONE: u32 = u32::MAX;
+
+// Disjoint sets over cell indices, with union by size and path halving, so a whole grid is
+// joined in close to linear time
+struct UnionFind {
+    parent: Vec<u32>,
+    size: Vec<u32>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        Self { parent: (0..len as u32).collect(), size: vec![1; len] }
+    }
+
+    fn find(&mut self, mut i: u32) -> u32 {
+        while self.parent[i as usize] != i {
+            let grandparent = self.parent[self.parent[i as usize] as usize];
+            self.parent[i as usize] = grandparent;
+            i = grandparent;
+        }
+        i
+    }
+
+    fn union(&mut self, a: u32, b: u32) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        let (small, large) = if self.size[a as usize] < self.size[b as usize] { (a, b) } else { (b, a) };
+        self.parent[small as usize] = large;
+        self.size[large as usize] += self.size[small as usize];
+    }
+}
+
+pub struct Components {
+    pub rows: u32,
+    pub cols: u32,
+    // Component of each cell, numbered in the order their first cell comes up row by row;
+    // NONE for dead cells
+    pub labels: Vec<u32>,
+    // Live cells in each component
+    pub sizes: Vec<u32>,
+}
+
+impl Components {
+    pub fn label(cells: &[bool], rows: u32, cols: u32) -> Self {
+        let mut sets = UnionFind::new(cells.len());
+        let index = |row: u32, col: u32| row * cols + col;
+        for row in 0..rows {
+            let up = (row + rows - 1) % rows;
+            for col in 0..cols {
+                if !cells[index(row, col) as usize] {
+                    continue;
+                }
+                let (left, right) = ((col + cols - 1) % cols, (col + 1) % cols);
+                // The other four neighbors join this cell when their own turn comes, and
+                // those across the bottom and right edges when the scan wraps to them
+                for neighbor in [index(row, left), index(up, left), index(up, col), index(up, right)] {
+                    if cells[neighbor as usize] {
+                        sets.union(index(row, col), neighbor);
+                    }
+                }
+            }
+        }
+
+        let mut labels = vec![NONE; cells.len()];
+        let mut numbers = vec![NONE; cells.len()];
+        let mut sizes = Vec::new();
+        for i in (0..cells.len()).filter(|&i| cells[i]) {
+            let root = sets.find(i as u32) as usize;
+            if numbers[root] == NONE {
+                numbers[root] = sizes.len() as u32;
+                sizes.push(0);
+            }
+            labels[i] = numbers[root];
+            sizes[numbers[root] as usize] += 1;
+        }
+        Self { rows, cols, labels, sizes }
+    }
+
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    // The biggest component, the first one found on a tie
+    pub fn largest(&self) -> Option<u32> {
+        (0..self.sizes.len() as u32).rev().max_by_key(|&id| self.sizes[id as usize])
+    }
+
+    // Component sizes, biggest first
+    pub fn sorted_sizes(&self) -> Vec<u32> {
+        let mut sizes = self.sizes.clone();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
+    }
+
+    // Centroid of a component as (column, row) in cell units, wrapping like the population's
+    pub fn centroid(&self, id: u32) -> Option<[f64; 2]> {
+        let cells: Vec<bool> = self.labels.iter().map(|&label| label == id).collect();
+        centroid(&cells, self.rows, self.cols)
+    }
+
+    // Per-cell colors, each component its own hue and dead cells dark
+    pub fn colors(&self, dead: [f32; 3]) -> Vec<[f32; 3]> {
+        self.labels.iter().map(|&label| if label == NONE { dead } else { color(label) }).collect()
+    }
+}
+
+// Hues a golden-ratio turn apart, so neighboring numbers look different
+pub fn color(id: u32) -> [f32; 3] {
+    let hue = (id as f32 * 0.618_034).fract() * 6.0;
+    let channel = |offset: f32| {
+        let distance = ((hue - offset).rem_euclid(6.0) - 3.0).abs();
+        (distance - 1.0).clamp(0.0, 1.0) * 0.75 + 0.25
+    };
+    [channel(0.0), channel(2.0), channel(4.0)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_diagonal_and_wrapping_neighbors_together() {
+        let grid = [
+            "O...O.",
+            "......",
+            ".OO...",
+            "...O..",
+            "......",
+            "....OO",
+        ];
+        let cells: Vec<bool> = grid.concat().bytes().map(|cell| cell == b'O').collect();
+        let components = Components::label(&cells, 6, 6);
+        // The two cells of the top row join through the bottom row's pair, across the top
+        // and left edges; the middle three touch diagonally
+        assert_eq!(components.count(), 2);
+        assert_eq!(components.sizes, vec![4, 3]);
+        assert_eq!(components.labels[0], components.labels[35]);
+        assert_eq!(components.labels[21], 1);
+        assert_eq!(components.labels[1], NONE);
+        assert_eq!(components.largest(), Some(0));
+        assert_eq!(components.sorted_sizes(), vec![4, 3]);
+
+        // The circular mean is close to the plain one away from the edges
+        let [x, y] = components.centroid(1).unwrap();
+        assert!((x - 2.0).abs() < 1e-9 && (y - 7.0 / 3.0).abs() < 0.05, "{x}, {y}");
+        assert_ne!(color(0), color(1));
+        assert!(Components::label(&[false; 4], 2, 2).largest().is_none());
+    }
+}
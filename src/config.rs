@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+
+use crate::accessibility::{Accessibility, MAX_TEXT_SCALE};
+use crate::autopause::AutoPause;
+use crate::layers::Layers;
+use crate::presets::{Presets, Slot};
+use crate::quality::FrameBudget;
+use crate::rule_library::NamedRule;
+use crate::session::Autosave;
+
+// Settings read at startup from `bio-rust.conf` in the working directory or the file given
+// with `--config`: one `key = value` per line, '#' starting a comment
+//
+//   high_contrast = true
+//   text_scale = 2
+//   announce_every = 100
+//   announce_command = notify-send Bio-Rust
+//   auto_pause = true
+//   auto_pause_period = 30
+//   auto_degrade = true
+//   frame_budget = 16
+//   autosave = true
+//   autosave_minutes = 5
+//   preset_a = B3/S23 gc blue
+//   preset_b = B36/S23 gc red
+//   layers = -bases,heat:0.6,cells,selection,-grid,annotations
+//   named_rule = flakes B3/S012345678 Snowflake-like growth
+pub const DEFAULT_PATH: &str = "bio-rust.conf";
+
+// Where downloads and the recovery session are kept: the platform's cache directory, or the
+// temp directory without one
+pub fn cache_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir);
+    base.join("bio-rust")
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    pub accessibility: Accessibility,
+    pub auto_pause: AutoPause,
+    pub presets: Presets,
+    pub frame_budget: FrameBudget,
+    pub autosave: Autosave,
+    pub layers: Layers,
+    // The user's rules for the rule library, one line each, later ones of a name winning
+    pub rules: Vec<NamedRule>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        Self::parse(&text).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+        let accessibility = &mut config.accessibility;
+        for (number, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {message}", number + 1);
+            let (key, value) = line.split_once('=').ok_or_else(|| error("expected 'key = value'".into()))?;
+            let (key, value) = (key.trim(), value.trim());
+            let invalid = || error(format!("invalid {key} '{value}'"));
+            match key {
+                "high_contrast" => accessibility.high_contrast = value.parse().map_err(|_| invalid())?,
+                "text_scale" => match value.parse() {
+                    Ok(scale) if (1..=MAX_TEXT_SCALE).contains(&scale) => accessibility.text_scale = scale,
+                    _ => return Err(error(format!("text_scale must be 1 to {MAX_TEXT_SCALE}"))),
+                },
+                "announce_every" => match value.parse() {
+                    Ok(0) | Err(_) => return Err(invalid()),
+                    Ok(every) => accessibility.announce_every = Some(every),
+                },
+                "announce_command" => accessibility.announce_command = Some(value.split_whitespace().map(str::to_string).collect()),
+                "auto_pause" => config.auto_pause.enabled = value.parse().map_err(|_| invalid())?,
+                "auto_pause_period" => match value.parse() {
+                    Ok(0) | Err(_) => return Err(invalid()),
+                    Ok(period) => config.auto_pause.max_period = period,
+                },
+                "auto_degrade" => config.frame_budget.enabled = value.parse().map_err(|_| invalid())?,
+                // Milliseconds
+                "frame_budget" => match value.parse::<f64>() {
+                    Ok(ms) if ms > 0.0 && ms.is_finite() => config.frame_budget.limit = std::time::Duration::from_secs_f64(ms / 1000.0),
+                    _ => return Err(invalid()),
+                },
+                "autosave" => config.autosave.enabled = value.parse().map_err(|_| invalid())?,
+                "autosave_minutes" => match value.parse::<f64>() {
+                    Ok(minutes) if minutes > 0.0 && minutes.is_finite() => config.autosave.interval = std::time::Duration::from_secs_f64(minutes * 60.0),
+                    _ => return Err(invalid()),
+                },
+                "preset_a" | "preset_b" => {
+                    let slot = if key == "preset_a" { Slot::A } else { Slot::B };
+                    config.presets.set(slot, value.parse().map_err(error)?);
+                }
+                "layers" => config.layers = value.parse().map_err(error)?,
+                "named_rule" => config.rules.push(value.parse().map_err(error)?),
+                _ => return Err(error(format!("unknown setting '{key}'"))),
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::Layer;
+
+    #[test]
+    fn parses_settings() {
+        let config = Config::parse("# accessibility\nhigh_contrast = true\ntext_scale=2  # bigger\n\nannounce_command = notify-send Bio-Rust\n").unwrap();
+        let accessibility = config.accessibility;
+        assert!(accessibility.high_contrast);
+        assert_eq!(accessibility.text_scale, 2);
+        assert_eq!(accessibility.announce_every, None);
+        assert_eq!(accessibility.announce_command, Some(vec!["notify-send".to_string(), "Bio-Rust".to_string()]));
+
+        assert_eq!(Config::parse("text_scale = 9"), Err("line 1: text_scale must be 1 to 3".into()));
+        assert!(Config::parse("announce_every = 0").is_err());
+        let config = Config::parse("auto_pause = true\nauto_pause_period = 30").unwrap();
+        assert_eq!(config.auto_pause, AutoPause { enabled: true, max_period: 30 });
+        assert!(Config::parse("auto_pause_period = 0").is_err());
+        let config = Config::parse("auto_degrade = false\nframe_budget = 8.5").unwrap();
+        assert_eq!((config.frame_budget.enabled, config.frame_budget.limit.as_micros()), (false, 8500));
+        assert!(Config::parse("frame_budget = 0").is_err());
+        let config = Config::parse("autosave = false\nautosave_minutes = 0.5").unwrap();
+        assert_eq!((config.autosave.enabled, config.autosave.interval.as_secs()), (false, 30));
+        assert!(Config::parse("autosave_minutes = -1").is_err());
+        let config = Config::parse("preset_b = B36/S23 hydrophobic red").unwrap();
+        assert_eq!(config.presets.get(Slot::B).map(ToString::to_string), Some("B36/S23 hydrophobic red".to_string()));
+        assert!(Config::parse("preset_a = B3/S23").is_err());
+        let config = Config::parse("layers = bases:0.5, cells, -heat").unwrap();
+        assert!(config.layers.shown(Layer::Bases) && !config.layers.shown(Layer::Grid));
+        assert!(Config::parse("layers = cells,texture").is_err());
+        let config = Config::parse("named_rule = flakes B3/S012345678 Snowflakes\nnamed_rule = maze B3/S1234").unwrap();
+        assert_eq!(config.rules.iter().map(|named| named.name.as_str()).collect::<Vec<_>>(), vec!["flakes", "maze"]);
+        assert!(Config::parse("named_rule = flakes").is_err());
+        assert!(Config::parse("\ncolour = red").unwrap_err().starts_with("line 2"));
+    }
+}
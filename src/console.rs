@@ -0,0 +1,722 @@
+use crate::action::Action;
+use crate::automaton::lenia::KernelSource;
+use crate::clock::{MAX_SPEED, MIN_SPEED};
+use crate::gc_rules;
+use crate::mutation::{DEFAULT_KAPPA, DEFAULT_MUTATION_SEED, MutationParams};
+use crate::formats::vcf::{VariantFilter, VariantKind};
+use crate::selection::Region;
+use crate::tools::Tool;
+use crate::turbo;
+use crate::universe::{self, Anchor};
+use crate::export::sheet::{self, SheetSettings};
+use crate::export::table::{self, Column};
+use crate::viewport::{MAX_CELL_SIZE, MAX_PADDING};
+use crate::seq_analysis::codon_usage::{CdsSource, DEFAULT_MIN_CODONS};
+use crate::seq_analysis::complexity::ComplexityParams;
+use crate::seq_analysis::difference::Comparison;
+use crate::seq_analysis::motif::Motif;
+use crate::seq_analysis::pcr::{DEFAULT_MAX_MISMATCHES, PrimerPair};
+use crate::seq_analysis::primers::PrimerConstraints;
+use crate::seq_analysis::pwm::Background;
+use crate::seq_analysis::signals::SignalFilter;
+use crate::seq_analysis::repeats::RepeatParams;
+
+const USAGE: &str = "Commands: toggle <row> <col>, paint <on|off> <row> <col>..., inspect <row> <col>, tool <toggle|paint|erase|inspect|select>, select <row> <col> <rows> <cols> | select none, seed offset <n>, bam <file> <chrom:start-end> [min depth] [reference.fa], open <file.fa> [record], fetch <accession>, rows <file.fa>, pattern <load|save> <file.cells|file.lif>, compare <file.fa> [align|position] | compare off, frame <+1|+2|+3|-1|-2|-3|off>, msa <file.fa|file.aln> [seed <0..1>] | msa off, preset <a|b> <B/S> <gc|hydrophobic> <red|blue|contrast> | preset <save|use> <a|b>, shuffle <uniform|dinucleotide> [seed] | shuffle off, rule <B/S|name>, rules <on|off> | rules save <name> [description], gcrule <on|off> | gcrule <poor B/S> <rich B/S>, walk <generations> | walk <off|mark|history> | walk export <file.csv>, speed <generations/s>, turbo <generations/frame|on|off>, pause <on|off>, jump <generations>, export <file.png|file.svg>, sheet <file.png> <every N generations> <rows>x<cols> [thumbnail width] | sheet off, table <file.arrow|file.parquet> [columns] | table off, hashes <file.csv|off>, verify <file.csv|off>, script <file.rhai|off>, shader <file.wgsl|off>, signal <file.bedgraph|file.wig> [chrom] | signal off, vcf <file> [chrom] [snv|indel|other|all] [min QUAL] | vcf off, fertility <0..1|off>, motif <IUPAC|/regex/|off>, signals <on|off> | signals [start|stop|kozak|sd]... [frame <+1|+2|+3|-1|-2|-3>...], repeats <on|off> | repeats [unit <min> <max>] [copies <n>], pcr <forward> <reverse> [max mismatches] | pcr off, primers [length <min> <max>] [tm <min> <max>] [gc <min%> <max%>] [self <bases>] | primers copy [n] | primers off, pwm <file> [0..1] | pwm off, pwm background <uniform|sequence|A C G T>, mask <show|seed> [min run] [window] [threshold] | mask off, codons gff <file.gff3> [seqid] | codons orf [min codons] | codons off, codons sort <codon|aa|count|rscu>, codons export <file.csv>, analysis <on|off> | analysis export <file.png>, help <on|off>, profile <on|off>, trail <on|off>, blobs <on|off|stats> | blobs color <on|off>, ruler <on|off>, contour <on|off>, smooth <on|off>, letters <on|off>, torus <on|off>, heightfield <on|off>, ant <on [N]|off|stats>, cyclic <on [threshold]|off>, protein <on|off>, lenia <on [standard|sequence]|off>, strip <on|off|goto N>, track <name|next|list> | track layer <on|off>, layer <bases|heat|cells|selection|grid|annotations> <on|off|up|down|0..1>, layers <on|off|spec like -bases,heat,cells:0.8,selection,-grid,annotations>, bookmark save <1-9> [name] | bookmark goto <slot|name> | bookmark list, note <row> <col> <text> | note remove <row> <col> | note list | note export <file.bed> [chrom], alive export <file.bed> [chrom], report <file.tsv>, session <save|load> <file.session.gz>, heatmap <on|off|reset> | heatmap export <file.png|file.csv>, diff <mark|on|off>, effect <trails|bloom> <on|off>, undo, redo, background, layout | layout fit <contain|cover|stretch> | layout padding <0..0.9> | layout cell <pixels|auto> | layout pan <x> <y> | layout lod <auto|off>, grid <rows>x<cols> [nw|n|ne|w|center|e|sw|s|se], contrast <on|off>, announce <generations|off>, mutate <cells|bases> <0..1> [kappa <ts/tv>] [seed <n>] | mutate off";
+
+// Relative PWM score a site needs to be highlighted when no threshold is given
+const DEFAULT_PWM_THRESHOLD: f64 = 0.8;
+
+// Reads a base needs to be covered by to start alive when seeding from a BAM/CRAM
+const DEFAULT_MIN_DEPTH: u32 = 10;
+
+// RNG seed of `shuffle` without one, so a plain `shuffle uniform` is the same shuffle
+// every time
+const DEFAULT_SHUFFLE_SEED: u64 = 1;
+
+// Ants started by `ant on` without a count, one per ATG codon on the grid
+const DEFAULT_ANTS: usize = 4;
+
+// Neighbors in the winning state a cell needs to be taken over in the cyclic automaton
+const DEFAULT_CYCLIC_THRESHOLD: u8 = 3;
+
+// Single-line command console, toggled with the backtick key. The line being typed
+// is shown in the window title.
+#[derive(Default)]
+pub struct Console {
+    input: Option<String>,
+}
+
+impl Console {
+    pub fn is_open(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn open(&mut self, prefill: &str) {
+        self.input = Some(prefill.to_string());
+    }
+
+    pub fn close(&mut self) {
+        self.input = None;
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        if let Some(input) = self.input.as_mut() {
+            input.extend(text.chars().filter(|c| !c.is_control()));
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        if let Some(input) = self.input.as_mut() {
+            input.pop();
+        }
+    }
+
+    // Close the console and parse whatever was typed. Empty lines produce nothing.
+    pub fn submit(&mut self) -> Option<Result<Action, String>> {
+        let line = self.input.take()?;
+        if line.trim().is_empty() {
+            return None;
+        }
+        Some(parse_command(&line))
+    }
+
+    pub fn prompt(&self) -> Option<String> {
+        self.input.as_ref().map(|input| format!("> {input}_"))
+    }
+}
+
+pub fn parse_command(line: &str) -> Result<Action, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    let number = |word: &str| word.parse::<u32>().map_err(|_| format!("Invalid number '{word}'"));
+
+    match words.as_slice() {
+        ["toggle", row, col] => Ok(Action::ToggleCell { row: number(row)?, col: number(col)? }),
+        ["paint", state @ ("on" | "off"), cells @ ..] if !cells.is_empty() && cells.len() % 2 == 0 => Ok(Action::Paint {
+            alive: *state == "on",
+            cells: cells.chunks(2).map(|cell| Ok((number(cell[0])?, number(cell[1])?))).collect::<Result<_, String>>()?,
+        }),
+        ["inspect", row, col] => Ok(Action::InspectCell { row: number(row)?, col: number(col)? }),
+        ["tool", name] => Tool::from_name(name)
+            .map(Action::SetTool)
+            .ok_or_else(|| format!("Unknown tool '{name}', expected {}", Tool::ALL.map(Tool::name).join(", "))),
+        ["select", "none"] => Ok(Action::ClearSelection),
+        ["select", row, col, rows, cols] => Ok(Action::Select(Region {
+            row: number(row)?,
+            col: number(col)?,
+            rows: number(rows)?,
+            cols: number(cols)?,
+        })),
+        ["background"] => Ok(Action::ToggleBackground),
+        ["undo"] => Ok(Action::Undo),
+        ["redo"] => Ok(Action::Redo),
+        ["analysis", "on"] => Ok(Action::ShowAnalysis(true)),
+        ["analysis", "off"] => Ok(Action::ShowAnalysis(false)),
+        ["analysis", "export", path] => Ok(Action::ExportAnalysis(path.into())),
+        ["help"] | ["help", "on"] => Ok(Action::ShowHelp(true)),
+        ["help", "off"] => Ok(Action::ShowHelp(false)),
+        ["profile", "on"] => Ok(Action::ShowProfiler(true)),
+        ["profile", "off"] => Ok(Action::ShowProfiler(false)),
+        ["heatmap", "on"] => Ok(Action::ShowHeatmap(true)),
+        ["heatmap", "off"] => Ok(Action::ShowHeatmap(false)),
+        ["diff", "mark"] => Ok(Action::MarkGeneration),
+        ["diff", "on"] => Ok(Action::ShowDiff(true)),
+        ["diff", "off"] => Ok(Action::ShowDiff(false)),
+        ["heatmap", "reset"] => Ok(Action::ResetHeatmap),
+        ["heatmap", "export", path] => Ok(Action::ExportHeatmap(path.into())),
+        ["effect", effect, "on"] => Ok(Action::SetEffect(effect.parse()?, true)),
+        ["effect", effect, "off"] => Ok(Action::SetEffect(effect.parse()?, false)),
+        ["seed", "offset", offset] => offset
+            .parse()
+            .map(|offset| Action::Seed { offset })
+            .map_err(|_| format!("Invalid offset '{offset}'")),
+        ["bam", path, region, rest @ ..] if rest.len() <= 2 => {
+            let min_depth = match rest.first() {
+                Some(depth) => depth.parse().map_err(|_| format!("Invalid depth '{depth}'"))?,
+                None => DEFAULT_MIN_DEPTH,
+            };
+            Ok(Action::SeedDepth {
+                path: path.into(),
+                region: region.parse()?,
+                min_depth,
+                reference: rest.get(1).map(|reference| reference.into()),
+            })
+        }
+        ["open", path] => Ok(Action::Open(path.into())),
+        ["open", path, record] => match record.parse::<usize>() {
+            Ok(record) if record > 0 => Ok(Action::OpenRecord { path: path.into(), record: record - 1 }),
+            _ => Err(format!("Invalid record '{record}', expected a number from 1")),
+        },
+        ["fetch", accession] => Ok(Action::Fetch(accession.to_string())),
+        ["rows", path] => Ok(Action::OpenRows(path.into())),
+        ["pattern", "load", path] => Ok(Action::LoadPattern(path.into())),
+        ["pattern", "save", path] => Ok(Action::SavePattern(path.into())),
+        ["compare", "off"] => Ok(Action::Compare(None)),
+        ["compare", path] => Ok(Action::Compare(Some((path.into(), Comparison::Aligned)))),
+        ["compare", path, comparison] => Ok(Action::Compare(Some((path.into(), comparison.parse()?)))),
+        ["frame", "off"] => Ok(Action::ReadFrame(None)),
+        ["frame", frame] => Ok(Action::ReadFrame(Some(frame.parse()?))),
+        ["preset", "save", slot] => Ok(Action::SavePreset(slot.parse()?)),
+        ["preset", "use", slot] => Ok(Action::UsePreset(slot.parse()?)),
+        ["preset", slot, preset @ ..] => Ok(Action::SetPreset(slot.parse()?, preset.join(" ").parse()?)),
+        ["msa", "off"] => Ok(Action::LoadAlignment(None)),
+        ["msa", path] => Ok(Action::LoadAlignment(Some((path.into(), None)))),
+        ["msa", path, "seed", threshold] => match threshold.parse::<f64>() {
+            Ok(threshold) if (0.0..=1.0).contains(&threshold) => Ok(Action::LoadAlignment(Some((path.into(), Some(threshold))))),
+            _ => Err(format!("Invalid threshold '{threshold}', expected a conservation between 0 and 1")),
+        },
+        ["shuffle", "off"] => Ok(Action::Shuffle(None)),
+        ["shuffle", kind] => Ok(Action::Shuffle(Some((kind.parse()?, DEFAULT_SHUFFLE_SEED)))),
+        ["shuffle", kind, seed] => {
+            let seed = seed.parse().map_err(|_| format!("Invalid seed '{seed}'"))?;
+            Ok(Action::Shuffle(Some((kind.parse()?, seed))))
+        }
+        ["mask", "off"] => Ok(Action::MaskLowComplexity(None)),
+        ["mask", mode @ ("show" | "seed"), values @ ..] if values.len() <= 3 => {
+            let mut params = ComplexityParams::default();
+            if let Some(min_run) = values.first() {
+                params.min_run = min_run.parse().map_err(|_| format!("Invalid run length '{min_run}'"))?;
+            }
+            if let Some(window) = values.get(1) {
+                params.window = window.parse().map_err(|_| format!("Invalid window '{window}'"))?;
+            }
+            if let Some(threshold) = values.get(2) {
+                params.threshold = threshold.parse().map_err(|_| format!("Invalid threshold '{threshold}'"))?;
+            }
+            Ok(Action::MaskLowComplexity(Some((params, *mode == "seed"))))
+        }
+        ["codons", "off"] => Ok(Action::CodonUsage(None)),
+        ["codons", "gff", path] => Ok(Action::CodonUsage(Some(CdsSource::Gff(path.into(), None)))),
+        ["codons", "gff", path, seqid] => Ok(Action::CodonUsage(Some(CdsSource::Gff(path.into(), Some(seqid.to_string()))))),
+        ["codons", "orf"] => Ok(Action::CodonUsage(Some(CdsSource::Orfs(DEFAULT_MIN_CODONS)))),
+        ["codons", "orf", min_codons] => min_codons
+            .parse()
+            .map(|min_codons| Action::CodonUsage(Some(CdsSource::Orfs(min_codons))))
+            .map_err(|_| format!("Invalid codon count '{min_codons}'")),
+        ["codons", "sort", key] => key.parse().map(Action::SortCodons),
+        ["codons", "export", path] => Ok(Action::ExportCodons(path.into())),
+        ["rule", rule] if rule.contains('/') => rule.parse().map(Action::SetRule),
+        ["rule", name] => Ok(Action::UseNamedRule(name.to_ascii_lowercase())),
+        ["rules"] | ["rules", "on"] => Ok(Action::ShowRuleLibrary(true)),
+        ["rules", "off"] => Ok(Action::ShowRuleLibrary(false)),
+        ["rules", "save", name, description @ ..] => Ok(Action::SaveNamedRule { name: name.to_string(), description: description.join(" ") }),
+        ["gcrule", "on"] => Ok(Action::GcRules(Some((gc_rules::DEFAULT_POOR, gc_rules::DEFAULT_RICH)))),
+        ["gcrule", "off"] => Ok(Action::GcRules(None)),
+        ["gcrule", poor, rich] => Ok(Action::GcRules(Some((poor.parse()?, rich.parse()?)))),
+        ["walk", "off"] => Ok(Action::WalkRules(None)),
+        ["walk", "mark"] => Ok(Action::MarkRule),
+        ["walk", "history"] => Ok(Action::ShowRuleHistory),
+        ["walk", "export", path] => Ok(Action::ExportRuleHistory(path.into())),
+        ["walk", interval] => match interval.parse::<u64>() {
+            Ok(interval) if interval > 0 => Ok(Action::WalkRules(Some(interval))),
+            _ => Err(format!("Invalid interval '{interval}', expected generations above zero")),
+        },
+        ["speed", speed] => match speed.parse::<f64>() {
+            Ok(speed) if (MIN_SPEED..=MAX_SPEED).contains(&speed) => Ok(Action::SetSpeed(speed)),
+            _ => Err(format!("Invalid speed '{speed}', expected {MIN_SPEED} to {MAX_SPEED} generations per second")),
+        },
+        ["turbo", "on"] => Ok(Action::Turbo(Some(turbo::DEFAULT_STEPS))),
+        ["turbo", "off"] => Ok(Action::Turbo(None)),
+        ["turbo", steps] => match steps.parse::<u32>() {
+            Ok(steps) if (1..=turbo::MAX_STEPS).contains(&steps) => Ok(Action::Turbo(Some(steps))),
+            _ => Err(format!("Invalid turbo '{steps}', expected 1 to {} generations a frame", turbo::MAX_STEPS)),
+        },
+        ["jump", generations] => generations
+            .parse()
+            .map(Action::Jump)
+            .map_err(|_| format!("Invalid generation count '{generations}'")),
+        ["trail", "on"] => Ok(Action::ShowTrail(true)),
+        ["trail", "off"] => Ok(Action::ShowTrail(false)),
+        ["blobs", "on"] => Ok(Action::TrackBlobs(true)),
+        ["blobs", "off"] => Ok(Action::TrackBlobs(false)),
+        ["blobs", "color", "on"] => Ok(Action::ColorBlobs(true)),
+        ["blobs", "color", "off"] => Ok(Action::ColorBlobs(false)),
+        ["blobs", "stats"] => Ok(Action::ShowBlobStats),
+        ["ruler", "on"] => Ok(Action::ShowRuler(true)),
+        ["ruler", "off"] => Ok(Action::ShowRuler(false)),
+        ["contour", "on"] => Ok(Action::ShowContours(true)),
+        ["contour", "off"] => Ok(Action::ShowContours(false)),
+        ["smooth", "on"] => Ok(Action::SmoothTransitions(true)),
+        ["smooth", "off"] => Ok(Action::SmoothTransitions(false)),
+        ["letters", "on"] => Ok(Action::ShowLetters(true)),
+        ["letters", "off"] => Ok(Action::ShowLetters(false)),
+        ["torus", "on"] => Ok(Action::ShowTorus(true)),
+        ["torus", "off"] => Ok(Action::ShowTorus(false)),
+        ["heightfield", "on"] => Ok(Action::ShowHeightfield(true)),
+        ["heightfield", "off"] => Ok(Action::ShowHeightfield(false)),
+        ["report", path] => Ok(Action::ExportReport(path.into())),
+        ["session", "save", path] => Ok(Action::SaveSession(path.into())),
+        ["session", "load", path] => Ok(Action::LoadSession(path.into())),
+        ["ant", "on"] => Ok(Action::Ants(Some(DEFAULT_ANTS))),
+        ["ant", "on", count] => count
+            .parse()
+            .map(|count| Action::Ants(Some(count)))
+            .map_err(|_| format!("Invalid ant count '{count}'")),
+        ["ant", "off"] => Ok(Action::Ants(None)),
+        ["ant", "stats"] => Ok(Action::ShowAntStats),
+        ["cyclic", "on"] => Ok(Action::Cyclic(Some(DEFAULT_CYCLIC_THRESHOLD))),
+        ["cyclic", "on", threshold] => match threshold.parse() {
+            Ok(threshold @ 1..=8) => Ok(Action::Cyclic(Some(threshold))),
+            _ => Err(format!("Invalid threshold '{threshold}', expected 1 to 8 neighbors")),
+        },
+        ["cyclic", "off"] => Ok(Action::Cyclic(None)),
+        ["protein", "on"] => Ok(Action::Protein(true)),
+        ["protein", "off"] => Ok(Action::Protein(false)),
+        ["lenia", "on"] => Ok(Action::Lenia(Some(KernelSource::Standard))),
+        ["lenia", "on", source] => Ok(Action::Lenia(Some(source.parse()?))),
+        ["lenia", "off"] => Ok(Action::Lenia(None)),
+        ["strip", "on"] => Ok(Action::ShowStrip(true)),
+        ["strip", "off"] => Ok(Action::ShowStrip(false)),
+        ["strip", "goto", position] => position
+            .parse()
+            .map(Action::ScrollStrip)
+            .map_err(|_| format!("Invalid sequence position '{position}'")),
+        ["track"] | ["track", "list"] => Ok(Action::ListTracks),
+        ["track", "next"] => Ok(Action::SelectTrack(None)),
+        ["track", "layer", "on"] => Ok(Action::ShowTrackLayer(true)),
+        ["track", "layer", "off"] => Ok(Action::ShowTrackLayer(false)),
+        ["track", name] => Ok(Action::SelectTrack(Some(name.to_string()))),
+        ["layer", layer, "on"] => Ok(Action::ShowLayer(layer.parse()?, true)),
+        ["layer", layer, "off"] => Ok(Action::ShowLayer(layer.parse()?, false)),
+        ["layer", layer, "up"] => Ok(Action::MoveLayer { layer: layer.parse()?, up: true }),
+        ["layer", layer, "down"] => Ok(Action::MoveLayer { layer: layer.parse()?, up: false }),
+        ["layer", layer, opacity] => match opacity.parse() {
+            Ok(opacity) if (0.0..=1.0).contains(&opacity) => Ok(Action::SetLayerOpacity(layer.parse()?, opacity)),
+            _ => Err(format!("Invalid opacity '{opacity}', expected 0 to 1")),
+        },
+        ["layers", "on"] => Ok(Action::ShowLayerPanel(true)),
+        ["layers", "off"] => Ok(Action::ShowLayerPanel(false)),
+        ["layers", spec] => Ok(Action::SetLayers(spec.parse()?)),
+        ["bookmark"] | ["bookmark", "list"] => Ok(Action::ListBookmarks),
+        ["bookmark", "save", slot, name @ ..] if name.len() <= 1 => {
+            let slot = slot.parse().map_err(|_| format!("Invalid bookmark slot '{slot}'"))?;
+            // Names that are numbers would be taken for slots
+            match name.first() {
+                Some(name) if name.parse::<usize>().is_ok() => Err(format!("Bookmark name '{name}' is a number, use a word")),
+                name => Ok(Action::SaveBookmark { slot, name: name.map(|name| name.to_string()) }),
+            }
+        }
+        ["bookmark", "goto", key] => Ok(Action::GotoBookmark(key.to_string())),
+        ["note"] | ["note", "list"] => Ok(Action::ListNotes),
+        ["note", "remove", row, col] => Ok(Action::Annotate { row: number(row)?, col: number(col)?, text: None }),
+        ["note", "export", path] => Ok(Action::ExportNotes { path: path.into(), chrom: None }),
+        ["note", "export", path, chrom] => Ok(Action::ExportNotes { path: path.into(), chrom: Some(chrom.to_string()) }),
+        ["alive", "export", path] => Ok(Action::ExportAlive { path: path.into(), chrom: None }),
+        ["alive", "export", path, chrom] => Ok(Action::ExportAlive { path: path.into(), chrom: Some(chrom.to_string()) }),
+        ["note", row, col, text @ ..] if !text.is_empty() => Ok(Action::Annotate { row: number(row)?, col: number(col)?, text: Some(text.join(" ")) }),
+        ["layout"] => Ok(Action::ShowLayout),
+        ["layout", "fit", fit] => Ok(Action::SetFit(fit.parse()?)),
+        ["layout", "padding", padding] => match padding.parse() {
+            Ok(padding) if (0.0..=MAX_PADDING).contains(&padding) => Ok(Action::SetPadding(padding)),
+            _ => Err(format!("Invalid padding '{padding}', expected 0 to {MAX_PADDING}")),
+        },
+        ["layout", "cell", "auto"] => Ok(Action::SetCellSize(None)),
+        ["layout", "cell", size] => match size.parse() {
+            Ok(size) if (1..=MAX_CELL_SIZE).contains(&size) => Ok(Action::SetCellSize(Some(size))),
+            _ => Err(format!("Invalid cell size '{size}', expected 1 to {MAX_CELL_SIZE} pixels or 'auto'")),
+        },
+        ["layout", "lod", "auto"] => Ok(Action::SetLevelOfDetail(true)),
+        ["layout", "lod", "off"] => Ok(Action::SetLevelOfDetail(false)),
+        ["layout", "pan", x, y] => match (x.parse(), y.parse()) {
+            (Ok(x), Ok(y)) => Ok(Action::SetPan([x, y])),
+            _ => Err(format!("Invalid pan '{x} {y}', expected logical pixels right and up")),
+        },
+        ["grid", size, anchor @ ..] if anchor.len() <= 1 => match universe::parse_grid_size(size) {
+            Some((rows, cols)) => Ok(Action::ResizeGrid { rows, cols, anchor: anchor.first().map_or(Ok(Anchor::default()), |anchor| anchor.parse())? }),
+            None => Err(format!("Invalid grid size '{size}', expected <rows>x<cols> like 512x512")),
+        },
+        ["contrast", "on"] => Ok(Action::HighContrast(true)),
+        ["contrast", "off"] => Ok(Action::HighContrast(false)),
+        ["announce", "off"] => Ok(Action::Announce(None)),
+        ["announce", every] => match every.parse() {
+            Ok(every) if every > 0 => Ok(Action::Announce(Some(every))),
+            _ => Err(format!("Invalid number of generations '{every}'")),
+        },
+        ["mutate", "off"] => Ok(Action::Mutate(None)),
+        ["mutate", target, rate, options @ ..] if options.len() % 2 == 0 => {
+            let rate = match rate.parse::<f64>() {
+                Ok(rate) if (0.0..=1.0).contains(&rate) => rate,
+                _ => return Err(format!("Invalid mutation rate '{rate}', expected a chance per cell between 0 and 1")),
+            };
+            let mut params = MutationParams { target: target.parse()?, rate, kappa: DEFAULT_KAPPA, seed: DEFAULT_MUTATION_SEED };
+            for option in options.chunks(2) {
+                match option {
+                    ["kappa", kappa] => match kappa.parse::<f64>() {
+                        Ok(kappa) if kappa >= 0.0 && kappa.is_finite() => params.kappa = kappa,
+                        _ => return Err(format!("Invalid transition/transversion ratio '{kappa}'")),
+                    },
+                    ["seed", seed] => params.seed = seed.parse().map_err(|_| format!("Invalid seed '{seed}'"))?,
+                    [option, _] => return Err(format!("Unknown mutation option '{option}', expected 'kappa' or 'seed'")),
+                    _ => unreachable!(),
+                }
+            }
+            Ok(Action::Mutate(Some(params)))
+        }
+        ["pause"] | ["pause", "on"] => Ok(Action::Pause(true)),
+        ["pause", "off"] => Ok(Action::Pause(false)),
+        ["export", path] => Ok(Action::ExportFrame(path.into())),
+        ["sheet", "off"] => Ok(Action::ContactSheet(None)),
+        ["sheet", path, every, size, width @ ..] if width.len() <= 1 => {
+            let every = every.parse().ok().filter(|&every| every > 0).ok_or_else(|| format!("Invalid generation interval '{every}'"))?;
+            let (rows, cols) = sheet::parse_grid(size).ok_or_else(|| format!("Invalid sheet size '{size}', expected <rows>x<cols> like 4x6, of at most {} thumbnails", sheet::MAX_TILES))?;
+            let mut settings = SheetSettings::new(path.into(), every, rows, cols);
+            if let [width] = width {
+                settings.thumbnail_width = number(width)
+                    .ok()
+                    .filter(|&width| width > 0 && width <= sheet::MAX_THUMBNAIL_WIDTH)
+                    .ok_or_else(|| format!("Invalid thumbnail width '{width}', expected 1 to {} pixels", sheet::MAX_THUMBNAIL_WIDTH))?;
+            }
+            Ok(Action::ContactSheet(Some(settings)))
+        }
+        ["table", "off"] => Ok(Action::RecordTable(None)),
+        ["hashes", "off"] => Ok(Action::RecordHashes(None)),
+        ["hashes", path] => Ok(Action::RecordHashes(Some(path.into()))),
+        ["verify", "off"] => Ok(Action::Verify(None)),
+        ["verify", path] => Ok(Action::Verify(Some(path.into()))),
+        ["table", path] => Ok(Action::RecordTable(Some((path.into(), Column::ALL.to_vec())))),
+        ["table", path, columns] => Ok(Action::RecordTable(Some((path.into(), table::parse_columns(columns)?)))),
+        ["shader", "off"] => Ok(Action::LoadShader(None)),
+        ["shader", path] => Ok(Action::LoadShader(Some(path.into()))),
+        ["signal", "off"] => Ok(Action::LoadSignal(None)),
+        ["signal", path] => Ok(Action::LoadSignal(Some((path.into(), None)))),
+        ["signal", path, chrom] => Ok(Action::LoadSignal(Some((path.into(), Some(chrom.to_string()))))),
+        ["vcf", "off"] => Ok(Action::LoadVariants(None)),
+        ["vcf", path, options @ ..] if options.len() <= 3 => {
+            let mut chrom = None;
+            let mut filter = VariantFilter { min_quality: None, kind: None };
+            for &option in options {
+                match option {
+                    "snv" => filter.kind = Some(VariantKind::Snv),
+                    "indel" => filter.kind = Some(VariantKind::Indel),
+                    "other" => filter.kind = Some(VariantKind::Other),
+                    "all" => filter.kind = None,
+                    _ => match option.parse::<f64>() {
+                        Ok(quality) => filter.min_quality = Some(quality),
+                        Err(_) => chrom = Some(option.to_string()),
+                    },
+                }
+            }
+            Ok(Action::LoadVariants(Some((path.into(), chrom, filter))))
+        }
+        ["fertility", "off"] => Ok(Action::SetBirthThreshold(None)),
+        ["fertility", threshold] => match threshold.parse::<f32>() {
+            Ok(threshold) if (0.0..=1.0).contains(&threshold) => Ok(Action::SetBirthThreshold(Some(threshold))),
+            _ => Err(format!("Invalid threshold '{threshold}', expected a value between 0 and 1")),
+        },
+        ["pcr", "off"] => Ok(Action::Pcr(None)),
+        ["pcr", forward, reverse] => Ok(Action::Pcr(Some((PrimerPair { forward: forward.to_string(), reverse: reverse.to_string() }, DEFAULT_MAX_MISMATCHES)))),
+        ["pcr", forward, reverse, max_mismatches] => match max_mismatches.parse() {
+            Ok(max_mismatches) => Ok(Action::Pcr(Some((PrimerPair { forward: forward.to_string(), reverse: reverse.to_string() }, max_mismatches)))),
+            Err(_) => Err(format!("Invalid number of mismatches '{max_mismatches}'")),
+        },
+        ["primers", "off"] => Ok(Action::DesignPrimers(None)),
+        ["primers", "copy"] => Ok(Action::CopyPrimers(None)),
+        ["primers", "copy", number] => match number.parse() {
+            Ok(number) if number > 0 => Ok(Action::CopyPrimers(Some(number))),
+            _ => Err(format!("Invalid primer number '{number}'")),
+        },
+        ["primers", options @ ..] => {
+            let mut constraints = PrimerConstraints::default();
+            let mut options = options.iter();
+            while let Some(&option) = options.next() {
+                let mut value = |what: &str| {
+                    let value = options.next().ok_or_else(|| format!("Missing {what} after '{option}'"))?;
+                    value.parse::<f64>().ok().filter(|value| *value >= 0.0 && value.is_finite()).ok_or_else(|| format!("Invalid {what} '{value}'"))
+                };
+                match option {
+                    "length" => constraints.length = (value("minimum length")? as usize, value("maximum length")? as usize),
+                    "tm" => constraints.tm = (value("minimum Tm")?, value("maximum Tm")?),
+                    "gc" => constraints.gc = (value("minimum GC")?, value("maximum GC")?),
+                    "self" => constraints.max_self_complementarity = value("self-complementarity")? as usize,
+                    _ => return Err(format!("Unknown primer constraint '{option}', expected 'length', 'tm', 'gc' or 'self'")),
+                }
+            }
+            constraints.validate()?;
+            Ok(Action::DesignPrimers(Some(constraints)))
+        }
+        ["motif", "off"] => Ok(Action::SearchMotif(None)),
+        ["signals", "off"] => Ok(Action::ShowSignals(None)),
+        ["signals", "on"] => Ok(Action::ShowSignals(Some(SignalFilter::default()))),
+        ["signals", filter @ ..] => Ok(Action::ShowSignals(Some(filter.join(" ").parse()?))),
+        ["repeats", "off"] => Ok(Action::FindRepeats(None)),
+        ["repeats", "on"] => Ok(Action::FindRepeats(Some(RepeatParams::default()))),
+        ["repeats", options @ ..] => Ok(Action::FindRepeats(Some(options.join(" ").parse()?))),
+        ["motif", motif] => match motif.strip_prefix('/').and_then(|motif| motif.strip_suffix('/')) {
+            Some(pattern) => Ok(Action::SearchMotif(Some(Motif::Regex(pattern.to_string())))),
+            None => Ok(Action::SearchMotif(Some(Motif::Iupac(motif.to_string())))),
+        },
+        ["pwm", "off"] => Ok(Action::ScanPwm(None)),
+        ["pwm", "background", "uniform"] => Ok(Action::SetPwmBackground(Background::Uniform)),
+        ["pwm", "background", "sequence"] => Ok(Action::SetPwmBackground(Background::Sequence)),
+        ["pwm", "background", a, c, g, t] => {
+            let mut frequencies = [0.0; 4];
+            for (frequency, word) in frequencies.iter_mut().zip([a, c, g, t]) {
+                *frequency = match word.parse::<f64>() {
+                    Ok(value) if value > 0.0 && value.is_finite() => value,
+                    _ => return Err(format!("Invalid frequency '{word}', expected a number above zero")),
+                };
+            }
+            Ok(Action::SetPwmBackground(Background::Frequencies(frequencies)))
+        }
+        ["pwm", path] => Ok(Action::ScanPwm(Some((path.into(), DEFAULT_PWM_THRESHOLD)))),
+        ["pwm", path, threshold] => match threshold.parse::<f64>() {
+            Ok(threshold) if (0.0..=1.0).contains(&threshold) => Ok(Action::ScanPwm(Some((path.into(), threshold)))),
+            _ => Err(format!("Invalid threshold '{threshold}', expected a relative score between 0 and 1")),
+        },
+        ["script", "off"] => Ok(Action::LoadScript(None)),
+        ["script", path] => Ok(Action::LoadScript(Some(path.into()))),
+        _ => Err(format!("Unknown command '{}'. {USAGE}", line.trim())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::Layer;
+    use crate::presets::Slot;
+    use crate::render::post::Effect;
+    use crate::mutation::MutationTarget;
+    use crate::rule::Rule;
+    use crate::seq_analysis::codon_usage::CodonSort;
+    use crate::seq_analysis::frames::ReadingFrame;
+    use crate::seq_analysis::shuffle::ShuffleKind;
+    use crate::viewport::Fit;
+
+    #[test]
+    fn parses_commands() {
+        assert_eq!(parse_command("seed offset 5000"), Ok(Action::Seed { offset: 5000 }));
+        assert_eq!(parse_command("rule B36/S23"), Ok(Action::SetRule("B36/S23".parse::<Rule>().unwrap())));
+        assert_eq!(parse_command("  speed 50 "), Ok(Action::SetSpeed(50.0)));
+        assert_eq!(parse_command("jump 1000"), Ok(Action::Jump(1000)));
+        assert_eq!(parse_command("turbo on"), Ok(Action::Turbo(Some(turbo::DEFAULT_STEPS))));
+        assert!(parse_command("turbo 0").is_err());
+        assert_eq!(parse_command("mask seed 8"), Ok(Action::MaskLowComplexity(Some((ComplexityParams { min_run: 8, ..ComplexityParams::default() }, true)))));
+        assert_eq!(parse_command("export frame.png"), Ok(Action::ExportFrame("frame.png".into())));
+        assert_eq!(parse_command("script rules/highlife.rhai"), Ok(Action::LoadScript(Some("rules/highlife.rhai".into()))));
+        assert_eq!(parse_command("script off"), Ok(Action::LoadScript(None)));
+        assert_eq!(parse_command("signal cov.bedgraph chr2"), Ok(Action::LoadSignal(Some(("cov.bedgraph".into(), Some("chr2".into()))))));
+        assert_eq!(parse_command("fertility 0.25"), Ok(Action::SetBirthThreshold(Some(0.25))));
+        assert_eq!(parse_command("bookmark save 2"), Ok(Action::SaveBookmark { slot: 2, name: None }));
+        assert!(parse_command("bookmark save 2 7").is_err());
+        assert!(parse_command("bookmark save 2 ori gin").is_err());
+        assert!(parse_command("fertility 2").is_err());
+        assert_eq!(
+            parse_command("mutate bases 0.01 seed 3"),
+            Ok(Action::Mutate(Some(MutationParams { target: MutationTarget::Bases, rate: 0.01, kappa: DEFAULT_KAPPA, seed: 3 })))
+        );
+        assert!(parse_command("mutate cells 2").is_err());
+        assert!(parse_command("mutate bases 0.01 kappa").is_err());
+        assert!(parse_command("primers length 25 18").is_err());
+        assert_eq!(parse_command("primers gc 45 55"), Ok(Action::DesignPrimers(Some(PrimerConstraints { gc: (45.0, 55.0), ..Default::default() }))));
+        assert!(parse_command("primers tm 55").is_err());
+
+        assert!(parse_command("speed 0").is_err());
+        assert!(parse_command("speed 1e-300").is_err());
+        assert!(parse_command("walk 0").is_err());
+        assert!(parse_command("seed offset -1").is_err());
+        assert!(parse_command("dance").is_err());
+        assert!(parse_command("toggle 1").is_err());
+        assert!(matches!(parse_command("bam reads.bam chr1:1-100"), Ok(Action::SeedDepth { min_depth: DEFAULT_MIN_DEPTH, .. })));
+        assert!(parse_command("bam reads.bam chr1").is_err());
+        assert_eq!(parse_command("pwm arnt.jaspar"), Ok(Action::ScanPwm(Some(("arnt.jaspar".into(), DEFAULT_PWM_THRESHOLD)))));
+        assert!(parse_command("pwm background 0.3 0 0.2 0.3").is_err());
+        assert_eq!(parse_command("codons orf"), Ok(Action::CodonUsage(Some(CdsSource::Orfs(DEFAULT_MIN_CODONS)))));
+        assert!(parse_command("codons sort gc").is_err());
+    }
+
+    #[test]
+    fn actions_round_trip_through_commands() {
+        let actions = [
+            Action::ToggleCell { row: 3, col: 7 },
+            Action::Paint { alive: true, cells: vec![(1, 2), (1, 3)] },
+            Action::Paint { alive: false, cells: vec![(0, 0)] },
+            Action::InspectCell { row: 4, col: 5 },
+            Action::SetTool(Tool::Erase),
+            Action::Select(Region { row: 1, col: 2, rows: 3, cols: 4 }),
+            Action::ClearSelection,
+            Action::Jump(1_000_000),
+            Action::Seed { offset: 42 },
+            Action::SeedDepth { path: "reads.bam".into(), region: "chr2:101-200".parse().unwrap(), min_depth: 5, reference: None },
+            Action::SeedDepth {
+                path: "reads.cram".into(),
+                region: "chr2:101-200".parse().unwrap(),
+                min_depth: 5,
+                reference: Some("ref.fa".into()),
+            },
+            Action::SetRule("B36/S23".parse().unwrap()),
+            Action::GcRules(Some(("B3/S2".parse().unwrap(), "B36/S23".parse().unwrap()))),
+            Action::GcRules(None),
+            Action::WalkRules(Some(200)),
+            Action::WalkRules(None),
+            Action::MarkRule,
+            Action::ShowRuleHistory,
+            Action::ExportRuleHistory("rules.csv".into()),
+            Action::SetSpeed(12.5),
+            Action::Turbo(Some(5000)),
+            Action::Turbo(None),
+            Action::ExportFrame("frame.png".into()),
+            Action::ContactSheet(Some(SheetSettings::new("sheet.png".into(), 50, 4, 6))),
+            Action::ContactSheet(None),
+            Action::RecordHashes(Some("run.hashes.csv".into())),
+            Action::RecordHashes(None),
+            Action::Verify(Some("run.hashes.csv".into())),
+            Action::Verify(None),
+            Action::OpenRows("aligned.fa".into()),
+            Action::LoadPattern("glider.cells".into()),
+            Action::SavePattern("out.lif".into()),
+            Action::LoadScript(Some("rules.rhai".into())),
+            Action::LoadScript(None),
+            Action::LoadSignal(Some(("cov.bedgraph".into(), Some("chr1".into())))),
+            Action::LoadSignal(None),
+            Action::LoadShader(Some("cells.wgsl".into())),
+            Action::LoadShader(None),
+            Action::SetEffect(Effect::Trails, true),
+            Action::ShowHeatmap(true),
+            Action::ShowHeatmap(false),
+            Action::MarkGeneration,
+            Action::ShowDiff(true),
+            Action::ShowDiff(false),
+            Action::ResetHeatmap,
+            Action::ExportHeatmap("heat.csv".into()),
+            Action::SetEffect(Effect::Bloom, false),
+            Action::Open("genome.fa".into()),
+            Action::OpenRecord { path: "genomes.fa".into(), record: 2 },
+            Action::Fetch("NC_000913.3".to_string()),
+            Action::Compare(Some(("strain_b.fa".into(), Comparison::Aligned))),
+            Action::Compare(Some(("strain_b.fa".into(), Comparison::Positional))),
+            Action::Compare(None),
+            Action::ReadFrame(Some(ReadingFrame::ALL[4])),
+            Action::ReadFrame(None),
+            Action::Shuffle(Some((ShuffleKind::Dinucleotide, 42))),
+            Action::Shuffle(None),
+            Action::MaskLowComplexity(Some((ComplexityParams::default(), false))),
+            Action::MaskLowComplexity(Some((ComplexityParams { min_run: 10, window: 32, threshold: 2.5 }, true))),
+            Action::MaskLowComplexity(None),
+            Action::CodonUsage(Some(CdsSource::Gff("genes.gff3".into(), None))),
+            Action::CodonUsage(Some(CdsSource::Gff("genes.gff3".into(), Some("chrM".into())))),
+            Action::CodonUsage(Some(CdsSource::Orfs(100))),
+            Action::CodonUsage(None),
+            Action::SortCodons(CodonSort::Rscu),
+            Action::SortCodons(CodonSort::AminoAcid),
+            Action::ExportCodons("codons.csv".into()),
+            Action::LoadVariants(Some(("calls.vcf".into(), Some("chr1".into()), VariantFilter { min_quality: Some(30.0), kind: Some(VariantKind::Snv) }))),
+            Action::LoadVariants(Some(("calls.vcf".into(), None, VariantFilter { min_quality: None, kind: None }))),
+            Action::LoadVariants(None),
+            Action::SetBirthThreshold(Some(0.3)),
+            Action::SetBirthThreshold(None),
+            Action::SearchMotif(Some(Motif::Iupac("GAANTC".into()))),
+            Action::SearchMotif(Some(Motif::Regex("GA+TC".into()))),
+            Action::SearchMotif(None),
+            Action::ShowSignals(Some(SignalFilter::default())),
+            Action::ShowSignals(Some("kozak sd frame -1".parse().unwrap())),
+            Action::ShowSignals(None),
+            Action::FindRepeats(Some(RepeatParams::default())),
+            Action::FindRepeats(Some(RepeatParams { unit: (1, 3), min_copies: 6 })),
+            Action::FindRepeats(None),
+            Action::Pcr(Some((PrimerPair { forward: "GTAAAACGACGGCCAGT".into(), reverse: "CAGGAAACAGCTATGAC".into() }, 2))),
+            Action::Pcr(None),
+            Action::DesignPrimers(Some(PrimerConstraints::default())),
+            Action::DesignPrimers(Some(PrimerConstraints { length: (20, 30), tm: (55.5, 65.0), ..Default::default() })),
+            Action::DesignPrimers(None),
+            Action::CopyPrimers(Some(2)),
+            Action::CopyPrimers(None),
+            Action::ScanPwm(Some(("MA0004.1.jaspar".into(), 0.85))),
+            Action::ScanPwm(None),
+            Action::LoadAlignment(Some(("globins.aln".into(), None))),
+            Action::LoadAlignment(Some(("globins.fa".into(), Some(0.5)))),
+            Action::LoadAlignment(None),
+            Action::SetPreset(Slot::B, "B36/S23 hydrophobic contrast".parse().unwrap()),
+            Action::SavePreset(Slot::A),
+            Action::UsePreset(Slot::B),
+            Action::SetPwmBackground(Background::Sequence),
+            Action::SetPwmBackground(Background::Frequencies([0.3, 0.2, 0.2, 0.3])),
+            Action::ShowTrail(true),
+            Action::ShowTrail(false),
+            Action::TrackBlobs(true),
+            Action::ColorBlobs(false),
+            Action::ShowBlobStats,
+            Action::ShowRuler(true),
+            Action::ShowRuler(false),
+            Action::ShowContours(true),
+            Action::SmoothTransitions(false),
+            Action::ShowLetters(false),
+            Action::UseNamedRule("highlife".to_string()),
+            Action::ShowRuleLibrary(false),
+            Action::SaveNamedRule { name: "flakes".to_string(), description: String::new() },
+            Action::SaveNamedRule { name: "flakes".to_string(), description: "Snowflake-like growth".to_string() },
+            Action::ShowTorus(true),
+            Action::ShowTorus(false),
+            Action::ShowHeightfield(true),
+            Action::ShowHeightfield(false),
+            Action::ExportReport("run.tsv".into()),
+            Action::SaveSession("run.session.gz".into()),
+            Action::LoadSession("run.session.gz".into()),
+            Action::Ants(Some(3)),
+            Action::Ants(None),
+            Action::ShowAntStats,
+            Action::Cyclic(Some(2)),
+            Action::Cyclic(None),
+            Action::Protein(true),
+            Action::Protein(false),
+            Action::Lenia(Some(KernelSource::Sequence)),
+            Action::Lenia(None),
+            Action::ShowStrip(false),
+            Action::ScrollStrip(1200),
+            Action::SelectTrack(Some("tm".into())),
+            Action::SelectTrack(None),
+            Action::ShowTrackLayer(true),
+            Action::ListTracks,
+            Action::ShowAnalysis(true),
+            Action::ShowAnalysis(false),
+            Action::ExportAnalysis("charts.png".into()),
+            Action::SaveBookmark { slot: 3, name: Some("origin".into()) },
+            Action::SaveBookmark { slot: 9, name: None },
+            Action::GotoBookmark("origin".into()),
+            Action::ListBookmarks,
+            Action::Annotate { row: 3, col: 4, text: Some("glider gun".to_string()) },
+            Action::Annotate { row: 3, col: 4, text: None },
+            Action::ListNotes,
+            Action::ExportNotes { path: "notes.bed".into(), chrom: None },
+            Action::ExportNotes { path: "notes.bed".into(), chrom: Some("chr1".to_string()) },
+            Action::ExportAlive { path: "alive.bed".into(), chrom: None },
+            Action::ExportAlive { path: "alive.bed".into(), chrom: Some("chr1".to_string()) },
+            Action::RecordTable(Some(("run.parquet".into(), vec![Column::Generation, Column::Cells]))),
+            Action::RecordTable(None),
+            Action::SetFit(Fit::Cover),
+            Action::SetPadding(0.05),
+            Action::ResizeGrid { rows: 64, cols: 128, anchor: Anchor::TopRight },
+            Action::SetCellSize(Some(12)),
+            Action::SetCellSize(None),
+            Action::SetPan([-40, 25]),
+            Action::SetLevelOfDetail(false),
+            Action::ShowLayout,
+            Action::ShowLayer(Layer::Bases, true),
+            Action::ShowLayer(Layer::Grid, false),
+            Action::SetLayerOpacity(Layer::Heat, 0.5),
+            Action::MoveLayer { layer: Layer::Annotations, up: false },
+            Action::MoveLayer { layer: Layer::Cells, up: true },
+            Action::SetLayers("bases,cells:0.75,-heat,annotations".parse().unwrap()),
+            Action::ShowLayerPanel(true),
+            Action::ShowLayerPanel(false),
+            Action::HighContrast(true),
+            Action::HighContrast(false),
+            Action::Announce(Some(100)),
+            Action::Announce(None),
+            Action::Mutate(Some(MutationParams { target: MutationTarget::Cells, rate: 0.001, kappa: DEFAULT_KAPPA, seed: 1 })),
+            Action::Mutate(Some(MutationParams { target: MutationTarget::Bases, rate: 0.25, kappa: 0.5, seed: 9 })),
+            Action::Mutate(None),
+            Action::Pause(true),
+            Action::Pause(false),
+            Action::ShowHelp(true),
+            Action::ShowHelp(false),
+            Action::ShowProfiler(true),
+            Action::ShowProfiler(false),
+            Action::Undo,
+            Action::Redo,
+            Action::ToggleBackground,
+        ];
+        for action in actions {
+            assert_eq!(parse_command(&action.to_string()), Ok(action));
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::render::text::{ADVANCE, GLYPH_HEIGHT, font_pixel, push_text};
+use crate::selection::Region;
+use crate::vertex::{Vertex, push_rect};
+use crate::viewport::Viewport;
+
+// The menu a right-click on a cell opens, of things to do with that cell, or with the
+// selection it is in: inspect it, note its base, reseed from it, center the grid on it and
+// copy its coordinates. It opens at the cursor, moved in to fit the window, and closes on the
+// next click, whether that picks an item or not, or on Escape.
+
+const PADDING: u32 = 3;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MenuItem {
+    Inspect,
+    Annotate,
+    Reseed,
+    Center,
+    Copy,
+}
+
+impl MenuItem {
+    pub const ALL: [MenuItem; 5] = [MenuItem::Inspect, MenuItem::Annotate, MenuItem::Reseed, MenuItem::Center, MenuItem::Copy];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MenuItem::Inspect => "Inspect sequence",
+            MenuItem::Annotate => "Annotate base",
+            MenuItem::Reseed => "Reseed from here",
+            MenuItem::Center => "Center camera",
+            MenuItem::Copy => "Copy coordinates",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContextMenu {
+    pub row: u32,
+    pub col: u32,
+    // The selection, when the cell is in it
+    pub region: Option<Region>,
+    // Clip-space point the menu was opened at, its top-left corner unless that doesn't fit
+    pub anchor: [f32; 2],
+}
+
+// Where the menu is drawn for a window size and text scale
+struct Frame {
+    pixel: [f32; 2],
+    left: f32,
+    right: f32,
+    // Top edge of the title and of each item, and the bottom edge of the last
+    edges: Vec<f32>,
+}
+
+impl ContextMenu {
+    pub fn title(&self) -> String {
+        match self.region {
+            Some(region) => format!("Cell {}, {} of {}x{}", self.row, self.col, region.rows, region.cols),
+            None => format!("Cell {}, {}", self.row, self.col),
+        }
+    }
+
+    // The cell, or the selection as the `select` command takes it
+    pub fn coordinates(&self) -> String {
+        match self.region {
+            Some(region) => format!("{} {} {} {}", region.row, region.col, region.rows, region.cols),
+            None => format!("{} {}", self.row, self.col),
+        }
+    }
+
+    fn frame(&self, viewport: &Viewport, text_scale: u32) -> Frame {
+        let pixel = font_pixel(viewport, text_scale);
+        let columns = MenuItem::ALL.iter().map(|item| item.label().len()).chain([self.title().len()]).max().unwrap_or(0) as u32;
+        let width = (columns * ADVANCE + 2 * PADDING) as f32 * pixel[0];
+        let row_height = (GLYPH_HEIGHT + 2 * PADDING) as f32 * pixel[1];
+        let height = (MenuItem::ALL.len() + 1) as f32 * row_height;
+        let left = self.anchor[0].min(1.0 - width).max(-1.0);
+        let top = self.anchor[1].max(-1.0 + height).min(1.0);
+        let edges = (0..=MenuItem::ALL.len() + 1).map(|i| top - i as f32 * row_height).collect();
+        Frame { pixel, left, right: left + width, edges }
+    }
+
+    // Item under a clip-space point; the title isn't one
+    pub fn item_at(&self, viewport: &Viewport, text_scale: u32, [x, y]: [f32; 2]) -> Option<MenuItem> {
+        let frame = self.frame(viewport, text_scale);
+        if !(frame.left..frame.right).contains(&x) {
+            return None;
+        }
+        let row = frame.edges[1..].windows(2).position(|edges| (edges[1]..edges[0]).contains(&y))?;
+        Some(MenuItem::ALL[row])
+    }
+
+    pub fn vertices(&self, viewport: &Viewport, text_scale: u32, hovered: Option<MenuItem>) -> Vec<Vertex> {
+        let frame = self.frame(viewport, text_scale);
+        let [pixel_x, pixel_y] = frame.pixel;
+        let (top, bottom) = (frame.edges[0], *frame.edges.last().unwrap());
+        let mut vertices = Vec::new();
+        push_rect(&mut vertices, [frame.left - pixel_x / 2.0, bottom - pixel_y / 2.0], [frame.right + pixel_x / 2.0, top + pixel_y / 2.0], [0.4, 0.4, 0.5]);
+        push_rect(&mut vertices, [frame.left, bottom], [frame.right, top], [0.03, 0.03, 0.06]);
+        let text_at = |edges: &[f32]| [frame.left + PADDING as f32 * pixel_x, edges[1] + PADDING as f32 * pixel_y];
+        push_text(&mut vertices, &self.title(), text_at(&frame.edges[0..2]), frame.pixel, [0.6, 0.6, 0.7]);
+        for (item, edges) in MenuItem::ALL.into_iter().zip(frame.edges[1..].windows(2)) {
+            if hovered == Some(item) {
+                push_rect(&mut vertices, [frame.left, edges[1]], [frame.right, edges[0]], [0.2, 0.2, 0.3]);
+            }
+            push_text(&mut vertices, item.label(), text_at(edges), frame.pixel, [0.85, 0.85, 0.85]);
+        }
+        vertices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_items_and_fits_the_window() {
+        let viewport = Viewport { width: 800, height: 600, scale_factor: 1.0 };
+        let menu = ContextMenu { row: 3, col: 4, region: None, anchor: [0.0, 0.0] };
+        let row_height = (GLYPH_HEIGHT + 2 * PADDING) as f32 * font_pixel(&viewport, 1)[1];
+        let item = |i: usize| menu.item_at(&viewport, 1, [0.01, -(i as f32 + 0.5) * row_height]);
+        // The title takes the first row
+        assert_eq!(item(0), None);
+        assert_eq!(item(1), Some(MenuItem::Inspect));
+        assert_eq!(item(5), Some(MenuItem::Copy));
+        assert_eq!(item(6), None);
+        assert_eq!(menu.item_at(&viewport, 1, [-0.01, -1.5 * row_height]), None);
+
+        // Opened in the bottom-right corner, it moves up and left to stay in the window
+        let corner = ContextMenu { anchor: [0.99, -0.99], ..menu.clone() };
+        let vertices = corner.vertices(&viewport, 1, Some(MenuItem::Center));
+        assert!(vertices.iter().all(|vertex| vertex.position[0] <= 1.01 && vertex.position[1] >= -1.01));
+        assert!(vertices.len() > menu.vertices(&viewport, 1, None).len());
+
+        let region = ContextMenu { region: Some(Region { row: 2, col: 1, rows: 5, cols: 6 }), ..menu.clone() };
+        assert_eq!((menu.coordinates(), region.coordinates()), ("3 4".to_string(), "2 1 5 6".to_string()));
+        assert_eq!(region.title(), "Cell 3, 4 of 5x6");
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+// Outlines of the connected live regions (`contour`, C), traced with marching squares over
+// the cell centers. Diagonal neighbors count as connected, as they do for the rule, so a
+// glider is one outline rather than three. Cells outside the traced range count as dead, so
+// every outline closes, including those of regions cut by the range or the grid's edges.
+// Outlines are only traced when the cells or the range change, and only up to MAX_TRACED
+// cells at a time.
+
+// Chaikin corner-cutting rounds applied to each outline
+const SMOOTHING: usize = 2;
+// Most cells traced at once; zoomed out further, no outlines are drawn
+pub const MAX_TRACED: usize = 1 << 20;
+
+// Point in doubled cell units, so the edge midpoints the outlines pass through are integers
+type Point = (i64, i64);
+
+// Closed outlines around the live cells in `rows` by `cols` of the grid, as (column, row) in
+// cell units with the cell centers on whole numbers, smoothed
+pub fn contours(cells: &[bool], grid_cols: u32, rows: Range<u32>, cols: Range<u32>) -> Vec<Vec<[f32; 2]>> {
+    let alive = |row: i64, col: i64| {
+        row >= 0 && col >= 0 && rows.contains(&(row as u32)) && cols.contains(&(col as u32)) && cells[(row * grid_cols as i64 + col) as usize]
+    };
+
+    // One square per 2x2 block of cell centers, starting one cell before the range
+    let mut segments = Vec::new();
+    for row in rows.start as i64 - 1..rows.end as i64 {
+        for col in cols.start as i64 - 1..cols.end as i64 {
+            let case = alive(row, col) as u8 | (alive(row, col + 1) as u8) << 1 | (alive(row + 1, col + 1) as u8) << 2 | (alive(row + 1, col) as u8) << 3;
+            let (north, south) = ((2 * col + 1, 2 * row), (2 * col + 1, 2 * row + 2));
+            let (west, east) = ((2 * col, 2 * row + 1), (2 * col + 2, 2 * row + 1));
+            let pairs: &[(Point, Point)] = match case {
+                1 | 14 => &[(north, west)],
+                2 | 13 => &[(north, east)],
+                3 | 12 => &[(west, east)],
+                4 | 11 => &[(east, south)],
+                6 | 9 => &[(north, south)],
+                7 | 8 => &[(west, south)],
+                // Diagonal pairs stay joined, cutting off the two dead corners instead
+                5 => &[(north, east), (west, south)],
+                10 => &[(north, west), (east, south)],
+                _ => &[],
+            };
+            segments.extend_from_slice(pairs);
+        }
+    }
+
+    // Every midpoint is shared by exactly two segments, so following them from any point
+    // comes back to it
+    let mut neighbors: HashMap<Point, Vec<Point>> = HashMap::new();
+    for &(a, b) in &segments {
+        neighbors.entry(a).or_default().push(b);
+        neighbors.entry(b).or_default().push(a);
+    }
+    let mut visited = HashSet::new();
+    let mut outlines = Vec::new();
+    for &(start, _) in &segments {
+        if visited.contains(&start) {
+            continue;
+        }
+        let mut outline = Vec::new();
+        let (mut previous, mut point) = (start, start);
+        loop {
+            visited.insert(point);
+            outline.push([point.0 as f32 / 2.0, point.1 as f32 / 2.0]);
+            let Some(&next) = neighbors[&point].iter().find(|&&next| next != previous && !visited.contains(&next)) else {
+                break;
+            };
+            (previous, point) = (point, next);
+        }
+        outlines.push(smooth(outline, SMOOTHING));
+    }
+    outlines
+}
+
+// The outlines last traced, with the cells and range they came from, so redraws of the same
+// generation and view reuse them
+#[derive(Default)]
+pub struct Contours {
+    cells: Vec<bool>,
+    rows: Range<u32>,
+    cols: Range<u32>,
+    outlines: Vec<Vec<[f32; 2]>>,
+}
+
+impl Contours {
+    // `contours` of the range, traced again only if something changed, or none past MAX_TRACED
+    pub fn trace(&mut self, cells: &[bool], grid_cols: u32, rows: Range<u32>, cols: Range<u32>) -> &[Vec<[f32; 2]>] {
+        if rows.len() * cols.len() > MAX_TRACED {
+            return &[];
+        }
+        if self.cells != cells || self.rows != rows || self.cols != cols {
+            self.outlines = contours(cells, grid_cols, rows.clone(), cols.clone());
+            self.cells.clear();
+            self.cells.extend_from_slice(cells);
+            (self.rows, self.cols) = (rows, cols);
+        }
+        &self.outlines
+    }
+}
+
+// Chaikin's corner cutting on a closed outline: each round replaces every edge with points a
+// quarter and three quarters along it
+fn smooth(mut outline: Vec<[f32; 2]>, rounds: usize) -> Vec<[f32; 2]> {
+    let lerp = |a: [f32; 2], b: [f32; 2], t: f32| [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t];
+    for _ in 0..rounds {
+        outline = (0..outline.len())
+            .flat_map(|i| {
+                let (a, b) = (outline[i], outline[(i + 1) % outline.len()]);
+                [lerp(a, b, 0.25), lerp(a, b, 0.75)]
+            })
+            .collect();
+    }
+    outline
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn traces_one_outline_per_region() {
+        // A block, and three cells touching at their corners
+        let grid = [
+            "OO....",
+            "OO..O.",
+            ".....O",
+            "....O.",
+        ];
+        let cells: Vec<bool> = grid.concat().bytes().map(|cell| cell == b'O').collect();
+        let outlines = contours(&cells, 6, 0..4, 0..6);
+        assert_eq!(outlines.len(), 2);
+        // The block's outline passes through the midpoints around its four cells: 8 of them,
+        // doubled twice by the smoothing
+        assert_eq!(outlines[0].len(), 8 * 4);
+        for point in &outlines[0] {
+            assert!((-0.5..=1.5).contains(&point[0]) && (-0.5..=1.5).contains(&point[1]), "{point:?}");
+        }
+        // The diagonal cells are one region
+        assert!(outlines[1].iter().all(|point| point[0] >= 3.5 && point[1] >= 0.5));
+
+        // Without the last column the diagonal splits in two
+        assert_eq!(contours(&cells, 6, 0..4, 0..5).len(), 3);
+        assert!(contours(&[false; 24], 6, 0..4, 0..6).is_empty());
+
+        let mut cached = Contours::default();
+        assert_eq!(cached.trace(&cells, 6, 0..4, 0..6).len(), 2);
+        assert_eq!(cached.trace(&cells, 6, 0..4, 0..5).len(), 3);
+        let mut cleared = cells.clone();
+        cleared[0] = false;
+        cleared[1] = false;
+        assert_eq!(cached.trace(&cleared, 6, 0..4, 0..5).len(), 3);
+        cleared[6] = false;
+        cleared[7] = false;
+        assert_eq!(cached.trace(&cleared, 6, 0..4, 0..5).len(), 2);
+        assert!(cached.trace(&vec![true; MAX_TRACED + 2], 2, 0..MAX_TRACED as u32 / 2 + 1, 0..2).is_empty());
+    }
+}
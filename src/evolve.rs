@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::fnv::cells_hash;
+use crate::rule::Rule;
+use crate::seq_analysis::shuffle::Rng;
+use crate::universe::Universe;
+
+// Rule evolution (`evolve`): a genetic algorithm searching Life-like rules, and where in the
+// sequence to seed from, for the runs that score best on a fitness function. Candidates are
+// run headlessly in parallel; all randomness comes from one seeded generator in the search
+// loop, so a seed gives the same rules on any number of threads.
+
+// Every candidate keeps a population per generation, so runs are capped at this length
+pub const MAX_GENERATIONS: u64 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EvolveSettings {
+    // Rounds of selection, and candidates in each round
+    pub rounds: u32,
+    pub population: usize,
+    // Generations each candidate is run for, on a grid of this size
+    pub generations: u64,
+    pub rows: u32,
+    pub cols: u32,
+    pub seed: u64,
+    pub threads: usize,
+}
+
+impl Default for EvolveSettings {
+    fn default() -> Self {
+        Self { rounds: 20, population: 32, generations: 300, rows: 64, cols: 64, seed: 1, threads: 1 }
+    }
+}
+
+// A candidate: the rule, and the sequence offset its grid is seeded from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Genome {
+    pub rule: Rule,
+    pub offset: usize,
+}
+
+// What a candidate's run looked like, for fitness functions to score
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trajectory {
+    pub cells: usize,
+    // Live cells at every generation from 0 to the end of the run
+    pub populations: Vec<usize>,
+    // First generation of the cycle the run settled into and its period, if it did
+    pub cycle: Option<(u64, u64)>,
+    // Block entropy of the last grid stepped, 0 to 1
+    pub entropy: f64,
+}
+
+pub trait Fitness: Sync {
+    // Higher is better
+    fn score(&self, trajectory: &Trajectory) -> f64;
+}
+
+// Mean fraction of cells alive over the second half of the run
+pub struct Population;
+
+impl Fitness for Population {
+    fn score(&self, trajectory: &Trajectory) -> f64 {
+        let late = &trajectory.populations[trajectory.populations.len() / 2..];
+        late.iter().sum::<usize>() as f64 / (late.len() * trajectory.cells).max(1) as f64
+    }
+}
+
+// How disordered the last grid is: random-looking grids score near 1, empty or uniform ones 0
+pub struct Entropy;
+
+impl Fitness for Entropy {
+    fn score(&self, trajectory: &Trajectory) -> f64 {
+        trajectory.entropy
+    }
+}
+
+// How much of the run passed before it settled into a cycle; 1 when it never did
+pub struct Longevity;
+
+impl Fitness for Longevity {
+    fn score(&self, trajectory: &Trajectory) -> f64 {
+        let generations = trajectory.populations.len().saturating_sub(1).max(1) as f64;
+        trajectory.cycle.map_or(1.0, |(start, _)| start as f64 / generations)
+    }
+}
+
+pub const FITNESS_NAMES: &str = "entropy, population or longevity";
+
+pub fn fitness(name: &str) -> Result<Box<dyn Fitness>, String> {
+    match name {
+        "entropy" => Ok(Box::new(Entropy)),
+        "population" => Ok(Box::new(Population)),
+        "longevity" => Ok(Box::new(Longevity)),
+        _ => Err(format!("Unknown fitness '{name}', expected {FITNESS_NAMES}")),
+    }
+}
+
+// Shannon entropy of the 2x2 blocks tiling the grid, over the 4 bits a block holds. Each term
+// is p·log2(1/p) rather than -p·log2(p), which is -0 for p = 1, so uniform grids score 0
+// and not the -0.0000 the sweep CSV and evolve TSV would print.
+pub fn block_entropy(cells: &[bool], rows: u32, cols: u32) -> f64 {
+    let mut counts = [0usize; 16];
+    for row in (0..rows - rows % 2).step_by(2) {
+        for col in (0..cols - cols % 2).step_by(2) {
+            let alive = |r: u32, c: u32| cells[(r * cols + c) as usize] as usize;
+            counts[alive(row, col) | alive(row, col + 1) << 1 | alive(row + 1, col) << 2 | alive(row + 1, col + 1) << 3] += 1;
+        }
+    }
+    let total = counts.iter().sum::<usize>().max(1) as f64;
+    counts.iter().filter(|&&count| count > 0).map(|&count| count as f64 / total).map(|p| p * (1.0 / p).log2()).sum::<f64>() / 4.0
+}
+
+// Run `genome` on a grid seeded from `dna`. Once the run repeats an earlier state it is
+// stopped, and the rest of its populations are filled in from the cycle.
+pub fn simulate(dna: &[u8], genome: Genome, settings: &EvolveSettings) -> Trajectory {
+    let mut universe = Universe::new(settings.rows, settings.cols, dna.get(genome.offset..).unwrap_or_default());
+    universe.rule = genome.rule;
+    let mut seen = HashMap::new();
+    let generations = settings.generations.min(MAX_GENERATIONS);
+    let mut populations = Vec::new();
+    let mut cycle = None;
+    loop {
+        match seen.entry(cells_hash(&universe.cells)) {
+            Entry::Occupied(first) => {
+                cycle = Some((*first.get(), universe.generation - first.get()));
+                break;
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(universe.generation);
+            }
+        }
+        populations.push(universe.cells.iter().filter(|&&alive| alive).count());
+        if universe.generation >= generations {
+            break;
+        }
+        universe.tick();
+    }
+    if let Some((start, period)) = cycle {
+        while populations.len() <= generations as usize {
+            let generation = populations.len() as u64;
+            populations.push(populations[(start + (generation - start) % period) as usize]);
+        }
+    }
+    let entropy = block_entropy(&universe.cells, universe.rows, universe.cols);
+    Trajectory { cells: universe.cells.len(), populations, cycle, entropy }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scored {
+    pub genome: Genome,
+    pub fitness: f64,
+    pub cycle: Option<(u64, u64)>,
+    pub final_population: usize,
+    // Round the genome first came up in
+    pub round: u32,
+}
+
+// B0 rules switch every dead cell on each generation, so births start at 1 neighbor
+const BIRTH_BITS: u16 = 0b1_1111_1110;
+const SURVIVE_BITS: u16 = 0b1_1111_1111;
+
+fn random_genome(rng: &mut Rng, offsets: usize) -> Genome {
+    let mut bits = |mask: u16, p: f64| (0..9).filter(|n| mask & (1 << n) != 0 && rng.unit() < p).fold(0, |bits, n| bits | (1 << n));
+    let rule = Rule { birth: bits(BIRTH_BITS, 0.25), survive: bits(SURVIVE_BITS, 0.3) };
+    Genome { rule, offset: rng.below(offsets) }
+}
+
+// Each neighbor count of each parent's rule with even odds, and one parent's offset
+fn crossover(rng: &mut Rng, a: Genome, b: Genome) -> Genome {
+    let mut mix = |a: u16, b: u16| (0..9).fold(0, |bits, n| bits | ((if rng.unit() < 0.5 { a } else { b }) & (1 << n)));
+    let rule = Rule { birth: mix(a.rule.birth, b.rule.birth), survive: mix(a.rule.survive, b.rule.survive) };
+    Genome { rule, offset: if rng.unit() < 0.5 { a.offset } else { b.offset } }
+}
+
+// About one of the 17 neighbor counts flipped, and now and then another offset
+fn mutate(rng: &mut Rng, genome: Genome, offsets: usize) -> Genome {
+    let mut flip = |mask: u16| (0..9).filter(|n| mask & (1 << n) != 0 && rng.unit() < 1.0 / 17.0).fold(0, |bits, n| bits | (1 << n));
+    let rule = Rule { birth: genome.rule.birth ^ flip(BIRTH_BITS), survive: genome.rule.survive ^ flip(SURVIVE_BITS) };
+    let offset = if rng.unit() < 0.2 { rng.below(offsets) } else { genome.offset };
+    Genome { rule, offset }
+}
+
+// The best of three candidates picked at random
+fn tournament<'a>(rng: &mut Rng, ranked: &'a [Scored]) -> &'a Scored {
+    (0..3).map(|_| &ranked[rng.below(ranked.len())]).max_by(|a, b| a.fitness.total_cmp(&b.fitness)).expect("three picks")
+}
+
+// Best first; ties go to the simpler rule, then the lower offset, so the order never depends
+// on the order candidates finished in
+fn rank(scored: &mut [Scored]) {
+    let key = |scored: &Scored| (scored.genome.rule.birth.count_ones() + scored.genome.rule.survive.count_ones(), scored.genome.rule.birth, scored.genome.rule.survive, scored.genome.offset);
+    scored.sort_by(|a, b| b.fitness.total_cmp(&a.fitness).then_with(|| key(a).cmp(&key(b))));
+}
+
+// Score `genomes` on up to `threads` threads, in their order
+fn evaluate(dna: &[u8], genomes: &[Genome], settings: &EvolveSettings, fitness: &dyn Fitness, round: u32) -> Vec<Scored> {
+    let next = AtomicUsize::new(0);
+    let scored = Mutex::new(vec![None; genomes.len()]);
+    std::thread::scope(|scope| {
+        for _ in 0..settings.threads.clamp(1, genomes.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(&genome) = genomes.get(index) else { break };
+                    let trajectory = simulate(dna, genome, settings);
+                    let score = Scored {
+                        genome,
+                        fitness: fitness.score(&trajectory),
+                        cycle: trajectory.cycle,
+                        final_population: trajectory.populations.last().copied().unwrap_or_default(),
+                        round,
+                    };
+                    scored.lock().unwrap()[index] = Some(score);
+                }
+            });
+        }
+    });
+    scored.into_inner().unwrap().into_iter().flatten().collect()
+}
+
+// Evolve rules for runs seeded from `dna`, calling `finished` with the ranked population
+// after each round. Conway's Life seeded from the start of the sequence is one of the first
+// candidates, so the best rule found never scores below it. Setting `cancel` stops after the
+// current round. Returns every distinct genome scored, best first.
+pub fn evolve(dna: &[u8], settings: &EvolveSettings, fitness: &dyn Fitness, cancel: &AtomicBool, mut finished: impl FnMut(u32, &[Scored])) -> Vec<Scored> {
+    let mut rng = Rng::new(settings.seed);
+    let offsets = dna.len().saturating_sub((settings.rows * settings.cols) as usize).max(1);
+    let size = settings.population.max(2);
+    let mut candidates = vec![Genome { rule: Rule::CONWAY, offset: 0 }];
+    candidates.extend((1..size).map(|_| random_genome(&mut rng, offsets)));
+
+    let mut scores: HashMap<Genome, Scored> = HashMap::new();
+    for round in 1..=settings.rounds.max(1) {
+        // Genomes scored in an earlier round keep their score, since runs are deterministic
+        let mut fresh: Vec<Genome> = candidates.iter().copied().filter(|genome| !scores.contains_key(genome)).collect();
+        fresh.sort_by_key(|genome| (genome.rule.birth, genome.rule.survive, genome.offset));
+        fresh.dedup();
+        for scored in evaluate(dna, &fresh, settings, fitness, round) {
+            scores.insert(scored.genome, scored);
+        }
+        let mut ranked: Vec<Scored> = candidates.iter().map(|genome| scores[genome].clone()).collect();
+        rank(&mut ranked);
+        // Copies of a genome rank next to each other
+        ranked.dedup_by_key(|scored| scored.genome);
+        finished(round, &ranked);
+        if round == settings.rounds || cancel.load(Ordering::Relaxed) {
+            break;
+        }
+
+        // The two best carry over unchanged; the rest are children of tournament winners
+        candidates = ranked.iter().take(2).map(|scored| scored.genome).collect();
+        while candidates.len() < size {
+            let (a, b) = (tournament(&mut rng, &ranked).genome, tournament(&mut rng, &ranked).genome);
+            let child = crossover(&mut rng, a, b);
+            candidates.push(mutate(&mut rng, child, offsets));
+        }
+    }
+    let mut all: Vec<Scored> = scores.into_values().collect();
+    rank(&mut all);
+    all
+}
+
+const COLUMNS: &str = "rule\toffset\tfitness\tstabilized_at\tperiod\tfinal_population\tround";
+
+// One row per genome under a header, NA for the cycle of runs that never settled
+pub fn to_tsv(scored: &[Scored]) -> String {
+    let mut tsv = format!("{COLUMNS}\n");
+    for scored in scored {
+        let (stabilized_at, period) = scored.cycle.map_or(("NA".to_string(), "NA".to_string()), |(start, period)| (start.to_string(), period.to_string()));
+        tsv.push_str(&format!(
+            "{}\t{}\t{:.4}\t{stabilized_at}\t{period}\t{}\t{}\n",
+            scored.genome.rule, scored.genome.offset, scored.fitness, scored.final_population, scored.round
+        ));
+    }
+    tsv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::tests::BLINKER;
+
+    #[test]
+    fn scores_trajectories() {
+        let settings = EvolveSettings { generations: 10, rows: 5, cols: 5, ..Default::default() };
+        let trajectory = simulate(BLINKER, Genome { rule: Rule::CONWAY, offset: 0 }, &settings);
+        assert_eq!(trajectory.cycle, Some((0, 2)));
+        assert_eq!(trajectory.populations, [3; 11]);
+        assert_eq!(Longevity.score(&trajectory), 0.0);
+        assert!((Population.score(&trajectory) - 3.0 / 25.0).abs() < 1e-9);
+
+        assert_eq!(format!("{:.4}", block_entropy(&[false; 16], 4, 4)), "0.0000");
+        assert_eq!(format!("{:.4}", block_entropy(&[true; 16], 4, 4)), "0.0000");
+        // Every one of the 16 block patterns once
+        let mut cells = vec![false; 8 * 8];
+        for pattern in 0..16u32 {
+            let (row, col) = (pattern / 4 * 2, pattern % 4 * 2);
+            for bit in 0..4 {
+                cells[((row + bit / 2) * 8 + col + bit % 2) as usize] = pattern & (1 << bit) != 0;
+            }
+        }
+        assert_eq!(block_entropy(&cells, 8, 8), 1.0);
+        assert!(fitness("novelty").is_err());
+    }
+
+    #[test]
+    fn rng_unit_draws_evenly_from_zero_to_one() {
+        // `Rng::unit` decides evolve's mutations, so its draws must cover [0, 1) without a lean
+        let mut rng = Rng::new(5);
+        let draws: Vec<f64> = (0..10_000).map(|_| rng.unit()).collect();
+        assert!(draws.iter().all(|&draw| (0.0..1.0).contains(&draw)));
+        let mean = draws.iter().sum::<f64>() / draws.len() as f64;
+        assert!((mean - 0.5).abs() < 0.01, "mean {mean}");
+        assert!(draws.iter().any(|&draw| draw < 0.01) && draws.iter().any(|&draw| draw > 0.99));
+    }
+
+    #[test]
+    fn evolves_reproducibly_on_any_number_of_threads() {
+        let dna: Vec<u8> = (0..2000u32).map(|i| b"ACGT"[(i * 7 + i / 3) as usize % 4]).collect();
+        let settings = EvolveSettings { rounds: 4, population: 12, generations: 60, rows: 16, cols: 16, seed: 3, threads: 1 };
+        let cancel = AtomicBool::new(false);
+        let mut best = Vec::new();
+        let one = evolve(&dna, &settings, &Entropy, &cancel, |_, ranked| best.push(ranked[0].fitness));
+        let four = evolve(&dna, &EvolveSettings { threads: 4, ..settings }, &Entropy, &cancel, |_, _| {});
+        assert_eq!(one, four);
+        // Elitism keeps the best score from going down, and it starts no lower than Life's
+        assert!(best.windows(2).all(|pair| pair[1] >= pair[0]));
+        let conway = one.iter().find(|scored| scored.genome == Genome { rule: Rule::CONWAY, offset: 0 }).expect("Life is scored");
+        assert!(one[0].fitness >= conway.fitness);
+        assert!(one.windows(2).all(|pair| pair[0].fitness >= pair[1].fitness));
+        assert!(to_tsv(&one[..1]).starts_with("rule\toffset\tfitness\t"));
+        assert_eq!(to_tsv(&one).lines().count(), one.len() + 1);
+    }
+}
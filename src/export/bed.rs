@@ -0,0 +1,64 @@
+use std::ops::Range;
+
+use crate::strip::SeedMap;
+
+// Live cells as sequence intervals (`alive export`), for intersecting the regions where life
+// thrives with real annotations in bedtools or a genome browser. Each live cell stands for the
+// base it was seeded from, wherever the layout put it: bases next to each other in the
+// sequence merge into one interval even when their cells are on different rows, and cells
+// seeded past the end of the sequence have no base to stand for. Cell notes (`note export`)
+// are written the same way, one base each.
+
+// Chromosome the intervals are on when none is given
+pub const DEFAULT_CHROM: &str = "sequence";
+
+// Positions of the live cells' bases, merged into sorted, non-overlapping half-open intervals
+pub fn alive_intervals(cells: &[bool], cols: u32, seed_map: &SeedMap, len: usize) -> Vec<Range<usize>> {
+    let mut positions: Vec<usize> = (0..cells.len() as u32)
+        .filter(|&idx| cells[idx as usize])
+        .filter_map(|idx| seed_map.position(idx / cols, idx % cols))
+        .filter(|&position| position < len)
+        .collect();
+    positions.sort_unstable();
+    positions.dedup();
+    let mut intervals: Vec<Range<usize>> = Vec::new();
+    for position in positions {
+        match intervals.last_mut() {
+            Some(last) if last.end == position => last.end += 1,
+            _ => intervals.push(position..position + 1),
+        }
+    }
+    intervals
+}
+
+// BED4: one `chrom start end name` line per named interval, 0-based and half-open
+pub fn to_bed<'a>(intervals: impl IntoIterator<Item = (Range<usize>, &'a str)>, chrom: Option<&str>) -> String {
+    let chrom = chrom.unwrap_or(DEFAULT_CHROM);
+    intervals.into_iter().map(|(interval, name)| format!("{chrom}\t{}\t{}\t{name}\n", interval.start, interval.end)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selection::Region;
+
+    #[test]
+    fn merges_neighboring_bases_across_rows() {
+        // A 3x4 grid seeded from base 100, its last row reseeded from base 10
+        let mut seed_map = SeedMap::new(3, 4);
+        seed_map.record(&Region::full(3, 4), 100);
+        seed_map.record(&Region { row: 2, col: 0, rows: 1, cols: 4 }, 10);
+        let cells = [
+            false, false, true, true, //
+            true, false, false, true, //
+            true, true, false, true,
+        ];
+        // 102-103 and 104 are on two rows but next to each other in the sequence
+        let intervals = alive_intervals(&cells, 4, &seed_map, 1000);
+        assert_eq!(intervals, vec![10..12, 13..14, 102..105, 107..108]);
+        assert_eq!(to_bed([(intervals[0].clone(), "alive")], Some("chr1")), "chr1\t10\t12\talive\n");
+        assert_eq!(to_bed([(7..8, "oscillator near the origin"), (120..121, "puffer")], None), "sequence\t7\t8\toscillator near the origin\nsequence\t120\t121\tpuffer\n");
+        // Cells past the end of a short sequence have no base
+        assert_eq!(alive_intervals(&cells, 4, &seed_map, 104), vec![10..12, 13..14, 102..104]);
+    }
+}
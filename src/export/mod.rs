@@ -0,0 +1,6 @@
+pub mod bed;
+pub mod png;
+pub mod sheet;
+pub mod svg;
+pub mod table;
+pub mod video;
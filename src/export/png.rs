@@ -0,0 +1,60 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::provenance::Provenance;
+
+// Write tightly packed RGBA8 pixels (top row first) as a PNG file, with an iTXt chunk for
+// each entry of the provenance. The image is encoded before the file is created, so a
+// failure leaves an existing file as it was.
+pub fn write_rgba(path: &Path, width: u32, height: u32, pixels: &[u8], provenance: Option<&Provenance>) -> io::Result<()> {
+    let _span = tracing::debug_span!("write", path = %path.display()).entered();
+    tracing::debug!("{width}x{height} pixels");
+    let mut bytes = Vec::new();
+    encode_rgba(&mut bytes, width, height, pixels, provenance)?;
+    BufWriter::new(File::create(path)?).write_all(&bytes)
+}
+
+pub fn encode_rgba(writer: impl Write, width: u32, height: u32, pixels: &[u8], provenance: Option<&Provenance>) -> io::Result<()> {
+    let mut encoder = ::png::Encoder::new(writer, width, height);
+    encoder.set_color(::png::ColorType::Rgba);
+    encoder.set_depth(::png::BitDepth::Eight);
+    // iTXt rather than tEXt, which only holds Latin-1, since paths and names can be anything
+    for (key, value) in provenance.map(Provenance::fields).unwrap_or_default() {
+        encoder.add_itxt_chunk(format!("bio-rust {key}"), value).map_err(io::Error::other)?;
+    }
+
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer.write_image_data(pixels).map_err(io::Error::other)?;
+    writer.finish().map_err(io::Error::other)
+}
+
+// Read a PNG file back as tightly packed RGBA8 pixels, top row first
+pub fn read_rgba(path: &Path) -> io::Result<(u32, u32, Vec<u8>)> {
+    let mut decoder = ::png::Decoder::new(File::open(path)?);
+    decoder.set_transformations(::png::Transformations::EXPAND | ::png::Transformations::ALPHA);
+    let mut reader = decoder.read_info().map_err(io::Error::other)?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).map_err(io::Error::other)?;
+    if info.color_type != ::png::ColorType::Rgba || info.bit_depth != ::png::BitDepth::Eight {
+        return Err(io::Error::other(format!("{} is not 8-bit RGBA", path.display())));
+    }
+    pixels.truncate(info.buffer_size());
+    Ok((info.width, info.height, pixels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_provenance_outside_latin1() {
+        let provenance = Provenance::new("/données/基因组.fa", 0x1234, 0, "B3/S23".to_string(), None, 7);
+        let mut bytes = Vec::new();
+        encode_rgba(&mut bytes, 1, 1, &[10, 20, 30, 255], Some(&provenance)).unwrap();
+        let reader = ::png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        let text = |key: &str| reader.info().utf8_text.iter().find(|chunk| chunk.keyword == key).map(|chunk| chunk.get_text().unwrap());
+        assert_eq!(text("bio-rust input").as_deref(), Some("/données/基因组.fa"));
+        assert_eq!(text("bio-rust generation").as_deref(), Some("7"));
+    }
+}
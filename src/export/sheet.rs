@@ -0,0 +1,211 @@
+use std::path::PathBuf;
+
+use crate::provenance::Provenance;
+use crate::render::text;
+
+// Contact sheets (`sheet`, `--contact-sheet`): a frame captured every `every` generations,
+// shrunk to a thumbnail, and the thumbnails laid out row by row in one PNG with the
+// generation under each, as a figure of how a run unfolds.
+
+pub const DEFAULT_THUMBNAIL_WIDTH: u32 = 320;
+// `--contact-sheet` without `--sheet-every` and `--sheet-grid`: 24 thumbnails, 100 generations apart
+pub const DEFAULT_EVERY: u64 = 100;
+pub const DEFAULT_GRID: (u32, u32) = (4, 6);
+// Every thumbnail is kept until the sheet is written, so sheets are capped at this many, and
+// this wide
+pub const MAX_TILES: u64 = 1024;
+pub const MAX_THUMBNAIL_WIDTH: u32 = 1024;
+// Around and between the thumbnails, in pixels
+const GAP: u32 = 12;
+const BACKGROUND: [u8; 4] = [255, 255, 255, 255];
+const LABEL_COLOR: [u8; 4] = [40, 40, 40, 255];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SheetSettings {
+    pub path: PathBuf,
+    pub every: u64,
+    pub rows: u32,
+    pub cols: u32,
+    pub thumbnail_width: u32,
+}
+
+impl SheetSettings {
+    pub fn new(path: PathBuf, every: u64, rows: u32, cols: u32) -> Self {
+        Self { path, every, rows, cols, thumbnail_width: DEFAULT_THUMBNAIL_WIDTH }
+    }
+}
+
+// `<rows>x<cols>` thumbnails, at most MAX_TILES of them
+pub fn parse_grid(size: &str) -> Option<(u32, u32)> {
+    crate::universe::parse_grid_size(size).filter(|&(rows, cols)| rows as u64 * cols as u64 <= MAX_TILES)
+}
+
+struct Thumbnail {
+    generation: u64,
+    pixels: Vec<u8>,
+}
+
+pub struct ContactSheet {
+    pub settings: SheetSettings,
+    // Generation of the next thumbnail
+    next: u64,
+    // Thumbnail size, fixed by the first frame's aspect ratio
+    size: Option<(u32, u32)>,
+    thumbnails: Vec<Thumbnail>,
+}
+
+impl ContactSheet {
+    // Starting with the frame of `generation`
+    pub fn new(settings: SheetSettings, generation: u64) -> Self {
+        Self { settings, next: generation, size: None, thumbnails: Vec::new() }
+    }
+
+    pub fn due(&self, generation: u64) -> bool {
+        !self.is_complete() && generation >= self.next
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.thumbnails.len() as u64 >= self.settings.rows as u64 * self.settings.cols as u64
+    }
+
+    pub fn len(&self) -> usize {
+        self.thumbnails.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.thumbnails.is_empty()
+    }
+
+    // Add the frame of `generation`, tightly packed RGBA8 rows top row first
+    pub fn push(&mut self, generation: u64, width: u32, height: u32, pixels: &[u8]) {
+        let thumbnail_width = self.settings.thumbnail_width.min(width).max(1);
+        let size = *self.size.get_or_insert((thumbnail_width, (height as u64 * thumbnail_width as u64 / width.max(1) as u64).max(1) as u32));
+        self.thumbnails.push(Thumbnail { generation, pixels: shrink(pixels, width, height, size.0, size.1) });
+        self.next = generation.saturating_add(self.settings.every);
+    }
+
+    // The sheet as RGBA8 pixels with its width and height. Rows are only as many as the
+    // thumbnails fill, so a sheet stopped early has no empty rows at the bottom.
+    // Sizes are worked out in u64, as thumbnails of tall frames make for tall sheets.
+    pub fn compose(&self) -> (u32, u32, Vec<u8>) {
+        let (thumbnail_width, thumbnail_height) = self.size.unwrap_or((1, 1));
+        let scale = if thumbnail_width >= 200 { 2 } else { 1 };
+        let label_height = (text::GLYPH_HEIGHT * scale + GAP / 2) as u64;
+        let (thumbnail_width, thumbnail_height, gap) = (thumbnail_width as u64, thumbnail_height as u64, GAP as u64);
+        let per_row = self.settings.cols.max(1) as u64;
+        let cols = per_row.min(self.thumbnails.len().max(1) as u64);
+        let rows = (self.thumbnails.len() as u64).div_ceil(per_row).max(1);
+        let width = cols * thumbnail_width + (cols + 1) * gap;
+        let height = rows * (thumbnail_height + label_height) + (rows + 1) * gap;
+
+        let mut sheet = BACKGROUND.repeat((width * height) as usize);
+        for (i, thumbnail) in self.thumbnails.iter().enumerate() {
+            let (row, col) = (i as u64 / per_row, i as u64 % per_row);
+            let (x, y) = (gap + col * (thumbnail_width + gap), gap + row * (thumbnail_height + label_height + gap));
+            let line_bytes = (thumbnail_width * 4) as usize;
+            for line in 0..thumbnail_height {
+                let from = line as usize * line_bytes;
+                let to = (((y + line) * width + x) * 4) as usize;
+                sheet[to..to + line_bytes].copy_from_slice(&thumbnail.pixels[from..from + line_bytes]);
+            }
+            let label = format!("Generation {}", thumbnail.generation);
+            draw_text(&mut sheet, width as u32, &label, (x as u32, (y + thumbnail_height + gap / 2) as u32), scale);
+        }
+        (width as u32, height as u32, sheet)
+    }
+
+    pub fn write(&self, provenance: &Provenance) -> Result<(), String> {
+        let (width, height, pixels) = self.compose();
+        super::png::write_rgba(&self.settings.path, width, height, &pixels, Some(provenance)).map_err(|err| format!("Failed to write {}: {err}", self.settings.path.display()))
+    }
+}
+
+// Area-averaged resize of RGBA8 pixels to a smaller size, so thin structures fade rather
+// than flicker in and out as nearest-neighbor sampling would
+fn shrink(pixels: &[u8], width: u32, height: u32, to_width: u32, to_height: u32) -> Vec<u8> {
+    let span = |i: u32, to: u32, from: u32| {
+        let start = (i as u64 * from as u64 / to as u64) as usize;
+        let end = ((i as u64 + 1) * from as u64 / to as u64) as usize;
+        start..end.max(start + 1)
+    };
+    let mut shrunk = Vec::with_capacity((to_width * to_height * 4) as usize);
+    for y in 0..to_height {
+        let rows = span(y, to_height, height);
+        for x in 0..to_width {
+            let cols = span(x, to_width, width);
+            let mut sum = [0u64; 4];
+            for row in rows.clone() {
+                for col in cols.clone() {
+                    let i = (row * width as usize + col) * 4;
+                    for (channel, total) in sum.iter_mut().enumerate() {
+                        *total += pixels[i + channel] as u64;
+                    }
+                }
+            }
+            let count = (rows.len() * cols.len()) as u64;
+            shrunk.extend(sum.map(|total| (total / count) as u8));
+        }
+    }
+    shrunk
+}
+
+// `label` with its top-left corner at `origin`, each font pixel `scale` pixels square, clipped
+// to the sheet's width
+fn draw_text(sheet: &mut [u8], width: u32, label: &str, origin: (u32, u32), scale: u32) {
+    let height = (sheet.len() / 4 / width as usize).min(u32::MAX as usize) as u32;
+    for (i, c) in label.chars().enumerate() {
+        for (row, bits) in text::glyph(c).into_iter().enumerate() {
+            for col in 0..text::GLYPH_WIDTH {
+                if bits & (1 << (text::GLYPH_WIDTH - 1 - col)) == 0 {
+                    continue;
+                }
+                let (left, top) = (origin.0 + (i as u32 * text::ADVANCE + col) * scale, origin.1 + row as u32 * scale);
+                for y in top..(top + scale).min(height) {
+                    for x in left..(left + scale).min(width) {
+                        let at = (y as usize * width as usize + x as usize) * 4;
+                        sheet[at..at + 4].copy_from_slice(&LABEL_COLOR);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lays_out_labelled_thumbnails() {
+        let mut sheet = ContactSheet::new(SheetSettings { thumbnail_width: 2, ..SheetSettings::new("sheet.png".into(), 10, 2, 2) }, 5);
+        assert!(sheet.due(5) && !sheet.is_complete());
+        // A 4x2 frame, left half black and right half grey, shrinks to a 2x1 thumbnail
+        let frame = [[0, 0, 0, 255], [0, 0, 0, 255], [100, 100, 100, 255], [200, 200, 200, 255]].repeat(2).concat();
+        sheet.push(5, 4, 2, &frame);
+        assert!(!sheet.due(14) && sheet.due(15));
+        sheet.push(17, 4, 2, &frame);
+        sheet.push(27, 4, 2, &frame);
+
+        let (width, height, pixels) = sheet.compose();
+        let label_height = text::GLYPH_HEIGHT + GAP / 2;
+        assert_eq!((width, height), (2 * 2 + 3 * GAP, 2 * (1 + label_height) + 3 * GAP));
+        let pixel = |x: u32, y: u32| &pixels[((y * width + x) * 4) as usize..][..4];
+        assert_eq!(pixel(GAP, GAP), [0, 0, 0, 255]);
+        assert_eq!(pixel(GAP + 1, GAP), [150, 150, 150, 255]);
+        assert_eq!(pixel(0, 0), BACKGROUND);
+        // The third thumbnail starts the second row; the fourth place stays empty
+        let second_row = 2 * GAP + 1 + label_height;
+        assert_eq!(pixel(GAP, second_row), [0, 0, 0, 255]);
+        assert_eq!(pixel(2 * GAP + 2, second_row), BACKGROUND);
+        assert!(pixels.chunks(4).any(|pixel| pixel == LABEL_COLOR));
+
+        sheet.push(37, 4, 2, &frame);
+        assert!(sheet.is_complete() && !sheet.due(1000));
+
+        assert_eq!(parse_grid("32x32"), Some((32, 32)));
+        assert_eq!(parse_grid("32x33"), None);
+        let mut endless = ContactSheet::new(SheetSettings::new("sheet.png".into(), u64::MAX, 2, 2), 5);
+        endless.push(5, 4, 2, &frame);
+        assert!(!endless.due(u64::MAX - 1));
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt::Write as _;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::provenance::Provenance;
+use crate::tracks::Track;
+use crate::universe::Universe;
+use crate::vertex::{ANT_COLOR, CONTRAST_OUTLINE, MASK_COLOR, Overlay, TRACK_MARK_COLOR, cell_color};
+
+// Vector figure of the grid window for papers and slides (`export frame.svg`): the cells as
+// rects in the on-screen palette, the selected track as a path below them, and every kind of
+// annotation as a titled group with an entry in the legend. Units are CELL per cell pitch.
+
+const CELL: f32 = 10.0;
+const TRACK_HEIGHT: f32 = 60.0;
+const MARGIN: f32 = 10.0;
+const FONT_SIZE: f32 = 9.0;
+
+pub struct Figure<'a> {
+    pub universe: &'a Universe,
+    pub overlay: &'a Overlay,
+    // Cells shown in the window
+    pub rows: Range<u32>,
+    pub cols: Range<u32>,
+    // Gap between cells as a share of the pitch
+    pub padding: f32,
+    pub track: Option<&'a Track>,
+    pub background: [f32; 3],
+    // Written as "key: value" lines in the figure's <metadata>
+    pub provenance: Option<&'a Provenance>,
+}
+
+fn hex([r, g, b]: [f32; 3]) -> String {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0) as u8;
+    format!("#{:02x}{:02x}{:02x}", channel(r), channel(g), channel(b))
+}
+
+impl Figure<'_> {
+    // Top-left corner of a cell, with the first visible row at the bottom as on screen
+    fn corner(&self, idx: usize) -> Option<[f32; 2]> {
+        let (row, col) = (idx as u32 / self.universe.cols, idx as u32 % self.universe.cols);
+        if !self.rows.contains(&row) || !self.cols.contains(&col) {
+            return None;
+        }
+        let inset = self.padding * CELL / 2.0;
+        Some([MARGIN + (col - self.cols.start) as f32 * CELL + inset, MARGIN + (self.rows.end - 1 - row) as f32 * CELL + inset])
+    }
+
+    pub fn to_svg(&self) -> String {
+        let (rows, cols) = (self.rows.len() as f32, self.cols.len() as f32);
+        let (grid_width, grid_height) = (cols * CELL, rows * CELL);
+        let track_top = MARGIN * 2.0 + grid_height;
+        let legend_top = track_top + if self.track.is_some() { TRACK_HEIGHT + MARGIN } else { 0.0 };
+        let size = CELL * (1.0 - self.padding);
+
+        // Annotations as (legend label, color, shapes), in the order they are drawn on screen
+        let mut annotations: Vec<(&str, [f32; 3], String)> = Vec::new();
+        let mut annotate = |label, color, cells: Vec<(usize, [f32; 3])>, shape: &dyn Fn([f32; 2], [f32; 3]) -> String| {
+            let shapes: String = cells.into_iter().filter_map(|(idx, color)| Some(shape(self.corner(idx)?, color))).collect();
+            if !shapes.is_empty() {
+                annotations.push((label, color, shapes));
+            }
+        };
+        let first_color = |cells: &[(usize, [f32; 3])]| cells.first().map_or([0.0; 3], |&(_, color)| color);
+        annotate("Masked low-complexity bases", MASK_COLOR, self.overlay.masked.iter().map(|&idx| (idx, MASK_COLOR)).collect(), &|[x, y], color| {
+            format!("<rect x=\"{x}\" y=\"{}\" width=\"{size}\" height=\"{}\" fill=\"{}\"/>\n", y + size * 0.8, size * 0.2, hex(color))
+        });
+        annotate("Motif and PWM sites", first_color(&self.overlay.highlights), self.overlay.highlights.clone(), &|[x, y], color| {
+            let stroke = size * 0.12;
+            let (corner, side) = (stroke / 2.0, size - stroke);
+            format!("<rect x=\"{}\" y=\"{}\" width=\"{side}\" height=\"{side}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{stroke}\"/>\n", x + corner, y + corner, hex(color))
+        });
+        annotate("Variants", first_color(&self.overlay.variants), self.overlay.variants.clone(), &|[x, y], color| {
+            let (inset, middle) = (size * 0.15, size / 2.0);
+            let (left, right, top, bottom) = (x + inset, x + size - inset, y + inset, y + size - inset);
+            format!("<path d=\"M{left} {}H{right}M{} {top}V{bottom}\" stroke=\"{}\" stroke-width=\"{}\"/>\n", y + middle, x + middle, hex(color), size * 0.16)
+        });
+        let inset_square = |[x, y]: [f32; 2], color| {
+            let (inset, side) = (size * 0.3, size * 0.4);
+            format!("<rect x=\"{}\" y=\"{}\" width=\"{side}\" height=\"{side}\" fill=\"{}\"/>\n", x + inset, y + inset, hex(color))
+        };
+        annotate("Markers", first_color(&self.overlay.markers), self.overlay.markers.clone(), &inset_square);
+        annotate("Ants", ANT_COLOR, self.overlay.ants.iter().map(|&idx| (idx, ANT_COLOR)).collect(), &inset_square);
+        if let Some(region) = self.overlay.selection {
+            // The visible part of the selection, framed from its top-left visible cell
+            let (top, bottom) = (region.row.saturating_add(region.rows).min(self.rows.end), region.row.max(self.rows.start));
+            let (left, right) = (region.col.max(self.cols.start), region.col.saturating_add(region.cols).min(self.cols.end));
+            if top > bottom && right > left && let Some([x, y]) = self.corner(((top - 1) * self.universe.cols + left) as usize) {
+                let inset = self.padding * CELL / 2.0;
+                let (width, height) = ((right - left) as f32 * CELL, (top - bottom) as f32 * CELL);
+                let frame = format!("<rect x=\"{}\" y=\"{}\" width=\"{width}\" height=\"{height}\" fill=\"none\" stroke=\"white\" stroke-dasharray=\"3 2\"/>\n", x - inset, y - inset);
+                annotations.push(("Selection", [1.0; 3], frame));
+            }
+        }
+
+        let (width, height) = (grid_width + MARGIN * 2.0, legend_top + annotations.len() as f32 * (FONT_SIZE + 4.0) + MARGIN);
+        let mut svg = String::new();
+        let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="monospace" font-size="{FONT_SIZE}">"#);
+        if let Some(provenance) = self.provenance {
+            svg.push_str("<metadata>\n");
+            for (key, value) in provenance.fields() {
+                let value = value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+                let _ = writeln!(svg, "{key}: {value}");
+            }
+            svg.push_str("</metadata>\n");
+        }
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="{}"/>"#, hex(self.background));
+
+        svg.push_str("<g id=\"cells\">\n");
+        for row in self.rows.clone() {
+            for col in self.cols.clone() {
+                let idx = (row * self.universe.cols + col) as usize;
+                let Some([x, y]) = self.corner(idx) else { continue };
+                // High contrast frames live cells across the gap, as on screen
+                if self.overlay.high_contrast && self.universe.cells[idx] {
+                    let inset = self.padding * CELL / 2.0;
+                    let _ = writeln!(svg, r#"<rect x="{}" y="{}" width="{CELL}" height="{CELL}" fill="{}"/>"#, x - inset, y - inset, hex(CONTRAST_OUTLINE));
+                }
+                let fill = hex(cell_color(self.universe, self.overlay, row, col));
+                let _ = writeln!(svg, r#"<rect x="{x}" y="{y}" width="{size}" height="{size}" fill="{fill}"/>"#);
+            }
+        }
+        svg.push_str("</g>\n");
+        for (label, _, shapes) in &annotations {
+            let _ = write!(svg, "<g id=\"{}\"><title>{label}</title>\n{shapes}</g>\n", label.to_lowercase().replace(' ', "-"));
+        }
+
+        if let Some(track) = self.track {
+            let _ = writeln!(svg, "<g id=\"track\"><title>{}</title>", track.label);
+            let (left, right, bottom) = (MARGIN, MARGIN + grid_width, track_top + TRACK_HEIGHT);
+            let _ = writeln!(svg, r##"<line x1="{left}" y1="{bottom}" x2="{right}" y2="{bottom}" stroke="#404040"/>"##);
+            if track.values.len() >= 2 {
+                let (low, high) = track.range();
+                let range = if high > low { high - low } else { 1.0 };
+                let point = |i: usize| {
+                    let t = i as f32 / (track.values.len() - 1) as f32;
+                    [left + t * grid_width, bottom - ((track.values[i] - low) / range) as f32 * TRACK_HEIGHT]
+                };
+                let mut path = String::new();
+                for i in 0..track.values.len() {
+                    let [x, y] = point(i);
+                    let _ = write!(path, "{}{x:.2} {y:.2}", if i == 0 { "M" } else { "L" });
+                }
+                let _ = writeln!(svg, r#"<path d="{path}" fill="none" stroke="{}" stroke-width="1.2"/>"#, hex(track.color));
+                if let Some(mark) = track.mark.filter(|&mark| mark < track.values.len()) {
+                    let x = point(mark)[0];
+                    let _ = writeln!(svg, r#"<line x1="{x}" y1="{track_top}" x2="{x}" y2="{bottom}" stroke="{}"/>"#, hex(TRACK_MARK_COLOR));
+                }
+            }
+            let _ = writeln!(svg, r##"<text x="{left}" y="{}" fill="#c0c0c0">{}</text>"##, track_top + FONT_SIZE, track.label);
+            svg.push_str("</g>\n");
+        }
+
+        svg.push_str("<g id=\"legend\">\n");
+        for (i, (label, color, _)) in annotations.iter().enumerate() {
+            let y = legend_top + i as f32 * (FONT_SIZE + 4.0);
+            let _ = writeln!(svg, r#"<rect x="{MARGIN}" y="{y}" width="{FONT_SIZE}" height="{FONT_SIZE}" fill="{}"/>"#, hex(*color));
+            let _ = writeln!(svg, r##"<text x="{}" y="{}" fill="#c0c0c0">{label}</text>"##, MARGIN + FONT_SIZE * 1.5, y + FONT_SIZE * 0.85);
+        }
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        let _span = tracing::debug_span!("write", path = %path.display()).entered();
+        std::fs::write(path, self.to_svg())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selection::Region;
+
+    #[test]
+    fn draws_cells_tracks_and_labeled_annotations() {
+        let mut universe = Universe::new(2, 3, b"GAAAAC");
+        universe.cells[4] = true;
+        let overlay = Overlay { markers: vec![(1, [0.9, 0.2, 0.9])], selection: Some(Region { row: 0, col: 0, rows: 1, cols: 2 }), ..Overlay::default() };
+        let track = Track { name: "gc", label: "GC content", values: vec![0.2, 0.8, 0.5], window: 2, color: [0.3, 0.8, 0.4], mark: Some(1) };
+        let provenance = Provenance::new("<stdin>", 0, 0, "B3/S23".to_string(), None, 0);
+        let figure = Figure { universe: &universe, overlay: &overlay, rows: 0..2, cols: 0..3, padding: 0.2, track: Some(&track), background: [0.05, 0.05, 0.15], provenance: Some(&provenance) };
+        let svg = figure.to_svg();
+
+        assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+        // Six cells: G, C and the toggled one alive in the on-screen green, or yellow when selected
+        assert_eq!(svg.matches(r#"width="8" height="8""#).count(), 6);
+        assert_eq!(svg.matches("#33cc33").count(), 2);
+        assert!(svg.contains("#cccc33"));
+        assert!(svg.contains("<title>Markers</title>") && svg.contains(">Markers</text>"));
+        assert!(svg.contains("<title>Selection</title>"));
+        assert!(!svg.contains("Variants"));
+        assert!(svg.contains(r#"<path d="M10.00 "#) && svg.contains("GC content"));
+        assert!(svg.contains("<metadata>\nversion: bio-rust ") && svg.contains("input: &lt;stdin&gt;\n"));
+    }
+}
@@ -0,0 +1,290 @@
+use std::path::Path;
+
+// Per-generation grids and statistics as Apache Arrow IPC or Parquet files, for analysis in
+// pandas, polars, R and the like (`arrow` feature). Rows are buffered and written in record
+// batches of BATCH_ROWS generations, or fewer once their cells reach BATCH_BYTES, so memory
+// stays bounded however long the recording runs and however large the grid.
+//
+// One row per stepped generation, with the selected columns of:
+//   generation    UInt64
+//   population    UInt64
+//   centroid_col  Float64, null when nothing is alive (the `centroid` column selects both)
+//   centroid_row  Float64, null when nothing is alive
+//   rule          Utf8, B/S notation
+//   cells         FixedSizeList<Boolean>[rows * cols], row-major, true for alive
+// The schema's metadata holds the grid's `rows` and `cols`.
+
+#[cfg(feature = "arrow")]
+const BATCH_ROWS: usize = 256;
+// Buffered cells take a byte each until they are written
+#[cfg(feature = "arrow")]
+const BATCH_BYTES: usize = 8 << 20;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Column {
+    Generation,
+    Population,
+    Centroid,
+    Rule,
+    Cells,
+}
+
+impl Column {
+    pub const ALL: [Column; 5] = [Column::Generation, Column::Population, Column::Centroid, Column::Rule, Column::Cells];
+}
+
+impl std::str::FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Column::ALL
+            .into_iter()
+            .find(|column| column.to_string() == s)
+            .ok_or_else(|| format!("Unknown column '{s}', expected generation, population, centroid, rule or cells"))
+    }
+}
+
+impl std::fmt::Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Column::Generation => write!(f, "generation"),
+            Column::Population => write!(f, "population"),
+            Column::Centroid => write!(f, "centroid"),
+            Column::Rule => write!(f, "rule"),
+            Column::Cells => write!(f, "cells"),
+        }
+    }
+}
+
+// Comma-separated column names, e.g. "generation,population,cells"
+pub fn parse_columns(s: &str) -> Result<Vec<Column>, String> {
+    let mut columns = Vec::new();
+    for name in s.split(',') {
+        let column = name.trim().parse()?;
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+    Ok(columns)
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TableFormat {
+    Ipc,
+    Parquet,
+}
+
+impl TableFormat {
+    pub fn of(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("arrow" | "ipc" | "feather") => Ok(TableFormat::Ipc),
+            Some("parquet") => Ok(TableFormat::Parquet),
+            _ => Err(format!("Can't tell the format of {}, expected a .arrow or .parquet file", path.display())),
+        }
+    }
+}
+
+#[cfg(feature = "arrow")]
+pub use recorder::TableRecorder;
+
+#[cfg(feature = "arrow")]
+mod recorder {
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::io::BufWriter;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use arrow_array::{ArrayRef, BooleanArray, FixedSizeListArray, Float64Array, RecordBatch, StringArray, UInt64Array};
+    use arrow_ipc::writer::FileWriter;
+    use arrow_schema::{DataType, Field, Schema, SchemaRef};
+    use parquet::arrow::ArrowWriter;
+    use parquet::basic::Compression;
+    use parquet::file::properties::WriterProperties;
+
+    use super::{BATCH_BYTES, BATCH_ROWS, Column, TableFormat};
+    use crate::provenance::Provenance;
+
+    enum Writer {
+        Ipc(FileWriter<BufWriter<File>>),
+        Parquet(ArrowWriter<File>),
+    }
+
+    struct Row {
+        generation: u64,
+        population: u64,
+        centroid: Option<[f64; 2]>,
+        rule: String,
+    }
+
+    pub struct TableRecorder {
+        pub path: PathBuf,
+        columns: Vec<Column>,
+        schema: SchemaRef,
+        writer: Writer,
+        // Rows not written yet, with their cells flattened when the cells are recorded
+        rows: Vec<Row>,
+        cells: Vec<bool>,
+        cell_count: usize,
+        written: usize,
+    }
+
+    fn cell_field() -> Arc<Field> {
+        Arc::new(Field::new("item", DataType::Boolean, false))
+    }
+
+    impl TableRecorder {
+        pub fn create(path: &Path, columns: Vec<Column>, rows: u32, cols: u32, provenance: &Provenance) -> Result<Self, String> {
+            let cell_count = (rows * cols) as usize;
+            let fields: Vec<Field> = columns
+                .iter()
+                .flat_map(|column| match column {
+                    Column::Generation => vec![Field::new("generation", DataType::UInt64, false)],
+                    Column::Population => vec![Field::new("population", DataType::UInt64, false)],
+                    Column::Centroid => vec![Field::new("centroid_col", DataType::Float64, true), Field::new("centroid_row", DataType::Float64, true)],
+                    Column::Rule => vec![Field::new("rule", DataType::Utf8, false)],
+                    Column::Cells => vec![Field::new("cells", DataType::FixedSizeList(cell_field(), cell_count as i32), false)],
+                })
+                .collect();
+            let mut metadata = HashMap::from([("rows".to_string(), rows.to_string()), ("cols".to_string(), cols.to_string())]);
+            metadata.extend(provenance.fields().into_iter().map(|(key, value)| (key.to_string(), value)));
+            let schema = Arc::new(Schema::new(fields).with_metadata(metadata));
+
+            // Known before the file is created, so a wrong extension leaves an existing file alone
+            let format = TableFormat::of(path)?;
+            let fail = |err: &dyn std::fmt::Display| format!("Failed to create {}: {err}", path.display());
+            let file = File::create(path).map_err(|err| fail(&err))?;
+            let writer = match format {
+                TableFormat::Ipc => Writer::Ipc(FileWriter::try_new(BufWriter::new(file), &schema).map_err(|err| fail(&err))?),
+                TableFormat::Parquet => {
+                    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+                    Writer::Parquet(ArrowWriter::try_new(file, Arc::clone(&schema), Some(properties)).map_err(|err| fail(&err))?)
+                }
+            };
+            Ok(Self { path: path.to_path_buf(), columns, schema, writer, rows: Vec::new(), cells: Vec::new(), cell_count, written: 0 })
+        }
+
+        pub fn record(&mut self, generation: u64, cells: &[bool], centroid: Option<[f64; 2]>, rule: &str) -> Result<(), String> {
+            let population = cells.iter().filter(|&&alive| alive).count() as u64;
+            self.rows.push(Row { generation, population, centroid, rule: rule.to_string() });
+            if self.columns.contains(&Column::Cells) {
+                self.cells.extend_from_slice(cells);
+            }
+            if self.rows.len() == BATCH_ROWS || self.cells.len() >= BATCH_BYTES {
+                self.flush()?;
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), String> {
+            if self.rows.is_empty() {
+                return Ok(());
+            }
+            let rows = std::mem::take(&mut self.rows);
+            let mut arrays: Vec<ArrayRef> = Vec::new();
+            for column in &self.columns {
+                match column {
+                    Column::Generation => arrays.push(Arc::new(UInt64Array::from_iter_values(rows.iter().map(|row| row.generation)))),
+                    Column::Population => arrays.push(Arc::new(UInt64Array::from_iter_values(rows.iter().map(|row| row.population)))),
+                    Column::Centroid => {
+                        for axis in 0..2 {
+                            arrays.push(Arc::new(Float64Array::from_iter(rows.iter().map(|row| row.centroid.map(|centroid| centroid[axis])))));
+                        }
+                    }
+                    Column::Rule => arrays.push(Arc::new(StringArray::from_iter_values(rows.iter().map(|row| &row.rule)))),
+                    Column::Cells => {
+                        let values = Arc::new(BooleanArray::from(std::mem::take(&mut self.cells)));
+                        let list = FixedSizeListArray::try_new(cell_field(), self.cell_count as i32, values, None).map_err(|err| err.to_string())?;
+                        arrays.push(Arc::new(list));
+                    }
+                }
+            }
+            let batch = RecordBatch::try_new(Arc::clone(&self.schema), arrays).map_err(|err| err.to_string())?;
+            match &mut self.writer {
+                Writer::Ipc(writer) => writer.write(&batch).map_err(|err| err.to_string())?,
+                Writer::Parquet(writer) => writer.write(&batch).map_err(|err| err.to_string())?,
+            }
+            self.written += rows.len();
+            Ok(())
+        }
+
+        // Write the buffered rows and the file's footer, returning the rows written in total
+        pub fn finish(mut self) -> Result<usize, String> {
+            self.flush()?;
+            match self.writer {
+                Writer::Ipc(mut writer) => writer.finish().map_err(|err| err.to_string())?,
+                Writer::Parquet(writer) => {
+                    writer.close().map_err(|err| err.to_string())?;
+                }
+            }
+            Ok(self.written)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_columns_and_formats() {
+        assert_eq!(parse_columns("generation, cells,generation"), Ok(vec![Column::Generation, Column::Cells]));
+        assert!(parse_columns("generation,age").is_err());
+        assert_eq!(TableFormat::of(Path::new("run.parquet")), Ok(TableFormat::Parquet));
+        assert_eq!(TableFormat::of(Path::new("run.arrow")), Ok(TableFormat::Ipc));
+        assert!(TableFormat::of(Path::new("run.csv")).is_err());
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn writes_batches_readable_back() {
+        use arrow_array::{Array, FixedSizeListArray, UInt64Array};
+        use crate::provenance::Provenance;
+
+        let dir = std::env::temp_dir().join(format!("bio-rust-table-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("run.arrow");
+        let provenance = Provenance::new("built-in", 0, 0, "B3/S23".to_string(), None, 0);
+        let mut recorder = TableRecorder::create(&path, vec![Column::Generation, Column::Centroid, Column::Cells], 2, 3, &provenance).unwrap();
+        for generation in 0..300 {
+            let alive = generation % 2 == 0;
+            recorder.record(generation, &[alive, false, false, false, false, alive], alive.then_some([1.0, 0.5]), "B3/S23").unwrap();
+        }
+        assert_eq!(recorder.finish(), Ok(300));
+
+        let reader = arrow_ipc::reader::FileReader::try_new(std::fs::File::open(&path).unwrap(), None).unwrap();
+        assert_eq!(reader.schema().metadata()["cols"], "3");
+        assert_eq!(reader.schema().metadata()["rule"], "B3/S23");
+        let batches: Vec<_> = reader.map(Result::unwrap).collect();
+        assert_eq!(batches.iter().map(|batch| batch.num_rows()).collect::<Vec<_>>(), [256, 44]);
+        let last = &batches[1];
+        let generations = last.column(0).as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(generations.value(43), 299);
+        assert!(last.column(1).is_null(43));
+        let cells = last.column(3).as_any().downcast_ref::<FixedSizeListArray>().unwrap();
+        assert_eq!(cells.value_length(), 6);
+
+        let path = dir.join("run.parquet");
+        let mut recorder = TableRecorder::create(&path, vec![Column::Rule], 2, 3, &provenance).unwrap();
+        recorder.record(0, &[false; 6], None, "B3/S23").unwrap();
+        assert_eq!(recorder.finish(), Ok(1));
+        assert!(std::fs::read(&path).unwrap().ends_with(b"PAR1"));
+
+        // Large grids are written a few generations at a time
+        let path = dir.join("large.arrow");
+        let mut recorder = TableRecorder::create(&path, vec![Column::Cells], 1, super::BATCH_BYTES as u32 / 2, &provenance).unwrap();
+        for generation in 0..5 {
+            recorder.record(generation, &vec![false; super::BATCH_BYTES / 2], None, "B3/S23").unwrap();
+        }
+        assert_eq!(recorder.finish(), Ok(5));
+        let reader = arrow_ipc::reader::FileReader::try_new(std::fs::File::open(&path).unwrap(), None).unwrap();
+        assert_eq!(reader.map(|batch| batch.unwrap().num_rows()).collect::<Vec<_>>(), [2, 2, 1]);
+
+        // A file of an unknown format is refused before anything is written to it
+        let path = dir.join("run.csv");
+        std::fs::write(&path, "kept").unwrap();
+        assert!(TableRecorder::create(&path, vec![Column::Rule], 2, 3, &provenance).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "kept");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
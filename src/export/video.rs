@@ -0,0 +1,177 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// Frames waiting to be written to ffmpeg. When the encoder falls this far behind, sending the
+// next frame blocks the render loop until it catches up, instead of queuing without bound.
+const QUEUE_FRAMES: usize = 8;
+
+// `--record-video` options
+#[derive(Clone, Debug, PartialEq)]
+pub struct VideoSettings {
+    pub path: PathBuf,
+    pub fps: u32,
+    // Output resolution; the window's size (rounded down to even) when not given
+    pub size: Option<(u32, u32)>,
+    // Recording stops after this much video
+    pub duration: Option<Duration>,
+}
+
+// Streams frames into an ffmpeg child process as raw RGBA on its stdin. Frames are taken at
+// the video's frame rate in wall-clock time, repeating the last one when rendering is slower,
+// so the video plays at the speed the simulation was watched.
+pub struct VideoRecorder {
+    pub settings: VideoSettings,
+    // Size of the frames fed to ffmpeg, fixed by the first frame; later frames of another
+    // size (after a window resize) are resampled to it
+    input: (u32, u32),
+    started: Instant,
+    pub frames: u64,
+    sender: Option<SyncSender<Vec<u8>>>,
+    writer: Option<JoinHandle<io::Result<()>>>,
+    child: Child,
+}
+
+impl VideoRecorder {
+    // Start ffmpeg for frames of `width`x`height` pixels
+    pub fn start(settings: VideoSettings, width: u32, height: u32) -> Result<Self, String> {
+        let mut child = Command::new("ffmpeg")
+            .args(ffmpeg_args(&settings, width, height))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::NotFound => "ffmpeg was not found on the PATH, install it to record video".to_string(),
+                _ => format!("Failed to start ffmpeg: {err}"),
+            })?;
+        let stdin = child.stdin.take().ok_or("ffmpeg has no stdin")?;
+
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_FRAMES);
+        let writer = thread::spawn(move || write_frames(stdin, receiver));
+        tracing::debug!("Recording {}x{} frames to {}", width, height, settings.path.display());
+        Ok(Self { settings, input: (width, height), started: Instant::now(), frames: 0, sender: Some(sender), writer: Some(writer), child })
+    }
+
+    // Frames due by now that have not been written yet
+    pub fn frames_due(&self) -> u64 {
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let due = (elapsed * self.settings.fps as f64) as u64 + 1;
+        due.min(self.max_frames()).saturating_sub(self.frames)
+    }
+
+    fn max_frames(&self) -> u64 {
+        self.settings.duration.map_or(u64::MAX, |duration| (duration.as_secs_f64() * self.settings.fps as f64).ceil() as u64)
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.frames >= self.max_frames()
+    }
+
+    // Queue a frame of tightly packed RGBA8 rows, top row first, `copies` times. Blocks
+    // while ffmpeg is QUEUE_FRAMES behind.
+    pub fn push(&mut self, width: u32, height: u32, pixels: Vec<u8>, copies: u64) -> Result<(), String> {
+        let (input_width, input_height) = self.input;
+        let pixels = if (width, height) == self.input { pixels } else { resample(&pixels, width, height, input_width, input_height) };
+        let sender = self.sender.as_ref().ok_or("Recording already finished")?;
+        for _ in 0..copies {
+            // The writer only hangs up after an error, which `finish` reports
+            if sender.send(pixels.clone()).is_err() {
+                return Err("ffmpeg stopped accepting frames".into());
+            }
+            self.frames += 1;
+        }
+        Ok(())
+    }
+
+    // Close ffmpeg's input and wait for it to write the file
+    pub fn finish(mut self) -> Result<u64, String> {
+        drop(self.sender.take());
+        let written = self.writer.take().map(|writer| writer.join());
+        let status = self.child.wait().map_err(|err| format!("Failed to wait for ffmpeg: {err}"))?;
+        match written {
+            Some(Ok(Err(err))) => return Err(format!("Failed to write to ffmpeg: {err}")),
+            Some(Err(_)) => return Err("The video writer thread panicked".into()),
+            _ => {}
+        }
+        if !status.success() {
+            return Err(format!("ffmpeg exited with {status}"));
+        }
+        Ok(self.frames)
+    }
+}
+
+fn write_frames(mut stdin: ChildStdin, receiver: Receiver<Vec<u8>>) -> io::Result<()> {
+    for frame in receiver {
+        stdin.write_all(&frame)?;
+    }
+    stdin.flush()
+}
+
+// Raw RGBA frames in, H.264 out. libx264 with yuv420p needs even dimensions.
+pub fn ffmpeg_args(settings: &VideoSettings, width: u32, height: u32) -> Vec<String> {
+    let scale = match settings.size {
+        Some((width, height)) => format!("scale={width}:{height}:flags=neighbor"),
+        None => "scale=trunc(iw/2)*2:trunc(ih/2)*2:flags=neighbor".into(),
+    };
+    [
+        "-y", "-loglevel", "error",
+        "-f", "rawvideo", "-pix_fmt", "rgba", "-s", &format!("{width}x{height}"), "-r", &settings.fps.to_string(), "-i", "-",
+        "-vf", &scale, "-c:v", "libx264", "-pix_fmt", "yuv420p",
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .chain(std::iter::once(settings.path.display().to_string()))
+    .collect()
+}
+
+// Nearest-neighbor resize of RGBA8 pixels
+fn resample(pixels: &[u8], width: u32, height: u32, to_width: u32, to_height: u32) -> Vec<u8> {
+    let mut resized = Vec::with_capacity((to_width * to_height * 4) as usize);
+    for y in 0..to_height {
+        let source_y = (y as u64 * height as u64 / to_height as u64) as usize;
+        for x in 0..to_width {
+            let source_x = (x as u64 * width as u64 / to_width as u64) as usize;
+            let i = (source_y * width as usize + source_x) * 4;
+            resized.extend_from_slice(&pixels[i..i + 4]);
+        }
+    }
+    resized
+}
+
+// "<width>x<height>" with both even, as libx264 requires
+pub fn parse_size(size: &str) -> Option<(u32, u32)> {
+    let (width, height) = size.split_once('x')?;
+    let (width, height): (u32, u32) = (width.parse().ok()?, height.parse().ok()?);
+    (width > 0 && height > 0 && width % 2 == 0 && height % 2 == 0).then_some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_ffmpeg_arguments() {
+        let settings = VideoSettings { path: "out.mp4".into(), fps: 30, size: Some((1280, 720)), duration: None };
+        let args = ffmpeg_args(&settings, 801, 600).join(" ");
+        assert!(args.contains("-s 801x600 -r 30 -i -"));
+        assert!(args.contains("-vf scale=1280:720:flags=neighbor"));
+        assert!(args.ends_with("yuv420p out.mp4"));
+
+        assert_eq!(parse_size("1280x720"), Some((1280, 720)));
+        assert_eq!(parse_size("1281x720"), None);
+    }
+
+    #[test]
+    fn resamples_to_the_recording_size() {
+        // 2x1 red and blue to 4x2
+        let pixels = [255, 0, 0, 255, 0, 0, 255, 255];
+        let resized = resample(&pixels, 2, 1, 4, 2);
+        assert_eq!(resized.len(), 4 * 2 * 4);
+        assert_eq!(&resized[4..8], &pixels[..4]);
+        assert_eq!(&resized[8..12], &pixels[4..]);
+        assert_eq!(&resized[16..20], &pixels[..4]);
+    }
+}
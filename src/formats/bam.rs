@@ -0,0 +1,97 @@
+// Per-base read depth from indexed BAM/CRAM files. Region parsing is always available;
+// reading alignments needs the `bam` feature (rust-htslib).
+
+// Reference interval, stored 0-based half-open but written like samtools: "chr1:1,001-2,000"
+// is 1-based and inclusive at both ends
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GenomicRegion {
+    pub chrom: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl GenomicRegion {
+    pub fn len(&self) -> usize {
+        (self.end - self.start) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end == self.start
+    }
+}
+
+impl std::str::FromStr for GenomicRegion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid region '{s}', expected chrom:start-end");
+        let (chrom, range) = s.rsplit_once(':').ok_or_else(invalid)?;
+        let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+        let coordinate = |value: &str| value.replace(',', "").parse::<u64>().map_err(|_| invalid());
+        let (start, end) = (coordinate(start)?, coordinate(end)?);
+
+        if chrom.is_empty() || start == 0 || end < start {
+            return Err(invalid());
+        }
+        Ok(Self { chrom: chrom.to_string(), start: start - 1, end })
+    }
+}
+
+impl std::fmt::Display for GenomicRegion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}-{}", self.chrom, self.start + 1, self.end)
+    }
+}
+
+// Reads covering each base of `region` (index 0 is `region.start`). CRAM files need the
+// reference they were compressed against.
+#[cfg(feature = "bam")]
+pub fn read_depth(path: &std::path::Path, region: &GenomicRegion, reference: Option<&std::path::Path>) -> Result<Vec<u32>, String> {
+    use rust_htslib::bam::{IndexedReader, Read};
+
+    let mut reader = IndexedReader::from_path(path).map_err(|err| format!("Failed to open {}: {err}", path.display()))?;
+    if let Some(reference) = reference {
+        reader.set_reference(reference).map_err(|err| format!("Failed to use reference {}: {err}", reference.display()))?;
+    }
+    let tid = reader
+        .header()
+        .tid(region.chrom.as_bytes())
+        .ok_or_else(|| format!("{} has no reference sequence named '{}'", path.display(), region.chrom))?;
+    reader
+        .fetch((tid, region.start, region.end))
+        .map_err(|err| format!("Failed to fetch {region} from {}: {err}", path.display()))?;
+
+    let mut depth = vec![0; region.len()];
+    let mut pileups = reader.pileup();
+    // The htslib default of 8000 would flatten deeply sequenced regions
+    pileups.set_max_depth(i32::MAX as u32);
+    for pileup in pileups {
+        let pileup = pileup.map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        // Reads overlapping the region also produce columns outside it
+        let position = pileup.pos() as u64;
+        if (region.start..region.end).contains(&position) {
+            depth[(position - region.start) as usize] = pileup.depth();
+        }
+    }
+    Ok(depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_samtools_style_regions() {
+        let region: GenomicRegion = "chr1:1,001-2,000".parse().unwrap();
+        assert_eq!(region, GenomicRegion { chrom: "chr1".into(), start: 1000, end: 2000 });
+        assert_eq!(region.len(), 1000);
+        assert_eq!(region.to_string(), "chr1:1001-2000");
+        // Contig names may themselves contain colons
+        assert_eq!("HLA-A*01:01:1-10".parse::<GenomicRegion>().unwrap().chrom, "HLA-A*01:01");
+
+        assert!("chr1".parse::<GenomicRegion>().is_err());
+        assert!("chr1:0-10".parse::<GenomicRegion>().is_err());
+        assert!("chr1:20-10".parse::<GenomicRegion>().is_err());
+        assert!(":1-10".parse::<GenomicRegion>().is_err());
+    }
+}
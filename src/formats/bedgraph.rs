@@ -0,0 +1,164 @@
+use std::path::Path;
+
+// One scored interval, 0-based and half-open like BED
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignalInterval {
+    pub chrom: String,
+    pub start: usize,
+    pub end: usize,
+    pub value: f32,
+}
+
+// Load a bedGraph or wiggle (fixedStep/variableStep) file, picking the parser from the
+// extension or, failing that, from the contents
+pub fn load(path: &Path) -> Result<Vec<SignalInterval>, String> {
+    let _span = tracing::debug_span!("read", path = %path.display()).entered();
+    let text = std::fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    tracing::debug!("Read {} bytes", text.len());
+
+    let is_wig = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("wig"))
+        || text.lines().any(|line| line.starts_with("fixedStep") || line.starts_with("variableStep"));
+
+    if is_wig { parse_wig(&text) } else { parse_bedgraph(&text) }
+}
+
+pub fn parse_bedgraph(text: &str) -> Result<Vec<SignalInterval>, String> {
+    let mut intervals = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        if is_header(line) {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [chrom, start, end, value] = fields[..] else {
+            return Err(format!("bedGraph line {}: expected 4 columns, found {}", i + 1, fields.len()));
+        };
+
+        intervals.push(SignalInterval {
+            chrom: chrom.to_string(),
+            start: parse_field(start, "start", i)?,
+            end: parse_field(end, "end", i)?,
+            value: parse_field(value, "value", i)?,
+        });
+    }
+
+    Ok(intervals)
+}
+
+// Wiggle is 1-based; positions are converted to 0-based intervals
+pub fn parse_wig(text: &str) -> Result<Vec<SignalInterval>, String> {
+    enum Mode { None, Fixed { next: usize, step: usize }, Variable }
+
+    let mut intervals = Vec::new();
+    let mut mode = Mode::None;
+    let mut chrom = String::new();
+    let mut span = 1;
+
+    for (i, line) in text.lines().enumerate() {
+        if is_header(line) {
+            continue;
+        }
+
+        let mut words = line.split_whitespace();
+        let first = words.next().unwrap_or_default();
+
+        if first == "fixedStep" || first == "variableStep" {
+            let mut start = 1;
+            let mut step = 1;
+            span = 1;
+            for word in words {
+                match word.split_once('=') {
+                    Some(("chrom", value)) => chrom = value.to_string(),
+                    Some(("start", value)) => start = parse_field(value, "start", i)?,
+                    Some(("step", value)) => step = parse_field(value, "step", i)?,
+                    Some(("span", value)) => span = parse_field(value, "span", i)?,
+                    _ => return Err(format!("wig line {}: unexpected '{word}'", i + 1)),
+                }
+            }
+            if start == 0 {
+                return Err(format!("wig line {}: positions are 1-based", i + 1));
+            }
+            mode = if first == "fixedStep" { Mode::Fixed { next: start, step } } else { Mode::Variable };
+            continue;
+        }
+
+        let (position, value) = match &mut mode {
+            Mode::Fixed { next, step } => {
+                let position = *next;
+                *next += *step;
+                (position, first)
+            }
+            Mode::Variable => {
+                let position: usize = parse_field(first, "position", i)?;
+                if position == 0 {
+                    return Err(format!("wig line {}: positions are 1-based", i + 1));
+                }
+                (position, words.next().unwrap_or_default())
+            }
+            Mode::None => return Err(format!("wig line {}: data before a fixedStep/variableStep declaration", i + 1)),
+        };
+
+        intervals.push(SignalInterval {
+            chrom: chrom.clone(),
+            start: position - 1,
+            end: position - 1 + span,
+            value: parse_field(value, "value", i)?,
+        });
+    }
+
+    Ok(intervals)
+}
+
+// Per-position values for one chromosome (the first one in the file when `chrom` is None),
+// scaled to 0..1 by the largest absolute value. Uncovered positions are zero.
+pub fn signal_per_position(intervals: &[SignalInterval], chrom: Option<&str>, len: usize) -> Vec<f32> {
+    let chrom = chrom.or_else(|| intervals.first().map(|interval| interval.chrom.as_str()));
+    let mut values = vec![0.0f32; len];
+
+    for interval in intervals.iter().filter(|interval| Some(interval.chrom.as_str()) == chrom) {
+        for value in values.iter_mut().take(interval.end).skip(interval.start) {
+            *value = interval.value;
+        }
+    }
+
+    let max = values.iter().fold(0.0f32, |max, value| max.max(value.abs()));
+    if max > 0.0 {
+        values.iter_mut().for_each(|value| *value = value.abs() / max);
+    }
+    values
+}
+
+fn is_header(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#') || line.starts_with("track") || line.starts_with("browser")
+}
+
+fn parse_field<T: std::str::FromStr>(field: &str, name: &str, line: usize) -> Result<T, String> {
+    field.parse().map_err(|_| format!("line {}: invalid {name} '{field}'", line + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bedgraph() {
+        let text = "track type=bedGraph\nchr1 0 3 1.5\nchr1\t5\t6\t-3\nchr2 0 10 9\n";
+        let intervals = parse_bedgraph(text).unwrap();
+        assert_eq!(intervals.len(), 3);
+        assert_eq!(intervals[1], SignalInterval { chrom: "chr1".into(), start: 5, end: 6, value: -3.0 });
+
+        assert_eq!(signal_per_position(&intervals, None, 7), vec![0.5, 0.5, 0.5, 0.0, 0.0, 1.0, 0.0]);
+        assert!(parse_bedgraph("chr1 0 3").is_err());
+    }
+
+    #[test]
+    fn parses_wig_steps() {
+        let text = "fixedStep chrom=chrM start=2 step=3 span=2\n1\n2\nvariableStep chrom=chrM\n10 4\n";
+        let intervals = parse_wig(text).unwrap();
+        let starts: Vec<(usize, usize)> = intervals.iter().map(|i| (i.start, i.end)).collect();
+        assert_eq!(starts, vec![(1, 3), (4, 6), (9, 10)]);
+        assert!(parse_wig("1.0\n").is_err());
+    }
+}
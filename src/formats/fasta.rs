@@ -0,0 +1,78 @@
+use std::io::Read;
+use std::path::Path;
+
+use bio::io::fasta;
+
+// The first record of a FASTA file, upper-cased
+pub fn load_first(path: &Path) -> Result<Vec<u8>, String> {
+    let _span = tracing::debug_span!("read", path = %path.display()).entered();
+    let reader = fasta::Reader::from_file(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    first_record(reader, path)
+}
+
+// The first record of FASTA data read from `reader`, with `path` naming it in errors
+pub fn read_first(reader: impl Read, path: &Path) -> Result<Vec<u8>, String> {
+    first_record(fasta::Reader::new(reader), path)
+}
+
+fn first_record<B: std::io::BufRead>(reader: fasta::Reader<B>, path: &Path) -> Result<Vec<u8>, String> {
+    match reader.records().next() {
+        Some(Ok(record)) => {
+            tracing::debug!("Record {} of {} bases", record.id(), record.seq().len());
+            Ok(record.seq().to_ascii_uppercase())
+        }
+        Some(Err(err)) => Err(format!("Failed to parse {}: {err}", path.display())),
+        None => Err(format!("{} has no FASTA records", path.display())),
+    }
+}
+
+// Record `index` (from 0) of FASTA data read from `reader`, upper-cased
+pub fn read_record(reader: impl Read, path: &Path, index: usize) -> Result<Vec<u8>, String> {
+    let mut records = fasta::Reader::new(reader).records();
+    match records.nth(index) {
+        Some(Ok(record)) => {
+            tracing::debug!("Record {} of {} bases", record.id(), record.seq().len());
+            Ok(record.seq().to_ascii_uppercase())
+        }
+        Some(Err(err)) => Err(format!("Failed to parse {}: {err}", path.display())),
+        None => Err(format!("{} has fewer than {} FASTA records", path.display(), index + 1)),
+    }
+}
+
+// Records in a FASTA file, counted from the header lines without parsing the sequences
+pub fn count_records(path: &Path) -> Result<usize, String> {
+    let file = std::fs::File::open(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    let mut count = 0;
+    for line in std::io::BufRead::split(std::io::BufReader::new(file), b'\n') {
+        let line = line.map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        count += usize::from(line.starts_with(b">"));
+    }
+    Ok(count)
+}
+
+// Every record of a FASTA file as (id, upper-cased sequence)
+pub fn load_all(path: &Path) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let _span = tracing::debug_span!("read", path = %path.display()).entered();
+    let reader = fasta::Reader::from_file(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    all_records(reader, path)
+}
+
+// Every record of FASTA data read from `reader`, with `path` naming it in errors
+pub fn read_all(reader: impl Read, path: &Path) -> Result<Vec<(String, Vec<u8>)>, String> {
+    all_records(fasta::Reader::new(reader), path)
+}
+
+fn all_records<B: std::io::BufRead>(reader: fasta::Reader<B>, path: &Path) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let records = reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|err| format!("Failed to parse {}: {err}", path.display()))?;
+            Ok((record.id().to_string(), record.seq().to_ascii_uppercase()))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if records.is_empty() {
+        return Err(format!("{} has no FASTA records", path.display()));
+    }
+    tracing::debug!("{} records", records.len());
+    Ok(records)
+}
@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use crate::seq_analysis::codon_usage::Cds;
+use crate::seq_analysis::motif::Strand;
+
+// One GFF3 feature line. `range` is 0-based and half-open; the file's columns are 1-based
+// and inclusive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Feature {
+    pub seqid: String,
+    pub kind: String,
+    pub range: std::ops::Range<usize>,
+    // None when the strand is '.' or '?'
+    pub strand: Option<Strand>,
+    // Bases to skip from the 5' end before the first complete codon, CDS features only
+    pub phase: Option<usize>,
+    pub id: Option<String>,
+    pub parent: Option<String>,
+}
+
+pub fn load(path: &Path) -> Result<Vec<Feature>, String> {
+    let _span = tracing::debug_span!("read", path = %path.display()).entered();
+    let bytes = std::fs::read(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    tracing::debug!("Read {} bytes", bytes.len());
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        return Err(format!("{} is compressed, decompress it first (gzip -d)", path.display()));
+    }
+    let text = String::from_utf8(bytes).map_err(|_| format!("{} is not a text file", path.display()))?;
+    parse(&text).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+pub fn parse(text: &str) -> Result<Vec<Feature>, String> {
+    let mut features = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        // Embedded sequences follow the annotation and are not features
+        if line.starts_with("##FASTA") {
+            break;
+        }
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [seqid, _source, kind, start, end, _score, strand, phase, attributes] = fields[..] else {
+            return Err(format!("line {}: expected 9 tab-separated columns, found {}", i + 1, fields.len()));
+        };
+
+        let coordinate = |value: &str| match value.parse::<usize>() {
+            Ok(value) if value > 0 => Ok(value),
+            _ => Err(format!("line {}: invalid coordinate '{value}'", i + 1)),
+        };
+        let (start, end) = (coordinate(start)?, coordinate(end)?);
+        if end < start {
+            return Err(format!("line {}: end {end} is before start {start}", i + 1));
+        }
+
+        let strand = match strand {
+            "+" => Some(Strand::Forward),
+            "-" => Some(Strand::Reverse),
+            "." | "?" => None,
+            _ => return Err(format!("line {}: invalid strand '{strand}'", i + 1)),
+        };
+        let phase = match phase {
+            "." => None,
+            "0" | "1" | "2" => phase.parse().ok(),
+            _ => return Err(format!("line {}: invalid phase '{phase}'", i + 1)),
+        };
+
+        let attribute = |key: &str| {
+            attributes
+                .split(';')
+                .filter_map(|pair| pair.trim().split_once('='))
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.to_string())
+        };
+
+        features.push(Feature {
+            seqid: seqid.to_string(),
+            kind: kind.to_string(),
+            range: start - 1..end,
+            strand,
+            phase,
+            id: attribute("ID"),
+            // Features with several parents are joined to the first
+            parent: attribute("Parent").map(|parent| parent.split(',').next().unwrap_or_default().to_string()),
+        });
+    }
+
+    Ok(features)
+}
+
+// Coding sequences on `seqid` (the first sequence when None): CDS features sharing a parent
+// transcript are joined into one, with the phase of the 5'-most segment trimmed off.
+// Unstranded CDS features are skipped.
+pub fn coding_sequences(features: &[Feature], seqid: Option<&str>) -> Vec<Cds> {
+    let Some(seqid) = seqid.or_else(|| features.first().map(|feature| feature.seqid.as_str())) else {
+        return Vec::new();
+    };
+
+    // Keyed by parent, then ID; features with neither stand alone
+    let mut groups: Vec<(Option<&str>, Strand, Vec<&Feature>)> = Vec::new();
+    for feature in features.iter().filter(|feature| feature.seqid == seqid && feature.kind == "CDS") {
+        let Some(strand) = feature.strand else {
+            continue;
+        };
+        let key = feature.parent.as_deref().or(feature.id.as_deref());
+        match groups.iter_mut().find(|(other, other_strand, _)| key.is_some() && *other == key && *other_strand == strand) {
+            Some((_, _, segments)) => segments.push(feature),
+            None => groups.push((key, strand, vec![feature])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(_, strand, mut segments)| {
+            segments.sort_by_key(|feature| feature.range.start);
+            let mut ranges: Vec<_> = segments.iter().map(|feature| feature.range.clone()).collect();
+            match strand {
+                Strand::Forward => {
+                    let first = &mut ranges[0];
+                    first.start = (first.start + segments[0].phase.unwrap_or(0)).min(first.end);
+                }
+                Strand::Reverse => {
+                    let last = ranges.len() - 1;
+                    let range = &mut ranges[last];
+                    range.end = range.end.saturating_sub(segments[last].phase.unwrap_or(0)).max(range.start);
+                }
+            }
+            Cds { segments: ranges, strand }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GFF: &str = "##gff-version 3\n\
+        chr1\t.\tgene\t1\t30\t.\t+\t.\tID=gene1\n\
+        chr1\t.\tCDS\t3\t8\t.\t+\t0\tID=cds1;Parent=mrna1\n\
+        chr1\t.\tCDS\t12\t20\t.\t+\t1\tID=cds1;Parent=mrna1\n\
+        chr1\t.\tCDS\t22\t30\t.\t-\t2\tParent=mrna2\n\
+        chr2\t.\tCDS\t1\t3\t.\t+\t0\tParent=mrna3\n\
+        ##FASTA\n\
+        >chr1\n";
+
+    #[test]
+    fn parses_features() {
+        let features = parse(GFF).unwrap();
+        assert_eq!(features.len(), 5);
+        assert_eq!(features[1].range, 2..8);
+        assert_eq!(features[1].parent.as_deref(), Some("mrna1"));
+        assert_eq!(features[3].strand, Some(Strand::Reverse));
+        assert_eq!(features[0].phase, None);
+        assert!(parse("chr1\t.\tCDS\t5\t4\t.\t+\t0\t.\n").is_err());
+    }
+
+    #[test]
+    fn joins_cds_segments_by_parent() {
+        let features = parse(GFF).unwrap();
+        assert_eq!(coding_sequences(&features, None), [
+            Cds { segments: vec![2..8, 11..20], strand: Strand::Forward },
+            // The phase is trimmed from the 5' end, which is the right end on the reverse strand
+            Cds::contiguous(21..28, Strand::Reverse),
+        ]);
+        assert_eq!(coding_sequences(&features, Some("chr2")).len(), 1);
+    }
+}
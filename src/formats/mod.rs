@@ -0,0 +1,6 @@
+pub mod bam;
+pub mod bedgraph;
+pub mod fasta;
+pub mod gff;
+pub mod msa;
+pub mod vcf;
@@ -0,0 +1,146 @@
+use std::path::Path;
+
+use crate::automaton::protein::is_protein;
+
+// A pre-computed multiple sequence alignment, as aligned FASTA (every record the same length,
+// '-' or '.' for gaps) or Clustal (a "CLUSTAL" header line, then blocks of "id  residues"
+// lines, with the consensus lines below each block ignored)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alignment {
+    pub ids: Vec<String>,
+    // Upper-cased, gaps as '-', all of the same length
+    pub sequences: Vec<Vec<u8>>,
+}
+
+pub const GAP: u8 = b'-';
+
+impl Alignment {
+    pub fn columns(&self) -> usize {
+        self.sequences.first().map_or(0, Vec::len)
+    }
+
+    // How conserved each column is, in 0..1: one minus the Shannon entropy of its residues,
+    // relative to the most an alphabet of 4 bases (or 20 amino acids) can have, times the
+    // share of sequences that aren't gapped there. A column of one residue without gaps is 1.
+    pub fn conservation(&self) -> Vec<f64> {
+        let alphabet: f64 = if self.sequences.iter().any(|seq| is_protein(seq)) { 20.0 } else { 4.0 };
+        (0..self.columns())
+            .map(|column| {
+                let mut counts = [0usize; 256];
+                for seq in &self.sequences {
+                    counts[seq[column] as usize] += 1;
+                }
+                counts[GAP as usize] = 0;
+                let residues = counts.iter().sum::<usize>() as f64;
+                if residues == 0.0 {
+                    return 0.0;
+                }
+                let entropy: f64 = counts
+                    .iter()
+                    .filter(|&&count| count > 0)
+                    .map(|&count| {
+                        let p = count as f64 / residues;
+                        -p * p.log2()
+                    })
+                    .sum();
+                (1.0 - entropy / alphabet.log2()).max(0.0) * residues / self.sequences.len() as f64
+            })
+            .collect()
+    }
+
+    // Conservation at each residue of the first sequence, the one the alignment is read
+    // against: position i of it, gaps left out, gets the score of the column holding it
+    pub fn reference_conservation(&self) -> Vec<f64> {
+        let Some(reference) = self.sequences.first() else {
+            return Vec::new();
+        };
+        self.conservation().into_iter().zip(reference).filter(|&(_, &residue)| residue != GAP).map(|(score, _)| score).collect()
+    }
+}
+
+pub fn load(path: &Path) -> Result<Alignment, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    parse(&text).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+pub fn parse(text: &str) -> Result<Alignment, String> {
+    let mut alignment = Alignment { ids: Vec::new(), sequences: Vec::new() };
+    let mut lines = text.lines().filter(|line| !line.trim().is_empty()).peekable();
+    let clustal = lines.peek().is_some_and(|line| line.starts_with("CLUSTAL"));
+    if clustal {
+        lines.next();
+    }
+    for line in lines {
+        if clustal {
+            // Consensus lines are indented; an optional residue count ends a line
+            if line.starts_with(char::is_whitespace) {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (Some(id), Some(residues)) = (fields.next(), fields.next()) else {
+                return Err(format!("expected 'id residues', found '{line}'"));
+            };
+            let index = alignment.ids.iter().position(|other| other == id).unwrap_or_else(|| {
+                alignment.ids.push(id.to_string());
+                alignment.sequences.push(Vec::new());
+                alignment.ids.len() - 1
+            });
+            alignment.sequences[index].extend(residues.bytes());
+        } else if let Some(header) = line.strip_prefix('>') {
+            alignment.ids.push(header.split_whitespace().next().unwrap_or_default().to_string());
+            alignment.sequences.push(Vec::new());
+        } else {
+            let Some(seq) = alignment.sequences.last_mut() else {
+                return Err("expected a '>' header or a CLUSTAL header first".into());
+            };
+            seq.extend(line.bytes().filter(|residue| !residue.is_ascii_whitespace()));
+        }
+    }
+
+    if alignment.sequences.is_empty() {
+        return Err("no aligned sequences".into());
+    }
+    for seq in &mut alignment.sequences {
+        for residue in seq.iter_mut() {
+            *residue = if *residue == b'.' { GAP } else { residue.to_ascii_uppercase() };
+        }
+    }
+    let columns = alignment.columns();
+    if let Some(index) = alignment.sequences.iter().position(|seq| seq.len() != columns) {
+        return Err(format!("{} has {} columns, {} has {columns}", alignment.ids[index], alignment.sequences[index].len(), alignment.ids[0]));
+    }
+    Ok(alignment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_both_formats_alike() {
+        let fasta = parse(">human chr1\nACGT-A\nC\n>mouse\nacgtta\nc\n>fly\nAC.TTG\nG\n").unwrap();
+        let clustal = parse("CLUSTAL W (1.83) multiple sequence alignment\n\nhuman  ACGT-\nmouse  ACGTT\nfly    AC-TT\n       ** *\n\nhuman  AC 6\nmouse  AC\nfly    GG\n").unwrap();
+        assert_eq!(fasta, clustal);
+        assert_eq!(fasta.ids, ["human", "mouse", "fly"]);
+        assert_eq!(fasta.sequences[2], b"AC-TTGG");
+
+        assert!(parse(">a\nACGT\n>b\nACG\n").unwrap_err().contains("b has 3 columns"));
+        assert!(parse("ACGT\n").is_err());
+        assert!(parse("").is_err());
+    }
+
+    #[test]
+    fn scores_conservation_per_column() {
+        let alignment = parse(">a\nAC-G\n>b\nAG-T\n>c\nAT-A\n>d\nA-AC\n").unwrap();
+        let conservation = alignment.conservation();
+        assert_eq!(conservation[0], 1.0);
+        // Three different bases and a gap
+        let entropy = 3f64.log2();
+        assert!((conservation[1] - (1.0 - entropy / 2.0) * 0.75).abs() < 1e-12);
+        // One sequence, no variation, but mostly gaps
+        assert_eq!(conservation[2], 0.25);
+        // All four bases equally often
+        assert_eq!(conservation[3], 0.0);
+        assert_eq!(alignment.reference_conservation(), [conservation[0], conservation[1], conservation[3]]);
+    }
+}
@@ -0,0 +1,162 @@
+use std::path::Path;
+
+// One VCF record. `position` is converted to 0-based; the file's POS is 1-based.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variant {
+    pub chrom: String,
+    pub position: usize,
+    pub reference: String,
+    pub alternates: Vec<String>,
+    // None when QUAL is '.'
+    pub quality: Option<f64>,
+    pub kind: VariantKind,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VariantKind {
+    Snv,
+    // Any alternate allele changing the length: insertions and deletions
+    Indel,
+    // Multi-base substitutions, symbolic alleles (<DEL>, breakends), no-calls
+    Other,
+}
+
+// Which records to show. Records without a QUAL fail any minimum quality.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct VariantFilter {
+    pub min_quality: Option<f64>,
+    // None keeps every kind
+    pub kind: Option<VariantKind>,
+}
+
+impl VariantFilter {
+    pub fn accepts(&self, variant: &Variant) -> bool {
+        self.kind.is_none_or(|kind| kind == variant.kind)
+            && self.min_quality.is_none_or(|min| variant.quality.is_some_and(|quality| quality >= min))
+    }
+}
+
+impl std::fmt::Display for VariantFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.kind {
+            Some(VariantKind::Snv) => write!(f, "snv")?,
+            Some(VariantKind::Indel) => write!(f, "indel")?,
+            Some(VariantKind::Other) => write!(f, "other")?,
+            None => write!(f, "all")?,
+        }
+        if let Some(min_quality) = self.min_quality {
+            write!(f, " {min_quality}")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn load(path: &Path) -> Result<Vec<Variant>, String> {
+    let _span = tracing::debug_span!("read", path = %path.display()).entered();
+    let bytes = std::fs::read(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    tracing::debug!("Read {} bytes", bytes.len());
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        return Err(format!("{} is compressed, decompress it first (bgzip -d)", path.display()));
+    }
+    let text = String::from_utf8(bytes).map_err(|_| format!("{} is not a text file", path.display()))?;
+    parse(&text).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+pub fn parse(text: &str) -> Result<Vec<Variant>, String> {
+    let mut variants = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [chrom, position, _id, reference, alternates, quality, ..] = fields[..] else {
+            return Err(format!("line {}: expected at least 6 tab-separated columns, found {}", i + 1, fields.len()));
+        };
+
+        let position: usize = position.parse().map_err(|_| format!("line {}: invalid position '{position}'", i + 1))?;
+        if position == 0 {
+            return Err(format!("line {}: positions are 1-based", i + 1));
+        }
+        let quality = match quality {
+            "." => None,
+            quality => Some(quality.parse().map_err(|_| format!("line {}: invalid QUAL '{quality}'", i + 1))?),
+        };
+        let alternates: Vec<String> = alternates.split(',').map(str::to_string).collect();
+
+        variants.push(Variant {
+            chrom: chrom.to_string(),
+            position: position - 1,
+            kind: classify(reference, &alternates),
+            reference: reference.to_string(),
+            alternates,
+            quality,
+        });
+    }
+
+    Ok(variants)
+}
+
+fn classify(reference: &str, alternates: &[String]) -> VariantKind {
+    let is_sequence = |allele: &str| !allele.is_empty() && allele.bytes().all(|base| b"ACGTNacgtn".contains(&base));
+    // '*' marks an allele deleted by an overlapping variant, not a variant of its own here
+    let alternates: Vec<&str> = alternates.iter().map(String::as_str).filter(|allele| *allele != "*").collect();
+
+    if !is_sequence(reference) || alternates.is_empty() || !alternates.iter().all(|allele| is_sequence(allele)) {
+        VariantKind::Other
+    } else if alternates.iter().any(|allele| allele.len() != reference.len()) {
+        VariantKind::Indel
+    } else if reference.len() == 1 {
+        VariantKind::Snv
+    } else {
+        VariantKind::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VCF: &str = "##fileformat=VCFv4.2
+#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO
+chr1\t3\trs1\tG\tA\t50\tPASS\t.
+chr1\t7\t.\tGA\tG\t12.5\tPASS\t.
+chr1\t9\t.\tC\tT,CTT\t.\t.\t.
+chr1\t12\t.\tAT\tGC\t99\tPASS\t.
+chr2\t4\t.\tT\t<DEL>\t80\tPASS\tSVTYPE=DEL
+";
+
+    #[test]
+    fn parses_records_and_classifies_variants() {
+        let variants = parse(VCF).unwrap();
+        assert_eq!(variants.len(), 5);
+        assert_eq!(variants[0], Variant {
+            chrom: "chr1".into(),
+            position: 2,
+            reference: "G".into(),
+            alternates: vec!["A".into()],
+            quality: Some(50.0),
+            kind: VariantKind::Snv,
+        });
+        let kinds: Vec<VariantKind> = variants.iter().map(|variant| variant.kind).collect();
+        assert_eq!(kinds, [VariantKind::Snv, VariantKind::Indel, VariantKind::Indel, VariantKind::Other, VariantKind::Other]);
+        assert_eq!(variants[2].quality, None);
+
+        assert!(parse("chr1\t0\t.\tA\tC\t1").is_err());
+        assert!(parse("chr1\t5\t.\tA").is_err());
+    }
+
+    #[test]
+    fn filters_by_quality_and_kind() {
+        let variants = parse(VCF).unwrap();
+        let positions = |filter: VariantFilter| -> Vec<usize> {
+            variants.iter().filter(|variant| filter.accepts(variant)).map(|variant| variant.position).collect()
+        };
+
+        assert_eq!(positions(VariantFilter { min_quality: None, kind: None }).len(), 5);
+        assert_eq!(positions(VariantFilter { min_quality: Some(20.0), kind: None }), [2, 11, 3]);
+        assert_eq!(positions(VariantFilter { min_quality: None, kind: Some(VariantKind::Indel) }), [6, 8]);
+        assert_eq!(positions(VariantFilter { min_quality: Some(10.0), kind: Some(VariantKind::Indel) }), [6]);
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+use gilrs::{Axis, Button, EventType, Gilrs};
+use tracing::info;
+
+use crate::action::Action;
+use crate::app::App;
+use crate::kiosk::next_theme;
+
+// Gamepad input (`--features gamepad`), for running on a TV with no keyboard in reach. Buttons
+// are bound like keys in the keymap, to actions worked out from the current state. The left
+// stick pans the grid and the right stick zooms it: while a stick is held over the dead zone
+// it repeats a pan or cell size action, a bigger step the further it is pushed.
+
+pub struct ButtonBinding {
+    // The button as listed in the README, in the Xbox layout
+    pub label: &'static str,
+    pub description: &'static str,
+    button: Button,
+    actions: fn(&App) -> Vec<Action>,
+}
+
+// The whole grid seeded again from the offset it was last seeded from
+fn reset(app: &App) -> Vec<Action> {
+    let mut actions = Vec::new();
+    if app.overlay.selection.is_some() {
+        actions.push(Action::ClearSelection);
+    }
+    actions.push(Action::Seed { offset: app.seed_offset() });
+    actions
+}
+
+pub const BUTTONS: &[ButtonBinding] = &[
+    ButtonBinding { label: "A", description: "Pause or resume", button: Button::South, actions: |app| vec![Action::Pause(!app.paused())] },
+    ButtonBinding { label: "B", description: "Step one generation", button: Button::East, actions: |_| vec![Action::Jump(1)] },
+    ButtonBinding { label: "X", description: "Reseed the grid", button: Button::West, actions: reset },
+    ButtonBinding { label: "Y", description: "Next color theme", button: Button::North, actions: |app| next_theme(app.theme()) },
+    ButtonBinding { label: "D-pad up", description: "Twice the speed", button: Button::DPadUp, actions: |app| vec![Action::SetSpeed(app.speed() * 2.0)] },
+    ButtonBinding { label: "D-pad down", description: "Half the speed", button: Button::DPadDown, actions: |app| vec![Action::SetSpeed(app.speed() / 2.0)] },
+    ButtonBinding { label: "Back", description: "Next track, drawn behind the cells", button: Button::Select, actions: |_| vec![Action::SelectTrack(None), Action::ShowTrackLayer(true)] },
+    ButtonBinding { label: "Start", description: "Show or hide the help", button: Button::Start, actions: |app| vec![Action::ShowHelp(!app.help_shown())] },
+    ButtonBinding { label: "Right stick press", description: "Fit and center the grid again", button: Button::RightThumb, actions: |_| vec![Action::SetCellSize(None), Action::SetPan([0, 0])] },
+];
+
+// Share of a stick's travel that counts as resting
+const DEAD_ZONE: f32 = 0.25;
+const STICK_REPEAT: Duration = Duration::from_millis(100);
+// Logical pixels panned and cell pixels zoomed per repeat with a stick pushed all the way
+const PAN_STEP: f32 = 40.0;
+const ZOOM_STEP: f32 = 3.0;
+
+// Actions for one repeat of the sticks: `left` pans the view, so the grid moves the other
+// way, and `right_y` grows the cells pushed up. Stick values are -1 to 1, up positive.
+pub fn stick_actions(pan: [i32; 2], cell_size: u32, left: [f32; 2], right_y: f32) -> Vec<Action> {
+    let deflection = |value: f32| if value.abs() < DEAD_ZONE { 0.0 } else { value };
+    let mut actions = Vec::new();
+    let step = left.map(|value| (deflection(value) * PAN_STEP).round() as i32);
+    if step != [0, 0] {
+        actions.push(Action::SetPan([pan[0] - step[0], pan[1] - step[1]]));
+    }
+    let zoom = (deflection(right_y) * ZOOM_STEP).round() as i64;
+    if zoom != 0 {
+        actions.push(Action::SetCellSize(Some((cell_size as i64 + zoom).max(1) as u32)));
+    }
+    actions
+}
+
+pub struct Gamepads {
+    gilrs: Gilrs,
+    last_repeat: Instant,
+}
+
+impl Gamepads {
+    pub fn new() -> Result<Self, String> {
+        let gilrs = Gilrs::new().map_err(|err| format!("Gamepads unavailable: {err}"))?;
+        for (_, gamepad) in gilrs.gamepads() {
+            info!("Gamepad: {}", gamepad.name());
+        }
+        Ok(Self { gilrs, last_repeat: Instant::now() })
+    }
+
+    // Actions for the buttons pressed since the last poll and, once per repeat, the sticks of
+    // every connected gamepad
+    pub fn poll(&mut self, app: &App) -> Vec<Action> {
+        let mut actions = Vec::new();
+        while let Some(event) = self.gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) => {
+                    if let Some(binding) = BUTTONS.iter().find(|binding| binding.button == button) {
+                        actions.extend((binding.actions)(app));
+                    }
+                }
+                EventType::Connected => info!("Gamepad connected: {}", self.gilrs.gamepad(event.id).name()),
+                EventType::Disconnected => info!("Gamepad disconnected"),
+                _ => {}
+            }
+        }
+
+        if self.last_repeat.elapsed() >= STICK_REPEAT {
+            self.last_repeat = Instant::now();
+            let (mut pan, mut cell_size) = (app.layout().pan, app.cell_size());
+            for (_, gamepad) in self.gilrs.gamepads() {
+                let left = [gamepad.value(Axis::LeftStickX), gamepad.value(Axis::LeftStickY)];
+                // Sticks on several gamepads add up
+                for action in stick_actions(pan, cell_size, left, gamepad.value(Axis::RightStickY)) {
+                    match action {
+                        Action::SetPan(to) => pan = to,
+                        Action::SetCellSize(Some(to)) => cell_size = to,
+                        _ => {}
+                    }
+                    actions.push(action);
+                }
+            }
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sticks_pan_and_zoom_past_the_dead_zone() {
+        assert!(stick_actions([0, 0], 10, [0.2, -0.1], 0.2).is_empty());
+        // Pushing right and up moves the view there, so the grid goes left and down
+        assert_eq!(stick_actions([5, 5], 10, [1.0, 0.5], 0.0), [Action::SetPan([-35, -15])]);
+        assert_eq!(stick_actions([0, 0], 10, [0.0, 0.0], 1.0), [Action::SetCellSize(Some(13))]);
+        assert_eq!(stick_actions([0, 0], 2, [0.0, 0.0], -1.0), [Action::SetCellSize(Some(1))]);
+        // Every button is bound once
+        assert!(BUTTONS.iter().enumerate().all(|(i, binding)| BUTTONS[..i].iter().all(|other| other.button != binding.button)));
+    }
+}
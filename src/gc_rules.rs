@@ -0,0 +1,118 @@
+use crate::rule::Rule;
+use crate::seq_analysis::cache::GcCounts;
+use crate::strip::SeedMap;
+
+// Sequence-dependent dynamics (`gcrule`): every cell follows a rule picked by the GC content
+// of the bases around the one it was seeded from. Cells in GC-rich stretches follow `rich`,
+// those in GC-poor ones `poor`, and the rest the universe's own rule, so the same pattern
+// grows or dies depending on where in the genome it sits.
+
+// Bases centered on a cell's base that its GC content is measured over
+pub const WINDOW: usize = 101;
+// GC content below which a cell is poor, and above which it is rich
+pub const POOR_BELOW: f64 = 0.4;
+pub const RICH_ABOVE: f64 = 0.6;
+
+// More births in GC-rich stretches (B36/S23, HighLife), harder survival in GC-poor ones
+pub const DEFAULT_RICH: Rule = Rule { birth: (1 << 3) | (1 << 6), survive: (1 << 2) | (1 << 3) };
+pub const DEFAULT_POOR: Rule = Rule { birth: 1 << 3, survive: 1 << 2 };
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GcClass {
+    Average,
+    Poor,
+    Rich,
+}
+
+#[derive(Clone, Debug)]
+pub struct GcRules {
+    pub poor: Rule,
+    pub rich: Rule,
+    // Class of every cell, row-major
+    pub classes: Vec<GcClass>,
+}
+
+impl GcRules {
+    pub fn new(poor: Rule, rich: Rule, dna: &[u8], seed_map: &SeedMap, rows: u32, cols: u32) -> Self {
+        let mut rules = Self { poor, rich, classes: Vec::new() };
+        rules.classify(dna, seed_map, rows, cols);
+        rules
+    }
+
+    // Rule of a cell of the given class, with `rule` for the average ones
+    pub fn rule_for(&self, class: GcClass, rule: Rule) -> Rule {
+        match class {
+            GcClass::Average => rule,
+            GcClass::Poor => self.poor,
+            GcClass::Rich => self.rich,
+        }
+    }
+
+    // Recompute the classes after a reseed or a change of sequence. Cells seeded from past the
+    // end of the sequence are average.
+    pub fn classify(&mut self, dna: &[u8], seed_map: &SeedMap, rows: u32, cols: u32) {
+        self.classify_with(dna, seed_map, rows, cols, &mut GcCounts::new());
+    }
+
+    // The same, counting G/C with `counts` (which must be for `dna`), so only the bases the
+    // seeded stretch moved onto are counted again
+    pub fn classify_with(&mut self, dna: &[u8], seed_map: &SeedMap, rows: u32, cols: u32, counts: &mut GcCounts) {
+        let positions: Vec<Option<usize>> = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| seed_map.position(row, col)))
+            .map(|position| position.filter(|&position| position < dna.len()))
+            .collect();
+        let (Some(lowest), Some(highest)) = (positions.iter().flatten().min(), positions.iter().flatten().max()) else {
+            self.classes = vec![GcClass::Average; positions.len()];
+            return;
+        };
+
+        // The stretch the windows cover
+        let start = lowest.saturating_sub(WINDOW / 2);
+        let end = (highest + WINDOW / 2 + 1).min(dna.len());
+        counts.cover(dna, start..end);
+
+        self.classes = positions
+            .iter()
+            .map(|position| {
+                let Some(position) = *position else {
+                    return GcClass::Average;
+                };
+                let (from, to) = (position.saturating_sub(WINDOW / 2).max(start), (position + WINDOW / 2 + 1).min(end));
+                let gc = f64::from(counts.count(from, to)) / (to - from) as f64;
+                if gc < POOR_BELOW {
+                    GcClass::Poor
+                } else if gc > RICH_ABOVE {
+                    GcClass::Rich
+                } else {
+                    GcClass::Average
+                }
+            })
+            .collect();
+    }
+
+    // Poor, average and rich cells
+    pub fn counts(&self) -> [usize; 3] {
+        [GcClass::Poor, GcClass::Average, GcClass::Rich].map(|class| self.classes.iter().filter(|&&other| other == class).count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_cells_by_local_gc() {
+        // An AT-rich stretch, a balanced one and a GC-rich one, each longer than the window
+        let dna: Vec<u8> = [b"AT".repeat(150), b"AG".repeat(150), b"GC".repeat(150)].concat();
+        let mut seed_map = SeedMap::new(1, 3);
+        seed_map.record(&crate::selection::Region { row: 0, col: 1, rows: 1, cols: 1 }, 450);
+        seed_map.record(&crate::selection::Region { row: 0, col: 2, rows: 1, cols: 1 }, 899);
+        let rules = GcRules::new(DEFAULT_POOR, DEFAULT_RICH, &dna, &seed_map, 1, 3);
+        assert_eq!(rules.classes, [GcClass::Poor, GcClass::Average, GcClass::Rich]);
+        assert_eq!(rules.counts(), [1, 1, 1]);
+        assert_eq!(rules.rule_for(GcClass::Average, Rule::CONWAY), Rule::CONWAY);
+
+        let rules = GcRules::new(DEFAULT_POOR, DEFAULT_RICH, b"GC", &SeedMap::new(2, 2), 2, 2);
+        assert_eq!(rules.classes, [GcClass::Rich, GcClass::Rich, GcClass::Average, GcClass::Average]);
+    }
+}
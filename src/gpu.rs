@@ -0,0 +1,747 @@
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use wgpu::*;
+use tracing::{error, warn};
+use wgpu::util::DeviceExt;
+use winit::window::Window;
+
+use crate::grid_texture::GridTexture;
+use crate::render::grid_resources::GpuGridResources;
+use crate::render::post::PostProcess;
+use crate::render::letters::LetterView;
+use crate::render::profiler::GpuProfiler;
+use crate::vertex::{Motion, Vertex};
+
+// Everything tied to a particular adapter/device, shared by all windows. When the device is
+// lost (driver reset, GPU switch) it is thrown away and rebuilt with `Gpu::new`, together
+// with every window's surface.
+pub struct Gpu {
+    pub adapter: Adapter,
+    pub device: Device,
+    pub queue: Queue,
+    shader: ShaderModule,
+    pipeline_layout: PipelineLayout,
+    time_layout: BindGroupLayout,
+    pub software: bool,
+    // Timestamp queries for the profiler overlay, when the adapter has them
+    pub profiler: Option<GpuProfiler>,
+    lost: Arc<AtomicBool>,
+}
+
+// A window's surface and the geometry drawn into it. Each window has its own swapchain
+// configuration and pipeline (surface formats may differ), but uses the shared device.
+pub struct WindowSurface {
+    pub window: Arc<Window>,
+    surface: Surface<'static>,
+    pub config: SurfaceConfiguration,
+    render_pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    vertex_count: u32,
+    time: TimeUniform,
+    // Smooth transitions: the pipeline playing them, if the shader has a `vs_smooth`, and the
+    // motions of the first vertices, none while they are off
+    smooth_pipeline: Option<RenderPipeline>,
+    motion_buffer: Option<Buffer>,
+    motion_count: u32,
+    // What draws the cells when they aren't vertex geometry: the texture renderer's cell
+    // textures, drawn before the vertex geometry, or a 3D view (torus or heightfield) drawn
+    // in a pass of its own, with only the vertex geometry's overlays over it
+    pub grid: GpuGridResources,
+    // Base letters drawn inside the cells when zoomed in, over the cells' vertex geometry
+    pub letters: Option<LetterView>,
+    // Post-processing chain; without it the frame is drawn straight to the surface
+    pub post: Option<PostProcess>,
+    // Whether its passes are timed by the profiler (only the grid window's are)
+    profiled: bool,
+}
+
+impl Gpu {
+    // Pick a device able to present to `window` and set up that window's surface
+    pub fn new(instance: &Instance, window: Arc<Window>, vertices: &[Vertex]) -> (Self, WindowSurface) {
+        // Prefer the real GPU, but degrade to the software adapter rather than giving up
+        for force_fallback_adapter in [false, true] {
+            let surface = instance.create_surface(window.clone()).unwrap();
+
+            let Some(adapter) = pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
+                power_preference: PowerPreference::HighPerformance,
+                force_fallback_adapter,
+                compatible_surface: Some(&surface),
+            })) else {
+                warn!("No {} adapter available", if force_fallback_adapter { "fallback" } else { "hardware" });
+                continue;
+            };
+
+            let (device, queue) = match pollster::block_on(adapter.request_device(
+                &DeviceDescriptor {
+                    label: None,
+                    // Timestamps are only for the profiler; devices without them still work
+                    required_features: adapter.features() & Features::TIMESTAMP_QUERY,
+                    required_limits: Limits::default(),
+                    memory_hints: Default::default(),
+                },
+                None,
+            )) {
+                Ok(device) => device,
+                Err(err) => {
+                    warn!("Failed to request device from {}: {err}", adapter.get_info().name);
+                    continue;
+                }
+            };
+
+            if force_fallback_adapter {
+                warn!("Falling back to software rendering ({})", adapter.get_info().name);
+            }
+
+            let gpu = Self::from_device(adapter, device, queue, force_fallback_adapter);
+            let target = gpu.configure(window, surface, vertices, true);
+            return (gpu, target);
+        }
+
+        panic!("No usable GPU adapter, not even the software fallback");
+    }
+
+    // A device for rendering without a window, as in the golden-image tests. The software
+    // adapter is preferred so output doesn't depend on the machine's GPU, and only the
+    // downlevel limits are asked for so it also works on GL.
+    pub fn headless(instance: &Instance) -> Result<Self, String> {
+        for force_fallback_adapter in [true, false] {
+            let Some(adapter) = pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
+                power_preference: PowerPreference::None,
+                force_fallback_adapter,
+                compatible_surface: None,
+            })) else {
+                continue;
+            };
+            let software = force_fallback_adapter || adapter.get_info().device_type == DeviceType::Cpu;
+            let (device, queue) = pollster::block_on(adapter.request_device(
+                &DeviceDescriptor {
+                    label: None,
+                    required_features: Features::empty(),
+                    required_limits: Limits::downlevel_defaults().using_resolution(adapter.limits()),
+                    memory_hints: Default::default(),
+                },
+                None,
+            ))
+            .map_err(|err| format!("Failed to request device from {}: {err}", adapter.get_info().name))?;
+            return Ok(Self::from_device(adapter, device, queue, software));
+        }
+        Err("No GPU adapter available".into())
+    }
+
+    fn from_device(adapter: Adapter, device: Device, queue: Queue, software: bool) -> Self {
+        let lost = Arc::new(AtomicBool::new(false));
+
+        let lost_flag = lost.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            error!("GPU device lost ({reason:?}): {message}");
+            lost_flag.store(true, Ordering::SeqCst);
+        });
+
+        // The default handler panics; out-of-memory is treated like a lost device instead
+        let error_flag = lost.clone();
+        device.on_uncaptured_error(Box::new(move |err| {
+            error!("GPU error: {err}");
+            if matches!(err, Error::OutOfMemory { .. }) {
+                error_flag.store(true, Ordering::SeqCst);
+            }
+        }));
+
+        let shader = Self::builtin_shader(&device);
+
+        let time_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Time Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX_FRAGMENT,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts: &[&time_layout],
+            push_constant_ranges: &[],
+        });
+
+        let profiler = GpuProfiler::new(&device, &queue);
+        Self { adapter, device, queue, shader, pipeline_layout, time_layout, software, profiler, lost }
+    }
+
+    // Surface for another window, drawn with this device
+    pub fn create_surface(&self, instance: &Instance, window: Arc<Window>, vertices: &[Vertex]) -> Result<WindowSurface, String> {
+        let surface = instance.create_surface(window.clone()).map_err(|err| format!("Failed to create surface: {err}"))?;
+        if surface.get_capabilities(&self.adapter).formats.is_empty() {
+            return Err(format!("{} can't present to this window", self.adapter.get_info().name));
+        }
+        Ok(self.configure(window, surface, vertices, false))
+    }
+
+    fn configure(&self, window: Arc<Window>, surface: Surface<'static>, vertices: &[Vertex], profiled: bool) -> WindowSurface {
+        let size = window.inner_size();
+        let surface_caps = surface.get_capabilities(&self.adapter);
+        let surface_format = surface_caps.formats[0];
+        let config = SurfaceConfiguration {
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            format: surface_format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: PresentMode::Fifo,
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        surface.configure(&self.device, &config);
+
+        let vertex_buffer = self.device.create_buffer_init(
+            &util::BufferInitDescriptor {
+                label: Some("Vertex Buffer"),
+                contents: bytemuck::cast_slice(vertices),
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            }
+        );
+
+        let render_pipeline = self.create_pipeline(&self.shader, config.format, false);
+        let smooth_pipeline = self.create_smooth_pipeline(&self.shader, config.format);
+        let grid = GpuGridResources::new(config.format, (config.width, config.height));
+
+        WindowSurface {
+            window,
+            surface,
+            config,
+            render_pipeline,
+            vertex_buffer,
+            vertex_count: vertices.len() as u32,
+            time: TimeUniform::new(self),
+            smooth_pipeline,
+            motion_buffer: None,
+            motion_count: 0,
+            grid,
+            letters: None,
+            post: None,
+            profiled,
+        }
+    }
+
+    fn builtin_shader(device: &Device) -> ShaderModule {
+        device.create_shader_module(include_wgsl!("shader.wgsl"))
+    }
+
+    // The cell pipeline, or with `smooth` the one playing smooth transitions, which also reads
+    // the vertices' motions from a second buffer
+    fn create_pipeline(&self, shader: &ShaderModule, format: TextureFormat, smooth: bool) -> RenderPipeline {
+        let buffers = [Vertex::desc(), Motion::desc()];
+        self.device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some(if smooth { "Smooth Render Pipeline" } else { "Render Pipeline" }),
+            layout: Some(&self.pipeline_layout),
+            vertex: VertexState {
+                module: shader,
+                entry_point: Some(if smooth { "vs_smooth" } else { "vs_main" }),
+                buffers: &buffers[..if smooth { 2 } else { 1 }],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::REPLACE),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    // The smooth transition pipeline, if the shader has a `vs_smooth` entry point; custom
+    // shaders without one step crisply
+    fn create_smooth_pipeline(&self, shader: &ShaderModule, format: TextureFormat) -> Option<RenderPipeline> {
+        self.device.push_error_scope(ErrorFilter::Validation);
+        let pipeline = self.create_pipeline(shader, format, true);
+        pollster::block_on(self.device.pop_error_scope()).is_none().then_some(pipeline)
+    }
+
+    // Compile WGSL source for the cell pipeline, checking that it also builds a pipeline for
+    // `format`. Errors are returned instead of reaching the uncaptured error handler.
+    pub fn compile_shader(&self, source: &str, format: TextureFormat) -> Result<ShaderModule, String> {
+        self.device.push_error_scope(ErrorFilter::Validation);
+        let shader = self.device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Custom Shader"),
+            source: ShaderSource::Wgsl(source.into()),
+        });
+        self.create_pipeline(&shader, format, false);
+        match pollster::block_on(self.device.pop_error_scope()) {
+            Some(err) => Err(err.to_string()),
+            None => Ok(shader),
+        }
+    }
+
+    // Replace the cell shader; None goes back to the built-in one. Every window's pipeline
+    // must be rebuilt with `WindowSurface::rebuild_pipeline` afterwards.
+    pub fn set_shader(&mut self, shader: Option<ShaderModule>) {
+        self.shader = shader.unwrap_or_else(|| Self::builtin_shader(&self.device));
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::SeqCst)
+    }
+}
+
+impl WindowSurface {
+    pub fn resize(&mut self, gpu: &Gpu, width: u32, height: u32) {
+        // Minimized windows report a zero size, which is not a valid surface
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.config.width = width;
+        self.config.height = height;
+        self.surface.configure(&gpu.device, &self.config);
+        if let Some(post) = self.post.as_mut() {
+            post.resize(gpu, self.config.format, width, height);
+        }
+        self.grid.resize(gpu, width, height);
+    }
+
+    pub fn rebuild_pipeline(&mut self, gpu: &Gpu) {
+        self.render_pipeline = gpu.create_pipeline(&gpu.shader, self.config.format, false);
+        self.smooth_pipeline = gpu.create_smooth_pipeline(&gpu.shader, self.config.format);
+    }
+
+    // How far the frame is through the smooth transition into the generation shown, 0 to 1
+    pub fn set_progress(&self, gpu: &Gpu, progress: f32) {
+        gpu.queue.write_buffer(&self.time.buffer, 0, bytemuck::bytes_of(&[progress, 0.0, 0.0, 0.0]));
+    }
+
+    pub fn upload(&mut self, gpu: &Gpu, vertices: &[Vertex]) {
+        let contents: &[u8] = bytemuck::cast_slice(vertices);
+        if contents.len() as BufferAddress > self.vertex_buffer.size() {
+            // More geometry than the buffer was created for (e.g. a new track): grow it
+            self.vertex_buffer = gpu.device.create_buffer_init(&util::BufferInitDescriptor {
+                label: Some("Vertex Buffer"),
+                contents,
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            });
+        } else if !contents.is_empty() {
+            gpu.queue.write_buffer(&self.vertex_buffer, 0, contents);
+        }
+        self.vertex_count = vertices.len() as u32;
+    }
+
+    // Motions of the first uploaded vertices, played while a smooth transition is on; empty
+    // draws every vertex as it is
+    pub fn upload_motions(&mut self, gpu: &Gpu, motions: &[Motion]) {
+        let contents: &[u8] = bytemuck::cast_slice(motions);
+        match &self.motion_buffer {
+            _ if contents.is_empty() => {}
+            Some(buffer) if contents.len() as BufferAddress <= buffer.size() => gpu.queue.write_buffer(buffer, 0, contents),
+            _ => {
+                self.motion_buffer = Some(gpu.device.create_buffer_init(&util::BufferInitDescriptor {
+                    label: Some("Motion Buffer"),
+                    contents,
+                    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                }));
+            }
+        }
+        self.motion_count = motions.len() as u32;
+    }
+
+    pub fn render(&mut self, gpu: &Gpu, clear_color: Color) {
+        let output = match self.surface.get_current_texture() {
+            Ok(output) => output,
+            Err(SurfaceError::Lost | SurfaceError::Outdated) => {
+                self.surface.configure(&gpu.device, &self.config);
+                return;
+            }
+            Err(SurfaceError::Timeout) => return,
+            Err(err @ (SurfaceError::OutOfMemory | SurfaceError::Other)) => {
+                warn!("Surface error: {err}");
+                gpu.lost.store(true, Ordering::SeqCst);
+                return;
+            }
+        };
+        let view = output.texture.create_view(&TextureViewDescriptor::default());
+
+        let mut encoder = gpu.device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Render Encoder"),
+        });
+        self.draw_frame(gpu, &mut encoder, &view, clear_color);
+
+        gpu.queue.submit(std::iter::once(encoder.finish()));
+        self.profiled_submit(gpu);
+        output.present();
+    }
+
+    // Render one frame offscreen at the current surface size and read it back as
+    // tightly packed RGBA8 rows, top row first.
+    pub fn capture(&mut self, gpu: &Gpu, clear_color: Color) -> (u32, u32, Vec<u8>) {
+        let (width, height) = (self.config.width, self.config.height);
+        let size = Extent3d { width, height, depth_or_array_layers: 1 };
+
+        let texture = gpu.device.create_texture(&TextureDescriptor {
+            label: Some("Capture Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: self.config.format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+
+        let mut encoder = gpu.device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Capture Encoder"),
+        });
+        self.draw_frame(gpu, &mut encoder, &view, clear_color);
+        let pixels = read_back(gpu, encoder, &texture);
+        self.profiled_submit(gpu);
+        (width, height, pixels)
+    }
+
+    // Draw the frame into `view`, through the post-processing chain when there is one
+    fn draw_frame(&mut self, gpu: &Gpu, encoder: &mut CommandEncoder, view: &TextureView, clear_color: Color) {
+        let profiler = gpu.profiler.as_ref().filter(|_| self.profiled);
+        match self.post.take() {
+            Some(mut post) => {
+                self.draw(encoder, post.scene(), clear_color, profiler);
+                post.apply(gpu, encoder, view, profiler);
+                self.post = Some(post);
+            }
+            None => self.draw(encoder, view, clear_color, profiler),
+        }
+        if let Some(profiler) = profiler {
+            profiler.resolve(encoder);
+        }
+    }
+
+    fn profiled_submit(&self, gpu: &Gpu) {
+        if let Some(profiler) = gpu.profiler.as_ref().filter(|_| self.profiled) {
+            profiler.submitted();
+        }
+    }
+
+    fn draw(&self, encoder: &mut CommandEncoder, view: &TextureView, clear_color: Color, profiler: Option<&GpuProfiler>) {
+        // A 3D view's pass clears the frame, and the overlays are drawn over what it left
+        let load = match (&self.grid.torus, &self.grid.heightfield) {
+            (Some(torus), _) => {
+                torus.draw(encoder, view, clear_color, profiler.and_then(|profiler| profiler.render_writes("torus")));
+                LoadOp::Load
+            }
+            (None, Some(heightfield)) => {
+                heightfield.draw(encoder, view, clear_color, profiler.and_then(|profiler| profiler.render_writes("heightfield")));
+                LoadOp::Load
+            }
+            (None, None) => LoadOp::Clear(clear_color),
+        };
+        let grid_texture = self.grid.texture.as_ref();
+        let smooth = match (&self.smooth_pipeline, &self.motion_buffer) {
+            (Some(pipeline), Some(buffer)) if self.motion_count > 0 => Some((pipeline, buffer, self.motion_count.min(self.vertex_count))),
+            _ => None,
+        };
+        let cells = Cells { pipeline: &self.render_pipeline, vertex_buffer: &self.vertex_buffer, vertex_count: self.vertex_count, time: &self.time.bind_group, smooth, grid_texture, letters: self.letters.as_ref() };
+        cells.draw(encoder, view, load, profiler.and_then(|profiler| profiler.render_writes("grid")));
+    }
+}
+
+// What the main pass draws: the cell texture, if any, then the vertex geometry over it, with
+// the base letters, if any, drawn partway through it
+struct Cells<'a> {
+    pipeline: &'a RenderPipeline,
+    vertex_buffer: &'a Buffer,
+    vertex_count: u32,
+    time: &'a BindGroup,
+    // The smooth transition pipeline and motions, drawing the vertices they cover
+    smooth: Option<(&'a RenderPipeline, &'a Buffer, u32)>,
+    grid_texture: Option<&'a GridTexture>,
+    letters: Option<&'a LetterView>,
+}
+
+impl Cells<'_> {
+    fn draw(&self, encoder: &mut CommandEncoder, view: &TextureView, load: LoadOp<Color>, timestamp_writes: Option<RenderPassTimestampWrites>) {
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load,
+                    store: StoreOp::Store,
+                },
+            })],
+            timestamp_writes,
+            ..Default::default()
+        });
+
+        if let Some(grid_texture) = self.grid_texture {
+            grid_texture.draw(&mut render_pass);
+        }
+        let split = self.letters.map_or(self.vertex_count, |letters| letters.after.min(self.vertex_count));
+        self.draw_vertices(&mut render_pass, 0..split);
+        if let Some(letters) = self.letters {
+            letters.draw(&mut render_pass);
+        }
+        self.draw_vertices(&mut render_pass, split..self.vertex_count);
+    }
+
+    fn draw_vertices(&self, render_pass: &mut RenderPass, vertices: Range<u32>) {
+        // Empty buffers can't be bound; with the texture renderer there may be nothing over it
+        if vertices.is_empty() {
+            return;
+        }
+        render_pass.set_bind_group(0, self.time, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        let mut plain = vertices.clone();
+        if let Some((pipeline, motions, count)) = self.smooth.filter(|&(_, _, count)| count > vertices.start) {
+            plain.start = count.min(vertices.end);
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_vertex_buffer(1, motions.slice(..));
+            render_pass.draw(vertices.start..plain.start, 0..1);
+        }
+        if !plain.is_empty() {
+            render_pass.set_pipeline(self.pipeline);
+            render_pass.draw(plain, 0..1);
+        }
+    }
+}
+
+// A fixed-size target drawn like a window surface but without a window, for rendering
+// known universes in the golden-image tests and exporting the analysis charts. It draws the
+// cells and overlays only: no 3D views and no post-processing.
+pub struct Offscreen {
+    pub width: u32,
+    pub height: u32,
+    render_pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    vertex_count: u32,
+    time: TimeUniform,
+    pub grid_texture: Option<GridTexture>,
+}
+
+impl Offscreen {
+    // sRGB like most window surfaces, so colors come out as they do on screen
+    pub const FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+    pub fn new(gpu: &Gpu, width: u32, height: u32, vertices: &[Vertex]) -> Self {
+        let vertex_buffer = gpu.device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Offscreen Vertex Buffer"),
+            contents: bytemuck::cast_slice(vertices),
+            usage: BufferUsages::VERTEX,
+        });
+        let render_pipeline = gpu.create_pipeline(&gpu.shader, Self::FORMAT, false);
+        Self { width, height, render_pipeline, vertex_buffer, vertex_count: vertices.len() as u32, time: TimeUniform::new(gpu), grid_texture: None }
+    }
+
+    // Draw a frame and read it back as tightly packed RGBA8 rows, top row first
+    pub fn capture(&self, gpu: &Gpu, clear_color: Color) -> Vec<u8> {
+        let texture = gpu.device.create_texture(&TextureDescriptor {
+            label: Some("Offscreen Texture"),
+            size: Extent3d { width: self.width, height: self.height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&TextureViewDescriptor::default());
+        let mut encoder = gpu.device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some("Offscreen Encoder"),
+        });
+        let cells = Cells { pipeline: &self.render_pipeline, vertex_buffer: &self.vertex_buffer, vertex_count: self.vertex_count, time: &self.time.bind_group, smooth: None, grid_texture: self.grid_texture.as_ref(), letters: None };
+        cells.draw(&mut encoder, &view, LoadOp::Clear(clear_color), None);
+        read_back(gpu, encoder, &texture)
+    }
+}
+
+// The cell shader's time uniform: how far the frame is through the smooth transition into
+// the generation shown, padded to 16 bytes. It starts at 1, the generation fully shown.
+struct TimeUniform {
+    buffer: Buffer,
+    bind_group: BindGroup,
+}
+
+impl TimeUniform {
+    fn new(gpu: &Gpu) -> Self {
+        let buffer = gpu.device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Time Buffer"),
+            contents: bytemuck::bytes_of(&[1.0f32, 0.0, 0.0, 0.0]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let bind_group = gpu.device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Time Bind Group"),
+            layout: &gpu.time_layout,
+            entries: &[BindGroupEntry { binding: 0, resource: buffer.as_entire_binding() }],
+        });
+        Self { buffer, bind_group }
+    }
+}
+
+// Copy `texture` out after the commands in `encoder` and wait for it, as tightly packed RGBA8
+// rows, top row first
+fn read_back(gpu: &Gpu, mut encoder: CommandEncoder, texture: &Texture) -> Vec<u8> {
+    let (width, height) = (texture.width(), texture.height());
+    let size = Extent3d { width, height, depth_or_array_layers: 1 };
+    // Buffer rows must be padded to COPY_BYTES_PER_ROW_ALIGNMENT
+    let row_bytes = width * 4;
+    let padded_row_bytes = row_bytes.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT) * COPY_BYTES_PER_ROW_ALIGNMENT;
+    let buffer = gpu.device.create_buffer(&BufferDescriptor {
+        label: Some("Capture Buffer"),
+        size: (padded_row_bytes * height) as BufferAddress,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    encoder.copy_texture_to_buffer(
+        TexelCopyTextureInfo {
+            texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes),
+                rows_per_image: Some(height),
+            },
+        },
+        size,
+    );
+    gpu.queue.submit(std::iter::once(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    slice.map_async(MapMode::Read, |result| {
+        if let Err(err) = result {
+            warn!("Failed to read back frame: {err}");
+        }
+    });
+    gpu.device.poll(Maintain::Wait);
+
+    let mut pixels = Vec::with_capacity((row_bytes * height) as usize);
+    for row in slice.get_mapped_range().chunks(padded_row_bytes as usize) {
+        pixels.extend_from_slice(&row[..row_bytes as usize]);
+    }
+    buffer.unmap();
+
+    // Surfaces are commonly BGRA; callers always get RGBA
+    if matches!(texture.format(), TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb) {
+        pixels.chunks_exact_mut(4).for_each(|pixel| pixel.swap(0, 2));
+    }
+    pixels
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use winit::dpi::PhysicalSize;
+
+    use super::*;
+    use crate::export::png;
+    use crate::grid_texture::GridLayout;
+    use crate::selection::Region;
+    use crate::universe::Universe;
+    use crate::vertex::{Overlay, create_grid_vertices, create_overlay_vertices};
+    use crate::viewport::{CellLayout, Viewport};
+
+    // A pixel matches its reference when no channel is further off than this, and a frame
+    // matches when at most a few pixels don't: adapters round colors and edges slightly
+    // differently
+    const CHANNEL_TOLERANCE: u8 = 4;
+    const MAX_DIFFERENT_PIXELS: usize = 8;
+    const SIZE: u32 = 96;
+    const CLEAR: Color = Color { r: 0.05, g: 0.05, b: 0.15, a: 1.0 };
+
+    // Draw the universe as the geometry or the texture renderer would, in a SIZE x SIZE frame
+    fn render(gpu: &Gpu, universe: &Universe, overlay: &Overlay, texture: bool) -> Vec<u8> {
+        let geometry = Viewport::new(PhysicalSize::new(SIZE, SIZE), 1.0).grid_geometry(universe.rows, universe.cols, &CellLayout::default());
+        let vertices = if texture { create_overlay_vertices(universe, &geometry, overlay) } else { create_grid_vertices(universe, &geometry, overlay) };
+        let mut target = Offscreen::new(gpu, SIZE, SIZE, &vertices);
+        if texture {
+            let grid_texture = GridTexture::new(gpu, Offscreen::FORMAT, GridLayout::new(&geometry, universe.rows, universe.cols));
+            grid_texture.upload(gpu, universe, overlay.selection);
+            target.grid_texture = Some(grid_texture);
+        }
+        target.capture(gpu, CLEAR)
+    }
+
+    // Compare a frame with tests/golden/<name>.png. With BIO_RUST_BLESS set the reference is
+    // written instead, after a deliberate change to how things look.
+    fn check_golden(name: &str, pixels: &[u8]) {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{name}.png"));
+        if std::env::var_os("BIO_RUST_BLESS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            png::write_rgba(&path, SIZE, SIZE, pixels, None).unwrap();
+            return;
+        }
+        let (width, height, expected) = png::read_rgba(&path).unwrap_or_else(|err| panic!("{}: {err} (BIO_RUST_BLESS=1 writes it)", path.display()));
+        assert_eq!((width, height), (SIZE, SIZE), "{name}");
+        let different = pixels
+            .chunks_exact(4)
+            .zip(expected.chunks_exact(4))
+            .filter(|(pixel, expected)| pixel.iter().zip(*expected).any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE))
+            .count();
+        if different > MAX_DIFFERENT_PIXELS {
+            let actual: PathBuf = std::env::temp_dir().join(format!("bio-rust-golden-{name}.png"));
+            let _ = png::write_rgba(&actual, SIZE, SIZE, pixels, None);
+            panic!("{name}: {different} pixels differ from {}, the frame drawn is in {}", path.display(), actual.display());
+        }
+    }
+
+    // Machines without any adapter, not even a software one, can't run these
+    fn headless() -> Option<Gpu> {
+        Gpu::headless(&Instance::default()).inspect_err(|err| eprintln!("Skipping golden-image test: {err}")).ok()
+    }
+
+    fn blinker() -> Universe {
+        let mut universe = Universe::new(5, 5, b"");
+        for col in 1..4 {
+            universe.cells[2 * 5 + col] = true;
+        }
+        universe
+    }
+
+    #[test]
+    fn both_renderers_match_the_golden_blinker() {
+        let Some(gpu) = headless() else {
+            return;
+        };
+        // The texture renderer must draw exactly what the geometry does
+        check_golden("blinker", &render(&gpu, &blinker(), &Overlay::default(), false));
+        check_golden("blinker", &render(&gpu, &blinker(), &Overlay::default(), true));
+    }
+
+    #[test]
+    fn overlays_match_the_golden_glider() {
+        let Some(gpu) = headless() else {
+            return;
+        };
+        let mut universe = Universe::new(8, 8, b"");
+        for (row, col) in [(5, 2), (4, 3), (3, 1), (3, 2), (3, 3)] {
+            universe.cells[row * 8 + col] = true;
+        }
+        let overlay = Overlay {
+            selection: Some(Region { row: 2, col: 1, rows: 4, cols: 3 }),
+            markers: vec![(0, [0.9, 0.2, 0.9])],
+            highlights: vec![(63, [1.0, 0.6, 0.1])],
+            ..Overlay::default()
+        };
+        check_golden("glider_overlays", &render(&gpu, &universe, &overlay, false));
+        check_golden("glider_overlays", &render(&gpu, &universe, &overlay, true));
+    }
+}
@@ -0,0 +1,252 @@
+use wgpu::*;
+use wgpu::util::DeviceExt;
+
+use crate::gpu::Gpu;
+use crate::selection::Region;
+use crate::universe::Universe;
+use crate::viewport::GridGeometry;
+
+// Alternative to per-cell geometry: the grid is uploaded as R8 textures, one texel per
+// cell, and drawn with one quad per texture. Grids too large for one texture are split
+// into tiles, and only tiles overlapping the window are uploaded and drawn.
+pub struct GridTexture {
+    tiles: Vec<TextureTile>,
+    pipeline: RenderPipeline,
+    layout: GridLayout,
+}
+
+struct TextureTile {
+    region: Region,
+    layout: GridLayout,
+    texture: Texture,
+    bind_group: BindGroup,
+}
+
+// Largest tile side; the device limit may be lower
+const MAX_TILE_SIZE: u32 = 2048;
+
+// Where the grid (or one tile of it) is drawn, matching the geometry layout in vertex.rs
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GridLayout {
+    pub origin: [f32; 2],
+    pub cell_size: [f32; 2],
+    pub pitch: [f32; 2],
+    pub grid: [u32; 2],
+}
+
+impl GridLayout {
+    pub fn new(geometry: &GridGeometry, rows: u32, cols: u32) -> Self {
+        Self { origin: geometry.origin, cell_size: geometry.cell, pitch: geometry.pitch, grid: [cols, rows] }
+    }
+
+    // Layout of the cells in `region`, drawn where they sit in the whole grid
+    fn tile(&self, region: &Region) -> Self {
+        // In f64, so tiles past 2^24 cells in still start on their own cell
+        let start = |axis: usize, index: u32| (self.origin[axis] as f64 + index as f64 * self.pitch[axis] as f64) as f32;
+        let origin = [start(0, region.col), start(1, region.row)];
+        Self { origin, grid: [region.cols, region.rows], ..*self }
+    }
+
+    // Whether any part of the grid falls inside clip space
+    pub fn is_visible(&self) -> bool {
+        let [cols, rows] = self.grid;
+        let (right, top) = (self.origin[0] + cols as f32 * self.pitch[0], self.origin[1] + rows as f32 * self.pitch[1]);
+        self.origin[0] < 1.0 && right > -1.0 && self.origin[1] < 1.0 && top > -1.0
+    }
+}
+
+// Texel values for the cells in `region`, row-major: bit 0 is alive, bit 1 selected
+pub fn cell_states(universe: &Universe, selection: Option<Region>, region: &Region) -> Vec<u8> {
+    let mut states = Vec::with_capacity((region.rows * region.cols) as usize);
+    for row in region.row..region.row + region.rows {
+        for col in region.col..region.col + region.cols {
+            let alive = universe.cells[(row * universe.cols + col) as usize];
+            let selected = selection.is_some_and(|selection| selection.contains(row, col));
+            states.push(alive as u8 | (selected as u8) << 1);
+        }
+    }
+    states
+}
+
+impl GridTexture {
+    pub fn new(gpu: &Gpu, format: TextureFormat, layout: GridLayout) -> Self {
+        let [cols, rows] = layout.grid;
+
+        let bind_group_layout = gpu.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Grid Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Uint,
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::VERTEX_FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let tile_size = MAX_TILE_SIZE.min(gpu.device.limits().max_texture_dimension_2d);
+        let tiles = Universe::tile_regions(rows, cols, tile_size)
+            .map(|region| {
+                let layout = layout.tile(&region);
+                let texture = gpu.device.create_texture(&TextureDescriptor {
+                    label: Some("Grid Texture"),
+                    size: Extent3d { width: region.cols, height: region.rows, depth_or_array_layers: 1 },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: TextureFormat::R8Uint,
+                    usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                    view_formats: &[],
+                });
+
+                let layout_buffer = gpu.device.create_buffer_init(&util::BufferInitDescriptor {
+                    label: Some("Grid Layout Buffer"),
+                    contents: bytemuck::bytes_of(&layout),
+                    usage: BufferUsages::UNIFORM,
+                });
+
+                let bind_group = gpu.device.create_bind_group(&BindGroupDescriptor {
+                    label: Some("Grid Bind Group"),
+                    layout: &bind_group_layout,
+                    entries: &[
+                        BindGroupEntry {
+                            binding: 0,
+                            resource: BindingResource::TextureView(&texture.create_view(&TextureViewDescriptor::default())),
+                        },
+                        BindGroupEntry {
+                            binding: 1,
+                            resource: layout_buffer.as_entire_binding(),
+                        },
+                    ],
+                });
+
+                TextureTile { region, layout, texture, bind_group }
+            })
+            .collect();
+
+        let shader = gpu.device.create_shader_module(include_wgsl!("grid_texture.wgsl"));
+        let pipeline_layout = gpu.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Grid Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = gpu.device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Grid Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::REPLACE),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Self { tiles, pipeline, layout }
+    }
+
+    // Layout of the whole grid; a different grid size or window needs a new texture
+    pub fn layout(&self) -> GridLayout {
+        self.layout
+    }
+
+    // Upload the current cell states of every visible tile
+    pub fn upload(&self, gpu: &Gpu, universe: &Universe, selection: Option<Region>) {
+        for tile in self.tiles.iter().filter(|tile| tile.layout.is_visible()) {
+            let region = tile.region;
+            gpu.queue.write_texture(
+                TexelCopyTextureInfo {
+                    texture: &tile.texture,
+                    mip_level: 0,
+                    origin: Origin3d::ZERO,
+                    aspect: TextureAspect::All,
+                },
+                &cell_states(universe, selection, &region),
+                TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(region.cols),
+                    rows_per_image: Some(region.rows),
+                },
+                Extent3d { width: region.cols, height: region.rows, depth_or_array_layers: 1 },
+            );
+        }
+    }
+
+    pub fn draw(&self, render_pass: &mut RenderPass) {
+        render_pass.set_pipeline(&self.pipeline);
+        for tile in self.tiles.iter().filter(|tile| tile.layout.is_visible()) {
+            render_pass.set_bind_group(0, &tile.bind_group, &[]);
+            render_pass.draw(0..6, 0..1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cell_states_mark_alive_and_selected() {
+        let mut universe = Universe::new(2, 3, b"");
+        universe.toggle(0, 0);
+        universe.toggle(1, 2);
+        let selection = Some(Region { row: 1, col: 1, rows: 5, cols: 5 });
+        assert_eq!(cell_states(&universe, selection, &Region::full(2, 3)), vec![1, 0, 0, 0, 2, 3]);
+        assert_eq!(cell_states(&universe, selection, &Region { row: 1, col: 1, rows: 1, cols: 2 }), vec![2, 3]);
+    }
+
+    #[test]
+    fn only_tiles_in_clip_space_are_visible() {
+        let geometry = GridGeometry { origin: [-0.6; 2], cell: [0.08; 2], pitch: [0.08 + 0.02; 2] };
+        let layout = GridLayout::new(&geometry, 100, 100);
+        assert!(layout.is_visible());
+        assert!(layout.tile(&Region { row: 0, col: 15, rows: 10, cols: 10 }).is_visible());
+        // Column 17 starts at x = 1.1, right of the window
+        assert!(!layout.tile(&Region { row: 0, col: 17, rows: 10, cols: 10 }).is_visible());
+        assert!(!layout.tile(&Region { row: 20, col: 0, rows: 10, cols: 10 }).is_visible());
+    }
+
+    // Shaders are otherwise only checked when a device compiles them at runtime
+    #[test]
+    fn shaders_validate() {
+        for source in [include_str!("grid_texture.wgsl"), include_str!("shader.wgsl"), include_str!("render/letters.wgsl")] {
+            let module = naga::front::wgsl::parse_str(source).unwrap();
+            naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+                .validate(&module)
+                .unwrap();
+        }
+    }
+}
@@ -0,0 +1,55 @@
+// Draws the whole grid as one quad, looking each cell's state up in an R8 texture
+struct Layout {
+  origin: vec2<f32>,
+  // Cell width and height, snapped to whole pixels
+  cell_size: vec2<f32>,
+  // Distance between neighboring cells: cell size plus padding
+  pitch: vec2<f32>,
+  // Columns, rows
+  grid: vec2<u32>,
+}
+
+@group(0) @binding(0) var cells: texture_2d<u32>;
+@group(0) @binding(1) var<uniform> grid_layout: Layout;
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  // Position relative to the grid origin, in clip space units
+  @location(0) grid_pos: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  var corners = array<vec2<f32>, 6>(
+    vec2<f32>(0.0, 0.0), vec2<f32>(1.0, 0.0), vec2<f32>(0.0, 1.0),
+    vec2<f32>(0.0, 1.0), vec2<f32>(1.0, 0.0), vec2<f32>(1.0, 1.0),
+  );
+  let extent = vec2<f32>(grid_layout.grid) * grid_layout.pitch;
+
+  var out: VertexOutput;
+  out.grid_pos = corners[index] * extent;
+  out.clip_position = vec4<f32>(grid_layout.origin + out.grid_pos, 0.0, 1.0);
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  // Same palette as the per-cell geometry: dead, alive, selected dead, selected alive
+  var palette = array<vec3<f32>, 4>(
+    vec3<f32>(0.1, 0.1, 0.1),
+    vec3<f32>(0.2, 0.8, 0.2),
+    vec3<f32>(0.3, 0.3, 0.12),
+    vec3<f32>(0.8, 0.8, 0.2),
+  );
+
+  let cell = floor(in.grid_pos / grid_layout.pitch);
+  let inside = in.grid_pos - cell * grid_layout.pitch;
+  // The padding between cells shows the background
+  if (inside.x > grid_layout.cell_size.x || inside.y > grid_layout.cell_size.y) {
+    discard;
+  }
+
+  let texel = min(vec2<u32>(cell), grid_layout.grid - vec2<u32>(1u));
+  let state = textureLoad(cells, texel, 0).r;
+  return vec4<f32>(palette[min(state, 3u)], 1.0);
+}
@@ -0,0 +1,114 @@
+// How often each cell has been alive over the run, showing which parts of the sequence keep
+// generating activity. HashLife jumps only count the generation they land on.
+pub struct Heatmap {
+    counts: Vec<u64>,
+    pub generations: u64,
+}
+
+// Colors from no activity to the most active cell, interpolated linearly
+const PALETTE: [[f32; 3]; 4] = [[0.05, 0.05, 0.1], [0.55, 0.1, 0.35], [0.95, 0.45, 0.1], [1.0, 1.0, 0.6]];
+
+impl Heatmap {
+    pub fn new(cells: usize) -> Self {
+        Self { counts: vec![0; cells], generations: 0 }
+    }
+
+    pub fn accumulate(&mut self, cells: &[bool]) {
+        if self.counts.len() != cells.len() {
+            *self = Self::new(cells.len());
+        }
+        for (count, &alive) in self.counts.iter_mut().zip(cells) {
+            *count += alive as u64;
+        }
+        self.generations += 1;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.counts.len());
+    }
+
+    pub fn counts(&self) -> &[u64] {
+        &self.counts
+    }
+
+    // Activity per cell in 0..1 on a log scale, so a few always-alive cells don't wash out
+    // everything else
+    pub fn levels(&self) -> Vec<f32> {
+        let max = self.counts.iter().copied().max().unwrap_or(0);
+        if max == 0 {
+            return vec![0.0; self.counts.len()];
+        }
+        let scale = (max as f32).ln_1p();
+        self.counts.iter().map(|&count| (count as f32).ln_1p() / scale).collect()
+    }
+
+    // One value per cell, a line per grid row, top row (the last) first like the window
+    pub fn to_csv(&self, cols: u32) -> String {
+        let mut csv = String::new();
+        for row in self.counts.chunks(cols as usize).rev() {
+            let values: Vec<String> = row.iter().map(u64::to_string).collect();
+            csv.push_str(&values.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    // One RGBA8 pixel per cell, top row first
+    pub fn to_rgba(&self, cols: u32) -> Vec<u8> {
+        let levels = self.levels();
+        levels
+            .chunks(cols as usize)
+            .rev()
+            .flatten()
+            .flat_map(|&level| {
+                let [r, g, b] = color(level);
+                [(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8, 255]
+            })
+            .collect()
+    }
+}
+
+pub fn color(level: f32) -> [f32; 3] {
+    let position = level.clamp(0.0, 1.0) * (PALETTE.len() - 1) as f32;
+    let i = (position as usize).min(PALETTE.len() - 2);
+    let t = position - i as f32;
+    let (from, to) = (PALETTE[i], PALETTE[i + 1]);
+    [0, 1, 2].map(|c| from[c] + (to[c] - from[c]) * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulates_and_scales_logarithmically() {
+        let mut heatmap = Heatmap::new(4);
+        for _ in 0..3 {
+            heatmap.accumulate(&[true, false, false, false]);
+        }
+        heatmap.accumulate(&[true, true, false, false]);
+
+        assert_eq!(heatmap.counts(), [4, 1, 0, 0]);
+        assert_eq!(heatmap.generations, 4);
+        let levels = heatmap.levels();
+        assert_eq!(levels[0], 1.0);
+        assert_eq!(levels[3], 0.0);
+        // ln(2) / ln(5): well above the linear 1/4
+        assert!((levels[1] - 0.4307).abs() < 1e-3);
+
+        // Rows are written top (last) row first
+        assert_eq!(heatmap.to_csv(2), "0,0\n4,1\n");
+        assert_eq!(&heatmap.to_rgba(2)[8..12], [255, 255, 153, 255]);
+
+        heatmap.reset();
+        assert_eq!(heatmap.counts(), [0, 0, 0, 0]);
+        assert_eq!(heatmap.generations, 0);
+    }
+
+    #[test]
+    fn palette_runs_from_dark_to_bright() {
+        assert_eq!(color(0.0), PALETTE[0]);
+        assert_eq!(color(1.0), PALETTE[3]);
+        assert_eq!(color(2.0), PALETTE[3]);
+    }
+}
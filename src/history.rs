@@ -0,0 +1,250 @@
+use std::collections::VecDeque;
+
+use crate::presets::Parameters;
+
+// Undo/redo for manual edits (toggles, reseeds, preset flips). Each edit stores only the
+// cells it changed and their previous state, plus the parameters before and after it when it
+// changed those, and the memory the stored edits take up is bounded, dropping the oldest edits
+// first.
+pub struct History {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    stored_bytes: usize,
+    max_bytes: usize,
+}
+
+// What the edits may take up by default
+const MAX_BYTES: usize = 32 << 20;
+
+// Cells changed by one edit, with the state they had before it; after it they held the opposite
+struct Edit {
+    changes: Vec<(usize, bool)>,
+    parameters: Option<Box<[Parameters; 2]>>,
+}
+
+impl Edit {
+    fn diff(before: &[bool], after: &[bool], parameters: Option<[Parameters; 2]>) -> Self {
+        let mut changes: Vec<_> = before
+            .iter()
+            .zip(after)
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(idx, (&before, _))| (idx, before))
+            .collect();
+        // Kept for a long time, so without the spare room collecting left
+        changes.shrink_to_fit();
+        let parameters = parameters.filter(|[before, after]| before != after).map(Box::new);
+        Self { changes, parameters }
+    }
+
+    // Roughly what the edit takes up: its changes, and its parameters with the flags or
+    // residues of their seed strategies
+    fn bytes(&self) -> usize {
+        let changes = self.changes.capacity() * std::mem::size_of::<(usize, bool)>();
+        let parameters = self.parameters.as_ref().map_or(0, |parameters| {
+            std::mem::size_of::<[Parameters; 2]>() + parameters.iter().map(|parameters| parameters.seed_strategy.heap_bytes()).sum::<usize>()
+        });
+        std::mem::size_of::<Edit>() + changes + parameters
+    }
+
+    fn restore(&self, cells: &mut [bool], undo: bool) {
+        for &(idx, before) in &self.changes {
+            if let Some(cell) = cells.get_mut(idx) {
+                *cell = if undo { before } else { !before };
+            }
+        }
+    }
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(MAX_BYTES)
+    }
+}
+
+impl History {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { undo: VecDeque::new(), redo: Vec::new(), stored_bytes: 0, max_bytes }
+    }
+
+    // Remember the edit that turned `before` into `after`. Edits that changed nothing are
+    // skipped, and any new edit discards the redo stack.
+    pub fn record(&mut self, before: &[bool], after: &[bool]) {
+        self.record_with(before, after, None);
+    }
+
+    // Like `record`, for an edit that may also have changed the parameters from the first of
+    // `parameters` to the second
+    pub fn record_with(&mut self, before: &[bool], after: &[bool], parameters: Option<[Parameters; 2]>) {
+        let edit = Edit::diff(before, after, parameters);
+        if edit.changes.is_empty() && edit.parameters.is_none() {
+            return;
+        }
+
+        self.stored_bytes -= self.redo.drain(..).map(|edit| edit.bytes()).sum::<usize>();
+        self.stored_bytes += edit.bytes();
+        self.undo.push_back(edit);
+
+        // Always keep the newest edit, even if it alone is over the budget
+        while self.stored_bytes > self.max_bytes && self.undo.len() > 1 {
+            if let Some(oldest) = self.undo.pop_front() {
+                self.stored_bytes -= oldest.bytes();
+            }
+        }
+    }
+
+    // Put the cells touched by the last edit back to their previous state. Returns the number
+    // of cells restored and the parameters to go back to, if the edit changed them, or None
+    // when there is nothing to undo.
+    pub fn undo(&mut self, cells: &mut [bool]) -> Option<(usize, Option<Parameters>)> {
+        let edit = self.undo.pop_back()?;
+        edit.restore(cells, true);
+        let undone = (edit.changes.len(), edit.parameters.as_ref().map(|parameters| parameters[0].clone()));
+        self.redo.push(edit);
+        Some(undone)
+    }
+
+    pub fn redo(&mut self, cells: &mut [bool]) -> Option<(usize, Option<Parameters>)> {
+        let edit = self.redo.pop()?;
+        edit.restore(cells, false);
+        let redone = (edit.changes.len(), edit.parameters.as_ref().map(|parameters| parameters[1].clone()));
+        self.undo.push_back(edit);
+        Some(redone)
+    }
+}
+
+// The cells at a marked generation, for the diff view comparing them with the current ones
+pub struct Snapshot {
+    pub generation: u64,
+    pub cells: Vec<bool>,
+}
+
+// What became of a cell since the snapshot
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Born,
+    Died,
+    Stayed,
+    Empty,
+}
+
+impl Change {
+    pub fn color(self) -> [f32; 3] {
+        match self {
+            Change::Born => [0.2, 0.9, 0.3],
+            Change::Died => [0.9, 0.25, 0.2],
+            Change::Stayed => [0.45, 0.5, 0.65],
+            Change::Empty => [0.1, 0.1, 0.1],
+        }
+    }
+}
+
+impl Snapshot {
+    pub fn changes(&self, cells: &[bool]) -> Vec<Change> {
+        self.cells
+            .iter()
+            .zip(cells)
+            .map(|(&before, &after)| match (before, after) {
+                (false, true) => Change::Born,
+                (true, false) => Change::Died,
+                (true, true) => Change::Stayed,
+                (false, false) => Change::Empty,
+            })
+            .collect()
+    }
+
+    // Cells born, died and alive throughout, since the snapshot
+    pub fn counts(&self, cells: &[bool]) -> [usize; 3] {
+        let changes = self.changes(cells);
+        [Change::Born, Change::Died, Change::Stayed].map(|change| changes.iter().filter(|&&other| other == change).count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::presets::Preset;
+    use crate::seq_analysis::frames::ReadingFrame;
+    use crate::universe::SeedStrategy;
+
+    #[test]
+    fn undo_and_redo_restore_edited_cells() {
+        let mut history = History::default();
+        let mut cells = vec![false; 6];
+
+        let before = cells.clone();
+        cells[1] = true;
+        cells[4] = true;
+        history.record(&before, &cells);
+
+        let before = cells.clone();
+        cells[4] = false;
+        history.record(&before, &cells);
+
+        // Cells the simulation changed in the meantime are left alone
+        cells[0] = true;
+
+        assert_eq!(history.undo(&mut cells), Some((1, None)));
+        assert_eq!(cells, [true, true, false, false, true, false]);
+        assert_eq!(history.undo(&mut cells), Some((2, None)));
+        assert_eq!(cells, [true, false, false, false, false, false]);
+        assert_eq!(history.undo(&mut cells), None);
+
+        assert_eq!(history.redo(&mut cells), Some((2, None)));
+        assert_eq!(cells, [true, true, false, false, true, false]);
+
+        // A new edit discards what could still be redone
+        let before = cells.clone();
+        cells[5] = true;
+        history.record(&before, &cells);
+        assert_eq!(history.redo(&mut cells), None);
+
+        // A flip that only changed the parameters is still an edit
+        let [a, b] = ["B3/S23 gc red", "B36/S23 gc blue"].map(|preset| preset.parse::<Preset>().unwrap().parameters());
+        history.record_with(&cells, &cells, Some([a.clone(), b.clone()]));
+        assert_eq!(history.undo(&mut cells), Some((0, Some(a.clone()))));
+        assert_eq!(history.redo(&mut cells), Some((0, Some(b))));
+        history.record_with(&cells, &cells, Some([a.clone(), a]));
+        assert_eq!(history.undo.len(), 3);
+    }
+
+    #[test]
+    fn snapshot_classifies_changes() {
+        let snapshot = Snapshot { generation: 3, cells: vec![false, true, true, false] };
+        let cells = [true, false, true, false];
+        assert_eq!(snapshot.changes(&cells), [Change::Born, Change::Died, Change::Stayed, Change::Empty]);
+        assert_eq!(snapshot.counts(&cells), [1, 1, 1]);
+    }
+
+    #[test]
+    fn drops_oldest_edits_over_budget() {
+        let edit = std::mem::size_of::<Edit>() + std::mem::size_of::<(usize, bool)>();
+        let mut history = History::new(3 * edit);
+        let mut cells = vec![false; 4];
+        for idx in 0..4 {
+            let before = cells.clone();
+            cells[idx] = true;
+            history.record(&before, &cells);
+        }
+
+        assert_eq!(history.stored_bytes, 3 * edit);
+        for _ in 0..3 {
+            assert_eq!(history.undo(&mut cells), Some((1, None)));
+        }
+        assert_eq!(history.undo(&mut cells), None);
+        assert_eq!(cells, [true, false, false, false]);
+
+        // Reseeding from a translated frame stores the residues, so a few such flips fill it
+        let mut history = History::new(1 << 20);
+        let gc = "B3/S23 gc red".parse::<Preset>().unwrap().parameters();
+        for frame in 0..4 {
+            let residues: Arc<[u8]> = vec![b'L'; 400_000].into();
+            let translated = Parameters { seed_strategy: SeedStrategy::Translated(ReadingFrame::ALL[frame], residues), ..gc.clone() };
+            history.record_with(&cells, &cells, Some([gc.clone(), translated]));
+        }
+        assert_eq!(history.undo.len(), 2);
+        assert!(history.stored_bytes <= 1 << 20);
+    }
+}
@@ -0,0 +1,210 @@
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+use crate::action::Action;
+use crate::app::App;
+use crate::seq_analysis::frames::ReadingFrame;
+use crate::turbo;
+
+// Key bindings of the grid window while the console is closed. The event loop looks keys up
+// here and the help overlay lists the active ones, so the two can't disagree.
+
+// What a binding does: apply actions worked out from the current state, apply an action for
+// the digit pressed, or open the console with a command to complete, printing a hint
+pub enum Command {
+    Apply(fn(&App) -> Vec<Action>),
+    Slot(fn(usize) -> Action),
+    Console { prefill: &'static str, hint: &'static str },
+}
+
+enum Trigger {
+    Char(&'static str),
+    Named(NamedKey),
+    // A letter with Ctrl, and with or without Shift
+    Ctrl { key: &'static str, shift: bool },
+    // 1 to `max`, with or without Ctrl
+    Digit { ctrl: bool, max: usize },
+    // Keys no other binding takes
+    Any,
+}
+
+pub struct Binding {
+    // The key as listed in the help overlay
+    pub label: &'static str,
+    pub description: &'static str,
+    trigger: Trigger,
+    // Whether the binding applies in the current state, e.g. R only with a selection
+    active: fn(&App) -> bool,
+    pub command: Command,
+}
+
+impl Binding {
+    pub fn is_active(&self, app: &App) -> bool {
+        (self.active)(app)
+    }
+
+    fn matches(&self, key: &Key, modifiers: ModifiersState) -> bool {
+        match (&self.trigger, key.as_ref()) {
+            (Trigger::Ctrl { key: letter, shift }, Key::Character(key)) => {
+                modifiers.control_key() && modifiers.shift_key() == *shift && key.eq_ignore_ascii_case(letter)
+            }
+            (Trigger::Digit { ctrl, max }, Key::Character(_)) => modifiers.control_key() == *ctrl && digit(key).is_some_and(|digit| digit <= *max),
+            (_, Key::Character(_)) if modifiers.control_key() => false,
+            (Trigger::Char(expected), Key::Character(key)) => key == *expected,
+            (Trigger::Named(expected), Key::Named(key)) => key == *expected,
+            (Trigger::Any, _) => true,
+            _ => false,
+        }
+    }
+}
+
+// [ and ] change the padding in twentieths of the cell pitch
+const PADDING_STEPS: f32 = 20.0;
+
+// One step more or less padding, divided last so replay files get "0.15" rather than
+// "0.15000001"
+fn step_padding(app: &App, steps: f32) -> f32 {
+    ((app.layout().padding * PADDING_STEPS).round() + steps).max(0.0) / PADDING_STEPS
+}
+
+// { and } shrink or grow the grid by an eighth on both sides, around the last resize anchor
+fn step_grid(app: &App, grow: bool) -> Action {
+    let step = |size: u32| {
+        let by = (size / 8).max(1);
+        if grow { size.saturating_add(by) } else { size.saturating_sub(by).max(1) }
+    };
+    let (rows, cols) = app.grid_size();
+    Action::ResizeGrid { rows: step(rows), cols: step(cols), anchor: app.resize_anchor() }
+}
+
+fn always(_: &App) -> bool {
+    true
+}
+
+// In lookup order; the first active binding matching a key wins
+pub const BINDINGS: &[Binding] = &[
+    Binding { label: "F1 / ?", description: "Show or hide this help", trigger: Trigger::Named(NamedKey::F1), active: always, command: Command::Apply(|app| vec![Action::ShowHelp(!app.help_shown())]) },
+    Binding { label: "", description: "", trigger: Trigger::Char("?"), active: always, command: Command::Apply(|app| vec![Action::ShowHelp(!app.help_shown())]) },
+    Binding { label: "Esc", description: "Close the help", trigger: Trigger::Named(NamedKey::Escape), active: App::help_shown, command: Command::Apply(|_| vec![Action::ShowHelp(false)]) },
+    Binding { label: "F3", description: "Show or hide the profiler", trigger: Trigger::Named(NamedKey::F3), active: always, command: Command::Apply(|app| vec![Action::ShowProfiler(!app.profiler_shown())]) },
+    Binding { label: "Ctrl+Z", description: "Undo the last edit", trigger: Trigger::Ctrl { key: "z", shift: false }, active: always, command: Command::Apply(|_| vec![Action::Undo]) },
+    Binding { label: "Ctrl+Shift+Z", description: "Redo the last edit", trigger: Trigger::Ctrl { key: "z", shift: true }, active: always, command: Command::Apply(|_| vec![Action::Redo]) },
+    Binding { label: "Ctrl+1..9", description: "Save a bookmark in that slot", trigger: Trigger::Digit { ctrl: true, max: 9 }, active: always, command: Command::Slot(|slot| Action::SaveBookmark { slot, name: None }) },
+    Binding {
+        label: "1..6",
+        description: "Read the sequence in that frame: +1 to +3, -1 to -3",
+        trigger: Trigger::Digit { ctrl: false, max: 6 },
+        active: App::frames_shown,
+        command: Command::Slot(|slot| Action::ReadFrame(Some(ReadingFrame::ALL[slot - 1]))),
+    },
+    Binding { label: "1..9", description: "Go to a bookmark", trigger: Trigger::Digit { ctrl: false, max: 9 }, active: App::has_bookmarks, command: Command::Slot(|slot| Action::GotoBookmark(slot.to_string())) },
+    Binding { label: "`", description: "Open the command console", trigger: Trigger::Char("`"), active: always, command: Command::Console { prefill: "", hint: "" } },
+    Binding { label: "P", description: "Pause or resume", trigger: Trigger::Char("p"), active: always, command: Command::Apply(|app| vec![Action::Pause(!app.paused())]) },
+    Binding { label: "Q", description: "Next mouse tool", trigger: Trigger::Char("q"), active: always, command: Command::Apply(|app| vec![Action::SetTool(app.tool().next())]) },
+    Binding { label: "V", description: "Flip between presets A and B", trigger: Trigger::Char("v"), active: always, command: Command::Apply(|app| vec![Action::UsePreset(app.presets.flipped())]) },
+    Binding {
+        label: "K",
+        description: "Turbo: many generations every frame",
+        trigger: Trigger::Char("k"),
+        active: always,
+        command: Command::Apply(|app| vec![Action::Turbo(if app.turbo_steps().is_some() { None } else { Some(turbo::DEFAULT_STEPS) })]),
+    },
+    Binding { label: "< / >", description: "Half or twice the turbo generations", trigger: Trigger::Char("<"), active: |app| app.turbo_steps().is_some(), command: Command::Apply(|app| vec![Action::Turbo(app.turbo_steps().map(turbo::slower))]) },
+    Binding { label: "", description: "", trigger: Trigger::Char(">"), active: |app| app.turbo_steps().is_some(), command: Command::Apply(|app| vec![Action::Turbo(app.turbo_steps().map(turbo::faster))]) },
+    Binding { label: "J", description: "Jump one million generations", trigger: Trigger::Char("j"), active: always, command: Command::Apply(|_| vec![Action::Jump(1_000_000)]) },
+    Binding { label: "A", description: "Show or hide the analysis window", trigger: Trigger::Char("a"), active: always, command: Command::Apply(|app| vec![Action::ShowAnalysis(!app.analysis_open())]) },
+    Binding { label: "H", description: "Show or hide the heat map", trigger: Trigger::Char("h"), active: always, command: Command::Apply(|app| vec![Action::ShowHeatmap(!app.heatmap_shown())]) },
+    Binding { label: "Shift+H", description: "Reset the heat map", trigger: Trigger::Char("H"), active: always, command: Command::Apply(|_| vec![Action::ResetHeatmap]) },
+    Binding { label: "B", description: "Mark this generation for the diff view", trigger: Trigger::Char("b"), active: always, command: Command::Apply(|_| vec![Action::MarkGeneration]) },
+    Binding { label: "Shift+B", description: "Show changes since the marked generation", trigger: Trigger::Char("B"), active: App::has_mark, command: Command::Apply(|app| vec![Action::ShowDiff(!app.diff_shown())]) },
+    Binding { label: "T", description: "Show or hide the centroid trail", trigger: Trigger::Char("t"), active: always, command: Command::Apply(|app| vec![Action::ShowTrail(!app.trail_shown())]) },
+    Binding { label: "G", description: "Show or hide the coordinate ruler", trigger: Trigger::Char("g"), active: always, command: Command::Apply(|app| vec![Action::ShowRuler(!app.ruler_shown())]) },
+    Binding { label: "C", description: "Show or hide the outlines of live regions", trigger: Trigger::Char("c"), active: always, command: Command::Apply(|app| vec![Action::ShowContours(!app.contours_shown())]) },
+    Binding { label: "M", description: "Smooth or crisp steps between generations", trigger: Trigger::Char("m"), active: always, command: Command::Apply(|app| vec![Action::SmoothTransitions(!app.smooth_transitions())]) },
+    Binding { label: "N", description: "Show or hide the rule library", trigger: Trigger::Char("n"), active: always, command: Command::Apply(|app| vec![Action::ShowRuleLibrary(!app.rule_library_shown())]) },
+    Binding { label: "L", description: "Show or hide the layer checkboxes", trigger: Trigger::Char("l"), active: always, command: Command::Apply(|app| vec![Action::ShowLayerPanel(!app.layer_panel_shown())]) },
+    Binding {
+        label: "F",
+        description: "Explore the reading frames",
+        trigger: Trigger::Char("f"),
+        active: always,
+        command: Command::Apply(|app| vec![Action::ReadFrame(if app.frames_shown() { None } else { Some(ReadingFrame::ALL[0]) })]),
+    },
+    Binding { label: "O", description: "Show or hide the 3D torus view", trigger: Trigger::Char("o"), active: always, command: Command::Apply(|app| vec![Action::ShowTorus(!app.torus_shown())]) },
+    Binding { label: "I", description: "Show or hide the cell-age heightfield", trigger: Trigger::Char("i"), active: always, command: Command::Apply(|app| vec![Action::ShowHeightfield(!app.heightfield_shown())]) },
+    Binding { label: "S", description: "Show or hide the sequence strip", trigger: Trigger::Char("s"), active: always, command: Command::Apply(|app| vec![Action::ShowStrip(!app.strip_shown())]) },
+    // Cycling tracks also shows the one picked behind the cells
+    Binding {
+        label: "Tab",
+        description: "Next track, drawn behind the cells",
+        trigger: Trigger::Named(NamedKey::Tab),
+        active: always,
+        command: Command::Apply(|_| vec![Action::SelectTrack(None), Action::ShowTrackLayer(true)]),
+    },
+    Binding {
+        label: "R",
+        description: "Reseed the selection from an offset",
+        trigger: Trigger::Char("r"),
+        active: |app| app.overlay.selection.is_some(),
+        command: Command::Console { prefill: "seed offset ", hint: "Type a sequence offset and press Enter (Escape cancels)" },
+    },
+    Binding { label: "Esc", description: "Clear the selection", trigger: Trigger::Named(NamedKey::Escape), active: |app| app.overlay.selection.is_some(), command: Command::Apply(|_| vec![Action::ClearSelection]) },
+    Binding { label: "+ / -", description: "Bigger or smaller cells", trigger: Trigger::Char("+"), active: always, command: Command::Apply(|app| vec![Action::SetCellSize(Some(app.cell_size() + 1))]) },
+    Binding { label: "", description: "", trigger: Trigger::Char("="), active: always, command: Command::Apply(|app| vec![Action::SetCellSize(Some(app.cell_size() + 1))]) },
+    Binding { label: "", description: "", trigger: Trigger::Char("-"), active: always, command: Command::Apply(|app| vec![Action::SetCellSize(Some(app.cell_size().saturating_sub(1).max(1)))]) },
+    Binding { label: "[ / ]", description: "Less or more space between cells", trigger: Trigger::Char("["), active: always, command: Command::Apply(|app| vec![Action::SetPadding(step_padding(app, -1.0))]) },
+    Binding { label: "", description: "", trigger: Trigger::Char("]"), active: always, command: Command::Apply(|app| vec![Action::SetPadding(step_padding(app, 1.0))]) },
+    Binding { label: "{ / }", description: "Smaller or bigger grid, keeping the live cells", trigger: Trigger::Char("{"), active: always, command: Command::Apply(|app| vec![step_grid(app, false)]) },
+    Binding { label: "", description: "", trigger: Trigger::Char("}"), active: always, command: Command::Apply(|app| vec![step_grid(app, true)]) },
+    Binding { label: "0", description: "Fit the cells to the window again", trigger: Trigger::Char("0"), active: always, command: Command::Apply(|_| vec![Action::SetCellSize(None)]) },
+    Binding { label: "Other keys", description: "Toggle the background color", trigger: Trigger::Any, active: always, command: Command::Apply(|_| vec![Action::ToggleBackground]) },
+];
+
+// The digit 1 to 9 of a key, for slot bindings
+pub fn digit(key: &Key) -> Option<usize> {
+    match key.as_ref() {
+        Key::Character(key) => key.parse().ok().filter(|digit| (1..=9).contains(digit)),
+        _ => None,
+    }
+}
+
+pub fn lookup(key: &Key, modifiers: ModifiersState, app: &App) -> Option<&'static Binding> {
+    BINDINGS.iter().find(|binding| binding.matches(key, modifiers) && binding.is_active(app))
+}
+
+// Bindings that apply right now, for the help overlay. Alternative keys folded into the
+// label of the binding before them are left out.
+pub fn active(app: &App) -> impl Iterator<Item = &'static Binding> {
+    BINDINGS.iter().filter(move |binding| !binding.label.is_empty() && binding.is_active(app))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_keys_with_modifiers() {
+        let binding = |label: &str| BINDINGS.iter().find(|binding| binding.label == label).unwrap();
+        let (undo, redo) = (binding("Ctrl+Z"), binding("Ctrl+Shift+Z"));
+        let z = Key::Character("z".into());
+        assert!(undo.matches(&z, ModifiersState::CONTROL));
+        assert!(!undo.matches(&z, ModifiersState::CONTROL | ModifiersState::SHIFT));
+        assert!(redo.matches(&Key::Character("Z".into()), ModifiersState::CONTROL | ModifiersState::SHIFT));
+
+        let jump = binding("J");
+        assert!(jump.matches(&Key::Character("j".into()), ModifiersState::empty()));
+        assert!(!jump.matches(&Key::Character("j".into()), ModifiersState::CONTROL));
+        assert!(binding("Other keys").matches(&Key::Named(NamedKey::Space), ModifiersState::empty()));
+
+        let three = Key::Character("3".into());
+        assert!(binding("Ctrl+1..9").matches(&three, ModifiersState::CONTROL));
+        assert!(!binding("1..9").matches(&three, ModifiersState::CONTROL));
+        assert!(binding("1..9").matches(&three, ModifiersState::empty()));
+        assert!(!binding("1..9").matches(&Key::Character("0".into()), ModifiersState::empty()));
+        assert_eq!(digit(&three), Some(3));
+        // The frame keys stop at 6, leaving 7 to 9 to the bookmarks
+        assert!(binding("1..6").matches(&three, ModifiersState::empty()));
+        assert!(!binding("1..6").matches(&Key::Character("7".into()), ModifiersState::empty()));
+        // Unlabeled bindings are alternatives listed with the binding before them
+        assert!(BINDINGS.iter().filter(|binding| binding.label.is_empty()).all(|binding| matches!(binding.trigger, Trigger::Char("?" | ">" | "=" | "-" | "]" | "}"))));
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::action::Action;
+use crate::presets::Theme;
+use crate::rule::Rule;
+use crate::seq_analysis::shuffle::Rng;
+
+// Kiosk mode (`--kiosk`), for a display nobody is standing at: a scene scheduler runs each
+// scene for a number of generations, then moves on to the next record of a multi-FASTA (or a
+// random window of the sequence) with the next rule and color theme, crossfading the cells
+// from the old scene to the new one.
+
+pub const DEFAULT_SCENE_GENERATIONS: u64 = 500;
+pub const SCENE_CROSSFADE: Duration = Duration::from_millis(1500);
+
+// Conway's Life, HighLife, Day & Night and 34 Life: all of them keep a seeded grid busy
+pub const DEFAULT_RULES: &[&str] = &["B3/S23", "B36/S23", "B3678/S34678", "B34/S34"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KioskSettings {
+    pub path: PathBuf,
+    pub generations: u64,
+    // Random windows of the first record rather than one record after another
+    pub windows: bool,
+    pub rules: Vec<Rule>,
+}
+
+impl KioskSettings {
+    pub fn new(path: PathBuf) -> Self {
+        let rules = DEFAULT_RULES.iter().map(|rule| rule.parse().expect("the default rules are valid")).collect();
+        Self { path, generations: DEFAULT_SCENE_GENERATIONS, windows: false, rules }
+    }
+}
+
+// Actions switching to the theme after `theme`: blue, red, then the high-contrast palette
+pub fn next_theme(theme: Theme) -> Vec<Action> {
+    match theme {
+        Theme::Blue => vec![Action::ToggleBackground],
+        Theme::Red => vec![Action::HighContrast(true)],
+        Theme::Contrast => vec![Action::HighContrast(false), Action::ToggleBackground],
+    }
+}
+
+pub struct Kiosk {
+    pub settings: KioskSettings,
+    records: usize,
+    // Scenes shown so far, the current one included
+    scene: u64,
+    // Generation the current scene came up at, once its sequence is in place
+    started: Option<u64>,
+    rng: Rng,
+}
+
+impl Kiosk {
+    pub fn new(settings: KioskSettings, records: usize, seed: u64) -> Self {
+        Self { settings, records: records.max(1), scene: 0, started: None, rng: Rng::new(seed) }
+    }
+
+    // The current scene's rule
+    fn rule(&self) -> Action {
+        Action::SetRule(self.settings.rules[(self.scene as usize - 1) % self.settings.rules.len()])
+    }
+
+    // Actions putting the first scene up
+    pub fn first_scene(&mut self) -> Vec<Action> {
+        self.scene = 1;
+        self.started = None;
+        let open = if self.settings.windows { Action::Open(self.settings.path.clone()) } else { Action::OpenRecord { path: self.settings.path.clone(), record: 0 } };
+        vec![open, Action::Pause(false), self.rule()]
+    }
+
+    // Actions for the next scene, coming after one shown in `theme` on a sequence of
+    // `length` bases seeding `cells` cells
+    pub fn next_scene(&mut self, theme: Theme, length: usize, cells: usize) -> Vec<Action> {
+        self.started = None;
+        let mut actions = if self.settings.windows || self.records == 1 {
+            // A full window when the sequence is long enough for one
+            vec![Action::Seed { offset: self.rng.below(length.saturating_sub(cells).max(1)) }]
+        } else {
+            vec![Action::OpenRecord { path: self.settings.path.clone(), record: self.scene as usize % self.records }]
+        };
+        self.scene += 1;
+        actions.push(Action::Pause(false));
+        actions.push(self.rule());
+        actions.extend(next_theme(theme));
+        actions
+    }
+
+    // Start counting the scene's generations from `generation`, unless it already started.
+    // Returns whether it just did.
+    pub fn start(&mut self, generation: u64) -> bool {
+        let started = self.started.is_none();
+        self.started.get_or_insert(generation);
+        started
+    }
+
+    // Whether the current scene has run its generations
+    pub fn due(&self, generation: u64) -> bool {
+        self.started.is_some_and(|started| generation.saturating_sub(started) >= self.settings.generations)
+    }
+
+    pub fn scene(&self) -> u64 {
+        self.scene
+    }
+}
+
+// Cell colors `t` of the way (0 to 1) from `from` to `to`
+pub fn crossfade(from: &[[f32; 3]], to: &[[f32; 3]], t: f32) -> Vec<[f32; 3]> {
+    let t = t.clamp(0.0, 1.0);
+    from.iter().zip(to).map(|(from, to)| [0, 1, 2].map(|channel| from[channel] + (to[channel] - from[channel]) * t)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycles_records_rules_and_themes() {
+        let mut kiosk = Kiosk::new(KioskSettings::new("genomes.fa".into()), 2, 7);
+        let first = kiosk.first_scene();
+        assert_eq!(first[0], Action::OpenRecord { path: "genomes.fa".into(), record: 0 });
+        assert_eq!(first[2], Action::SetRule(Rule::CONWAY));
+        // Nothing is due until the sequence is in and the scene has run
+        assert!(!kiosk.due(1000));
+        assert!(kiosk.start(10) && !kiosk.start(20));
+        assert!(!kiosk.due(509) && kiosk.due(510));
+
+        let second = kiosk.next_scene(Theme::Blue, 1000, 100);
+        assert_eq!(second[0], Action::OpenRecord { path: "genomes.fa".into(), record: 1 });
+        assert_eq!(second[2], Action::SetRule("B36/S23".parse().unwrap()));
+        assert_eq!(second[3..], [Action::ToggleBackground]);
+        // Past the last record it starts over
+        let third = kiosk.next_scene(Theme::Red, 1000, 100);
+        assert_eq!(third[0], Action::OpenRecord { path: "genomes.fa".into(), record: 0 });
+        assert_eq!(kiosk.scene(), 3);
+
+        let mut windows = Kiosk::new(KioskSettings { windows: true, ..KioskSettings::new("genome.fa".into()) }, 5, 7);
+        assert_eq!(windows.first_scene()[0], Action::Open("genome.fa".into()));
+        for _ in 0..20 {
+            assert!(matches!(windows.next_scene(Theme::Contrast, 1000, 100)[0], Action::Seed { offset } if offset < 900));
+        }
+    }
+
+    #[test]
+    fn crossfades_cell_colors() {
+        let faded = crossfade(&[[0.0, 0.0, 0.0], [1.0, 1.0, 1.0]], &[[1.0, 0.5, 0.0], [1.0, 1.0, 1.0]], 0.5);
+        assert_eq!(faded, [[0.5, 0.25, 0.0], [1.0, 1.0, 1.0]]);
+        assert_eq!(crossfade(&[[0.0; 3]], &[[1.0; 3]], 2.0), [[1.0; 3]]);
+    }
+}
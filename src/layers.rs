@@ -0,0 +1,312 @@
+use crate::render::text::{ADVANCE, GLYPH_HEIGHT, font_pixel, push_text};
+use crate::vertex::{Vertex, push_rect};
+use crate::viewport::Viewport;
+
+// What the grid is drawn from, as a stack of layers painted bottom to top (`layer`, `layers`,
+// the `layers` config key and the panel L shows). Each layer can be hidden and has an
+// opacity, mixing it with whatever is below. The cell layers give every cell a color and are
+// composited per cell; grid lines sit in the gaps between cells, and annotations (markers,
+// highlights, trails...) are shapes over the cells, so the cell layers above them tint them.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Layer {
+    // Each cell colored by the base it was seeded from
+    Bases,
+    // The selected sequence-analysis track, also drawn across the gap around each cell
+    Heat,
+    // Live and dead cells, or whatever view colors them
+    Cells,
+    Selection,
+    Grid,
+    Annotations,
+}
+
+impl Layer {
+    // Bottom to top, as they are stacked by default
+    pub const ALL: [Layer; 6] = [Layer::Bases, Layer::Heat, Layer::Cells, Layer::Selection, Layer::Grid, Layer::Annotations];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layer::Bases => "bases",
+            Layer::Heat => "heat",
+            Layer::Cells => "cells",
+            Layer::Selection => "selection",
+            Layer::Grid => "grid",
+            Layer::Annotations => "annotations",
+        }
+    }
+}
+
+impl std::str::FromStr for Layer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Layer::ALL.into_iter().find(|layer| layer.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = Layer::ALL.iter().map(|layer| layer.name()).collect();
+            format!("Unknown layer '{s}', expected one of {}", names.join(", "))
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LayerState {
+    pub layer: Layer,
+    pub shown: bool,
+    // 0 (invisible) to 1 (covers what is below)
+    pub opacity: f32,
+}
+
+// Color of a cell below the annotations, and how the cell layers above them change a color:
+// `scale` times it plus `offset`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Composite {
+    pub under: [f32; 3],
+    pub scale: f32,
+    pub offset: [f32; 3],
+}
+
+impl Composite {
+    // `color` as seen through the layers above the annotations
+    pub fn over(&self, color: [f32; 3]) -> [f32; 3] {
+        [0, 1, 2].map(|channel| color[channel] * self.scale + self.offset[channel])
+    }
+
+    pub fn cell(&self) -> [f32; 3] {
+        self.over(self.under)
+    }
+}
+
+pub fn mix(under: [f32; 3], color: [f32; 3], opacity: f32) -> [f32; 3] {
+    [0, 1, 2].map(|channel| under[channel] + (color[channel] - under[channel]) * opacity)
+}
+
+// The stack, bottom layer first
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layers {
+    pub stack: Vec<LayerState>,
+}
+
+// Bases and heat start hidden, as do the grid lines; the rest is what the grid always showed
+impl Default for Layers {
+    fn default() -> Self {
+        let shown = |layer| !matches!(layer, Layer::Bases | Layer::Heat | Layer::Grid);
+        Self { stack: Layer::ALL.into_iter().map(|layer| LayerState { layer, shown: shown(layer), opacity: 1.0 }).collect() }
+    }
+}
+
+impl Layers {
+    pub fn get(&self, layer: Layer) -> &LayerState {
+        self.stack.iter().find(|state| state.layer == layer).expect("every layer is in the stack")
+    }
+
+    fn get_mut(&mut self, layer: Layer) -> &mut LayerState {
+        self.stack.iter_mut().find(|state| state.layer == layer).expect("every layer is in the stack")
+    }
+
+    pub fn shown(&self, layer: Layer) -> bool {
+        self.get(layer).shown
+    }
+
+    // Opacity it is drawn with, 0 while hidden
+    pub fn opacity(&self, layer: Layer) -> f32 {
+        let state = self.get(layer);
+        if state.shown { state.opacity } else { 0.0 }
+    }
+
+    pub fn show(&mut self, layer: Layer, shown: bool) {
+        self.get_mut(layer).shown = shown;
+    }
+
+    pub fn set_opacity(&mut self, layer: Layer, opacity: f32) {
+        self.get_mut(layer).opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    // Move a layer one place up or down the stack, if it isn't already at that end
+    pub fn raise(&mut self, layer: Layer, up: bool) {
+        let index = self.stack.iter().position(|state| state.layer == layer).expect("every layer is in the stack");
+        let other = if up { index + 1 } else { index.wrapping_sub(1) };
+        if other < self.stack.len() {
+            self.stack.swap(index, other);
+        }
+    }
+
+    // Whether `a` is drawn below `b`
+    pub fn below(&self, a: Layer, b: Layer) -> bool {
+        let index = |layer| self.stack.iter().position(|state: &LayerState| state.layer == layer);
+        index(a) < index(b)
+    }
+
+    // Composite a cell over `backdrop`, from the color each cell layer gives it, if any
+    pub fn compose(&self, backdrop: [f32; 3], paint: impl Fn(Layer) -> Option<[f32; 3]>) -> Composite {
+        let mut composite = Composite { under: backdrop, scale: 1.0, offset: [0.0; 3] };
+        let mut above = false;
+        for state in self.stack.iter().filter(|state| state.shown) {
+            if state.layer == Layer::Annotations {
+                above = true;
+                continue;
+            }
+            let Some(color) = paint(state.layer) else { continue };
+            if above {
+                composite.scale *= 1.0 - state.opacity;
+                composite.offset = mix(composite.offset, color, state.opacity);
+            } else {
+                composite.under = mix(composite.under, color, state.opacity);
+            }
+        }
+        composite
+    }
+}
+
+// `bases:0.5, heat, -grid, cells` in the config and the `layers` command: the layers listed
+// in that order from the bottom, at the opacity after the colon if any, hidden if the name
+// starts with '-', and any not listed hidden below them
+impl std::str::FromStr for Layers {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut listed = Vec::new();
+        for item in s.split([',', ' ']).filter(|item| !item.is_empty()) {
+            let (shown, item) = match item.strip_prefix('-') {
+                Some(item) => (false, item),
+                None => (true, item),
+            };
+            let (name, opacity) = item.split_once(':').unwrap_or((item, "1"));
+            let layer: Layer = name.parse()?;
+            let opacity = opacity.parse().ok().filter(|opacity| (0.0..=1.0).contains(opacity)).ok_or_else(|| format!("Invalid opacity '{opacity}' for layer {name}, expected 0 to 1"))?;
+            if listed.iter().any(|state: &LayerState| state.layer == layer) {
+                return Err(format!("Layer {name} is listed twice"));
+            }
+            listed.push(LayerState { layer, shown, opacity });
+        }
+        let mut stack: Vec<LayerState> = Layer::ALL.into_iter().filter(|layer| !listed.iter().any(|state| state.layer == *layer)).map(|layer| LayerState { layer, shown: false, opacity: 1.0 }).collect();
+        stack.extend(listed);
+        Ok(Self { stack })
+    }
+}
+
+impl std::fmt::Display for Layers {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let items: Vec<String> = self
+            .stack
+            .iter()
+            .map(|state| {
+                let hidden = if state.shown { "" } else { "-" };
+                if state.opacity == 1.0 { format!("{hidden}{}", state.layer.name()) } else { format!("{hidden}{}:{}", state.layer.name(), state.opacity) }
+            })
+            .collect();
+        write!(f, "{}", items.join(","))
+    }
+}
+
+// Font pixels around the panel's rows, and the side of a checkbox
+const PANEL_PADDING: u32 = 3;
+const CHECKBOX: u32 = GLYPH_HEIGHT;
+
+// The layers as a column of checkboxes in the top-left corner of the window, the top layer
+// first, each with its name and opacity; clicking a row shows or hides its layer
+pub struct LayerPanel {
+    pixel: [f32; 2],
+    right: f32,
+    // Top edge of each row, and the bottom edge of the last
+    edges: Vec<f32>,
+}
+
+impl LayerPanel {
+    pub fn new(viewport: &Viewport, text_scale: u32) -> Self {
+        let pixel = font_pixel(viewport, text_scale);
+        let row_height = (GLYPH_HEIGHT + 2 * PANEL_PADDING) as f32 * pixel[1];
+        // A checkbox, a space, the longest name and " 100%"
+        let width = CHECKBOX + ADVANCE + ("annotations".len() as u32 + 5) * ADVANCE + 2 * PANEL_PADDING;
+        let edges = (0..=Layer::ALL.len()).map(|i| 1.0 - i as f32 * row_height).collect();
+        Self { pixel, right: -1.0 + width as f32 * pixel[0], edges }
+    }
+
+    // Layer of the row under a clip-space point
+    pub fn layer_at(&self, layers: &Layers, [x, y]: [f32; 2]) -> Option<Layer> {
+        if x > self.right {
+            return None;
+        }
+        let row = self.edges.windows(2).position(|edges| (edges[1]..edges[0]).contains(&y))?;
+        layers.stack.iter().rev().nth(row).map(|state| state.layer)
+    }
+
+    pub fn vertices(&self, layers: &Layers) -> Vec<Vertex> {
+        let mut vertices = Vec::new();
+        let [pixel_x, pixel_y] = self.pixel;
+        let padding = [PANEL_PADDING as f32 * pixel_x, PANEL_PADDING as f32 * pixel_y];
+        push_rect(&mut vertices, [-1.0, *self.edges.last().unwrap()], [self.right, 1.0], [0.03, 0.03, 0.06]);
+        for (state, edges) in layers.stack.iter().rev().zip(self.edges.windows(2)) {
+            let (left, bottom) = (-1.0 + padding[0], edges[1] + padding[1]);
+            let size = [CHECKBOX as f32 * pixel_x, CHECKBOX as f32 * pixel_y];
+            push_rect(&mut vertices, [left, bottom], [left + size[0], bottom + size[1]], [0.4, 0.4, 0.5]);
+            let fill = if state.shown { [0.85, 0.85, 0.9] } else { [0.03, 0.03, 0.06] };
+            push_rect(&mut vertices, [left + pixel_x, bottom + pixel_y], [left + size[0] - pixel_x, bottom + size[1] - pixel_y], fill);
+            let text = format!("{:<11} {:>3}%", state.layer.name(), (state.opacity * 100.0).round());
+            let color = if state.shown { [0.85, 0.85, 0.85] } else { [0.45, 0.45, 0.5] };
+            push_text(&mut vertices, &text, [left + size[0] + ADVANCE as f32 * pixel_x, bottom], self.pixel, color);
+        }
+        vertices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn composites_layers_in_stack_order() {
+        let mut layers = Layers::default();
+        let paint = |layer| match layer {
+            Layer::Bases => Some([1.0, 0.0, 0.0]),
+            Layer::Cells => Some([0.0, 1.0, 0.0]),
+            Layer::Selection => Some([0.0, 0.0, 1.0]),
+            _ => None,
+        };
+        // By default the selection covers the cells, which cover the hidden bases
+        assert_eq!(layers.compose([0.0; 3], paint).cell(), [0.0, 0.0, 1.0]);
+        layers.show(Layer::Selection, false);
+        layers.show(Layer::Bases, true);
+        layers.set_opacity(Layer::Cells, 0.5);
+        assert_eq!(layers.compose([0.0; 3], paint).cell(), [0.5, 0.5, 0.0]);
+
+        // Cell layers above the annotations tint them, and not the other way round
+        layers.raise(Layer::Bases, true);
+        layers.raise(Layer::Bases, true);
+        assert!(layers.below(Layer::Cells, Layer::Bases));
+        for _ in 0..4 {
+            layers.raise(Layer::Bases, true);
+        }
+        layers.set_opacity(Layer::Bases, 0.5);
+        let composite = layers.compose([0.0; 3], paint);
+        assert_eq!(composite.under, [0.0, 0.5, 0.0]);
+        assert_eq!(composite.cell(), [0.5, 0.25, 0.0]);
+        assert_eq!(composite.over([1.0; 3]), [1.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn parses_and_prints_stacks() {
+        let layers: Layers = "heat, cells:0.5 annotations".parse().unwrap();
+        assert_eq!(layers.to_string(), "-bases,-selection,-grid,heat,cells:0.5,annotations");
+        assert!(!layers.shown(Layer::Selection) && layers.opacity(Layer::Cells) == 0.5);
+        assert!(layers.below(Layer::Grid, Layer::Heat));
+        assert_eq!(layers.to_string().parse::<Layers>(), Ok(layers));
+        assert_eq!(Layers::default().to_string(), "-bases,-heat,cells,selection,-grid,annotations");
+        assert_eq!(Layers::default().to_string().parse::<Layers>(), Ok(Layers::default()));
+        assert!("cells, cells".parse::<Layers>().is_err());
+        assert!("cells:2".parse::<Layers>().is_err());
+        assert!("cels".parse::<Layers>().is_err());
+    }
+
+    #[test]
+    fn panel_rows_hit_their_layers() {
+        let viewport = Viewport { width: 800, height: 600, scale_factor: 1.0 };
+        let panel = LayerPanel::new(&viewport, 1);
+        let layers = Layers::default();
+        let middle = |row: usize| [-0.99, (panel.edges[row] + panel.edges[row + 1]) / 2.0];
+        assert_eq!(panel.layer_at(&layers, middle(0)), Some(Layer::Annotations));
+        assert_eq!(panel.layer_at(&layers, middle(5)), Some(Layer::Bases));
+        assert_eq!(panel.layer_at(&layers, [0.5, middle(0)[1]]), None);
+        assert_eq!(panel.layer_at(&layers, [-0.99, -0.5]), None);
+        assert!(!panel.vertices(&layers).is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+pub mod accessibility;
+#[cfg(feature = "gui")]
+pub mod action;
+pub mod age;
+#[cfg(feature = "gui")]
+pub mod app;
+pub mod automaton;
+pub mod autopause;
+pub mod batch;
+pub mod bitgrid;
+pub mod bookmarks;
+pub mod centroid;
+pub mod checkpoint;
+pub mod clipboard;
+pub mod clock;
+pub mod components;
+pub mod config;
+#[cfg(feature = "gui")]
+pub mod console;
+#[cfg(feature = "gui")]
+pub mod context_menu;
+pub mod contour;
+pub mod evolve;
+pub mod export;
+pub mod fnv;
+pub mod formats;
+#[cfg(feature = "gamepad")]
+pub mod gamepad;
+pub mod gc_rules;
+#[cfg(feature = "gui")]
+pub mod gpu;
+#[cfg(feature = "gui")]
+pub mod grid_texture;
+pub mod heatmap;
+pub mod history;
+#[cfg(feature = "gui")]
+pub mod keymap;
+#[cfg(feature = "gui")]
+pub mod kiosk;
+pub mod layers;
+pub mod loader;
+pub mod lod;
+pub mod logging;
+pub mod msa;
+pub mod mutation;
+#[cfg(feature = "net")]
+pub mod ncbi;
+pub mod notes;
+pub mod presets;
+pub mod provenance;
+pub mod quality;
+pub mod render;
+#[cfg(feature = "gui")]
+pub mod replay;
+pub mod report;
+pub mod rule;
+pub mod rule_library;
+pub mod rule_walk;
+#[cfg(feature = "gui")]
+pub mod ruler;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod selection;
+pub mod session;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod shader_file;
+pub mod sim;
+pub mod strip;
+pub mod sweep;
+#[cfg(feature = "gui")]
+pub mod tools;
+#[cfg(feature = "gui")]
+pub mod touch;
+pub mod tracks;
+pub mod transition;
+pub mod turbo;
+pub mod seq_analysis;
+pub mod universe;
+pub mod verify;
+pub mod vertex;
+pub mod viewport;
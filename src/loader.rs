@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver};
+
+use crate::formats::fasta;
+use crate::msa::{self, RowRecords};
+use crate::seq_analysis::cache::{self, WindowStats};
+use crate::tracks::TrackRegistry;
+
+// Opening a genome (`open`, `--fasta`) without blocking the event loop: the file is read,
+// parsed and analyzed on a background thread while the current sequence stays interactive,
+// and the app swaps the result in when it is ready.
+
+// A sequence read by a `SequenceLoader`, with the tracks of its analyses
+pub struct LoadedSequence {
+    pub path: PathBuf,
+    pub dna: Vec<u8>,
+    pub tracks: TrackRegistry,
+    // The analyses the tracks were built from, for the app's cache, and the sequence's id
+    pub stats: Arc<WindowStats>,
+    pub id: u64,
+    // Putative origin of replication, the cumulative skew minimum
+    pub origin: usize,
+    // Record of each grid row, when the records were stacked one per row
+    pub rows: Option<RowRecords>,
+}
+
+pub struct SequenceLoader {
+    pub path: PathBuf,
+    // Bytes read so far, out of the file's size (or the download's, once known)
+    read: Arc<AtomicU64>,
+    size: Arc<AtomicU64>,
+    result: Receiver<Result<LoadedSequence, String>>,
+}
+
+// Counts the bytes that pass through it, for the progress shown while loading
+struct Counting<R> {
+    inner: R,
+    read: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.read.fetch_add(count as u64, Ordering::Relaxed);
+        Ok(count)
+    }
+}
+
+impl SequenceLoader {
+    pub fn start(path: &Path) -> Result<Self, String> {
+        Self::spawn(path, |reader, path| fasta::read_first(reader, path).map(|dna| (dna, None)))
+    }
+
+    // Record `index` (from 0) of the file
+    pub fn start_record(path: &Path, index: usize) -> Result<Self, String> {
+        Self::spawn(path, move |reader, path| fasta::read_record(reader, path, index).map(|dna| (dna, None)))
+    }
+
+    // Every record of the file, one per row of a `rows`x`cols` grid (see `msa::stack`)
+    pub fn start_rows(path: &Path, rows: u32, cols: u32) -> Result<Self, String> {
+        Self::spawn(path, move |reader, path| {
+            let records = fasta::read_all(reader, path)?;
+            if records.len() > rows as usize {
+                tracing::warn!("Only the first {rows} of {} records fit in the grid", records.len());
+            }
+            let (dna, rows) = msa::stack(&records, rows, cols);
+            Ok((dna, Some(rows)))
+        })
+    }
+
+    // Parse the file on a background thread, then analyze the sequence
+    fn spawn<F>(path: &Path, parse: F) -> Result<Self, String>
+    where
+        F: FnOnce(Counting<BufReader<File>>, &Path) -> Result<(Vec<u8>, Option<RowRecords>), String> + Send + 'static,
+    {
+        let file = File::open(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        let size = file.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+        let read = Arc::new(AtomicU64::new(0));
+        let (sender, result) = mpsc::channel();
+        let (counting, owned) = (Counting { inner: BufReader::new(file), read: Arc::clone(&read) }, path.to_path_buf());
+        std::thread::Builder::new()
+            .name("loader".into())
+            .spawn(move || {
+                let _span = tracing::debug_span!("load", path = %owned.display()).entered();
+                // The app may have moved on to another file; nobody is listening then
+                let _ = sender.send(parse(counting, &owned).map(|(dna, rows)| LoadedSequence { rows, ..analyze(owned, dna) }));
+            })
+            .map_err(|err| format!("Failed to start loading {}: {err}", path.display()))?;
+        Ok(Self { path: path.to_path_buf(), read, size: Arc::new(AtomicU64::new(size)), result })
+    }
+
+    // A sequence downloaded from NCBI by accession (see `ncbi::fetch`), or read from the cache
+    #[cfg(feature = "net")]
+    pub fn start_accession(accession: &str) -> Result<Self, String> {
+        crate::ncbi::check_accession(accession)?;
+        let (read, size) = (Arc::new(AtomicU64::new(0)), Arc::new(AtomicU64::new(0)));
+        let (sender, result) = mpsc::channel();
+        let (owned, progress) = (accession.to_string(), (Arc::clone(&read), Arc::clone(&size)));
+        std::thread::Builder::new()
+            .name("loader".into())
+            .spawn(move || {
+                let _span = tracing::debug_span!("fetch", accession = %owned).entered();
+                let loaded = crate::ncbi::fetch(&owned, &progress.0, &progress.1).and_then(|path| fasta::load_first(&path).map(|dna| analyze(path, dna)));
+                let _ = sender.send(loaded);
+            })
+            .map_err(|err| format!("Failed to start downloading {accession}: {err}"))?;
+        Ok(Self { path: accession.into(), read, size, result })
+    }
+
+    // Share of the file read so far, in 0..=1
+    pub fn progress(&self) -> f64 {
+        let size = self.size.load(Ordering::Relaxed);
+        if size == 0 { 1.0 } else { (self.read.load(Ordering::Relaxed) as f64 / size as f64).min(1.0) }
+    }
+
+    // The sequence once it has been loaded, or why it couldn't be
+    pub fn poll(&self) -> Option<Result<LoadedSequence, String>> {
+        match self.result.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(format!("Loading {} failed", self.path.display()))),
+        }
+    }
+
+    // The same, blocking until the loader is done
+    pub fn wait(&self) -> Result<LoadedSequence, String> {
+        self.result.recv().unwrap_or_else(|_| Err(format!("Loading {} failed", self.path.display())))
+    }
+}
+
+// Tracks over 100 windows of the sequence, as at startup
+pub fn analyze(path: PathBuf, dna: Vec<u8>) -> LoadedSequence {
+    let stats = Arc::new(WindowStats::compute(&dna, (dna.len() / 100).max(1)));
+    let tracks = TrackRegistry::new(&stats);
+    LoadedSequence { path, origin: stats.skew.min_position(), id: cache::sequence_id(&dna), dna, tracks, stats, rows: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_in_the_background() {
+        let path = std::env::temp_dir().join(format!("bio-rust-loader-{}.fa", std::process::id()));
+        std::fs::write(&path, ">chr1\nggatcc\nGCGC\n>chr2\nAAAA\n").unwrap();
+        let loader = SequenceLoader::start(&path).unwrap();
+        let loaded = loop {
+            if let Some(result) = loader.poll() {
+                break result.unwrap();
+            }
+            std::thread::yield_now();
+        };
+        assert_eq!(loaded.dna, b"GGATCCGCGC");
+        assert_eq!(loader.progress(), 1.0);
+        assert!(loaded.tracks.selected().values.len() <= 100);
+
+
+        let loader = SequenceLoader::start_rows(&path, 4, 8).unwrap();
+        let loaded = loop {
+            if let Some(result) = loader.poll() {
+                break result.unwrap();
+            }
+            std::thread::yield_now();
+        };
+        assert_eq!(loaded.dna, b"GGATCCGCAAAA----");
+        assert_eq!(loaded.rows.unwrap().ids, ["chr1", "chr2"]);
+
+        assert_eq!(SequenceLoader::start_record(&path, 1).unwrap().wait().unwrap().dna, b"AAAA");
+        assert!(SequenceLoader::start_record(&path, 2).unwrap().wait().is_err());
+        assert_eq!(fasta::count_records(&path), Ok(2));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(SequenceLoader::start(&path).is_err());
+    }
+}
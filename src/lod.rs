@@ -0,0 +1,94 @@
+use std::ops::Range;
+
+// Level of detail for grids zoomed out past one pixel per cell (`lod`): instead of a quad per
+// cell, each block of `block`x`block` cells is drawn as one square shaded by how many of its
+// cells are alive, like a level of a mipmap. Blocks are powers of two, aligned to the grid so
+// they don't shimmer while panning, and the smallest that are still a couple of pixels wide,
+// which keeps the quads drawn to about as many as the window has pixels to show them.
+
+// Smallest side of a block on screen, in physical pixels
+pub const MIN_BLOCK_PIXELS: f32 = 2.0;
+// How far the cell size has to move past a level's limit before switching, so a zoom or a
+// resize that hovers near one doesn't flip between two levels every frame
+const HYSTERESIS: f32 = 1.25;
+const MAX_BLOCK: u32 = 1 << 12;
+
+// Block side for cells `cell_pixels` physical pixels apart, without hysteresis: single cells
+// while they are a pixel or more
+fn ideal(cell_pixels: f32) -> u32 {
+    if cell_pixels >= 1.0 {
+        return 1;
+    }
+    let mut block = 2;
+    while block < MAX_BLOCK && block as f32 * cell_pixels < MIN_BLOCK_PIXELS {
+        block *= 2;
+    }
+    block
+}
+
+// Block side to draw with next, keeping `current` while the cell size stays within the
+// hysteresis band around its limits
+pub fn block_size(current: u32, cell_pixels: f32) -> u32 {
+    let (finest, coarsest) = (ideal(cell_pixels * HYSTERESIS), ideal(cell_pixels / HYSTERESIS));
+    if (finest..=coarsest).contains(&current) { current } else { ideal(cell_pixels) }
+}
+
+// Blocks overlapping `range` of a row or column of `count` cells
+pub fn blocks(range: Range<u32>, count: u32, block: u32) -> Range<u32> {
+    range.start / block..range.end.min(count).div_ceil(block)
+}
+
+// Live cells in each block of `rows` by `cols` blocks (as from `blocks`), row of blocks by
+// row of blocks
+pub fn reduce(cells: &[bool], grid_rows: u32, grid_cols: u32, rows: Range<u32>, cols: Range<u32>, block: u32) -> Vec<u32> {
+    let width = cols.len();
+    let mut counts = vec![0; rows.len() * width];
+    let (first_col, last_col) = (cols.start * block, (cols.end * block).min(grid_cols));
+    for row in rows.start * block..(rows.end * block).min(grid_rows) {
+        let line = &cells[(row * grid_cols) as usize..][..grid_cols as usize];
+        let counts = &mut counts[(row / block - rows.start) as usize * width..][..width];
+        for col in (first_col..last_col).filter(|&col| line[col as usize]) {
+            counts[(col / block - cols.start) as usize] += 1;
+        }
+    }
+    counts
+}
+
+// How bright a block is drawn, 0 (all dead) to 1 (all alive). Any live cell at all shows, so
+// a lone glider isn't lost in a large block; denser blocks are brighter.
+pub fn shade(alive: u32, cells: u32) -> f32 {
+    if alive == 0 { 0.0 } else { 0.25 + 0.75 * (alive as f32 / cells as f32).sqrt() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switches_levels_with_hysteresis() {
+        assert_eq!(block_size(1, 3.0), 1);
+        assert_eq!(block_size(1, 0.5), 4);
+        // Just below a pixel the single cells stay until the hysteresis band is left
+        assert_eq!(block_size(1, 0.9), 1);
+        assert_eq!(block_size(1, 0.7), 4);
+        assert_eq!(block_size(4, 0.9), 4);
+        assert_eq!(block_size(4, 1.3), 1);
+        // 0.24 pixels a cell wants blocks of 16, but blocks of 8 are kept down to 0.2
+        assert_eq!(block_size(8, 0.24), 8);
+        assert_eq!(block_size(8, 0.19), 16);
+        assert_eq!(block_size(1, 0.0001), MAX_BLOCK);
+    }
+
+    #[test]
+    fn counts_live_cells_per_block() {
+        // 5x5 cells in blocks of 2: the last row and column of blocks are partial
+        let cells: Vec<bool> = "OO...O....O...O.O...O...O".bytes().map(|cell| cell == b'O').collect();
+        assert_eq!(blocks(0..5, 5, 2), 0..3);
+        assert_eq!(blocks(3..4, 5, 2), 1..2);
+        assert_eq!(reduce(&cells, 5, 5, 0..3, 0..3, 2), vec![3, 0, 0, 2, 0, 1, 1, 0, 1]);
+        assert_eq!(reduce(&cells, 5, 5, 1..3, 2..3, 2), vec![1, 1]);
+        assert_eq!(shade(0, 4), 0.0);
+        assert_eq!(shade(4, 4), 1.0);
+        assert!(shade(1, 4096) > 0.25);
+    }
+}
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use tracing::Level;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt;
+use tracing_subscriber::prelude::*;
+
+// Install the global subscriber. Verbosity 0 prints this crate's info messages as plain
+// lines and only warnings from dependencies; `-v` adds debug messages with timestamps,
+// levels and spans (init, I/O), `-vv` adds per-tick and per-frame trace spans. JSON output
+// has one object per event with its span context. wgpu's `log` records are forwarded too.
+// Everything goes to stderr, keeping stdout for `batch` summaries.
+pub fn init(verbosity: u8, json: bool) {
+    let (ours, others) = match verbosity {
+        0 => (Level::INFO, Level::WARN),
+        1 => (Level::DEBUG, Level::INFO),
+        _ => (Level::TRACE, Level::DEBUG),
+    };
+    // The library and the binary (the package name has a dash)
+    let filter = Targets::new().with_target("bio_rust", ours).with_target("Bio_Rust", ours).with_default(others);
+    let registry = tracing_subscriber::registry().with(filter);
+
+    let result = if json {
+        registry.with(fmt::layer().with_writer(std::io::stderr).json().with_current_span(true).with_span_list(false)).try_init()
+    } else if verbosity == 0 {
+        registry.with(fmt::layer().with_writer(std::io::stderr).without_time().with_level(false).with_target(false)).try_init()
+    } else {
+        registry.with(fmt::layer().with_writer(std::io::stderr)).try_init()
+    };
+    if let Err(err) = result {
+        eprintln!("Failed to set up logging: {err}");
+    }
+}
+
+// Upper bounds of the frame time buckets in milliseconds; the last bucket is unbounded
+const BUCKETS_MS: [f64; 7] = [4.0, 8.0, 16.7, 33.3, 50.0, 100.0, 250.0];
+
+// Histogram of the time between frames, summarized on exit
+#[derive(Default)]
+pub struct FrameTimes {
+    counts: [u64; BUCKETS_MS.len() + 1],
+    total: Duration,
+    max: Duration,
+}
+
+impl FrameTimes {
+    pub fn record(&mut self, frame: Duration) {
+        let ms = frame.as_secs_f64() * 1000.0;
+        let bucket = BUCKETS_MS.iter().position(|&bound| ms < bound).unwrap_or(BUCKETS_MS.len());
+        self.counts[bucket] += 1;
+        self.total += frame;
+        self.max = self.max.max(frame);
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    // Upper bound of the bucket holding the given fraction of frames, in milliseconds;
+    // infinite when it falls in the last bucket
+    pub fn percentile(&self, fraction: f64) -> f64 {
+        let target = (self.frames() as f64 * fraction).ceil() as u64;
+        let mut seen = 0;
+        for (bucket, &count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= target.max(1) {
+                return BUCKETS_MS.get(bucket).copied().unwrap_or(f64::INFINITY);
+            }
+        }
+        f64::INFINITY
+    }
+
+    pub fn summary(&self) -> String {
+        let frames = self.frames();
+        if frames == 0 {
+            return "No frames rendered".into();
+        }
+        let mean = self.total.as_secs_f64() * 1000.0 / frames as f64;
+        let mut summary = format!(
+            "{frames} frames, mean {mean:.1} ms, max {:.1} ms, p50 < {} ms, p99 < {} ms\n",
+            self.max.as_secs_f64() * 1000.0,
+            self.percentile(0.5),
+            self.percentile(0.99),
+        );
+        let mut lower = 0.0;
+        for (bucket, &count) in self.counts.iter().enumerate() {
+            let label = match BUCKETS_MS.get(bucket) {
+                Some(upper) => format!("{lower:>5}-{upper:<5} ms"),
+                None => format!("{lower:>5}+      ms"),
+            };
+            let bar = "#".repeat((count * 40).div_ceil(frames) as usize);
+            summary.push_str(&format!("  {label} {count:>8} {bar}\n"));
+            lower = BUCKETS_MS.get(bucket).copied().unwrap_or(lower);
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_times_bucket_and_summarize() {
+        let mut times = FrameTimes::default();
+        assert_eq!(times.summary(), "No frames rendered");
+        for _ in 0..98 {
+            times.record(Duration::from_millis(16));
+        }
+        times.record(Duration::from_millis(40));
+        times.record(Duration::from_millis(300));
+
+        assert_eq!(times.frames(), 100);
+        assert_eq!(times.percentile(0.5), 16.7);
+        assert_eq!(times.percentile(0.99), 50.0);
+        assert_eq!(times.percentile(1.0), f64::INFINITY);
+        assert!(times.summary().starts_with("100 frames, mean 19.1 ms, max 300.0 ms"));
+    }
+}
@@ -1,231 +1,1084 @@
-mod universe;
-mod vertex;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use winit::{
-    event::{Event, WindowEvent},
-    event_loop::EventLoop,
-    window::WindowBuilder,
+use indicatif::{ProgressBar, ProgressStyle};
+use tracing::{error, info, warn};
+
+use bio_rust::batch::{self, BatchSettings, Checkpoint, RecordSummary};
+use bio_rust::checkpoint::{self, MutationState, RunState};
+use bio_rust::evolve::{self, EvolveSettings};
+use bio_rust::formats::fasta;
+use bio_rust::logging;
+use bio_rust::mutation::{DEFAULT_KAPPA, MutationParams, MutationTarget, Mutator};
+use bio_rust::rule::Rule;
+use bio_rust::selection::Region;
+use bio_rust::sweep::{self, Param, Point, Sampling};
+use bio_rust::universe::io::{self as pattern_io, Pattern};
+use bio_rust::universe::{Universe, parse_grid_size};
+#[cfg(feature = "analysis")]
+use bio_rust::{export::png, seq_analysis::dotplot::{self, DotPlot}};
+
+// Only the viewer needs these
+#[cfg(feature = "gui")]
+use {
+    std::io::IsTerminal,
+    std::path::Path,
+    std::sync::Arc,
+    std::time::Duration,
+    winit::{
+        event::{ElementState, Event, MouseButton, MouseScrollDelta, WindowEvent},
+        event_loop::EventLoop,
+        keyboard::{Key, ModifiersState, NamedKey},
+        window::{Fullscreen, WindowBuilder},
+    },
+    bio::seq_analysis::gc::gc_content,
+    bio_rust::action::Action,
+    bio_rust::app::{App, Renderer},
+    bio_rust::export::sheet::{self, SheetSettings},
+    bio_rust::export::video::{self, VideoSettings},
+    bio_rust::config::{self, Config},
+    bio_rust::keymap::{self, Command},
+    bio_rust::kiosk::{Kiosk, KioskSettings},
+    bio_rust::quality::QualityManager,
+    bio_rust::replay::{Recorder, Replay},
+    bio_rust::session,
+    bio_rust::viewport::{CellLayout, MAX_CELL_SIZE, MAX_PADDING},
+    bio_rust::seq_analysis::skew::cumulative_gc_skew,
 };
-use bio::seq_analysis::gc::gc_content;
-use wgpu::*;
-use wgpu::util::DeviceExt;
 
-use crate::universe::Universe;
-use crate::vertex::{Vertex, create_grid_vertices};
+const USAGE: &str = "Usage: bio-rust [-v|-vv] [--log-json] [--record session.rpl] [--replay session.rpl] [--deterministic] [--renderer geometry|texture] [--fit contain|cover|stretch] [--cell-size <pixels>] [--padding 0.2] [--grid <rows>x<cols>] [--fasta genome.fa | --rows aligned.fa | --accession NC_000913.3 | --kiosk genomes.fa [--scene-generations 500] [--kiosk-windows] [--kiosk-rules B3/S23,B36/S23]] [--rule-name highlife] [--shader cells.wgsl] [--serve 127.0.0.1:8080] [--config bio-rust.conf] [--recover] [--record-video out.mp4 [--fps 30] [--video-size <width>x<height>] [--video-duration <seconds>]] [--contact-sheet sheet.png [--sheet-every 100] [--sheet-grid <rows>x<cols>]]
+       bio-rust batch genomes.fa [-v|-vv] [--generations 500] [--grid <rows>x<cols>] [--rule B3/S23] [--threads N] [--output summary.tsv] [--checkpoint file] [--resume]
+       bio-rust run genome.fa [-v|-vv] [--generations 1000000] [--grid <rows>x<cols>] [--rule B3/S23] [--mutate <0..1>] [--seed 1] [--checkpoint-every 100000] [--checkpoint-dir checkpoints] [--keep 3] [--output final.cells]
+       bio-rust run --resume <checkpoint.bin|checkpoints> [-v|-vv] [--generations 1000000] [--checkpoint-every 100000] [--checkpoint-dir checkpoints] [--keep 3] [--output final.cells]
+       bio-rust evolve genome.fa [-v|-vv] [--fitness entropy|population|longevity] [--rounds 20] [--population 32] [--generations 300] [--grid <rows>x<cols>] [--seed 1] [--threads N] [--top 10] [--output rules.tsv]
+       bio-rust sweep [--fasta genome.fa] --param rule=B3/S23,B36/S23 --param density=0.1..0.9 [--param offset=0..5000] [--param grid=32x32,64x64] [-v|-vv] [--sampling grid|lhs] [--steps 5] [--samples 100] [--generations 300] [--grid <rows>x<cols>] [--rule B3/S23] [--seed 1] [--threads N] [--output sweep.csv]
+       bio-rust dotplot genome.fa [other.fa] --output dotplot.png [-v|-vv] [--word 12] [--size 800] [--strand forward|both]";
+
+// Frames per second of the fixed clock with `--deterministic` and no video
+#[cfg(feature = "gui")]
+const DETERMINISTIC_FPS: u32 = 60;
+
+// Generations `run` goes to without `--generations`, and steps between progress updates
+const DEFAULT_RUN_GENERATIONS: u64 = 1_000_000;
+const RUN_STEP: u64 = 10_000;
 
 fn main() {
+    if std::env::args().nth(1).as_deref() == Some("batch") {
+        return batch(std::env::args().skip(2));
+    }
+    if std::env::args().nth(1).as_deref() == Some("run") {
+        return run(std::env::args().skip(2));
+    }
+    if std::env::args().nth(1).as_deref() == Some("evolve") {
+        return evolve(std::env::args().skip(2));
+    }
+    if std::env::args().nth(1).as_deref() == Some("sweep") {
+        return sweep(std::env::args().skip(2));
+    }
+    if std::env::args().nth(1).as_deref() == Some("dotplot") {
+        #[cfg(feature = "analysis")]
+        return dotplot(std::env::args().skip(2));
+        #[cfg(not(feature = "analysis"))]
+        usage_error("dotplot needs a build with `--features analysis`");
+    }
+    interactive();
+}
+
+// The viewer: a window onto the grid, driven by the mouse, the keyboard and the console
+#[cfg(feature = "gui")]
+fn interactive() {
+    let mut record = None;
+    let mut replay = None;
+    let mut renderer = Renderer::Geometry;
+    let mut layout = CellLayout::default();
+    let mut shader = None;
+    let mut open = None;
+    let mut grid = (10, 10);
+    let mut verbosity = 0;
+    let mut json_logs = false;
+    let mut video_path = None;
+    let mut fps = 30;
+    let mut video_size = None;
+    let mut video_duration = None;
+    let mut sheet_path = None;
+    let mut sheet_every = sheet::DEFAULT_EVERY;
+    let mut sheet_grid = sheet::DEFAULT_GRID;
+    let mut serve = None;
+    let mut config_path = None;
+    let mut deterministic = false;
+    let mut recover = false;
+    let mut kiosk: Option<KioskSettings> = None;
+    let mut scene_generations = None;
+    let mut kiosk_windows = false;
+    let mut kiosk_rules = None;
+    let mut rule_name = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-v" => verbosity += 1,
+            "-vv" => verbosity += 2,
+            "--log-json" => json_logs = true,
+            "--deterministic" => deterministic = true,
+            "--kiosk-windows" => kiosk_windows = true,
+            "--recover" => recover = true,
+            flag => match (flag, args.next()) {
+                ("--record", Some(path)) => record = Some(path),
+                ("--replay", Some(path)) => replay = Some(path),
+                ("--shader", Some(path)) => shader = Some(path),
+                ("--fasta", Some(path)) => open = Some(Action::Open(path.into())),
+                ("--rows", Some(path)) => open = Some(Action::OpenRows(path.into())),
+                ("--accession", Some(accession)) => open = Some(Action::Fetch(accession)),
+                ("--kiosk", Some(path)) => kiosk = Some(KioskSettings::new(path.into())),
+                ("--scene-generations", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => scene_generations = Some(value),
+                    _ => usage_error(&format!("Invalid scene length '{value}', expected a number of generations")),
+                },
+                ("--kiosk-rules", Some(rules)) => match rules.split(',').map(str::parse).collect::<Result<Vec<Rule>, String>>() {
+                    Ok(rules) => kiosk_rules = Some(rules),
+                    Err(err) => usage_error(&err),
+                },
+                ("--rule-name", Some(name)) => rule_name = Some(name.to_ascii_lowercase()),
+                ("--record-video", Some(path)) => video_path = Some(path),
+                ("--serve", Some(address)) => serve = Some(address),
+                ("--config", Some(path)) => config_path = Some(path),
+                ("--fps", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => fps = value,
+                    _ => usage_error(&format!("Invalid frame rate '{value}'")),
+                },
+                ("--video-size", Some(size)) => match video::parse_size(&size) {
+                    Some(size) => video_size = Some(size),
+                    None => usage_error(&format!("Invalid video size '{size}', expected even <width>x<height> like 1280x720")),
+                },
+                ("--video-duration", Some(seconds)) => match seconds.parse::<f64>() {
+                    Ok(seconds) if seconds > 0.0 => video_duration = Some(Duration::from_secs_f64(seconds)),
+                    _ => usage_error(&format!("Invalid video duration '{seconds}'")),
+                },
+                ("--contact-sheet", Some(path)) => sheet_path = Some(path),
+                ("--sheet-every", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => sheet_every = value,
+                    _ => usage_error(&format!("Invalid sheet interval '{value}', expected a number of generations")),
+                },
+                ("--sheet-grid", Some(size)) => match sheet::parse_grid(&size) {
+                    Some(size) => sheet_grid = size,
+                    None => usage_error(&format!("Invalid sheet size '{size}', expected <rows>x<cols> like 4x6, of at most {} thumbnails", sheet::MAX_TILES)),
+                },
+                ("--grid", Some(size)) => match parse_grid_size(&size) {
+                    Some(size) => grid = size,
+                    None => usage_error(&format!("Invalid grid size '{size}', expected <rows>x<cols> like 512x512")),
+                },
+                ("--renderer", Some(name)) => match name.parse() {
+                    Ok(name) => renderer = name,
+                    Err(err) => usage_error(&err),
+                },
+                ("--fit", Some(name)) => match name.parse() {
+                    Ok(name) => layout.fit = name,
+                    Err(err) => usage_error(&err),
+                },
+                ("--cell-size", Some(size)) => match size.parse() {
+                    Ok(size) if (1..=MAX_CELL_SIZE).contains(&size) => layout.cell_size = Some(size),
+                    _ => usage_error(&format!("Invalid cell size '{size}', expected 1 to {MAX_CELL_SIZE} pixels")),
+                },
+                ("--padding", Some(padding)) => match padding.parse() {
+                    Ok(padding) if (0.0..=MAX_PADDING).contains(&padding) => layout.padding = padding,
+                    _ => usage_error(&format!("Invalid padding '{padding}', expected 0 to {MAX_PADDING}")),
+                },
+                _ => {
+                    eprintln!("{USAGE}");
+                    std::process::exit(2);
+                }
+            },
+        }
+    }
+
+    logging::init(verbosity, json_logs);
+    let init = tracing::debug_span!("init").entered();
+
     let dna = b"GATCCAGATCGATCCGATCGATC";
     let gc = gc_content(dna);
-    println!("--- Bio Analysis ---");
-    println!("Sequence: {}", std::str::from_utf8(dna).unwrap());
-    println!("GC-Content: {:.2}%", gc * 100.0);
-    println!("--------------------");
+    info!("--- Bio Analysis ---");
+    info!("Sequence: {}", std::str::from_utf8(dna).unwrap());
+    info!("GC-Content: {:.2}%", gc * 100.0);
+    let skew = cumulative_gc_skew(dna, (dna.len() / 100).max(1));
+    info!("Cumulative GC-Skew: min {:.2} at base {} (putative origin), max {:.2} at base {}",
+        skew.cumulative[skew.min_window], skew.min_position(),
+        skew.cumulative[skew.max_window], skew.max_position());
+    info!("--------------------");
 
-    let event_loop = EventLoop::new().unwrap();
+    // The default config file is optional, one given explicitly is not
+    let config = match &config_path {
+        Some(path) => Config::load(path.as_ref()).unwrap_or_else(|err| fail(&err)),
+        None if Path::new(config::DEFAULT_PATH).exists() => Config::load(config::DEFAULT_PATH.as_ref()).unwrap_or_else(|err| fail(&err)),
+        None => Config::default(),
+    };
+
+    // A session left behind by a run that didn't close cleanly is offered back, and kept
+    // aside if it isn't taken
+    let recovery = session::recovery_path();
+    if config.autosave.enabled && recovery.exists() && !recover {
+        recover = offer_recovery(&recovery);
+        if !recover {
+            let discarded = recovery.with_file_name("discarded.session.gz");
+            match std::fs::rename(&recovery, &discarded) {
+                Ok(()) => info!("Moved the unrestored session to {}, `session load` restores it", discarded.display()),
+                Err(err) => warn!("Failed to move {} aside: {err}", recovery.display()),
+            }
+        }
+    }
 
-    let instance = Instance::default();
+    let event_loop = EventLoop::new().unwrap();
 
-    let window = Box::leak(Box::new(
+    let window = Arc::new(
         WindowBuilder::new()
             .with_title("Bio Rust")
             .build(&event_loop)
             .unwrap()
-    ));
-
-    let surface = instance.create_surface(&*window).unwrap();
-
-    let adapter = pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
-        power_preference: PowerPreference::HighPerformance,
-        force_fallback_adapter: false,
-        compatible_surface: Some(&surface),
-    })).expect("Failed to request adapter");
-
-    let (device, queue) = pollster::block_on(adapter.request_device(
-        &wgpu::DeviceDescriptor{
-            label: None,
-            required_features: Features::empty(),
-            required_limits: Limits::default(),
-            memory_hints: Default::default(),
-        },
-        None,
-    )).expect("Failed to request device");
-
-    let size = window.inner_size();
-    let surface_caps = surface.get_capabilities(&adapter);
-    let surface_format = surface_caps.formats[0];
-    let config = SurfaceConfiguration {
-        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        format: surface_format,
-        width: size.width,
-        height: size.height,
-        present_mode: PresentMode::Fifo,
-        alpha_mode: surface_caps.alpha_modes[0],
-        view_formats: vec![],
-        desired_maximum_frame_latency: 2,
-    };
-    surface.configure(&device, &config);
+    );
 
-    let mut universe = Universe::new(10, 10, dna);
-    let cell_size = 0.08;
-    let mut grid_data = create_grid_vertices(&universe, cell_size);
+    let mut app = App::new(window.clone(), dna.to_vec(), &skew, renderer, layout, grid);
+    app.accessibility = config.accessibility;
+    app.auto_pause = config.auto_pause;
+    app.presets = config.presets;
+    app.quality = QualityManager::new(config.frame_budget);
+    if config.autosave.enabled {
+        session::watch_panics();
+    }
+    app.autosave = config.autosave;
+    app.overlay.layers = config.layers;
+    for named in config.rules {
+        app.rule_library.add(named);
+    }
+    app.config_path = config_path.unwrap_or_else(|| config::DEFAULT_PATH.to_string()).into();
+    if let Some(name) = rule_name {
+        if app.rule_library.get(&name).is_none() {
+            usage_error(&format!("Unknown rule name '{name}' (the console's `rules` lists them)"));
+        }
+        app.apply(Action::UseNamedRule(name));
+    }
+    // A recorded video gets one frame per frame of the fixed clock
+    if deterministic {
+        let frame_rate = if video_path.is_some() { fps } else { DETERMINISTIC_FPS };
+        app.make_deterministic(frame_rate);
+        info!("Deterministic: stepping on a fixed clock of {frame_rate} frames/s");
+    }
+
+    if let Some(path) = shader {
+        app.apply(Action::LoadShader(Some(path.into())));
+    }
 
-    let vertex_buffer = device.create_buffer_init(
-        &wgpu::util::BufferInitDescriptor {
-            label: Some("Vertex Buffer"),
-            contents: bytemuck::cast_slice(&grid_data),
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+    if recover {
+        if recovery.exists() {
+            app.apply(Action::LoadSession(recovery.clone()));
+        } else {
+            warn!("No session to recover at {}", recovery.display());
         }
-    );
+    }
 
-    let shader = device.create_shader_module(wgpu::include_wgsl!("shader.wgsl"));
+    #[cfg(not(feature = "net"))]
+    if let Some(Action::Fetch(accession)) = &open {
+        usage_error(&format!("--accession {accession} needs a build with `--features net`"));
+    }
+    // The built-in sequence is shown until the genome has been read
+    if let Some(action) = open {
+        app.apply(action);
+    }
 
-    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-        label: Some("Render Pipeline Layout"),
-        bind_group_layouts: &[],
-        push_constant_ranges: &[],
-    });
+    if let Some(mut settings) = kiosk {
+        settings.generations = scene_generations.unwrap_or(settings.generations);
+        settings.windows = kiosk_windows;
+        settings.rules = kiosk_rules.unwrap_or(settings.rules);
+        let records = fasta::count_records(&settings.path).unwrap_or_else(|err| fail(&err));
+        let seed = if deterministic { 0 } else { std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |since| since.as_nanos() as u64) };
+        info!("Kiosk: {} records of {}, {} generations a scene", records, settings.path.display(), settings.generations);
+        let mut kiosk = Kiosk::new(settings, records, seed);
+        for action in kiosk.first_scene() {
+            app.apply(action);
+        }
+        app.kiosk = Some(kiosk);
+        window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+        window.set_cursor_visible(false);
+    } else if scene_generations.is_some() || kiosk_windows || kiosk_rules.is_some() {
+        usage_error("--scene-generations, --kiosk-windows and --kiosk-rules need --kiosk genomes.fa");
+    }
 
-    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: Some("Render Pipeline"),
-        layout: Some(&render_pipeline_layout),
-        vertex: wgpu::VertexState {
-            module: &shader,
-            entry_point: Some("vs_main"), 
-            buffers: &[Vertex::desc()],  
-            compilation_options: Default::default(),
-        },
-        fragment: Some(wgpu::FragmentState {
-            module: &shader,
-            entry_point: Some("fs_main"),
-            targets: &[Some(wgpu::ColorTargetState {
-                format: config.format,
-                blend: Some(wgpu::BlendState::REPLACE),
-                write_mask: wgpu::ColorWrites::ALL,
-            })],
-            compilation_options: Default::default(),
-        }),
-        primitive: wgpu::PrimitiveState {
-            topology: wgpu::PrimitiveTopology::TriangleList, 
-            ..Default::default()
-        },
-        depth_stencil: None,
-        multisample: wgpu::MultisampleState::default(),
-        multiview: None,
-        cache: None,
-    });
+    if let Some(path) = video_path {
+        let settings = VideoSettings { path: path.into(), fps, size: video_size, duration: video_duration };
+        if let Err(err) = app.record_video(settings) {
+            error!("{err}");
+            std::process::exit(1);
+        }
+    }
 
-    println!("Running");
+    if let Some(path) = sheet_path {
+        let (rows, cols) = sheet_grid;
+        app.apply(Action::ContactSheet(Some(SheetSettings::new(path.into(), sheet_every, rows, cols))));
+    }
 
-    let mut color_toggle = false;
-    let mut cursor_pos = winit::dpi::PhysicalPosition::new(0.0, 0.0);
-    let mut last_update_inst = std::time::Instant::now();
+    if let Some(address) = serve {
+        #[cfg(feature = "server")]
+        match bio_rust::server::Server::start(&address) {
+            Ok(server) => {
+                info!("Serving the API on http://{}", server.address);
+                app.server = Some(server);
+            }
+            Err(err) => fail(&err),
+        }
+        #[cfg(not(feature = "server"))]
+        usage_error(&format!("--serve {address} needs a build with `--features server`"));
+    }
+
+    if let Some(path) = replay {
+        match Replay::load(path.as_ref()) {
+            Ok(replay) => {
+                info!("Replaying {path}");
+                app.replay = Some(replay);
+            }
+            Err(err) => {
+                error!("{err}");
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(path) = record {
+        match Recorder::create(path.as_ref()) {
+            Ok(recorder) => {
+                info!("Recording to {path}");
+                app.recorder = Some(recorder);
+            }
+            Err(err) => {
+                error!("Failed to create {path}: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
 
-    let window_ref = &*window;
+    #[cfg(feature = "gamepad")]
+    let mut gamepads = bio_rust::gamepad::Gamepads::new().inspect_err(|err| warn!("{err}")).ok();
+
+    drop(init);
+    info!("Running");
+
+    let mut cursor_pos = winit::dpi::PhysicalPosition::new(0.0, 0.0);
+    let mut modifiers = ModifiersState::empty();
 
     event_loop.run(move |event, target| {
         match event {
+            // The analysis window only draws charts and reads them out under the cursor; input
+            // is taken from the main window
+            Event::WindowEvent { window_id, event } if app.is_analysis_window(window_id) => match event {
+                WindowEvent::CloseRequested => app.apply(Action::ShowAnalysis(false)),
+                WindowEvent::CursorMoved { position, .. } => app.hover_analysis(Some(position)),
+                WindowEvent::CursorLeft { .. } => app.hover_analysis(None),
+                WindowEvent::Resized(size) => app.resize(window_id, size.width, size.height),
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => app.rescale(window_id, scale_factor),
+                WindowEvent::RedrawRequested => app.render(window_id),
+                _ => {}
+            },
+
             Event::WindowEvent { event: WindowEvent::CloseRequested, ..} => {
-                println!("Closing");
+                info!("Closing");
+                info!("Frame times: {}", app.frame_times.summary());
+                app.finish_video();
+                app.finish_sheet();
+                app.finish_table();
+                app.finish_hashes();
                 target.exit();
             }
 
+            Event::WindowEvent { event: WindowEvent::ModifiersChanged(new), .. } => {
+                modifiers = new.state();
+            }
+
             Event::WindowEvent { event: WindowEvent::CursorMoved { position, .. }, .. } => {
                 cursor_pos = position;
+                app.hover(cursor_pos);
+                app.drag(cursor_pos);
             }
 
-            Event::WindowEvent { 
-                event: WindowEvent::MouseInput { 
-                    state: winit::event::ElementState::Pressed,
-                    button: winit::event::MouseButton::Left,
+            Event::WindowEvent {
+                event: WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Left,
                     ..
-                }, 
-                .. 
-            } => {
-                let size = window_ref.inner_size();
-                let x = (cursor_pos.x as f32 / size.width as f32) * 2.0 - 1.0;
-                let y = (cursor_pos.y as f32 / size.height as f32) * -2.0 + 1.0;
-
-                for row in 0..universe.rows {
-                    for col in 0..universe.cols {
-                        let padding = 0.02;
-                        let x_offset = (col as f32 * (cell_size + padding)) - 0.6;
-                        let y_offset = (row as f32 * (cell_size + padding)) - 0.6;
-
-                        if x >= x_offset && x <= x_offset + cell_size &&
-                           y >= y_offset && y <= y_offset + cell_size {
-                            universe.toggle(row, col);
-                            grid_data = create_grid_vertices(&universe, cell_size);
-                            if !grid_data.is_empty() {
-                                queue.write_buffer(&vertex_buffer, 0, bytemuck::cast_slice(&grid_data));
-                            }
-                        }
-                    }
+                },
+                ..
+            } => app.click(cursor_pos, modifiers.shift_key()),
+
+            Event::WindowEvent {
+                event: WindowEvent::MouseInput {
+                    state: ElementState::Released,
+                    button: MouseButton::Left,
+                    ..
+                },
+                ..
+            } => app.release(),
+
+            // Right-clicking a cell opens the menu of things to do with it
+            Event::WindowEvent {
+                event: WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: MouseButton::Right,
+                    ..
+                },
+                ..
+            } => app.open_context_menu(cursor_pos),
+
+            // Taps, drags and long presses act like the mouse, and pinches zoom and pan the grid
+            Event::WindowEvent { event: WindowEvent::Touch(touch), .. } => app.touch(touch),
+
+            // The wheel scrolls the sequence strip, four bases per line, or zooms the torus view
+            Event::WindowEvent { event: WindowEvent::MouseWheel { delta, .. }, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => if x != 0.0 { x } else { -y },
+                    MouseScrollDelta::PixelDelta(position) => (if position.x != 0.0 { position.x } else { -position.y } / 20.0) as f32,
+                };
+                if app.torus_shown() {
+                    app.zoom_torus(lines);
+                } else {
+                    app.scroll_strip((lines * 4.0).round() as i64);
                 }
             }
 
+            Event::WindowEvent { window_id, event: WindowEvent::Resized(size) } => {
+                app.resize(window_id, size.width, size.height);
+            }
+
+            // Moving to a monitor with another DPI; winit applies its suggested new size
+            Event::WindowEvent { window_id, event: WindowEvent::ScaleFactorChanged { scale_factor, .. } } => {
+                app.rescale(window_id, scale_factor);
+            }
+
             Event::AboutToWait => {
-                if last_update_inst.elapsed() >= std::time::Duration::from_millis(1000) {
-                    universe.tick();
-                    grid_data = create_grid_vertices(&universe, cell_size);
-                    queue.write_buffer(&vertex_buffer, 0, bytemuck::cast_slice(&grid_data));
-                    last_update_inst = std::time::Instant::now();
+                if app.wants_analysis_window() {
+                    match WindowBuilder::new().with_title("Bio Rust - Analysis").build(target) {
+                        Ok(window) => app.open_analysis_window(Arc::new(window)),
+                        Err(err) => {
+                            warn!("Failed to open the analysis window: {err}");
+                            app.apply(Action::ShowAnalysis(false));
+                        }
+                    }
                 }
-                window_ref.request_redraw();
-            }
-
-            Event::WindowEvent { event: WindowEvent::RedrawRequested, .. } => {
-                let output = surface.get_current_texture().unwrap();
-                let view = output.texture.create_view(&TextureViewDescriptor::default());
-
-                let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                });
-
-                {
-                    let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                        label: Some("Render Pass"),
-                        color_attachments: &[Some(RenderPassColorAttachment {
-                            view: &view,
-                            resolve_target: None,
-                            ops: Operations {
-                                load: LoadOp::Clear(Color { 
-                                    r: if color_toggle { 0.15 } else { 0.05 }, 
-                                    g: 0.05, 
-                                    b: if !color_toggle { 0.15 } else { 0.05 }, 
-                                    a: 1.0 
-                                }),
-                                store: StoreOp::Store,
-                            },
-                        })],
-                        ..Default::default()
-                    });
-
-                    render_pass.set_pipeline(&render_pipeline);
-                    render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
-                    render_pass.draw(0..grid_data.len() as u32, 0..1);
+                #[cfg(feature = "gamepad")]
+                if let Some(gamepads) = gamepads.as_mut() {
+                    for action in gamepads.poll(&app) {
+                        app.apply(action);
+                    }
                 }
+                app.update();
+            }
 
-                queue.submit(std::iter::once(encoder.finish()));
-                output.present();
-            }
-            Event::WindowEvent { 
-                event: WindowEvent::KeyboardInput { 
-                    event: input, 
-                    .. 
-                }, 
-                .. 
-            } => {
-                if input.state == winit::event::ElementState::Pressed {
-                    color_toggle = !color_toggle;
-
-                    if color_toggle {
-                        println!("Background: Dim Red");
-                    } else {
-                        println!("Background: Dim Blue");
+            Event::WindowEvent { window_id, event: WindowEvent::RedrawRequested } => {
+                app.render(window_id);
+            }
+
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput {
+                    event: input,
+                    ..
+                },
+                ..
+            } if input.state == ElementState::Pressed && app.console.is_open() => {
+                match input.logical_key.as_ref() {
+                    Key::Named(NamedKey::Escape) | Key::Character("`") => app.console.close(),
+                    Key::Named(NamedKey::Backspace) => app.console.backspace(),
+                    Key::Named(NamedKey::Enter) => match app.console.submit() {
+                        Some(Ok(action)) => app.apply(action),
+                        Some(Err(err)) => warn!("{err}"),
+                        None => {}
+                    },
+                    _ => {
+                        if let Some(text) = &input.text {
+                            app.console.push_str(text);
+                        }
+                    }
+                }
+            }
+
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput {
+                    event: input,
+                    ..
+                },
+                ..
+            } if input.state == ElementState::Pressed && input.logical_key == Key::Named(NamedKey::Escape) && app.context_menu_open() => {
+                app.close_context_menu();
+            }
+
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput {
+                    event: input,
+                    ..
+                },
+                ..
+            } if input.state == ElementState::Pressed => {
+                match keymap::lookup(&input.logical_key, modifiers, &app).map(|binding| &binding.command) {
+                    Some(Command::Apply(actions)) => {
+                        for action in actions(&app) {
+                            app.apply(action);
+                        }
+                    }
+                    Some(Command::Slot(action)) => {
+                        if let Some(slot) = keymap::digit(&input.logical_key) {
+                            app.apply(action(slot));
+                        }
+                    }
+                    Some(Command::Console { prefill, hint }) => {
+                        app.console.open(prefill);
+                        if !hint.is_empty() {
+                            info!("{hint}");
+                        }
                     }
+                    None => {}
                 }
             }
             _ => {},
         }
     }).unwrap();
-}
\ No newline at end of file
+}
+
+#[cfg(not(feature = "gui"))]
+fn interactive() {
+    usage_error("The viewer needs a build with `--features gui`; batch, run, evolve, sweep and dotplot work without it");
+}
+
+// Ask on the terminal whether to restore the session of a run that didn't close cleanly.
+// Without a terminal to ask on, it isn't restored.
+#[cfg(feature = "gui")]
+fn offer_recovery(path: &Path) -> bool {
+    let saved = std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok().and_then(|modified| modified.elapsed().ok());
+    let ago = saved.map_or(String::new(), |elapsed| format!(" {} minutes ago", elapsed.as_secs() / 60));
+    info!("The last run didn't close cleanly, its session was saved{ago}");
+    if !std::io::stdin().is_terminal() {
+        info!("Run with --recover to restore it");
+        return false;
+    }
+    eprint!("Restore it? [y/N] ");
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok() && matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+// Headless runs of every record in a multi-FASTA, summarized as a TSV on stdout or in a file
+fn batch(mut args: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut settings = BatchSettings::default();
+    let mut threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let mut output = None;
+    let mut checkpoint = None;
+    let mut resume = false;
+    let mut verbosity = 0;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-v" => verbosity += 1,
+            "-vv" => verbosity += 2,
+            "--resume" => resume = true,
+            flag if !flag.starts_with('-') && path.is_none() => path = Some(flag.to_string()),
+            flag => match (flag, args.next()) {
+                ("--generations", Some(value)) => match value.parse() {
+                    Ok(value) if value <= evolve::MAX_GENERATIONS => settings.generations = value,
+                    _ => usage_error(&format!("Invalid generation count '{value}', expected at most {}", evolve::MAX_GENERATIONS)),
+                },
+                ("--grid", Some(size)) => match parse_grid_size(&size) {
+                    Some((rows, cols)) => (settings.rows, settings.cols) = (rows, cols),
+                    None => usage_error(&format!("Invalid grid size '{size}', expected <rows>x<cols> like 512x512")),
+                },
+                ("--rule", Some(rule)) => match rule.parse() {
+                    Ok(rule) => settings.rule = rule,
+                    Err(err) => usage_error(&err),
+                },
+                ("--threads", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => threads = value,
+                    _ => usage_error(&format!("Invalid thread count '{value}'")),
+                },
+                ("--output", Some(file)) => output = Some(file),
+                ("--checkpoint", Some(file)) => checkpoint = Some(file),
+                _ => usage_error("Invalid batch arguments"),
+            },
+        }
+    }
+    let Some(path) = path else { usage_error("batch needs a FASTA file") };
+
+    let checkpoint_path = PathBuf::from(checkpoint.unwrap_or_else(|| format!("{path}.checkpoint")));
+
+    logging::init(verbosity, false);
+    let records = fasta::load_all(path.as_ref()).unwrap_or_else(|err| fail(&err));
+    if let Some(id) = batch::duplicate_id(&records) {
+        fail(&format!("{path} has more than one record named '{id}', batch needs unique ids"));
+    }
+    let done = match (resume, checkpoint_path.exists()) {
+        (true, true) => Checkpoint::load(&checkpoint_path, &settings).unwrap_or_else(|err| fail(&err)),
+        (true, false) => {
+            warn!("No checkpoint at {}, starting from the first record", checkpoint_path.display());
+            Vec::new()
+        }
+        (false, true) => fail(&format!("{} exists, pass --resume to continue that batch or delete it", checkpoint_path.display())),
+        (false, false) => Vec::new(),
+    };
+    let resumed: HashSet<&str> = done.iter().map(|summary| summary.id.as_str()).collect();
+    let remaining: Vec<_> = records.iter().filter(|(id, _)| !resumed.contains(id.as_str())).cloned().collect();
+    let checkpoint = Checkpoint::create(&checkpoint_path, &settings, &done).unwrap_or_else(|err| fail(&err));
+
+    // Ctrl+C lets the batch stop between generations and keep what it has
+    let cancel = install_cancel_handler("save partial results");
+
+    info!("Running {} of {} records for up to {} generations on {} threads", remaining.len(), records.len(), settings.generations, threads);
+    let progress = ProgressBar::new(records.len() as u64).with_position(done.len() as u64);
+    progress.set_style(
+        ProgressStyle::with_template("{elapsed_precise} [{bar:40}] {pos}/{len} records, ETA {eta}").unwrap().progress_chars("=> "),
+    );
+    let finished = batch::run(&remaining, &settings, threads, cancel, |summary| {
+        let status = match (summary.stabilized_at, summary.period) {
+            (Some(generation), Some(period)) => format!("period {period} from generation {generation}"),
+            _ => format!("still changing at generation {}", settings.generations),
+        };
+        // Without a terminal there is no bar to print above, so the status is logged instead
+        if progress.is_hidden() {
+            info!("{}: {status}", summary.id);
+        } else {
+            progress.println(format!("{}: {status}", summary.id));
+        }
+        progress.inc(1);
+        if let Err(err) = checkpoint.append(summary) {
+            progress.suspend(|| warn!("{err}"));
+        }
+    });
+    progress.finish_and_clear();
+
+    // Resumed and new summaries in the file's order
+    let mut summaries: HashMap<&str, &RecordSummary> = done.iter().chain(&finished).map(|summary| (summary.id.as_str(), summary)).collect();
+    let all: Vec<RecordSummary> = records.iter().filter_map(|(id, _)| summaries.remove(id.as_str()).cloned()).collect();
+    let interrupted = cancel.load(Ordering::Relaxed);
+    if interrupted {
+        warn!(
+            "Interrupted after {} of {} records, rerun with --resume to continue from {}",
+            all.len(), records.len(), checkpoint.path.display()
+        );
+    } else if let Err(err) = checkpoint.remove() {
+        warn!("{err}");
+    }
+
+    let tsv = batch::to_tsv(&all);
+    let written = match &output {
+        Some(file) => std::fs::write(file, tsv).map(|()| info!("Wrote {file}")),
+        None => std::io::stdout().write_all(tsv.as_bytes()),
+    };
+    if let Err(err) = written {
+        fail(&format!("Failed to write the summary: {err}"));
+    }
+    if interrupted {
+        std::process::exit(130);
+    }
+}
+
+// A dot plot of the first record of a FASTA file against itself, or against the first record
+// of a second file, written straight to a PNG without opening a window
+#[cfg(feature = "analysis")]
+fn dotplot(mut args: impl Iterator<Item = String>) {
+    let mut paths: Vec<String> = Vec::new();
+    let mut word = dotplot::DEFAULT_WORD;
+    let mut size = dotplot::DEFAULT_SIZE;
+    let mut both_strands = true;
+    let mut output = None;
+    let mut verbosity = 0;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-v" => verbosity += 1,
+            "-vv" => verbosity += 2,
+            flag if !flag.starts_with('-') && paths.len() < 2 => paths.push(flag.to_string()),
+            flag => match (flag, args.next()) {
+                ("--word", Some(value)) => match value.parse() {
+                    Ok(value) if (1..=dotplot::MAX_WORD).contains(&value) => word = value,
+                    _ => usage_error(&format!("Invalid word size '{value}', expected 1 to {}", dotplot::MAX_WORD)),
+                },
+                ("--size", Some(value)) => match value.parse() {
+                    Ok(value) if (1..=dotplot::MAX_SIZE).contains(&value) => size = value,
+                    _ => usage_error(&format!("Invalid image size '{value}', expected 1 to {}", dotplot::MAX_SIZE)),
+                },
+                ("--strand", Some(strand)) => match strand.as_str() {
+                    "forward" => both_strands = false,
+                    "both" => both_strands = true,
+                    _ => usage_error(&format!("Unknown strand '{strand}', expected forward or both")),
+                },
+                ("--output", Some(file)) => output = Some(file),
+                _ => usage_error("Invalid dotplot arguments"),
+            },
+        }
+    }
+    if paths.is_empty() {
+        usage_error("dotplot needs a FASTA file");
+    }
+    let Some(output) = output else { usage_error("dotplot needs --output plot.png") };
+
+    logging::init(verbosity, false);
+    let first = |path: &String| {
+        let records = fasta::load_all(path.as_ref()).unwrap_or_else(|err| fail(&err));
+        records.into_iter().next().unwrap_or_else(|| fail(&format!("{path} has no sequences")))
+    };
+    let (x_id, x) = first(&paths[0]);
+    let (y_id, y) = match paths.get(1) {
+        Some(path) => first(path),
+        None => (x_id.clone(), x.clone()),
+    };
+    let plot = DotPlot::compute(&x, &y, word, size, both_strands).unwrap_or_else(|err| fail(&err));
+    let (forward, reverse) = plot.totals();
+    info!("{x_id} ({} bases) against {y_id} ({} bases) with {word}-base words: {forward} forward and {reverse} reverse complement matches", x.len(), y.len());
+    match png::write_rgba(output.as_ref(), plot.width, plot.height, &plot.to_rgba(), None) {
+        Ok(()) => info!("Wrote {output} ({}x{} pixels)", plot.width, plot.height),
+        Err(err) => fail(&format!("Failed to write {output}: {err}")),
+    }
+}
+
+// One long headless run of a sequence, checkpointed every N generations so that a crash or a
+// reboot only loses the generations since the last checkpoint
+fn run(mut args: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut generations = DEFAULT_RUN_GENERATIONS;
+    let (mut grid, mut rule) = (None, None);
+    let (mut rate, mut seed) = (None, bio_rust::mutation::DEFAULT_MUTATION_SEED);
+    let mut every = checkpoint::DEFAULT_EVERY;
+    let mut dir = None;
+    let mut keep = checkpoint::DEFAULT_KEEP;
+    let mut resume = None;
+    let mut output = None;
+    let mut verbosity = 0;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-v" => verbosity += 1,
+            "-vv" => verbosity += 2,
+            flag if !flag.starts_with('-') && path.is_none() => path = Some(flag.to_string()),
+            flag => match (flag, args.next()) {
+                ("--generations", Some(value)) => match value.parse() {
+                    Ok(value) => generations = value,
+                    Err(_) => usage_error(&format!("Invalid generation count '{value}'")),
+                },
+                ("--grid", Some(size)) => match parse_grid_size(&size) {
+                    Some(size) => grid = Some(size),
+                    None => usage_error(&format!("Invalid grid size '{size}', expected <rows>x<cols> like 512x512")),
+                },
+                ("--rule", Some(value)) => rule = Some(value.parse::<Rule>().unwrap_or_else(|err| usage_error(&err))),
+                ("--mutate", Some(value)) => match value.parse::<f64>() {
+                    Ok(value) if (0.0..=1.0).contains(&value) => rate = Some(value),
+                    _ => usage_error(&format!("Invalid mutation rate '{value}', expected 0..1")),
+                },
+                ("--seed", Some(value)) => match value.parse() {
+                    Ok(value) => seed = value,
+                    Err(_) => usage_error(&format!("Invalid seed '{value}'")),
+                },
+                ("--checkpoint-every", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => every = value,
+                    _ => usage_error(&format!("Invalid checkpoint interval '{value}', expected generations above zero")),
+                },
+                ("--checkpoint-dir", Some(value)) => dir = Some(PathBuf::from(value)),
+                ("--keep", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => keep = value,
+                    _ => usage_error(&format!("Invalid checkpoint count '{value}', expected at least 1")),
+                },
+                ("--resume", Some(value)) => resume = Some(PathBuf::from(value)),
+                ("--output", Some(file)) => output = Some(file),
+                _ => usage_error("Invalid run arguments"),
+            },
+        }
+    }
+
+    logging::init(verbosity, false);
+    // A resumed run takes everything but its length and checkpoints from the checkpoint
+    let mutation = |rate: f64, seed: u64| MutationParams { target: MutationTarget::Cells, rate, kappa: DEFAULT_KAPPA, seed };
+    let (run, mut universe, mut mutator) = match (&resume, path) {
+        (Some(resume), None) => {
+            if grid.is_some() || rule.is_some() || rate.is_some() {
+                usage_error("--grid, --rule and --mutate come from the checkpoint when resuming");
+            }
+            let (file, state) = checkpoint::load(resume).unwrap_or_else(|err| fail(&err));
+            info!("Resuming from generation {} of {}", state.generation, file.display());
+            let mutator = state.mutation.map(|saved| Mutator::resume(mutation(saved.rate, seed), saved.rng, saved.count));
+            (state.run, state.universe(), mutator)
+        }
+        (None, Some(path)) => {
+            let dna = fasta::load_first(path.as_ref()).unwrap_or_else(|err| fail(&err));
+            let (rows, cols) = grid.unwrap_or((BatchSettings::default().rows, BatchSettings::default().cols));
+            let mut universe = Universe::new(rows, cols, &dna);
+            universe.rule = rule.unwrap_or_default();
+            let run = checkpoint::run_id(&dna, (rows, cols), universe.rule, rate.map(|rate| (rate, seed)));
+            (run, universe, rate.map(|rate| Mutator::new(mutation(rate, seed))))
+        }
+        _ => usage_error("run needs a FASTA file, or --resume and a checkpoint"),
+    };
+    // A resumed run goes on writing checkpoints next to the one it resumed from
+    let dir = dir.unwrap_or_else(|| match &resume {
+        Some(resume) if resume.is_dir() => resume.clone(),
+        Some(resume) => resume.parent().map(ToOwned::to_owned).unwrap_or_default(),
+        None => PathBuf::from(checkpoint::DEFAULT_DIR),
+    });
+    // A new run doesn't write among the checkpoints of another
+    if resume.is_none() && dir.is_dir() && checkpoint::checkpoints(&dir, None).is_ok_and(|found| !found.is_empty()) {
+        fail(&format!("{} already holds checkpoints, pass --resume to continue that run or pick another --checkpoint-dir", dir.display()));
+    }
+
+    // Ctrl+C writes a checkpoint of the generation reached and stops
+    let cancel = install_cancel_handler("write a checkpoint");
+
+    info!(
+        "Running a {}x{} grid under {} to generation {generations}, a checkpoint every {every} generations in {}",
+        universe.rows, universe.cols, universe.rule, dir.display()
+    );
+    let save = |universe: &Universe, mutator: &Option<Mutator>| {
+        let mutation = mutator.as_ref().map(MutationState::of);
+        checkpoint::save(&dir, &RunState::of(run, universe, mutation), keep)
+    };
+    let progress = ProgressBar::new(generations).with_position(universe.generation.min(generations));
+    progress.set_style(ProgressStyle::with_template("{elapsed_precise} [{bar:40}] {pos}/{len} generations, ETA {eta}").unwrap().progress_chars("=> "));
+    while universe.generation < generations && !cancel.load(Ordering::Relaxed) {
+        let next_checkpoint = (universe.generation / every + 1) * every;
+        let step = (next_checkpoint.min(generations) - universe.generation).min(RUN_STEP);
+        checkpoint::advance(&mut universe, mutator.as_mut(), step);
+        progress.set_position(universe.generation);
+        if universe.generation.is_multiple_of(every) {
+            match save(&universe, &mutator) {
+                Ok(path) => progress.suspend(|| tracing::debug!("Wrote {}", path.display())),
+                Err(err) => progress.suspend(|| warn!("{err}")),
+            }
+        }
+    }
+    progress.finish_and_clear();
+
+    let population = batch::population(&universe);
+    let interrupted = cancel.load(Ordering::Relaxed);
+    if interrupted {
+        match save(&universe, &mutator) {
+            Ok(path) => warn!("Interrupted at generation {}, rerun with --resume {} to continue", universe.generation, path.display()),
+            Err(err) => error!("Interrupted at generation {}, and {err}", universe.generation),
+        }
+    } else {
+        info!("Generation {}: {population} live cells", universe.generation);
+    }
+    if let Some(file) = output {
+        let pattern = Pattern::from_region(&universe, &Region::full(universe.rows, universe.cols));
+        match pattern_io::save(file.as_ref(), &pattern) {
+            Ok(()) => info!("Wrote {file}"),
+            Err(err) => fail(&err),
+        }
+    }
+    if interrupted {
+        std::process::exit(130);
+    }
+}
+
+// A genetic search of rule space for the runs of a sequence that score best on a fitness
+// function, the top rules printed as a TSV on stdout or written to a file
+fn evolve(mut args: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut settings = EvolveSettings { threads: std::thread::available_parallelism().map_or(1, |threads| threads.get()), ..Default::default() };
+    let mut fitness = "entropy".to_string();
+    let mut top = 10;
+    let mut output = None;
+    let mut verbosity = 0;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-v" => verbosity += 1,
+            "-vv" => verbosity += 2,
+            flag if !flag.starts_with('-') && path.is_none() => path = Some(flag.to_string()),
+            flag => match (flag, args.next()) {
+                ("--fitness", Some(name)) => fitness = name,
+                ("--rounds", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => settings.rounds = value,
+                    _ => usage_error(&format!("Invalid round count '{value}'")),
+                },
+                ("--population", Some(value)) => match value.parse() {
+                    Ok(value) if value >= 2 => settings.population = value,
+                    _ => usage_error(&format!("Invalid population size '{value}', expected at least 2")),
+                },
+                ("--generations", Some(value)) => match value.parse() {
+                    Ok(value) if value <= evolve::MAX_GENERATIONS => settings.generations = value,
+                    _ => usage_error(&format!("Invalid generation count '{value}', expected at most {}", evolve::MAX_GENERATIONS)),
+                },
+                ("--grid", Some(size)) => match parse_grid_size(&size) {
+                    Some((rows, cols)) => (settings.rows, settings.cols) = (rows, cols),
+                    None => usage_error(&format!("Invalid grid size '{size}', expected <rows>x<cols> like 512x512")),
+                },
+                ("--seed", Some(value)) => match value.parse() {
+                    Ok(value) => settings.seed = value,
+                    Err(_) => usage_error(&format!("Invalid seed '{value}'")),
+                },
+                ("--threads", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => settings.threads = value,
+                    _ => usage_error(&format!("Invalid thread count '{value}'")),
+                },
+                ("--top", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => top = value,
+                    _ => usage_error(&format!("Invalid rule count '{value}'")),
+                },
+                ("--output", Some(file)) => output = Some(file),
+                _ => usage_error("Invalid evolve arguments"),
+            },
+        }
+    }
+    let Some(path) = path else { usage_error("evolve needs a FASTA file") };
+    let fitness = evolve::fitness(&fitness).unwrap_or_else(|err| usage_error(&err));
+
+    logging::init(verbosity, false);
+    let records = fasta::load_all(path.as_ref()).unwrap_or_else(|err| fail(&err));
+    let Some((id, dna)) = records.first() else { fail(&format!("{path} has no sequences")) };
+
+    // Ctrl+C finishes the round being scored and reports the best rules so far
+    let cancel = install_cancel_handler("keep partial results");
+
+    info!(
+        "Evolving {} rules over {} rounds on {id} ({} bases), {} generations on a {}x{} grid, {} threads",
+        settings.population, settings.rounds, dna.len(), settings.generations, settings.rows, settings.cols, settings.threads
+    );
+    let scored = evolve::evolve(dna, &settings, fitness.as_ref(), cancel, |round, ranked| {
+        let mean = ranked.iter().map(|scored| scored.fitness).sum::<f64>() / ranked.len() as f64;
+        let best = &ranked[0];
+        info!("Round {round}: best {} from base {} at {:.4}, mean {mean:.4}", best.genome.rule, best.genome.offset, best.fitness);
+    });
+    if cancel.load(Ordering::Relaxed) {
+        warn!("Interrupted, reporting the rules scored so far");
+    }
+
+    let tsv = evolve::to_tsv(&scored[..top.min(scored.len())]);
+    let written = match &output {
+        Some(file) => std::fs::write(file, tsv).map(|()| info!("Wrote {file}")),
+        None => std::io::stdout().write_all(tsv.as_bytes()),
+    };
+    if let Err(err) = written {
+        fail(&format!("Failed to write the rules: {err}"));
+    }
+}
+
+// Headless runs over a grid or Latin hypercube of parameters, one CSV row of outcomes per
+// run on stdout or in a file
+fn sweep(mut args: impl Iterator<Item = String>) {
+    let mut fasta_path = None;
+    let mut params: Vec<Param> = Vec::new();
+    let mut sampling = "grid".to_string();
+    let mut steps = 5;
+    let mut samples = 100;
+    let mut generations = 300;
+    let mut base = Point { rule: Rule::default(), density: None, offset: 0, rows: 64, cols: 64, seed: 0 };
+    let mut seed = 1;
+    let mut threads = std::thread::available_parallelism().map_or(1, |threads| threads.get());
+    let mut output = None;
+    let mut verbosity = 0;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-v" => verbosity += 1,
+            "-vv" => verbosity += 2,
+            flag => match (flag, args.next()) {
+                ("--fasta", Some(path)) => fasta_path = Some(path),
+                ("--param", Some(param)) => match param.parse::<Param>() {
+                    Ok(param) => {
+                        params.retain(|other| other.name != param.name);
+                        params.push(param);
+                    }
+                    Err(err) => usage_error(&err),
+                },
+                ("--sampling", Some(name)) => sampling = name,
+                ("--steps", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => steps = value,
+                    _ => usage_error(&format!("Invalid step count '{value}'")),
+                },
+                ("--samples", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => samples = value,
+                    _ => usage_error(&format!("Invalid sample count '{value}'")),
+                },
+                ("--generations", Some(value)) => match value.parse() {
+                    Ok(value) if value <= evolve::MAX_GENERATIONS => generations = value,
+                    _ => usage_error(&format!("Invalid generation count '{value}', expected at most {}", evolve::MAX_GENERATIONS)),
+                },
+                ("--grid", Some(size)) => match parse_grid_size(&size) {
+                    Some((rows, cols)) => (base.rows, base.cols) = (rows, cols),
+                    None => usage_error(&format!("Invalid grid size '{size}', expected <rows>x<cols> like 512x512")),
+                },
+                ("--rule", Some(rule)) => match rule.parse() {
+                    Ok(rule) => base.rule = rule,
+                    Err(err) => usage_error(&err),
+                },
+                ("--seed", Some(value)) => match value.parse() {
+                    Ok(value) => seed = value,
+                    Err(_) => usage_error(&format!("Invalid seed '{value}'")),
+                },
+                ("--threads", Some(value)) => match value.parse() {
+                    Ok(value) if value > 0 => threads = value,
+                    _ => usage_error(&format!("Invalid thread count '{value}'")),
+                },
+                ("--output", Some(file)) => output = Some(file),
+                _ => usage_error("Invalid sweep arguments"),
+            },
+        }
+    }
+    if params.is_empty() {
+        usage_error("sweep needs at least one --param, like --param density=0.1..0.9");
+    }
+    let sampling = match sampling.as_str() {
+        "grid" => Sampling::Grid { steps },
+        "lhs" => Sampling::LatinHypercube { samples },
+        _ => usage_error(&format!("Unknown sampling '{sampling}', expected grid or lhs")),
+    };
+    let soups = params.iter().any(|param| param.name == sweep::Name::Density);
+    if !soups && fasta_path.is_none() {
+        usage_error("sweep needs --fasta genome.fa to seed from, or a density parameter for random soups");
+    }
+
+    logging::init(verbosity, false);
+    let dna = match &fasta_path {
+        Some(path) => {
+            let records = fasta::load_all(path.as_ref()).unwrap_or_else(|err| fail(&err));
+            records.into_iter().next().map(|(_, dna)| dna).unwrap_or_else(|| fail(&format!("{path} has no sequences")))
+        }
+        None => Vec::new(),
+    };
+    if soups && fasta_path.is_some() {
+        warn!("The density parameter seeds random soups, so the sequence isn't used");
+    }
+    let points = sweep::plan(&params, sampling, base, seed).unwrap_or_else(|err| usage_error(&err));
+
+    // Ctrl+C stops the runs still going and keeps the rows finished so far
+    let cancel = install_cancel_handler("save partial results");
+
+    info!("Sweeping {} runs of up to {generations} generations on {threads} threads", points.len());
+    let progress = ProgressBar::new(points.len() as u64);
+    progress.set_style(ProgressStyle::with_template("{elapsed_precise} [{bar:40}] {pos}/{len} runs, ETA {eta}").unwrap().progress_chars("=> "));
+    // Rows keep their run number from the plan, so an interrupted sweep shows which are missing
+    let rows = sweep::run(&dna, &points, generations, threads, cancel, |_| progress.inc(1));
+    progress.finish_and_clear();
+    let interrupted = cancel.load(Ordering::Relaxed);
+    if interrupted {
+        warn!("Interrupted after {} of {} runs", rows.len(), points.len());
+    }
+    let csv = sweep::to_csv(&rows);
+    let written = match &output {
+        Some(file) => std::fs::write(file, csv).map(|()| info!("Wrote {file}")),
+        None => std::io::stdout().write_all(csv.as_bytes()),
+    };
+    if let Err(err) = written {
+        fail(&format!("Failed to write the sweep: {err}"));
+    }
+    if interrupted {
+        std::process::exit(130);
+    }
+}
+
+fn fail(message: &str) -> ! {
+    error!("{message}");
+    std::process::exit(1);
+}
+
+// The first Ctrl+C sets the returned flag for the command to stop at its next chance, a second
+// one exits right away. `lost` says what is given up if the handler can't be installed.
+fn install_cancel_handler(lost: &str) -> &'static AtomicBool {
+    static CANCEL: AtomicBool = AtomicBool::new(false);
+    if let Err(err) = ctrlc::set_handler(|| {
+        if CANCEL.swap(true, Ordering::Relaxed) {
+            std::process::exit(130);
+        }
+    }) {
+        warn!("Ctrl+C will not {lost}: {err}");
+    }
+    &CANCEL
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{message}\n{USAGE}");
+    std::process::exit(2);
+}
+
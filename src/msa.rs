@@ -0,0 +1,85 @@
+use std::ops::Range;
+
+use crate::formats::msa::GAP;
+use crate::render::text::{ADVANCE, GLYPH_HEIGHT, font_pixel, push_text};
+use crate::vertex::{Vertex, push_rect};
+use crate::viewport::{GridGeometry, Viewport};
+
+// Per-row layout (`rows`): each grid row is seeded from its own record of a multi-FASTA, e.g.
+// aligned sequences, so the grid starts as a pseudo-MSA view whose columns then evolve under
+// the automaton. The records are stacked into one sequence of `cols` bases per row, cut or
+// padded with gaps, so the seed map, the strip and the tracks see an ordinary sequence.
+
+// Characters of a record id shown in its row label
+const LABEL_CHARS: usize = 12;
+
+// Record id of each seeded row, top row first
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowRecords {
+    pub ids: Vec<String>,
+}
+
+// The records as one sequence of `cols` bases per row, with the ids of the rows they fill;
+// records past the last row are left out
+pub fn stack(records: &[(String, Vec<u8>)], rows: u32, cols: u32) -> (Vec<u8>, RowRecords) {
+    let records = &records[..records.len().min(rows as usize)];
+    let mut dna = Vec::with_capacity(records.len() * cols as usize);
+    for (_, seq) in records {
+        let kept = seq.len().min(cols as usize);
+        dna.extend_from_slice(&seq[..kept]);
+        // Gaps are never alive under any seed strategy
+        dna.resize(dna.len() + cols as usize - kept, GAP);
+    }
+    (dna, RowRecords { ids: records.iter().map(|(id, _)| id.clone()).collect() })
+}
+
+impl RowRecords {
+    pub fn id(&self, row: u32) -> Option<&str> {
+        self.ids.get(row as usize).map(String::as_str)
+    }
+
+    // Labels left of the grid, at the rows in `rows` that have a record.
+    // When rows are shorter than a label, only every so many rows gets one.
+    pub fn vertices(&self, geometry: &GridGeometry, rows: Range<u32>, viewport: &Viewport, text_scale: u32) -> Vec<Vertex> {
+        let mut vertices = Vec::new();
+        let pixel = font_pixel(viewport, text_scale);
+        let height = (GLYPH_HEIGHT + 2) as f32 * pixel[1];
+        let every = (height / geometry.pitch[1]).ceil().max(1.0) as u32;
+        for row in rows.filter(|row| row % every == 0) {
+            let Some(id) = self.id(row) else {
+                break;
+            };
+            let label: String = id.chars().take(LABEL_CHARS).collect();
+            let [_, bottom] = geometry.cell_origin(row, 0);
+            let baseline = bottom + geometry.cell[1] / 2.0 - GLYPH_HEIGHT as f32 * pixel[1] / 2.0;
+            // Just left of the grid when there is room, over its first cells otherwise
+            let width = (label.len() as u32 * ADVANCE + 2) as f32 * pixel[0];
+            let left = (geometry.origin[0] - width).max(-1.0);
+            push_rect(&mut vertices, [left, baseline - pixel[1]], [left + width, baseline + (GLYPH_HEIGHT + 1) as f32 * pixel[1]], [0.03, 0.03, 0.06]);
+            push_text(&mut vertices, &label, [left + pixel[0], baseline], pixel, [0.85, 0.85, 0.85]);
+        }
+        vertices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacks_one_record_per_row() {
+        let records = [("a".to_string(), b"GCGCGC".to_vec()), ("b".to_string(), b"AT".to_vec()), ("c".to_string(), b"GG".to_vec())];
+        let (dna, rows) = stack(&records, 2, 4);
+        assert_eq!(dna, b"GCGCAT--");
+        assert_eq!(rows.ids, ["a", "b"]);
+        assert_eq!(rows.id(1), Some("b"));
+        assert_eq!(rows.id(2), None);
+
+        // Rows too short for a label each only label every other one
+        let geometry = GridGeometry { origin: [-0.5; 2], cell: [0.01; 2], pitch: [0.012; 2] };
+        let viewport = Viewport { width: 800, height: 600, scale_factor: 1.0 };
+        let labelled = |rows: &RowRecords| rows.vertices(&geometry, 0..2, &viewport, 1).len();
+        let one = RowRecords { ids: vec!["a".into()] };
+        assert_eq!(labelled(&rows), labelled(&one));
+    }
+}
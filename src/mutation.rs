@@ -0,0 +1,155 @@
+use crate::seq_analysis::shuffle::Rng;
+
+// Point mutations as noise: every generation, each cell mutates with probability `rate`.
+// Mutating a cell flips it; mutating a base substitutes another one in the sequence, a
+// transition (A<->G, C<->T) `kappa` times as likely as each of the two transversions, as in
+// Kimura's two-parameter model, and the cells seeded from that base are derived again.
+// Runs are reproducible from the seed.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MutationTarget {
+    Cells,
+    // The bases the cells were seeded from
+    Bases,
+}
+
+impl std::str::FromStr for MutationTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cells" => Ok(MutationTarget::Cells),
+            "bases" => Ok(MutationTarget::Bases),
+            _ => Err(format!("Unknown mutation target '{s}', expected 'cells' or 'bases'")),
+        }
+    }
+}
+
+impl std::fmt::Display for MutationTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MutationTarget::Cells => write!(f, "cells"),
+            MutationTarget::Bases => write!(f, "bases"),
+        }
+    }
+}
+
+// Transition/transversion rate ratio; around 2 is typical of nuclear DNA
+pub const DEFAULT_KAPPA: f64 = 2.0;
+pub const DEFAULT_MUTATION_SEED: u64 = 1;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MutationParams {
+    pub target: MutationTarget,
+    // Chance per cell per generation, 0..1
+    pub rate: f64,
+    pub kappa: f64,
+    pub seed: u64,
+}
+
+pub struct Mutator {
+    pub params: MutationParams,
+    rng: Rng,
+    // Mutations drawn so far
+    pub count: u64,
+}
+
+impl Mutator {
+    pub fn new(params: MutationParams) -> Self {
+        Self { params, rng: Rng::new(params.seed), count: 0 }
+    }
+
+    // Carry on from a checkpoint, with the RNG state and count `rng_state` returned then
+    pub fn resume(params: MutationParams, rng_state: u64, count: u64) -> Self {
+        Self { params, rng: Rng::new(rng_state), count }
+    }
+
+    pub fn rng_state(&self) -> u64 {
+        self.rng.state()
+    }
+
+    // Uniform in (0, 1]
+    fn uniform(&mut self) -> f64 {
+        ((self.rng.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+
+    // Indices in 0..len that mutate this generation. The gaps between them are drawn from
+    // the geometric distribution, so a low rate costs little on a large grid.
+    pub fn draw(&mut self, len: usize) -> Vec<usize> {
+        let rate = self.params.rate;
+        if rate <= 0.0 {
+            return Vec::new();
+        }
+        let hits: Vec<usize> = if rate >= 1.0 {
+            (0..len).collect()
+        } else {
+            let log_miss = (1.0 - rate).ln();
+            let mut hits = Vec::new();
+            let mut index = 0;
+            while index < len {
+                let gap = (self.uniform().ln() / log_miss).floor();
+                if gap >= (len - index) as f64 {
+                    break;
+                }
+                index += gap as usize;
+                hits.push(index);
+                index += 1;
+            }
+            hits
+        };
+        self.count += hits.len() as u64;
+        hits
+    }
+
+    // The base replacing `base`; anything but A, C, G and T stays as it is
+    pub fn substitute(&mut self, base: u8) -> u8 {
+        let (transition, transversions) = match base.to_ascii_uppercase() {
+            b'A' => (b'G', [b'C', b'T']),
+            b'G' => (b'A', [b'C', b'T']),
+            b'C' => (b'T', [b'A', b'G']),
+            b'T' => (b'C', [b'A', b'G']),
+            _ => return base,
+        };
+        let kappa = self.params.kappa;
+        if self.uniform() <= kappa / (kappa + 2.0) { transition } else { transversions[self.rng.below(2)] }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mutator(rate: f64, kappa: f64, seed: u64) -> Mutator {
+        Mutator::new(MutationParams { target: MutationTarget::Bases, rate, kappa, seed })
+    }
+
+    #[test]
+    fn draws_about_the_rate_reproducibly() {
+        let mut first = mutator(0.01, DEFAULT_KAPPA, 7);
+        let hits: Vec<Vec<usize>> = (0..100).map(|_| first.draw(10_000)).collect();
+        // 100 generations of 10 000 cells at 1%: 10 000 expected, give or take 100
+        assert!((9_600..10_400).contains(&first.count), "{}", first.count);
+        assert!(hits.iter().all(|hits| hits.windows(2).all(|pair| pair[0] < pair[1]) && hits.last() < Some(&10_000)));
+
+        let mut again = mutator(0.01, DEFAULT_KAPPA, 7);
+        assert_eq!((0..100).map(|_| again.draw(10_000)).collect::<Vec<_>>(), hits);
+        assert!(mutator(0.0, DEFAULT_KAPPA, 7).draw(100).is_empty());
+        assert_eq!(mutator(1.0, DEFAULT_KAPPA, 7).draw(5), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn transitions_follow_kappa() {
+        let transitions = |kappa: f64| {
+            let mut mutator = mutator(1.0, kappa, 3);
+            (0..10_000).filter(|_| mutator.substitute(b'A') == b'G').count()
+        };
+        // kappa / (kappa + 2) of the substitutions are transitions
+        assert!((4_800..5_200).contains(&transitions(2.0)));
+        assert!((9_000..9_300).contains(&transitions(20.0)));
+        assert_eq!(transitions(0.0), 0);
+
+        let mut mutator = mutator(1.0, 1.0, 3);
+        assert!((0..100).all(|_| mutator.substitute(b'C') != b'C'));
+        assert_eq!(mutator.substitute(b'N'), b'N');
+    }
+}
@@ -0,0 +1,131 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+// Sequences downloaded from NCBI by accession (`--accession`, `fetch`, `--features net`)
+// through the E-utilities efetch API. Every download is kept in a cache directory, so an
+// accession is only fetched once. Requests are spaced out to stay within NCBI's limit of 3
+// a second, or 10 with an API key in `NCBI_API_KEY`.
+
+const EFETCH: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/efetch.fcgi";
+const USER_AGENT: &str = concat!("bio-rust/", env!("CARGO_PKG_VERSION"));
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// When the last request was sent, shared by every download in the process
+static LAST_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+
+// Accessions are letters, digits and underscores with an optional version, like
+// NC_000913.3, which also keeps them safe to use as file names and in URLs
+pub fn check_accession(accession: &str) -> Result<(), String> {
+    let (name, version) = accession.split_once('.').unwrap_or((accession, "1"));
+    let valid = !name.is_empty()
+        && name.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+        && !version.is_empty()
+        && version.bytes().all(|byte| byte.is_ascii_digit());
+    if valid { Ok(()) } else { Err(format!("Invalid accession '{accession}', expected one like NC_000913.3")) }
+}
+
+pub fn efetch_url(accession: &str, api_key: Option<&str>) -> String {
+    let mut url = format!("{EFETCH}?db=nuccore&id={accession}&rettype=fasta&retmode=text");
+    if let Some(key) = api_key {
+        url.push_str(&format!("&api_key={key}"));
+    }
+    url
+}
+
+// Time to wait before the next request, `interval` after the one sent at `last`
+fn wait_for(last: Option<Instant>, interval: Duration, now: Instant) -> Duration {
+    last.map_or(Duration::ZERO, |last| (last + interval).saturating_duration_since(now))
+}
+
+// The cached FASTA file of an accession, downloading it first if it isn't cached yet. The
+// bytes received so far and the size of the download, when NCBI gives one, are stored in
+// `read` and `size` for the progress shown while loading.
+pub fn fetch(accession: &str, read: &AtomicU64, size: &AtomicU64) -> Result<PathBuf, String> {
+    check_accession(accession)?;
+    let dir = crate::config::cache_dir().join("ncbi");
+    let path = dir.join(format!("{accession}.fa"));
+    if path.exists() {
+        tracing::debug!("{accession} cached at {}", path.display());
+        return Ok(path);
+    }
+    std::fs::create_dir_all(&dir).map_err(|err| format!("Failed to create the cache directory {}: {err}", dir.display()))?;
+
+    let api_key = std::env::var("NCBI_API_KEY").ok().filter(|key| !key.is_empty());
+    let interval = Duration::from_millis(if api_key.is_some() { 100 } else { 334 });
+    {
+        let mut last = LAST_REQUEST.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        std::thread::sleep(wait_for(*last, interval, Instant::now()));
+        *last = Some(Instant::now());
+    }
+
+    tracing::info!("Downloading {accession} from NCBI");
+    let response = ureq::get(&efetch_url(accession, api_key.as_deref()))
+        .set("User-Agent", USER_AGENT)
+        .timeout(TIMEOUT)
+        .call()
+        .map_err(|err| match err {
+            ureq::Error::Status(400 | 404, _) => format!("NCBI has no nucleotide record {accession}"),
+            ureq::Error::Status(429, _) => format!("NCBI is rate limiting requests, try {accession} again in a moment"),
+            ureq::Error::Status(code, response) => format!("NCBI answered {code} {} for {accession}", response.status_text()),
+            ureq::Error::Transport(err) => format!("Failed to reach NCBI for {accession}: {err}"),
+        })?;
+    if let Some(length) = response.header("Content-Length").and_then(|length| length.parse().ok()) {
+        size.store(length, Ordering::Relaxed);
+    }
+
+    // Written next to the cache entry and moved into place once complete, so an interrupted
+    // download is never mistaken for a cached one
+    let partial = dir.join(format!("{accession}.fa.part"));
+    let download = || -> io::Result<Vec<u8>> {
+        let (mut body, mut file) = (response.into_reader(), File::create(&partial)?);
+        let (mut buffer, mut start) = (vec![0; 64 * 1024], Vec::new());
+        loop {
+            let count = body.read(&mut buffer)?;
+            if count == 0 {
+                return Ok(start);
+            }
+            if start.len() < 64 {
+                start.extend_from_slice(&buffer[..count.min(64)]);
+            }
+            file.write_all(&buffer[..count])?;
+            read.fetch_add(count as u64, Ordering::Relaxed);
+        }
+    };
+    let start = download().map_err(|err| {
+        let _ = std::fs::remove_file(&partial);
+        format!("Failed to download {accession}: {err}")
+    })?;
+    // NCBI answers some unknown accessions with an error message rather than a status
+    if !start.starts_with(b">") {
+        let _ = std::fs::remove_file(&partial);
+        let message = String::from_utf8_lossy(&start);
+        return Err(format!("NCBI returned no sequence for {accession}: {}", message.lines().next().unwrap_or("empty reply").trim()));
+    }
+    std::fs::rename(&partial, &path).map_err(|err| format!("Failed to cache {accession} at {}: {err}", path.display()))?;
+    tracing::info!("Cached {accession} at {}", path.display());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_accessions_and_spaces_requests() {
+        assert!(check_accession("NC_000913.3").is_ok());
+        assert!(check_accession("U00096").is_ok());
+        for invalid in ["", "NC_000913.", "../etc/passwd", "NC 1", "NC_1.x", "a&id=b"] {
+            assert!(check_accession(invalid).is_err(), "{invalid}");
+        }
+        assert_eq!(efetch_url("U00096", Some("key")), format!("{EFETCH}?db=nuccore&id=U00096&rettype=fasta&retmode=text&api_key=key"));
+
+        let (now, interval) = (Instant::now(), Duration::from_millis(334));
+        assert_eq!(wait_for(None, interval, now), Duration::ZERO);
+        assert_eq!(wait_for(Some(now), interval, now + Duration::from_millis(100)), Duration::from_millis(234));
+        assert_eq!(wait_for(Some(now), interval, now + Duration::from_secs(1)), Duration::ZERO);
+    }
+}
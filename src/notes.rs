@@ -0,0 +1,77 @@
+// Notes attached to sequence positions (`note`, right-click on a cell), for marking where
+// something interesting emerged. A note belongs to the base a cell was seeded from rather
+// than to the cell, so it follows the base when the grid is reseeded from another offset,
+// and it is exported as a BED interval of that one base.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Note {
+    pub position: usize,
+    pub text: String,
+}
+
+// At most one note per position, kept in position order
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Notes {
+    notes: Vec<Note>,
+}
+
+// BED names and session lines are single lines of text
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl Notes {
+    // Attach `text` to `position`, returning the note it replaced
+    pub fn set(&mut self, position: usize, text: &str) -> Option<String> {
+        let note = Note { position, text: one_line(text) };
+        match self.notes.binary_search_by_key(&position, |note| note.position) {
+            Ok(i) => Some(std::mem::replace(&mut self.notes[i], note).text),
+            Err(i) => {
+                self.notes.insert(i, note);
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, position: usize) -> Option<Note> {
+        let i = self.notes.binary_search_by_key(&position, |note| note.position).ok()?;
+        Some(self.notes.remove(i))
+    }
+
+    pub fn get(&self, position: usize) -> Option<&Note> {
+        let i = self.notes.binary_search_by_key(&position, |note| note.position).ok()?;
+        Some(&self.notes[i])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Note> {
+        self.notes.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_one_note_per_position_in_order() {
+        let mut notes = Notes::default();
+        assert_eq!(notes.set(120, "glider\tgun"), None);
+        assert_eq!(notes.set(7, "oscillator  near\nthe origin"), None);
+        assert_eq!(notes.set(120, "puffer"), Some("glider gun".to_string()));
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes.get(7).map(|note| note.text.as_str()), Some("oscillator near the origin"));
+
+        assert_eq!(notes.remove(7).map(|note| note.position), Some(7));
+        assert_eq!(notes.remove(7), None);
+        assert!(notes.get(7).is_none());
+        assert_eq!(notes.iter().map(|note| note.position).collect::<Vec<_>>(), [120]);
+    }
+}
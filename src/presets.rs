@@ -0,0 +1,167 @@
+use crate::rule::Rule;
+use crate::universe::SeedStrategy;
+
+// Two parameter presets, A and B (`preset`), for comparing how the same sequence plays out
+// under different settings. Using one reseeds the grid from the same offset with its seeding
+// and runs it under its rule and theme, so flipping between them (V) shows both outcomes
+// from the same start. A flip is an edit like any other: undo puts back the cells and the
+// parameters from before it.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Slot {
+    A,
+    B,
+}
+
+impl std::str::FromStr for Slot {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "a" => Ok(Slot::A),
+            "b" => Ok(Slot::B),
+            _ => Err(format!("Unknown preset '{s}', expected a or b")),
+        }
+    }
+}
+
+impl std::fmt::Display for Slot {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", if *self == Slot::A { "a" } else { "b" })
+    }
+}
+
+// Which bases start alive: a preset can't hold a comparison or an alignment, only the
+// strategies that come from the sequence alone
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Seeding {
+    Gc,
+    Hydrophobic,
+}
+
+impl Seeding {
+    pub fn strategy(self) -> SeedStrategy {
+        match self {
+            Seeding::Gc => SeedStrategy::GcContent,
+            Seeding::Hydrophobic => SeedStrategy::Hydrophobic,
+        }
+    }
+}
+
+// The window's look: the dim red or dim blue background, or the high-contrast palette
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Theme {
+    Red,
+    Blue,
+    Contrast,
+}
+
+// Parameters a preset sets, as they stood before and after an edit. The seed strategy is
+// kept whole, so undoing a flip restores a comparison or alignment seeding too.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Parameters {
+    pub rule: Rule,
+    pub seed_strategy: SeedStrategy,
+    pub theme: Theme,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Preset {
+    pub rule: Rule,
+    pub seeding: Seeding,
+    pub theme: Theme,
+}
+
+impl Preset {
+    pub fn parameters(&self) -> Parameters {
+        Parameters { rule: self.rule, seed_strategy: self.seeding.strategy(), theme: self.theme }
+    }
+}
+
+// "<rule> <gc|hydrophobic> <red|blue|contrast>", as in the console and the config file
+impl std::str::FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [rule, seeding, theme] = s.split_whitespace().collect::<Vec<_>>()[..] else {
+            return Err(format!("Invalid preset '{s}', expected <B/S> <gc|hydrophobic> <red|blue|contrast>"));
+        };
+        let seeding = match seeding {
+            "gc" => Seeding::Gc,
+            "hydrophobic" => Seeding::Hydrophobic,
+            _ => return Err(format!("Unknown seeding '{seeding}', expected gc or hydrophobic")),
+        };
+        let theme = match theme {
+            "red" => Theme::Red,
+            "blue" => Theme::Blue,
+            "contrast" => Theme::Contrast,
+            _ => return Err(format!("Unknown theme '{theme}', expected red, blue or contrast")),
+        };
+        Ok(Self { rule: rule.parse()?, seeding, theme })
+    }
+}
+
+impl std::fmt::Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let seeding = if self.seeding == Seeding::Gc { "gc" } else { "hydrophobic" };
+        let theme = match self.theme {
+            Theme::Red => "red",
+            Theme::Blue => "blue",
+            Theme::Contrast => "contrast",
+        };
+        write!(f, "{} {seeding} {theme}", self.rule)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Presets {
+    pub a: Option<Preset>,
+    pub b: Option<Preset>,
+    // The preset used last
+    pub active: Option<Slot>,
+}
+
+impl Presets {
+    pub fn get(&self, slot: Slot) -> Option<&Preset> {
+        match slot {
+            Slot::A => self.a.as_ref(),
+            Slot::B => self.b.as_ref(),
+        }
+    }
+
+    pub fn set(&mut self, slot: Slot, preset: Preset) {
+        match slot {
+            Slot::A => self.a = Some(preset),
+            Slot::B => self.b = Some(preset),
+        }
+    }
+
+    // The preset a flip goes to: B after A, A otherwise
+    pub fn flipped(&self) -> Slot {
+        if self.active == Some(Slot::A) { Slot::B } else { Slot::A }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_flips_presets() {
+        let preset: Preset = "B36/S23 hydrophobic contrast".parse().unwrap();
+        assert_eq!(preset.seeding, Seeding::Hydrophobic);
+        assert_eq!(preset.to_string().parse(), Ok(preset));
+        assert_eq!(preset.parameters().seed_strategy, SeedStrategy::Hydrophobic);
+        assert!("B3/S23 gc".parse::<Preset>().is_err());
+        assert!("B3/S23 at red".parse::<Preset>().is_err());
+
+        let mut presets = Presets::default();
+        assert_eq!(presets.flipped(), Slot::A);
+        presets.set(Slot::B, preset);
+        presets.active = Some(Slot::A);
+        assert_eq!(presets.flipped(), Slot::B);
+        assert_eq!(presets.get(Slot::B), Some(&preset));
+        assert_eq!(presets.get(Slot::A), None);
+        assert_eq!("B".parse(), Ok(Slot::B));
+    }
+}
@@ -0,0 +1,94 @@
+// Where an exported result came from, so it can be reproduced: the build, the sequence and
+// how the grid was seeded from it, the rule, the mutation seed and the generation. Exports
+// carry it in their own way: PNG text chunks, '#' comment lines above CSV and TSV tables,
+// Arrow schema metadata, SVG metadata, lines in saved sessions and a field of the server's
+// JSON state.
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Provenance {
+    // File or accession the sequence was read from, or "built-in"
+    pub input: String,
+    // FNV-1a of the sequence's bases, as the analysis cache identifies it
+    pub input_hash: u64,
+    // Offset the whole grid was last seeded from
+    pub seed_offset: usize,
+    pub rule: String,
+    // Seed of the random mutations, if any were drawn
+    pub rng_seed: Option<u64>,
+    pub generation: u64,
+}
+
+impl Provenance {
+    // `input_hash` is the sequence's `cache::sequence_id`, computed once per sequence by the caller
+    pub fn new(input: &str, input_hash: u64, seed_offset: usize, rule: String, rng_seed: Option<u64>, generation: u64) -> Self {
+        Self { input: input.to_string(), input_hash, seed_offset, rule, rng_seed, generation }
+    }
+
+    // Each entry as (key, value), the build first
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("version", format!("bio-rust {VERSION}")),
+            ("input", self.input.clone()),
+            ("input_hash", format!("{:016x}", self.input_hash)),
+            ("seed_offset", self.seed_offset.to_string()),
+            ("rule", self.rule.clone()),
+            ("rng_seed", self.rng_seed.map_or("none".to_string(), |seed| seed.to_string())),
+            ("generation", self.generation.to_string()),
+        ]
+    }
+
+    // `text` with a '#' comment line per entry above it, for CSV and TSV files
+    pub fn commented(&self, text: &str) -> String {
+        let mut commented: String = self.fields().into_iter().map(|(key, value)| format!("# {key}: {value}\n")).collect();
+        commented.push_str(text);
+        commented
+    }
+
+    pub fn to_json(&self) -> String {
+        let entries: Vec<String> = self
+            .fields()
+            .into_iter()
+            .map(|(key, value)| match key {
+                "seed_offset" | "generation" => format!("\"{key}\":{value}"),
+                "rng_seed" if self.rng_seed.is_none() => format!("\"{key}\":null"),
+                "rng_seed" => format!("\"{key}\":{value}"),
+                _ => format!("\"{key}\":\"{}\"", escape(&value)),
+            })
+            .collect();
+        format!("{{{}}}", entries.join(","))
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| match c {
+            '"' | '\\' => vec!['\\', c],
+            c if c.is_control() => format!("\\u{:04x}", c as u32).chars().collect(),
+            c => vec![c],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::seq_analysis::cache::sequence_id;
+
+    #[test]
+    fn writes_comments_and_json() {
+        let provenance = Provenance::new("C:\\genomes\\\"ecoli\".fa", sequence_id(b"GATC"), 120, "B3/S23".to_string(), None, 42);
+        let csv = provenance.commented("generation,population\n");
+        assert!(csv.starts_with("# version: bio-rust "));
+        assert!(csv.contains("# seed_offset: 120\n# rule: B3/S23\n# rng_seed: none\n# generation: 42\ngeneration,population\n"));
+        assert_eq!(csv.lines().filter(|line| line.starts_with('#')).count(), provenance.fields().len());
+
+        let json = provenance.to_json();
+        assert!(json.contains("\"input\":\"C:\\\\genomes\\\\\\\"ecoli\\\".fa\""), "{json}");
+        assert!(json.ends_with("\"seed_offset\":120,\"rule\":\"B3/S23\",\"rng_seed\":null,\"generation\":42}"), "{json}");
+        assert_eq!(provenance.input_hash, sequence_id(b"GATC"));
+        let seeded = Provenance { rng_seed: Some(7), ..provenance };
+        assert!(seeded.to_json().contains("\"rng_seed\":7,"));
+    }
+}
@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+// Automatic quality degradation (`auto_degrade` and `frame_budget` in the config): the CPU
+// time of every update, from the ticks to the upload, is averaged over recent frames, leaving
+// out the wait for the display. When the average stays over the budget, the grid window drops a quality level,
+// and once it stays well under the budget it climbs back. Climbing back to a level that
+// soon proves too slow again makes the next climb wait twice as long, so a frame time near
+// the budget doesn't make the quality flicker.
+
+pub const DEFAULT_FRAME_BUDGET: Duration = Duration::from_millis(16);
+// Weight of the newest frame in the running average
+const SMOOTHING: f64 = 0.1;
+// Frames over the budget in a row before dropping a level
+const DEGRADE_FRAMES: u32 = 30;
+// Frames under HEADROOM times the budget in a row before climbing a level, at first
+const RESTORE_FRAMES: u32 = 120;
+const MAX_RESTORE_FRAMES: u32 = 3600;
+const HEADROOM: f64 = 0.5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameBudget {
+    pub enabled: bool,
+    pub limit: Duration,
+}
+
+impl Default for FrameBudget {
+    fn default() -> Self {
+        Self { enabled: true, limit: DEFAULT_FRAME_BUDGET }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Quality {
+    Full,
+    // Dead cells drawn as one fill, without the lines between cells, and coarser tracks
+    Reduced,
+    // Coarser tracks still, and generations shown only every few frames
+    Minimal,
+}
+
+impl std::fmt::Display for Quality {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Quality::Full => write!(f, "full"),
+            Quality::Reduced => write!(f, "reduced"),
+            Quality::Minimal => write!(f, "minimal"),
+        }
+    }
+}
+
+pub struct QualityManager {
+    pub budget: FrameBudget,
+    level: Quality,
+    // Running average of the update's CPU time, in milliseconds
+    average: f64,
+    over: u32,
+    under: u32,
+    restore_frames: u32,
+    // Frames since the last climb, while it may still be undone
+    since_restore: Option<u32>,
+}
+
+impl Default for QualityManager {
+    fn default() -> Self {
+        Self::new(FrameBudget::default())
+    }
+}
+
+impl QualityManager {
+    pub fn new(budget: FrameBudget) -> Self {
+        Self { budget, level: Quality::Full, average: 0.0, over: 0, under: 0, restore_frames: RESTORE_FRAMES, since_restore: None }
+    }
+
+    pub fn level(&self) -> Quality {
+        self.level
+    }
+
+    pub fn average_ms(&self) -> f64 {
+        self.average
+    }
+
+    // Record a frame's CPU time, returning the new level if it changed
+    pub fn record(&mut self, cpu_time: Duration) -> Option<Quality> {
+        let ms = cpu_time.as_secs_f64() * 1000.0;
+        self.average += (ms - self.average) * SMOOTHING;
+        if !self.budget.enabled {
+            return self.set_level(Quality::Full);
+        }
+        let budget = self.budget.limit.as_secs_f64() * 1000.0;
+        (self.over, self.under) = match self.average {
+            average if average > budget => (self.over + 1, 0),
+            average if average < budget * HEADROOM => (0, self.under + 1),
+            _ => (0, 0),
+        };
+        self.since_restore = self.since_restore.map(|frames| frames + 1).filter(|&frames| frames < self.restore_frames);
+
+        if self.over >= DEGRADE_FRAMES && self.level < Quality::Minimal {
+            // The last climb didn't hold
+            self.restore_frames = match self.since_restore {
+                Some(_) => (self.restore_frames * 2).min(MAX_RESTORE_FRAMES),
+                None => RESTORE_FRAMES,
+            };
+            self.since_restore = None;
+            let level = if self.level == Quality::Full { Quality::Reduced } else { Quality::Minimal };
+            return self.set_level(level);
+        }
+        if self.under >= self.restore_frames && self.level > Quality::Full {
+            self.since_restore = Some(0);
+            let level = if self.level == Quality::Minimal { Quality::Reduced } else { Quality::Full };
+            return self.set_level(level);
+        }
+        None
+    }
+
+    fn set_level(&mut self, level: Quality) -> Option<Quality> {
+        if level == self.level {
+            return None;
+        }
+        self.level = level;
+        (self.over, self.under) = (0, 0);
+        Some(level)
+    }
+
+    // Whether every cell is drawn with the lines between cells showing
+    pub fn grid_lines(&self) -> bool {
+        self.level == Quality::Full
+    }
+
+    // Most points a track chart is drawn with
+    pub fn track_points(&self) -> usize {
+        match self.level {
+            Quality::Full => usize::MAX,
+            Quality::Reduced => 256,
+            Quality::Minimal => 64,
+        }
+    }
+
+    // Frames a new generation may wait before the grid is rebuilt, so the generations
+    // stepped meanwhile are uploaded together
+    pub fn frames_per_upload(&self) -> u32 {
+        if self.level == Quality::Minimal { 3 } else { 1 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(quality: &mut QualityManager, ms: u64, count: u32) -> Vec<Quality> {
+        (0..count).filter_map(|_| quality.record(Duration::from_millis(ms))).collect()
+    }
+
+    #[test]
+    fn degrades_over_budget_and_restores_with_headroom() {
+        let mut quality = QualityManager::default();
+        assert!(frames(&mut quality, 10, 500).is_empty());
+        assert_eq!(frames(&mut quality, 30, 60), [Quality::Reduced]);
+        assert_eq!(frames(&mut quality, 30, 60), [Quality::Minimal]);
+        assert!(!quality.grid_lines() && quality.frames_per_upload() > 1);
+        assert!(frames(&mut quality, 30, 500).is_empty());
+
+        // Climbing takes longer than dropping
+        assert!(frames(&mut quality, 2, 100).is_empty());
+        assert_eq!(frames(&mut quality, 2, 400), [Quality::Reduced, Quality::Full]);
+        assert_eq!(quality.track_points(), usize::MAX);
+
+        // Off, it stays at full quality
+        let mut off = QualityManager::new(FrameBudget { enabled: false, ..Default::default() });
+        assert!(frames(&mut off, 30, 500).is_empty());
+        assert!(off.average_ms() > 29.0);
+    }
+
+    #[test]
+    fn backs_off_when_a_restore_does_not_hold() {
+        // Frames at `ms` until the level changes
+        let until_change = |quality: &mut QualityManager, ms: u64| (1..).find(|_| quality.record(Duration::from_millis(ms)).is_some()).unwrap();
+        let mut quality = QualityManager::default();
+        until_change(&mut quality, 30);
+        // Fast enough at the reduced level, too slow at full quality
+        let first = until_change(&mut quality, 2);
+        assert_eq!(quality.level(), Quality::Full);
+        until_change(&mut quality, 30);
+        assert_eq!(quality.level(), Quality::Reduced);
+        let second = until_change(&mut quality, 2);
+        assert!(second >= first * 2 - 20, "{first} {second}");
+    }
+}
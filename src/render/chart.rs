@@ -0,0 +1,273 @@
+use crate::render::text::{ADVANCE, GLYPH_HEIGHT, push_text};
+use crate::vertex::{TRACK_MARK_COLOR, Vertex, push_line, push_rect};
+
+// Line and area charts of a series, e.g. a sequence track or the population curve, drawn as
+// quads so they share the grid's triangle-list pipeline. The value axis is fitted to round
+// bounds around the values. With text on, a chart also shows its label, the values of its
+// ticks and, under the cursor, a readout of the point there.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChartStyle {
+    Line,
+    // Filled down to the bottom of the chart
+    Area,
+}
+
+pub struct Chart<'a> {
+    pub label: &'a str,
+    pub values: &'a [f64],
+    pub color: [f32; 3],
+    pub style: ChartStyle,
+    // Point marked with a vertical tick, e.g. the skew minimum
+    pub mark: Option<usize>,
+    // The readout gives a point's position as `x_step` per point, in `x_unit`
+    pub x_step: usize,
+    pub x_unit: &'a str,
+}
+
+// Clip-space rectangle a chart fills
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl Frame {
+    pub fn contains(&self, [x, y]: [f32; 2]) -> bool {
+        (self.min[0]..=self.max[0]).contains(&x) && (self.min[1]..=self.max[1]).contains(&y)
+    }
+
+    // Frames of `count` charts stacked top to bottom, filling the whole window
+    pub fn stacked(count: usize) -> Vec<Frame> {
+        let height = 1.8 / count.max(1) as f32;
+        (0..count)
+            .map(|i| {
+                let top = 0.9 - i as f32 * height;
+                // Leave a gap between charts
+                Frame { min: [-0.9, top - height + 0.1], max: [0.9, top] }
+            })
+            .collect()
+    }
+}
+
+// Value axis: round bounds around the values and the step between its ticks
+#[derive(Debug, PartialEq)]
+pub struct Scale {
+    pub low: f64,
+    pub high: f64,
+    pub step: f64,
+}
+
+impl Scale {
+    // Bounds on a round step of 1, 2 or 5 times a power of ten, the smallest that takes at
+    // most `ticks` steps to cover the values
+    pub fn fit(values: &[f64], ticks: usize) -> Self {
+        let finite = || values.iter().copied().filter(|value| value.is_finite());
+        let (mut low, mut high) = (finite().fold(f64::INFINITY, f64::min), finite().fold(f64::NEG_INFINITY, f64::max));
+        if low > high {
+            (low, high) = (0.0, 1.0);
+        } else if low == high {
+            let pad = if low == 0.0 { 1.0 } else { low.abs() * 0.1 };
+            (low, high) = (low - pad, high + pad);
+        }
+        let round = |rough: f64| {
+            let magnitude = 10f64.powf(rough.log10().floor());
+            [1.0, 2.0, 5.0, 10.0].into_iter().map(|factor| factor * magnitude).find(|&step| step >= rough).unwrap_or(rough)
+        };
+        let ticks = ticks.max(1) as f64;
+        let mut step = round((high - low) / ticks);
+        // Rounding the bounds out can take more steps than asked for
+        while (high / step).ceil() - (low / step).floor() > ticks {
+            step = round(step * 1.5);
+        }
+        Self { low: (low / step).floor() * step, high: (high / step).ceil() * step, step }
+    }
+
+    // Height of `value` in 0..1 between the bounds
+    pub fn level(&self, value: f64) -> f32 {
+        ((value - self.low) / (self.high - self.low)) as f32
+    }
+
+    pub fn ticks(&self) -> Vec<f64> {
+        let steps = ((self.high - self.low) / self.step).round() as usize;
+        (0..=steps).map(|i| self.low + i as f64 * self.step).collect()
+    }
+
+    // Decimals the step needs
+    pub fn decimals(&self) -> usize {
+        (-self.step.log10().floor()).max(0.0) as usize
+    }
+
+    pub fn format(&self, value: f64) -> String {
+        // Adding zero turns -0 into 0
+        format!("{:.*}", self.decimals(), value + 0.0)
+    }
+}
+
+// Font pixels between a tick's label and its line, and at least between two labels
+const LABEL_GAP: u32 = 2;
+const MAX_TICKS: usize = 5;
+
+impl Chart<'_> {
+    // Point under the clip-space `x`, if the chart has any there
+    pub fn index_at(&self, frame: &Frame, x: f32) -> Option<usize> {
+        if self.values.is_empty() || !(frame.min[0]..=frame.max[0]).contains(&x) {
+            return None;
+        }
+        let t = (x - frame.min[0]) / (frame.max[0] - frame.min[0]).max(f32::EPSILON);
+        Some((t * (self.values.len() - 1) as f32).round() as usize)
+    }
+
+    // The readout of point `index`, e.g. "GC content 1200 bp: 0.52"
+    pub fn readout(&self, index: usize) -> Option<String> {
+        let value = *self.values.get(index)?;
+        // One decimal finer than an axis with a few ticks
+        let decimals = Scale::fit(self.values, 4).decimals() + 1;
+        let unit = if self.x_unit.is_empty() { String::new() } else { format!(" {}", self.x_unit) };
+        Some(format!("{} {}{unit}: {:.*}", self.label, index * self.x_step, decimals, value + 0.0))
+    }
+
+    // The chart filling `frame`, with text at font pixels of `text` if given and, for a
+    // cursor at clip-space `hover` over it, a line through the point there
+    pub fn vertices(&self, frame: &Frame, text: Option<[f32; 2]>, hover: Option<f32>) -> Vec<Vertex> {
+        let (min, max) = (frame.min, frame.max);
+        let mut vertices = Vec::new();
+        // Dim baseline so an empty or flat chart is still visible
+        push_rect(&mut vertices, [min[0], min[1]], [max[0], min[1] + 0.004], [0.25, 0.25, 0.25]);
+
+        // Up to MAX_TICKS, as many as there is room for labels
+        let ticks = text.map_or(1, |pixel| ((max[1] - min[1]) / ((GLYPH_HEIGHT + 2 * LABEL_GAP) as f32 * pixel[1])) as usize);
+        let scale = Scale::fit(self.values, ticks.clamp(1, MAX_TICKS));
+        // Text goes on top of the chart, so it is collected apart
+        let mut labels = Vec::new();
+        if let Some(pixel) = text {
+            let dim = self.color.map(|channel| channel * 0.25);
+            for tick in scale.ticks() {
+                let y = min[1] + scale.level(tick) * (max[1] - min[1]);
+                push_rect(&mut vertices, [min[0], y - pixel[1] / 4.0], [max[0], y + pixel[1] / 4.0], dim);
+                // Above the line, or below it at the top of the chart
+                let above = y + LABEL_GAP as f32 * pixel[1];
+                let bottom = if above + GLYPH_HEIGHT as f32 * pixel[1] > max[1] { y - (LABEL_GAP + GLYPH_HEIGHT) as f32 * pixel[1] } else { above };
+                push_text(&mut labels, &scale.format(tick), [min[0] + LABEL_GAP as f32 * pixel[0], bottom], pixel, self.color.map(|channel| channel * 0.7));
+            }
+            let width = (self.label.chars().count() as u32 * ADVANCE) as f32 * pixel[0];
+            push_text(&mut labels, self.label, [max[0] - width, max[1] + LABEL_GAP as f32 * pixel[1]], pixel, self.color);
+        }
+
+        if self.values.len() < 2 {
+            vertices.extend(labels);
+            return vertices;
+        }
+        let point = |i: usize| -> [f32; 2] {
+            let t = i as f32 / (self.values.len() - 1) as f32;
+            [min[0] + t * (max[0] - min[0]), min[1] + scale.level(self.values[i]) * (max[1] - min[1])]
+        };
+
+        if self.style == ChartStyle::Area {
+            let fill = self.color.map(|channel| channel * 0.25);
+            for i in 0..self.values.len() - 1 {
+                let (a, b) = (point(i), point(i + 1));
+                for position in [[a[0], min[1]], a, b, [a[0], min[1]], b, [b[0], min[1]]] {
+                    vertices.push(Vertex { position, color: fill, ..Default::default() });
+                }
+            }
+        }
+        for i in 0..self.values.len() - 1 {
+            push_line(&mut vertices, point(i), point(i + 1), 0.006, self.color);
+        }
+
+        if let Some(i) = self.mark.filter(|&i| i < self.values.len()) {
+            let x = point(i)[0];
+            push_rect(&mut vertices, [x - 0.004, min[1]], [x + 0.004, max[1]], TRACK_MARK_COLOR);
+        }
+
+        if let Some(index) = hover.and_then(|x| self.index_at(frame, x)) {
+            let [x, y] = point(index);
+            push_rect(&mut vertices, [x - 0.002, min[1]], [x + 0.002, max[1]], [0.8, 0.8, 0.8]);
+            push_rect(&mut vertices, [x - 0.01, y - 0.01], [x + 0.01, y + 0.01], [1.0, 1.0, 1.0]);
+            if let (Some(pixel), Some(readout)) = (text, self.readout(index)) {
+                push_text(&mut labels, &readout, [min[0], max[1] + LABEL_GAP as f32 * pixel[1]], pixel, [1.0, 1.0, 1.0]);
+            }
+        }
+        vertices.extend(labels);
+        vertices
+    }
+}
+
+// `values` averaged over runs of consecutive points, so there are at most `max` of them, with
+// the number of points in a run
+pub fn decimate(values: &[f64], max: usize) -> (Vec<f64>, usize) {
+    let stride = values.len().div_ceil(max.max(1)).max(1);
+    (values.chunks(stride).map(|run| run.iter().sum::<f64>() / run.len() as f64).collect(), stride)
+}
+
+// `charts` stacked top to bottom in the frames of `Frame::stacked`; the one under the
+// clip-space `hover` reads out the point there
+pub fn create_stacked_charts(charts: &[Chart], text: Option<[f32; 2]>, hover: Option<[f32; 2]>) -> Vec<Vertex> {
+    let frames = Frame::stacked(charts.len());
+    charts
+        .iter()
+        .zip(&frames)
+        .flat_map(|(chart, frame)| {
+            let hover = hover.filter(|&position| frame.contains(position)).map(|[x, _]| x);
+            chart.vertices(frame, text, hover)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_fits_round_bounds() {
+        let scale = Scale::fit(&[0.23, 0.61, 0.48], 5);
+        assert_eq!((scale.low, scale.step), (0.2, 0.1));
+        assert!((scale.high - 0.7).abs() < 1e-9);
+        assert_eq!(scale.ticks().iter().map(|&tick| scale.format(tick)).collect::<Vec<_>>(), ["0.2", "0.3", "0.4", "0.5", "0.6", "0.7"]);
+        assert_eq!(scale.format(-0.0), "0.0");
+        // Fewer ticks take a wider step
+        assert_eq!(Scale::fit(&[0.23, 0.61, 0.48], 4).step, 0.2);
+
+        let wide = Scale::fit(&[-130.0, 1840.0], 3);
+        assert_eq!((wide.low, wide.high, wide.step), (-1000.0, 2000.0, 1000.0));
+        assert_eq!(wide.format(1000.0), "1000");
+        // A flat series gets some room around it
+        let flat = Scale::fit(&[5.0; 3], 4);
+        assert!(flat.low < 5.0 && flat.high > 5.0);
+        assert_eq!(Scale::fit(&[], 4).level(0.5), 0.5);
+    }
+
+    #[test]
+    fn decimates_to_at_most_the_points_asked_for() {
+        let values: Vec<f64> = (0..10).map(f64::from).collect();
+        assert_eq!(decimate(&values, 4), (vec![1.0, 4.0, 7.0, 9.0], 3));
+        assert_eq!(decimate(&values, usize::MAX), (values.clone(), 1));
+        assert_eq!(decimate(&[], 4), (Vec::new(), 1));
+    }
+
+    #[test]
+    fn hover_reads_out_the_point_under_the_cursor() {
+        let values = [0.5, 0.25, 0.75, 1.0, 0.0];
+        let chart = Chart { label: "GC", values: &values, color: [1.0; 3], style: ChartStyle::Line, mark: None, x_step: 100, x_unit: "bp" };
+        let frame = Frame { min: [-1.0, -1.0], max: [1.0, 0.0] };
+        assert_eq!(chart.index_at(&frame, 0.0), Some(2));
+        assert_eq!(chart.index_at(&frame, 0.9), Some(4));
+        assert_eq!(chart.index_at(&frame, 1.5), None);
+        assert_eq!(chart.readout(3).as_deref(), Some("GC 300 bp: 1.00"));
+
+        let plain = chart.vertices(&frame, None, None);
+        assert!(chart.vertices(&frame, None, Some(0.0)).len() > plain.len());
+        let area = Chart { style: ChartStyle::Area, ..chart };
+        assert_eq!(area.vertices(&frame, None, None).len(), plain.len() + 4 * 6);
+
+        // Only the chart under the cursor reads out
+        let charts = [Chart { ..area }, Chart { label: "B", ..area }];
+        let pixel = [0.01, 0.01];
+        let hovered = create_stacked_charts(&charts, Some(pixel), Some([0.0, 0.5]));
+        let text = create_stacked_charts(&charts, Some(pixel), None);
+        let mut readout = Vec::new();
+        push_text(&mut readout, "GC 200 bp: 0.75", [0.0; 2], pixel, [1.0; 3]);
+        assert_eq!(hovered.len(), text.len() + 12 + readout.len());
+    }
+}
@@ -0,0 +1,130 @@
+use wgpu::TextureFormat;
+
+use crate::gpu::Gpu;
+use crate::grid_texture::{GridLayout, GridTexture};
+use crate::render::heightfield::HeightfieldView;
+use crate::render::torus::TorusView;
+use crate::universe::Universe;
+
+// Everything on the GPU sized by the grid: the texture renderer's cell textures and the 3D
+// views' cell texture and column buffer. Each one is written with exactly as many cells as
+// it was made for, so before anything is uploaded `rebuild` is called with the universe and
+// the way its cells are drawn: a grid that changed size (a resize, another sequence, a
+// session or pattern of another size) or a switch of renderer or view gets the objects it
+// needs built anew and the others dropped, rather than the old ones written past their end.
+pub struct GpuGridResources {
+    format: TextureFormat,
+    // Window size the 3D views render at
+    size: (u32, u32),
+    // Rows and columns the objects below were built for
+    grid: (u32, u32),
+    pub texture: Option<GridTexture>,
+    pub torus: Option<TorusView>,
+    pub heightfield: Option<HeightfieldView>,
+}
+
+// How the cells are drawn, and so which of the objects they need
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CellView {
+    // As vertex geometry, which needs none of them
+    Geometry,
+    // By the texture renderer, laid out on the window like this
+    Texture(GridLayout),
+    Torus,
+    Heightfield,
+}
+
+impl GpuGridResources {
+    pub fn new(format: TextureFormat, size: (u32, u32)) -> Self {
+        Self { format, size, grid: (0, 0), texture: None, torus: None, heightfield: None }
+    }
+
+    pub fn resize(&mut self, gpu: &Gpu, width: u32, height: u32) {
+        self.size = (width, height);
+        if let Some(torus) = self.torus.as_mut() {
+            torus.resize(gpu, width, height);
+        }
+        if let Some(heightfield) = self.heightfield.as_mut() {
+            heightfield.resize(gpu, width, height);
+        }
+    }
+
+    // Make the objects match the universe's grid and the view, building what is missing and
+    // dropping everything else. A 3D view the device can't hold is an error, and left out.
+    pub fn rebuild(&mut self, gpu: &Gpu, universe: &Universe, view: CellView) -> Result<(), String> {
+        let grid = (universe.rows, universe.cols);
+        if grid != self.grid {
+            self.clear();
+            self.grid = grid;
+        }
+        let (texture, torus, heightfield) = (self.texture.take(), self.torus.take(), self.heightfield.take());
+        match view {
+            CellView::Geometry => {}
+            // Panning and zooming move the tiles, so a texture is also rebuilt for a new layout
+            CellView::Texture(layout) => {
+                self.texture = Some(texture.filter(|texture| texture.layout() == layout).unwrap_or_else(|| GridTexture::new(gpu, self.format, layout)));
+            }
+            CellView::Torus => {
+                self.torus = Some(match torus {
+                    Some(torus) => torus,
+                    None => TorusView::new(gpu, self.format, grid, self.size)?,
+                });
+            }
+            CellView::Heightfield => {
+                self.heightfield = Some(match heightfield {
+                    Some(heightfield) => heightfield,
+                    None => HeightfieldView::new(gpu, self.format, grid, self.size)?,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // Drop every object, e.g. with the device they belonged to
+    pub fn clear(&mut self) {
+        self.texture = None;
+        self.torus = None;
+        self.heightfield = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::dpi::PhysicalSize;
+    use wgpu::Instance;
+
+    use super::*;
+    use crate::viewport::{CellLayout, Viewport};
+
+    #[test]
+    fn rebuilds_for_a_resized_grid() {
+        let Ok(gpu) = Gpu::headless(&Instance::default()).inspect_err(|err| eprintln!("Skipping GPU resource test: {err}")) else {
+            return;
+        };
+        let mut resources = GpuGridResources::new(TextureFormat::Rgba8UnormSrgb, (64, 64));
+        let layout = |universe: &Universe| {
+            let geometry = Viewport::new(PhysicalSize::new(64, 64), 1.0).grid_geometry(universe.rows, universe.cols, &CellLayout::default());
+            GridLayout::new(&geometry, universe.rows, universe.cols)
+        };
+
+        let small = Universe::new(4, 6, b"");
+        resources.rebuild(&gpu, &small, CellView::Torus).unwrap();
+        assert_eq!(resources.torus.as_ref().map(TorusView::grid), Some((4, 6)));
+
+        // The torus is rebuilt for the bigger grid, and uploading all of it fits
+        let big = Universe::new(9, 5, b"");
+        resources.rebuild(&gpu, &big, CellView::Torus).unwrap();
+        assert_eq!(resources.torus.as_ref().map(TorusView::grid), Some((9, 5)));
+
+        // Switching views drops what the old one needed
+        resources.rebuild(&gpu, &big, CellView::Texture(layout(&big))).unwrap();
+        assert!(resources.torus.is_none());
+        assert_eq!(resources.texture.as_ref().map(GridTexture::layout), Some(layout(&big)));
+        resources.texture.as_ref().unwrap().upload(&gpu, &big, None);
+        resources.rebuild(&gpu, &small, CellView::Heightfield).unwrap();
+        assert!(resources.texture.is_none());
+        assert_eq!(resources.heightfield.as_ref().map(HeightfieldView::grid), Some((4, 6)));
+        resources.rebuild(&gpu, &small, CellView::Geometry).unwrap();
+        assert!(resources.heightfield.is_none());
+    }
+}
@@ -0,0 +1,358 @@
+use wgpu::*;
+use wgpu::util::DeviceExt;
+
+use crate::gpu::Gpu;
+use crate::heatmap;
+use crate::render::torus::{DEPTH_FORMAT, Mat4, cross, depth_target, dot, normalize};
+use crate::selection::Region;
+use crate::universe::Universe;
+
+// Heightfield view (`heightfield on`, I): the grid seen from above at an isometric angle,
+// with every live cell a column as tall as it is old, so still lifes and other structures
+// that last rise out of the churn like terrain while short-lived cells stay flat. Each cell
+// is an instance of one box mesh, scaled to its height in the vertex shader, and lit by a
+// fixed directional light.
+
+// Height of the oldest columns, as a share of the grid's longer side
+const HEIGHT_SHARE: f32 = 0.15;
+// Height of a cell that was just born, in cells
+const BASE_HEIGHT: f32 = 0.3;
+// Most cells drawn, one instance each
+const MAX_COLUMNS: u32 = 1 << 20;
+// Direction the camera looks from: the corner at row 0, column 0, up high
+const EYE: [f32; 3] = [-1.0, -1.0, 1.2];
+const DEAD: [f32; 3] = [0.1, 0.1, 0.1];
+const SELECTED_DEAD: [f32; 3] = [0.3, 0.3, 0.12];
+const SELECTED_ALIVE: [f32; 3] = [0.8, 0.8, 0.2];
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+struct BoxVertex {
+    // Corner of the unit box, scaled to the cell's footprint and height in the shader
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+
+// One cell's column
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Column {
+    pub color: [f32; 3],
+    pub height: f32,
+}
+
+impl BoxVertex {
+    const ATTRIBUTES: [VertexAttribute; 2] = vertex_attr_array![0 => Float32x3, 1 => Float32x3];
+
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<BoxVertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+impl Column {
+    const ATTRIBUTES: [VertexAttribute; 2] = vertex_attr_array![2 => Float32x3, 3 => Float32];
+
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<Column>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+// The unit box without its bottom, two triangles per face
+fn box_mesh() -> Vec<BoxVertex> {
+    // Each face as its normal and its corners in order around it
+    let faces: [([f32; 3], [[f32; 3]; 4]); 5] = [
+        ([0.0, 0.0, 1.0], [[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 1.0, 1.0]]),
+        ([-1.0, 0.0, 0.0], [[0.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 1.0], [0.0, 1.0, 0.0]]),
+        ([1.0, 0.0, 0.0], [[1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [1.0, 1.0, 1.0], [1.0, 0.0, 1.0]]),
+        ([0.0, -1.0, 0.0], [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 0.0, 1.0], [0.0, 0.0, 1.0]]),
+        ([0.0, 1.0, 0.0], [[0.0, 1.0, 0.0], [0.0, 1.0, 1.0], [1.0, 1.0, 1.0], [1.0, 1.0, 0.0]]),
+    ];
+    faces
+        .iter()
+        .flat_map(|&(normal, corners)| [0, 1, 2, 0, 2, 3].map(|i| BoxVertex { position: corners[i], normal }))
+        .collect()
+}
+
+// Height of the oldest columns of a rows x cols grid, in cells
+pub fn max_height(rows: u32, cols: u32) -> f32 {
+    (rows.max(cols) as f32 * HEIGHT_SHARE).max(BASE_HEIGHT * 2.0)
+}
+
+// Color and height of every cell, row-major, from its age level in 0..1
+pub fn columns(universe: &Universe, levels: &[f32], selection: Option<Region>) -> Vec<Column> {
+    let top = max_height(universe.rows, universe.cols);
+    (0..universe.rows)
+        .flat_map(|row| (0..universe.cols).map(move |col| (row, col)))
+        .zip(universe.cells.iter().zip(levels))
+        .map(|((row, col), (&alive, &level))| {
+            let selected = selection.is_some_and(|selection| selection.contains(row, col));
+            let color = match (alive, selected) {
+                (false, false) => DEAD,
+                (false, true) => SELECTED_DEAD,
+                (true, false) => heatmap::color(0.3 + 0.7 * level),
+                (true, true) => SELECTED_ALIVE,
+            };
+            let height = if alive { BASE_HEIGHT + level * (top - BASE_HEIGHT) } else { 0.0 };
+            Column { color, height }
+        })
+        .collect()
+}
+
+// Orthographic projection of the grid, centered on the origin and up to `max_height` tall,
+// seen from `EYE` and scaled to fit the window. Depth runs from 0 at the corner nearest the
+// camera to 1 at the furthest.
+pub fn isometric(rows: u32, cols: u32, max_height: f32, aspect: f32) -> Mat4 {
+    let eye = normalize(EYE);
+    let right = normalize(cross([-eye[0], -eye[1], -eye[2]], [0.0, 0.0, 1.0]));
+    let up = cross(right, [-eye[0], -eye[1], -eye[2]]);
+
+    let (half_cols, half_rows) = (cols as f32 / 2.0, rows as f32 / 2.0);
+    let corners: Vec<[f32; 3]> = [-half_cols, half_cols]
+        .into_iter()
+        .flat_map(|x| [-half_rows, half_rows].into_iter().flat_map(move |y| [[x, y, 0.0], [x, y, max_height]]))
+        .collect();
+    let range = |axis: [f32; 3]| {
+        let projected = corners.iter().map(|&corner| dot(corner, axis));
+        let (min, max) = projected.fold((f32::MAX, f32::MIN), |(min, max), value| (min.min(value), max.max(value)));
+        ((min + max) / 2.0, (max - min) / 2.0)
+    };
+    let ((center_x, half_x), (center_y, half_y), (center_z, half_z)) = (range(right), range(up), range(eye));
+    // Leave a margin, and keep cells square in pixels
+    let scale = 0.95 * (aspect / half_x).min(1.0 / half_y);
+    let (sx, sy, sz) = (scale / aspect, scale, 0.5 / (half_z + 1.0));
+
+    [
+        [right[0] * sx, up[0] * sy, -eye[0] * sz, 0.0],
+        [right[1] * sx, up[1] * sy, -eye[1] * sz, 0.0],
+        [right[2] * sx, up[2] * sy, -eye[2] * sz, 0.0],
+        [-center_x * sx, -center_y * sy, 0.5 + center_z * sz, 1.0],
+    ]
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Scene {
+    view_projection: Mat4,
+    // Columns, rows
+    grid: [u32; 2],
+    padding: [u32; 2],
+}
+
+pub struct HeightfieldView {
+    pipeline: RenderPipeline,
+    mesh: Buffer,
+    mesh_count: u32,
+    columns: Buffer,
+    scene: Buffer,
+    bind_group: BindGroup,
+    depth: TextureView,
+    // Columns, rows
+    grid: [u32; 2],
+}
+
+impl HeightfieldView {
+    // Fails for grids of more than `MAX_COLUMNS` cells
+    pub fn new(gpu: &Gpu, format: TextureFormat, (rows, cols): (u32, u32), (width, height): (u32, u32)) -> Result<Self, String> {
+        if rows as u64 * cols as u64 > MAX_COLUMNS as u64 {
+            return Err(format!("The {rows}x{cols} grid is too large for the heightfield view, at most {MAX_COLUMNS} cells"));
+        }
+
+        let vertices = box_mesh();
+        let mesh = gpu.device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Heightfield Mesh Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: BufferUsages::VERTEX,
+        });
+        let columns = gpu.device.create_buffer(&BufferDescriptor {
+            label: Some("Heightfield Column Buffer"),
+            size: (rows * cols) as BufferAddress * std::mem::size_of::<Column>() as BufferAddress,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let scene = gpu.device.create_buffer(&BufferDescriptor {
+            label: Some("Heightfield Scene Buffer"),
+            size: std::mem::size_of::<Scene>() as BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = gpu.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Heightfield Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::VERTEX,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = gpu.device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Heightfield Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: scene.as_entire_binding(),
+            }],
+        });
+
+        let shader = gpu.device.create_shader_module(include_wgsl!("heightfield.wgsl"));
+        let pipeline_layout = gpu.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Heightfield Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = gpu.device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Heightfield Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[BoxVertex::desc(), Column::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::REPLACE),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Ok(Self {
+            pipeline,
+            mesh,
+            mesh_count: vertices.len() as u32,
+            columns,
+            scene,
+            bind_group,
+            depth: depth_target(gpu, width, height),
+            grid: [cols, rows],
+        })
+    }
+
+    // Rows and columns there are instances for; another grid size needs a new view
+    pub fn grid(&self) -> (u32, u32) {
+        (self.grid[1], self.grid[0])
+    }
+
+    pub fn resize(&mut self, gpu: &Gpu, width: u32, height: u32) {
+        self.depth = depth_target(gpu, width, height);
+    }
+
+    pub fn upload(&self, gpu: &Gpu, columns: &[Column], aspect: f32) {
+        let [cols, rows] = self.grid;
+        gpu.queue.write_buffer(&self.columns, 0, bytemuck::cast_slice(columns));
+        let view_projection = isometric(rows, cols, max_height(rows, cols), aspect);
+        gpu.queue.write_buffer(&self.scene, 0, bytemuck::bytes_of(&Scene { view_projection, grid: self.grid, padding: [0; 2] }));
+    }
+
+    // Clear `view` to `clear_color` and draw the columns; overlays are drawn over it afterwards
+    pub fn draw(&self, encoder: &mut CommandEncoder, view: &TextureView, clear_color: Color, timestamp_writes: Option<RenderPassTimestampWrites>) {
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Heightfield Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(clear_color),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: &self.depth,
+                depth_ops: Some(Operations {
+                    load: LoadOp::Clear(1.0),
+                    store: StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes,
+            ..Default::default()
+        });
+        let [cols, rows] = self.grid;
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.mesh.slice(..));
+        render_pass.set_vertex_buffer(1, self.columns.slice(..));
+        render_pass.draw(0..self.mesh_count, 0..rows * cols);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(matrix: &Mat4, [x, y, z]: [f32; 3]) -> [f32; 3] {
+        std::array::from_fn(|row| matrix[0][row] * x + matrix[1][row] * y + matrix[2][row] * z + matrix[3][row])
+    }
+
+    #[test]
+    fn columns_rise_with_age() {
+        let mut universe = Universe::new(2, 2, b"");
+        universe.toggle(0, 0);
+        universe.toggle(1, 1);
+        let columns = columns(&universe, &[1.0, 0.0, 0.0, 0.0], Some(Region { row: 1, col: 0, rows: 1, cols: 2 }));
+        assert_eq!(columns[0].height, max_height(2, 2));
+        assert_eq!(columns[1], Column { color: DEAD, height: 0.0 });
+        assert_eq!(columns[2].color, SELECTED_DEAD);
+        assert_eq!(columns[3], Column { color: SELECTED_ALIVE, height: BASE_HEIGHT });
+        assert_eq!(box_mesh().len(), 30);
+    }
+
+    #[test]
+    fn isometric_view_fits_the_grid() {
+        let (rows, cols) = (40, 100);
+        let top = max_height(rows, cols);
+        let matrix = isometric(rows, cols, top, 16.0 / 9.0);
+        for x in [-50.0, 50.0] {
+            for y in [-20.0, 20.0] {
+                for z in [0.0, top] {
+                    let [x, y, depth] = project(&matrix, [x, y, z]);
+                    assert!(x.abs() <= 0.951 && y.abs() <= 0.951 && (0.0..=1.0).contains(&depth), "{x} {y} {depth}");
+                }
+            }
+        }
+        // Columns point up the screen, and the corner at row 0, column 0 is nearest
+        let [_, base, _] = project(&matrix, [0.0, 0.0, 0.0]);
+        let [_, peak, _] = project(&matrix, [0.0, 0.0, top]);
+        assert!(peak > base);
+        assert!(project(&matrix, [-50.0, -20.0, 0.0])[2] < project(&matrix, [50.0, 20.0, 0.0])[2]);
+    }
+
+    #[test]
+    fn shader_validates() {
+        let module = naga::front::wgsl::parse_str(include_str!("heightfield.wgsl")).unwrap();
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+            .validate(&module)
+            .unwrap();
+    }
+}
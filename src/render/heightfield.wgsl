@@ -0,0 +1,46 @@
+// Draws every cell as a box as tall as the cell is old, one instance per cell
+struct Scene {
+  view_projection: mat4x4<f32>,
+  // Columns, rows
+  grid: vec2<u32>,
+}
+
+@group(0) @binding(0) var<uniform> scene: Scene;
+
+struct VertexInput {
+  // Corner of the unit box
+  @location(0) position: vec3<f32>,
+  @location(1) normal: vec3<f32>,
+  // The cell's column
+  @location(2) color: vec3<f32>,
+  @location(3) height: f32,
+};
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) normal: vec3<f32>,
+  @location(1) color: vec3<f32>,
+};
+
+// Gap around each column, as a share of a cell
+const INSET: f32 = 0.06;
+
+@vertex
+fn vs_main(in: VertexInput, @builtin(instance_index) index: u32) -> VertexOutput {
+  let cell = vec2<f32>(f32(index % scene.grid.x), f32(index / scene.grid.x));
+  let footprint = cell + vec2<f32>(INSET) + in.position.xy * (1.0 - 2.0 * INSET) - vec2<f32>(scene.grid) / 2.0;
+
+  var out: VertexOutput;
+  out.clip_position = scene.view_projection * vec4<f32>(footprint, in.position.z * in.height, 1.0);
+  out.normal = in.normal;
+  out.color = in.color;
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  // Sunlight from the upper left of the view, with some ambient light so no face goes black
+  let light = normalize(vec3<f32>(-0.5, 0.3, 1.0));
+  let shade = 0.3 + 0.7 * max(dot(in.normal, light), 0.0);
+  return vec4<f32>(in.color * shade, 1.0);
+}
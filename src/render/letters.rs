@@ -0,0 +1,257 @@
+use std::ops::Range;
+
+use wgpu::*;
+use wgpu::util::DeviceExt;
+
+use crate::gpu::Gpu;
+use crate::render::text::{GLYPH_HEIGHT, GLYPH_WIDTH, glyph};
+use crate::viewport::GridGeometry;
+
+// Base letters (`letters`): zoomed in until a cell is at least MIN_CELL_PIXELS across, each
+// cell shows the letter of the base it was seeded from, so the sequence can be read off the
+// grid without the inspector. The overlay font's glyphs for the bases are rendered once into
+// a small atlas texture, and every letter is an instance of one quad sampling it, drawn
+// over the cells and under the tracks and panels.
+
+pub const MIN_CELL_PIXELS: f32 = 24.0;
+// Letter height as a share of the cell
+const LETTER_SHARE: f32 = 0.6;
+// Glyphs in the atlas, in order; anything else is drawn as the last one
+const ALPHABET: &[u8] = b"ACGTUN?";
+// Letters over light cells are dark, and light over dark ones
+const DARK: [f32; 3] = [0.05, 0.05, 0.05];
+const LIGHT: [f32; 3] = [0.95, 0.95, 0.95];
+// Most letters drawn; the instance buffer grows up to this
+const MAX_LETTERS: usize = 1 << 20;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Letter {
+    // Bottom-left corner and size of the letter's box in clip space
+    pub origin: [f32; 2],
+    pub size: [f32; 2],
+    pub color: [f32; 3],
+    // Index in the atlas
+    pub glyph: u32,
+}
+
+impl Letter {
+    const ATTRIBUTES: [VertexAttribute; 4] = vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x3, 3 => Uint32];
+
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<Letter>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+// Atlas index of a base's glyph, whatever its case
+pub fn glyph_index(base: u8) -> u32 {
+    let base = base.to_ascii_uppercase();
+    ALPHABET.iter().position(|&letter| letter == base).unwrap_or(ALPHABET.len() - 1) as u32
+}
+
+// The alphabet's glyphs side by side as R8 texels, top row first: 255 where a glyph is lit
+pub fn atlas() -> Vec<u8> {
+    let width = ALPHABET.len() * GLYPH_WIDTH as usize;
+    let mut texels = vec![0; width * GLYPH_HEIGHT as usize];
+    for (i, &letter) in ALPHABET.iter().enumerate() {
+        for (row, bits) in glyph(letter as char).into_iter().enumerate() {
+            for col in 0..GLYPH_WIDTH as usize {
+                if bits & (1 << (GLYPH_WIDTH as usize - 1 - col)) != 0 {
+                    texels[row * width + i * GLYPH_WIDTH as usize + col] = 255;
+                }
+            }
+        }
+    }
+    texels
+}
+
+// Dark or light, whichever stands out on a cell of this color
+pub fn contrasting(background: [f32; 3]) -> [f32; 3] {
+    let luminance = 0.2126 * background[0] + 0.7152 * background[1] + 0.0722 * background[2];
+    if luminance > 0.4 { DARK } else { LIGHT }
+}
+
+// A letter centered in each visible cell `base` gives a base and the cell's color for,
+// with font pixels a whole number of physical `pixel`s so the glyphs stay crisp; none while
+// the cells are smaller than MIN_CELL_PIXELS
+pub fn layout(geometry: &GridGeometry, rows: Range<u32>, cols: Range<u32>, pixel: [f32; 2], mut base: impl FnMut(u32, u32) -> Option<(u8, [f32; 3])>) -> Vec<Letter> {
+    let cell_pixels = (geometry.cell[0] / pixel[0]).min(geometry.cell[1] / pixel[1]);
+    if cell_pixels < MIN_CELL_PIXELS {
+        return Vec::new();
+    }
+    let scale = (cell_pixels * LETTER_SHARE / GLYPH_HEIGHT as f32).floor().max(1.0);
+    let size = [GLYPH_WIDTH as f32 * scale * pixel[0], GLYPH_HEIGHT as f32 * scale * pixel[1]];
+    // Offset from the cell's corner, on whole pixels
+    let inset = [0, 1].map(|axis| ((geometry.cell[axis] - size[axis]) / 2.0 / pixel[axis]).floor() * pixel[axis]);
+    let mut letters = Vec::new();
+    for row in rows {
+        for col in cols.clone() {
+            let Some((base, background)) = base(row, col) else { continue };
+            let [x, y] = geometry.cell_origin(row, col);
+            letters.push(Letter { origin: [x + inset[0], y + inset[1]], size, color: contrasting(background), glyph: glyph_index(base) });
+            if letters.len() == MAX_LETTERS {
+                return letters;
+            }
+        }
+    }
+    letters
+}
+
+pub struct LetterView {
+    pipeline: RenderPipeline,
+    bind_group: BindGroup,
+    instances: Buffer,
+    count: u32,
+    // Vertices of the grid geometry drawn before the letters: the cells and their annotations
+    pub after: u32,
+}
+
+impl LetterView {
+    pub fn new(gpu: &Gpu, format: TextureFormat) -> Self {
+        let size = Extent3d { width: ALPHABET.len() as u32 * GLYPH_WIDTH, height: GLYPH_HEIGHT, depth_or_array_layers: 1 };
+        let texture = gpu.device.create_texture_with_data(
+            &gpu.queue,
+            &TextureDescriptor {
+                label: Some("Letter Atlas"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::R8Unorm,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+                view_formats: &[],
+            },
+            util::TextureDataOrder::LayerMajor,
+            &atlas(),
+        );
+
+        let bind_group_layout = gpu.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Letter Bind Group Layout"),
+            entries: &[BindGroupLayoutEntry {
+                binding: 0,
+                visibility: ShaderStages::FRAGMENT,
+                ty: BindingType::Texture {
+                    sample_type: TextureSampleType::Float { filterable: false },
+                    view_dimension: TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = gpu.device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Letter Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: BindingResource::TextureView(&texture.create_view(&TextureViewDescriptor::default())),
+            }],
+        });
+
+        let shader = gpu.device.create_shader_module(include_wgsl!("letters.wgsl"));
+        let pipeline_layout = gpu.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Letter Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = gpu.device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Letter Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[Letter::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::REPLACE),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let instances = gpu.device.create_buffer(&BufferDescriptor {
+            label: Some("Letter Instance Buffer"),
+            size: std::mem::size_of::<Letter>() as BufferAddress,
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        Self { pipeline, bind_group, instances, count: 0, after: 0 }
+    }
+
+    // Letters to draw after the first `after` vertices of the grid geometry
+    pub fn upload(&mut self, gpu: &Gpu, letters: &[Letter], after: u32) {
+        let contents: &[u8] = bytemuck::cast_slice(letters);
+        if contents.len() as BufferAddress > self.instances.size() {
+            self.instances = gpu.device.create_buffer_init(&util::BufferInitDescriptor {
+                label: Some("Letter Instance Buffer"),
+                contents,
+                usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+            });
+        } else if !contents.is_empty() {
+            gpu.queue.write_buffer(&self.instances, 0, contents);
+        }
+        self.count = letters.len() as u32;
+        self.after = after;
+    }
+
+    pub fn draw(&self, render_pass: &mut RenderPass) {
+        if self.count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instances.slice(..));
+        render_pass.draw(0..6, 0..self.count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_appear_in_big_enough_cells() {
+        // The atlas holds each glyph as the overlay font draws it
+        let texels = atlas();
+        let width = ALPHABET.len() * GLYPH_WIDTH as usize;
+        assert_eq!(texels.len(), width * GLYPH_HEIGHT as usize);
+        // The top row of C (.###.), third glyph in
+        assert_eq!(&texels[5..10], &[0, 255, 255, 255, 0]);
+        assert_eq!((glyph_index(b'g'), glyph_index(b'N'), glyph_index(b'R')), (2, 5, 6));
+
+        // 4x4 cells of 30 pixels, one pixel being 0.01 across
+        let geometry = GridGeometry { origin: [-1.0; 2], cell: [0.3; 2], pitch: [0.32; 2] };
+        let pixel = [0.01; 2];
+        let letters = layout(&geometry, 0..2, 0..2, pixel, |row, col| (col > 0).then_some((b'A', [0.2, 0.8, 0.2 * row as f32])));
+        assert_eq!(letters.len(), 2);
+        // Font pixels 2 pixels across, the most that fit 7 rows in 60% of 30 pixels, centered
+        // on whole pixels
+        let letter = letters[0];
+        assert!((letter.size[0] - 0.1).abs() < 1e-6 && (letter.size[1] - 0.14).abs() < 1e-6);
+        let [x, y] = geometry.cell_origin(0, 1);
+        assert!((letter.origin[0] - (x + 0.1)).abs() < 1e-6 && (letter.origin[1] - (y + 0.08)).abs() < 1e-6);
+        assert_eq!((letter.color, letter.glyph), (DARK, 0));
+        assert_eq!(contrasting([0.1; 3]), LIGHT);
+
+        // 20-pixel cells are too small to read letters in
+        let small = GridGeometry { cell: [0.2; 2], ..geometry };
+        assert!(layout(&small, 0..2, 0..2, pixel, |_, _| Some((b'A', [0.0; 3]))).is_empty());
+    }
+}
@@ -0,0 +1,50 @@
+// Base letters drawn inside the cells, one instance per cell: a quad over the letter's box
+// whose fragments look their glyph's pixel up in the atlas
+const GLYPH_WIDTH: u32 = 5u;
+const GLYPH_HEIGHT: u32 = 7u;
+
+// The glyphs side by side, each GLYPH_WIDTH texels wide, top row first
+@group(0) @binding(0) var atlas: texture_2d<f32>;
+
+struct Letter {
+  // Bottom-left corner and size of the letter's box in clip space
+  @location(0) origin: vec2<f32>,
+  @location(1) size: vec2<f32>,
+  @location(2) color: vec3<f32>,
+  // Glyph index in the atlas
+  @location(3) glyph: u32,
+};
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  // Position in the letter's box, 0 to 1 from its bottom-left corner
+  @location(0) uv: vec2<f32>,
+  @location(1) color: vec3<f32>,
+  @location(2) @interpolate(flat) glyph: u32,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32, letter: Letter) -> VertexOutput {
+  var corners = array<vec2<f32>, 6>(
+    vec2<f32>(0.0, 0.0), vec2<f32>(1.0, 0.0), vec2<f32>(0.0, 1.0),
+    vec2<f32>(0.0, 1.0), vec2<f32>(1.0, 0.0), vec2<f32>(1.0, 1.0),
+  );
+
+  var out: VertexOutput;
+  out.uv = corners[index];
+  out.clip_position = vec4<f32>(letter.origin + out.uv * letter.size, 0.0, 1.0);
+  out.color = letter.color;
+  out.glyph = letter.glyph;
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  let pixel = min(vec2<u32>(in.uv * vec2<f32>(f32(GLYPH_WIDTH), f32(GLYPH_HEIGHT))), vec2<u32>(GLYPH_WIDTH - 1u, GLYPH_HEIGHT - 1u));
+  // The atlas starts at the glyphs' top row, the box at its bottom
+  let texel = vec2<u32>(in.glyph * GLYPH_WIDTH + pixel.x, GLYPH_HEIGHT - 1u - pixel.y);
+  if (textureLoad(atlas, texel, 0).r < 0.5) {
+    discard;
+  }
+  return vec4<f32>(in.color, 1.0);
+}
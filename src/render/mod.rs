@@ -0,0 +1,15 @@
+pub mod chart;
+#[cfg(feature = "gui")]
+pub mod grid_resources;
+#[cfg(feature = "gui")]
+pub mod heightfield;
+#[cfg(feature = "gui")]
+pub mod letters;
+pub mod panel;
+#[cfg(feature = "gui")]
+pub mod post;
+#[cfg(feature = "gui")]
+pub mod profiler;
+pub mod text;
+#[cfg(feature = "gui")]
+pub mod torus;
@@ -0,0 +1,92 @@
+use crate::render::text::{ADVANCE, GLYPH_HEIGHT, font_pixel, push_text};
+use crate::vertex::{Vertex, push_rect};
+use crate::viewport::Viewport;
+
+// Font pixels between the panel's edge and its text, and between lines
+const MARGIN: u32 = 4;
+const LINE_SPACING: u32 = 4;
+
+// Lines of colored text on a framed dark panel in the top-left corner of the window, drawn
+// over everything else, e.g. the help overlay
+pub fn create_panel_vertices(lines: &[(String, [f32; 3])], viewport: &Viewport, text_scale: u32) -> Vec<Vertex> {
+    let pixel = font_pixel(viewport, text_scale);
+    panel_at(lines, [-1.0 + MARGIN as f32 * pixel[0], 1.0 - MARGIN as f32 * pixel[1]], pixel)
+}
+
+// Line of the top-left panel of `lines` under a point in clip space, e.g. to click a row
+pub fn panel_line_at(lines: &[(String, [f32; 3])], viewport: &Viewport, text_scale: u32, [x, y]: [f32; 2]) -> Option<usize> {
+    let pixel = font_pixel(viewport, text_scale);
+    let [width, _] = panel_size(lines);
+    let (left, top) = (-1.0 + MARGIN as f32 * pixel[0], 1.0 - MARGIN as f32 * pixel[1]);
+    if x < left || x > left + width as f32 * pixel[0] {
+        return None;
+    }
+    // Each line owns half the spacing above and below it
+    let offset = (top - y) / pixel[1] - MARGIN as f32 + LINE_SPACING as f32 / 2.0;
+    let line = (offset / (GLYPH_HEIGHT + LINE_SPACING) as f32).floor();
+    (line >= 0.0 && (line as usize) < lines.len()).then_some(line as usize)
+}
+
+// The same panel as a popup with its top-left corner at `anchor` (clip space), moved left
+// or down as far as it takes to fit in the window, e.g. a note shown on hover
+pub fn create_popup_vertices(lines: &[(String, [f32; 3])], anchor: [f32; 2], viewport: &Viewport, text_scale: u32) -> Vec<Vertex> {
+    let pixel = font_pixel(viewport, text_scale);
+    let [width, height] = panel_size(lines).map(|size| size as f32);
+    let left = anchor[0].min(1.0 - (width + MARGIN as f32) * pixel[0]).max(-1.0);
+    let top = anchor[1].max(-1.0 + (height + MARGIN as f32) * pixel[1]).min(1.0);
+    panel_at(lines, [left, top], pixel)
+}
+
+// Font pixels the panel of `lines` takes
+fn panel_size(lines: &[(String, [f32; 3])]) -> [u32; 2] {
+    let columns = lines.iter().map(|(text, _)| text.chars().count()).max().unwrap_or(0) as u32;
+    [columns * ADVANCE + 2 * MARGIN, lines.len() as u32 * (GLYPH_HEIGHT + LINE_SPACING) + 2 * MARGIN - LINE_SPACING]
+}
+
+fn panel_at(lines: &[(String, [f32; 3])], [left, top]: [f32; 2], pixel: [f32; 2]) -> Vec<Vertex> {
+    let line_height = GLYPH_HEIGHT + LINE_SPACING;
+    let size = panel_size(lines);
+    let (right, bottom) = (left + size[0] as f32 * pixel[0], top - size[1] as f32 * pixel[1]);
+
+    let mut vertices = Vec::new();
+    push_rect(&mut vertices, [left - pixel[0] / 2.0, bottom - pixel[1] / 2.0], [right + pixel[0] / 2.0, top + pixel[1] / 2.0], [0.4, 0.4, 0.5]);
+    push_rect(&mut vertices, [left, bottom], [right, top], [0.03, 0.03, 0.06]);
+    for (i, (text, color)) in lines.iter().enumerate() {
+        let baseline = top - (MARGIN + GLYPH_HEIGHT + i as u32 * line_height) as f32 * pixel[1];
+        push_text(&mut vertices, text, [left + MARGIN as f32 * pixel[0], baseline], pixel, *color);
+    }
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panel_fits_its_longest_line() {
+        let viewport = Viewport { width: 800, height: 600, scale_factor: 1.0 };
+        let lines = [("Keys".to_string(), [1.0; 3]), ("F1 / ?  Help".to_string(), [1.0; 3])];
+        let vertices = create_panel_vertices(&lines, &viewport, 1);
+        let right = vertices.iter().map(|vertex| vertex.position[0]).fold(f32::MIN, f32::max);
+        let bottom = vertices.iter().map(|vertex| vertex.position[1]).fold(f32::MAX, f32::min);
+        // 12 columns, the margins on both sides and half a pixel of frame
+        let pixel = font_pixel(&viewport, 1);
+        assert!((right - (-1.0 + (4 + 12 * ADVANCE + 8) as f32 * pixel[0] + pixel[0] / 2.0)).abs() < 1e-5);
+        assert!(bottom > 0.0);
+        assert!(create_panel_vertices(&[], &viewport, 1).len() < vertices.len());
+        let doubled = create_panel_vertices(&lines, &viewport, 2);
+        assert!(doubled.iter().map(|vertex| vertex.position[0]).fold(f32::MIN, f32::max) > right);
+
+        // A popup near the right edge moves left to stay in the window
+        let popup = create_popup_vertices(&lines, [0.99, 0.0], &viewport, 1);
+        assert!(popup.iter().all(|vertex| vertex.position[0] <= 1.0 && vertex.position[1] >= -1.0));
+
+        // Clicks find the line whose text they land on
+        let baseline = |i: u32| 1.0 - (4 + 4 + GLYPH_HEIGHT + i * (GLYPH_HEIGHT + 4)) as f32 * pixel[1];
+        let left = -1.0 + 10.0 * pixel[0];
+        assert_eq!(panel_line_at(&lines, &viewport, 1, [left, baseline(0) + pixel[1]]), Some(0));
+        assert_eq!(panel_line_at(&lines, &viewport, 1, [left, baseline(1) + pixel[1]]), Some(1));
+        assert_eq!(panel_line_at(&lines, &viewport, 1, [left, baseline(2) + pixel[1]]), None);
+        assert_eq!(panel_line_at(&lines, &viewport, 1, [right + pixel[0], baseline(0) + pixel[1]]), None);
+    }
+}
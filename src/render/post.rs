@@ -0,0 +1,288 @@
+use wgpu::*;
+use wgpu::util::DeviceExt;
+
+use crate::gpu::Gpu;
+use crate::render::profiler::GpuProfiler;
+
+// Optional effects applied after the grid is drawn. The frame is rendered into an offscreen
+// texture, then each enabled effect runs as a full-screen pass and the last pass writes the
+// window's surface.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Effects {
+    // Blend each frame with the faded previous result, leaving trails behind moving cells
+    pub trails: bool,
+    // Glow around bright (alive) cells
+    pub bloom: bool,
+}
+
+impl Effects {
+    pub fn any(&self) -> bool {
+        self.trails || self.bloom
+    }
+
+    pub fn set(&mut self, effect: Effect, enabled: bool) {
+        match effect {
+            Effect::Trails => self.trails = enabled,
+            Effect::Bloom => self.bloom = enabled,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Effect {
+    Trails,
+    Bloom,
+}
+
+impl std::str::FromStr for Effect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "trails" => Ok(Effect::Trails),
+            "bloom" => Ok(Effect::Bloom),
+            _ => Err(format!("Unknown effect '{s}', expected 'trails' or 'bloom'")),
+        }
+    }
+}
+
+impl std::fmt::Display for Effect {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Effect::Trails => write!(f, "trails"),
+            Effect::Bloom => write!(f, "bloom"),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Params {
+    texel: [f32; 2],
+    decay: f32,
+    bloom: f32,
+}
+
+// Share of the previous frame kept by the trail pass, and the bloom strength
+const TRAIL_DECAY: f32 = 0.92;
+const BLOOM_STRENGTH: f32 = 1.5;
+
+pub struct PostProcess {
+    pub effects: Effects,
+    // The grid and overlays are drawn here instead of the surface
+    scene: TextureView,
+    // Trail results, alternating between reading the previous frame and writing the new one
+    history: [TextureView; 2],
+    current_history: usize,
+    params: Buffer,
+    sampler: Sampler,
+    bind_group_layout: BindGroupLayout,
+    copy: RenderPipeline,
+    trail: RenderPipeline,
+    bloom: RenderPipeline,
+}
+
+impl PostProcess {
+    pub fn new(gpu: &Gpu, format: TextureFormat, width: u32, height: u32, effects: Effects) -> Self {
+        let bind_group_layout = gpu.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Post Bind Group Layout"),
+            entries: &[
+                texture_entry(0),
+                texture_entry(1),
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let shader = gpu.device.create_shader_module(include_wgsl!("post.wgsl"));
+        let pipeline_layout = gpu.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Post Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| {
+            gpu.device.create_render_pipeline(&RenderPipelineDescriptor {
+                label: Some("Post Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    targets: &[Some(ColorTargetState {
+                        format,
+                        blend: Some(BlendState::REPLACE),
+                        write_mask: ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        };
+
+        let params = gpu.device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Post Params Buffer"),
+            contents: bytemuck::bytes_of(&Params::new(width, height)),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+        let sampler = gpu.device.create_sampler(&SamplerDescriptor {
+            label: Some("Post Sampler"),
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..Default::default()
+        });
+
+        Self {
+            effects,
+            scene: target(gpu, format, width, height),
+            history: [target(gpu, format, width, height), target(gpu, format, width, height)],
+            current_history: 0,
+            params,
+            sampler,
+            bind_group_layout,
+            copy: pipeline("fs_copy"),
+            trail: pipeline("fs_trail"),
+            bloom: pipeline("fs_bloom"),
+        }
+    }
+
+    // Recreate the offscreen targets at the new size; trails start over
+    pub fn resize(&mut self, gpu: &Gpu, format: TextureFormat, width: u32, height: u32) {
+        self.scene = target(gpu, format, width, height);
+        self.history = [target(gpu, format, width, height), target(gpu, format, width, height)];
+        gpu.queue.write_buffer(&self.params, 0, bytemuck::bytes_of(&Params::new(width, height)));
+    }
+
+    // Where the frame should be drawn before `apply`
+    pub fn scene(&self) -> &TextureView {
+        &self.scene
+    }
+
+    // Run the enabled effects on the scene and write the result to `output`
+    pub fn apply(&mut self, gpu: &Gpu, encoder: &mut CommandEncoder, output: &TextureView, profiler: Option<&GpuProfiler>) {
+        let mut source = &self.scene;
+
+        if self.effects.trails {
+            let previous = self.current_history;
+            self.current_history = 1 - previous;
+            let written = &self.history[self.current_history];
+            self.pass(gpu, encoder, &self.trail, [source, &self.history[previous]], written, profiler.and_then(|profiler| profiler.render_writes("trails")));
+            source = written;
+        }
+
+        let (last, label) = if self.effects.bloom { (&self.bloom, "bloom") } else { (&self.copy, "post copy") };
+        self.pass(gpu, encoder, last, [source, source], output, profiler.and_then(|profiler| profiler.render_writes(label)));
+    }
+
+    // `inputs` are the current frame and the previous one
+    fn pass(&self, gpu: &Gpu, encoder: &mut CommandEncoder, pipeline: &RenderPipeline, [current, previous]: [&TextureView; 2], output: &TextureView, timestamp_writes: Option<RenderPassTimestampWrites>) {
+        let bind_group = gpu.device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Post Bind Group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                BindGroupEntry { binding: 0, resource: BindingResource::TextureView(current) },
+                BindGroupEntry { binding: 1, resource: BindingResource::TextureView(previous) },
+                BindGroupEntry { binding: 2, resource: BindingResource::Sampler(&self.sampler) },
+                BindGroupEntry { binding: 3, resource: self.params.as_entire_binding() },
+            ],
+        });
+
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Post Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: output,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: StoreOp::Store,
+                },
+            })],
+            timestamp_writes,
+            ..Default::default()
+        });
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+impl Params {
+    fn new(width: u32, height: u32) -> Self {
+        Self { texel: [1.0 / width as f32, 1.0 / height as f32], decay: TRAIL_DECAY, bloom: BLOOM_STRENGTH }
+    }
+}
+
+fn texture_entry(binding: u32) -> BindGroupLayoutEntry {
+    BindGroupLayoutEntry {
+        binding,
+        visibility: ShaderStages::FRAGMENT,
+        ty: BindingType::Texture {
+            sample_type: TextureSampleType::Float { filterable: true },
+            view_dimension: TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+// Offscreen color target that later passes can sample
+fn target(gpu: &Gpu, format: TextureFormat, width: u32, height: u32) -> TextureView {
+    gpu.device
+        .create_texture(&TextureDescriptor {
+            label: Some("Post Target"),
+            size: Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+        .create_view(&TextureViewDescriptor::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effects_toggle_independently() {
+        let mut effects = Effects::default();
+        assert!(!effects.any());
+        effects.set("trails".parse().unwrap(), true);
+        effects.set(Effect::Bloom, true);
+        effects.set(Effect::Trails, false);
+        assert_eq!(effects, Effects { trails: false, bloom: true });
+        assert!("blur".parse::<Effect>().is_err());
+    }
+
+    #[test]
+    fn shader_validates() {
+        let module = naga::front::wgsl::parse_str(include_str!("post.wgsl")).unwrap();
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+            .validate(&module)
+            .unwrap();
+    }
+}
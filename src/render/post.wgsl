@@ -0,0 +1,62 @@
+// Full-screen passes of the post-processing chain, all sharing one bind group layout
+struct Params {
+  // Size of one pixel in texture coordinates
+  texel: vec2<f32>,
+  // How much of the previous frame survives each frame in the trail pass
+  decay: f32,
+  // Strength of the bloom glow
+  bloom: f32,
+}
+
+@group(0) @binding(0) var current: texture_2d<f32>;
+@group(0) @binding(1) var previous: texture_2d<f32>;
+@group(0) @binding(2) var linear_sampler: sampler;
+@group(0) @binding(3) var<uniform> params: Params;
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+};
+
+// One triangle covering the whole target
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+  let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+  var out: VertexOutput;
+  out.clip_position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+  out.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+  return out;
+}
+
+@fragment
+fn fs_copy(in: VertexOutput) -> @location(0) vec4<f32> {
+  return textureSample(current, linear_sampler, in.uv);
+}
+
+// Phosphor-style persistence: the previous result fades out unless the new frame is brighter.
+// The small constant makes faded pixels reach zero instead of sticking at the lowest 8-bit step.
+@fragment
+fn fs_trail(in: VertexOutput) -> @location(0) vec4<f32> {
+  let now = textureSample(current, linear_sampler, in.uv).rgb;
+  let before = textureSample(previous, linear_sampler, in.uv).rgb;
+  let faded = max(before * params.decay - vec3<f32>(1.0 / 255.0), vec3<f32>(0.0));
+  return vec4<f32>(max(now, faded), 1.0);
+}
+
+// Glow around bright pixels: a blurred copy of everything above the threshold is added back
+@fragment
+fn fs_bloom(in: VertexOutput) -> @location(0) vec4<f32> {
+  let color = textureSample(current, linear_sampler, in.uv).rgb;
+  var glow = vec3<f32>(0.0);
+  var total = 0.0;
+  for (var x = -3; x <= 3; x++) {
+    for (var y = -3; y <= 3; y++) {
+      let offset = vec2<f32>(f32(x), f32(y)) * 2.5;
+      let weight = exp(-dot(offset, offset) / 32.0);
+      let tap = textureSample(current, linear_sampler, in.uv + offset * params.texel).rgb;
+      glow += max(tap - vec3<f32>(0.4), vec3<f32>(0.0)) * weight;
+      total += weight;
+    }
+  }
+  return vec4<f32>(color + glow / total * params.bloom, 1.0);
+}
@@ -0,0 +1,192 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use wgpu::*;
+
+// Profiler overlay (`profile on`, F3): how long each GPU pass and each part of the CPU frame
+// takes, averaged over recent frames, to tell which render feature a slowdown comes from.
+// GPU passes are timed with timestamp queries written at their start and end. A frame's
+// queries are resolved into a buffer that is read back without waiting, so results arrive a
+// frame or two late, and no passes are timed while a readback is in flight. Adapters without
+// timestamp queries only get the CPU breakdown.
+
+// Passes timed per frame at most, two queries each
+const MAX_PASSES: u32 = 16;
+// Weight of the newest sample in the running averages
+const SMOOTHING: f64 = 0.1;
+
+// Running averages of named durations in milliseconds, in the order they were first seen
+#[derive(Debug, Default)]
+pub struct Timings {
+    entries: Vec<(&'static str, f64)>,
+}
+
+impl Timings {
+    pub fn record(&mut self, label: &'static str, duration: Duration) {
+        let ms = duration.as_secs_f64() * 1000.0;
+        match self.entries.iter_mut().find(|(name, _)| *name == label) {
+            Some((_, average)) => *average += (ms - *average) * SMOOTHING,
+            None => self.entries.push((label, ms)),
+        }
+    }
+
+    pub fn entries(&self) -> &[(&'static str, f64)] {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+struct Frame {
+    enabled: bool,
+    // Passes given queries since the last resolve, in query order
+    passes: Vec<&'static str>,
+    // Passes whose timestamps are being read back, and whether the readback was requested
+    in_flight: Option<(Vec<&'static str>, bool)>,
+    timings: Timings,
+}
+
+pub struct GpuProfiler {
+    query_set: QuerySet,
+    resolve: Buffer,
+    readback: Buffer,
+    // Nanoseconds per timestamp tick
+    period: f64,
+    // Outcome of the readback's mapping, once it is known
+    mapped: Arc<Mutex<Option<bool>>>,
+    frame: Mutex<Frame>,
+}
+
+impl GpuProfiler {
+    // None when the device wasn't created with timestamp queries
+    pub fn new(device: &Device, queue: &Queue) -> Option<Self> {
+        if !device.features().contains(Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let count = MAX_PASSES * 2;
+        let query_set = device.create_query_set(&QuerySetDescriptor { label: Some("Profiler Queries"), ty: QueryType::Timestamp, count });
+        let size = count as BufferAddress * QUERY_SIZE as BufferAddress;
+        let resolve = device.create_buffer(&BufferDescriptor {
+            label: Some("Profiler Resolve Buffer"),
+            size,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = device.create_buffer(&BufferDescriptor {
+            label: Some("Profiler Readback Buffer"),
+            size,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let frame = Frame { enabled: false, passes: Vec::new(), in_flight: None, timings: Timings::default() };
+        Some(Self { query_set, resolve, readback, period: f64::from(queue.get_timestamp_period()), mapped: Arc::default(), frame: Mutex::new(frame) })
+    }
+
+    fn frame(&self) -> std::sync::MutexGuard<'_, Frame> {
+        self.frame.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Passes are only timed while the overlay is shown
+    pub fn set_enabled(&self, enabled: bool) {
+        let mut frame = self.frame();
+        frame.enabled = enabled;
+        frame.passes.clear();
+        frame.timings.clear();
+    }
+
+    // First of the two queries of a pass, when it can be timed
+    fn begin(&self, label: &'static str) -> Option<u32> {
+        let mut frame = self.frame();
+        if !frame.enabled || frame.in_flight.is_some() || frame.passes.len() as u32 == MAX_PASSES {
+            return None;
+        }
+        frame.passes.push(label);
+        Some((frame.passes.len() as u32 - 1) * 2)
+    }
+
+    pub fn render_writes(&self, label: &'static str) -> Option<RenderPassTimestampWrites<'_>> {
+        let first = self.begin(label)?;
+        Some(RenderPassTimestampWrites { query_set: &self.query_set, beginning_of_pass_write_index: Some(first), end_of_pass_write_index: Some(first + 1) })
+    }
+
+    pub fn compute_writes(&self, label: &'static str) -> Option<ComputePassTimestampWrites<'_>> {
+        let first = self.begin(label)?;
+        Some(ComputePassTimestampWrites { query_set: &self.query_set, beginning_of_pass_write_index: Some(first), end_of_pass_write_index: Some(first + 1) })
+    }
+
+    // Copy the timestamps of the passes so far to the readback buffer, at the end of the
+    // frame's last encoder
+    pub fn resolve(&self, encoder: &mut CommandEncoder) {
+        let mut frame = self.frame();
+        if frame.passes.is_empty() || frame.in_flight.is_some() {
+            return;
+        }
+        let count = frame.passes.len() as u32 * 2;
+        encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve, 0, &self.readback, 0, count as BufferAddress * QUERY_SIZE as BufferAddress);
+        frame.in_flight = Some((std::mem::take(&mut frame.passes), false));
+    }
+
+    // Start reading back what `resolve` copied, once its encoder has been submitted
+    pub fn submitted(&self) {
+        let mut frame = self.frame();
+        let Some((_, requested @ false)) = frame.in_flight.as_mut() else {
+            return;
+        };
+        *requested = true;
+        let mapped = Arc::clone(&self.mapped);
+        self.readback.slice(..).map_async(MapMode::Read, move |result| {
+            if let Err(err) = &result {
+                tracing::warn!("Failed to read back GPU timestamps: {err}");
+            }
+            *mapped.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(result.is_ok());
+        });
+    }
+
+    // Fold in the timestamps that have been read back since the last call
+    pub fn collect(&self, device: &Device) {
+        device.poll(Maintain::Poll);
+        let Some(ok) = self.mapped.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take() else {
+            return;
+        };
+        let mut frame = self.frame();
+        let Some((passes, _)) = frame.in_flight.take() else {
+            return;
+        };
+        if !ok {
+            return;
+        }
+        let ticks: Vec<u64> = bytemuck::cast_slice(&self.readback.slice(..).get_mapped_range()).to_vec();
+        self.readback.unmap();
+        for (label, pair) in passes.into_iter().zip(ticks.chunks_exact(2)) {
+            let nanos = pair[1].saturating_sub(pair[0]) as f64 * self.period;
+            frame.timings.record(label, Duration::from_nanos(nanos as u64));
+        }
+    }
+
+    // Average time of each pass, in milliseconds
+    pub fn timings(&self) -> Vec<(&'static str, f64)> {
+        self.frame().timings.entries().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn averages_each_label_in_first_seen_order() {
+        let mut timings = Timings::default();
+        timings.record("grid", Duration::from_millis(2));
+        timings.record("bloom", Duration::from_millis(1));
+        timings.record("grid", Duration::from_millis(12));
+        assert_eq!(timings.entries().len(), 2);
+        assert_eq!(timings.entries()[0].0, "grid");
+        assert!((timings.entries()[0].1 - 3.0).abs() < 1e-9);
+        assert!((timings.entries()[1].1 - 1.0).abs() < 1e-9);
+        timings.clear();
+        assert!(timings.entries().is_empty());
+    }
+}
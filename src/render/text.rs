@@ -0,0 +1,119 @@
+use crate::vertex::{Vertex, push_rect};
+use crate::viewport::Viewport;
+
+// Glyphs are 5x7 font pixels, drawn one character every ADVANCE font pixels
+pub const GLYPH_WIDTH: u32 = 5;
+pub const GLYPH_HEIGHT: u32 = 7;
+pub const ADVANCE: u32 = GLYPH_WIDTH + 1;
+
+// Rows of a glyph top to bottom, the highest of the 5 bits being the leftmost pixel.
+// Letters are upper case only (lower case is drawn with them), plus digits and a little
+// punctuation; anything else is drawn as '?'.
+pub fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        'A' => [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'B' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110],
+        'C' => [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110],
+        'D' => [0b11110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b11110],
+        'E' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111],
+        'F' => [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000],
+        'G' => [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111],
+        'H' => [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001],
+        'I' => [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        'J' => [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100],
+        'K' => [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001],
+        'L' => [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111],
+        'M' => [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001],
+        'N' => [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001],
+        'O' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'P' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000],
+        'Q' => [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101],
+        'R' => [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001],
+        'S' => [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110],
+        'T' => [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100],
+        'U' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110],
+        'V' => [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100],
+        'W' => [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010],
+        'X' => [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001],
+        'Y' => [0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100, 0b00100],
+        'Z' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111],
+        '0' => [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110],
+        '1' => [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110],
+        '2' => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111],
+        '3' => [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110],
+        '4' => [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010],
+        '5' => [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110],
+        '6' => [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110],
+        '7' => [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000],
+        '8' => [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110],
+        '9' => [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100],
+        '-' => [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000],
+        '.' => [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100],
+        ':' => [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000],
+        ',' => [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000],
+        '\'' => [0b01100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000],
+        '`' => [0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000],
+        '+' => [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000],
+        '/' => [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000],
+        '%' => [0b11001, 0b11010, 0b00010, 0b00100, 0b01000, 0b01011, 0b10011],
+        '(' => [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010],
+        ')' => [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000],
+        ' ' => [0; 7],
+        _ => [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100],
+    }
+}
+
+// Clip-space size of one font pixel: two physical pixels at 1x, so the letters stay legible,
+// and whole physical pixels at any scale, times the accessibility text scale
+pub fn font_pixel(viewport: &Viewport, text_scale: u32) -> [f32; 2] {
+    let scale = (viewport.scale_factor * 2.0).round().max(1.0) as f32 * text_scale as f32;
+    viewport.pixel().map(|pixel| pixel * scale)
+}
+
+// Draw `text` from its bottom-left corner at `origin` in clip space, with each font pixel
+// `pixel` wide and high. Runs of lit pixels in a glyph row become one quad, so text shares
+// the grid's triangle-list pipeline at a few quads per character.
+pub fn push_text(vertices: &mut Vec<Vertex>, text: &str, origin: [f32; 2], pixel: [f32; 2], color: [f32; 3]) {
+    for (i, c) in text.chars().enumerate() {
+        let left = origin[0] + (i as u32 * ADVANCE) as f32 * pixel[0];
+        for (row, bits) in glyph(c).into_iter().enumerate() {
+            let bottom = origin[1] + (GLYPH_HEIGHT - 1 - row as u32) as f32 * pixel[1];
+            let mut col = 0;
+            while col < GLYPH_WIDTH {
+                let lit = |col: u32| bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0;
+                if !lit(col) {
+                    col += 1;
+                    continue;
+                }
+                let start = col;
+                while col < GLYPH_WIDTH && lit(col) {
+                    col += 1;
+                }
+                let x = |col: u32| left + col as f32 * pixel[0];
+                push_rect(vertices, [x(start), bottom], [x(col), bottom + pixel[1]], color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_one_quad_per_pixel_run() {
+        let mut vertices = Vec::new();
+        // 'T' is a full top row and a six pixel stem
+        push_text(&mut vertices, "T", [0.0, 0.0], [1.0, 1.0], [1.0; 3]);
+        assert_eq!(vertices.len(), 7 * 6);
+        assert_eq!(vertices[1].position, [0.0, 6.0]);
+        assert_eq!(vertices[5].position, [5.0, 7.0]);
+
+        // Lower case is drawn as upper case, the second character one advance along
+        let (mut lower, mut upper) = (Vec::new(), Vec::new());
+        push_text(&mut lower, " acgtn", [0.0, 0.0], [1.0, 1.0], [1.0; 3]);
+        push_text(&mut upper, " ACGTN", [0.0, 0.0], [1.0, 1.0], [1.0; 3]);
+        assert_eq!(lower.len(), upper.len());
+        assert!(lower.iter().all(|vertex| vertex.position[0] >= ADVANCE as f32));
+    }
+}
@@ -0,0 +1,475 @@
+use std::f32::consts::{PI, TAU};
+
+use wgpu::*;
+use wgpu::util::DeviceExt;
+
+use crate::gpu::Gpu;
+use crate::grid_texture::cell_states;
+use crate::selection::Region;
+use crate::universe::Universe;
+
+// 3D torus view (`torus on`, O): the grid wraps around at its edges, so it is drawn on an
+// actual torus, with the columns running around the ring and the rows around the tube.
+// Gliders leaving one edge and coming back at the other then just keep going around. The
+// cells are looked up per fragment from an R8 texture as in the texture renderer, so the
+// mesh only needs enough segments to look round. Dragging rotates it like an arcball and
+// the wheel moves the camera closer or further.
+
+// Radius of the ring and of the tube around it
+const MAJOR_RADIUS: f32 = 1.0;
+const MINOR_RADIUS: f32 = 0.4;
+const MAJOR_SEGMENTS: u32 = 128;
+const MINOR_SEGMENTS: u32 = 48;
+const FIELD_OF_VIEW: f32 = PI / 4.0;
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+// How far the camera can move from the center, in ring radii
+const MIN_DISTANCE: f32 = 1.6;
+const MAX_DISTANCE: f32 = 12.0;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TorusVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    // Column and row of the grid as shares of its width and height
+    pub uv: [f32; 2],
+}
+
+impl TorusVertex {
+    const ATTRIBUTES: [VertexAttribute; 3] = vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2];
+
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<TorusVertex>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+// Torus around the z axis, as a grid of (major + 1) x (minor + 1) vertices and two triangles
+// per quad. The seam vertices are doubled, so u and v run from 0 to 1 without wrapping back.
+pub fn torus_mesh(major: u32, minor: u32, major_radius: f32, minor_radius: f32) -> (Vec<TorusVertex>, Vec<u32>) {
+    let mut vertices = Vec::with_capacity(((major + 1) * (minor + 1)) as usize);
+    for i in 0..=major {
+        let u = i as f32 / major as f32;
+        let (sin_theta, cos_theta) = (u * TAU).sin_cos();
+        for j in 0..=minor {
+            let v = j as f32 / minor as f32;
+            let (sin_phi, cos_phi) = (v * TAU).sin_cos();
+            let ring = major_radius + minor_radius * cos_phi;
+            vertices.push(TorusVertex {
+                position: [ring * cos_theta, ring * sin_theta, minor_radius * sin_phi],
+                normal: [cos_phi * cos_theta, cos_phi * sin_theta, sin_phi],
+                uv: [u, v],
+            });
+        }
+    }
+
+    let mut indices = Vec::with_capacity((major * minor * 6) as usize);
+    for i in 0..major {
+        for j in 0..minor {
+            let corner = |di: u32, dj: u32| (i + di) * (minor + 1) + j + dj;
+            indices.extend([corner(0, 0), corner(1, 0), corner(0, 1), corner(0, 1), corner(1, 0), corner(1, 1)]);
+        }
+    }
+    (vertices, indices)
+}
+
+pub type Mat3 = [[f32; 3]; 3];
+// Column-major, as WGSL reads a mat4x4
+pub type Mat4 = [[f32; 4]; 4];
+
+pub fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+pub fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let length = dot(a, a).sqrt();
+    a.map(|component| component / length)
+}
+
+// Rows of a, columns of b; matrices here are lists of columns
+fn mul3(a: &Mat3, b: &Mat3) -> Mat3 {
+    std::array::from_fn(|col| std::array::from_fn(|row| (0..3).map(|k| a[k][row] * b[col][k]).sum()))
+}
+
+fn mul4(a: &Mat4, b: &Mat4) -> Mat4 {
+    std::array::from_fn(|col| std::array::from_fn(|row| (0..4).map(|k| a[k][row] * b[col][k]).sum()))
+}
+
+// Rotation by `angle` around the unit vector `axis`
+fn axis_angle(axis: [f32; 3], angle: f32) -> Mat3 {
+    let (sin, cos) = angle.sin_cos();
+    let [x, y, z] = axis;
+    let t = 1.0 - cos;
+    [
+        [t * x * x + cos, t * x * y + sin * z, t * x * z - sin * y],
+        [t * x * y - sin * z, t * y * y + cos, t * y * z + sin * x],
+        [t * x * z + sin * y, t * y * z - sin * x, t * z * z + cos],
+    ]
+}
+
+// Perspective projection to WebGPU's clip space, depth 0 at `near` and 1 at `far`
+fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+    let f = 1.0 / (fov_y / 2.0).tan();
+    [[f / aspect, 0.0, 0.0, 0.0], [0.0, f, 0.0, 0.0], [0.0, 0.0, far / (near - far), -1.0], [0.0, 0.0, near * far / (near - far), 0.0]]
+}
+
+// The camera: the torus turned by `rotation` in front of a camera `distance` away on the z axis
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Arcball {
+    pub rotation: Mat3,
+    pub distance: f32,
+}
+
+impl Default for Arcball {
+    // Tilted back, so the hole and the outside of the ring both show
+    fn default() -> Self {
+        Self { rotation: axis_angle([1.0, 0.0, 0.0], -1.0), distance: 3.6 }
+    }
+}
+
+impl Arcball {
+    // A point of the window in clip space on the unit sphere in front of it; points outside
+    // the sphere's outline go to its edge
+    fn sphere_point([x, y]: [f32; 2]) -> [f32; 3] {
+        let squared = x * x + y * y;
+        if squared <= 1.0 { [x, y, (1.0 - squared).sqrt()] } else { normalize([x, y, 0.0]) }
+    }
+
+    // Turn the torus as if the point of the sphere under `from` were dragged to `to`
+    pub fn drag(&mut self, from: [f32; 2], to: [f32; 2]) {
+        let (a, b) = (Self::sphere_point(from), Self::sphere_point(to));
+        let axis = cross(a, b);
+        if dot(axis, axis) < 1e-12 {
+            return;
+        }
+        let angle = dot(a, b).clamp(-1.0, 1.0).acos();
+        self.rotation = mul3(&axis_angle(normalize(axis), angle), &self.rotation);
+        // Keep it a rotation as rounding errors add up over many drags
+        let x = normalize(self.rotation[0]);
+        let z = normalize(cross(x, self.rotation[1]));
+        self.rotation = [x, cross(z, x), z];
+    }
+
+    // Move the camera closer (negative) or further by a number of wheel lines
+    pub fn zoom(&mut self, lines: f32) {
+        self.distance = (self.distance * 1.1f32.powf(lines)).clamp(MIN_DISTANCE, MAX_DISTANCE);
+    }
+
+    pub fn view_projection(&self, aspect: f32) -> Mat4 {
+        let [x, y, z] = self.rotation;
+        let view = [[x[0], x[1], x[2], 0.0], [y[0], y[1], y[2], 0.0], [z[0], z[1], z[2], 0.0], [0.0, 0.0, -self.distance, 1.0]];
+        mul4(&perspective(FIELD_OF_VIEW, aspect, 0.1, self.distance + MAJOR_RADIUS + MINOR_RADIUS + 1.0), &view)
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+struct Camera {
+    view_projection: Mat4,
+    // Turns the normals for the lighting, which stays fixed while the torus turns
+    rotation: Mat4,
+    grid: [u32; 2],
+    padding: [u32; 2],
+}
+
+pub struct TorusView {
+    pipeline: RenderPipeline,
+    vertex_buffer: Buffer,
+    index_buffer: Buffer,
+    index_count: u32,
+    texture: Texture,
+    camera: Buffer,
+    bind_group: BindGroup,
+    depth: TextureView,
+    // Columns, rows
+    grid: [u32; 2],
+}
+
+impl TorusView {
+    // Fails for grids wider or taller than the largest texture the device has
+    pub fn new(gpu: &Gpu, format: TextureFormat, (rows, cols): (u32, u32), (width, height): (u32, u32)) -> Result<Self, String> {
+        let max = gpu.device.limits().max_texture_dimension_2d;
+        if rows > max || cols > max {
+            return Err(format!("The {rows}x{cols} grid is too large for the torus view, at most {max} cells a side"));
+        }
+
+        let (vertices, indices) = torus_mesh(MAJOR_SEGMENTS, MINOR_SEGMENTS, MAJOR_RADIUS, MINOR_RADIUS);
+        let vertex_buffer = gpu.device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Torus Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: BufferUsages::VERTEX,
+        });
+        let index_buffer = gpu.device.create_buffer_init(&util::BufferInitDescriptor {
+            label: Some("Torus Index Buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: BufferUsages::INDEX,
+        });
+
+        let texture = gpu.device.create_texture(&TextureDescriptor {
+            label: Some("Torus Cell Texture"),
+            size: Extent3d { width: cols, height: rows, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::R8Uint,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let camera = gpu.device.create_buffer(&BufferDescriptor {
+            label: Some("Torus Camera Buffer"),
+            size: std::mem::size_of::<Camera>() as BufferAddress,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = gpu.device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Torus Bind Group Layout"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Uint,
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::VERTEX_FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let bind_group = gpu.device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Torus Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&texture.create_view(&TextureViewDescriptor::default())),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: camera.as_entire_binding(),
+                },
+            ],
+        });
+
+        let shader = gpu.device.create_shader_module(include_wgsl!("torus.wgsl"));
+        let pipeline_layout = gpu.device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Torus Pipeline Layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = gpu.device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Torus Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[TorusVertex::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                targets: &[Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::REPLACE),
+                    write_mask: ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: Some(DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: StencilState::default(),
+                bias: DepthBiasState::default(),
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        Ok(Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            texture,
+            camera,
+            bind_group,
+            depth: depth_target(gpu, width, height),
+            grid: [cols, rows],
+        })
+    }
+
+    // Rows and columns the cell texture holds; another grid size needs a new view
+    pub fn grid(&self) -> (u32, u32) {
+        (self.grid[1], self.grid[0])
+    }
+
+    pub fn resize(&mut self, gpu: &Gpu, width: u32, height: u32) {
+        self.depth = depth_target(gpu, width, height);
+    }
+
+    pub fn upload(&self, gpu: &Gpu, universe: &Universe, selection: Option<Region>, camera: &Arcball, aspect: f32) {
+        let [cols, rows] = self.grid;
+        gpu.queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &cell_states(universe, selection, &Region::full(rows, cols)),
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(cols),
+                rows_per_image: Some(rows),
+            },
+            Extent3d { width: cols, height: rows, depth_or_array_layers: 1 },
+        );
+
+        let [x, y, z] = camera.rotation;
+        let rotation = [[x[0], x[1], x[2], 0.0], [y[0], y[1], y[2], 0.0], [z[0], z[1], z[2], 0.0], [0.0, 0.0, 0.0, 1.0]];
+        let uniform = Camera { view_projection: camera.view_projection(aspect), rotation, grid: self.grid, padding: [0; 2] };
+        gpu.queue.write_buffer(&self.camera, 0, bytemuck::bytes_of(&uniform));
+    }
+
+    // Clear `view` to `clear_color` and draw the torus; overlays are drawn over it afterwards
+    pub fn draw(&self, encoder: &mut CommandEncoder, view: &TextureView, clear_color: Color, timestamp_writes: Option<RenderPassTimestampWrites>) {
+        let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Torus Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(clear_color),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                view: &self.depth,
+                depth_ops: Some(Operations {
+                    load: LoadOp::Clear(1.0),
+                    store: StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes,
+            ..Default::default()
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint32);
+        render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+    }
+}
+
+// Depth buffer for a 3D view drawn at the window's size
+pub fn depth_target(gpu: &Gpu, width: u32, height: u32) -> TextureView {
+    gpu.device
+        .create_texture(&TextureDescriptor {
+            label: Some("Torus Depth Texture"),
+            size: Extent3d { width: width.max(1), height: height.max(1), depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+        .create_view(&TextureViewDescriptor::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn mesh_wraps_the_grid_around_a_torus() {
+        let (vertices, indices) = torus_mesh(8, 4, 1.0, 0.25);
+        assert_eq!(vertices.len(), 9 * 5);
+        assert_eq!(indices.len(), 8 * 4 * 6);
+        assert!(indices.iter().all(|&index| (index as usize) < vertices.len()));
+        for vertex in &vertices {
+            // Every vertex lies on the tube, with its normal pointing away from the ring
+            let [x, y, z] = vertex.position;
+            let ring = (x * x + y * y).sqrt();
+            assert!(close((ring - 1.0).powi(2) + z * z, 0.25 * 0.25));
+            assert!(close(dot(vertex.normal, vertex.normal), 1.0));
+        }
+        // The seam is doubled: the first and last columns of vertices sit in the same place
+        assert_eq!(vertices[0].uv, [0.0, 0.0]);
+        assert_eq!(vertices[vertices.len() - 1].uv, [1.0, 1.0]);
+        assert!(vertices[0].position.iter().zip(vertices[8 * 5].position).all(|(&a, b)| close(a, b)));
+    }
+
+    #[test]
+    fn arcball_rotates_and_stays_a_rotation() {
+        let mut camera = Arcball { rotation: axis_angle([0.0, 0.0, 1.0], 0.0), distance: 3.0 };
+        // Dragging right turns the front of the torus to the right, around the y axis
+        camera.drag([0.0, 0.0], [0.5, 0.0]);
+        let [x, _, z] = camera.rotation;
+        assert!(close(x[0], 0.75f32.sqrt()) && close(x[2], -0.5));
+        assert!(close(z[0], 0.5));
+        // Lots of drags later, it still neither scales nor shears
+        for step in 0..500 {
+            camera.drag([0.1, (step % 7) as f32 * 0.1], [-0.2, 0.3]);
+        }
+        let [x, y, z] = camera.rotation;
+        assert!(close(dot(x, x), 1.0) && close(dot(y, y), 1.0) && close(dot(x, y), 0.0));
+        assert!(close(dot(cross(x, y), z), 1.0));
+
+        camera.zoom(-100.0);
+        assert_eq!(camera.distance, MIN_DISTANCE);
+        camera.zoom(100.0);
+        assert_eq!(camera.distance, MAX_DISTANCE);
+    }
+
+    #[test]
+    fn whole_torus_is_in_view() {
+        let camera = Arcball::default();
+        let matrix = camera.view_projection(1.5);
+        let (vertices, _) = torus_mesh(16, 8, MAJOR_RADIUS, MINOR_RADIUS);
+        for vertex in vertices {
+            let [x, y, z] = vertex.position;
+            let clip: [f32; 4] = std::array::from_fn(|row| matrix[0][row] * x + matrix[1][row] * y + matrix[2][row] * z + matrix[3][row]);
+            let [x, y, depth] = [clip[0] / clip[3], clip[1] / clip[3], clip[2] / clip[3]];
+            assert!(x.abs() < 1.0 && y.abs() < 1.0 && (0.0..1.0).contains(&depth), "{x} {y} {depth}");
+        }
+    }
+
+    #[test]
+    fn shader_validates() {
+        let module = naga::front::wgsl::parse_str(include_str!("torus.wgsl")).unwrap();
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+            .validate(&module)
+            .unwrap();
+    }
+}
@@ -0,0 +1,49 @@
+// Draws the grid on a torus mesh, looking each cell's state up in an R8 texture
+struct Camera {
+  view_projection: mat4x4<f32>,
+  rotation: mat4x4<f32>,
+  // Columns, rows
+  grid: vec2<u32>,
+}
+
+@group(0) @binding(0) var cells: texture_2d<u32>;
+@group(0) @binding(1) var<uniform> camera: Camera;
+
+struct VertexInput {
+  @location(0) position: vec3<f32>,
+  @location(1) normal: vec3<f32>,
+  @location(2) uv: vec2<f32>,
+};
+
+struct VertexOutput {
+  @builtin(position) clip_position: vec4<f32>,
+  // Turned with the torus, for lighting from the camera's side
+  @location(0) normal: vec3<f32>,
+  @location(1) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+  var out: VertexOutput;
+  out.clip_position = camera.view_projection * vec4<f32>(in.position, 1.0);
+  out.normal = (camera.rotation * vec4<f32>(in.normal, 0.0)).xyz;
+  out.uv = in.uv;
+  return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+  // Same palette as the flat grid: dead, alive, selected dead, selected alive
+  var palette = array<vec3<f32>, 4>(
+    vec3<f32>(0.1, 0.1, 0.1),
+    vec3<f32>(0.2, 0.8, 0.2),
+    vec3<f32>(0.3, 0.3, 0.12),
+    vec3<f32>(0.8, 0.8, 0.2),
+  );
+
+  let texel = min(vec2<u32>(in.uv * vec2<f32>(camera.grid)), camera.grid - vec2<u32>(1u));
+  let state = textureLoad(cells, texel, 0).r;
+  let light = normalize(vec3<f32>(0.3, 0.5, 1.0));
+  let shade = 0.35 + 0.65 * max(dot(normalize(in.normal), light), 0.0);
+  return vec4<f32>(palette[min(state, 3u)] * shade, 1.0);
+}
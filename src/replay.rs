@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+
+use crate::action::Action;
+use crate::console::parse_command;
+
+// Replay files are plain text: one `<generation> <console command>` line per action,
+// applied before that generation is stepped. Lines starting with '#' are comments.
+const HEADER: &str = "# bio-rust replay v1";
+
+// Appends every applied action to a replay file as it happens, so a crash still
+// leaves a usable recording
+pub struct Recorder {
+    file: LineWriter<File>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut file = LineWriter::new(File::create(path)?);
+        writeln!(file, "{HEADER}")?;
+        Ok(Self { file })
+    }
+
+    pub fn record(&mut self, generation: u64, action: &Action) -> io::Result<()> {
+        writeln!(self.file, "{generation} {action}")
+    }
+}
+
+pub struct Replay {
+    events: VecDeque<(u64, Action)>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let _span = tracing::debug_span!("read", path = %path.display()).entered();
+        let text = std::fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        tracing::debug!("Read {} bytes", text.len());
+        Self::parse(&text).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut events = VecDeque::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (generation, command) = line.split_once(' ').ok_or_else(|| format!("line {}: expected '<generation> <command>'", number + 1))?;
+            let generation = generation.parse().map_err(|_| format!("line {}: invalid generation '{generation}'", number + 1))?;
+            let action = parse_command(command).map_err(|err| format!("line {}: {err}", number + 1))?;
+            events.push_back((generation, action));
+        }
+        Ok(Self { events })
+    }
+
+    // Next action recorded at or before `generation`, in file order
+    pub fn next_due(&mut self, generation: u64) -> Option<Action> {
+        if self.events.front()?.0 <= generation {
+            self.events.pop_front().map(|(_, action)| action)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replays_actions_at_their_generation() {
+        let mut replay = Replay::parse("# bio-rust replay v1\n0 toggle 1 2\n0 speed 20\n\n5 rule B36/S23\n5 jump 100\n105 select none\n").unwrap();
+
+        assert_eq!(replay.next_due(0), Some(Action::ToggleCell { row: 1, col: 2 }));
+        assert_eq!(replay.next_due(0), Some(Action::SetSpeed(20.0)));
+        assert_eq!(replay.next_due(0), None);
+        assert_eq!(replay.next_due(5), Some(Action::SetRule("B36/S23".parse().unwrap())));
+        assert_eq!(replay.next_due(5), Some(Action::Jump(100)));
+        assert_eq!(replay.next_due(105), Some(Action::ClearSelection));
+        assert!(replay.is_finished());
+
+        assert!(Replay::parse("x toggle 1 2").is_err());
+        assert!(Replay::parse("3 dance").is_err());
+    }
+}
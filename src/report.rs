@@ -0,0 +1,34 @@
+use crate::rule::Rule;
+
+// Summary of a run: where it stands, how the population moved, and the centroid trajectory
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulationReport {
+    pub generation: u64,
+    pub rule: Rule,
+    pub population: usize,
+    // Highest population among the recent generations kept for the charts
+    pub max_population: usize,
+    // (generation, [column, row]) centroids, unwrapped across the grid edges
+    pub trajectory: Vec<(u64, [f64; 2])>,
+    // Cells per generation along (columns, rows)
+    pub drift: Option<[f64; 2]>,
+}
+
+impl SimulationReport {
+    // "key<TAB>value" lines, then the trajectory as a TSV table
+    pub fn to_tsv(&self) -> String {
+        let mut tsv = format!(
+            "generation\t{}\nrule\t{}\npopulation\t{}\nmax_population\t{}\n",
+            self.generation, self.rule, self.population, self.max_population
+        );
+        match self.drift {
+            Some([dx, dy]) => tsv.push_str(&format!("drift\t{dx:.4}\t{dy:.4}\n")),
+            None => tsv.push_str("drift\tNA\tNA\n"),
+        }
+        tsv.push_str("\ngeneration\tcentroid_col\tcentroid_row\n");
+        for (generation, [x, y]) in &self.trajectory {
+            tsv.push_str(&format!("{generation}\t{x:.3}\t{y:.3}\n"));
+        }
+        tsv
+    }
+}
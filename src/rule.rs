@@ -0,0 +1,75 @@
+// Outer-totalistic "Life-like" rule: bit n of `birth`/`survive` is set when a cell
+// with n live neighbors is born/survives.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rule {
+    pub birth: u16,
+    pub survive: u16,
+}
+
+impl Rule {
+    // B3/S23
+    pub const CONWAY: Rule = Rule { birth: 1 << 3, survive: (1 << 2) | (1 << 3) };
+
+    pub fn next_state(&self, alive: bool, live_neighbors: u8) -> bool {
+        let mask = if alive { self.survive } else { self.birth };
+        mask & (1 << live_neighbors) != 0
+    }
+
+    pub fn is_conway(&self) -> bool {
+        *self == Rule::CONWAY
+    }
+}
+
+// Parses B/S notation such as "B3/S23" or "b36/s23"
+impl std::str::FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (birth, survive) = s
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| format!("Invalid rule '{s}', expected B/S notation like B3/S23"))?;
+
+        let digits = |part: &str, prefix: char| -> Result<u16, String> {
+            let part = part.strip_prefix([prefix, prefix.to_ascii_lowercase()])
+                .ok_or_else(|| format!("Invalid rule '{s}', expected '{prefix}' section"))?;
+            part.chars().try_fold(0u16, |mask, c| match c.to_digit(10) {
+                Some(n) if n <= 8 => Ok(mask | (1 << n)),
+                _ => Err(format!("Invalid neighbor count '{c}' in rule '{s}'")),
+            })
+        };
+
+        Ok(Rule { birth: digits(birth, 'B')?, survive: digits(survive, 'S')? })
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let counts = |mask: u16| (0..=8).filter(|n| mask & (1 << n) != 0).map(|n| n.to_string()).collect::<String>();
+        write!(f, "B{}/S{}", counts(self.birth), counts(self.survive))
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::CONWAY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_formats_bs_notation() {
+        let high_life: Rule = "B36/S23".parse().unwrap();
+        assert_eq!(high_life, Rule { birth: (1 << 3) | (1 << 6), survive: (1 << 2) | (1 << 3) });
+        assert_eq!(high_life.to_string(), "B36/S23");
+        assert_eq!("b3/s23".parse::<Rule>(), Ok(Rule::CONWAY));
+        assert_eq!("B/S".parse::<Rule>().unwrap().to_string(), "B/S");
+
+        assert!("B39/S23".parse::<Rule>().is_err());
+        assert!("S23/B3".parse::<Rule>().is_err());
+        assert!("23/3".parse::<Rule>().is_err());
+    }
+}
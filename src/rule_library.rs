@@ -0,0 +1,138 @@
+use std::io::Write;
+use std::path::Path;
+
+use crate::rule::Rule;
+
+// The rule library (`rules`, `rule <name>`, `--rule-name`): notable Life-like rules by name,
+// each with a line on what it does, plus the user's own, saved to the config file as
+// `named_rule = <name> <B/S> [description]` lines. A user rule with a built-in's name
+// replaces it.
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NamedRule {
+    // Lower case letters, digits and dashes, as typed in `rule <name>`
+    pub name: String,
+    pub rule: Rule,
+    pub description: String,
+}
+
+const BUILTIN: &[(&str, &str, &str)] = &[
+    ("life", "B3/S23", "Conway's Life: gliders, oscillators and still lifes"),
+    ("highlife", "B36/S23", "Like Life, plus a pattern that copies itself"),
+    ("seeds", "B2/S", "Every cell dies at once, yet most patterns explode"),
+    ("day-and-night", "B3678/S34678", "Live and dead regions behave the same way"),
+    ("anneal", "B4678/S35678", "Majority vote: blobs that smooth out and merge"),
+    ("diamoeba", "B35678/S5678", "Large diamond-shaped amoebas that grow or shrink"),
+    ("34-life", "B34/S34", "Small patterns that pulse, grow or fly"),
+    ("2x2", "B36/S125", "Patterns made of 2x2 blocks"),
+    ("morley", "B368/S245", "Chaotic, with many high-period spaceships"),
+    ("maze", "B3/S12345", "Grows into maze-like corridors"),
+    ("coral", "B3/S45678", "Slow coral-like growth"),
+    ("life-without-death", "B3/S012345678", "Cells never die: ladders and inkblots"),
+    ("replicator", "B1357/S1357", "Every pattern makes copies of itself"),
+    ("long-life", "B345/S5", "Oscillators with very long periods"),
+    ("walled-cities", "B45678/S2345", "Dense cities grow inside walls"),
+];
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+        return Err(format!("Invalid rule name '{name}' (lower case letters, digits and dashes)"));
+    }
+    Ok(())
+}
+
+// "<name> <B/S> [description]", as in the config file
+impl std::str::FromStr for NamedRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let (Some(name), Some(rule)) = (words.next(), words.next()) else {
+            return Err(format!("Invalid named rule '{s}', expected <name> <B/S> [description]"));
+        };
+        validate_name(name)?;
+        Ok(Self { name: name.to_string(), rule: rule.parse()?, description: words.collect::<Vec<_>>().join(" ") })
+    }
+}
+
+impl std::fmt::Display for NamedRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}", self.name, self.rule)?;
+        if !self.description.is_empty() {
+            write!(f, " {}", self.description)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleLibrary {
+    // Built-ins first, in their order, then the user's in the order they were added
+    pub rules: Vec<NamedRule>,
+}
+
+impl Default for RuleLibrary {
+    fn default() -> Self {
+        let rules = BUILTIN
+            .iter()
+            .map(|&(name, rule, description)| NamedRule { name: name.to_string(), rule: rule.parse().expect("built-in rules are valid"), description: description.to_string() })
+            .collect();
+        Self { rules }
+    }
+}
+
+impl RuleLibrary {
+    pub fn get(&self, name: &str) -> Option<&NamedRule> {
+        self.rules.iter().find(|named| named.name == name.to_ascii_lowercase())
+    }
+
+    // Add a rule, replacing the one of the same name
+    pub fn add(&mut self, named: NamedRule) {
+        match self.rules.iter_mut().find(|other| other.name == named.name) {
+            Some(other) => *other = named,
+            None => self.rules.push(named),
+        }
+    }
+}
+
+// Add a named rule to the end of a config file, creating it if needed; read back, it
+// replaces any earlier one of the same name
+pub fn save(path: &Path, named: &NamedRule) -> Result<(), String> {
+    validate_name(&named.name)?;
+    let error = |err: std::io::Error| format!("Failed to save rule {} to {}: {err}", named.name, path.display());
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path).map_err(error)?;
+    // Start on a line of its own, should the file not end with a newline
+    let newline = std::fs::read(path).map_err(error)?.last().is_some_and(|&byte| byte != b'\n');
+    writeln!(file, "{}named_rule = {named}", if newline { "\n" } else { "" }).map_err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_and_saves_named_rules() {
+        let mut library = RuleLibrary::default();
+        assert_eq!(library.get("HighLife").map(|named| named.rule.to_string()), Some("B36/S23".to_string()));
+        assert_eq!(library.get("life").map(|named| named.rule), Some(Rule::CONWAY));
+        // Every built-in parses to the rule it is written as and has its own name
+        assert_eq!(library.rules.iter().map(|named| named.rule.to_string()).collect::<Vec<_>>(), BUILTIN.iter().map(|&(_, rule, _)| rule).collect::<Vec<_>>());
+        assert!(library.rules.iter().all(|named| named.to_string().parse() == Ok(named.clone())));
+        assert!(library.rules.iter().enumerate().all(|(i, named)| library.rules[..i].iter().all(|other| other.name != named.name)));
+
+        let mine: NamedRule = "flakes B3/S012345678 Snowflake-like growth".parse().unwrap();
+        assert_eq!(mine.description, "Snowflake-like growth");
+        library.add(mine.clone());
+        library.add("seeds B2/S0".parse().unwrap());
+        assert_eq!(library.rules.len(), BUILTIN.len() + 1);
+        assert_eq!(library.get("seeds").map(|named| named.description.as_str()), Some(""));
+        assert!("Big B3/S23".parse::<NamedRule>().is_err());
+        assert!("lonely".parse::<NamedRule>().is_err());
+
+        let path = std::env::temp_dir().join(format!("bio-rust-rules-{}.conf", std::process::id()));
+        std::fs::write(&path, "high_contrast = true").unwrap();
+        save(&path, &mine).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "high_contrast = true\nnamed_rule = flakes B3/S012345678 Snowflake-like growth\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use crate::rule::Rule;
+
+// Rule walk: every `interval` generations the next codon of the sequence flips one bit of
+// the active rule, so the sequence drives a tour through Life-like rule space. B0 is never
+// flipped on, since it makes the whole grid strobe.
+pub struct RuleWalk {
+    pub interval: u64,
+    // Next base of the sequence to read, wrapping around at the end
+    position: usize,
+    pub history: Vec<RuleStep>,
+}
+
+// A rule the walk arrived at, with the population when it was left (or so far, for the
+// current rule) to tell dying rules from interesting ones
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleStep {
+    pub generation: u64,
+    pub rule: Rule,
+    pub population: usize,
+    // Marked by the user as worth keeping
+    pub marked: bool,
+}
+
+// Birth counts 1-8 and survival counts 0-8
+const FLIPPABLE_BITS: usize = 17;
+
+// Rule with the bit chosen by a codon flipped: bases are read as base-4 digits (A, C, G, T)
+// and the resulting 0..64 taken modulo the 17 flippable bits. Codons with other characters
+// leave the rule unchanged.
+pub fn mutate(rule: Rule, codon: &[u8]) -> Rule {
+    let value = codon.iter().try_fold(0usize, |value, base| {
+        let digit = match base.to_ascii_uppercase() {
+            b'A' => 0,
+            b'C' => 1,
+            b'G' => 2,
+            b'T' => 3,
+            _ => return None,
+        };
+        Some(value * 4 + digit)
+    });
+
+    let mut rule = rule;
+    match value.map(|value| value % FLIPPABLE_BITS) {
+        Some(bit @ 0..8) => rule.birth ^= 1 << (bit + 1),
+        Some(bit) => rule.survive ^= 1 << (bit - 8),
+        None => {}
+    }
+    rule
+}
+
+impl RuleWalk {
+    // Start walking from `rule`, reading the sequence from its first base
+    pub fn new(interval: u64, rule: Rule, generation: u64, population: usize) -> Self {
+        let start = RuleStep { generation, rule, population, marked: false };
+        Self { interval: interval.max(1), position: 0, history: vec![start] }
+    }
+
+    pub fn current(&self) -> &RuleStep {
+        self.history.last().expect("the walk starts with a rule")
+    }
+
+    // Generations until the next mutation is due
+    pub fn remaining(&self, generation: u64) -> u64 {
+        let since = generation.saturating_sub(self.current().generation);
+        self.interval - since.min(self.interval)
+    }
+
+    // Record the population under the current rule, and mutate `rule` (the active one, which
+    // may have been set by hand since) when the next step is due at `generation`
+    pub fn advance(&mut self, rule: Rule, dna: &[u8], generation: u64, population: usize) -> Option<Rule> {
+        let current = self.history.last_mut().expect("the walk starts with a rule");
+        current.population = population;
+        if self.remaining(generation) > 0 || dna.len() < 3 {
+            return None;
+        }
+
+        let codon: Vec<u8> = (0..3).map(|i| dna[(self.position + i) % dna.len()]).collect();
+        self.position = (self.position + 3) % dna.len();
+        let rule = mutate(rule, &codon);
+        self.history.push(RuleStep { generation, rule, population, marked: false });
+        Some(rule)
+    }
+
+    pub fn mark_current(&mut self) {
+        if let Some(current) = self.history.last_mut() {
+            current.marked = true;
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("generation,rule,population,marked\n");
+        for step in &self.history {
+            csv.push_str(&format!("{},{},{},{}\n", step.generation, step.rule, step.population, step.marked));
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codons_flip_one_rule_bit() {
+        // AAC = 1: birth on 2 neighbors
+        assert_eq!(mutate(Rule::CONWAY, b"AAC").to_string(), "B23/S23");
+        // AGA = 8: survival on 0 neighbors
+        assert_eq!(mutate(Rule::CONWAY, b"aga").to_string(), "B3/S023");
+        // TTT = 63 = 12 mod 17: survival on 4 neighbors
+        assert_eq!(mutate(Rule::CONWAY, b"TTT").to_string(), "B3/S234");
+        assert_eq!(mutate(Rule::CONWAY, b"ANA"), Rule::CONWAY);
+        // No codon ever turns B0 on
+        let mut rule = Rule::CONWAY;
+        for value in 0..64u8 {
+            let codon = [value / 16, value / 4 % 4, value % 4].map(|digit| b"ACGT"[digit as usize]);
+            rule = mutate(rule, &codon);
+            assert_eq!(rule.birth & 1, 0);
+        }
+    }
+
+    #[test]
+    fn walks_every_interval() {
+        let mut walk = RuleWalk::new(10, Rule::CONWAY, 0, 50);
+        let mut rule = Rule::CONWAY;
+        let mut advance = |generation, population| {
+            rule = walk.advance(rule, b"AACAGA", generation, population).unwrap_or(rule);
+            rule.to_string()
+        };
+        assert_eq!(advance(5, 40), "B3/S23");
+        assert_eq!(advance(10, 30), "B23/S23");
+        assert_eq!(advance(20, 20), "B23/S023");
+        // The sequence wraps around
+        assert_eq!(advance(30, 10), "B3/S023");
+
+        assert_eq!(walk.remaining(35), 5);
+        walk.history[1].marked = true;
+
+        assert_eq!(walk.history.len(), 4);
+        assert_eq!(walk.to_csv().lines().nth(2), Some("10,B23/S23,20,true"));
+    }
+}
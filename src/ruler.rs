@@ -0,0 +1,116 @@
+use std::ops::Range;
+
+use crate::render::text::{ADVANCE, GLYPH_HEIGHT, font_pixel, push_text};
+use crate::strip::SeedMap;
+use crate::vertex::{Vertex, push_rect};
+use crate::viewport::{GridGeometry, Viewport};
+
+// Coordinate ruler along the grid (`ruler`, G), as in a genome browser: the left axis gives
+// the sequence position each row starts at, from the seed map, so offsets and partial reseeds
+// show; the top axis counts bases into the row. Ticks sit at round steps of 1, 2 or 5 times a
+// power of ten, the smallest that keeps labels apart at the current zoom.
+
+// Widest label in characters, e.g. "+462.5k", "123.46M" or "+3.05G", plus one
+const LABEL_CHARS: u32 = 8;
+// Font pixels between a tick label and the next
+const LABEL_GAP: u32 = 3;
+// Length of a tick, in font pixels
+const TICK: u32 = 3;
+const BACKGROUND: [f32; 3] = [0.03, 0.03, 0.06];
+const TEXT: [f32; 3] = [0.75, 0.75, 0.8];
+
+// Smallest of 1, 2, 5, 10, 20, 50, ... at least `min`
+pub fn tick_step(min: f32) -> u32 {
+    let mut step = 1;
+    loop {
+        for factor in [1, 2, 5] {
+            if (step * factor) as f32 >= min {
+                return step * factor;
+            }
+        }
+        step *= 10;
+    }
+}
+
+// Positions of more than five digits in thousands, millions or billions, e.g. 462500 ->
+// "462.5k"
+pub fn short_position(position: usize) -> String {
+    let trimmed = |value: f64| format!("{value:.2}").trim_end_matches('0').trim_end_matches('.').to_string();
+    match position {
+        0..100_000 => position.to_string(),
+        100_000..1_000_000 => format!("{}k", trimmed(position as f64 / 1e3)),
+        1_000_000..1_000_000_000 => format!("{}M", trimmed(position as f64 / 1e6)),
+        _ => format!("{}G", trimmed(position as f64 / 1e9)),
+    }
+}
+
+// Ticks and labels for the visible `rows` and `cols` of the grid; with `row_axis` off only
+// the top axis is drawn, e.g. when the rows are labeled with their records instead
+pub fn create_ruler_vertices(geometry: &GridGeometry, seed_map: &SeedMap, (rows, cols): (Range<u32>, Range<u32>), viewport: &Viewport, text_scale: u32, row_axis: bool) -> Vec<Vertex> {
+    let mut vertices = Vec::new();
+    let pixel = font_pixel(viewport, text_scale);
+    let height = (GLYPH_HEIGHT + TICK + 2) as f32 * pixel[1];
+
+    // Top axis, just above the last row, or against the top of the window
+    let grid_top = geometry.end(rows.end.max(1), 1);
+    let bottom = grid_top.min(1.0 - height);
+    let left = geometry.origin[0].max(-1.0);
+    let right = geometry.end(cols.end, 0).min(1.0);
+    push_rect(&mut vertices, [left, bottom], [right, bottom + height], BACKGROUND);
+    let step = tick_step((LABEL_CHARS * ADVANCE + LABEL_GAP) as f32 * pixel[0] / geometry.pitch[0]);
+    for col in cols.clone().filter(|col| col % step == 0) {
+        let x = geometry.cell_origin(0, col)[0] + geometry.cell[0] / 2.0;
+        push_rect(&mut vertices, [x - pixel[0] / 2.0, bottom], [x + pixel[0] / 2.0, bottom + TICK as f32 * pixel[1]], TEXT);
+        push_text(&mut vertices, &format!("+{}", short_position(col as usize)), [x + pixel[0], bottom + (TICK + 1) as f32 * pixel[1]], pixel, TEXT);
+    }
+
+    if !row_axis {
+        return vertices;
+    }
+    // Left axis, right-aligned against the grid, or against the left of the window
+    let width = (LABEL_CHARS * ADVANCE + TICK) as f32 * pixel[0];
+    let axis_right = geometry.origin[0].max(-1.0 + width);
+    let axis_bottom = geometry.origin[1].max(-1.0);
+    push_rect(&mut vertices, [axis_right - width, axis_bottom], [axis_right, grid_top.min(bottom)], BACKGROUND);
+    let step = tick_step((GLYPH_HEIGHT + LABEL_GAP) as f32 * pixel[1] / geometry.pitch[1]);
+    for row in rows.filter(|row| row % step == 0) {
+        let Some(position) = seed_map.position(row, 0) else {
+            continue;
+        };
+        let y = geometry.cell_origin(row, 0)[1] + geometry.cell[1] / 2.0;
+        push_rect(&mut vertices, [axis_right - TICK as f32 * pixel[0], y - pixel[1] / 2.0], [axis_right, y + pixel[1] / 2.0], TEXT);
+        let label = short_position(position);
+        let label_left = axis_right - ((label.len() as u32 * ADVANCE) + TICK + 1) as f32 * pixel[0];
+        push_text(&mut vertices, &label, [label_left, y - GLYPH_HEIGHT as f32 * pixel[1] / 2.0], pixel, TEXT);
+    }
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_and_labels_stay_round() {
+        assert_eq!([0.3, 1.0, 1.5, 3.0, 7.0, 11.0, 180.0, 4000.0].map(tick_step), [1, 1, 2, 5, 10, 20, 200, 5000]);
+        assert_eq!(short_position(99_999), "99999");
+        assert_eq!(short_position(125_000), "125k");
+        assert_eq!(short_position(462_500), "462.5k");
+        assert_eq!(short_position(4_641_652), "4.64M");
+        assert_eq!(short_position(250_000_000), "250M");
+        assert_eq!(short_position(123_456_789), "123.46M");
+        assert_eq!(short_position(1_000_000_000), "1G");
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(short_position(1 << 32), "4.29G");
+
+        // Labels go to the rows in view that are on a step, from the seeding offset
+        let geometry = GridGeometry { origin: [-0.5; 2], cell: [0.1; 2], pitch: [0.12; 2] };
+        let viewport = Viewport { width: 800, height: 600, scale_factor: 1.0 };
+        let mut seed_map = SeedMap::new(10, 10);
+        let top_only = create_ruler_vertices(&geometry, &seed_map, (0..10, 0..10), &viewport, 1, false).len();
+        let both = create_ruler_vertices(&geometry, &seed_map, (0..10, 0..10), &viewport, 1, true).len();
+        seed_map.record(&crate::selection::Region::full(10, 10), 1000);
+        assert!(create_ruler_vertices(&geometry, &seed_map, (0..10, 0..10), &viewport, 1, true).len() > both);
+        assert!(both > top_only);
+    }
+}
@@ -0,0 +1,102 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use rhai::{AST, Engine, Scope};
+
+// User scripts written in Rhai. A script may define either or both of:
+//
+//     fn next(alive, neighbors, base) { ... }   // -> bool, replaces the B/S rule
+//     fn seed(position, base) { ... }           // -> bool, replaces G/C seeding
+//
+// `base` is the nucleotide under the cell as a character ('N' past the end of the sequence).
+// The engine is sandboxed: no file or network access, and every call has an operation budget,
+// so a runaway loop fails with an error instead of hanging the app.
+pub struct Script {
+    engine: Engine,
+    ast: AST,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(100_000);
+        engine.set_max_call_levels(32);
+        engine.set_max_expr_depths(64, 32);
+        engine.set_max_string_size(4096);
+        engine.set_max_array_size(4096);
+        engine.set_max_map_size(4096);
+        engine.disable_symbol("eval");
+
+        let ast = Self::compile(&engine, path)?;
+        Ok(Self { engine, ast, path: path.to_path_buf(), modified: modified_time(path) })
+    }
+
+    fn compile(engine: &Engine, path: &Path) -> Result<AST, String> {
+        engine
+            .compile_file(path.to_path_buf())
+            .map_err(|err| format!("Failed to load script {}: {err}", path.display()))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Recompile when the file changed on disk. Returns None if nothing changed; on a compile
+    // error the previous version keeps running.
+    pub fn reload_if_changed(&mut self) -> Option<Result<(), String>> {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        Some(Self::compile(&self.engine, &self.path).map(|ast| self.ast = ast))
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.has_fn("next", 3)
+    }
+
+    pub fn has_seed(&self) -> bool {
+        self.has_fn("seed", 2)
+    }
+
+    fn has_fn(&self, name: &str, params: usize) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name && f.params.len() == params)
+    }
+
+    pub fn next(&self, alive: bool, neighbors: u8, base: char) -> Result<bool, String> {
+        self.engine
+            .call_fn::<bool>(&mut Scope::new(), &self.ast, "next", (alive, neighbors as i64, base))
+            .map_err(|err| format!("Script error in next(): {err}"))
+    }
+
+    pub fn seed(&self, position: usize, base: char) -> Result<bool, String> {
+        self.engine
+            .call_fn::<bool>(&mut Scope::new(), &self.ast, "seed", (position as i64, base))
+            .map_err(|err| format!("Script error in seed(): {err}"))
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_example_script() {
+        let script = Script::load(Path::new("scripts/highlife.rhai")).unwrap();
+        assert!(script.has_next() && script.has_seed());
+
+        assert_eq!(script.next(false, 6, 'G'), Ok(true));
+        assert_eq!(script.next(false, 6, 'A'), Ok(false));
+        assert_eq!(script.next(true, 2, 'A'), Ok(true));
+        assert_eq!(script.seed(0, 'A'), Ok(true));
+        assert_eq!(script.seed(0, 'C'), Ok(false));
+    }
+}
@@ -0,0 +1,82 @@
+// Rectangular block of cells, e.g. the area dragged out with Shift + Left Click
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Region {
+    pub row: u32,
+    pub col: u32,
+    pub rows: u32,
+    pub cols: u32,
+}
+
+impl Region {
+    pub fn full(rows: u32, cols: u32) -> Self {
+        Self { row: 0, col: 0, rows, cols }
+    }
+
+    // Region spanning two corner cells given in any order, both inclusive
+    pub fn from_corners(a: (u32, u32), b: (u32, u32)) -> Self {
+        let (row, col) = (a.0.min(b.0), a.1.min(b.1));
+        Self {
+            row,
+            col,
+            rows: a.0.max(b.0) - row + 1,
+            cols: a.1.max(b.1) - col + 1,
+        }
+    }
+
+    pub fn contains(&self, row: u32, col: u32) -> bool {
+        // Subtracting first, so regions reaching past u32::MAX don't overflow
+        row >= self.row && row - self.row < self.rows && col >= self.col && col - self.col < self.cols
+    }
+
+    // The part of the region inside a grid of `rows` x `cols`, empty if it is all outside
+    pub fn within(&self, rows: u32, cols: u32) -> Self {
+        let (row, col) = (self.row.min(rows), self.col.min(cols));
+        Self { row, col, rows: self.row.saturating_add(self.rows).min(rows) - row, cols: self.col.saturating_add(self.cols).min(cols) - col }
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows as usize * self.cols as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// `row,col,rowsxcols`, as in session files
+impl std::fmt::Display for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{},{},{}x{}", self.row, self.col, self.rows, self.cols)
+    }
+}
+
+impl std::str::FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid region '{s}', expected row,col,rowsxcols");
+        let mut fields = s.splitn(3, ',');
+        let mut next = || fields.next().ok_or_else(invalid);
+        let (row, col, size) = (next()?, next()?, next()?);
+        let (rows, cols) = size.split_once('x').ok_or_else(invalid)?;
+        let number = |value: &str| value.parse::<u32>().map_err(|_| invalid());
+        Ok(Self { row: number(row)?, col: number(col)?, rows: number(rows)?, cols: number(cols)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clips_to_the_grid() {
+        let huge = Region { row: 4_000_000_000, col: 0, rows: 4_000_000_000, cols: 1 };
+        assert!(huge.within(10, 10).is_empty());
+        assert_eq!(Region { row: 8, col: 2, rows: 5, cols: 3 }.within(10, 4), Region { row: 8, col: 2, rows: 2, cols: 2 });
+        assert_eq!(Region::full(3, 3).within(10, 10), Region::full(3, 3));
+
+        let region = Region { row: 8, col: 2, rows: 5, cols: 3 };
+        assert_eq!(region.to_string().parse(), Ok(region));
+        assert!("8,2,5".parse::<Region>().is_err());
+    }
+}
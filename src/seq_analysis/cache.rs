@@ -0,0 +1,214 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::fnv::fnv1a;
+use crate::seq_analysis::skew::{Skew, cumulative_gc_skew};
+use crate::tracks::{entropies, gc_windows, melting_temperatures};
+
+// Caches for the per-sequence analyses, so going back to a sequence seen before (undoing a
+// shuffle, leaving a comparison) and scrubbing the seeding window along a genome don't redo
+// work that was already done.
+
+// Identifies a sequence by its content (FNV-1a), so an identical sequence finds its
+// analyses however it came back
+pub fn sequence_id(dna: &[u8]) -> u64 {
+    fnv1a(dna.iter().copied())
+}
+
+// Every per-window analysis the tracks are built from
+#[derive(Clone)]
+pub struct WindowStats {
+    pub gc: Vec<f64>,
+    pub skew: Skew,
+    pub tm: Vec<f64>,
+    pub entropy: Vec<f64>,
+}
+
+impl WindowStats {
+    pub fn compute(dna: &[u8], window: usize) -> Self {
+        Self { gc: gc_windows(dna, window), skew: cumulative_gc_skew(dna, window), tm: melting_temperatures(dna, window), entropy: entropies(dna, window) }
+    }
+}
+
+// Least recently used entries are evicted first once `capacity` is reached
+pub struct Lru<K, V> {
+    // Most recently used first
+    entries: VecDeque<(K, V)>,
+    capacity: usize,
+}
+
+impl<K: PartialEq, V> Lru<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self { entries: VecDeque::new(), capacity: capacity.max(1) }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        let index = self.entries.iter().position(|(other, _)| other == key)?;
+        let entry = self.entries.remove(index)?;
+        self.entries.push_front(entry);
+        self.entries.front().map(|(_, value)| value)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.entries.retain(|(other, _)| *other != key);
+        self.entries.push_front((key, value));
+        self.entries.truncate(self.capacity);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+// Running G/C count over the stretch of the sequence last asked for, slid along with the
+// requests: only bases entering the stretch are counted, from either end, and bases far
+// outside it are dropped again
+pub struct GcCounts {
+    // Id of the sequence counted
+    sequence: u64,
+    start: usize,
+    // G/C count up to each position from `start` on, relative to an arbitrary base so the
+    // stretch can also grow to the left
+    running: VecDeque<i64>,
+}
+
+impl GcCounts {
+    pub fn new() -> Self {
+        Self { sequence: 0, start: 0, running: VecDeque::from([0]) }
+    }
+
+    // The counts for the sequence with id `sequence`, starting over when it is another one
+    pub fn for_sequence(&mut self, sequence: u64) -> &mut Self {
+        if sequence != self.sequence {
+            *self = Self { sequence, ..Self::new() };
+        }
+        self
+    }
+
+    // Make `count` work over `range` of the sequence
+    pub fn cover(&mut self, dna: &[u8], range: Range<usize>) {
+        let range = range.start.min(dna.len())..range.end.min(dna.len());
+        let end = self.start + self.running.len() - 1;
+        if range.end < self.start || range.start > end {
+            *self = Self { sequence: self.sequence, start: range.start, running: VecDeque::from([0]) };
+        }
+        let is_gc = |base: u8| i64::from(matches!(base, b'G' | b'C'));
+        while self.start > range.start {
+            self.start -= 1;
+            let front = self.running[0];
+            self.running.push_front(front - is_gc(dna[self.start]));
+        }
+        let end = self.start + self.running.len() - 1;
+        for &base in dna.get(end..range.end).unwrap_or_default() {
+            let back = self.running[self.running.len() - 1];
+            self.running.push_back(back + is_gc(base));
+        }
+        // Keep up to the range's own length on either side for scrubbing back and forth
+        let slack = range.len().max(1);
+        while range.start - self.start > slack {
+            self.running.pop_front();
+            self.start += 1;
+        }
+        while self.start + self.running.len() - 1 > range.end + slack {
+            self.running.pop_back();
+        }
+    }
+
+    // G/C bases in `from..to`, inside the range last covered
+    pub fn count(&self, from: usize, to: usize) -> u32 {
+        (self.running[to - self.start] - self.running[from - self.start]) as u32
+    }
+}
+
+impl Default for GcCounts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Window statistics of recent sequences and window sizes
+const CACHED_SEQUENCES: usize = 8;
+
+pub struct StatsCache {
+    windows: Lru<(u64, usize), Arc<WindowStats>>,
+    pub gc_counts: GcCounts,
+}
+
+impl StatsCache {
+    pub fn new() -> Self {
+        Self { windows: Lru::new(CACHED_SEQUENCES), gc_counts: GcCounts::new() }
+    }
+
+    // Statistics of `dna` (with id `sequence`) over windows of `window` bases, computed only
+    // when they are not cached yet
+    pub fn window_stats(&mut self, dna: &[u8], sequence: u64, window: usize) -> Arc<WindowStats> {
+        let key = (sequence, window);
+        if let Some(stats) = self.windows.get(&key) {
+            tracing::debug!("Window statistics cached for {} bases in windows of {window}", dna.len());
+            return Arc::clone(stats);
+        }
+        let stats = Arc::new(WindowStats::compute(dna, window));
+        self.windows.insert(key, Arc::clone(&stats));
+        stats
+    }
+
+    // Keep statistics that were computed elsewhere, e.g. by the sequence loader
+    pub fn insert(&mut self, sequence: u64, window: usize, stats: Arc<WindowStats>) {
+        self.windows.insert((sequence, window), stats);
+    }
+}
+
+impl Default for StatsCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_the_least_recently_used() {
+        let mut lru = Lru::new(2);
+        lru.insert("a", 1);
+        lru.insert("b", 2);
+        assert_eq!(lru.get(&"a"), Some(&1));
+        lru.insert("c", 3);
+        assert_eq!(lru.get(&"b"), None);
+        assert_eq!(lru.get(&"a"), Some(&1));
+        assert_eq!(lru.get(&"c"), Some(&3));
+        assert_eq!(lru.len(), 2);
+
+        let mut cache = StatsCache::new();
+        let id = sequence_id(b"GGGCCCATAT");
+        let first = cache.window_stats(b"GGGCCCATAT", id, 5);
+        assert!(Arc::ptr_eq(&first, &cache.window_stats(b"GGGCCCATAT", id, 5)));
+        assert!(!Arc::ptr_eq(&first, &cache.window_stats(b"GGGCCCATAT", id, 2)));
+        assert_eq!((first.gc.len(), first.gc[0]), (2, 1.0));
+    }
+
+    #[test]
+    fn sliding_counts_match_a_recount() {
+        let dna: Vec<u8> = (0..500u32).map(|i| b"ACGTGGCAAT"[(i * i % 7 + i % 3) as usize]).collect();
+        let mut counts = GcCounts::new();
+        let counts = counts.for_sequence(sequence_id(&dna));
+        // Scrub forwards, backwards, jump away and back
+        for range in [100..200, 120..230, 90..150, 10..40, 400..500, 380..420, 0..500] {
+            counts.cover(&dna, range.clone());
+            for (from, to) in [(range.start, range.end), (range.start + 3, range.end - 5)] {
+                let expected = dna[from..to].iter().filter(|&&base| base == b'G' || base == b'C').count() as u32;
+                assert_eq!(counts.count(from, to), expected, "{from}..{to}");
+            }
+        }
+        // Another sequence starts over
+        let counts = counts.for_sequence(sequence_id(b"GGGG"));
+        counts.cover(b"GGGG", 0..4);
+        assert_eq!(counts.count(1, 3), 2);
+    }
+}
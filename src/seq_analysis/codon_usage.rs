@@ -0,0 +1,302 @@
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::seq_analysis::motif::Strand;
+
+// Standard genetic code, codons ordered T, C, A, G at each position; '*' is a stop
+const GENETIC_CODE: &[u8; 64] = b"FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG";
+
+// A coding sequence: one or more segments (exons) read in transcription order, on the
+// reverse strand from the end of the last segment backwards
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cds {
+    pub segments: Vec<Range<usize>>,
+    pub strand: Strand,
+}
+
+impl Cds {
+    // A CDS without introns
+    pub fn contiguous(range: Range<usize>, strand: Strand) -> Self {
+        Self { segments: vec![range], strand }
+    }
+
+    // Coding bases in transcription order with their forward-strand positions
+    fn bases(&self, dna: &[u8]) -> Vec<(usize, u8)> {
+        let valid = |segment: &Range<usize>| segment.start.min(dna.len())..segment.end.min(dna.len());
+        match self.strand {
+            Strand::Forward => self.segments.iter().flat_map(|segment| valid(segment).map(|i| (i, dna[i]))).collect(),
+            Strand::Reverse => self
+                .segments
+                .iter()
+                .rev()
+                .flat_map(|segment| valid(segment).rev().map(|i| (i, bio::alphabets::dna::complement(dna[i]))))
+                .collect(),
+        }
+    }
+}
+
+// Where the coding sequences come from: CDS features of a GFF3 file on one sequence (the
+// first when None), or open reading frames of at least this many codons
+#[derive(Clone, Debug, PartialEq)]
+pub enum CdsSource {
+    Gff(PathBuf, Option<String>),
+    Orfs(usize),
+}
+
+pub const DEFAULT_MIN_CODONS: usize = 30;
+
+impl std::fmt::Display for CdsSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CdsSource::Gff(path, None) => write!(f, "gff {}", path.display()),
+            CdsSource::Gff(path, Some(seqid)) => write!(f, "gff {} {seqid}", path.display()),
+            CdsSource::Orfs(min_codons) => write!(f, "orf {min_codons}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CodonUsage {
+    pub codon: String,
+    // One-letter code, '*' for stop codons
+    pub amino_acid: char,
+    pub count: u64,
+    // Relative synonymous codon usage: observed count over the count expected if all codons
+    // of the amino acid were used equally. 1 is neutral, 0 for amino acids never seen.
+    pub rscu: f64,
+}
+
+// Usage of all 64 codons over a set of coding sequences
+#[derive(Clone, Debug, PartialEq)]
+pub struct CodonTable {
+    pub codons: Vec<CodonUsage>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CodonSort {
+    Codon,
+    AminoAcid,
+    // Most used first
+    Count,
+    // Highest RSCU first
+    Rscu,
+}
+
+impl std::str::FromStr for CodonSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "codon" => Ok(CodonSort::Codon),
+            "aa" => Ok(CodonSort::AminoAcid),
+            "count" => Ok(CodonSort::Count),
+            "rscu" => Ok(CodonSort::Rscu),
+            _ => Err(format!("Unknown sort key '{s}', expected codon, aa, count or rscu")),
+        }
+    }
+}
+
+impl std::fmt::Display for CodonSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            CodonSort::Codon => write!(f, "codon"),
+            CodonSort::AminoAcid => write!(f, "aa"),
+            CodonSort::Count => write!(f, "count"),
+            CodonSort::Rscu => write!(f, "rscu"),
+        }
+    }
+}
+
+// Amino acid encoded by a codon, None if it contains anything but A/C/G/T
+pub fn translate(codon: &[u8]) -> Option<char> {
+    codon_index(codon).map(|index| GENETIC_CODE[index] as char)
+}
+
+fn codon_index(codon: &[u8]) -> Option<usize> {
+    codon.iter().try_fold(0, |index, base| {
+        let digit = match base.to_ascii_uppercase() {
+            b'T' => 0,
+            b'C' => 1,
+            b'A' => 2,
+            b'G' => 3,
+            _ => return None,
+        };
+        Some(index * 4 + digit)
+    })
+}
+
+fn codon_name(index: usize) -> String {
+    [index / 16, index / 4 % 4, index % 4].iter().map(|&digit| b"TCAG"[digit] as char).collect()
+}
+
+impl CodonTable {
+    // Count the complete codons of every CDS. Codons with ambiguous bases are skipped.
+    pub fn from_cds(dna: &[u8], cds: &[Cds]) -> Self {
+        let mut counts = [0u64; 64];
+        for cds in cds {
+            let bases: Vec<u8> = cds.bases(dna).into_iter().map(|(_, base)| base).collect();
+            for codon in bases.chunks_exact(3) {
+                if let Some(index) = codon_index(codon) {
+                    counts[index] += 1;
+                }
+            }
+        }
+
+        let codons = (0..64)
+            .map(|index| {
+                let amino_acid = GENETIC_CODE[index];
+                let synonyms: Vec<usize> = (0..64).filter(|&other| GENETIC_CODE[other] == amino_acid).collect();
+                let total: u64 = synonyms.iter().map(|&other| counts[other]).sum();
+                let rscu = if total == 0 { 0.0 } else { counts[index] as f64 * synonyms.len() as f64 / total as f64 };
+                CodonUsage { codon: codon_name(index), amino_acid: amino_acid as char, count: counts[index], rscu }
+            })
+            .collect();
+        Self { codons }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.codons.iter().map(|usage| usage.count).sum()
+    }
+
+    pub fn sort(&mut self, by: CodonSort) {
+        match by {
+            CodonSort::Codon => self.codons.sort_by(|a, b| a.codon.cmp(&b.codon)),
+            CodonSort::AminoAcid => self.codons.sort_by(|a, b| a.amino_acid.cmp(&b.amino_acid).then(b.rscu.total_cmp(&a.rscu))),
+            CodonSort::Count => self.codons.sort_by(|a, b| b.count.cmp(&a.count).then(a.codon.cmp(&b.codon))),
+            CodonSort::Rscu => self.codons.sort_by(|a, b| b.rscu.total_cmp(&a.rscu).then(a.codon.cmp(&b.codon))),
+        }
+    }
+
+    // Relative adaptiveness of a codon: its RSCU over the highest RSCU among its synonyms,
+    // so the preferred codon of each amino acid scores 1
+    pub fn adaptiveness(&self, codon: &[u8]) -> Option<f64> {
+        let amino_acid = translate(codon)?;
+        let name = String::from_utf8_lossy(codon).to_ascii_uppercase();
+        let usage = self.codons.iter().find(|usage| usage.codon == name)?;
+        let best = self
+            .codons
+            .iter()
+            .filter(|other| other.amino_acid == amino_acid)
+            .map(|other| other.rscu)
+            .fold(0.0, f64::max);
+        (best > 0.0).then(|| usage.rscu / best)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("codon,amino_acid,count,rscu\n");
+        for usage in &self.codons {
+            csv.push_str(&format!("{},{},{},{:.4}\n", usage.codon, usage.amino_acid, usage.count, usage.rscu));
+        }
+        csv
+    }
+}
+
+// Adaptiveness of the codon covering each position of `dna`, None outside coding sequences
+pub fn optimality(dna: &[u8], cds: &[Cds], table: &CodonTable) -> Vec<Option<f64>> {
+    let mut optimality = vec![None; dna.len()];
+    for cds in cds {
+        for codon in cds.bases(dna).chunks_exact(3) {
+            let bases: Vec<u8> = codon.iter().map(|&(_, base)| base).collect();
+            if let Some(score) = table.adaptiveness(&bases) {
+                for &(position, _) in codon {
+                    optimality[position] = Some(score);
+                }
+            }
+        }
+    }
+    optimality
+}
+
+// Open reading frames on both strands: an ATG followed by at least `min_codons` codons up to
+// and including the first in-frame stop. ORFs without a stop before the sequence ends are
+// not reported.
+pub fn find_orfs(dna: &[u8], min_codons: usize) -> Vec<Cds> {
+    let mut orfs: Vec<Cds> = forward_orfs(dna, min_codons)
+        .into_iter()
+        .map(|range| Cds::contiguous(range, Strand::Forward))
+        .collect();
+
+    let reverse_complement = bio::alphabets::dna::revcomp(dna);
+    orfs.extend(
+        forward_orfs(&reverse_complement, min_codons)
+            .into_iter()
+            .map(|range| dna.len() - range.end..dna.len() - range.start)
+            .map(|range| Cds::contiguous(range, Strand::Reverse)),
+    );
+    orfs
+}
+
+fn forward_orfs(dna: &[u8], min_codons: usize) -> Vec<Range<usize>> {
+    let mut orfs = Vec::new();
+    for frame in 0..3 {
+        let mut start = None;
+        for position in (frame..dna.len().saturating_sub(2)).step_by(3) {
+            match (start, translate(&dna[position..position + 3])) {
+                (None, _) if dna[position..position + 3].eq_ignore_ascii_case(b"ATG") => start = Some(position),
+                (Some(orf_start), Some('*')) => {
+                    let end = position + 3;
+                    if (end - orf_start) / 3 > min_codons {
+                        orfs.push(orf_start..end);
+                    }
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    orfs.sort_by_key(|orf| orf.start);
+    orfs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_with_the_standard_code() {
+        assert_eq!(translate(b"ATG"), Some('M'));
+        assert_eq!(translate(b"tgg"), Some('W'));
+        assert_eq!(translate(b"TAA"), Some('*'));
+        assert_eq!(translate(b"GGN"), None);
+    }
+
+    #[test]
+    fn computes_rscu_and_adaptiveness() {
+        // Leucine (6 codons): CTG three times, TTA once; lysine (2): AAA twice
+        let dna = b"CTGCTGCTGTTAAAAAAA";
+        let table = CodonTable::from_cds(dna, &[Cds::contiguous(0..dna.len(), Strand::Forward)]);
+        let rscu = |codon: &str| table.codons.iter().find(|usage| usage.codon == codon).unwrap().rscu;
+
+        assert_eq!(table.total(), 6);
+        assert_eq!(rscu("CTG"), 4.5);
+        assert_eq!(rscu("TTA"), 1.5);
+        assert_eq!(rscu("CTC"), 0.0);
+        assert_eq!(rscu("AAA"), 2.0);
+        assert_eq!(table.adaptiveness(b"TTA"), Some(1.0 / 3.0));
+        assert_eq!(table.adaptiveness(b"AAG"), Some(0.0));
+        // Never-seen amino acids have no preferred codon
+        assert_eq!(table.adaptiveness(b"TGG"), None);
+
+        let mut sorted = table.clone();
+        sorted.sort(CodonSort::Count);
+        assert_eq!(sorted.codons[0].codon, "CTG");
+        assert!(table.to_csv().contains("\nCTG,L,3,4.5000\n"));
+    }
+
+    #[test]
+    fn finds_orfs_on_both_strands() {
+        // Forward: ATG AAA TGA at 2; reverse: TCA TTT CAT is the reverse complement of ATG AAA TGA
+        let dna = b"CCATGAAATGACCTCATTTCATCC";
+        let orfs = find_orfs(dna, 1);
+        assert_eq!(orfs, [
+            Cds::contiguous(2..11, Strand::Forward),
+            Cds::contiguous(13..22, Strand::Reverse),
+        ]);
+
+        let table = CodonTable::from_cds(dna, &orfs);
+        assert_eq!(table.codons.iter().find(|usage| usage.codon == "AAA").unwrap().count, 2);
+        let optimality = optimality(dna, &orfs, &table);
+        assert_eq!(optimality[1], None);
+        assert_eq!(optimality[20], Some(1.0));
+    }
+}
@@ -0,0 +1,168 @@
+use std::ops::Range;
+
+// Low-complexity detection: homopolymer runs (including runs of N) and a DUST-like
+// triplet score over sliding windows. Intervals are 0-based half-open, sorted and merged.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ComplexityParams {
+    // Shortest run of one base that is masked
+    pub min_run: usize,
+    // DUST window length in bases
+    pub window: usize,
+    // Windows scoring above this are masked. The score is sum(c * (c - 1) / 2) / (l - 1)
+    // over the counts c of the l triplets in the window; 2.0 matches DUST's level 20.
+    pub threshold: f64,
+}
+
+impl Default for ComplexityParams {
+    fn default() -> Self {
+        Self { min_run: 6, window: 64, threshold: 2.0 }
+    }
+}
+
+pub fn low_complexity(dna: &[u8], params: &ComplexityParams) -> Vec<Range<usize>> {
+    let mut intervals = homopolymer_runs(dna, params.min_run);
+    intervals.extend(dust(dna, params.window, params.threshold));
+    intervals.sort_by_key(|interval| interval.start);
+    merge(intervals)
+}
+
+// Runs of at least `min_run` identical bases, compared case-insensitively
+pub fn homopolymer_runs(dna: &[u8], min_run: usize) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for end in 1..=dna.len() {
+        if end == dna.len() || !dna[end].eq_ignore_ascii_case(&dna[start]) {
+            if end - start >= min_run.max(1) {
+                runs.push(start..end);
+            }
+            start = end;
+        }
+    }
+    runs
+}
+
+// Windows of `window` bases (the whole sequence when shorter) whose triplet score is
+// above `threshold`. Triplets containing anything but A/C/G/T are not counted.
+pub fn dust(dna: &[u8], window: usize, threshold: f64) -> Vec<Range<usize>> {
+    let window = window.min(dna.len());
+    if window < 4 {
+        return Vec::new();
+    }
+
+    let code = |base: u8| match base.to_ascii_uppercase() {
+        b'A' => Some(0),
+        b'C' => Some(1),
+        b'G' => Some(2),
+        b'T' => Some(3),
+        _ => None,
+    };
+    let triplets: Vec<Option<usize>> = dna
+        .windows(3)
+        .map(|bases| Some(code(bases[0])? * 16 + code(bases[1])? * 4 + code(bases[2])?))
+        .collect();
+
+    let per_window = window - 2;
+    let mut counts = TripletCounts::new();
+    for &triplet in &triplets[..per_window] {
+        counts.add(triplet);
+    }
+
+    let mut intervals = Vec::new();
+    for start in 0..=dna.len() - window {
+        if start > 0 {
+            counts.remove(triplets[start - 1]);
+            counts.add(triplets[start + per_window - 1]);
+        }
+        if counts.score() > threshold {
+            intervals.push(start..start + window);
+        }
+    }
+    merge(intervals)
+}
+
+// Triplet counts of the current window, and the number of identical triplet pairs they make
+struct TripletCounts {
+    counts: [u32; 64],
+    pairs: u64,
+    counted: u32,
+}
+
+impl TripletCounts {
+    fn new() -> Self {
+        Self { counts: [0; 64], pairs: 0, counted: 0 }
+    }
+
+    fn add(&mut self, triplet: Option<usize>) {
+        if let Some(triplet) = triplet {
+            self.pairs += self.counts[triplet] as u64;
+            self.counts[triplet] += 1;
+            self.counted += 1;
+        }
+    }
+
+    fn remove(&mut self, triplet: Option<usize>) {
+        if let Some(triplet) = triplet {
+            self.counts[triplet] -= 1;
+            self.pairs -= self.counts[triplet] as u64;
+            self.counted -= 1;
+        }
+    }
+
+    fn score(&self) -> f64 {
+        if self.counted > 1 { self.pairs as f64 / (self.counted - 1) as f64 } else { 0.0 }
+    }
+}
+
+// Whether `position` falls inside one of the sorted, non-overlapping `intervals`
+pub fn is_masked(intervals: &[Range<usize>], position: usize) -> bool {
+    let next = intervals.partition_point(|interval| interval.end <= position);
+    intervals.get(next).is_some_and(|interval| interval.contains(&position))
+}
+
+// Merge overlapping or touching intervals, which must be sorted by start
+fn merge(intervals: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(intervals.len());
+    for interval in intervals {
+        match merged.last_mut() {
+            Some(last) if interval.start <= last.end => last.end = last.end.max(interval.end),
+            _ => merged.push(interval),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_homopolymer_and_n_runs() {
+        let dna = b"ACaaaaaaGTNNNNNNNNCGTTTTT";
+        assert_eq!(homopolymer_runs(dna, 6), [2..8, 10..18]);
+        assert_eq!(homopolymer_runs(dna, 5), [2..8, 10..18, 20..25]);
+    }
+
+    #[test]
+    fn dust_masks_repeats_but_not_mixed_sequence() {
+        let mixed = b"ACGTTGCAAGCTTCGAGATCCTAGGCATGCATCGATCGGTACCAGTTAACGCGTACTGAGCTTA";
+        assert!(dust(mixed, 64, 2.0).is_empty());
+
+        // A dinucleotide repeat between two stretches of mixed sequence
+        let mut dna = mixed.to_vec();
+        dna.extend(b"AT".repeat(40));
+        dna.extend(mixed);
+        let masked = dust(&dna, 64, 2.0);
+        assert_eq!(masked.len(), 1);
+        assert!(masked[0].start <= 64 && masked[0].end >= 144);
+        assert!(is_masked(&masked, 100));
+        assert!(!is_masked(&masked, 0));
+        assert!(!is_masked(&masked, dna.len() - 1));
+    }
+
+    #[test]
+    fn merges_runs_and_dust_windows() {
+        let dna = b"AAAAAAAAAACGT";
+        let params = ComplexityParams { min_run: 6, window: 8, threshold: 1.0 };
+        assert_eq!(low_complexity(dna, &params), vec![0..12]);
+    }
+}
@@ -0,0 +1,115 @@
+use bio::alignment::AlignmentOperation;
+use bio::alignment::pairwise::Aligner;
+
+// How positions of one sequence are paired with positions of another
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    // Global alignment, so an indel doesn't shift every later position out of register.
+    // Needs time and memory proportional to the product of the lengths.
+    Aligned,
+    // Position i against position i
+    Positional,
+}
+
+// Largest product of the lengths aligned; longer pairs are compared by position instead,
+// as the alignment matrix would take gigabytes and minutes
+pub const MAX_ALIGNED: usize = 25_000_000;
+
+impl Comparison {
+    // The comparison actually run on sequences of these lengths
+    pub fn for_lengths(self, a: usize, b: usize) -> Comparison {
+        match self {
+            Comparison::Aligned if a.saturating_mul(b) > MAX_ALIGNED => Comparison::Positional,
+            comparison => comparison,
+        }
+    }
+}
+
+impl std::str::FromStr for Comparison {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "align" => Ok(Comparison::Aligned),
+            "position" => Ok(Comparison::Positional),
+            _ => Err(format!("Unknown comparison '{s}', expected 'align' or 'position'")),
+        }
+    }
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Comparison::Aligned => write!(f, "align"),
+            Comparison::Positional => write!(f, "position"),
+        }
+    }
+}
+
+// For each position of `a`, whether it differs from `b`: a substitution, a base missing
+// from `b`, or (when aligned) a position of `a` right before bases `b` has in addition.
+// Bases are compared case-insensitively. Sequences too long to align are compared by position.
+pub fn mismatches(a: &[u8], b: &[u8], comparison: Comparison) -> Vec<bool> {
+    match comparison.for_lengths(a.len(), b.len()) {
+        Comparison::Positional => a
+            .iter()
+            .enumerate()
+            .map(|(i, base)| b.get(i).is_none_or(|other| !base.eq_ignore_ascii_case(other)))
+            .collect(),
+        Comparison::Aligned => {
+            let score = |x: u8, y: u8| if x.eq_ignore_ascii_case(&y) { 1 } else { -1 };
+            let alignment = Aligner::with_capacity(a.len(), b.len(), -5, -1, score).global(a, b);
+
+            let mut mismatches = vec![false; a.len()];
+            let mut position = 0;
+            for operation in alignment.operations {
+                match operation {
+                    AlignmentOperation::Match => position += 1,
+                    AlignmentOperation::Subst | AlignmentOperation::Ins => {
+                        mismatches[position] = true;
+                        position += 1;
+                    }
+                    // Bases only `b` has sit between two positions of `a`; mark the one before
+                    AlignmentOperation::Del => {
+                        if let Some(mismatch) = mismatches.get_mut(position.saturating_sub(1)) {
+                            *mismatch = true;
+                        }
+                    }
+                    AlignmentOperation::Xclip(_) | AlignmentOperation::Yclip(_) => {}
+                }
+            }
+            mismatches
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_by_position() {
+        let mismatches = mismatches(b"GATTACA", b"gaCTAC", Comparison::Positional);
+        assert_eq!(mismatches, [false, false, true, false, false, false, true]);
+    }
+
+    #[test]
+    fn alignment_keeps_positions_in_register_after_indels() {
+        // One inserted base in `b`: position-wise everything after it differs
+        let (a, b) = (b"ACGTACGTAC", b"ACGTTACGTAC");
+        assert_eq!(mismatches(a, b, Comparison::Positional).iter().filter(|&&m| m).count(), 6);
+        assert_eq!(mismatches(a, b, Comparison::Aligned).iter().filter(|&&m| m).count(), 1);
+
+        // A deleted base and a substitution
+        let mismatches = mismatches(b"ACGTACGTAC", b"ACGACGTTC", Comparison::Aligned);
+        assert_eq!(mismatches.iter().filter(|&&m| m).count(), 2);
+        assert!(mismatches[8]);
+    }
+
+    #[test]
+    fn long_sequences_are_compared_by_position() {
+        assert_eq!(Comparison::Aligned.for_lengths(5000, 5000), Comparison::Aligned);
+        assert_eq!(Comparison::Aligned.for_lengths(5001, 5000), Comparison::Positional);
+        assert_eq!(Comparison::Aligned.for_lengths(usize::MAX, 2), Comparison::Positional);
+    }
+}
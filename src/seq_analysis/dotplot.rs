@@ -0,0 +1,157 @@
+// Dot plots (`bio-rust dotplot`): every word (k-mer) of one sequence that is also a word of
+// the other is a dot at its position in both, the first along the x axis and the second down
+// the y axis. Against itself a sequence draws its main diagonal, with its repeats as lines
+// parallel to it; with the other strand, words whose reverse complement matches draw inverted
+// repeats as lines across it. The plot is binned to the image, each pixel counting the words
+// matching in its block of positions, and drawn on the CPU, so no window or GPU is needed.
+
+pub const DEFAULT_WORD: usize = 12;
+pub const DEFAULT_SIZE: u32 = 800;
+// Pixels along the longer side; a square plot this big holds 1 GiB of counts per strand
+pub const MAX_SIZE: u32 = 16_384;
+// Words of two bits per base fit a u64
+pub const MAX_WORD: usize = 32;
+// Words found more often than this in the first sequence are skipped, so low-complexity
+// stretches like poly-A runs don't take quadratic time
+pub const MAX_WORD_HITS: usize = 1000;
+
+// Dots on white: forward matches in dark blue, reverse complement ones in red
+const BACKGROUND: [f32; 3] = [255.0, 255.0, 255.0];
+const FORWARD_INK: [f32; 3] = [20.0, 30.0, 90.0];
+const REVERSE_INK: [f32; 3] = [210.0, 30.0, 30.0];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DotPlot {
+    pub width: u32,
+    pub height: u32,
+    // Matching words per pixel, top row first: the same word, and the reverse complement
+    // (empty when only the forward strand was compared)
+    pub forward: Vec<u32>,
+    pub reverse: Vec<u32>,
+}
+
+fn code(base: u8) -> Option<u64> {
+    match base.to_ascii_uppercase() {
+        b'A' => Some(0),
+        b'C' => Some(1),
+        b'G' => Some(2),
+        b'T' => Some(3),
+        _ => None,
+    }
+}
+
+// Each word without ambiguous bases as (start, code, code of its reverse complement)
+fn words(dna: &[u8], word: usize) -> impl Iterator<Item = (usize, u64, u64)> + '_ {
+    let mask = if word == MAX_WORD { u64::MAX } else { (1 << (2 * word)) - 1 };
+    let (mut forward, mut reverse, mut valid) = (0u64, 0u64, 0);
+    dna.iter().enumerate().filter_map(move |(i, &base)| {
+        let Some(code) = code(base) else {
+            valid = 0;
+            return None;
+        };
+        forward = ((forward << 2) | code) & mask;
+        reverse = (reverse >> 2) | ((3 - code) << (2 * (word - 1)));
+        valid += 1;
+        (valid >= word).then(|| (i + 1 - word, forward, reverse))
+    })
+}
+
+impl DotPlot {
+    // Plot `x` across and `y` down with words of `word` bases, the longer sequence `size`
+    // pixels long and the other in proportion, never more than a pixel per base
+    pub fn compute(x: &[u8], y: &[u8], word: usize, size: u32, both_strands: bool) -> Result<Self, String> {
+        if word == 0 || word > MAX_WORD {
+            return Err(format!("Invalid word size {word} (1 to {MAX_WORD} bases)"));
+        }
+        if size == 0 || size > MAX_SIZE {
+            return Err(format!("Invalid image size {size} (1 to {MAX_SIZE} pixels)"));
+        }
+        if x.len() < word || y.len() < word {
+            return Err(format!("Both sequences need at least {word} bases"));
+        }
+        let scale = (size as f64 / x.len().max(y.len()) as f64).min(1.0);
+        let (width, height) = ((x.len() as f64 * scale).ceil().max(1.0) as u32, (y.len() as f64 * scale).ceil().max(1.0) as u32);
+        let pixel = |position: usize, len: usize, pixels: u32| (position as u64 * pixels as u64 / len as u64) as usize;
+
+        // The words of x sorted by code, so each word of y finds its matches by binary search
+        let mut index: Vec<(u64, usize)> = words(x, word).map(|(start, forward, _)| (forward, start)).collect();
+        index.sort_unstable();
+        let matches = |code: u64| {
+            let from = index.partition_point(|&(other, _)| other < code);
+            let to = index.partition_point(|&(other, _)| other <= code);
+            if to - from > MAX_WORD_HITS { &index[0..0] } else { &index[from..to] }
+        };
+
+        let mut plot = Self { width, height, forward: vec![0; width as usize * height as usize], reverse: Vec::new() };
+        if both_strands {
+            plot.reverse = vec![0; plot.forward.len()];
+        }
+        for (start, forward, reverse) in words(y, word) {
+            let row = pixel(start, y.len(), height) * width as usize;
+            for &(_, position) in matches(forward) {
+                plot.forward[row + pixel(position, x.len(), width)] += 1;
+            }
+            if both_strands {
+                for &(_, position) in matches(reverse) {
+                    plot.reverse[row + pixel(position, x.len(), width)] += 1;
+                }
+            }
+        }
+        Ok(plot)
+    }
+
+    // Words matching on each strand
+    pub fn totals(&self) -> (u64, u64) {
+        let sum = |counts: &[u32]| counts.iter().map(|&count| count as u64).sum();
+        (sum(&self.forward), sum(&self.reverse))
+    }
+
+    // Tightly packed RGBA8 pixels, top row first, each pixel darker the more words match in
+    // it on a log scale up to the busiest pixel
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let busiest = self.forward.iter().chain(&self.reverse).copied().max().unwrap_or(0);
+        let shade = |count: u32| if count == 0 { 0.0 } else { (count as f32).ln_1p() / (busiest as f32).ln_1p() };
+        let mix = |from: [f32; 3], to: [f32; 3], amount: f32| [0, 1, 2].map(|channel| from[channel] + (to[channel] - from[channel]) * amount);
+        (0..self.forward.len())
+            .flat_map(|i| {
+                let color = mix(BACKGROUND, FORWARD_INK, shade(self.forward[i]));
+                let [r, g, b] = mix(color, REVERSE_INK, shade(self.reverse.get(i).copied().unwrap_or(0)));
+                [r.round() as u8, g.round() as u8, b.round() as u8, 255]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plots_repeats_and_inverted_repeats() {
+        // GATTACA twice, 10 bases apart, then its reverse complement TGTAATC
+        let dna = b"GATTACACCCCCCCCCCGATTACAGGGTGTAATC";
+        let plot = DotPlot::compute(dna, dna, 7, 100, true).unwrap();
+        assert_eq!((plot.width, plot.height), (dna.len() as u32, dna.len() as u32));
+        let at = |counts: &[u32], x: usize, y: usize| counts[y * dna.len() + x];
+        // The main diagonal, and the repeat off it both ways
+        assert_eq!(at(&plot.forward, 0, 0), 1);
+        assert_eq!((at(&plot.forward, 17, 0), at(&plot.forward, 0, 17)), (1, 1));
+        assert_eq!([(27, 0), (27, 17), (0, 27), (17, 27)].map(|(x, y)| at(&plot.reverse, x, y)), [1; 4]);
+        assert_eq!(at(&plot.reverse, 0, 0), 0);
+
+        // Binned down to a quarter of the size, the dots add up in the same pixels
+        let small = DotPlot::compute(dna, dna, 7, 8, false).unwrap();
+        assert!(DotPlot::compute(dna, dna, 7, MAX_SIZE + 1, false).is_err());
+        assert_eq!((small.width, small.height, small.reverse.len()), (8, 8, 0));
+        assert_eq!(small.totals().0, plot.totals().0);
+        let rgba = small.to_rgba();
+        assert_eq!(rgba.len(), 8 * 8 * 4);
+        assert_eq!(&rgba[4..8], &[255; 4]);
+        assert_ne!(&rgba[0..4], &[255; 4]);
+
+        assert!(DotPlot::compute(dna, dna, 0, 8, true).is_err());
+        assert!(DotPlot::compute(b"GATC", dna, 7, 8, true).is_err());
+        // Ambiguous bases match nothing
+        assert_eq!(DotPlot::compute(b"NNNNNNNNNN", b"NNNNNNNNNN", 3, 8, true).unwrap().totals(), (0, 0));
+    }
+}
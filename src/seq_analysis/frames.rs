@@ -0,0 +1,146 @@
+use std::ops::Range;
+
+use crate::seq_analysis::codon_usage::translate;
+use crate::seq_analysis::motif::Strand;
+
+// The six reading frames (`frame`, F and then 1 to 6): three codon offsets on each strand.
+// Frames +1 to +3 read the sequence from its first, second and third base; -1 to -3 read
+// the reverse complement the same way, from the last base backwards. Every base gets the
+// amino acid of the codon it is part of, so the grid can be seeded and colored by what the
+// sequence would code for in that frame.
+
+// Share of stop codons among random codons, 3 of 64
+pub const RANDOM_STOP_DENSITY: f64 = 3.0 / 64.0;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ReadingFrame {
+    pub strand: Strand,
+    // Bases skipped before the first codon, 0 to 2
+    pub offset: usize,
+}
+
+impl ReadingFrame {
+    // In the order of their keys, 1 to 6
+    pub const ALL: [ReadingFrame; 6] = [
+        ReadingFrame { strand: Strand::Forward, offset: 0 },
+        ReadingFrame { strand: Strand::Forward, offset: 1 },
+        ReadingFrame { strand: Strand::Forward, offset: 2 },
+        ReadingFrame { strand: Strand::Reverse, offset: 0 },
+        ReadingFrame { strand: Strand::Reverse, offset: 1 },
+        ReadingFrame { strand: Strand::Reverse, offset: 2 },
+    ];
+
+    pub fn number(self) -> usize {
+        Self::ALL.iter().position(|&frame| frame == self).unwrap_or(0) + 1
+    }
+}
+
+impl std::fmt::Display for ReadingFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let sign = if self.strand == Strand::Forward { '+' } else { '-' };
+        write!(f, "{sign}{}", self.offset + 1)
+    }
+}
+
+impl std::str::FromStr for ReadingFrame {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|frame| frame.to_string() == s).ok_or_else(|| format!("Invalid reading frame '{s}' (expected +1, +2, +3, -1, -2 or -3)"))
+    }
+}
+
+// Each whole codon of a frame as the forward-strand positions it covers and its amino acid,
+// None if it has ambiguous bases
+pub fn for_each_codon(dna: &[u8], frame: ReadingFrame, mut f: impl FnMut(Range<usize>, Option<char>)) {
+    match frame.strand {
+        Strand::Forward => {
+            for start in (frame.offset..dna.len().saturating_sub(2)).step_by(3) {
+                f(start..start + 3, translate(&dna[start..start + 3]));
+            }
+        }
+        Strand::Reverse => {
+            let mut end = dna.len().saturating_sub(frame.offset);
+            while end >= 3 {
+                let codon = [dna[end - 1], dna[end - 2], dna[end - 3]].map(bio::alphabets::dna::complement);
+                f(end - 3..end, translate(&codon));
+                end -= 3;
+            }
+        }
+    }
+}
+
+// Frame reading a codon at these forward-strand positions on a strand
+pub fn frame_of(len: usize, codon: &Range<usize>, strand: Strand) -> ReadingFrame {
+    let offset = match strand {
+        Strand::Forward => codon.start % 3,
+        Strand::Reverse => (len - codon.end) % 3,
+    };
+    ReadingFrame { strand, offset }
+}
+
+// Amino acid of the codon each base is part of in a frame, by forward-strand position; 0 for
+// the bases before the first whole codon and after the last, and in ambiguous codons
+pub fn residues(dna: &[u8], frame: ReadingFrame) -> Vec<u8> {
+    let mut residues = vec![0; dna.len()];
+    for_each_codon(dna, frame, |range, amino_acid| residues[range].fill(amino_acid.map_or(0, |amino_acid| amino_acid as u8)));
+    residues
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FrameStats {
+    pub frame: ReadingFrame,
+    pub codons: usize,
+    pub stops: usize,
+}
+
+impl FrameStats {
+    // Stops per codon; coding frames have far fewer than RANDOM_STOP_DENSITY
+    pub fn stop_density(&self) -> f64 {
+        self.stops as f64 / self.codons.max(1) as f64
+    }
+}
+
+// Whole codons and stop codons of all six frames, in key order
+pub fn stats(dna: &[u8]) -> Vec<FrameStats> {
+    ReadingFrame::ALL
+        .into_iter()
+        .map(|frame| {
+            let mut stats = FrameStats { frame, codons: 0, stops: 0 };
+            for_each_codon(dna, frame, |_, amino_acid| {
+                stats.codons += 1;
+                stats.stops += usize::from(amino_acid == Some('*'));
+            });
+            stats
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translates_all_six_frames() {
+        // ATG AAA TAG CCC forward, GGG CTA TTT CAT on the reverse complement
+        let dna = b"ATGAAATAGCCC";
+        let [plus_one, plus_two, _, minus_one, minus_two, _] = ReadingFrame::ALL;
+        assert_eq!(residues(dna, plus_one), b"MMMKKK***PPP");
+        assert_eq!(residues(dna, plus_two), b"\0***NNNSSS\0\0");
+        assert_eq!(residues(dna, minus_one), b"HHHFFFLLLGGG");
+        // GGC, TAT and TTC read backwards from the second-last base
+        assert_eq!(residues(dna, minus_two), b"\0\0FFFYYYGGG\0");
+
+        let stats = stats(dna);
+        assert_eq!(stats.iter().map(|stats| (stats.codons, stats.stops)).collect::<Vec<_>>(), vec![(4, 1), (3, 1), (3, 0), (4, 0), (3, 0), (3, 0)]);
+        assert_eq!(stats[0].stop_density(), 0.25);
+
+        assert_eq!(minus_two.to_string(), "-2");
+        assert_eq!("-2".parse(), Ok(minus_two));
+        assert_eq!(minus_two.number(), 5);
+        assert!("4".parse::<ReadingFrame>().is_err());
+        assert_eq!(frame_of(dna.len(), &(8..11), Strand::Reverse), minus_two);
+        assert_eq!(frame_of(dna.len(), &(4..7), Strand::Forward), plus_two);
+        assert!(residues(b"AT", plus_one).iter().all(|&residue| residue == 0));
+    }
+}
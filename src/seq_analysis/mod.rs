@@ -0,0 +1,22 @@
+pub mod cache;
+pub mod codon_usage;
+#[cfg(feature = "analysis")]
+pub mod complexity;
+#[cfg(feature = "analysis")]
+pub mod difference;
+#[cfg(feature = "analysis")]
+pub mod dotplot;
+pub mod frames;
+pub mod motif;
+#[cfg(feature = "analysis")]
+pub mod pcr;
+#[cfg(feature = "analysis")]
+pub mod primers;
+#[cfg(feature = "analysis")]
+pub mod pwm;
+#[cfg(feature = "analysis")]
+pub mod repeats;
+pub mod shuffle;
+#[cfg(feature = "analysis")]
+pub mod signals;
+pub mod skew;
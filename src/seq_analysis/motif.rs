@@ -0,0 +1,156 @@
+use regex::bytes::Regex;
+
+// Motif search over both strands. Reverse strand matches are reported in forward
+// coordinates, so `start..end` always indexes the sequence as given.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Motif {
+    // IUPAC nucleotide code string such as "GAANTC"
+    Iupac(String),
+    // Regular expression over the bases, e.g. "GA+TC"
+    Regex(String),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Strand {
+    Forward,
+    Reverse,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+    pub strand: Strand,
+}
+
+impl Motif {
+    pub fn search(&self, seq: &[u8]) -> Result<Vec<Match>, String> {
+        match self {
+            Motif::Iupac(motif) => search_iupac(seq, motif),
+            Motif::Regex(pattern) => search_regex(seq, pattern),
+        }
+    }
+}
+
+impl std::fmt::Display for Motif {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Motif::Iupac(motif) => write!(f, "{motif}"),
+            Motif::Regex(pattern) => write!(f, "/{pattern}/"),
+        }
+    }
+}
+
+// Bases a nucleotide code stands for, one bit each for A, C, G and T
+pub fn iupac_mask(code: u8) -> Option<u8> {
+    let (a, c, g, t) = (1, 2, 4, 8);
+    Some(match code.to_ascii_uppercase() {
+        b'A' => a,
+        b'C' => c,
+        b'G' => g,
+        b'T' | b'U' => t,
+        b'R' => a | g,
+        b'Y' => c | t,
+        b'S' => c | g,
+        b'W' => a | t,
+        b'K' => g | t,
+        b'M' => a | c,
+        b'B' => c | g | t,
+        b'D' => a | g | t,
+        b'H' => a | c | t,
+        b'V' => a | c | g,
+        b'N' => a | c | g | t,
+        _ => return None,
+    })
+}
+
+// Swapping A<->T and C<->G bits complements any code, degenerate or not
+pub fn complement_mask(mask: u8) -> u8 {
+    (mask & 1) << 3 | (mask & 2) << 1 | (mask & 4) >> 1 | (mask & 8) >> 3
+}
+
+// Every (overlapping) occurrence of an IUPAC motif. A sequence base matches when all the
+// bases it stands for are allowed by the motif, so an N in the sequence only matches N.
+pub fn search_iupac(seq: &[u8], motif: &str) -> Result<Vec<Match>, String> {
+    let forward = motif
+        .bytes()
+        .map(|code| iupac_mask(code).ok_or_else(|| format!("Invalid IUPAC code '{}' in motif '{motif}'", code as char)))
+        .collect::<Result<Vec<u8>, String>>()?;
+    if forward.is_empty() {
+        return Err("Empty motif".to_string());
+    }
+    let reverse: Vec<u8> = forward.iter().rev().map(|&mask| complement_mask(mask)).collect();
+
+    let seq: Vec<u8> = seq.iter().map(|&base| iupac_mask(base).unwrap_or(0)).collect();
+    let matches = |motif: &[u8], window: &[u8]| window.iter().zip(motif).all(|(&base, &allowed)| base != 0 && base & !allowed == 0);
+
+    let mut found = Vec::new();
+    for (start, window) in seq.windows(forward.len()).enumerate() {
+        let end = start + forward.len();
+        let on_forward = matches(&forward, window);
+        if on_forward {
+            found.push(Match { start, end, strand: Strand::Forward });
+        }
+        // Palindromic sites such as GAATTC would otherwise be reported twice
+        if !on_forward && matches(&reverse, window) {
+            found.push(Match { start, end, strand: Strand::Reverse });
+        }
+    }
+    Ok(found)
+}
+
+// Non-overlapping regex matches on each strand, case-insensitive
+pub fn search_regex(seq: &[u8], pattern: &str) -> Result<Vec<Match>, String> {
+    let regex = Regex::new(&format!("(?i){pattern}")).map_err(|err| format!("Invalid regex '{pattern}': {err}"))?;
+    let reverse_complement = bio::alphabets::dna::revcomp(seq);
+
+    let mut found: Vec<Match> = regex
+        .find_iter(seq)
+        .filter(|m| !m.is_empty())
+        .map(|m| Match { start: m.start(), end: m.end(), strand: Strand::Forward })
+        .collect();
+    found.extend(
+        regex
+            .find_iter(&reverse_complement)
+            .filter(|m| !m.is_empty())
+            .map(|m| Match { start: seq.len() - m.end(), end: seq.len() - m.start(), strand: Strand::Reverse }),
+    );
+    found.sort_by_key(|m| (m.start, m.strand == Strand::Reverse));
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn starts(matches: &[Match], strand: Strand) -> Vec<usize> {
+        matches.iter().filter(|m| m.strand == strand).map(|m| m.start).collect()
+    }
+
+    #[test]
+    fn iupac_matches_both_strands() {
+        // GAANTC: GAATTC (a palindrome, reported once) and GAAGTC at 8, GACTTC (reverse of GAAGTC) at 16
+        let found = search_iupac(b"CGAATTCAGAAGTCAAGACTTC", "GAANTC").unwrap();
+        assert_eq!(starts(&found, Strand::Forward), vec![1, 8]);
+        assert_eq!(starts(&found, Strand::Reverse), vec![16]);
+        assert!(found.iter().all(|m| m.end - m.start == 6));
+
+        let found = search_iupac(b"acgt", "RYN").unwrap();
+        assert_eq!((starts(&found, Strand::Forward), starts(&found, Strand::Reverse)), (vec![0], vec![1]));
+        assert!(search_iupac(b"ACGT", "GXA").is_err());
+        // An N in the sequence is not known to be an A, but is covered by N
+        assert!(search_iupac(b"AN", "AA").unwrap().is_empty());
+        assert_eq!(search_iupac(b"AN", "AN").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn regex_matches_map_back_to_forward_coordinates() {
+        let found = search_regex(b"TTGAAATCCCGGATTTC", "GA+TC").unwrap();
+        assert_eq!(found, vec![
+            Match { start: 2, end: 8, strand: Strand::Forward },
+            // GAAATC on the reverse strand is GATTTC on the forward one
+            Match { start: 11, end: 17, strand: Strand::Reverse },
+        ]);
+        assert!(search_regex(b"ACGT", "(").is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::seq_analysis::motif::{Strand, complement_mask, iupac_mask};
+
+// In-silico PCR: where the primers of a pair bind, with up to a given number of mismatches,
+// and the products they would amplify. Primers are written 5' to 3' in IUPAC codes. A primer
+// binding the forward strand as written extends rightwards, one whose reverse complement is
+// found extends leftwards, and a product runs from a rightward site to a leftward site
+// downstream of it, either primer acting as either. Sites are in forward coordinates, like
+// motif matches.
+
+// Bases at a primer's 3' end that must match: a mismatch there stops the polymerase
+pub const THREE_PRIME_CLAMP: usize = 3;
+pub const DEFAULT_MAX_MISMATCHES: usize = 2;
+// Shorter primers, or more mismatches, bind all over a genome and make the pairing explode
+pub const MIN_PRIMER_LEN: usize = 15;
+pub const MAX_MISMATCHES: usize = 5;
+// Longest product reported, in bases
+pub const MAX_PRODUCT: usize = 10_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrimerPair {
+    pub forward: String,
+    pub reverse: String,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Primer {
+    Forward,
+    Reverse,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Site {
+    pub start: usize,
+    pub end: usize,
+    pub strand: Strand,
+    pub primer: Primer,
+    pub mismatches: usize,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Amplicon {
+    // The rightward site, the leftward site and the bases between them
+    pub start: usize,
+    pub end: usize,
+    pub left: Site,
+    pub right: Site,
+}
+
+impl Amplicon {
+    // Product size in bases, primers included
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end == self.start
+    }
+}
+
+// Every site where `primer` binds with at most `max_mismatches` mismatches, none of them in
+// the clamp at its 3' end. As with motifs, an N in the sequence matches only an N.
+pub fn binding_sites(seq: &[u8], primer: &str, which: Primer, max_mismatches: usize) -> Result<Vec<Site>, String> {
+    let forward = primer
+        .bytes()
+        .map(|code| iupac_mask(code).ok_or_else(|| format!("Invalid IUPAC code '{}' in primer '{primer}'", code as char)))
+        .collect::<Result<Vec<u8>, String>>()?;
+    if forward.len() < MIN_PRIMER_LEN {
+        return Err(format!("Primer '{primer}' is too short, expected at least {MIN_PRIMER_LEN} bases"));
+    }
+    if max_mismatches > MAX_MISMATCHES {
+        return Err(format!("Up to {MAX_MISMATCHES} mismatches can be allowed, not {max_mismatches}"));
+    }
+    let reverse: Vec<u8> = forward.iter().rev().map(|&mask| complement_mask(mask)).collect();
+    let len = forward.len();
+
+    let seq: Vec<u8> = seq.iter().map(|&base| iupac_mask(base).unwrap_or(0)).collect();
+    // Mismatches of `window` against `masks`, or None if one falls in `clamp`
+    let mismatches = |masks: &[u8], window: &[u8], clamp: std::ops::Range<usize>| {
+        let mut count = 0;
+        for (i, (&base, &allowed)) in window.iter().zip(masks).enumerate() {
+            if base == 0 || base & !allowed != 0 {
+                if clamp.contains(&i) {
+                    return None;
+                }
+                count += 1;
+            }
+        }
+        Some(count)
+    };
+
+    let mut sites = Vec::new();
+    for (start, window) in seq.windows(len).enumerate() {
+        let end = start + len;
+        // The 3' end is the window's end on the forward strand and its start on the reverse
+        let strands = [(Strand::Forward, &forward, len - THREE_PRIME_CLAMP..len), (Strand::Reverse, &reverse, 0..THREE_PRIME_CLAMP)];
+        for (strand, masks, clamp) in strands {
+            if let Some(mismatches) = mismatches(masks, window, clamp).filter(|&count| count <= max_mismatches) {
+                sites.push(Site { start, end, strand, primer: which, mismatches });
+            }
+        }
+    }
+    Ok(sites)
+}
+
+// Binding sites of both primers and the products of up to `max_product` bases between them,
+// ordered by start. Each rightward site is only paired with the leftward sites starting
+// within `max_product` bases downstream of it, found by binary search.
+pub fn amplify(seq: &[u8], primers: &PrimerPair, max_mismatches: usize, max_product: usize) -> Result<(Vec<Site>, Vec<Amplicon>), String> {
+    let mut sites = binding_sites(seq, &primers.forward, Primer::Forward, max_mismatches)?;
+    sites.extend(binding_sites(seq, &primers.reverse, Primer::Reverse, max_mismatches)?);
+    sites.sort_by_key(|site| (site.start, site.strand == Strand::Reverse));
+
+    let leftward: Vec<&Site> = sites.iter().filter(|site| site.strand == Strand::Reverse).collect();
+    let mut amplicons = Vec::new();
+    for left in sites.iter().filter(|site| site.strand == Strand::Forward) {
+        let first = leftward.partition_point(|right| right.start < left.start);
+        let downstream = leftward[first..].iter().take_while(|right| right.start - left.start <= max_product);
+        let fitting = downstream.filter(|right| right.end - left.start <= max_product);
+        amplicons.extend(fitting.map(|&&right| Amplicon { start: left.start, end: right.end, left: *left, right }));
+    }
+    amplicons.sort_by_key(|amplicon| (amplicon.start, amplicon.end));
+    Ok((sites, amplicons))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn revcomp(seq: &str) -> String {
+        String::from_utf8(bio::alphabets::dna::revcomp(seq.as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn amplifies_between_a_known_primer_pair() {
+        // M13/pUC forward and reverse primers around a 60 base insert
+        let pair = PrimerPair { forward: "GTAAAACGACGGCCAGT".into(), reverse: "CAGGAAACAGCTATGAC".into() };
+        let insert = "ACGTTGCAAGGCTTAACCGGTTAGCATGCAAATTTCCCGGGATATCGCGCTTAAGGCCAT";
+        let template = format!("TTTT{}{insert}{}TTTT", pair.forward, revcomp(&pair.reverse));
+        let (sites, amplicons) = amplify(template.as_bytes(), &pair, 0, MAX_PRODUCT).unwrap();
+        assert_eq!(sites.len(), 2);
+        assert_eq!(amplicons.len(), 1);
+        assert_eq!((amplicons[0].start, amplicons[0].len()), (4, 17 + 60 + 17));
+        assert_eq!((amplicons[0].left.primer, amplicons[0].right.primer), (Primer::Forward, Primer::Reverse));
+
+        // On the other strand the product is the same size, primed the other way round
+        let (_, flipped) = amplify(revcomp(&template).as_bytes(), &pair, 0, MAX_PRODUCT).unwrap();
+        assert_eq!(flipped.iter().map(Amplicon::len).collect::<Vec<_>>(), [94]);
+        assert_eq!(flipped[0].left.primer, Primer::Reverse);
+        // Too long a product for the limit
+        assert!(amplify(template.as_bytes(), &pair, 0, 90).unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn tolerates_mismatches_away_from_the_three_prime_end() {
+        // 16S rRNA 27F, degenerate at M (A or C), and 1492R
+        let pair = PrimerPair { forward: "AGAGTTTGATCMTGGCTCAG".into(), reverse: "GGTTACCTTGTTACGACTT".into() };
+        let left = "AGAGTTTGATCCTGGCTCAG";
+        // Two mismatches near the 5' end of the reverse primer's site
+        let right = revcomp("GCTTACCTAGTTACGACTT");
+        let template = format!("CCCC{left}{}{right}CCCC", "ATGC".repeat(350));
+        let amplicons = |max_mismatches| amplify(template.as_bytes(), &pair, max_mismatches, MAX_PRODUCT).unwrap().1;
+        assert!(amplicons(1).is_empty());
+        let found = amplicons(2);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].len(), found[0].right.mismatches, found[0].left.mismatches), (20 + 1400 + 19, 2, 0));
+
+        // A mismatch in the last three bases blocks binding however many are allowed
+        let clamped = template.replacen(left, "AGAGTTTGATCCTGGCTCAT", 1);
+        assert!(amplify(clamped.as_bytes(), &pair, 5, MAX_PRODUCT).unwrap().1.is_empty());
+        assert!(binding_sites(b"ACGT", "ACGTACGTACGTAC", Primer::Forward, 0).unwrap_err().contains("too short"));
+        assert!(binding_sites(b"ACGT", "ACGX", Primer::Forward, 0).is_err());
+        assert!(binding_sites(b"ACGT", left, Primer::Forward, MAX_MISMATCHES + 1).is_err());
+    }
+}
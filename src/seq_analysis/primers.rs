@@ -0,0 +1,219 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::ops::Range;
+
+use crate::seq_analysis::motif::Strand;
+use crate::tracks::melting_temperature;
+
+// Primer design: every stretch of a region that would make a primer within the length,
+// melting temperature and GC constraints, and that can't pair with itself over too long a
+// run, ranked by how close it comes to the middle of the Tm and GC ranges. Reverse primers
+// are the reverse complements of the same stretches, so both strands are covered.
+
+// Longest region and primer designed for
+pub const MAX_REGION: usize = 100_000;
+pub const MAX_LENGTH: usize = 60;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrimerConstraints {
+    // Inclusive ranges
+    pub length: (usize, usize),
+    pub tm: (f64, f64),
+    // Percent G and C
+    pub gc: (f64, f64),
+    // Longest run of bases a primer may pair with itself, folded back or as a dimer
+    pub max_self_complementarity: usize,
+}
+
+impl Default for PrimerConstraints {
+    fn default() -> Self {
+        Self { length: (18, 25), tm: (52.0, 62.0), gc: (40.0, 60.0), max_self_complementarity: 4 }
+    }
+}
+
+impl PrimerConstraints {
+    pub fn validate(&self) -> Result<(), String> {
+        let (min, max) = self.length;
+        if min == 0 || min > max || max > MAX_LENGTH {
+            return Err(format!("Invalid primer lengths {min} to {max}, expected 1 to {MAX_LENGTH} bases with the minimum first"));
+        }
+        for (what, (min, max)) in [("Tm", self.tm), ("GC", self.gc)] {
+            if min > max {
+                return Err(format!("Invalid {what} range {min} to {max}, expected the minimum first"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for PrimerConstraints {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "length {} {} tm {} {} gc {} {} self {}",
+            self.length.0, self.length.1, self.tm.0, self.tm.1, self.gc.0, self.gc.1, self.max_self_complementarity
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Candidate {
+    // The stretch primed, in forward coordinates
+    pub start: usize,
+    pub end: usize,
+    pub strand: Strand,
+    // 5' to 3'
+    pub sequence: String,
+    pub tm: f64,
+    pub gc: f64,
+    pub self_complementarity: usize,
+}
+
+// Longest run of consecutive bases of `primer` that pair with another stretch of it
+// antiparallel, at any offset: the primer slid along its own reverse complement
+pub fn self_complementarity(primer: &[u8]) -> usize {
+    let reverse = bio::alphabets::dna::revcomp(primer);
+    let n = primer.len() as isize;
+    let mut longest = 0;
+    for shift in 1 - n..n {
+        let mut run = 0;
+        for i in 0.max(-shift)..n.min(n - shift) {
+            run = if primer[i as usize] == reverse[(i + shift) as usize] { run + 1 } else { 0 };
+            longest = longest.max(run);
+        }
+    }
+    longest
+}
+
+// A stretch that fits the constraints, ordered by its penalty so the heap keeps the best
+struct Ranked {
+    penalty: f64,
+    candidate: Candidate,
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.penalty.total_cmp(&other.penalty).then(self.candidate.start.cmp(&other.candidate.start)).then(self.candidate.end.cmp(&other.candidate.end))
+    }
+}
+
+// Up to `count` candidates on each strand within `range` of `dna`, best first. Candidates
+// overlapping a better one on the same strand are left out, so they spread over the region.
+pub fn design(dna: &[u8], range: Range<usize>, constraints: &PrimerConstraints, count: usize) -> Result<Vec<Candidate>, String> {
+    constraints.validate()?;
+    let range = range.start.min(dna.len())..range.end.min(dna.len());
+    if range.len() > MAX_REGION {
+        return Err(format!("Primers are designed for up to {MAX_REGION} bases, not {}", range.len()));
+    }
+    let (tm_mid, gc_mid) = ((constraints.tm.0 + constraints.tm.1) / 2.0, (constraints.gc.0 + constraints.gc.1) / 2.0);
+    // Every pick passes over at most the stretches overlapping it, so only that many more
+    // than `count` of the best ones are kept
+    let (min_len, max_len) = constraints.length;
+    let overlapping = 2 * max_len * (max_len - min_len + 1);
+    let kept = count.saturating_mul(overlapping + 1);
+    let mut found = BinaryHeap::new();
+    for start in range.clone() {
+        for len in min_len..=max_len {
+            let Some(bases) = dna.get(start..start + len).filter(|_| start + len <= range.end) else { break };
+            let bases = bases.to_ascii_uppercase();
+            if !bases.iter().all(|base| b"ACGT".contains(base)) {
+                continue;
+            }
+            let gc = bases.iter().filter(|&&base| base == b'G' || base == b'C').count() as f64 * 100.0 / len as f64;
+            let tm = melting_temperature(&bases);
+            if !(constraints.gc.0..=constraints.gc.1).contains(&gc) || !(constraints.tm.0..=constraints.tm.1).contains(&tm) {
+                continue;
+            }
+            let self_complementarity = self_complementarity(&bases);
+            if self_complementarity > constraints.max_self_complementarity {
+                continue;
+            }
+            // A degree off the middle Tm weighs as much as 5% off the middle GC
+            let penalty = (tm - tm_mid).abs() + (gc - gc_mid).abs() / 5.0;
+            let sequence = String::from_utf8_lossy(&bases).into_owned();
+            found.push(Ranked { penalty, candidate: Candidate { start, end: start + len, strand: Strand::Forward, sequence, tm, gc, self_complementarity } });
+            if found.len() > kept {
+                found.pop();
+            }
+        }
+    }
+
+    // Both strands rank the same stretches alike, so they are picked once
+    let mut picked: Vec<Candidate> = Vec::new();
+    for Ranked { candidate, .. } in found.into_sorted_vec() {
+        if picked.len() == count {
+            break;
+        }
+        if picked.iter().all(|other| candidate.end <= other.start || other.end <= candidate.start) {
+            picked.push(candidate);
+        }
+    }
+    let reverse = picked.iter().map(|candidate| {
+        let sequence = String::from_utf8_lossy(&bio::alphabets::dna::revcomp(candidate.sequence.as_bytes())).into_owned();
+        Candidate { strand: Strand::Reverse, sequence, ..candidate.clone() }
+    });
+    let reverse: Vec<Candidate> = reverse.collect();
+    picked.extend(reverse);
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scores_self_complementarity() {
+        // GAATTC pairs with itself over all six bases
+        assert_eq!(self_complementarity(b"GAATTC"), 6);
+        assert_eq!(self_complementarity(b"AAAAAAAA"), 0);
+        // A palindrome pairs with itself end to end
+        assert_eq!(self_complementarity(b"AAAACGCGTTTT"), 12);
+        assert_eq!(self_complementarity(b"AAAACCCTTTT"), 4);
+    }
+
+    #[test]
+    fn proposes_primers_within_the_constraints() {
+        let dna = b"ATATATATATATATATATATGCTGACCTGAAGCTTGCAGTCCAAGGTACTGATCGTTGACCATGAACGTTGCATGGCTTGACATTATATATATATATATATAT";
+        let constraints = PrimerConstraints::default();
+        let candidates = design(dna, 0..dna.len(), &constraints, 3).unwrap();
+        assert!(!candidates.is_empty());
+        for candidate in &candidates {
+            let len = candidate.end - candidate.start;
+            assert!((18..=25).contains(&len) && candidate.sequence.len() == len);
+            assert!((52.0..=62.0).contains(&candidate.tm) && (40.0..=60.0).contains(&candidate.gc));
+            assert!(candidate.self_complementarity <= 4);
+            // The AT repeats are too low in GC and pair with themselves
+            assert!((14..90).contains(&candidate.start));
+            let forward = &dna[candidate.start..candidate.end];
+            let expected = if candidate.strand == Strand::Forward { forward.to_vec() } else { bio::alphabets::dna::revcomp(forward) };
+            assert_eq!(candidate.sequence.as_bytes(), expected);
+        }
+        let forward: Vec<_> = candidates.iter().filter(|candidate| candidate.strand == Strand::Forward).collect();
+        assert!(forward.len() <= 3);
+        assert!(forward.windows(2).all(|pair| pair[0].end <= pair[1].start || pair[1].end <= pair[0].start));
+
+        // Nothing fits outside the region or under impossible constraints
+        assert!(design(dna, 0..20, &constraints, 3).unwrap().is_empty());
+        assert!(design(dna, 0..dna.len(), &PrimerConstraints { tm: (80.0, 90.0), ..constraints.clone() }, 3).unwrap().is_empty());
+
+        // Swapped or oversized limits and huge regions are refused
+        assert!(design(dna, 0..dna.len(), &PrimerConstraints { length: (25, 18), ..constraints.clone() }, 3).is_err());
+        assert!(design(dna, 0..dna.len(), &PrimerConstraints { gc: (60.0, 40.0), ..constraints.clone() }, 3).is_err());
+        assert!(design(dna, 0..dna.len(), &PrimerConstraints { length: (18, 500), ..constraints.clone() }, 3).is_err());
+        assert!(design(&vec![b'A'; MAX_REGION + 1], 0..MAX_REGION + 1, &constraints, 3).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use std::path::Path;
+
+// Position weight matrices: per-position base counts (a PFM, as distributed by JASPAR)
+// turned into log-odds scores against a background model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pwm {
+    pub name: String,
+    // A, C, G, T counts for each motif position
+    pub counts: Vec<[f64; 4]>,
+}
+
+// Base frequencies the motif is scored against
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Background {
+    Uniform,
+    // Composition of the scanned sequence itself
+    Sequence,
+    // A, C, G, T frequencies, normalised when used
+    Frequencies([f64; 4]),
+}
+
+// Pseudocount spread over the bases by background frequency, so unseen bases aren't -inf
+const PSEUDOCOUNT: f64 = 0.8;
+
+fn base_index(base: u8) -> Option<usize> {
+    match base.to_ascii_uppercase() {
+        b'A' => Some(0),
+        b'C' => Some(1),
+        b'G' => Some(2),
+        b'T' | b'U' => Some(3),
+        _ => None,
+    }
+}
+
+impl Background {
+    pub fn frequencies(&self, seq: &[u8]) -> [f64; 4] {
+        let weights = match self {
+            Background::Uniform => [1.0; 4],
+            Background::Sequence => {
+                // Start from one of each so a base absent from the sequence still has a frequency
+                let mut counts = [1.0; 4];
+                seq.iter().filter_map(|&base| base_index(base)).for_each(|i| counts[i] += 1.0);
+                counts
+            }
+            Background::Frequencies(frequencies) => *frequencies,
+        };
+        let total: f64 = weights.iter().sum();
+        weights.map(|weight| weight / total)
+    }
+}
+
+impl std::fmt::Display for Background {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Background::Uniform => write!(f, "uniform"),
+            Background::Sequence => write!(f, "sequence"),
+            Background::Frequencies([a, c, g, t]) => write!(f, "{a} {c} {g} {t}"),
+        }
+    }
+}
+
+impl Pwm {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let _span = tracing::debug_span!("read", path = %path.display()).entered();
+        let text = std::fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        tracing::debug!("Read {} bytes", text.len());
+        let mut pwm = Self::parse(&text).map_err(|err| format!("{}: {err}", path.display()))?;
+        if pwm.name.is_empty() {
+            pwm.name = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+        }
+        Ok(pwm)
+    }
+
+    // Reads a JASPAR matrix ("A [ 4 19 0 ... ]" rows under a ">ID name" header) or a bare
+    // PFM of four rows of counts in A, C, G, T order
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut name = String::new();
+        let mut rows: Vec<Vec<f64>> = Vec::new();
+
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(header) = line.strip_prefix('>') {
+                name = header.trim().to_string();
+                continue;
+            }
+            let mut line = line;
+            if let Some((base, rest)) = line.split_once(|c: char| c.is_whitespace() || c == '[')
+                && base.len() == 1
+                && let Some(expected) = base.bytes().next().and_then(base_index)
+            {
+                if expected != rows.len() {
+                    return Err(format!("Row '{base}' is out of order, expected A, C, G, T"));
+                }
+                line = rest;
+            }
+            let row = line
+                .split(|c: char| c.is_whitespace() || c == '[' || c == ']')
+                .filter(|word| !word.is_empty())
+                .map(|word| word.parse::<f64>().map_err(|_| format!("Invalid count '{word}'")))
+                .collect::<Result<Vec<f64>, String>>()?;
+            rows.push(row);
+        }
+
+        if rows.len() != 4 {
+            return Err(format!("Expected 4 rows (A, C, G, T), found {}", rows.len()));
+        }
+        let width = rows[0].len();
+        if width == 0 || rows.iter().any(|row| row.len() != width) {
+            return Err("Rows must have the same, non-zero number of columns".to_string());
+        }
+        if rows.iter().flatten().any(|&count| count < 0.0 || !count.is_finite()) {
+            return Err("Counts must be non-negative numbers".to_string());
+        }
+
+        let counts = (0..width).map(|i| [rows[0][i], rows[1][i], rows[2][i], rows[3][i]]).collect();
+        Ok(Self { name, counts })
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    // log2(P(base | motif position) / P(base | background)) for every position
+    pub fn log_odds(&self, background: &[f64; 4]) -> Vec<[f64; 4]> {
+        self.counts
+            .iter()
+            .map(|counts| {
+                let total: f64 = counts.iter().sum();
+                std::array::from_fn(|i| {
+                    let probability = (counts[i] + PSEUDOCOUNT * background[i]) / (total + PSEUDOCOUNT);
+                    (probability / background[i]).log2()
+                })
+            })
+            .collect()
+    }
+
+    // Relative score (0 = worst possible, 1 = best possible) of the site starting at each
+    // position, the better of both strands. None where the motif doesn't fit or the site
+    // contains anything but A, C, G and T.
+    pub fn scan(&self, seq: &[u8], background: Background) -> Vec<Option<f64>> {
+        let forward = self.log_odds(&background.frequencies(seq));
+        // Reverse complement: positions reversed, A<->T and C<->G swapped
+        let reverse: Vec<[f64; 4]> = forward.iter().rev().map(|scores| [scores[3], scores[2], scores[1], scores[0]]).collect();
+
+        let min: f64 = forward.iter().map(|scores| scores.iter().cloned().fold(f64::INFINITY, f64::min)).sum();
+        let max: f64 = forward.iter().map(|scores| scores.iter().cloned().fold(f64::NEG_INFINITY, f64::max)).sum();
+        let range = if max > min { max - min } else { 1.0 };
+
+        let score = |matrix: &[[f64; 4]], site: &[u8]| -> Option<f64> {
+            site.iter().zip(matrix).map(|(&base, scores)| base_index(base).map(|i| scores[i])).sum()
+        };
+
+        let mut scores = vec![None; seq.len()];
+        for (start, site) in seq.windows(self.len()).enumerate() {
+            if let (Some(forward), Some(reverse)) = (score(&forward, site), score(&reverse, site)) {
+                scores[start] = Some((forward.max(reverse) - min) / range);
+            }
+        }
+        scores
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JASPAR: &str = ">MA0004.1 Arnt
+A  [ 4 19  0  0  0  0 ]
+C  [16  0 20  0  0  0 ]
+G  [ 0  1  0 20  0 20 ]
+T  [ 0  0  0  0 20  0 ]
+";
+
+    #[test]
+    fn parses_jaspar_and_bare_pfm() {
+        let pwm = Pwm::parse(JASPAR).unwrap();
+        assert_eq!(pwm.name, "MA0004.1 Arnt");
+        assert_eq!(pwm.len(), 6);
+        assert_eq!(pwm.counts[1], [19.0, 0.0, 1.0, 0.0]);
+
+        let bare = Pwm::parse("4 19 0 0 0 0\n16 0 20 0 0 0\n0 1 0 20 0 20\n0 0 0 0 20 0\n").unwrap();
+        assert_eq!(bare.counts, pwm.counts);
+
+        assert!(Pwm::parse("1 2\n3 4\n5 6").is_err());
+        assert!(Pwm::parse("C [1 2]\nA [1 2]\nG [1 2]\nT [1 2]").is_err());
+        assert!(Pwm::parse("1 2\n3\n5 6\n7 8").is_err());
+    }
+
+    #[test]
+    fn scores_consensus_highest_on_both_strands() {
+        let pwm = Pwm::parse(JASPAR).unwrap();
+        // CACGTG (the consensus, a palindrome) at 2, a weak site at 10, CACGTG again at 18
+        let seq = b"TTCACGTGTTAAAAAATTCACGTGNN";
+        let scores = pwm.scan(seq, Background::Uniform);
+
+        assert_eq!(scores.len(), seq.len());
+        assert!((scores[2].unwrap() - 1.0).abs() < 0.05);
+        assert!(scores[10].unwrap() < 0.5);
+        assert_eq!(scores[2], scores[18]);
+        // Sites running into the Ns, or past the end, have no score
+        assert_eq!(scores[19], None);
+        assert_eq!(scores[25], None);
+    }
+
+    #[test]
+    fn background_changes_log_odds() {
+        let pwm = Pwm::parse(JASPAR).unwrap();
+        let uniform = pwm.log_odds(&Background::Uniform.frequencies(b""));
+        let gc_rich = pwm.log_odds(&Background::Frequencies([1.0, 4.0, 4.0, 1.0]).frequencies(b""));
+        // C at position 0 is less surprising against a GC-rich background
+        assert!(gc_rich[0][1] < uniform[0][1]);
+        assert_eq!(Background::Sequence.frequencies(b"AAAAAA"), [0.7, 0.1, 0.1, 0.1]);
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+// Tandem repeats (`repeats`): a unit of a few bases copied several times in a row, like the
+// microsatellite ATATATAT. For every unit size the sequence is compared with itself shifted by
+// that size: each k-mer equal to the k-mer right after it seeds a repeat, extended while the
+// bases keep matching the ones a unit further on. Units made of a shorter one (ATAT of AT)
+// are left to that one, and where repeats of different units overlap the longest is kept.
+
+// Largest unit the finder accepts, to keep a scan to a few passes over the sequence
+pub const MAX_UNIT: usize = 50;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RepeatParams {
+    // Inclusive range of unit sizes in bases
+    pub unit: (usize, usize),
+    // Whole copies a repeat needs
+    pub min_copies: usize,
+}
+
+impl Default for RepeatParams {
+    fn default() -> Self {
+        Self { unit: (2, 6), min_copies: 4 }
+    }
+}
+
+impl std::fmt::Display for RepeatParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "unit {} {} copies {}", self.unit.0, self.unit.1, self.min_copies)
+    }
+}
+
+// "unit <min> <max>" and "copies <n>" in any order; missing ones keep their default
+impl std::str::FromStr for RepeatParams {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut params = Self::default();
+        let mut words = s.split_whitespace();
+        while let Some(option) = words.next() {
+            let mut value = |what: &str| {
+                let value = words.next().ok_or_else(|| format!("Missing {what} after '{option}'"))?;
+                value.parse::<usize>().map_err(|_| format!("Invalid {what} '{value}'"))
+            };
+            match option {
+                "unit" => params.unit = (value("minimum unit")?, value("maximum unit")?),
+                "copies" => params.min_copies = value("number of copies")?,
+                _ => return Err(format!("Unknown repeat option '{option}' (expected unit or copies)")),
+            }
+        }
+        if params.unit.0 == 0 || params.unit.0 > params.unit.1 || params.unit.1 > MAX_UNIT {
+            return Err(format!("Invalid unit range {}..{} (1 to {MAX_UNIT} bases)", params.unit.0, params.unit.1));
+        }
+        if params.min_copies < 2 {
+            return Err(format!("Invalid number of copies {} (at least 2)", params.min_copies));
+        }
+        Ok(params)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TandemRepeat {
+    pub start: usize,
+    pub end: usize,
+    // The first copy, upper case
+    pub unit: String,
+}
+
+impl TandemRepeat {
+    // Whole and partial copies, e.g. 4.5 for ATATATATA
+    pub fn copies(&self) -> f64 {
+        (self.end - self.start) as f64 / self.unit.len() as f64
+    }
+
+    // Which copy a position in the repeat is part of, counting from 0
+    pub fn copy_of(&self, position: usize) -> usize {
+        (position - self.start) / self.unit.len()
+    }
+}
+
+// The repeat a position is part of, of repeats sorted by position and not overlapping
+pub fn covering(repeats: &[TandemRepeat], position: usize) -> Option<&TandemRepeat> {
+    let after = repeats.partition_point(|repeat| repeat.start <= position);
+    repeats[..after].last().filter(|repeat| position < repeat.end)
+}
+
+// Only unambiguous bases repeat, whatever their case
+fn same(a: u8, b: u8) -> bool {
+    a.eq_ignore_ascii_case(&b) && matches!(a.to_ascii_uppercase(), b'A' | b'C' | b'G' | b'T')
+}
+
+// Whether a unit isn't a shorter unit repeated
+fn is_primitive(unit: &[u8]) -> bool {
+    (1..unit.len()).filter(|&period| unit.len().is_multiple_of(period)).all(|period| unit[period..].iter().zip(unit).any(|(&a, &b)| !same(a, b)))
+}
+
+// Tandem repeats of the sequence, sorted by position and not overlapping
+pub fn find(dna: &[u8], params: &RepeatParams) -> Vec<TandemRepeat> {
+    let mut found = Vec::new();
+    for unit in params.unit.0..=params.unit.1 {
+        // Bases matching the one a unit further on, back from position i
+        let mut run = 0;
+        for i in 0..=dna.len().saturating_sub(unit) {
+            if i + unit < dna.len() && same(dna[i], dna[i + unit]) {
+                run += 1;
+                continue;
+            }
+            let start = i - run;
+            if run + unit >= unit * params.min_copies && is_primitive(&dna[start..start + unit]) {
+                found.push(TandemRepeat { start, end: i + unit, unit: String::from_utf8_lossy(&dna[start..start + unit]).to_ascii_uppercase() });
+            }
+            run = 0;
+        }
+    }
+
+    // Longest first, the smaller unit on a tie, each kept if it overlaps none kept before it
+    found.sort_by_key(|repeat| (std::cmp::Reverse(repeat.end - repeat.start), repeat.unit.len(), repeat.start));
+    let mut kept: BTreeMap<usize, TandemRepeat> = BTreeMap::new();
+    for repeat in found {
+        // Of those kept, only the last to start before this one ends can overlap it
+        let overlaps = kept.range(..repeat.end).next_back().is_some_and(|(_, other)| other.end > repeat.start);
+        if !overlaps {
+            kept.insert(repeat.start, repeat);
+        }
+    }
+    kept.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_repeats_by_their_shortest_unit() {
+        // (AT)x5, then (CAG)x4 plus two bases of a fifth copy, then (ATTC) only twice
+        let dna = b"GCATATATATATCCCAGCAGCAGcagCATTATTCATTCG";
+        let repeats = find(dna, &RepeatParams::default());
+        assert_eq!(repeats.iter().map(|repeat| (repeat.start, repeat.end, repeat.unit.as_str())).collect::<Vec<_>>(), vec![(2, 12, "AT"), (14, 28, "CAG")]);
+        assert_eq!(repeats[0].copies(), 5.0);
+        assert!((repeats[1].copies() - 14.0 / 3.0).abs() < 1e-9);
+        assert_eq!((repeats[1].copy_of(14), repeats[1].copy_of(16), repeats[1].copy_of(17)), (0, 0, 1));
+        let starts = [0, 2, 11, 12, 13, 27, 28].map(|position| covering(&repeats, position).map(|repeat| repeat.start));
+        assert_eq!(starts, [None, Some(2), Some(2), None, None, Some(14), None]);
+
+        let params: RepeatParams = "copies 2 unit 4 4".parse().unwrap();
+        assert_eq!(params, RepeatParams { unit: (4, 4), min_copies: 2 });
+        assert_eq!(params.to_string().parse(), Ok(params));
+        // ATAT is AT twice, so only ATTC is found with four-base units
+        assert_eq!(find(dna, &params).iter().map(|repeat| repeat.unit.as_str()).collect::<Vec<_>>(), vec!["ATTC"]);
+
+        // Ambiguous bases never repeat
+        assert!(find(b"NNNNNNNNNNNN", &RepeatParams { unit: (1, 2), min_copies: 2 }).is_empty());
+        assert!("unit 3 2".parse::<RepeatParams>().is_err());
+        assert!("copies 1".parse::<RepeatParams>().is_err());
+        assert!("unit 2".parse::<RepeatParams>().is_err());
+    }
+}
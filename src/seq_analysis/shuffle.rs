@@ -0,0 +1,173 @@
+// Randomized versions of a sequence for control runs: if the dynamics survive shuffling,
+// they come from the composition rather than the order of the bases. Shuffles are
+// reproducible from their seed.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ShuffleKind {
+    // Any order of the same bases
+    Uniform,
+    // Same dinucleotide counts, first and last base (Altschul-Erickson)
+    Dinucleotide,
+}
+
+impl std::str::FromStr for ShuffleKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(ShuffleKind::Uniform),
+            "dinucleotide" => Ok(ShuffleKind::Dinucleotide),
+            _ => Err(format!("Unknown shuffle '{s}', expected 'uniform' or 'dinucleotide'")),
+        }
+    }
+}
+
+impl std::fmt::Display for ShuffleKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ShuffleKind::Uniform => write!(f, "uniform"),
+            ShuffleKind::Dinucleotide => write!(f, "dinucleotide"),
+        }
+    }
+}
+
+// SplitMix64: small, fast and good enough for shuffling, with no dependency
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    // The state to restore the generator with through `new`, e.g. from a checkpoint
+    pub fn state(&self) -> u64 {
+        self.0
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in 0..1
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Uniform in 0..n, n > 0
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    // Fisher-Yates
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+pub fn shuffle(dna: &[u8], kind: ShuffleKind, seed: u64) -> Vec<u8> {
+    let mut rng = Rng::new(seed);
+    match kind {
+        ShuffleKind::Uniform => {
+            let mut shuffled = dna.to_vec();
+            rng.shuffle(&mut shuffled);
+            shuffled
+        }
+        ShuffleKind::Dinucleotide => dinucleotide_shuffle(dna, &mut rng),
+    }
+}
+
+// Every dinucleotide is an edge from its first base to its second, so the sequence is an
+// Eulerian path through the bases and any other Eulerian path from the same first base has
+// the same dinucleotides. A random one is drawn by picking, for every base but the last,
+// the edge it leaves by for the last time so these edges form a tree towards the last base
+// (Wilson's algorithm), shuffling the other edges, and walking.
+fn dinucleotide_shuffle(dna: &[u8], rng: &mut Rng) -> Vec<u8> {
+    let (Some(&first), Some(&last)) = (dna.first(), dna.last()) else {
+        return Vec::new();
+    };
+    let mut edges: Vec<Vec<u8>> = vec![Vec::new(); 256];
+    for pair in dna.windows(2) {
+        edges[pair[0] as usize].push(pair[1]);
+    }
+
+    // Index of each base's final edge, chosen by loop-erased random walks towards `last`
+    let mut in_tree = [false; 256];
+    in_tree[last as usize] = true;
+    let mut final_edge = [0usize; 256];
+    for start in 0..256 {
+        let mut base = start;
+        while !in_tree[base] && !edges[base].is_empty() {
+            final_edge[base] = rng.below(edges[base].len());
+            base = edges[base][final_edge[base]] as usize;
+        }
+        let mut base = start;
+        while !in_tree[base] && !edges[base].is_empty() {
+            in_tree[base] = true;
+            base = edges[base][final_edge[base]] as usize;
+        }
+    }
+
+    for (base, targets) in edges.iter_mut().enumerate() {
+        if targets.is_empty() {
+            continue;
+        }
+        let others = targets.len() - 1;
+        if base != last as usize {
+            targets.swap(final_edge[base], others);
+            rng.shuffle(&mut targets[..others]);
+        } else {
+            rng.shuffle(targets);
+        }
+    }
+
+    let mut used = [0usize; 256];
+    let mut shuffled = Vec::with_capacity(dna.len());
+    let mut base = first;
+    shuffled.push(base);
+    while let Some(&next) = edges[base as usize].get(used[base as usize]) {
+        used[base as usize] += 1;
+        shuffled.push(next);
+        base = next;
+    }
+    shuffled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dinucleotides(dna: &[u8]) -> Vec<[u8; 2]> {
+        let mut pairs: Vec<[u8; 2]> = dna.windows(2).map(|pair| [pair[0], pair[1]]).collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn dinucleotide_shuffle_keeps_the_pairs() {
+        let dna = b"ATGCGCGATATTTACGCGGGCATCGATCAGCTAGCTTTAAACGCGATCGACTNNACGT".repeat(3);
+        for seed in 0..20 {
+            let shuffled = shuffle(&dna, ShuffleKind::Dinucleotide, seed);
+            assert_eq!(shuffled.len(), dna.len());
+            assert_eq!((shuffled[0], shuffled[shuffled.len() - 1]), (dna[0], dna[dna.len() - 1]));
+            assert_eq!(dinucleotides(&shuffled), dinucleotides(&dna));
+        }
+        assert_ne!(shuffle(&dna, ShuffleKind::Dinucleotide, 1), dna);
+        assert_eq!(shuffle(&dna, ShuffleKind::Dinucleotide, 7), shuffle(&dna, ShuffleKind::Dinucleotide, 7));
+    }
+
+    #[test]
+    fn uniform_shuffle_keeps_the_composition() {
+        let dna = b"AAAACCCGGT";
+        let mut shuffled = shuffle(dna, ShuffleKind::Uniform, 3);
+        assert_ne!(shuffled, dna);
+        shuffled.sort();
+        assert_eq!(shuffled, dna);
+        assert_eq!(shuffle(b"", ShuffleKind::Dinucleotide, 3), b"");
+    }
+}
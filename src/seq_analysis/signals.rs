@@ -0,0 +1,173 @@
+use std::ops::{Range, RangeInclusive};
+
+use crate::seq_analysis::frames::{self, ReadingFrame};
+use crate::seq_analysis::motif::{Strand, search_iupac};
+
+// Translation signals on both strands (`signals`): start codons (ATG), stop codons (TAA, TAG,
+// TGA), Kozak-like contexts (RNNATGG: a purine three bases before an ATG and a G after it,
+// as eukaryotic ribosomes prefer) and Shine-Dalgarno-like sites (AGGAGG a few bases before
+// an ATG, where bacterial ribosomes bind). Each one is placed in the reading frame of its
+// codon, the ATG for the two ribosome-binding motifs, so they can be filtered by frame.
+
+const KOZAK: &str = "RNNATGG";
+const SHINE_DALGARNO: &str = "AGGAGG";
+// Bases between a Shine-Dalgarno site and the start codon it belongs to
+const SPACING: RangeInclusive<usize> = 4..=10;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SignalKind {
+    Start,
+    Stop,
+    Kozak,
+    ShineDalgarno,
+}
+
+impl SignalKind {
+    pub const ALL: [SignalKind; 4] = [SignalKind::Start, SignalKind::Stop, SignalKind::Kozak, SignalKind::ShineDalgarno];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SignalKind::Start => "start",
+            SignalKind::Stop => "stop",
+            SignalKind::Kozak => "kozak",
+            SignalKind::ShineDalgarno => "sd",
+        }
+    }
+}
+
+impl std::str::FromStr for SignalKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|kind| kind.name() == s).ok_or_else(|| format!("Invalid signal '{s}' (expected start, stop, kozak or sd)"))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Signal {
+    pub kind: SignalKind,
+    // First base in the reading direction: the start of a forward codon or site, the last
+    // base of a reverse one
+    pub position: usize,
+    pub frame: ReadingFrame,
+}
+
+// Which signals are shown: some kinds, in some frames
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignalFilter {
+    pub kinds: Vec<SignalKind>,
+    pub frames: Vec<ReadingFrame>,
+}
+
+impl Default for SignalFilter {
+    fn default() -> Self {
+        Self { kinds: SignalKind::ALL.to_vec(), frames: ReadingFrame::ALL.to_vec() }
+    }
+}
+
+impl SignalFilter {
+    pub fn accepts(&self, signal: &Signal) -> bool {
+        self.kinds.contains(&signal.kind) && self.frames.contains(&signal.frame)
+    }
+}
+
+// Kinds, then "frame" and the frames when not all six, e.g. "start stop frame +1 -2"
+impl std::fmt::Display for SignalFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let kinds: Vec<&str> = self.kinds.iter().map(|kind| kind.name()).collect();
+        write!(f, "{}", kinds.join(" "))?;
+        if self.frames.len() < ReadingFrame::ALL.len() {
+            write!(f, " frame")?;
+            for frame in &self.frames {
+                write!(f, " {frame}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Missing kinds or frames mean all of them
+impl std::str::FromStr for SignalFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let mut filter = Self { kinds: Vec::new(), frames: Vec::new() };
+        for word in words.by_ref() {
+            if word == "frame" {
+                break;
+            }
+            filter.kinds.push(word.parse()?);
+        }
+        for word in words {
+            filter.frames.push(word.parse()?);
+        }
+        if filter.kinds.is_empty() {
+            filter.kinds = SignalKind::ALL.to_vec();
+        }
+        if filter.frames.is_empty() {
+            filter.frames = ReadingFrame::ALL.to_vec();
+        }
+        Ok(filter)
+    }
+}
+
+// Every signal of the sequence, sorted by position
+pub fn find(dna: &[u8]) -> Vec<Signal> {
+    let first = |bases: &Range<usize>, strand| if strand == Strand::Forward { bases.start } else { bases.end - 1 };
+    let mut signals = Vec::new();
+    for frame in ReadingFrame::ALL {
+        frames::for_each_codon(dna, frame, |codon, amino_acid| {
+            let kind = match amino_acid {
+                Some('M') => SignalKind::Start,
+                Some('*') => SignalKind::Stop,
+                _ => return,
+            };
+            signals.push(Signal { kind, position: first(&codon, frame.strand), frame });
+        });
+    }
+
+    // Both motifs are valid IUPAC, so the searches can't fail
+    for site in search_iupac(dna, KOZAK).unwrap_or_default() {
+        let codon = if site.strand == Strand::Forward { site.start + 3..site.start + 6 } else { site.end - 6..site.end - 3 };
+        signals.push(Signal { kind: SignalKind::Kozak, position: first(&(site.start..site.end), site.strand), frame: frames::frame_of(dna.len(), &codon, site.strand) });
+    }
+    for site in search_iupac(dna, SHINE_DALGARNO).unwrap_or_default() {
+        // The nearest start codon downstream, on the site's strand
+        let codon = SPACING.clone().find_map(|gap| match site.strand {
+            Strand::Forward => Some(site.end + gap..site.end + gap + 3).filter(|codon| codon.end <= dna.len() && dna[codon.clone()].eq_ignore_ascii_case(b"ATG")),
+            Strand::Reverse => site.start.checked_sub(gap + 3).map(|start| start..start + 3).filter(|codon| dna[codon.clone()].eq_ignore_ascii_case(b"CAT")),
+        });
+        if let Some(codon) = codon {
+            signals.push(Signal { kind: SignalKind::ShineDalgarno, position: first(&(site.start..site.end), site.strand), frame: frames::frame_of(dna.len(), &codon, site.strand) });
+        }
+    }
+    signals.sort_by_key(|signal| signal.position);
+    signals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn places_signals_in_their_frames() {
+        // A Shine-Dalgarno site 6 bases before an ATG in a Kozak context, a stop in frame +1,
+        // and the reverse complement of an ATG (CAT) read in frame -2
+        let dna = b"AGGAGGTTTAAAATGGCTTAGCATC";
+        let signals = find(dna);
+        let of = |kind| signals.iter().filter(|signal| signal.kind == kind).map(|signal| (signal.position, signal.frame.to_string())).collect::<Vec<_>>();
+        assert_eq!(of(SignalKind::ShineDalgarno), vec![(0, "+1".to_string())]);
+        assert_eq!(of(SignalKind::Kozak), vec![(9, "+1".to_string())]);
+        assert_eq!(of(SignalKind::Start), vec![(12, "+1".to_string()), (23, "-2".to_string())]);
+        assert!(of(SignalKind::Stop).contains(&(18, "+1".to_string())));
+        assert!(signals.windows(2).all(|pair| pair[0].position <= pair[1].position));
+
+        let filter: SignalFilter = "start stop frame +1 -2".parse().unwrap();
+        assert_eq!(filter.to_string(), "start stop frame +1 -2");
+        assert_eq!(signals.iter().filter(|signal| filter.accepts(signal)).count(), of(SignalKind::Start).len() + of(SignalKind::Stop).iter().filter(|(_, frame)| frame == "+1" || frame == "-2").count());
+        assert_eq!("".parse::<SignalFilter>(), Ok(SignalFilter::default()));
+        assert_eq!(SignalFilter::default().to_string(), "start stop kozak sd");
+        assert!("start frame +4".parse::<SignalFilter>().is_err());
+    }
+}
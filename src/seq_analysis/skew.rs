@@ -0,0 +1,80 @@
+// GC skew: (G - C) / (G + C) per window, and its running sum along the sequence.
+// On bacterial chromosomes the cumulative skew bottoms out near the origin of
+// replication and peaks near the terminus.
+#[derive(Clone)]
+pub struct Skew {
+    // Cumulative skew after each window
+    pub cumulative: Vec<f64>,
+    // Windows where the cumulative skew is lowest/highest
+    pub min_window: usize,
+    pub max_window: usize,
+    pub window: usize,
+    len: usize,
+}
+
+impl Skew {
+    // Base offset where the cumulative skew is lowest: the putative origin of replication
+    pub fn min_position(&self) -> usize {
+        self.window_end(self.min_window)
+    }
+
+    // Base offset where the cumulative skew is highest: the putative terminus
+    pub fn max_position(&self) -> usize {
+        self.window_end(self.max_window)
+    }
+
+    fn window_end(&self, window: usize) -> usize {
+        if self.cumulative.is_empty() { 0 } else { ((window + 1) * self.window).min(self.len) }
+    }
+}
+
+// Skew of consecutive, non-overlapping windows. Windows without any G or C have zero skew;
+// the last window may be shorter than `window`.
+pub fn gc_skew(seq: &[u8], window: usize) -> Vec<f64> {
+    seq.chunks(window.max(1))
+        .map(|chunk| {
+            let g = chunk.iter().filter(|&&b| b == b'G' || b == b'g').count() as f64;
+            let c = chunk.iter().filter(|&&b| b == b'C' || b == b'c').count() as f64;
+            if g + c == 0.0 { 0.0 } else { (g - c) / (g + c) }
+        })
+        .collect()
+}
+
+pub fn cumulative_gc_skew(seq: &[u8], window: usize) -> Skew {
+    let window = window.max(1);
+    let cumulative: Vec<f64> = gc_skew(seq, window)
+        .iter()
+        .scan(0.0, |total, skew| {
+            *total += skew;
+            Some(*total)
+        })
+        .collect();
+
+    // First occurrence wins on ties
+    let (mut min_window, mut max_window) = (0, 0);
+    for (i, &value) in cumulative.iter().enumerate() {
+        if value < cumulative[min_window] { min_window = i; }
+        if value > cumulative[max_window] { max_window = i; }
+    }
+
+    Skew { cumulative, min_window, max_window, window, len: seq.len() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cumulative_skew_and_extrema() {
+        let skew = cumulative_gc_skew(b"CCATGGGG", 2);
+        // Windows: CC (-1), AT (0), GG (1), GG (1)
+        assert_eq!(skew.cumulative, vec![-1.0, -1.0, 0.0, 1.0]);
+        assert_eq!((skew.min_window, skew.min_position()), (0, 2));
+        assert_eq!((skew.max_window, skew.max_position()), (3, 8));
+    }
+
+    #[test]
+    fn windows_without_gc_have_zero_skew() {
+        assert_eq!(gc_skew(b"ATATGCG", 4), vec![0.0, 1.0 / 3.0]);
+    }
+}
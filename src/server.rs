@@ -0,0 +1,235 @@
+use std::io::Read;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+
+use tiny_http::{Header, Method, Request, Response};
+use tracing::warn;
+use tungstenite::handshake::derive_accept_key;
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+
+use crate::action::Action;
+use crate::console::parse_command;
+
+// HTTP/WebSocket API for driving and watching the simulation from outside (`--serve`).
+// Requests are served on their own threads and passed to the main thread, which owns the
+// app, as queries; the answers come back over a channel.
+//
+//   GET  /state      generation, population, rule, mode, speed and provenance as JSON, with
+//                    ?components=1 also the blob counts
+//   GET  /grid       the cells as plaintext rows of '.' and 'O'
+//   GET  /frame.png  the grid window as shown
+//   POST /start      resume the simulation
+//   POST /stop       pause it
+//   POST /command    run a control command in the body: pause, jump, rule or speed
+//   GET  /ws         WebSocket streaming the /state JSON after every update
+//
+// There is no authentication. Requests a web page makes carry an Origin header and are
+// refused, so a site open in the browser can't drive the app, and commands that read or write
+// files aren't accepted at all.
+
+// Updates queued for a WebSocket client that isn't keeping up before it misses some
+const STREAM_BACKLOG: usize = 16;
+// Longest command body read
+const MAX_BODY: u64 = 4096;
+// Requests and WebSocket streams handled at once; more are turned away
+const MAX_HANDLERS: usize = 32;
+
+pub enum Query {
+    // With the connected groups of live cells when asked for, as labeling them is a pass over
+    // the whole grid
+    State { components: bool },
+    Grid,
+    Frame,
+    Apply(Action),
+}
+
+pub enum Reply {
+    Json(String),
+    Text(String),
+    Png(Vec<u8>),
+    Error(u16, String),
+}
+
+type Queries = Sender<(Query, Sender<Reply>)>;
+type Subscribers = Arc<Mutex<Vec<SyncSender<String>>>>;
+
+pub struct Server {
+    // Where it listens, with the port picked when port 0 was asked for
+    pub address: String,
+    queries: Receiver<(Query, Sender<Reply>)>,
+    subscribers: Subscribers,
+}
+
+impl Server {
+    pub fn start(address: &str) -> Result<Self, String> {
+        let http = tiny_http::Server::http(address).map_err(|err| format!("Failed to listen on {address}: {err}"))?;
+        let address = http.server_addr().to_string();
+        if !address.parse::<SocketAddr>().is_ok_and(|address| address.ip().is_loopback()) {
+            warn!("The API on {address} has no authentication and can be reached from other machines");
+        }
+        let (sender, queries) = mpsc::channel();
+        let subscribers = Subscribers::default();
+        let streams = Arc::clone(&subscribers);
+        let handlers = Arc::new(AtomicUsize::new(0));
+        std::thread::Builder::new()
+            .name("server".into())
+            .spawn(move || {
+                for request in http.incoming_requests() {
+                    if handlers.fetch_add(1, Ordering::SeqCst) >= MAX_HANDLERS {
+                        handlers.fetch_sub(1, Ordering::SeqCst);
+                        respond(request, Reply::Error(503, "Too many requests at once".into()));
+                        continue;
+                    }
+                    let (sender, streams, handlers) = (sender.clone(), Arc::clone(&streams), Arc::clone(&handlers));
+                    std::thread::spawn(move || {
+                        handle(request, &sender, &streams);
+                        handlers.fetch_sub(1, Ordering::SeqCst);
+                    });
+                }
+            })
+            .map_err(|err| format!("Failed to start the server: {err}"))?;
+        Ok(Self { address, queries, subscribers })
+    }
+
+    // A query waiting for the app, with where to send the answer
+    pub fn next_query(&self) -> Option<(Query, Sender<Reply>)> {
+        self.queries.try_recv().ok()
+    }
+
+    pub fn has_subscribers(&self) -> bool {
+        !self.subscribers.lock().unwrap().is_empty()
+    }
+
+    // Send a message to every WebSocket client, forgetting those that have disconnected
+    pub fn publish(&self, message: &str) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| !matches!(subscriber.try_send(message.to_string()), Err(TrySendError::Disconnected(_))));
+    }
+}
+
+// Commands that only steer the run, the ones `POST /command` accepts
+fn is_control(action: &Action) -> bool {
+    matches!(action, Action::Pause(_) | Action::Jump(_) | Action::SetRule(_) | Action::UseNamedRule(_) | Action::SetSpeed(_))
+}
+
+fn handle(mut request: Request, queries: &Queries, subscribers: &Subscribers) {
+    if request.headers().iter().any(|header| header.field.equiv("Origin")) {
+        return respond(request, Reply::Error(403, "Requests from web pages are refused".into()));
+    }
+    let (path, parameters) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let components = parameters.split('&').any(|parameter| parameter == "components=1");
+    let path = path.to_string();
+    let query = match (request.method(), path.as_str()) {
+        (Method::Get, "/ws") => return stream(request, subscribers),
+        (Method::Get, "/state") => Query::State { components },
+        (Method::Get, "/grid") => Query::Grid,
+        (Method::Get, "/frame.png") => Query::Frame,
+        (Method::Post, "/start") => Query::Apply(Action::Pause(false)),
+        (Method::Post, "/stop") => Query::Apply(Action::Pause(true)),
+        (Method::Post, "/command") => {
+            let mut body = String::new();
+            if let Err(err) = request.as_reader().take(MAX_BODY).read_to_string(&mut body) {
+                return respond(request, Reply::Error(400, format!("Failed to read the command: {err}")));
+            }
+            match parse_command(&body) {
+                Ok(action) if is_control(&action) => Query::Apply(action),
+                Ok(action) => return respond(request, Reply::Error(403, format!("'{action}' can't be run over the API, only pause, jump, rule and speed"))),
+                Err(err) => return respond(request, Reply::Error(400, err)),
+            }
+        }
+        (method, path) => {
+            let message = format!("No {method} {path}, see the README for the API");
+            return respond(request, Reply::Error(404, message));
+        }
+    };
+
+    let (sender, answer) = mpsc::channel();
+    let reply = match queries.send((query, sender)) {
+        Ok(()) => answer.recv().unwrap_or_else(|_| Reply::Error(503, "The app has stopped".into())),
+        Err(_) => Reply::Error(503, "The app has stopped".into()),
+    };
+    respond(request, reply);
+}
+
+fn respond(request: Request, reply: Reply) {
+    let (status, content_type, body) = match reply {
+        Reply::Json(json) => (200, "application/json", json.into_bytes()),
+        Reply::Text(text) => (200, "text/plain; charset=utf-8", text.into_bytes()),
+        Reply::Png(png) => (200, "image/png", png),
+        Reply::Error(status, message) => (status, "text/plain; charset=utf-8", format!("{message}\n").into_bytes()),
+    };
+    let header = Header::from_bytes("Content-Type", content_type).unwrap();
+    // The client may be gone already; nothing to do about it
+    let _ = request.respond(Response::from_data(body).with_status_code(status).with_header(header));
+}
+
+// Upgrade to a WebSocket and forward published updates until the client goes away. The
+// stream only goes out; controls use the HTTP endpoints.
+fn stream(request: Request, subscribers: &Subscribers) {
+    let key = request.headers().iter().find(|header| header.field.equiv("Sec-WebSocket-Key"));
+    let Some(accept) = key.map(|header| derive_accept_key(header.value.as_bytes())) else {
+        return respond(request, Reply::Error(400, "Expected a WebSocket upgrade".into()));
+    };
+    let response = Response::empty(101).with_header(Header::from_bytes("Sec-WebSocket-Accept", accept).unwrap());
+    let mut socket = WebSocket::from_raw_socket(request.upgrade("websocket", response), Role::Server, None);
+
+    let (sender, updates) = mpsc::sync_channel(STREAM_BACKLOG);
+    subscribers.lock().unwrap().push(sender);
+    for update in updates {
+        if socket.send(Message::text(update)).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    fn request(address: &str, text: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(text.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn passes_queries_to_the_app() {
+        let server = Server::start("127.0.0.1:0").unwrap();
+        let address = server.address.clone();
+        let client = std::thread::spawn(move || {
+            let command = "POST /command HTTP/1.1\r\nHost: x\r\nConnection: close\r\nContent-Length: 10\r\n\r\npause on\r\n";
+            let export = "POST /command HTTP/1.1\r\nHost: x\r\nConnection: close\r\nContent-Length: 17\r\n\r\nexport frame.png\n";
+            let page = "POST /command HTTP/1.1\r\nHost: x\r\nOrigin: http://example.com\r\nConnection: close\r\nContent-Length: 10\r\n\r\npause on\r\n";
+            [
+                request(&address, export),
+                request(&address, page),
+                request(&address, command),
+                request(&address, "GET /nothing HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n"),
+            ]
+        });
+
+        let (query, reply) = loop {
+            if let Some(query) = server.next_query() {
+                break query;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        };
+        assert!(matches!(query, Query::Apply(Action::Pause(true))));
+        reply.send(Reply::Json("{}".into())).unwrap();
+
+        let [export, page, command, missing] = client.join().unwrap();
+        assert!(export.starts_with("HTTP/1.1 403"), "{export}");
+        assert!(page.starts_with("HTTP/1.1 403"), "{page}");
+        assert!(command.starts_with("HTTP/1.1 200") && command.ends_with("{}"), "{command}");
+        assert!(missing.starts_with("HTTP/1.1 404"), "{missing}");
+    }
+}
@@ -0,0 +1,304 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+
+use crate::bookmarks::Bookmarks;
+use crate::notes::Notes;
+use crate::provenance::Provenance;
+use crate::rule::Rule;
+
+// Sessions saved to a file and restored (`session save`, `session load`): the sequence, the
+// grid's cells and generation, and the settings that decide what happens next. They are
+// plain text, gzip-compressed, since the sequence and the cells compress well:
+//
+//   bio-rust session 1
+//   version bio-rust 0.1.0
+//   input genome.fa
+//   input_hash 8f0e5a39c2d1b6a7
+//   rng_seed 7
+//   generation 340
+//   grid 64x64
+//   rule B3/S23
+//   speed 10
+//   paused false
+//   seed 0
+//   strip 0
+//   note 120 glider gun
+//   bookmark 1 120 0 64x64 - 0,0,64x64+0 auto 0,0 origin
+//   ..O.O...
+//   sequence GATCCAGATC...
+//   .O..O...
+//
+// with a line for each note and two for each bookmark (see `Bookmark::encode`), if any, and
+// one line of cells per row after the sequence. The
+// provenance lines after the header are optional, and `rng_seed` only there when mutations
+// were drawn; `input_hash` is checked against the sequence when the session is loaded. The
+// same file, in the cache directory, serves for crash recovery: it is autosaved every few
+// minutes and when any thread of the app panics, and deleted when the window closes, so
+// finding it at launch means the last run didn't end cleanly.
+
+const HEADER: &str = "bio-rust session 1";
+pub const DEFAULT_AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Autosave {
+    pub enabled: bool,
+    pub interval: Duration,
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self { enabled: true, interval: DEFAULT_AUTOSAVE_INTERVAL }
+    }
+}
+
+pub fn recovery_path() -> PathBuf {
+    crate::config::cache_dir().join("recovery.session.gz")
+}
+
+static PANICKED: AtomicBool = AtomicBool::new(false);
+
+// Note every panic, on any thread, before the default hook reports it, so the app saves the
+// session right away instead of at the next autosave
+pub fn watch_panics() {
+    let report = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        PANICKED.store(true, Ordering::Relaxed);
+        report(info);
+    }));
+}
+
+// Whether a thread panicked since the last call
+pub fn take_panicked() -> bool {
+    PANICKED.swap(false, Ordering::Relaxed)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session {
+    pub dna: Vec<u8>,
+    pub rows: u32,
+    pub cols: u32,
+    pub generation: u64,
+    pub cells: Vec<bool>,
+    pub rule: Rule,
+    pub speed: f64,
+    pub paused: bool,
+    // Offset the whole grid was last seeded from, and the first base shown in the strip
+    pub seed_offset: usize,
+    pub strip_start: usize,
+    pub notes: Notes,
+    pub bookmarks: Bookmarks,
+    // File or accession the sequence was first read from, and the seed of the mutations drawn
+    pub input: Option<String>,
+    pub rng_seed: Option<u64>,
+}
+
+impl Session {
+    pub fn provenance(&self) -> Provenance {
+        let input = self.input.as_deref().unwrap_or("unknown");
+        Provenance::new(input, crate::seq_analysis::cache::sequence_id(&self.dna), self.seed_offset, self.rule.to_string(), self.rng_seed, self.generation)
+    }
+
+    pub fn encode(&self) -> String {
+        let mut text = format!("{HEADER}\n");
+        // The rest of the provenance is in the session already
+        for (key, value) in self.provenance().fields() {
+            let skip = match key {
+                "input" => self.input.is_none(),
+                "rng_seed" => self.rng_seed.is_none(),
+                "version" | "input_hash" => false,
+                _ => true,
+            };
+            if !skip {
+                text.push_str(&format!("{key} {value}\n"));
+            }
+        }
+        text.push_str(&format!(
+            "generation {}\ngrid {}x{}\nrule {}\nspeed {}\npaused {}\nseed {}\nstrip {}\n",
+            self.generation,
+            self.rows,
+            self.cols,
+            self.rule,
+            self.speed,
+            self.paused,
+            self.seed_offset,
+            self.strip_start,
+        ));
+        for note in self.notes.iter() {
+            text.push_str(&format!("note {} {}\n", note.position, note.text));
+        }
+        for (slot, bookmark) in self.bookmarks.iter() {
+            text.push_str(&bookmark.encode(slot));
+        }
+        text.push_str(&format!("sequence {}\n", String::from_utf8_lossy(&self.dna)));
+        for row in self.cells.chunks(self.cols as usize) {
+            text.extend(row.iter().map(|&alive| if alive { 'O' } else { '.' }));
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn decode(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().peekable();
+        if lines.next() != Some(HEADER) {
+            return Err("not a bio-rust session".to_string());
+        }
+        fn field<'a>(lines: &mut impl Iterator<Item = &'a str>, key: &str) -> Result<&'a str, String> {
+            lines
+                .next()
+                .and_then(|line| line.strip_prefix(key)?.strip_prefix(' '))
+                .ok_or_else(|| format!("expected '{key}'"))
+        }
+        fn invalid(key: &str, value: &str) -> String {
+            format!("invalid {key} '{value}'")
+        }
+        fn number<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+            value.parse().map_err(|_| invalid(key, value))
+        }
+
+        let (mut input, mut input_hash, mut rng_seed) = (None, None, None);
+        while let Some((key, value)) = lines.next_if(|line| !line.starts_with("generation ")).and_then(|line| line.split_once(' ')) {
+            match key {
+                "version" => {}
+                "input" => input = Some(value.to_string()),
+                "input_hash" => input_hash = Some(u64::from_str_radix(value, 16).map_err(|_| invalid(key, value))?),
+                "rng_seed" => rng_seed = Some(number(key, value)?),
+                _ => return Err(format!("expected 'generation', found '{key}'")),
+            }
+        }
+        let generation = number("generation", field(&mut lines, "generation")?)?;
+        let grid = field(&mut lines, "grid")?;
+        let (rows, cols) = crate::universe::parse_grid_size(grid).ok_or_else(|| invalid("grid", grid))?;
+        let rule = field(&mut lines, "rule")?.parse()?;
+        let speed = field(&mut lines, "speed")?;
+        let speed = speed.parse::<f64>().ok().filter(|speed| *speed > 0.0 && speed.is_finite()).ok_or_else(|| invalid("speed", speed))?;
+        let paused = number("paused", field(&mut lines, "paused")?)?;
+        let seed_offset = number("seed", field(&mut lines, "seed")?)?;
+        let strip_start = number("strip", field(&mut lines, "strip")?)?;
+        let mut notes = Notes::default();
+        while let Some(note) = lines.next_if(|line| line.starts_with("note ")) {
+            let note = &note["note ".len()..];
+            let (position, text) = note.split_once(' ').unwrap_or((note, ""));
+            notes.set(number("note", position)?, text);
+        }
+        let mut bookmarks = Bookmarks::default();
+        while let Some(line) = lines.next_if(|line| line.starts_with("bookmark ")) {
+            let cells = lines.next().ok_or("expected the cells of a bookmark")?;
+            let (slot, bookmark) = crate::bookmarks::Bookmark::decode(&line["bookmark ".len()..], cells)?;
+            bookmarks.save(slot, bookmark)?;
+        }
+        let dna = field(&mut lines, "sequence")?.as_bytes().to_vec();
+        if input_hash.is_some_and(|hash| hash != crate::seq_analysis::cache::sequence_id(&dna)) {
+            return Err("the sequence doesn't match its input_hash".to_string());
+        }
+
+        let mut cells = Vec::with_capacity(rows as usize * cols as usize);
+        for line in lines.by_ref().take(rows as usize) {
+            if line.len() != cols as usize {
+                return Err(format!("row {} has {} cells, expected {cols}", cells.len() / cols as usize, line.len()));
+            }
+            cells.extend(line.bytes().map(|cell| cell == b'O'));
+        }
+        if cells.len() != rows as usize * cols as usize {
+            return Err(format!("expected {rows} rows of cells"));
+        }
+        Ok(Self { dna, rows, cols, generation, cells, rule, speed, paused, seed_offset, strip_start, notes, bookmarks, input, rng_seed })
+    }
+
+    // Compressed, and written next to `path` first and then moved over it, so a crash while
+    // saving never leaves a half-written session behind
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let error = |err: std::io::Error| format!("Failed to save the session to {}: {err}", path.display());
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(error)?;
+        }
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".part");
+        let partial = PathBuf::from(partial);
+        let write = || -> std::io::Result<()> {
+            let mut encoder = GzEncoder::new(File::create(&partial)?, Compression::default());
+            encoder.write_all(self.encode().as_bytes())?;
+            encoder.finish()?.sync_all()
+        };
+        write().and_then(|()| std::fs::rename(&partial, path)).map_err(|err| {
+            let _ = std::fs::remove_file(&partial);
+            error(err)
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut text = String::new();
+        File::open(path)
+            .and_then(|file| GzDecoder::new(file).read_to_string(&mut text))
+            .map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        Self::decode(&text).map_err(|err| format!("{}: {err}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_a_compressed_file() {
+        let mut notes = Notes::default();
+        notes.set(3, "still life");
+        let mut bookmarks = Bookmarks::default();
+        let bookmark = crate::bookmarks::Bookmark {
+            name: "start".to_string(),
+            generation: 2,
+            cells: vec![false, true, false, false, false, false],
+            seed_map: crate::strip::SeedMap::new(2, 3),
+            strip_start: 0,
+            selection: None,
+            cell_size: None,
+            pan: [0, 0],
+        };
+        bookmarks.save(1, bookmark).unwrap();
+        let session = Session {
+            dna: b"GATCCAGATC".to_vec(),
+            rows: 2,
+            cols: 3,
+            generation: 340,
+            cells: vec![true, false, false, false, true, true],
+            rule: Rule::CONWAY,
+            speed: 12.5,
+            paused: true,
+            seed_offset: 4,
+            strip_start: 2,
+            notes,
+            bookmarks,
+            input: Some("genomes/ecoli.fa".to_string()),
+            rng_seed: None,
+        };
+        assert!(session.encode().ends_with("strip 2\nnote 3 still life\nbookmark 1 2 0 2x3 - 0,0,2x3+0 auto 0,0 start\n.O....\nsequence GATCCAGATC\nO..\n.OO\n"));
+        let input_hash = format!("input_hash {:016x}\n", crate::seq_analysis::cache::sequence_id(b"GATCCAGATC"));
+        assert!(session.encode().contains(&format!("\ninput genomes/ecoli.fa\n{input_hash}generation 340\n")));
+        assert_eq!(Session::decode(&session.encode()), Ok(session.clone()));
+
+        let path = std::env::temp_dir().join(format!("bio-rust-session-{}.gz", std::process::id()));
+        session.save(&path).unwrap();
+        assert_eq!(Session::load(&path), Ok(session.clone()));
+        std::fs::remove_file(&path).unwrap();
+
+        let truncated = session.encode().replace(".OO\n", "");
+        assert_eq!(Session::decode(&truncated), Err("expected 2 rows of cells".to_string()));
+        assert!(Session::decode(&session.encode().replace("speed 12.5", "speed 0")).is_err());
+        assert!(Session::decode("bio-rust session 2\n").is_err());
+        assert!(Session::decode(&session.encode().replace("sequence GATCC", "sequence GATCG")).is_err());
+
+        // Sessions saved before the provenance lines still load
+        let plain = Session { input: None, ..session.clone() };
+        let old: String = plain.encode().lines().filter(|line| !line.starts_with("version ") && !line.starts_with("input_hash ")).map(|line| format!("{line}\n")).collect();
+        assert_eq!(Session::decode(&old), Ok(plain));
+        let mutated = Session { rng_seed: Some(7), ..session };
+        assert!(mutated.encode().contains("\nrng_seed 7\n"));
+        assert_eq!(Session::decode(&mutated.encode()), Ok(mutated));
+    }
+}
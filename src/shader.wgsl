@@ -1,22 +1,52 @@
 struct VertexInput {
   @location(0) position: vec2<f32>,
   @location(1) color: vec3<f32>,
+  @location(2) signal: f32,
 }
 
+// Smooth transitions, from a second vertex buffer: what the color was before the generation,
+// less what it is now, and how much of the cell's size around its middle goes over it
+struct Motion {
+  @location(3) fade: vec3<f32>,
+  @location(4) anchor: vec2<f32>,
+  @location(5) shrink: f32,
+}
+
+// How far through the generation's transition the frame is, 0 to 1
+struct Time {
+  progress: f32,
+}
+
+@group(0) @binding(0) var<uniform> time: Time;
+
 struct VertexOutput {
   @builtin(position) clip_position: vec4<f32>,
   @location(0) color: vec3<f32>,
 };
 
-@vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
+fn shade(position: vec2<f32>, color: vec3<f32>, signal: f32) -> VertexOutput {
   var out: VertexOutput;
-  out.color = model.color;
-  out.clip_position = vec4<f32>(model.position, 0.0, 1.0);
+  // Signal tracks tint cells towards blue in proportion to their value
+  out.color = color + signal * vec3<f32>(0.05, 0.15, 0.35);
+  out.clip_position = vec4<f32>(position, 0.0, 1.0);
   return out;
 }
 
+@vertex
+fn vs_main(model: VertexInput) -> VertexOutput {
+  return shade(model.position, model.color, model.signal);
+}
+
+// The grid's cells while a smooth transition plays: births fade in from the color before,
+// deaths shrink out
+@vertex
+fn vs_smooth(model: VertexInput, motion: Motion) -> VertexOutput {
+  let color = model.color + motion.fade * (1.0 - time.progress);
+  let position = motion.anchor + (model.position - motion.anchor) * (1.0 - motion.shrink * time.progress);
+  return shade(position, color, model.signal);
+}
+
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
   return vec4<f32>(in.color, 1.0);
-}
\ No newline at end of file
+}
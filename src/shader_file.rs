@@ -0,0 +1,39 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// A WGSL file replacing the built-in cell shader, polled for changes like scripts so the
+// look of the cells can be tweaked without rebuilding
+pub struct ShaderFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    // Compile error of the version on disk, shown until a fixed version is saved
+    pub error: Option<String>,
+}
+
+impl ShaderFile {
+    pub fn open(path: &Path) -> Self {
+        Self { path: path.to_path_buf(), modified: modified_time(path), error: None }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn read(&self) -> Result<String, String> {
+        std::fs::read_to_string(&self.path).map_err(|err| format!("Failed to read shader {}: {err}", self.path.display()))
+    }
+
+    // The new source when the file changed on disk since the last check
+    pub fn changed(&mut self) -> Option<Result<String, String>> {
+        let modified = modified_time(&self.path);
+        if modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        Some(self.read())
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
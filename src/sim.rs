@@ -0,0 +1,184 @@
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+
+use crate::universe::{Packed, Universe};
+
+// Ticking on a dedicated thread, so a large grid or a fast speed doesn't stall input and
+// drawing. The app sends the worker a bit-packed copy of the universe and asks for
+// generations; the worker keeps it packed from one generation to the next and sends back the
+// cells that changed in each one, found by comparing the packed words, which the app applies
+// to its own universe once per frame before uploading the grid.
+//
+// Anything that changes the app's universe other than a tick (an edit, a rule change, a
+// reseed) reloads the worker with a new epoch. Updates computed from an older epoch are
+// dropped on arrival, so the app never applies generations of a universe it has replaced.
+
+pub enum SimCommand {
+    // Replace the worker's universe, starting the given epoch
+    Load(u64, Packed),
+    // Step the loaded universe this many more generations
+    Step(u32),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SimUpdate {
+    pub epoch: u64,
+    pub generation: u64,
+    // Indices of the cells that were flipped by this generation
+    pub changed: Vec<usize>,
+}
+
+impl SimUpdate {
+    pub fn apply(&self, universe: &mut Universe) {
+        for &idx in &self.changed {
+            universe.cells[idx] = !universe.cells[idx];
+        }
+        universe.generation = self.generation;
+    }
+}
+
+pub struct Simulation {
+    commands: Sender<SimCommand>,
+    updates: Receiver<SimUpdate>,
+    epoch: u64,
+    // Generations asked for in this epoch that haven't arrived yet
+    pending: u32,
+}
+
+impl Simulation {
+    pub fn start() -> Result<Self, String> {
+        let (commands, received) = mpsc::channel();
+        let (sender, updates) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("simulation".into())
+            .spawn(move || run(&received, &sender))
+            .map_err(|err| format!("Failed to start the simulation thread: {err}"))?;
+        Ok(Self { commands, updates, epoch: 0, pending: 0 })
+    }
+
+    // Hand the worker a packed copy of `universe`, abandoning the generations still in flight
+    pub fn load(&mut self, universe: &Universe) {
+        self.epoch += 1;
+        self.pending = 0;
+        self.send(SimCommand::Load(self.epoch, universe.packed()));
+    }
+
+    pub fn step(&mut self, generations: u32) {
+        if generations > 0 {
+            self.pending += generations;
+            self.send(SimCommand::Step(generations));
+        }
+    }
+
+    pub fn pending(&self) -> u32 {
+        self.pending
+    }
+
+    // The next generation of the current epoch that has arrived, if any
+    pub fn next_update(&mut self) -> Option<SimUpdate> {
+        loop {
+            let update = self.updates.try_recv().ok()?;
+            if update.epoch == self.epoch {
+                self.pending -= 1;
+                return Some(update);
+            }
+        }
+    }
+
+    fn send(&self, command: SimCommand) {
+        // The worker only stops when this end is dropped
+        self.commands.send(command).expect("simulation thread stopped");
+    }
+}
+
+// Worker loop: waits for commands while idle, and checks for new ones between generations
+// so a reload doesn't wait for a long batch to finish
+fn run(commands: &Receiver<SimCommand>, updates: &Sender<SimUpdate>) {
+    let (mut packed, mut epoch, mut remaining) = (None, 0, 0u32);
+    loop {
+        let command = if remaining == 0 {
+            match commands.recv() {
+                Ok(command) => Some(command),
+                Err(_) => return,
+            }
+        } else {
+            match commands.try_recv() {
+                Ok(command) => Some(command),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Disconnected) => return,
+            }
+        };
+        match command {
+            Some(SimCommand::Load(new_epoch, new_packed)) => {
+                (packed, epoch, remaining) = (Some(new_packed), new_epoch, 0);
+            }
+            Some(SimCommand::Step(generations)) => remaining += generations,
+            None => {}
+        }
+
+        let Some(packed) = packed.as_mut().filter(|_| remaining > 0) else {
+            continue;
+        };
+        let previous = packed.step();
+        remaining -= 1;
+        let changed = packed.grid.changed(&previous);
+        if updates.send(SimUpdate { epoch, generation: packed.generation, changed }).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selection::Region;
+
+    fn glider() -> Universe {
+        let mut universe = Universe::new(8, 8, b"");
+        universe.fill_region(&Region { row: 0, col: 0, rows: 3, cols: 3 }, |i| [1, 5, 6, 7, 8].contains(&i));
+        universe
+    }
+
+    fn wait(simulation: &mut Simulation) -> SimUpdate {
+        loop {
+            if let Some(update) = simulation.next_update() {
+                return update;
+            }
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn updates_match_ticking_in_place() {
+        fn assert_send<T: Send>() {}
+        assert_send::<Packed>();
+
+        let mut simulation = Simulation::start().unwrap();
+        // Rows of several words, the last one partly used, as well as a small grid
+        let dna: Vec<u8> = (0..6 * 130).map(|idx: usize| b"GCATTGAC"[idx * idx % 7]).collect();
+        for universe in [glider(), Universe::new(6, 130, &dna)] {
+            let (mut expected, mut mirrored) = (universe.clone(), universe);
+            simulation.load(&mirrored);
+            simulation.step(12);
+            for _ in 0..12 {
+                expected.tick();
+                wait(&mut simulation).apply(&mut mirrored);
+                assert_eq!(mirrored.cells, expected.cells);
+                assert_eq!(mirrored.generation, expected.generation);
+            }
+            assert_eq!(simulation.pending(), 0);
+        }
+    }
+
+    #[test]
+    fn reloading_drops_generations_in_flight() {
+        let mut simulation = Simulation::start().unwrap();
+        simulation.load(&glider());
+        simulation.step(1000);
+        let mut empty = Universe::new(8, 8, b"");
+        empty.generation = 50;
+        simulation.load(&empty);
+        simulation.step(1);
+        assert_eq!(simulation.pending(), 1);
+        assert_eq!(wait(&mut simulation), SimUpdate { epoch: 2, generation: 51, changed: Vec::new() });
+    }
+}
@@ -0,0 +1,282 @@
+use std::ops::Range;
+
+use crate::automaton::cyclic::{self, BASES};
+use crate::render::text::{ADVANCE, GLYPH_HEIGHT, font_pixel, push_text};
+use crate::selection::Region;
+use crate::vertex::{Vertex, push_rect};
+use crate::viewport::Viewport;
+
+// Which sequence position each cell was seeded from. Every seed fills a region in row-major
+// order from an offset; later seeds cover earlier ones, and seeding the whole grid forgets
+// everything before it. Positions are usize throughout, as genomes of billions of bases
+// have positions past u32::MAX; only cells, at most MAX_CELLS of them, are counted in u32.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeedMap {
+    rows: u32,
+    cols: u32,
+    seeds: Vec<(Region, usize)>,
+}
+
+impl SeedMap {
+    // A grid seeded from the start of the sequence
+    pub fn new(rows: u32, cols: u32) -> Self {
+        Self { rows, cols, seeds: vec![(Region::full(rows, cols), 0)] }
+    }
+
+    // A map with these seeds, oldest first, as `seeds` lists them
+    pub fn from_seeds(rows: u32, cols: u32, seeds: Vec<(Region, usize)>) -> Self {
+        Self { rows, cols, seeds }
+    }
+
+    pub fn seeds(&self) -> &[(Region, usize)] {
+        &self.seeds
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.rows, self.cols)
+    }
+
+    pub fn record(&mut self, region: &Region, offset: usize) {
+        // Cells outside the grid are never seeded
+        let rows = region.rows.min(self.rows.saturating_sub(region.row));
+        let cols = region.cols.min(self.cols.saturating_sub(region.col));
+        let region = Region { rows, cols, ..*region };
+        if region == Region::full(self.rows, self.cols) {
+            self.seeds.clear();
+        }
+        self.seeds.push((region, offset));
+    }
+
+    // Sequence position the cell was seeded from
+    pub fn position(&self, row: u32, col: u32) -> Option<usize> {
+        let (region, offset) = self.seeds.iter().rev().find(|(region, _)| region.contains(row, col))?;
+        Some(offset + (row - region.row) as usize * region.cols as usize + (col - region.col) as usize)
+    }
+
+    // Cells (row-major indices) currently seeded from `position`
+    pub fn cells(&self, position: usize) -> Vec<usize> {
+        let mut cells = Vec::new();
+        for (region, offset) in &self.seeds {
+            let Some(i) = position.checked_sub(*offset).filter(|&i| i < region.len()) else {
+                continue;
+            };
+            let (row, col) = (region.row + (i / region.cols as usize) as u32, region.col + (i % region.cols as usize) as u32);
+            let idx = row as usize * self.cols as usize + col as usize;
+            if self.position(row, col) == Some(position) && !cells.contains(&idx) {
+                cells.push(idx);
+            }
+        }
+        cells
+    }
+
+    // Cells seeded from the positions of `items`, which must be sorted by position, as (cell
+    // index, index into `items`). Unlike `cells` this looks up many positions at once, in time
+    // proportional to the number found.
+    pub fn cells_of_sorted<T>(&self, items: &[T], position: impl Fn(&T) -> usize) -> Vec<(usize, usize)> {
+        let mut cells = Vec::new();
+        for (seed, (region, offset)) in self.seeds.iter().enumerate() {
+            let first = items.partition_point(|item| position(item) < *offset);
+            let end = items.partition_point(|item| position(item) < offset + region.len());
+            for (i, item) in items.iter().enumerate().take(end).skip(first) {
+                let i_in_region = position(item) - offset;
+                let (row, col) = (region.row + (i_in_region / region.cols as usize) as u32, region.col + (i_in_region % region.cols as usize) as u32);
+                // Cells a later seed covers belong to it
+                if self.seeds.iter().rposition(|(region, _)| region.contains(row, col)) == Some(seed) {
+                    cells.push((row as usize * self.cols as usize + col as usize, i));
+                }
+            }
+        }
+        cells
+    }
+
+    // Follow the grid to `rows` x `cols` with its cells moved by `shift` rows and columns
+    // (see `Universe::resize`). Seeds cut on the left or right no longer fill their cells in
+    // one run, so they are split into a seed per row; cells moved in from outside have none.
+    pub fn resize(&mut self, rows: u32, cols: u32, (shift_row, shift_col): (i64, i64)) {
+        let mut seeds = Vec::new();
+        for &(region, offset) in &self.seeds {
+            let (row, col) = (region.row as i64 + shift_row, region.col as i64 + shift_col);
+            let (first_row, first_col) = (row.max(0), col.max(0));
+            let end_row = (row + region.rows as i64).min(rows as i64);
+            let end_col = (col + region.cols as i64).min(cols as i64);
+            if first_row >= end_row || first_col >= end_col {
+                continue;
+            }
+            let skipped = |r: i64, c: i64| offset + ((r - row) * region.cols as i64 + c - col) as usize;
+            let width = (end_col - first_col) as u32;
+            if width == region.cols {
+                let region = Region { row: first_row as u32, col: first_col as u32, rows: (end_row - first_row) as u32, cols: width };
+                seeds.push((region, skipped(first_row, first_col)));
+            } else {
+                for r in first_row..end_row {
+                    seeds.push((Region { row: r as u32, col: first_col as u32, rows: 1, cols: width }, skipped(r, first_col)));
+                }
+            }
+        }
+        *self = Self { rows, cols, seeds };
+    }
+
+    // Sequence ranges mapped into the grid, oldest seed first
+    pub fn windows(&self) -> Vec<Range<usize>> {
+        self.seeds.iter().map(|(region, offset)| *offset..offset + region.len()).collect()
+    }
+}
+
+// Placement of the sequence strip along the bottom of the window: the position of the first
+// base shown, then one glyph per base. Everything is in whole physical pixels.
+pub struct StripLayout {
+    // Clip-space size of one font pixel
+    pixel: [f32; 2],
+    // Left edge of the first base
+    bases_left: f32,
+    pub bases: usize,
+    pub bottom: f32,
+    pub top: f32,
+}
+
+// Characters reserved for the position label: 11 digits, up to 99 billion, and a space
+const LABEL_CHARS: u32 = 12;
+// Font pixels of padding above and below the glyphs
+const PADDING: u32 = 2;
+
+impl StripLayout {
+    pub fn new(viewport: &Viewport, text_scale: u32) -> Self {
+        let pixel = font_pixel(viewport, text_scale);
+        let width = (2.0 / pixel[0]).round() as u32;
+        let label = LABEL_CHARS * ADVANCE + 2;
+        Self {
+            pixel,
+            bases_left: -1.0 + label as f32 * pixel[0],
+            bases: (width.saturating_sub(label) / ADVANCE) as usize,
+            bottom: -1.0,
+            top: -1.0 + (GLYPH_HEIGHT + 2 * PADDING) as f32 * pixel[1],
+        }
+    }
+
+    // Index of the base shown under a clip-space point, counting from the first one shown
+    pub fn base_at(&self, [x, y]: [f32; 2]) -> Option<usize> {
+        if y < self.bottom || y > self.top || x < self.bases_left {
+            return None;
+        }
+        let index = ((x - self.bases_left) / (ADVANCE as f32 * self.pixel[0])) as usize;
+        (index < self.bases).then_some(index)
+    }
+
+    // Base `index` along the strip as (left, right) in clip space
+    fn span(&self, index: usize) -> (f32, f32) {
+        let advance = ADVANCE as f32 * self.pixel[0];
+        let left = self.bases_left + index as f32 * advance;
+        (left, left + advance)
+    }
+}
+
+// A, C, G and T get the cyclic automaton's state colors; ambiguity codes and N are grey
+pub fn base_color(base: u8) -> [f32; 3] {
+    match BASES.iter().position(|&b| b == base.to_ascii_uppercase()) {
+        Some(state) => cyclic::color(state as u8),
+        None => [0.55, 0.55, 0.55],
+    }
+}
+
+// The bases from `start` on, the ones inside any of `windows` (the part of the sequence
+// mapped into the grid) on a lighter background and `hovered` boxed in white
+pub fn create_strip_vertices(dna: &[u8], start: usize, layout: &StripLayout, windows: &[Range<usize>], hovered: Option<usize>) -> Vec<Vertex> {
+    let mut vertices = Vec::new();
+    push_rect(&mut vertices, [-1.0, layout.bottom], [1.0, layout.top], [0.02, 0.02, 0.03]);
+
+    let pixel = layout.pixel;
+    let text_bottom = layout.bottom + PADDING as f32 * pixel[1];
+    push_text(&mut vertices, &format!("{start:>11}"), [-1.0 + pixel[0], text_bottom], pixel, [0.7, 0.7, 0.7]);
+
+    let end = (start + layout.bases).min(dna.len());
+    for (index, position) in (start..end).enumerate() {
+        let (left, right) = layout.span(index);
+        if hovered == Some(position) {
+            push_rect(&mut vertices, [left, layout.bottom], [right, layout.top], [0.9, 0.9, 0.9]);
+            push_rect(&mut vertices, [left + pixel[0] / 2.0, layout.bottom + pixel[1] / 2.0], [right - pixel[0] / 2.0, layout.top - pixel[1] / 2.0], [0.02, 0.02, 0.03]);
+        } else if windows.iter().any(|window| window.contains(&position)) {
+            push_rect(&mut vertices, [left, layout.bottom], [right, layout.top], [0.14, 0.14, 0.2]);
+        }
+        let glyph = [left + pixel[0] / 2.0, text_bottom];
+        push_text(&mut vertices, &(dna[position] as char).to_string(), glyph, pixel, base_color(dna[position]));
+    }
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resizing_keeps_the_positions_of_moved_cells() {
+        let mut seeds = SeedMap::new(4, 5);
+        // Grown by one row and column on every side, then cropped to the middle 2x3
+        seeds.resize(6, 7, (1, 1));
+        assert_eq!(seeds.position(0, 3), None);
+        assert_eq!(seeds.position(2, 3), Some(7));
+        seeds.resize(2, 3, (-2, -2));
+        assert_eq!(seeds.position(0, 0), Some(6));
+        assert_eq!(seeds.position(1, 2), Some(13));
+        assert_eq!(seeds.cells(8), vec![2]);
+        assert_eq!(seeds.windows(), vec![6..9, 11..14]);
+    }
+
+    #[test]
+    fn maps_cells_to_the_positions_they_were_seeded_from() {
+        let mut seeds = SeedMap::new(4, 5);
+        assert_eq!(seeds.position(1, 2), Some(7));
+        // A 2x2 block reseeded from offset 100 covers cells 6, 7, 11 and 12
+        seeds.record(&Region { row: 1, col: 1, rows: 2, cols: 2 }, 100);
+        assert_eq!(seeds.position(1, 2), Some(101));
+        assert_eq!(seeds.position(2, 1), Some(102));
+        assert_eq!(seeds.position(3, 4), Some(19));
+        assert_eq!(seeds.cells(102), vec![11]);
+        // Position 7 was seeded into cell 7, which the block has covered since
+        assert_eq!(seeds.cells(7), Vec::<usize>::new());
+        assert_eq!(seeds.windows(), vec![0..20, 100..104]);
+        assert_eq!(seeds.cells_of_sorted(&[7, 19, 101, 102], |&position| position), vec![(19, 1), (7, 2), (11, 3)]);
+
+        seeds.record(&Region::full(4, 5), 50);
+        assert_eq!(seeds.windows(), vec![50..70]);
+        assert_eq!(seeds.cells(51), vec![1]);
+    }
+
+    // Genomes of billions of bases are seeded from offsets past u32::MAX
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn maps_positions_past_u32_max() {
+        let boundary = 1usize << 32;
+        let mut seeds = SeedMap::new(4, 5);
+        seeds.record(&Region::full(4, 5), boundary - 7);
+        assert_eq!(seeds.position(1, 1), Some(boundary - 1));
+        assert_eq!(seeds.position(1, 2), Some(boundary));
+        assert_eq!(seeds.position(3, 4), Some(boundary + 12));
+        assert_eq!(seeds.cells(boundary), vec![7]);
+        assert_eq!(seeds.cells_of_sorted(&[boundary - 8, boundary - 1, boundary, boundary + 13], |&position| position), vec![(6, 1), (7, 2)]);
+        assert_eq!(seeds.windows(), vec![boundary - 7..boundary + 13]);
+
+        // A partial reseed just past the boundary, then the grid grown around it
+        seeds.record(&Region { row: 2, col: 1, rows: 1, cols: 3 }, boundary + 1000);
+        seeds.resize(6, 7, (1, 1));
+        assert_eq!(seeds.position(3, 2), Some(boundary + 1000));
+        assert_eq!(seeds.position(4, 5), Some(boundary + 12));
+        assert_eq!(seeds.cells(boundary + 1002), vec![3 * 7 + 4]);
+
+        // Regions reaching past u32::MAX neither overflow nor wrap
+        let edge = Region { row: u32::MAX - 2, col: 0, rows: 3, cols: 1 };
+        assert!(edge.contains(u32::MAX, 0) && !edge.contains(u32::MAX - 3, 0) && !edge.contains(u32::MAX, 1));
+        let huge = Region { row: 0, col: 0, rows: u32::MAX, cols: u32::MAX };
+        assert_eq!(huge.len(), (u32::MAX as usize).pow(2));
+    }
+
+    #[test]
+    fn strip_hit_testing() {
+        // 2 physical pixels per font pixel: 12 pixels per base after a 148 pixel label
+        let layout = StripLayout::new(&Viewport { width: 800, height: 600, scale_factor: 1.0 }, 1);
+        assert_eq!(layout.bases, 54);
+        let clip = |x: f32, y: f32| [x / 400.0 - 1.0, 1.0 - y / 300.0];
+        assert_eq!(layout.base_at(clip(148.0 + 12.0 * 3.0 + 1.0, 595.0)), Some(3));
+        assert_eq!(layout.base_at(clip(100.0, 595.0)), None);
+        assert_eq!(layout.base_at(clip(300.0, 500.0)), None);
+    }
+}
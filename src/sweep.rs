@@ -0,0 +1,335 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::batch::{self, Outcome};
+use crate::centroid::{self, Trajectory};
+use crate::evolve::block_entropy;
+use crate::rule::Rule;
+use crate::selection::Region;
+use crate::seq_analysis::shuffle::Rng;
+use crate::universe::{self, Universe};
+
+// Parameter sweeps (`sweep`): headless runs over combinations of the rule, the seeding and
+// the grid size, planned as a full grid of values or a Latin hypercube sample, with one
+// CSV row of outcomes per run.
+
+// More runs than this are refused rather than left running for days
+pub const MAX_RUNS: usize = 100_000;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Name {
+    Rule,
+    // Fraction of cells alive in a random soup, in place of seeding from the sequence
+    Density,
+    // Sequence position the grid is seeded from
+    Offset,
+    Grid,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Domain {
+    Rules(Vec<Rule>),
+    Sizes(Vec<(u32, u32)>),
+    Numbers(Vec<f64>),
+    // Inclusive
+    Range(f64, f64),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Param {
+    pub name: Name,
+    pub domain: Domain,
+}
+
+// "rule=B3/S23,B36/S23", "density=0.1..0.9", "offset=0,500,1000" or "grid=32x32,64x64"
+impl std::str::FromStr for Param {
+    type Err = String;
+
+    fn from_str(param: &str) -> Result<Self, String> {
+        let (name, values) = param.split_once('=').ok_or_else(|| format!("Invalid parameter '{param}', expected name=values like density=0.1..0.9"))?;
+        let numbers = |values: &str, min: f64, max: f64| -> Result<Domain, String> {
+            let number = |value: &str| value.parse::<f64>().ok().filter(|value| (min..=max).contains(value)).ok_or_else(|| format!("Invalid {name} '{value}', expected {min} to {max}"));
+            match values.split_once("..") {
+                Some((from, to)) => {
+                    let (from, to) = (number(from)?, number(to)?);
+                    if from > to {
+                        return Err(format!("Invalid {name} range '{values}', the start is past the end"));
+                    }
+                    Ok(Domain::Range(from, to))
+                }
+                None => values.split(',').map(number).collect::<Result<_, _>>().map(Domain::Numbers),
+            }
+        };
+        let (name, domain) = match name {
+            "rule" => (Name::Rule, Domain::Rules(values.split(',').map(str::parse).collect::<Result<_, _>>()?)),
+            "grid" => {
+                let size = |size: &str| universe::parse_grid_size(size).ok_or_else(|| format!("Invalid grid size '{size}'"));
+                (Name::Grid, Domain::Sizes(values.split(',').map(size).collect::<Result<_, _>>()?))
+            }
+            "density" => (Name::Density, numbers(values, 0.0, 1.0)?),
+            "offset" => (Name::Offset, numbers(values, 0.0, u32::MAX as f64)?),
+            _ => return Err(format!("Unknown parameter '{name}', expected rule, density, offset or grid")),
+        };
+        Ok(Self { name, domain })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Sampling {
+    // Every combination, with `steps` evenly spaced values across each range
+    Grid { steps: usize },
+    // `samples` runs, each range and list split into that many strata and every stratum of
+    // every parameter used once
+    LatinHypercube { samples: usize },
+}
+
+// One run's parameters. Without a density the grid is seeded from the sequence.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Point {
+    pub rule: Rule,
+    pub density: Option<f64>,
+    pub offset: usize,
+    pub rows: u32,
+    pub cols: u32,
+    // Seed of the random soup, if there is one
+    pub seed: u64,
+}
+
+impl Point {
+    fn set(&mut self, name: Name, value: Value) {
+        match (name, value) {
+            (Name::Rule, Value::Rule(rule)) => self.rule = rule,
+            (Name::Grid, Value::Size(rows, cols)) => (self.rows, self.cols) = (rows, cols),
+            (Name::Density, Value::Number(density)) => self.density = Some(density),
+            (Name::Offset, Value::Number(offset)) => self.offset = offset.round() as usize,
+            _ => unreachable!("values come from their parameter's domain"),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Value {
+    Rule(Rule),
+    Size(u32, u32),
+    Number(f64),
+}
+
+impl Domain {
+    // The values a grid sweep takes
+    fn values(&self, steps: usize) -> Vec<Value> {
+        match self {
+            Domain::Rules(rules) => rules.iter().map(|&rule| Value::Rule(rule)).collect(),
+            Domain::Sizes(sizes) => sizes.iter().map(|&(rows, cols)| Value::Size(rows, cols)).collect(),
+            Domain::Numbers(numbers) => numbers.iter().map(|&number| Value::Number(number)).collect(),
+            Domain::Range(from, to) if steps < 2 || from == to => vec![Value::Number((from + to) / 2.0)],
+            Domain::Range(from, to) => (0..steps).map(|step| Value::Number(from + (to - from) * step as f64 / (steps - 1) as f64)).collect(),
+        }
+    }
+
+    // The value at `t` (0 to 1) of the way through the domain
+    fn at(&self, t: f64) -> Value {
+        let pick = |len: usize| ((t * len as f64) as usize).min(len - 1);
+        match self {
+            Domain::Rules(rules) => Value::Rule(rules[pick(rules.len())]),
+            Domain::Sizes(sizes) => {
+                let (rows, cols) = sizes[pick(sizes.len())];
+                Value::Size(rows, cols)
+            }
+            Domain::Numbers(numbers) => Value::Number(numbers[pick(numbers.len())]),
+            Domain::Range(from, to) => Value::Number(from + (to - from) * t),
+        }
+    }
+}
+
+// The runs of a sweep over `params`, starting from `base`. Every run gets its own soup seed
+// from `seed`, so each row can be rerun on its own.
+pub fn plan(params: &[Param], sampling: Sampling, base: Point, seed: u64) -> Result<Vec<Point>, String> {
+    let mut rng = Rng::new(seed);
+    let mut points = vec![base];
+    match sampling {
+        Sampling::Grid { steps } => {
+            for param in params {
+                let values = param.domain.values(steps);
+                if points.len().saturating_mul(values.len()) > MAX_RUNS {
+                    return Err(format!("The sweep has more than {MAX_RUNS} runs, use fewer values or --sampling lhs"));
+                }
+                points = points
+                    .iter()
+                    .flat_map(|point| {
+                        values.iter().map(move |&value| {
+                            let mut point = *point;
+                            point.set(param.name, value);
+                            point
+                        })
+                    })
+                    .collect();
+            }
+        }
+        Sampling::LatinHypercube { samples } => {
+            if samples > MAX_RUNS {
+                return Err(format!("The sweep has more than {MAX_RUNS} runs"));
+            }
+            points = vec![base; samples.max(1)];
+            for param in params {
+                let mut strata: Vec<usize> = (0..points.len()).collect();
+                rng.shuffle(&mut strata);
+                for (point, stratum) in points.iter_mut().zip(strata) {
+                    point.set(param.name, param.domain.at((stratum as f64 + rng.unit()) / samples.max(1) as f64));
+                }
+            }
+        }
+    }
+    for point in &mut points {
+        point.seed = rng.next_u64();
+    }
+    Ok(points)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunSummary {
+    pub point: Point,
+    pub outcome: Outcome,
+    // Fraction of cells alive at the end, and the block entropy of the last grid
+    pub final_density: f64,
+    pub entropy: f64,
+    // Centroid drift over the run, in cells per generation along (columns, rows)
+    pub drift: Option<[f64; 2]>,
+}
+
+// Run one point for up to `generations`. None when `cancel` is set before the end.
+pub fn run_point(dna: &[u8], point: &Point, generations: u64, cancel: &AtomicBool) -> Option<RunSummary> {
+    let mut universe = match point.density {
+        Some(density) => {
+            let mut universe = Universe::new(point.rows, point.cols, b"");
+            let mut rng = Rng::new(point.seed);
+            universe.fill_region(&Region::full(point.rows, point.cols), |_| rng.unit() < density);
+            universe
+        }
+        None => Universe::new(point.rows, point.cols, dna.get(point.offset..).unwrap_or_default()),
+    };
+    universe.rule = point.rule;
+    let mut trajectory = Trajectory::new(point.rows, point.cols, generations as usize + 1);
+    let outcome = batch::run_universe(&mut universe, generations, cancel, |universe| {
+        trajectory.record(universe.generation, centroid::centroid(&universe.cells, universe.rows, universe.cols));
+    })?;
+    Some(RunSummary {
+        point: *point,
+        outcome,
+        final_density: outcome.final_population as f64 / universe.cells.len() as f64,
+        entropy: block_entropy(&universe.cells, universe.rows, universe.cols),
+        drift: trajectory.drift(),
+    })
+}
+
+// Run every point on up to `threads` threads, calling `finished` as each one completes.
+// Setting `cancel` abandons the runs still going. The completed runs come back in the
+// points' order, with their index among them.
+pub fn run(dna: &[u8], points: &[Point], generations: u64, threads: usize, cancel: &AtomicBool, finished: impl Fn(&RunSummary) + Sync) -> Vec<(usize, RunSummary)> {
+    let next = AtomicUsize::new(0);
+    let summaries = Mutex::new(vec![None; points.len()]);
+    std::thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, points.len().max(1)) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(point) = points.get(index) else { break };
+                    let Some(summary) = run_point(dna, point, generations, cancel) else { break };
+                    finished(&summary);
+                    summaries.lock().unwrap()[index] = Some(summary);
+                }
+            });
+        }
+    });
+    summaries.into_inner().unwrap().into_iter().enumerate().filter_map(|(index, summary)| Some((index, summary?))).collect()
+}
+
+const COLUMNS: &str = "run,rule,density,offset,grid,seed,stabilized_at,period,max_population,final_population,final_density,entropy,drift_x,drift_y";
+
+// One row per run, numbered from 1 in the plan's order, NA where a value doesn't apply
+pub fn to_csv(summaries: &[(usize, RunSummary)]) -> String {
+    let or_na = |value: Option<String>| value.unwrap_or_else(|| "NA".to_string());
+    let mut csv = format!("{COLUMNS}\n");
+    for (run, summary) in summaries {
+        let (point, outcome) = (&summary.point, &summary.outcome);
+        let drift = |axis: usize| or_na(summary.drift.map(|drift| format!("{:.4}", drift[axis])));
+        csv.push_str(&format!(
+            "{},{},{},{},{}x{},{},{},{},{},{},{:.4},{:.4},{},{}\n",
+            run + 1,
+            point.rule,
+            or_na(point.density.map(|density| format!("{density:.4}"))),
+            if point.density.is_some() { "NA".to_string() } else { point.offset.to_string() },
+            point.rows,
+            point.cols,
+            or_na(point.density.map(|_| point.seed.to_string())),
+            or_na(outcome.stabilized_at.map(|generation| generation.to_string())),
+            or_na(outcome.period.map(|period| period.to_string())),
+            outcome.max_population,
+            outcome.final_population,
+            summary.final_density,
+            summary.entropy,
+            drift(0),
+            drift(1),
+        ));
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::tests::BLINKER;
+
+    fn base() -> Point {
+        Point { rule: Rule::CONWAY, density: None, offset: 0, rows: 16, cols: 16, seed: 0 }
+    }
+
+    #[test]
+    fn plans_grids_and_latin_hypercubes() {
+        let params: Vec<Param> = ["rule=B3/S23,B36/S23", "density=0.1..0.9"].iter().map(|param| param.parse().unwrap()).collect();
+        assert_eq!(params[1].domain, Domain::Range(0.1, 0.9));
+        assert!("density=0.9..0.1".parse::<Param>().is_err());
+        assert!("density=1.5".parse::<Param>().is_err());
+        assert!("speed=10".parse::<Param>().is_err());
+
+        let grid = plan(&params, Sampling::Grid { steps: 3 }, base(), 1).unwrap();
+        assert_eq!(grid.len(), 6);
+        for (point, density) in grid.iter().zip([0.1, 0.5, 0.9]) {
+            assert!((point.density.unwrap() - density).abs() < 1e-9);
+        }
+        assert_eq!(grid[3].rule, "B36/S23".parse().unwrap());
+        assert_eq!(plan(&params, Sampling::Grid { steps: 3 }, base(), 1), Ok(grid));
+
+        // One sample in each tenth of the density range, and half of them per rule
+        let lhs = plan(&params, Sampling::LatinHypercube { samples: 10 }, base(), 1).unwrap();
+        let mut strata: Vec<usize> = lhs.iter().map(|point| ((point.density.unwrap() - 0.1) / 0.08) as usize).collect();
+        strata.sort();
+        assert_eq!(strata, (0..10).collect::<Vec<_>>());
+        assert_eq!(lhs.iter().filter(|point| point.rule == Rule::CONWAY).count(), 5);
+
+        let huge: Vec<Param> = ["offset=0..1", "density=0..1"].iter().map(|param| param.parse().unwrap()).collect();
+        assert!(plan(&huge, Sampling::Grid { steps: 1000 }, base(), 1).is_err());
+    }
+
+    #[test]
+    fn runs_points_into_tidy_rows() {
+        // The blinker behind two bases the offset skips
+        let blinker = [b"TT", BLINKER].concat();
+        let point = Point { offset: 2, rows: 5, cols: 5, ..base() };
+        let cancel = AtomicBool::new(false);
+        let summary = run_point(&blinker, &point, 50, &cancel).unwrap();
+        assert_eq!((summary.outcome.stabilized_at, summary.outcome.period), (Some(0), Some(2)));
+        assert_eq!(summary.drift, Some([0.0, 0.0]));
+
+        let soups = [Point { density: Some(0.0), ..point }, Point { density: Some(1.0), ..point }];
+        let summaries = run(b"", &soups, 50, 2, &cancel, |_| {});
+        assert_eq!(summaries.iter().map(|(index, summary)| (*index, summary.outcome.final_population)).collect::<Vec<_>>(), [(0, 0), (1, 0)]);
+        assert_eq!(summaries[1].1.outcome.max_population, 25);
+
+        let csv = to_csv(&[(0, summary), summaries[1].clone()]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], COLUMNS);
+        assert_eq!(lines[1], "1,B3/S23,NA,2,5x5,NA,0,2,3,3,0.1200,0.3750,0.0000,0.0000");
+        assert!(lines[2].starts_with("2,B3/S23,1.0000,NA,5x5,0,1,1,25,0,"));
+        assert!(lines[2].ends_with(",NA,NA"));
+    }
+}
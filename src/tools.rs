@@ -0,0 +1,250 @@
+use winit::window::CursorIcon;
+
+use crate::action::Action;
+use crate::render::text::{ADVANCE, GLYPH_HEIGHT, font_pixel, push_text};
+use crate::selection::Region;
+use crate::vertex::{Vertex, push_rect};
+use crate::viewport::Viewport;
+
+// Mouse tools: what a left click or drag on the grid does. The event loop only reports
+// presses, drags and releases on cells; the tool turns them into actions, so a new tool is a
+// variant here with its name, cursor, status text and gesture, and the toolbar and the
+// `tool` command pick it up.
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Tool {
+    #[default]
+    Toggle,
+    Paint,
+    Erase,
+    Inspect,
+    Select,
+}
+
+impl Tool {
+    // In toolbar order
+    pub const ALL: [Tool; 5] = [Tool::Toggle, Tool::Paint, Tool::Erase, Tool::Inspect, Tool::Select];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Tool::Toggle => "toggle",
+            Tool::Paint => "paint",
+            Tool::Erase => "erase",
+            Tool::Inspect => "inspect",
+            Tool::Select => "select",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tool| tool.name() == name)
+    }
+
+    // The one after it in the toolbar, wrapping around
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|&tool| tool == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn cursor(self) -> CursorIcon {
+        match self {
+            Tool::Toggle => CursorIcon::Pointer,
+            Tool::Paint => CursorIcon::Crosshair,
+            Tool::Erase => CursorIcon::NotAllowed,
+            Tool::Inspect => CursorIcon::Help,
+            Tool::Select => CursorIcon::Cell,
+        }
+    }
+
+    // What the left button does, shown under the toolbar
+    pub fn status(self) -> &'static str {
+        match self {
+            Tool::Toggle => "Click flips a cell",
+            Tool::Paint => "Drag brings cells to life",
+            Tool::Erase => "Drag kills cells",
+            Tool::Inspect => "Click logs a cell and its base",
+            Tool::Select => "Drag selects a region",
+        }
+    }
+
+    // Start using the tool on a cell: the gesture a drag continues, if any, and the actions
+    // applied right away
+    pub fn press(self, (row, col): (u32, u32)) -> (Option<Gesture>, Vec<Action>) {
+        match self {
+            Tool::Toggle => (None, vec![Action::ToggleCell { row, col }]),
+            Tool::Inspect => (None, vec![Action::InspectCell { row, col }]),
+            Tool::Paint | Tool::Erase => (Some(Gesture::Stroke { alive: self == Tool::Paint, cells: vec![(row, col)] }), Vec::new()),
+            Tool::Select => (Some(Gesture::Select { anchor: (row, col), corner: (row, col) }), Vec::new()),
+        }
+    }
+}
+
+impl std::fmt::Display for Tool {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+// A drag in progress, from the press to the release. Only the finished gesture is an action;
+// until then it is just drawn.
+#[derive(Debug, PartialEq)]
+pub enum Gesture {
+    // Cells a paint or erase drag went over, in order and without repeats
+    Stroke { alive: bool, cells: Vec<(u32, u32)> },
+    // Cell the selection started at and the one it reaches
+    Select { anchor: (u32, u32), corner: (u32, u32) },
+}
+
+// Frame colors of the cells a stroke will bring to life or kill
+const PAINT_COLOR: [f32; 3] = [0.3, 1.0, 0.4];
+const ERASE_COLOR: [f32; 3] = [1.0, 0.3, 0.3];
+
+impl Gesture {
+    // Follow the cursor to `exact`, the cell under it (None off the grid), or `nearest`, the
+    // cell closest to it. Returns whether anything changed.
+    pub fn drag(&mut self, exact: Option<(u32, u32)>, nearest: (u32, u32)) -> bool {
+        match self {
+            Gesture::Stroke { cells, .. } => {
+                let (Some(to), Some(&from)) = (exact, cells.last()) else {
+                    return false;
+                };
+                let before = cells.len();
+                // Fast drags skip cells between mouse events; the stroke goes through them
+                for cell in line(from, to) {
+                    if !cells.contains(&cell) {
+                        cells.push(cell);
+                    }
+                }
+                cells.len() != before
+            }
+            Gesture::Select { corner, .. } => std::mem::replace(corner, nearest) != nearest,
+        }
+    }
+
+    // Framed cells drawn while stroking, as overlay entries
+    pub fn stroke(&self, cols: u32) -> Vec<(usize, [f32; 3])> {
+        match self {
+            Gesture::Stroke { alive, cells } => {
+                let color = if *alive { PAINT_COLOR } else { ERASE_COLOR };
+                cells.iter().map(|&(row, col)| ((row * cols + col) as usize, color)).collect()
+            }
+            Gesture::Select { .. } => Vec::new(),
+        }
+    }
+
+    // Region drawn while selecting
+    pub fn selection(&self) -> Option<Region> {
+        match self {
+            Gesture::Select { anchor, corner } => Some(Region::from_corners(*anchor, *corner)),
+            Gesture::Stroke { .. } => None,
+        }
+    }
+
+    pub fn finish(self) -> Vec<Action> {
+        match self {
+            Gesture::Stroke { alive, cells } => vec![Action::Paint { alive, cells }],
+            Gesture::Select { anchor, corner } => vec![Action::Select(Region::from_corners(anchor, corner))],
+        }
+    }
+}
+
+// Cells on the straight line between two cells, both included
+fn line(from: (u32, u32), to: (u32, u32)) -> Vec<(u32, u32)> {
+    let (dr, dc) = (to.0 as i64 - from.0 as i64, to.1 as i64 - from.1 as i64);
+    let steps = dr.abs().max(dc.abs());
+    (0..=steps)
+        .map(|step| {
+            let t = if steps == 0 { 0.0 } else { step as f64 / steps as f64 };
+            ((from.0 as f64 + dr as f64 * t).round() as u32, (from.1 as f64 + dc as f64 * t).round() as u32)
+        })
+        .collect()
+}
+
+// Font pixels around a button's label
+const BUTTON_PADDING: u32 = 3;
+
+// A row of buttons in the top-right corner of the window, one per tool, with the selected
+// tool's status below them
+pub struct ToolbarLayout {
+    pixel: [f32; 2],
+    // Left edge of each button in ALL order, and the right edge of the last
+    edges: Vec<f32>,
+    bottom: f32,
+}
+
+impl ToolbarLayout {
+    pub fn new(viewport: &Viewport, text_scale: u32) -> Self {
+        let pixel = font_pixel(viewport, text_scale);
+        let widths: Vec<u32> = Tool::ALL.iter().map(|tool| tool.name().len() as u32 * ADVANCE + 2 * BUTTON_PADDING).collect();
+        let mut left = 1.0 - (widths.iter().sum::<u32>() + BUTTON_PADDING) as f32 * pixel[0];
+        let mut edges = vec![left];
+        for width in widths {
+            left += width as f32 * pixel[0];
+            edges.push(left);
+        }
+        Self { pixel, edges, bottom: 1.0 - (GLYPH_HEIGHT + 2 * BUTTON_PADDING) as f32 * pixel[1] }
+    }
+
+    pub fn tool_at(&self, [x, y]: [f32; 2]) -> Option<Tool> {
+        if y < self.bottom {
+            return None;
+        }
+        let index = self.edges.windows(2).position(|edges| (edges[0]..edges[1]).contains(&x))?;
+        Some(Tool::ALL[index])
+    }
+
+    pub fn vertices(&self, selected: Tool) -> Vec<Vertex> {
+        let mut vertices = Vec::new();
+        let [pixel_x, pixel_y] = self.pixel;
+        for (tool, edges) in Tool::ALL.into_iter().zip(self.edges.windows(2)) {
+            let (background, text) = if tool == selected { ([0.85, 0.85, 0.9], [0.03, 0.03, 0.06]) } else { ([0.03, 0.03, 0.06], [0.85, 0.85, 0.85]) };
+            push_rect(&mut vertices, [edges[0], self.bottom], [edges[1], 1.0], [0.4, 0.4, 0.5]);
+            push_rect(&mut vertices, [edges[0] + pixel_x / 2.0, self.bottom + pixel_y / 2.0], [edges[1] - pixel_x / 2.0, 1.0], background);
+            push_text(&mut vertices, tool.name(), [edges[0] + BUTTON_PADDING as f32 * pixel_x, self.bottom + BUTTON_PADDING as f32 * pixel_y], self.pixel, text);
+        }
+        // Status right-aligned under the buttons
+        let status = selected.status();
+        let left = 1.0 - (status.len() as u32 * ADVANCE + BUTTON_PADDING) as f32 * pixel_x;
+        let baseline = self.bottom - (GLYPH_HEIGHT + BUTTON_PADDING) as f32 * pixel_y;
+        push_rect(&mut vertices, [left - BUTTON_PADDING as f32 * pixel_x, baseline - pixel_y], [1.0, self.bottom], [0.03, 0.03, 0.06]);
+        push_text(&mut vertices, status, [left, baseline], self.pixel, [0.85, 0.85, 0.85]);
+        vertices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strokes_fill_gaps_and_finish_as_one_action() {
+        let (gesture, actions) = Tool::Paint.press((2, 2));
+        let mut gesture = gesture.unwrap();
+        assert!(actions.is_empty());
+        // A jump of three cells in one mouse event
+        assert!(gesture.drag(Some((2, 5)), (2, 5)));
+        assert!(!gesture.drag(None, (0, 0)));
+        assert!(!gesture.drag(Some((2, 4)), (2, 4)));
+        assert_eq!(gesture.stroke(10).len(), 4);
+        assert_eq!(gesture.finish(), [Action::Paint { alive: true, cells: vec![(2, 2), (2, 3), (2, 4), (2, 5)] }]);
+
+        assert_eq!(Tool::Toggle.press((1, 2)), (None, vec![Action::ToggleCell { row: 1, col: 2 }]));
+        let mut selecting = Tool::Select.press((4, 4)).0.unwrap();
+        assert!(selecting.drag(None, (1, 6)));
+        assert_eq!(selecting.selection(), Some(Region { row: 1, col: 4, rows: 4, cols: 3 }));
+        assert_eq!(line((0, 0), (2, 4)), [(0, 0), (1, 1), (1, 2), (2, 3), (2, 4)]);
+    }
+
+    #[test]
+    fn toolbar_buttons_hit_their_tools() {
+        let viewport = Viewport { width: 800, height: 600, scale_factor: 1.0 };
+        let layout = ToolbarLayout::new(&viewport, 1);
+        let pixel = font_pixel(&viewport, 1);
+        let middle = |index: usize| [(layout.edges[index] + layout.edges[index + 1]) / 2.0, 1.0 - pixel[1]];
+        for (index, tool) in Tool::ALL.into_iter().enumerate() {
+            assert_eq!(layout.tool_at(middle(index)), Some(tool));
+        }
+        assert_eq!(layout.tool_at([0.0, 0.0]), None);
+        assert_eq!(Tool::from_name("erase"), Some(Tool::Erase));
+        assert_eq!(Tool::Select.next(), Tool::Toggle);
+    }
+}
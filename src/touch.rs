@@ -0,0 +1,178 @@
+use std::time::{Duration, Instant};
+
+use winit::dpi::PhysicalPosition;
+use winit::event::TouchPhase;
+
+// Touch gestures, recognized from winit's touch events so the window can turn them into the
+// same presses, drags and actions as the mouse. One finger taps, drags once it moves past the
+// slop, or long-presses when held still. A second finger landing before the first moved turns
+// it into a pinch, which zooms by the change in distance between the fingers and pans by the
+// movement of their midpoint. Fingers beyond those a gesture uses are ignored, and once a
+// gesture ends nothing new starts until every finger has lifted.
+
+// Physical pixels a finger may wander before a hold becomes a drag
+const SLOP: f64 = 12.0;
+const LONG_PRESS: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TouchGesture {
+    Tap(PhysicalPosition<f64>),
+    LongPress(PhysicalPosition<f64>),
+    // A one-finger drag: where it started, each move, and the lift
+    DragStart(PhysicalPosition<f64>),
+    Drag(PhysicalPosition<f64>),
+    DragEnd,
+    // A two-finger pinch: the midpoint it started at, then the distance between the fingers
+    // relative to the start and where their midpoint is now
+    PinchStart(PhysicalPosition<f64>),
+    Pinch { scale: f64, midpoint: PhysicalPosition<f64> },
+    PinchEnd,
+}
+
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Idle,
+    // One finger down that hasn't moved past the slop yet
+    Holding { id: u64, start: PhysicalPosition<f64>, since: Instant },
+    Dragging { id: u64 },
+    Pinching { ids: [u64; 2], distance: f64 },
+    // The gesture ended; waiting for the rest of the fingers to lift
+    Spent,
+}
+
+#[derive(Debug, Default)]
+pub struct TouchTracker {
+    // Fingers down and where they are
+    touches: Vec<(u64, PhysicalPosition<f64>)>,
+    state: State,
+}
+
+fn distance(a: PhysicalPosition<f64>, b: PhysicalPosition<f64>) -> f64 {
+    (a.x - b.x).hypot(a.y - b.y)
+}
+
+fn midpoint(a: PhysicalPosition<f64>, b: PhysicalPosition<f64>) -> PhysicalPosition<f64> {
+    PhysicalPosition::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0)
+}
+
+impl TouchTracker {
+    fn position(&self, id: u64) -> Option<PhysicalPosition<f64>> {
+        self.touches.iter().find(|(touch, _)| *touch == id).map(|&(_, position)| position)
+    }
+
+    // Gestures a touch event starts, continues or ends
+    pub fn input(&mut self, id: u64, phase: TouchPhase, position: PhysicalPosition<f64>, now: Instant) -> Vec<TouchGesture> {
+        match phase {
+            TouchPhase::Started => {
+                self.touches.retain(|(touch, _)| *touch != id);
+                self.touches.push((id, position));
+                match self.state {
+                    State::Idle => {
+                        self.state = State::Holding { id, start: position, since: now };
+                        Vec::new()
+                    }
+                    State::Holding { id: first, .. } => {
+                        let Some(first_position) = self.position(first) else { return Vec::new() };
+                        self.state = State::Pinching { ids: [first, id], distance: distance(first_position, position).max(1.0) };
+                        vec![TouchGesture::PinchStart(midpoint(first_position, position))]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            TouchPhase::Moved => {
+                if let Some(touch) = self.touches.iter_mut().find(|(touch, _)| *touch == id) {
+                    touch.1 = position;
+                }
+                match self.state {
+                    State::Holding { id: held, start, .. } if held == id && distance(start, position) > SLOP => {
+                        self.state = State::Dragging { id };
+                        vec![TouchGesture::DragStart(start), TouchGesture::Drag(position)]
+                    }
+                    State::Dragging { id: dragged } if dragged == id => vec![TouchGesture::Drag(position)],
+                    State::Pinching { ids, distance: start } if ids.contains(&id) => match (self.position(ids[0]), self.position(ids[1])) {
+                        (Some(a), Some(b)) => vec![TouchGesture::Pinch { scale: distance(a, b) / start, midpoint: midpoint(a, b) }],
+                        _ => Vec::new(),
+                    },
+                    _ => Vec::new(),
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.retain(|(touch, _)| *touch != id);
+                let gestures = match self.state {
+                    State::Holding { id: held, start, .. } if held == id && phase == TouchPhase::Ended => vec![TouchGesture::Tap(start)],
+                    State::Dragging { id: dragged } if dragged == id => vec![TouchGesture::DragEnd],
+                    State::Pinching { ids, .. } if ids.contains(&id) => vec![TouchGesture::PinchEnd],
+                    State::Holding { id: held, .. } if held != id => return Vec::new(),
+                    State::Dragging { .. } | State::Pinching { .. } => return Vec::new(),
+                    _ => Vec::new(),
+                };
+                self.state = if self.touches.is_empty() { State::Idle } else { State::Spent };
+                gestures
+            }
+        }
+    }
+
+    // A long press, once a finger has been held still long enough
+    pub fn poll(&mut self, now: Instant) -> Option<TouchGesture> {
+        let State::Holding { start, since, .. } = self.state else {
+            return None;
+        };
+        if now.duration_since(since) < LONG_PRESS {
+            return None;
+        }
+        self.state = State::Spent;
+        Some(TouchGesture::LongPress(start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(x: f64, y: f64) -> PhysicalPosition<f64> {
+        PhysicalPosition::new(x, y)
+    }
+
+    #[test]
+    fn taps_drags_and_long_presses() {
+        let now = Instant::now();
+        let mut touch = TouchTracker::default();
+        assert!(touch.input(1, TouchPhase::Started, at(100.0, 100.0), now).is_empty());
+        // Within the slop it is still a tap
+        assert!(touch.input(1, TouchPhase::Moved, at(105.0, 100.0), now).is_empty());
+        assert_eq!(touch.input(1, TouchPhase::Ended, at(105.0, 100.0), now), [TouchGesture::Tap(at(100.0, 100.0))]);
+
+        touch.input(2, TouchPhase::Started, at(100.0, 100.0), now);
+        assert_eq!(touch.input(2, TouchPhase::Moved, at(150.0, 100.0), now), [TouchGesture::DragStart(at(100.0, 100.0)), TouchGesture::Drag(at(150.0, 100.0))]);
+        assert_eq!(touch.input(2, TouchPhase::Moved, at(160.0, 100.0), now), [TouchGesture::Drag(at(160.0, 100.0))]);
+        // A dragging finger never long-presses
+        assert_eq!(touch.poll(now + LONG_PRESS), None);
+        assert_eq!(touch.input(2, TouchPhase::Ended, at(160.0, 100.0), now), [TouchGesture::DragEnd]);
+
+        touch.input(3, TouchPhase::Started, at(50.0, 50.0), now);
+        assert_eq!(touch.poll(now + LONG_PRESS / 2), None);
+        assert_eq!(touch.poll(now + LONG_PRESS), Some(TouchGesture::LongPress(at(50.0, 50.0))));
+        // Lifting after the long press doesn't tap as well
+        assert!(touch.input(3, TouchPhase::Ended, at(50.0, 50.0), now).is_empty());
+    }
+
+    #[test]
+    fn two_fingers_pinch() {
+        let now = Instant::now();
+        let mut touch = TouchTracker::default();
+        touch.input(1, TouchPhase::Started, at(100.0, 100.0), now);
+        assert_eq!(touch.input(2, TouchPhase::Started, at(200.0, 100.0), now), [TouchGesture::PinchStart(at(150.0, 100.0))]);
+        assert_eq!(touch.input(2, TouchPhase::Moved, at(300.0, 100.0), now), [TouchGesture::Pinch { scale: 2.0, midpoint: at(200.0, 100.0) }]);
+        // A third finger takes no part
+        assert!(touch.input(3, TouchPhase::Started, at(0.0, 0.0), now).is_empty());
+        assert!(touch.input(3, TouchPhase::Moved, at(10.0, 0.0), now).is_empty());
+        assert_eq!(touch.input(1, TouchPhase::Ended, at(100.0, 100.0), now), [TouchGesture::PinchEnd]);
+        // Nothing new starts until every finger has lifted
+        assert!(touch.input(2, TouchPhase::Moved, at(400.0, 100.0), now).is_empty());
+        assert!(touch.input(2, TouchPhase::Ended, at(400.0, 100.0), now).is_empty());
+        assert!(touch.input(3, TouchPhase::Ended, at(10.0, 0.0), now).is_empty());
+        touch.input(4, TouchPhase::Started, at(0.0, 0.0), now);
+        assert_eq!(touch.input(4, TouchPhase::Ended, at(0.0, 0.0), now), [TouchGesture::Tap(at(0.0, 0.0))]);
+    }
+}
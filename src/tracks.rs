@@ -0,0 +1,203 @@
+use bio::seq_analysis::gc::gc_content;
+
+use crate::heatmap;
+use crate::render::chart::{Chart, ChartStyle};
+use crate::seq_analysis::cache::WindowStats;
+
+// Per-window analyses of the sequence. The selected one is charted below the grid and, when
+// the track layer is on, drawn behind the cells: each cell is tinted by the window holding
+// the base it was seeded from.
+pub struct Track {
+    // Console name, e.g. "gc"
+    pub name: &'static str,
+    pub label: &'static str,
+    // One value per window of `window` bases, the last window possibly shorter
+    pub values: Vec<f64>,
+    pub window: usize,
+    pub color: [f32; 3],
+    // Window marked on the chart, e.g. the skew minimum
+    pub mark: Option<usize>,
+}
+
+impl Track {
+    pub fn range(&self) -> (f64, f64) {
+        let min = self.values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = self.values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        (min, max)
+    }
+
+    // Value of the window holding `position`, scaled to 0..1 between the track's lowest and
+    // highest; 0.5 for a flat track
+    pub fn level(&self, position: usize) -> Option<f32> {
+        self.levels()(position)
+    }
+
+    // `level` for many positions: the range is found once, and as the windows are all the
+    // same size the one holding a position is found by dividing, without a search
+    pub fn levels(&self) -> impl Fn(usize) -> Option<f32> + '_ {
+        let (min, max) = self.range();
+        move |position| {
+            let value = *self.values.get(position / self.window.max(1))?;
+            Some(if max > min { ((value - min) / (max - min)) as f32 } else { 0.5 })
+        }
+    }
+
+    // The track as a line chart, read out by the base each window starts at
+    pub fn chart(&self) -> Chart<'_> {
+        Chart { label: self.label, values: &self.values, color: self.color, style: ChartStyle::Line, mark: self.mark, x_step: self.window, x_unit: "bp" }
+    }
+
+    // Background tint of a cell seeded from a position: the heat map palette, dimmed so
+    // the cells stay readable on top
+    pub fn backgrounds(&self) -> impl Fn(usize) -> Option<[f32; 3]> + '_ {
+        let levels = self.levels();
+        move |position| levels(position).map(|level| heatmap::color(level).map(|channel| channel * 0.45))
+    }
+}
+
+// The tracks that can be shown, in Tab order. Analyses add themselves with `register`.
+pub struct TrackRegistry {
+    tracks: Vec<Track>,
+    selected: usize,
+}
+
+impl TrackRegistry {
+    // GC content, cumulative skew, melting temperature and complexity over the skew's
+    // windows, with the skew selected
+    pub fn new(stats: &WindowStats) -> Self {
+        let skew = &stats.skew;
+        let window = skew.window;
+        let mut registry = Self { tracks: Vec::new(), selected: 0 };
+        registry.register(Track { name: "gc", label: "GC content", values: stats.gc.clone(), window, color: [0.3, 0.8, 0.4], mark: None });
+        registry.register(Track {
+            name: "skew",
+            label: "cumulative GC skew",
+            values: skew.cumulative.clone(),
+            window,
+            color: [0.3, 0.6, 0.9],
+            mark: Some(skew.min_window),
+        });
+        registry.register(Track { name: "tm", label: "melting temperature (C)", values: stats.tm.clone(), window, color: [0.9, 0.5, 0.3], mark: None });
+        registry.register(Track { name: "complexity", label: "base entropy (bits)", values: stats.entropy.clone(), window, color: [0.7, 0.5, 0.9], mark: None });
+        registry.selected = 1;
+        registry
+    }
+
+    // Add a track, replacing any with the same name
+    pub fn register(&mut self, track: Track) {
+        match self.tracks.iter().position(|other| other.name == track.name) {
+            Some(index) => self.tracks[index] = track,
+            None => self.tracks.push(track),
+        }
+    }
+
+    // Drop a track, e.g. one of an analysis that was turned off; the selection moves to the
+    // next track when it was the one removed
+    pub fn remove(&mut self, name: &str) {
+        let Some(index) = self.tracks.iter().position(|track| track.name == name) else {
+            return;
+        };
+        self.tracks.remove(index);
+        if self.selected > index || self.selected == self.tracks.len() {
+            self.selected -= 1;
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Track> {
+        self.tracks.iter().find(|track| track.name == name)
+    }
+
+    pub fn selected(&self) -> &Track {
+        &self.tracks[self.selected]
+    }
+
+    pub fn select(&mut self, name: &str) -> Result<&Track, String> {
+        match self.tracks.iter().position(|track| track.name == name) {
+            Some(index) => {
+                self.selected = index;
+                Ok(&self.tracks[index])
+            }
+            None => Err(format!("Unknown track '{name}', expected one of {}", self.names().join(", "))),
+        }
+    }
+
+    // Select the track after the current one, wrapping around
+    pub fn select_next(&mut self) -> &Track {
+        self.selected = (self.selected + 1) % self.tracks.len();
+        &self.tracks[self.selected]
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.tracks.iter().map(|track| track.name).collect()
+    }
+}
+
+pub fn gc_windows(dna: &[u8], window: usize) -> Vec<f64> {
+    dna.chunks(window.max(1)).map(|chunk| gc_content(chunk) as f64).collect()
+}
+
+// Melting temperature of each window as a duplex
+pub fn melting_temperatures(dna: &[u8], window: usize) -> Vec<f64> {
+    dna.chunks(window.max(1)).map(melting_temperature).collect()
+}
+
+// Melting temperature of `bases` as a duplex: the Wallace rule 2(A+T) + 4(G+C) below 14
+// bases, 64.9 + 41(G+C - 16.4)/N from there on. Bases other than ACGT are left out.
+pub fn melting_temperature(bases: &[u8]) -> f64 {
+    let count = |of: &[u8]| bases.iter().filter(|base| of.contains(&base.to_ascii_uppercase())).count() as f64;
+    let (at, gc) = (count(b"AT"), count(b"GC"));
+    if at + gc < 14.0 { 2.0 * at + 4.0 * gc } else { 64.9 + 41.0 * (gc - 16.4) / (at + gc) }
+}
+
+// Shannon entropy of each window's base composition, from 0 (one base) to 2 bits (all four
+// equally often)
+pub fn entropies(dna: &[u8], window: usize) -> Vec<f64> {
+    dna.chunks(window.max(1))
+        .map(|chunk| {
+            let mut counts = [0usize; 4];
+            for base in chunk {
+                if let Some(index) = b"ACGT".iter().position(|b| b == &base.to_ascii_uppercase()) {
+                    counts[index] += 1;
+                }
+            }
+            let total = counts.iter().sum::<usize>() as f64;
+            counts
+                .iter()
+                .filter(|&&count| count > 0)
+                .map(|&count| {
+                    let p = count as f64 / total;
+                    -p * p.log2()
+                })
+                .sum()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_and_cycles_tracks() {
+        let dna = b"AAAAAAAAAAAAAAAAGGGGCCCCGGGGCCCCACGTACGTACGTACGT";
+        let mut registry = TrackRegistry::new(&WindowStats::compute(dna, 16));
+        assert_eq!(registry.names(), ["gc", "skew", "tm", "complexity"]);
+        assert_eq!(registry.selected().name, "skew");
+
+        let tm = registry.get("tm").unwrap();
+        assert_eq!(tm.values[0], 64.9 + 41.0 * -16.4 / 16.0);
+        let complexity = registry.get("complexity").unwrap();
+        assert_eq!(complexity.values, [0.0, 1.0, 2.0]);
+        assert_eq!([0, 16, 40, 48].map(|position| complexity.level(position)), [Some(0.0), Some(0.5), Some(1.0), None]);
+
+        assert_eq!(registry.select_next().name, "tm");
+        assert_eq!(registry.select_next().name, "complexity");
+        assert_eq!(registry.select_next().name, "gc");
+        assert!(registry.select("melting").is_err());
+        assert_eq!(registry.select("complexity").map(|track| track.label), Ok("base entropy (bits)"));
+        registry.remove("complexity");
+        registry.remove("conservation");
+        assert_eq!(registry.names(), ["gc", "skew", "tm"]);
+        assert_eq!(registry.selected().name, "tm");
+    }
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use crate::universe::Universe;
+
+// Smooth transitions between generations (`smooth`, M): rather than popping on and off, born
+// cells fade in and dying ones shrink out over the tick interval. The cells shown before the
+// current generation are kept so the geometry draws each changed cell as changing from them
+// (vertex.rs), and the shader plays the change as its time uniform goes from 0 to 1, so the
+// geometry is only rebuilt when another generation is shown rather than every frame.
+pub struct Transition {
+    // Cells of the generation shown before the current one, as drawn changing from
+    pub previous: Vec<bool>,
+    current: Vec<bool>,
+    generation: u64,
+    // Clock time the current generation was first shown at
+    shown_at: Duration,
+}
+
+impl Transition {
+    pub fn new(universe: &Universe, now: Duration) -> Self {
+        Self { previous: universe.cells.clone(), current: universe.cells.clone(), generation: universe.generation, shown_at: now }
+    }
+
+    // Record the universe about to be shown. Another generation plays the change from the
+    // one shown before it; edits within a generation and a resized grid are shown as they are.
+    pub fn show(&mut self, universe: &Universe, now: Duration) {
+        if universe.cells.len() != self.current.len() {
+            *self = Self::new(universe, now);
+        } else if universe.generation != self.generation {
+            self.previous = std::mem::replace(&mut self.current, universe.cells.clone());
+            self.generation = universe.generation;
+            self.shown_at = now;
+        } else if universe.cells != self.current {
+            for (idx, &alive) in universe.cells.iter().enumerate() {
+                if alive != self.current[idx] {
+                    self.previous[idx] = alive;
+                    self.current[idx] = alive;
+                }
+            }
+        }
+    }
+
+    // How far through the transition a frame at `now` is, 0 to 1, playing over `interval`
+    pub fn progress(&self, now: Duration, interval: Duration) -> f32 {
+        if interval.is_zero() {
+            return 1.0;
+        }
+        (now.saturating_sub(self.shown_at).as_secs_f32() / interval.as_secs_f32()).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_generations_but_not_edits() {
+        let mut universe = Universe::new(5, 5, b"");
+        for col in 1..4 {
+            universe.toggle(2, col);
+        }
+        let mut transition = Transition::new(&universe, Duration::ZERO);
+        assert_eq!(transition.previous, universe.cells);
+
+        // The blinker turns upright: the ends of the row die and two cells are born
+        let row = universe.cells.clone();
+        universe.tick();
+        transition.show(&universe, Duration::from_millis(100));
+        assert_eq!(transition.previous, row);
+        assert_eq!(transition.progress(Duration::from_millis(150), Duration::from_millis(200)), 0.25);
+        assert_eq!(transition.progress(Duration::from_secs(1), Duration::from_millis(200)), 1.0);
+        assert_eq!(transition.progress(Duration::ZERO, Duration::from_millis(200)), 0.0);
+
+        // A cell toggled mid-transition changes at once, the others keep playing
+        universe.toggle(0, 0);
+        transition.show(&universe, Duration::from_millis(150));
+        assert!(transition.previous[0]);
+        assert_eq!(transition.previous[1..], row[1..]);
+        assert_eq!(transition.progress(Duration::from_millis(150), Duration::from_millis(200)), 0.25);
+
+        // A resized grid starts over with nothing to play
+        let resized = Universe::new(4, 4, b"");
+        transition.show(&resized, Duration::from_millis(200));
+        assert_eq!(transition.previous, resized.cells);
+    }
+}
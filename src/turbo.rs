@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+// Turbo (`turbo 1000`, K): instead of the generations the speed makes due, every frame steps a
+// fixed number of them at once, so a run fast-forwards through thousands of generations while
+// the window still shows where it is once a frame. The plain rule jumps there in one go and
+// Lenia on the GPU chains compute dispatches, reading the states back once; the grid is
+// uploaded once a frame either way. Whatever is stepped a generation at a time stops when the
+// frame's time is up, and the rest of the batch is stepped on the next frames. The rate it
+// reaches is measured over about a second at a time, for the title.
+
+pub const DEFAULT_STEPS: u32 = 100;
+pub const MAX_STEPS: u32 = 1_000_000;
+// Batches stepped a generation at a time (script rules, mutations, the other automata, rules
+// HashLife can't jump) are cut to this
+pub const MAX_STEPPED: u32 = 10_000;
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug)]
+pub struct Turbo {
+    // Generations per frame
+    pub steps: u32,
+    // Generations of the current batch not stepped yet
+    owed: u32,
+    // Generations stepped since the current measurement started
+    window: (Instant, u64),
+    // Generations per second over the last full measurement
+    rate: Option<f64>,
+}
+
+impl Turbo {
+    pub fn new(steps: u32, now: Instant) -> Self {
+        Self { steps: steps.clamp(1, MAX_STEPS), owed: 0, window: (now, 0), rate: None }
+    }
+
+    // Generations to step this frame: what is left of the batch, or a new one of at most
+    // `limit` generations
+    pub fn due(&mut self, limit: u32) -> u32 {
+        if self.owed == 0 {
+            self.owed = self.steps.min(limit);
+        }
+        self.owed
+    }
+
+    // Count a frame's generations, updating the rate once a measurement is long enough
+    pub fn record(&mut self, generations: u64, now: Instant) {
+        self.owed = self.owed.saturating_sub(generations.min(u64::from(u32::MAX)) as u32);
+        let (started, counted) = &mut self.window;
+        *counted += generations;
+        let elapsed = now.saturating_duration_since(*started);
+        if elapsed >= RATE_WINDOW {
+            self.rate = Some(*counted as f64 / elapsed.as_secs_f64());
+            self.window = (now, 0);
+        }
+    }
+
+    pub fn rate(&self) -> Option<f64> {
+        self.rate
+    }
+}
+
+// Twice or half the steps, for the < and > keys
+pub fn faster(steps: u32) -> u32 {
+    steps.saturating_mul(2).min(MAX_STEPS)
+}
+
+pub fn slower(steps: u32) -> u32 {
+    (steps / 2).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_generations_per_second() {
+        let start = Instant::now();
+        let mut turbo = Turbo::new(500, start);
+        turbo.record(500, start + Duration::from_millis(400));
+        assert_eq!(turbo.rate(), None);
+        turbo.record(1500, start + Duration::from_millis(1000));
+        assert_eq!(turbo.rate(), Some(2000.0));
+        // The next measurement starts from nothing
+        turbo.record(500, start + Duration::from_millis(3000));
+        assert_eq!(turbo.rate(), Some(250.0));
+
+        // A batch cut short is finished before the next one starts
+        assert_eq!(turbo.due(MAX_STEPPED), 500);
+        turbo.record(200, start);
+        assert_eq!(turbo.due(MAX_STEPPED), 300);
+        turbo.record(300, start);
+        turbo.steps = 50_000;
+        assert_eq!((turbo.due(MAX_STEPPED), turbo.due(MAX_STEPPED)), (MAX_STEPPED, MAX_STEPPED));
+
+        assert_eq!(Turbo::new(0, start).steps, 1);
+        assert_eq!((faster(MAX_STEPS / 2 + 1), faster(100), slower(100), slower(1)), (MAX_STEPS, 200, 50, 1));
+    }
+}
@@ -1,23 +1,263 @@
+use std::sync::Arc;
+
+use crate::automaton::hashlife;
+use crate::automaton::protein::PropertyClass;
+use crate::bitgrid::BitGrid;
+use crate::gc_rules::{GcClass, GcRules};
+use crate::rule::Rule;
+use crate::selection::Region;
+use crate::seq_analysis::frames::ReadingFrame;
+
+pub mod io;
+
+// How sequence bases decide which cells start alive
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SeedStrategy {
+    // G/C bases are alive
+    #[default]
+    GcContent,
+    // Positions differing from a second sequence are alive, one flag per position
+    // (see `seq_analysis::difference::mismatches`)
+    Difference(Vec<bool>),
+    // Hydrophobic residues of a protein sequence are alive
+    Hydrophobic,
+    // Positions in alignment columns conserved less than a threshold are alive, one flag per
+    // position (see `formats::msa::Alignment::reference_conservation`)
+    Unconserved(Vec<bool>),
+    // Positions whose codon in the reading frame codes for a hydrophobic amino acid are
+    // alive, one residue per position (see `seq_analysis::frames::residues`); shared, as the
+    // strategy is copied into every history entry
+    Translated(ReadingFrame, Arc<[u8]>),
+}
+
+impl SeedStrategy {
+    pub fn alive(&self, position: usize, base: u8) -> bool {
+        match self {
+            SeedStrategy::GcContent => base == b'G' || base == b'C',
+            SeedStrategy::Difference(flags) | SeedStrategy::Unconserved(flags) => flags.get(position).copied().unwrap_or(false),
+            SeedStrategy::Hydrophobic => PropertyClass::of(base) == Some(PropertyClass::Hydrophobic),
+            SeedStrategy::Translated(_, residues) => residues.get(position).is_some_and(|&residue| PropertyClass::of(residue) == Some(PropertyClass::Hydrophobic)),
+        }
+    }
+
+    // Bytes its flags or residues take up, counting shared residues in full
+    pub fn heap_bytes(&self) -> usize {
+        match self {
+            SeedStrategy::Difference(flags) | SeedStrategy::Unconserved(flags) => flags.capacity(),
+            SeedStrategy::Translated(_, residues) => residues.len(),
+            SeedStrategy::GcContent | SeedStrategy::Hydrophobic => 0,
+        }
+    }
+}
+
+// The part of the grid that stays in place when it is resized (`grid`): the grid grows or
+// is cropped on the far sides. Rows count up from the bottom, so the top is the last row.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    pub const ALL: [Anchor; 9] = [
+        Anchor::TopLeft,
+        Anchor::Top,
+        Anchor::TopRight,
+        Anchor::Left,
+        Anchor::Center,
+        Anchor::Right,
+        Anchor::BottomLeft,
+        Anchor::Bottom,
+        Anchor::BottomRight,
+    ];
+
+    // Rows and columns the old cells move by when a `from` grid becomes a `to` grid
+    pub fn offset(self, (from_rows, from_cols): (u32, u32), (to_rows, to_cols): (u32, u32)) -> (i64, i64) {
+        let (rows, cols) = (to_rows as i64 - from_rows as i64, to_cols as i64 - from_cols as i64);
+        let row = match self {
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => 0,
+            Anchor::Left | Anchor::Center | Anchor::Right => rows / 2,
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => rows,
+        };
+        let col = match self {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => 0,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => cols / 2,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => cols,
+        };
+        (row, col)
+    }
+}
+
+// Compass points, "nw" to "se", or "center"
+impl std::str::FromStr for Anchor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Anchor::ALL
+            .into_iter()
+            .find(|anchor| anchor.to_string() == s)
+            .ok_or_else(|| format!("Unknown anchor '{s}', expected nw, n, ne, w, center, e, sw, s or se"))
+    }
+}
+
+impl std::fmt::Display for Anchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            Anchor::TopLeft => "nw",
+            Anchor::Top => "n",
+            Anchor::TopRight => "ne",
+            Anchor::Left => "w",
+            Anchor::Center => "center",
+            Anchor::Right => "e",
+            Anchor::BottomLeft => "sw",
+            Anchor::Bottom => "s",
+            Anchor::BottomRight => "se",
+        };
+        write!(f, "{name}")
+    }
+}
+
+// "<rows>x<cols>", up to 2^30 cells so cell indices fit in a u32
+pub fn parse_grid_size(size: &str) -> Option<(u32, u32)> {
+    let (rows, cols) = size.split_once('x')?;
+    let (rows, cols): (u32, u32) = (rows.parse().ok()?, cols.parse().ok()?);
+    (rows > 0 && cols > 0 && rows as u64 * cols as u64 <= MAX_CELLS).then_some((rows, cols))
+}
+
+pub const MAX_CELLS: u64 = 1 << 30;
+
+// Copy of a `from` grid's values in a `to` grid, moved by `shift` rows and columns
+fn shifted<T: Copy>(values: &[T], from: (u32, u32), to: (u32, u32), shift: (i64, i64), fill: T) -> Vec<T> {
+    let mut moved = vec![fill; (to.0 * to.1) as usize];
+    for row in 0..from.0 {
+        let to_row = row as i64 + shift.0;
+        if !(0..to.0 as i64).contains(&to_row) {
+            continue;
+        }
+        for col in 0..from.1 {
+            let to_col = col as i64 + shift.1;
+            if (0..to.1 as i64).contains(&to_col) {
+                moved[(to_row * to.1 as i64 + to_col) as usize] = values[(row * from.1 + col) as usize];
+            }
+        }
+    }
+    moved
+}
+
+// A universe's cells bit-packed, with what stepping them needs, so they stay packed from one
+// generation to the next. With GC rules the grid is stepped once per rule and each cell keeps
+// the result of its own; the GC classes and the cells fertile enough to be born don't change
+// meanwhile, so their masks are packed once too.
+pub struct Packed {
+    pub grid: BitGrid,
+    pub generation: u64,
+    rule: Rule,
+    classes: Option<[(Rule, BitGrid); 2]>,
+    fertile: Option<BitGrid>,
+}
+
+impl Packed {
+    // Step a generation, returning the grid it was stepped from
+    pub fn step(&mut self) -> BitGrid {
+        let mut next = self.grid.step(&self.rule);
+        for (rule, mask) in self.classes.iter().flatten() {
+            next.select(&self.grid.step(rule), mask);
+        }
+        if let Some(fertile) = &self.fertile {
+            next.limit_births(&self.grid, fertile);
+        }
+        self.generation += 1;
+        std::mem::replace(&mut self.grid, next)
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Universe {
     pub cells: Vec<bool>,
     pub rows: u32,
     pub cols: u32,
+    pub rule: Rule,
+    pub generation: u64,
+    // Optional per-cell metadata in 0..1 (e.g. coverage from a bedGraph); None until one is
+    // loaded, so grids near the size limit don't pay four bytes a cell for it, and read as zero
+    pub signal: Option<Vec<f32>>,
+    // When set, dead cells whose signal is below the threshold cannot be born
+    pub birth_threshold: Option<f32>,
+    // When set, cells follow a rule picked by the GC content around their base instead of `rule`
+    pub gc_rules: Option<GcRules>,
 }
 
 impl Universe {
     pub fn new(rows: u32, cols: u32, dna: &[u8]) -> Self {
-        let mut cells = vec![false; (rows * cols) as usize];
-        
-        // Seed the cells based on DNA sequence
-        // G/C bases create "Alive" cells
-        for (i, &base) in dna.iter().enumerate() {
-            if i >= cells.len() { break; }
-            if base == b'G' || base == b'C' {
-                cells[i] = true;
+        let mut universe = Self {
+            cells: vec![false; (rows * cols) as usize],
+            rows,
+            cols,
+            rule: Rule::default(),
+            generation: 0,
+            signal: None,
+            birth_threshold: None,
+            gc_rules: None,
+        };
+        universe.seed_region(&Region::full(rows, cols), dna, 0);
+        universe
+    }
+
+    // Re-seed only the cells inside `region`, reading the sequence from `offset` onwards
+    // in row-major order. G/C bases create "Alive" cells; cells past the end of the
+    // sequence are left dead. Everything outside the region keeps evolving untouched.
+    pub fn seed_region(&mut self, region: &Region, dna: &[u8], offset: usize) {
+        self.seed_region_with(region, dna, offset, |position, base| SeedStrategy::GcContent.alive(position, base));
+    }
+
+    // Like `seed_region`, but `alive(position, base)` decides each cell from its sequence position and base
+    pub fn seed_region_with(&mut self, region: &Region, dna: &[u8], offset: usize, mut alive: impl FnMut(usize, u8) -> bool) {
+        self.fill_region(region, |i| {
+            let position = offset + i;
+            dna.get(position).is_some_and(|&base| alive(position, base))
+        });
+    }
+
+    // Set the cells inside `region` in row-major order from `alive(i)`, with i counting from 0
+    pub fn fill_region(&mut self, region: &Region, mut alive: impl FnMut(usize) -> bool) {
+        let mut i = 0;
+
+        for row in region.row..region.row.saturating_add(region.rows).min(self.rows) {
+            for col in region.col..region.col.saturating_add(region.cols).min(self.cols) {
+                let idx = (row * self.cols + col) as usize;
+                self.cells[idx] = alive(i);
+                i += 1;
             }
         }
-        
-        Self { cells, rows, cols }
+    }
+
+    // Change the grid to `rows` x `cols`, keeping every cell that still fits where `anchor`
+    // puts it: cells pushed past an edge are cropped and new cells start dead. The signal and
+    // GC classes move with their cells. Returns how many rows and columns the cells moved by.
+    pub fn resize(&mut self, rows: u32, cols: u32, anchor: Anchor) -> (i64, i64) {
+        let (shift_row, shift_col) = anchor.offset((self.rows, self.cols), (rows, cols));
+        let (from, to) = ((self.rows, self.cols), (rows, cols));
+        let shift = (shift_row, shift_col);
+        self.cells = shifted(&self.cells, from, to, shift, false);
+        self.signal = self.signal.take().map(|signal| shifted(&signal, from, to, shift, 0.0));
+        if let Some(gc_rules) = self.gc_rules.as_mut() {
+            gc_rules.classes = shifted(&gc_rules.classes, from, to, shift, GcClass::Average);
+        }
+        (self.rows, self.cols) = (rows, cols);
+        (shift_row, shift_col)
+    }
+
+    // The signal of a cell, zero without one
+    pub fn signal_at(&self, idx: usize) -> f32 {
+        self.signal.as_ref().map_or(0.0, |signal| signal[idx])
     }
 
     pub fn toggle(&mut self, row: u32, col: u32) {
@@ -26,32 +266,95 @@ impl Universe {
     }
 
     pub fn tick(&mut self) {
+        self.tick_many(1);
+    }
+
+    // Step `generations` on one bit-packed copy of the grid, packed before the first and
+    // written back after the last, so runs of ticks stay packed in between
+    pub fn tick_many(&mut self, generations: u64) {
+        if generations == 0 {
+            return;
+        }
+        let mut packed = self.packed();
+        for _ in 0..generations {
+            packed.step();
+        }
+        packed.grid.write_cells(&mut self.cells);
+        self.generation = packed.generation;
+    }
+
+    // The universe bit-packed for stepping
+    pub fn packed(&self) -> Packed {
+        let (rows, cols) = (self.rows, self.cols);
+        let rule = self.gc_rules.as_ref().map_or(self.rule, |gc_rules| gc_rules.rule_for(GcClass::Average, self.rule));
+        let classes = self.gc_rules.as_ref().map(|gc_rules| {
+            [GcClass::Poor, GcClass::Rich].map(|class| (gc_rules.rule_for(class, self.rule), BitGrid::from_fn(rows, cols, |idx| gc_rules.classes[idx] == class)))
+        });
+        let fertile = self.birth_threshold.map(|threshold| BitGrid::from_fn(rows, cols, |idx| self.signal_at(idx) >= threshold));
+        Packed { grid: BitGrid::from_cells(rows, cols, &self.cells), generation: self.generation, rule, classes, fertile }
+    }
+
+    // A `rows` x `cols` grid split into tiles of at most `tile_size` x `tile_size` cells, row by
+    // row, as the grid texture is uploaded
+    pub fn tile_regions(rows: u32, cols: u32, tile_size: u32) -> impl Iterator<Item = Region> {
+        (0..rows).step_by(tile_size as usize).flat_map(move |row| {
+            (0..cols).step_by(tile_size as usize).map(move |col| Region {
+                row,
+                col,
+                rows: tile_size.min(rows - row),
+                cols: tile_size.min(cols - col),
+            })
+        })
+    }
+
+    // Whether `jump` goes through HashLife rather than a tick per generation
+    pub fn jumps(&self) -> bool {
+        hashlife::supports(&self.rule) && self.birth_threshold.is_none() && self.gc_rules.is_none()
+    }
+
+    // Advance many generations at once. HashLife handles any rule without B0 on a
+    // uniform grid; anything else falls back to ticking one generation at a time.
+    pub fn jump(&mut self, generations: u64) {
+        if self.jumps() {
+            hashlife::jump(self, generations);
+        } else {
+            self.tick_many(generations);
+        }
+    }
+
+    // Reference per-cell implementation, kept for equivalence tests and benchmarks
+    pub fn tick_scalar(&mut self) {
+        let (rule, threshold, signal, gc_rules) = (self.rule, self.birth_threshold, self.signal.clone(), self.gc_rules.clone());
+        self.tick_with(|idx, alive, live_neighbors| {
+            let fertile = alive || threshold.is_none_or(|threshold| signal.as_ref().map_or(0.0, |signal| signal[idx]) >= threshold);
+            let rule = gc_rules.as_ref().map_or(rule, |gc_rules| gc_rules.rule_for(gc_rules.classes[idx], rule));
+            fertile && rule.next_state(alive, live_neighbors)
+        });
+    }
+
+    // Tick with a custom transition `next_state(idx, alive, live_neighbors)` instead of the rule,
+    // e.g. one provided by a script
+    pub fn tick_with(&mut self, mut next_state: impl FnMut(usize, bool, u8) -> bool) {
         let mut next = self.cells.clone();
 
         for row in 0..self.rows {
             for col in 0..self.cols {
                 let live_neighbors = self.live_neighbor_count(row, col);
                 let idx = (row * self.cols + col) as usize;
-
-                let next_state = match (self.cells[idx], live_neighbors) {
-                    (true, x) if x < 2 => false,   // Underpopulation
-                    (true, 2) | (true, 3) => true, // Survival
-                    (true, x) if x > 3 => false,   // Overpopulation
-                    (false, 3) => true,            // Birth
-                    (otherwise, _) => otherwise,   // Stay same
-                };
-
-                next[idx] = next_state;
+                next[idx] = next_state(idx, self.cells[idx], live_neighbors);
             }
         }
         self.cells = next;
+        self.generation += 1;
     }
 
     fn live_neighbor_count(&self, row: u32, col: u32) -> u8 {
         let mut count = 0;
-        for delta_row in [self.rows - 1, 0, 1].iter().cloned() {
-            for delta_col in [self.cols - 1, 0, 1].iter().cloned() {
-                if delta_row == 0 && delta_col == 0 { continue; }
+        // Skip the centre by position, not by offset: on a grid one cell wide the
+        // wrapped offsets are zero too, and then the cell is its own neighbor
+        for (i, delta_row) in [self.rows - 1, 0, 1].into_iter().enumerate() {
+            for (j, delta_col) in [self.cols - 1, 0, 1].into_iter().enumerate() {
+                if i == 1 && j == 1 { continue; }
 
                 let neighbor_row = (row + delta_row) % self.rows;
                 let neighbor_col = (col + delta_col) % self.cols;
@@ -62,3 +365,319 @@ impl Universe {
         count
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // A vertical blinker on a 5x5 grid: G/C at (1, 2), (2, 2), (3, 2)
+    pub(crate) const BLINKER: &[u8] = b"AAAAAAACAAAAGAAAACAAAAAAA";
+
+    // Deterministic pseudo-random DNA so the tests don't need an RNG dependency
+    fn pseudo_dna(len: usize, mut state: u64) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                b"ACGT"[(state >> 62) as usize]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn packed_tick_matches_scalar() {
+        let sizes = [(2, 2), (3, 5), (10, 10), (7, 63), (9, 64), (5, 65), (12, 130), (64, 200)];
+        let high_life = Rule { birth: (1 << 3) | (1 << 6), survive: (1 << 2) | (1 << 3) };
+        let day_and_night = Rule { birth: 0b1_1100_1000, survive: 0b1_1101_1000 };
+
+        for rule in [Rule::CONWAY, high_life, day_and_night] {
+            for (i, &(rows, cols)) in sizes.iter().enumerate() {
+                let dna = pseudo_dna((rows * cols) as usize, i as u64 + 1);
+                let mut packed = Universe::new(rows, cols, &dna);
+                let mut scalar = Universe::new(rows, cols, &dna);
+                packed.rule = rule;
+                scalar.rule = rule;
+
+                for generation in 0..20 {
+                    packed.tick();
+                    scalar.tick_scalar();
+                    assert_eq!(packed.cells, scalar.cells, "{rows}x{cols} diverged at generation {generation}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gc_rules_match_scalar() {
+        let (rows, cols) = (20, 30);
+        // Runs of AT-rich, balanced and GC-rich sequence, so all three rules are in play
+        let dna: Vec<u8> = (0..(rows * cols) as usize).map(|i| match (i / 150) % 3 {
+            0 => b"ATTA"[i % 4],
+            1 => b"ACGT"[i % 4],
+            _ => b"GCCG"[i % 4],
+        }).collect();
+        let mut packed = Universe::new(rows, cols, &pseudo_dna(dna.len(), 3));
+        let b0 = Rule { birth: 1 | (1 << 3), survive: 1 << 2 };
+        let gc_rules = GcRules::new(b0, crate::gc_rules::DEFAULT_RICH, &dna, &crate::strip::SeedMap::new(rows, cols), rows, cols);
+        assert!(gc_rules.counts().iter().all(|&count| count > 0));
+        packed.gc_rules = Some(gc_rules);
+        let mut scalar = packed.clone();
+
+        for generation in 0..15 {
+            packed.tick();
+            scalar.tick_scalar();
+            assert_eq!(packed.cells, scalar.cells, "diverged at generation {generation}");
+        }
+    }
+
+    #[test]
+    fn runs_of_packed_ticks_match_scalar() {
+        let day_and_night = Rule { birth: 0b1_1100_1000, survive: 0b1_1101_1000 };
+        let b0 = Rule { birth: 1 | (1 << 3), survive: 1 << 2 };
+
+        for rule in [Rule::CONWAY, day_and_night, b0] {
+            for (i, &(rows, cols)) in [(20, 30), (16, 16), (5, 70), (1, 9), (9, 1), (3, 128)].iter().enumerate() {
+                let mut dna = pseudo_dna((rows * cols) as usize, i as u64 + 7);
+                // A dead stretch
+                dna[..(rows * cols / 3) as usize].fill(b'A');
+                let mut packed = Universe::new(rows, cols, &dna);
+                packed.rule = rule;
+                // Half the cells too poorly covered to be born
+                packed.signal = Some((0..dna.len()).map(|idx| (idx % 2) as f32).collect());
+                packed.birth_threshold = Some(0.5);
+                let mut scalar = packed.clone();
+
+                packed.tick_many(15);
+                for _ in 0..15 {
+                    scalar.tick_scalar();
+                }
+                assert_eq!(packed.cells, scalar.cells, "{rows}x{cols} diverged after 15 generations");
+                assert_eq!(packed.generation, 15);
+            }
+        }
+    }
+
+    #[test]
+    fn seed_region_only_touches_region() {
+        let mut universe = Universe::new(4, 4, b"AAAAAAAAAAAAAAAA");
+        universe.cells[0] = true;
+        universe.seed_region(&Region { row: 1, col: 1, rows: 2, cols: 2 }, b"TTGCAG", 2);
+
+        let alive: Vec<usize> = (0..16).filter(|&i| universe.cells[i]).collect();
+        // Offset 2 reads "GCAG": (1,1) G, (1,2) C, (2,1) A, (2,2) G
+        assert_eq!(alive, vec![0, 5, 6, 10]);
+    }
+
+    #[test]
+    fn resize_keeps_cells_at_the_anchor() {
+        // One live cell in each corner of a 3x4 grid, rows counting up from the bottom
+        let corners = |universe: &Universe| -> Vec<(u32, u32)> {
+            (0..universe.cells.len() as u32).filter(|&i| universe.cells[i as usize]).map(|i| (i / universe.cols, i % universe.cols)).collect()
+        };
+        let grid = || {
+            let mut universe = Universe::new(3, 4, b"");
+            let mut signal = vec![0.0; 12];
+            for idx in [0, 3, 8, 11] {
+                universe.cells[idx] = true;
+                signal[idx] = 1.0;
+            }
+            universe.signal = Some(signal);
+            universe
+        };
+        let grown = [
+            (Anchor::TopLeft, (2, 0)),
+            (Anchor::Top, (2, 1)),
+            (Anchor::TopRight, (2, 2)),
+            (Anchor::Left, (1, 0)),
+            (Anchor::Center, (1, 1)),
+            (Anchor::Right, (1, 2)),
+            (Anchor::BottomLeft, (0, 0)),
+            (Anchor::Bottom, (0, 1)),
+            (Anchor::BottomRight, (0, 2)),
+        ];
+        for (anchor, (row, col)) in grown {
+            let mut universe = grid();
+            assert_eq!(universe.resize(5, 6, anchor), (row as i64, col as i64), "{anchor}");
+            assert_eq!((universe.rows, universe.cols, universe.cells.len()), (5, 6, 30));
+            let expected = vec![(row, col), (row, col + 3), (row + 2, col), (row + 2, col + 3)];
+            assert_eq!(corners(&universe), expected, "{anchor}");
+            assert_eq!(universe.signal.iter().flatten().filter(|&&signal| signal == 1.0).count(), 4, "{anchor}");
+
+            // Shrinking back with the same anchor undoes the move
+            universe.resize(3, 4, anchor);
+            assert_eq!(corners(&universe), corners(&grid()), "{anchor}");
+        }
+
+        // Cropping to one cell keeps only the corner at the anchor
+        for anchor in [Anchor::TopLeft, Anchor::TopRight, Anchor::BottomLeft, Anchor::BottomRight] {
+            let mut universe = grid();
+            universe.resize(1, 1, anchor);
+            assert_eq!((universe.cells.clone(), universe.signal), (vec![true], Some(vec![1.0])), "{anchor}");
+        }
+        // The middle of the grid, and the edges around it, hold none of them
+        for anchor in [Anchor::Top, Anchor::Left, Anchor::Center, Anchor::Right, Anchor::Bottom] {
+            let mut universe = grid();
+            universe.resize(1, 2, anchor);
+            assert_eq!(universe.cells, vec![false, false], "{anchor}");
+        }
+    }
+
+    #[test]
+    fn anchors_round_trip_through_their_names() {
+        for anchor in Anchor::ALL {
+            assert_eq!(anchor.to_string().parse(), Ok(anchor));
+        }
+        assert!("middle".parse::<Anchor>().is_err());
+    }
+
+    #[test]
+    fn birth_threshold_limits_births_to_high_signal() {
+        let dna = pseudo_dna(24 * 24, 7);
+        let mut packed = Universe::new(24, 24, &dna);
+        packed.signal = Some((0..24 * 24).map(|i| (i % 24) as f32 / 23.0).collect());
+        packed.birth_threshold = Some(0.5);
+
+        let mut scalar = Universe::new(24, 24, &dna);
+        scalar.signal = packed.signal.clone();
+        scalar.birth_threshold = packed.birth_threshold;
+
+        for _ in 0..10 {
+            let before = packed.cells.clone();
+            packed.tick();
+            scalar.tick_scalar();
+            assert_eq!(packed.cells, scalar.cells);
+
+            for (idx, (&was, &is)) in before.iter().zip(&packed.cells).enumerate() {
+                assert!(was || !is || packed.signal_at(idx) >= 0.5, "cell {idx} was born below the threshold");
+            }
+        }
+    }
+
+    // Empty universe with the given (row, col) cells alive
+    fn with_cells(rows: u32, cols: u32, alive: &[(u32, u32)]) -> Universe {
+        let mut universe = Universe::new(rows, cols, b"");
+        for &(row, col) in alive {
+            universe.toggle(row, col);
+        }
+        universe
+    }
+
+    fn shifted(universe: &Universe, delta_row: u32, delta_col: u32) -> Vec<bool> {
+        let mut cells = vec![false; universe.cells.len()];
+        for row in 0..universe.rows {
+            for col in 0..universe.cols {
+                let to = ((row + delta_row) % universe.rows * universe.cols + (col + delta_col) % universe.cols) as usize;
+                cells[to] = universe.cells[(row * universe.cols + col) as usize];
+            }
+        }
+        cells
+    }
+
+    #[test]
+    fn still_lifes_are_stable() {
+        let block = [(1, 1), (1, 2), (2, 1), (2, 2)];
+        let beehive = [(1, 2), (1, 3), (2, 1), (2, 4), (3, 2), (3, 3)];
+        let loaf = [(1, 2), (1, 3), (2, 1), (2, 4), (3, 2), (3, 4), (4, 3)];
+        let boat = [(1, 1), (1, 2), (2, 1), (2, 3), (3, 2)];
+
+        for pattern in [&block[..], &beehive, &loaf, &boat] {
+            let mut universe = with_cells(8, 8, pattern);
+            let initial = universe.cells.clone();
+            for _ in 0..5 {
+                universe.tick();
+                assert_eq!(universe.cells, initial, "{pattern:?} changed");
+            }
+        }
+    }
+
+    #[test]
+    fn blinker_has_period_two() {
+        let mut universe = with_cells(5, 5, &[(2, 1), (2, 2), (2, 3)]);
+        let horizontal = universe.cells.clone();
+
+        universe.tick();
+        assert_eq!(universe.cells, with_cells(5, 5, &[(1, 2), (2, 2), (3, 2)]).cells);
+        universe.tick();
+        assert_eq!(universe.cells, horizontal);
+        assert_eq!(universe.generation, 2);
+    }
+
+    #[test]
+    fn glider_translates_and_wraps() {
+        let mut universe = with_cells(8, 10, &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+        let initial = with_cells(8, 10, &[(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)]);
+
+        // One cell diagonally every 4 generations, crossing the edges on a torus
+        for period in 1..=40 {
+            for _ in 0..4 {
+                universe.tick();
+            }
+            assert_eq!(universe.cells, shifted(&initial, period % 8, period % 10), "after {period} periods");
+        }
+        // lcm(8, 10) periods bring it back to where it started
+        assert_eq!(universe.cells, initial.cells);
+    }
+
+    fn any_universe() -> impl Strategy<Value = Universe> {
+        (1u32..20, 1u32..80).prop_flat_map(|(rows, cols)| {
+            proptest::collection::vec(any::<bool>(), (rows * cols) as usize).prop_map(move |cells| {
+                let mut universe = Universe::new(rows, cols, b"");
+                universe.cells = cells;
+                universe
+            })
+        })
+    }
+
+    fn any_rule() -> impl Strategy<Value = Rule> {
+        (0u16..1 << 9, 0u16..1 << 9).prop_map(|(birth, survive)| Rule { birth, survive })
+    }
+
+    proptest! {
+        #[test]
+        fn packed_and_hashlife_match_reference(mut universe in any_universe(), rule in any_rule(), generations in 1u64..40) {
+            universe.rule = rule;
+            let mut reference = universe.clone();
+            let mut jumped = universe.clone();
+
+            for _ in 0..generations {
+                universe.tick();
+                reference.tick_scalar();
+                prop_assert_eq!(&universe.cells, &reference.cells);
+            }
+            jumped.jump(generations);
+            prop_assert_eq!(&jumped.cells, &reference.cells);
+        }
+
+        #[test]
+        fn tick_is_independent_of_visiting_order(universe in any_universe(), seed in any::<u64>()) {
+            let mut ticked = universe.clone();
+            ticked.tick();
+
+            // Visit cells in a pseudo-random order, always reading the previous generation
+            let mut order: Vec<usize> = (0..universe.cells.len()).collect();
+            let mut state = seed;
+            for i in (1..order.len()).rev() {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                order.swap(i, (state >> 33) as usize % (i + 1));
+            }
+            let mut next = vec![false; universe.cells.len()];
+            for idx in order {
+                let (row, col) = (idx as u32 / universe.cols, idx as u32 % universe.cols);
+                next[idx] = universe.rule.next_state(universe.cells[idx], universe.live_neighbor_count(row, col));
+            }
+            prop_assert_eq!(ticked.cells, next);
+        }
+
+        #[test]
+        fn tick_commutes_with_translation(universe in any_universe(), delta_row in 0u32..20, delta_col in 0u32..80) {
+            let mut moved = universe.clone();
+            moved.cells = shifted(&universe, delta_row, delta_col);
+            moved.tick();
+
+            let mut ticked = universe.clone();
+            ticked.tick();
+
+            prop_assert_eq!(moved.cells, shifted(&ticked, delta_row, delta_col));
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::path::Path;
+
+use super::{MAX_CELLS, Universe};
+use crate::selection::Region;
+
+// Cell patterns in the text formats other Life tools read and write, for moving patterns in
+// and out of the grid (`pattern load` / `pattern save`):
+//
+//   Life 1.06  `.lif`, `.life`  a "#Life 1.06" header, then one "x y" line per live cell,
+//                               x being the column; coordinates may be negative
+//   plaintext  `.cells`         '!' comment lines, then one line per row with '.' for dead
+//                               and 'O' for live cells; short rows are padded with dead cells
+//
+// Files with another extension are told apart by their contents.
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Format {
+    Life106,
+    Plaintext,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "lif" | "life" => Some(Format::Life106),
+            "cells" => Some(Format::Plaintext),
+            _ => None,
+        }
+    }
+
+    // Life 1.06 when the header is there or the first line is a pair of numbers
+    pub fn detect(text: &str) -> Self {
+        let first = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+        let coordinates = first.split_whitespace().map(|value| value.parse::<i64>()).collect::<Result<Vec<_>, _>>();
+        if first.starts_with("#Life 1.06") || coordinates.is_ok_and(|values| values.len() == 2) {
+            Format::Life106
+        } else {
+            Format::Plaintext
+        }
+    }
+}
+
+// Cells of the pattern's bounding box in row-major order
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub rows: u32,
+    pub cols: u32,
+    pub cells: Vec<bool>,
+}
+
+impl Pattern {
+    // Live cells given as (row, col), trimmed to the box around them
+    fn from_live(live: &[(i64, i64)]) -> Result<Self, String> {
+        let (Some(top), Some(left)) = (live.iter().map(|cell| cell.0).min(), live.iter().map(|cell| cell.1).min()) else {
+            return Ok(Self { rows: 0, cols: 0, cells: Vec::new() });
+        };
+        let rows = live.iter().map(|cell| cell.0).max().unwrap_or(top).abs_diff(top).saturating_add(1);
+        let cols = live.iter().map(|cell| cell.1).max().unwrap_or(left).abs_diff(left).saturating_add(1);
+        let (rows, cols) = fits(rows, cols)?;
+        let mut cells = vec![false; rows as usize * cols as usize];
+        for (row, col) in live {
+            cells[((row - top) * cols as i64 + col - left) as usize] = true;
+        }
+        Ok(Self { rows, cols, cells })
+    }
+
+    // Live cells of `region` of the grid, trimmed to the box around them
+    pub fn from_region(universe: &Universe, region: &Region) -> Self {
+        let mut live = Vec::new();
+        for row in region.row..region.row.saturating_add(region.rows).min(universe.rows) {
+            for col in region.col..region.col.saturating_add(region.cols).min(universe.cols) {
+                if universe.cells[(row * universe.cols + col) as usize] {
+                    live.push((row as i64, col as i64));
+                }
+            }
+        }
+        Self::from_live(&live).expect("a grid region fits in u32")
+    }
+
+    pub fn population(&self) -> usize {
+        self.cells.iter().filter(|&&alive| alive).count()
+    }
+
+    pub fn parse(text: &str, format: Format) -> Result<Self, String> {
+        match format {
+            Format::Life106 => {
+                let mut live = Vec::new();
+                for (number, line) in text.lines().enumerate() {
+                    let line = line.trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    let values: Vec<i64> = line
+                        .split_whitespace()
+                        .map(|value| value.parse())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| format!("line {}: expected 'x y', found '{line}'", number + 1))?;
+                    let [x, y] = values[..] else {
+                        return Err(format!("line {}: expected 'x y', found '{line}'", number + 1));
+                    };
+                    live.push((y, x));
+                }
+                Self::from_live(&live)
+            }
+            Format::Plaintext => {
+                let mut lines: Vec<&str> = text.lines().filter(|line| !line.starts_with('!')).map(str::trim_end).collect();
+                while lines.last().is_some_and(|line| line.is_empty()) {
+                    lines.pop();
+                }
+                // Dead space around the live cells is part of the pattern
+                let (rows, cols) = (lines.len(), lines.iter().map(|line| line.chars().count()).max().unwrap_or(0));
+                fits(rows as u64, cols as u64)?;
+                let mut cells = vec![false; rows * cols];
+                for (row, line) in lines.iter().enumerate() {
+                    for (col, symbol) in line.chars().enumerate() {
+                        match symbol {
+                            'O' | '*' => cells[row * cols + col] = true,
+                            '.' => {}
+                            _ => return Err(format!("row {}: unexpected '{symbol}'", row + 1)),
+                        }
+                    }
+                }
+                Ok(Self { rows: rows as u32, cols: cols as u32, cells })
+            }
+        }
+    }
+
+    pub fn write(&self, format: Format) -> String {
+        let live = self.cells.iter().enumerate().filter(|&(_, &alive)| alive).map(|(i, _)| (i as u32 / self.cols, i as u32 % self.cols));
+        match format {
+            Format::Life106 => {
+                let mut text = "#Life 1.06\n".to_string();
+                for (row, col) in live {
+                    text.push_str(&format!("{col} {row}\n"));
+                }
+                text
+            }
+            Format::Plaintext => {
+                let mut text = "!Name: bio-rust\n".to_string();
+                for row in self.cells.chunks(self.cols.max(1) as usize) {
+                    text.extend(row.iter().map(|&alive| if alive { 'O' } else { '.' }));
+                    text.push('\n');
+                }
+                text
+            }
+        }
+    }
+
+    // Clear `region` and put the pattern in its middle
+    pub fn place(&self, universe: &mut Universe, region: &Region) -> Result<(), String> {
+        if self.rows > region.rows || self.cols > region.cols {
+            return Err(format!("Pattern of {}x{} cells doesn't fit in {}x{}", self.rows, self.cols, region.rows, region.cols));
+        }
+        let (top, left) = ((region.rows - self.rows) / 2, (region.cols - self.cols) / 2);
+        universe.fill_region(region, |i| {
+            let (row, col) = (i as u32 / region.cols, i as u32 % region.cols);
+            let inside = (top..top + self.rows).contains(&row) && (left..left + self.cols).contains(&col);
+            inside && self.cells[((row - top) * self.cols + col - left) as usize]
+        });
+        Ok(())
+    }
+}
+
+// Patterns are held as a full grid of cells, so one larger than the biggest grid is refused
+// before it is allocated
+fn fits(rows: u64, cols: u64) -> Result<(u32, u32), String> {
+    match (u32::try_from(rows), u32::try_from(cols)) {
+        (Ok(small_rows), Ok(small_cols)) if rows.saturating_mul(cols) <= MAX_CELLS => Ok((small_rows, small_cols)),
+        _ => Err(format!("Pattern of {rows}x{cols} cells is larger than {MAX_CELLS} cells")),
+    }
+}
+
+pub fn load(path: &Path) -> Result<Pattern, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+    let format = Format::from_path(path).unwrap_or_else(|| Format::detect(&text));
+    Pattern::parse(&text, format).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+pub fn save(path: &Path, pattern: &Pattern) -> Result<(), String> {
+    let format = Format::from_path(path).ok_or_else(|| format!("Unknown pattern format for {}, expected .cells, .lif or .life", path.display()))?;
+    std::fs::write(path, pattern.write(format)).map_err(|err| format!("Failed to write {}: {err}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLIDER: &str = "!Name: Glider\n.O\n..O\nOOO\n";
+
+    #[test]
+    fn round_trips_both_formats() {
+        let glider = Pattern::parse(GLIDER, Format::Plaintext).unwrap();
+        assert_eq!((glider.rows, glider.cols, glider.population()), (3, 3, 5));
+        for format in [Format::Life106, Format::Plaintext] {
+            let text = glider.write(format);
+            assert_eq!(Format::detect(&text), format);
+            assert_eq!(Pattern::parse(&text, format).unwrap(), glider);
+        }
+
+        // Negative coordinates and repeats, trimmed to the pattern
+        let life = Pattern::parse("#Life 1.06\n0 -1\n1 0\n-1 1\n0 1\n1 1\n1 1\n", Format::Life106).unwrap();
+        assert_eq!(life, glider);
+        assert_eq!(Format::detect("3 4\n"), Format::Life106);
+        assert!(Pattern::parse("#Life 1.06\n1 2 3\n", Format::Life106).unwrap_err().starts_with("line 2"));
+        assert!(Pattern::parse("..X\n", Format::Plaintext).is_err());
+        // Two far apart cells would need a grid of 2^60 cells
+        assert!(Pattern::parse("0 0\n1073741823 1073741823\n", Format::Life106).unwrap_err().contains("larger"));
+        assert!(Pattern::parse("-9223372036854775808 0\n9223372036854775807 0\n", Format::Life106).is_err());
+        assert_eq!(Format::from_path(Path::new("glider.LIF")), Some(Format::Life106));
+    }
+
+    #[test]
+    fn places_in_the_middle_and_trims_on_the_way_out() {
+        let glider = Pattern::parse(GLIDER, Format::Plaintext).unwrap();
+        let mut universe = Universe::new(7, 9, b"GGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGGG");
+        let region = Region::full(7, 9);
+        glider.place(&mut universe, &region).unwrap();
+        assert_eq!(universe.cells.iter().filter(|&&alive| alive).count(), 5);
+        assert!(universe.cells[(2 * 9 + 4) as usize]);
+        assert_eq!(Pattern::from_region(&universe, &region), glider);
+
+        // Plaintext keeps leading dead space
+        let padded = Pattern::parse("...\n..O\n", Format::Plaintext).unwrap();
+        assert_eq!((padded.rows, padded.cols), (2, 3));
+        assert!(glider.place(&mut universe, &Region { row: 0, col: 0, rows: 2, cols: 9 }).is_err());
+    }
+}
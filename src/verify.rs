@@ -0,0 +1,170 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::fnv::cells_hash;
+use crate::provenance::Provenance;
+
+// Checking a run against a reference run (`hashes`, `verify`): recording writes a hash of the
+// cells after every generation, and verifying compares each generation stepped with the
+// reference's, flagging the first one that differs. Recorded on one machine and verified on
+// another, or with the simulation thread against inline stepping, it shows whether they
+// compute the same run cell for cell. Files are CSV under the provenance comments:
+//
+//   # version: bio-rust 0.1.0
+//   ...
+//   generation,hash
+//   0,5e2f0d8c1a93b447
+//   1,9d04c7e6b1f2a385
+
+const HEADER: &str = "generation,hash";
+
+pub struct HashRecorder {
+    pub path: PathBuf,
+    writer: BufWriter<File>,
+    pub count: u64,
+}
+
+impl HashRecorder {
+    pub fn create(path: &Path, provenance: &Provenance) -> Result<Self, String> {
+        let error = |err: std::io::Error| format!("Failed to create {}: {err}", path.display());
+        let mut writer = BufWriter::new(File::create(path).map_err(error)?);
+        writer.write_all(provenance.commented(&format!("{HEADER}\n")).as_bytes()).map_err(error)?;
+        Ok(Self { path: path.to_path_buf(), writer, count: 0 })
+    }
+
+    pub fn record(&mut self, generation: u64, cells: &[bool]) -> Result<(), String> {
+        writeln!(self.writer, "{generation},{:016x}", cells_hash(cells)).map_err(|err| err.to_string())?;
+        self.count += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<u64, String> {
+        self.writer.flush().map_err(|err| err.to_string())?;
+        Ok(self.count)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Reference {
+    pub hashes: BTreeMap<u64, u64>,
+}
+
+impl Reference {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {err}", path.display()))?;
+        Self::parse(&text).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.starts_with('#') && !line.trim().is_empty());
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err(format!("expected a '{HEADER}' header"));
+        }
+        let mut hashes = BTreeMap::new();
+        for (number, line) in lines {
+            let invalid = || format!("line {}: invalid row '{line}'", number + 1);
+            let (generation, hash) = line.trim().split_once(',').ok_or_else(invalid)?;
+            let generation = generation.parse().map_err(|_| invalid())?;
+            hashes.insert(generation, u64::from_str_radix(hash, 16).map_err(|_| invalid())?);
+        }
+        if hashes.is_empty() {
+            return Err("no generations".to_string());
+        }
+        Ok(Self { hashes })
+    }
+
+    pub fn last(&self) -> u64 {
+        self.hashes.last_key_value().map_or(0, |(&generation, _)| generation)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Divergence {
+    pub generation: u64,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+// A reference being compared with the run as it goes
+pub struct Verifier {
+    pub path: PathBuf,
+    pub reference: Reference,
+    // Generations compared so far, and the first that differed
+    pub checked: BTreeSet<u64>,
+    pub divergence: Option<Divergence>,
+}
+
+impl Verifier {
+    pub fn new(path: PathBuf, reference: Reference) -> Self {
+        Self { path, reference, checked: BTreeSet::new(), divergence: None }
+    }
+
+    // Compare a generation with the reference, returning the divergence the first time one
+    // is found. Generations the reference lacks, and any after a divergence, aren't compared.
+    pub fn check(&mut self, generation: u64, cells: &[bool]) -> Option<Divergence> {
+        if self.divergence.is_some() {
+            return None;
+        }
+        let &expected = self.reference.hashes.get(&generation)?;
+        self.checked.insert(generation);
+        let actual = cells_hash(cells);
+        if actual != expected {
+            self.divergence = Some(Divergence { generation, expected, actual });
+        }
+        self.divergence
+    }
+
+    // Every generation of the reference compared and found equal
+    pub fn is_complete(&self) -> bool {
+        self.divergence.is_none() && self.checked.len() == self.reference.hashes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::universe::Universe;
+
+    #[test]
+    fn flags_the_first_generation_that_differs() {
+        let path = std::env::temp_dir().join(format!("bio-rust-hashes-{}.csv", std::process::id()));
+        let mut universe = Universe::new(16, 16, b"GATCCAGATCGATCCGATCGATC");
+        let provenance = Provenance::new("built-in", 0, 0, universe.rule.to_string(), None, 0);
+        let mut recorder = HashRecorder::create(&path, &provenance).unwrap();
+        let mut scalar = universe.clone();
+        for _ in 0..10 {
+            recorder.record(universe.generation, &universe.cells).unwrap();
+            universe.tick();
+        }
+        assert_eq!(recorder.finish(), Ok(10));
+
+        // The tiled and the scalar stepping agree
+        let reference = Reference::load(&path).unwrap();
+        assert_eq!(reference.last(), 9);
+        let mut verifier = Verifier::new(path.clone(), reference);
+        for _ in 0..10 {
+            assert_eq!(verifier.check(scalar.generation, &scalar.cells), None);
+            scalar.tick_scalar();
+        }
+        assert!(verifier.is_complete());
+        assert_eq!(verifier.check(10, &scalar.cells), None);
+
+        // A flipped cell is caught at its generation, and only reported once
+        let mut verifier = Verifier::new(path.clone(), Reference::load(&path).unwrap());
+        let mut universe = Universe::new(16, 16, b"GATCCAGATCGATCCGATCGATC");
+        assert_eq!(verifier.check(0, &universe.cells), None);
+        universe.tick();
+        universe.cells[3] = !universe.cells[3];
+        let divergence = verifier.check(1, &universe.cells).unwrap();
+        assert_eq!((divergence.generation, divergence.actual), (1, cells_hash(&universe.cells)));
+        universe.tick();
+        assert_eq!(verifier.check(2, &universe.cells), None);
+        assert!(!verifier.is_complete());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(Reference::parse("generation,hash\n").is_err());
+        assert!(Reference::parse("generation,hash\n3,xyz\n").unwrap_err().starts_with("line 2"));
+    }
+}
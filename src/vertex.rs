@@ -1,14 +1,26 @@
+use std::ops::Range;
+
+#[cfg(feature = "gui")]
 use wgpu::*;
+
+use crate::heatmap;
+use crate::layers::{Composite, Layer, Layers, mix};
+use crate::lod;
+use crate::selection::Region;
 use crate::universe::Universe;
+use crate::viewport::GridGeometry;
 
 #[repr(C)]
-#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(Copy, Clone, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct Vertex {
     pub position: [f32; 2],
     pub color: [f32; 3],
+    // Per-cell signal (coverage, conservation...) in 0..1, brightening the cell
+    pub signal: f32,
 }
 
 impl Vertex {
+    #[cfg(feature = "gui")]
     pub fn desc() -> VertexBufferLayout<'static> {
         VertexBufferLayout {
             array_stride: std::mem::size_of::<Vertex>() as BufferAddress,
@@ -23,39 +35,627 @@ impl Vertex {
                     offset: std::mem::size_of::<[f32; 2]>() as BufferAddress,
                     shader_location: 1,
                     format: VertexFormat::Float32x3,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 5]>() as BufferAddress,
+                    shader_location: 2,
+                    format: VertexFormat::Float32,
+                }
+            ]
+        }
+    }
+}
+
+// Smooth transitions of the grid's vertices, in a buffer of their own next to the vertices
+// so nothing else pays for them, played by the shader as the time uniform goes from 0 to 1
+// over the generation. `fade` is the color before the generation less the vertex's color,
+// and `shrink` how much of the shape's size around `anchor` (the cell's middle) goes; all
+// zero for vertices drawn as they are.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Motion {
+    pub fade: [f32; 3],
+    pub anchor: [f32; 2],
+    pub shrink: f32,
+}
+
+impl Motion {
+    #[cfg(feature = "gui")]
+    pub fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<Motion>() as BufferAddress,
+            step_mode: VertexStepMode::Vertex,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 3,
+                    format: VertexFormat::Float32x3,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 5]>() as BufferAddress,
+                    shader_location: 5,
+                    format: VertexFormat::Float32,
                 }
             ]
         }
     }
 }
 
-pub fn create_grid_vertices(universe: &Universe, cell_size: f32) -> Vec<Vertex> {
+// Shapes drawn over a single cell, filled rather than framing it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Glyph {
+    // Triangle pointing right, or left
+    Arrow { right: bool },
+    // Upright bar through the middle
+    Bar,
+    Diamond,
+    // Triangle hanging from the top edge
+    Pin,
+}
+
+// Extra information drawn on top of the cells
+#[derive(Default)]
+pub struct Overlay {
+    pub selection: Option<Region>,
+    // Heat map view: per-cell activity in 0..1 colors the cells instead of their state
+    pub heat: Option<Vec<f32>>,
+    // Per-cell colors of a multi-state automaton (cyclic), drawn instead of the cell states
+    pub colors: Option<Vec<[f32; 3]>>,
+    // Codon optimality view: cells inside coding sequences are tinted from red (rare codon)
+    // to green (preferred codon) by the adaptiveness in 0..1; empty when off
+    pub optimality: Vec<Option<f32>>,
+    // Cells marked with a small inset square of the given color
+    pub markers: Vec<(usize, [f32; 3])>,
+    // Cells outlined with a frame of the given color, e.g. motif matches
+    pub highlights: Vec<(usize, [f32; 3])>,
+    // Cells with an ant on them, in ant mode
+    pub ants: Vec<usize>,
+    // Cells seeded from the base under the cursor in the sequence strip, outlined in white
+    pub hovered: Vec<usize>,
+    // Cells of a paint or erase stroke in progress, outlined in the color of what it does
+    pub stroke: Vec<(usize, [f32; 3])>,
+    // Cells underlined in grey, e.g. masked low-complexity positions
+    pub masked: Vec<usize>,
+    // Cells seeded from a base with a note, marked with a small square in the top-right corner
+    pub notes: Vec<usize>,
+    // Cells marked with a plus of the given color, e.g. VCF variants
+    pub variants: Vec<(usize, [f32; 3])>,
+    // Cells marked with a small shape of the given color, e.g. start and stop codons
+    pub glyphs: Vec<(usize, Glyph, [f32; 3])>,
+    // Cells shaded with diagonal stripes of the given color, e.g. tandem repeats
+    pub stripes: Vec<(usize, [f32; 3])>,
+    // Color of each visible cell drawn behind the cell and the gap around it, e.g. the
+    // selected sequence-analysis track; empty when off
+    pub background: RegionColors,
+    // Population centroid trail as (column, row) in cell units, oldest first; the last
+    // point is the current centroid
+    pub trail: Vec<[f32; 2]>,
+    // Centroid trail of the largest group of live cells, drawn like the population's
+    pub blob_trail: Vec<[f32; 2]>,
+    // Closed outlines of the live regions as (column, row) in cell units; empty when off
+    pub contours: Vec<Vec<[f32; 2]>>,
+    // Stack of layers the cells are composited from
+    pub layers: Layers,
+    // Color of the base each visible cell was seeded from, for the bases layer; empty when it
+    // is hidden
+    pub bases: RegionColors,
+    // Window background the layers are composited over
+    pub backdrop: [f32; 3],
+    // Accessibility palette: white live cells framed in amber across the gap, on black
+    pub high_contrast: bool,
+    // Dead cells drawn as one fill under the grid rather than one by one, which hides the
+    // lines between cells but saves most of the vertices
+    pub fill_dead: bool,
+}
+
+// Colors of the cells of a region of the grid, e.g. the part in view, row by row
+#[derive(Clone, Debug, Default)]
+pub struct RegionColors {
+    pub region: Region,
+    pub colors: Vec<Option<[f32; 3]>>,
+}
+
+impl RegionColors {
+    pub fn get(&self, row: u32, col: u32) -> Option<[f32; 3]> {
+        if !self.region.contains(row, col) {
+            return None;
+        }
+        let idx = (row - self.region.row) as usize * self.region.cols as usize + (col - self.region.col) as usize;
+        self.colors.get(idx).copied().flatten()
+    }
+}
+
+pub const CONTRAST_OUTLINE: [f32; 3] = [1.0, 0.75, 0.0];
+// Underline of masked cells, ants, and the tick marking a track's notable window
+pub const MASK_COLOR: [f32; 3] = [0.5, 0.5, 0.5];
+pub const ANT_COLOR: [f32; 3] = [1.0, 0.35, 0.1];
+pub const TRACK_MARK_COLOR: [f32; 3] = [0.9, 0.2, 0.9];
+pub const NOTE_COLOR: [f32; 3] = [1.0, 0.85, 0.3];
+pub const BLOB_TRAIL_COLOR: [f32; 3] = [1.0, 0.6, 0.2];
+pub const CONTOUR_COLOR: [f32; 3] = [0.6, 0.95, 1.0];
+const GRID_LINE_COLOR: [f32; 3] = [0.45, 0.45, 0.5];
+// Width of a shading stripe along the cell's edges, in cells
+const STRIPE_WIDTH: f32 = 0.2;
+pub const ALIVE_COLOR: [f32; 3] = [0.2, 0.8, 0.2];
+pub const DEAD_COLOR: [f32; 3] = [0.1, 0.1, 0.1];
+
+// Fill color of a cell, from its state and whatever view colors it instead
+pub fn cell_color(universe: &Universe, overlay: &Overlay, row: u32, col: u32) -> [f32; 3] {
+    let selected = overlay.selection.is_some_and(|region| region.contains(row, col));
+    let idx = (row * universe.cols + col) as usize;
+    state_color(overlay, idx, universe.cells[idx], selected)
+}
+
+// The same, as if the cell was or wasn't alive and selected: the cells and selection layers,
+// and the cell as it was before a smooth transition
+fn state_color(overlay: &Overlay, idx: usize, alive: bool, selected: bool) -> [f32; 3] {
+    let optimality = overlay.optimality.get(idx).copied().flatten();
+    match (&overlay.heat, alive, selected) {
+        (Some(heat), _, _) => heatmap::color(heat[idx]),
+        (None, _, _) if let Some(colors) = &overlay.colors => colors[idx],
+        (None, alive, false) if let Some(optimality) = optimality => optimality_color(optimality, alive),
+        (None, true, false) if overlay.high_contrast => [1.0, 1.0, 1.0],
+        (None, false, false) if overlay.high_contrast => [0.0, 0.0, 0.0],
+        (None, true, true) if overlay.high_contrast => [1.0, 1.0, 0.0],
+        (None, false, true) if overlay.high_contrast => [0.35, 0.35, 0.0],
+        (None, true, false) => ALIVE_COLOR,       // Alive: Green
+        (None, false, false) => DEAD_COLOR,       // Dead: Dark Grey
+        (None, true, true) => [0.8, 0.8, 0.2],    // Selected Alive: Yellow
+        (None, false, true) => [0.3, 0.3, 0.12],  // Selected Dead: Dim Yellow
+    }
+}
+
+// A cell composited from the cell layers (layers.rs), as if it was or wasn't alive
+fn composite(universe: &Universe, overlay: &Overlay, row: u32, col: u32, alive: bool) -> Composite {
+    let idx = (row * universe.cols + col) as usize;
+    let selected = overlay.selection.is_some_and(|region| region.contains(row, col));
+    overlay.layers.compose(overlay.backdrop, |layer| match layer {
+        Layer::Bases => overlay.bases.get(row, col),
+        Layer::Heat => overlay.background.get(row, col),
+        Layer::Cells => Some(state_color(overlay, idx, alive, false)),
+        Layer::Selection => selected.then(|| state_color(overlay, idx, alive, true)),
+        Layer::Grid | Layer::Annotations => None,
+    })
+}
+
+// Every visible cell, composited from the layers, with the grid lines in the gaps and the
+// annotations over them
+pub fn create_grid_vertices(universe: &Universe, geometry: &GridGeometry, overlay: &Overlay) -> Vec<Vertex> {
+    grid_vertices(universe, geometry, overlay, None)
+}
+
+// The same with smooth transitions from `previous`, the cells of the generation shown before:
+// cells that changed since are drawn changing from how they looked. The motions go with the
+// vertices from the first up to the last one that moves.
+pub fn create_smooth_grid_vertices(universe: &Universe, geometry: &GridGeometry, overlay: &Overlay, previous: &[bool]) -> (Vec<Vertex>, Vec<Motion>) {
+    let mut motions = Vec::new();
+    let vertices = grid_vertices(universe, geometry, overlay, Some((previous, &mut motions)));
+    (vertices, motions)
+}
+
+fn grid_vertices(universe: &Universe, geometry: &GridGeometry, overlay: &Overlay, mut smooth: Option<(&[bool], &mut Vec<Motion>)>) -> Vec<Vertex> {
     let mut vertices = Vec::new();
-    let padding = 0.02;
+    let [width, height] = geometry.cell;
+    let gap = [geometry.pitch[0] - width, geometry.pitch[1] - height];
+    let (rows, cols) = (geometry.visible_range(universe.rows, 1), geometry.visible_range(universe.cols, 0));
+    let layers = &overlay.layers;
+
+    let dead = if overlay.high_contrast { [0.0; 3] } else { DEAD_COLOR };
+    let compose = |row: u32, col: u32, alive: bool| composite(universe, overlay, row, col, alive);
+    // A dead cell no other layer paints, as the fill under the grid shows it
+    let fill = layers.compose(overlay.backdrop, |layer| (layer == Layer::Cells).then_some(dead)).cell();
+    if overlay.fill_dead && !rows.is_empty() && !cols.is_empty() {
+        let (first, last) = (geometry.cell_origin(rows.start, cols.start), geometry.cell_origin(rows.end - 1, cols.end - 1));
+        let corners = [first, [last[0] + width, last[1] + height]];
+        push_rect(&mut vertices, [0, 1].map(|axis| corners[0][axis].min(corners[1][axis])), [0, 1].map(|axis| corners[0][axis].max(corners[1][axis])), fill);
+    }
+
+    // Below the heat layer, its frames around the cells cover the lines
+    let grid_lines = layers.shown(Layer::Grid).then(|| {
+        let color = mix(overlay.backdrop, GRID_LINE_COLOR, layers.opacity(Layer::Grid));
+        create_grid_line_vertices(geometry, rows.clone(), cols.clone(), color)
+    });
+    let heat = layers.opacity(Layer::Heat);
+    if !layers.below(Layer::Heat, Layer::Grid) {
+        vertices.extend(grid_lines.iter().flatten());
+    }
 
-    for row in 0..universe.rows {
-        for col in 0..universe.cols {
+    let mut composites = Vec::with_capacity(rows.len() * cols.len());
+    for row in rows.clone() {
+        for col in cols.clone() {
             let idx = (row * universe.cols + col) as usize;
-            
-            let color = if universe.cells[idx] {
-                [0.2, 0.8, 0.2] // Alive: Green
+            let alive = universe.cells[idx];
+            let composite = compose(row, col, alive);
+            composites.push(composite);
+            let color = composite.cell();
+            let signal = universe.signal_at(idx);
+            // How the cell looked before the generation, if it changed in a way that shows
+            let before = smooth
+                .as_ref()
+                .and_then(|(previous, _)| previous.get(idx).copied())
+                .filter(|&was| was != alive)
+                .map(|was| compose(row, col, was).cell())
+                .filter(|&before| before != color);
+
+            let [x_offset, y_offset] = geometry.cell_origin(row, col);
+            let (max, anchor) = ([x_offset + width, y_offset + height], [x_offset + width / 2.0, y_offset + height / 2.0]);
+            let outline = (overlay.high_contrast && alive && layers.shown(Layer::Cells)).then_some(CONTRAST_OUTLINE);
+            let frame = outline.or_else(|| overlay.background.get(row, col).filter(|_| heat > 0.0).map(|tint| mix(overlay.backdrop, tint, heat)));
+            if let Some(frame) = frame {
+                push_rect(&mut vertices, [x_offset - gap[0] / 2.0, y_offset - gap[1] / 2.0], [x_offset + width + gap[0] / 2.0, y_offset + height + gap[1] / 2.0], frame);
+            }
+
+            // Births fade in from how the cell looked; deaths keep their look in a square
+            // shrinking out over the new one
+            let fade = match before {
+                Some(before) if alive => [0, 1, 2].map(|channel| before[channel] - color[channel]),
+                _ => [0.0; 3],
+            };
+            let mut moving = |vertices: &[Vertex], motion: Motion| {
+                if let Some((_, motions)) = smooth.as_mut() {
+                    motions.resize(vertices.len(), Motion::default());
+                    motions.extend([motion; 6]);
+                }
+            };
+            if !(overlay.fill_dead && color == fill && signal == 0.0 && frame.is_none() && fade == [0.0; 3]) {
+                if fade != [0.0; 3] {
+                    moving(&vertices, Motion { fade, anchor, shrink: 0.0 });
+                }
+                vertices.extend(rect([x_offset, y_offset], max, color).map(|vertex| Vertex { signal, ..vertex }));
+            }
+            if let Some(before) = before.filter(|_| !alive) {
+                moving(&vertices, Motion { anchor, shrink: 1.0, ..Default::default() });
+                vertices.extend(rect([x_offset, y_offset], max, before).map(|vertex| Vertex { signal, ..vertex }));
+            }
+        }
+    }
+    if layers.below(Layer::Heat, Layer::Grid) {
+        vertices.extend(grid_lines.iter().flatten());
+    }
+
+    if !layers.shown(Layer::Annotations) {
+        return vertices;
+    }
+    let mut annotations = create_overlay_vertices(universe, geometry, overlay);
+    // Unless drawn as they are, each shape is mixed with the cell under its middle and seen
+    // through the cell layers above
+    let opacity = layers.opacity(Layer::Annotations);
+    let covered = layers.stack.iter().skip_while(|state| state.layer != Layer::Annotations).any(|state| state.shown && !matches!(state.layer, Layer::Annotations | Layer::Grid));
+    if opacity < 1.0 || covered {
+        for triangle in annotations.chunks_mut(3) {
+            let middle = [0, 1].map(|axis| triangle.iter().map(|vertex| vertex.position[axis]).sum::<f32>() / 3.0);
+            let (row, col) = geometry.nearest_cell(universe.rows, universe.cols, middle[0], middle[1]);
+            let composite = if rows.contains(&row) && cols.contains(&col) {
+                composites[((row - rows.start) as usize) * cols.len() + (col - cols.start) as usize]
             } else {
-                [0.1, 0.1, 0.1] // Dead: Dark Grey
+                Composite { under: overlay.backdrop, scale: 1.0, offset: [0.0; 3] }
             };
+            for vertex in triangle {
+                vertex.color = composite.over(mix(composite.under, vertex.color, opacity));
+            }
+        }
+    }
+    vertices.extend(annotations);
+    vertices
+}
 
-            let x_offset = (col as f32 * (cell_size + padding)) - 0.6;
-            let y_offset = (row as f32 * (cell_size + padding)) - 0.6;
+// Lines through the gaps between the visible cells, across the visible part of the grid
+fn create_grid_line_vertices(geometry: &GridGeometry, rows: Range<u32>, cols: Range<u32>, color: [f32; 3]) -> Vec<Vertex> {
+    let mut vertices = Vec::new();
+    if rows.is_empty() || cols.is_empty() {
+        return vertices;
+    }
+    let [width, height] = geometry.cell;
+    let (first, last) = (geometry.cell_origin(rows.start, cols.start), geometry.cell_origin(rows.end - 1, cols.end - 1));
+    let (bottom, top) = (first[1].min(last[1] + height), first[1].max(last[1] + height));
+    let (left, right) = (first[0].min(last[0] + width), first[0].max(last[0] + width));
+    for col in cols.start + 1..cols.end {
+        let x = geometry.cell_origin(rows.start, col)[0];
+        push_rect(&mut vertices, [x - (geometry.pitch[0] - width), bottom], [x, top], color);
+    }
+    for row in rows.start + 1..rows.end {
+        let y = geometry.cell_origin(row, cols.start)[1];
+        push_rect(&mut vertices, [left, y - (geometry.pitch[1] - height)], [right, y], color);
+    }
+    vertices
+}
 
-            vertices.extend_from_slice(&[
-                Vertex { position: [x_offset, y_offset + cell_size], color },
-                Vertex { position: [x_offset, y_offset], color },
-                Vertex { position: [x_offset + cell_size, y_offset], color },
+// The grid zoomed out past a pixel a cell, each `block`x`block` cells one square (lod.rs).
+// Views that color the cells themselves and changed layers are averaged over each block,
+// composited like single cells; otherwise blocks are
+// shaded by how many cells are alive, and empty ones left to a fill under the grid.
+pub fn create_lod_vertices(universe: &Universe, geometry: &GridGeometry, overlay: &Overlay, block: u32) -> Vec<Vertex> {
+    let mut vertices = Vec::new();
+    let rows = lod::blocks(geometry.visible_range(universe.rows, 1), universe.rows, block);
+    let cols = lod::blocks(geometry.visible_range(universe.cols, 0), universe.cols, block);
+    if rows.is_empty() || cols.is_empty() {
+        return create_overlay_vertices(universe, geometry, overlay);
+    }
+    // Bottom-left and top-right corners of a block, the grid's last ones cut short
+    let corners = |row: u32, col: u32| {
+        let [x, y] = geometry.cell_origin(row * block, col * block);
+        let size = [((col + 1) * block).min(universe.cols) - col * block, ((row + 1) * block).min(universe.rows) - row * block];
+        ([x, y], [x + size[0] as f32 * geometry.pitch[0], y + size[1] as f32 * geometry.pitch[1]], size[0] * size[1])
+    };
 
-                Vertex { position: [x_offset, y_offset + cell_size], color },
-                Vertex { position: [x_offset + cell_size, y_offset], color },
-                Vertex { position: [x_offset + cell_size, y_offset + cell_size], color },
-            ]);
+    let dead = if overlay.high_contrast { [0.0; 3] } else { DEAD_COLOR };
+    let colored = overlay.heat.is_some() || overlay.colors.is_some() || !overlay.optimality.is_empty() || overlay.selection.is_some() || overlay.layers != Layers::default();
+    if colored {
+        for row in rows.clone() {
+            for col in cols.clone() {
+                let (min, max, count) = corners(row, col);
+                let mut sum = [0.0; 3];
+                for cell_row in row * block..((row + 1) * block).min(universe.rows) {
+                    for cell_col in col * block..((col + 1) * block).min(universe.cols) {
+                        let alive = universe.cells[(cell_row * universe.cols + cell_col) as usize];
+                        let color = composite(universe, overlay, cell_row, cell_col, alive).cell();
+                        (0..3).for_each(|channel| sum[channel] += color[channel]);
+                    }
+                }
+                push_rect(&mut vertices, min, max, sum.map(|channel| channel / count as f32));
+            }
+        }
+    } else {
+        let (first, _, _) = corners(rows.start, cols.start);
+        let (_, last, _) = corners(rows.end - 1, cols.end - 1);
+        push_rect(&mut vertices, first, last, dead);
+        let alive = if overlay.high_contrast { [1.0; 3] } else { ALIVE_COLOR };
+        let counts = lod::reduce(&universe.cells, universe.rows, universe.cols, rows.clone(), cols.clone(), block);
+        for (i, &count) in counts.iter().enumerate().filter(|&(_, &count)| count > 0) {
+            let (row, col) = (rows.start + (i / cols.len()) as u32, cols.start + (i % cols.len()) as u32);
+            let (min, max, cells) = corners(row, col);
+            let shade = lod::shade(count, cells);
+            push_rect(&mut vertices, min, max, [0, 1, 2].map(|channel| dead[channel] + (alive[channel] - dead[channel]) * shade));
         }
     }
+
+    vertices.extend(create_overlay_vertices(universe, geometry, overlay));
     vertices
 }
+
+// Highlights and markers drawn over the cells, without the cells themselves
+pub fn create_overlay_vertices(universe: &Universe, geometry: &GridGeometry, overlay: &Overlay) -> Vec<Vertex> {
+    let mut vertices = Vec::new();
+    let [width, height] = geometry.cell;
+    let (rows, cols) = (geometry.visible_range(universe.rows, 1), geometry.visible_range(universe.cols, 0));
+    let hidden = |idx: usize| idx >= universe.cells.len() || !rows.contains(&(idx as u32 / universe.cols)) || !cols.contains(&(idx as u32 % universe.cols));
+    let origin = |idx: usize| geometry.cell_origin(idx as u32 / universe.cols, idx as u32 % universe.cols);
+
+    for &idx in &overlay.masked {
+        if hidden(idx) {
+            continue;
+        }
+        let [x, y] = origin(idx);
+        push_rect(&mut vertices, [x, y], [x + width, y + height * 0.2], MASK_COLOR);
+    }
+
+    // Four bands across the cell, between the diagonals u + v = c and c + STRIPE_WIDTH
+    for &(idx, color) in &overlay.stripes {
+        if hidden(idx) {
+            continue;
+        }
+        let [x, y] = origin(idx);
+        let at = |u: f32, v: f32| [x + width * u, y + height * v];
+        for c in [0.25, 0.75, 1.25, 1.75] {
+            let w = STRIPE_WIDTH;
+            let [a, b, d, e] = match c < 1.0 {
+                true => [at(c, 0.0), at(c + w, 0.0), at(0.0, c + w), at(0.0, c)],
+                false => [at(1.0, c - 1.0), at(1.0, c + w - 1.0), at(c + w - 1.0, 1.0), at(c - 1.0, 1.0)],
+            };
+            push_triangle(&mut vertices, a, b, d, color);
+            push_triangle(&mut vertices, a, d, e, color);
+        }
+    }
+
+    let hovered = overlay.hovered.iter().map(|&idx| (idx, [1.0; 3]));
+    for (idx, color) in overlay.highlights.iter().copied().chain(hovered).chain(overlay.stroke.iter().copied()) {
+        if hidden(idx) {
+            continue;
+        }
+        let [x, y] = origin(idx);
+        let (frame_x, frame_y) = (width * 0.12, height * 0.12);
+        push_rect(&mut vertices, [x, y], [x + width, y + frame_y], color);
+        push_rect(&mut vertices, [x, y + height - frame_y], [x + width, y + height], color);
+        push_rect(&mut vertices, [x, y], [x + frame_x, y + height], color);
+        push_rect(&mut vertices, [x + width - frame_x, y], [x + width, y + height], color);
+    }
+
+    for &(idx, color) in &overlay.variants {
+        if hidden(idx) {
+            continue;
+        }
+        let [x, y] = origin(idx);
+        let (inset_x, inset_y) = (width * 0.15, height * 0.15);
+        let (half_x, half_y) = (width * 0.08, height * 0.08);
+        let (middle_x, middle_y) = (x + width / 2.0, y + height / 2.0);
+        push_rect(&mut vertices, [x + inset_x, middle_y - half_y], [x + width - inset_x, middle_y + half_y], color);
+        push_rect(&mut vertices, [middle_x - half_x, y + inset_y], [middle_x + half_x, y + height - inset_y], color);
+    }
+
+    for &(idx, glyph, color) in &overlay.glyphs {
+        if hidden(idx) {
+            continue;
+        }
+        let [x, y] = origin(idx);
+        let at = |u: f32, v: f32| [x + width * u, y + height * v];
+        match glyph {
+            Glyph::Arrow { right: true } => push_triangle(&mut vertices, at(0.2, 0.15), at(0.2, 0.85), at(0.85, 0.5), color),
+            Glyph::Arrow { right: false } => push_triangle(&mut vertices, at(0.8, 0.15), at(0.8, 0.85), at(0.15, 0.5), color),
+            Glyph::Bar => push_rect(&mut vertices, at(0.38, 0.1), at(0.62, 0.9), color),
+            Glyph::Diamond => {
+                push_triangle(&mut vertices, at(0.2, 0.5), at(0.8, 0.5), at(0.5, 0.85), color);
+                push_triangle(&mut vertices, at(0.2, 0.5), at(0.8, 0.5), at(0.5, 0.15), color);
+            }
+            Glyph::Pin => push_triangle(&mut vertices, at(0.2, 1.0), at(0.8, 1.0), at(0.5, 0.45), color),
+        }
+    }
+
+    for &idx in &overlay.notes {
+        if hidden(idx) {
+            continue;
+        }
+        let [x, y] = origin(idx);
+        push_rect(&mut vertices, [x + width * 0.6, y + height * 0.6], [x + width, y + height], NOTE_COLOR);
+    }
+
+    // Cell units to clip space, at the cell centers
+    let position = |[x, y]: [f32; 2]| {
+        [geometry.origin[0] + x * geometry.pitch[0] + width / 2.0, geometry.origin[1] + y * geometry.pitch[1] + height / 2.0]
+    };
+    // A quarter of a cell thick, but never thinner than the trail's half, so outlines stay
+    // visible when zoomed far out
+    let thickness = (width.min(height) * 0.25).max(0.004);
+    for outline in &overlay.contours {
+        for (i, &a) in outline.iter().enumerate() {
+            push_line(&mut vertices, position(a), position(outline[(i + 1) % outline.len()]), thickness, CONTOUR_COLOR);
+        }
+    }
+    // The trail's line, and the square marking its head
+    let trails = [(&overlay.trail, [0.95, 0.4, 0.95], [1.0, 0.6, 1.0]), (&overlay.blob_trail, BLOB_TRAIL_COLOR, [1.0, 0.8, 0.45])];
+    for (trail, color, head) in trails {
+        for (i, pair) in trail.windows(2).enumerate() {
+            let (a, b) = (pair[0], pair[1]);
+            // Steps that wrapped around an edge would cross the whole grid
+            if (a[0] - b[0]).abs() > universe.cols as f32 / 2.0 || (a[1] - b[1]).abs() > universe.rows as f32 / 2.0 {
+                continue;
+            }
+            // Older parts of the trail fade out
+            let fade = 0.3 + 0.7 * (i + 1) as f32 / trail.len() as f32;
+            push_line(&mut vertices, position(a), position(b), 0.008, color.map(|channel: f32| channel * fade));
+        }
+        if let Some(&last) = trail.last() {
+            let [x, y] = position(last);
+            push_rect(&mut vertices, [x - 0.012, y - 0.012], [x + 0.012, y + 0.012], head);
+        }
+    }
+
+    let ants = overlay.ants.iter().map(|&idx| (idx, ANT_COLOR));
+    for (idx, color) in overlay.markers.iter().copied().chain(ants) {
+        if hidden(idx) {
+            continue;
+        }
+        let [x, y] = origin(idx);
+        let (inset_x, inset_y) = (width * 0.3, height * 0.3);
+        push_rect(&mut vertices, [x + inset_x, y + inset_y], [x + width - inset_x, y + height - inset_y], color);
+    }
+    vertices
+}
+
+// Red through yellow to green, dimmed for dead cells
+fn optimality_color(optimality: f32, alive: bool) -> [f32; 3] {
+    let t = optimality.clamp(0.0, 1.0);
+    let color = [(2.0 - 2.0 * t).min(1.0) * 0.9, (2.0 * t).min(1.0) * 0.8, 0.15];
+    let brightness = if alive { 1.0 } else { 0.35 };
+    color.map(|channel| channel * brightness)
+}
+
+// Segment from `a` to `b` as a quad `thickness` wide
+pub fn push_line(vertices: &mut Vec<Vertex>, a: [f32; 2], b: [f32; 2], thickness: f32, color: [f32; 3]) {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let len = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+    let (nx, ny) = (-dy / len * thickness / 2.0, dx / len * thickness / 2.0);
+
+    vertices.extend_from_slice(&[
+        Vertex { position: [a[0] + nx, a[1] + ny], color, ..Default::default() },
+        Vertex { position: [a[0] - nx, a[1] - ny], color, ..Default::default() },
+        Vertex { position: [b[0] - nx, b[1] - ny], color, ..Default::default() },
+
+        Vertex { position: [a[0] + nx, a[1] + ny], color, ..Default::default() },
+        Vertex { position: [b[0] - nx, b[1] - ny], color, ..Default::default() },
+        Vertex { position: [b[0] + nx, b[1] + ny], color, ..Default::default() },
+    ]);
+}
+
+pub fn push_triangle(vertices: &mut Vec<Vertex>, a: [f32; 2], b: [f32; 2], c: [f32; 2], color: [f32; 3]) {
+    vertices.extend_from_slice(&[
+        Vertex { position: a, color, ..Default::default() },
+        Vertex { position: b, color, ..Default::default() },
+        Vertex { position: c, color, ..Default::default() },
+    ]);
+}
+
+pub fn push_rect(vertices: &mut Vec<Vertex>, min: [f32; 2], max: [f32; 2], color: [f32; 3]) {
+    vertices.extend_from_slice(&rect(min, max, color));
+}
+
+fn rect(min: [f32; 2], max: [f32; 2], color: [f32; 3]) -> [Vertex; 6] {
+    [
+        Vertex { position: [min[0], max[1]], color, ..Default::default() },
+        Vertex { position: [min[0], min[1]], color, ..Default::default() },
+        Vertex { position: [max[0], min[1]], color, ..Default::default() },
+
+        Vertex { position: [min[0], max[1]], color, ..Default::default() },
+        Vertex { position: [max[0], min[1]], color, ..Default::default() },
+        Vertex { position: [max[0], max[1]], color, ..Default::default() },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn culls_cells_outside_the_window() {
+        // 16 cells fit across the window from the grid origin, the 17th starts at its edge,
+        // plus the margin
+        let geometry = GridGeometry { origin: [-0.6; 2], cell: [0.08; 2], pitch: [0.08 + 0.02; 2] };
+        assert_eq!(geometry.visible_range(10, 0), 0..10);
+        assert_eq!(geometry.visible_range(1000, 0), 0..19);
+
+        let mut universe = Universe::new(100, 100, b"");
+        universe.toggle(50, 50);
+        let overlay = Overlay { highlights: vec![(0, [1.0; 3]), (50 * 100 + 50, [1.0; 3])], ..Default::default() };
+        // Six vertices per cell, plus four frame quads for the one visible highlight
+        assert_eq!(create_grid_vertices(&universe, &geometry, &overlay).len(), (19 * 19 + 4) * 6);
+
+        // Filled, the visible cells are all dead and only the fill and the highlight are drawn
+        let filled = Overlay { fill_dead: true, ..overlay };
+        assert_eq!(create_grid_vertices(&universe, &geometry, &filled).len(), (1 + 4) * 6);
+        universe.toggle(1, 1);
+        assert_eq!(create_grid_vertices(&universe, &geometry, &filled).len(), (1 + 1 + 4) * 6);
+    }
+
+    #[test]
+    fn births_fade_in_and_deaths_shrink_out() {
+        let geometry = GridGeometry { origin: [-0.6; 2], cell: [0.08; 2], pitch: [0.08 + 0.02; 2] };
+        let mut universe = Universe::new(2, 2, b"");
+        universe.toggle(0, 0);
+        // Cell 0 was born and cell 1 died; cell 3 stayed alive
+        universe.toggle(1, 1);
+        let overlay = Overlay { fill_dead: true, ..Default::default() };
+        let (vertices, motions) = create_smooth_grid_vertices(&universe, &geometry, &overlay, &[false, true, false, true]);
+        // The fill, then the live cells in order, with a square shrinking out of the dead one;
+        // the cell that stayed alive comes after the last motion
+        assert_eq!((vertices.len(), motions.len()), (4 * 6, 3 * 6));
+        assert!(vertices[6..12].iter().all(|vertex| vertex.color == ALIVE_COLOR));
+        assert!(motions[6..12].iter().all(|motion| motion.fade == [0, 1, 2].map(|channel| DEAD_COLOR[channel] - ALIVE_COLOR[channel])));
+        let [x, y] = geometry.cell_origin(0, 1);
+        assert!(vertices[12..18].iter().all(|vertex| vertex.color == ALIVE_COLOR));
+        assert!(motions[12..18].iter().all(|motion| motion.shrink == 1.0 && motion.anchor == [x + 0.04, y + 0.04]));
+        assert!(motions[..6].iter().all(|motion| *motion == Motion::default()));
+
+        // Without a transition the dead cell is simply gone
+        assert_eq!(create_grid_vertices(&universe, &geometry, &overlay).len(), 3 * 6);
+    }
+
+    #[test]
+    fn blocks_show_the_bases_layer() {
+        let geometry = GridGeometry { origin: [-0.6; 2], cell: [0.08; 2], pitch: [0.08 + 0.02; 2] };
+        let universe = Universe::new(2, 2, b"");
+        let mut overlay = Overlay { fill_dead: true, ..Default::default() };
+        overlay.layers.show(Layer::Bases, true);
+        overlay.layers.show(Layer::Cells, false);
+        // Only the bottom row is in the region, so the block averages one colored cell of two
+        let region = Region { row: 0, col: 0, rows: 1, cols: 2 };
+        overlay.bases = RegionColors { region, colors: vec![Some([1.0, 0.0, 0.0]), None] };
+        assert_eq!((overlay.bases.get(0, 0), overlay.bases.get(1, 0)), (Some([1.0, 0.0, 0.0]), None));
+        let vertices = create_lod_vertices(&universe, &geometry, &overlay, 2);
+        let backdrop = overlay.backdrop;
+        assert_eq!(vertices[0].color, [0.25 + backdrop[0] * 0.75, backdrop[1], backdrop[2]]);
+    }
+}
@@ -0,0 +1,353 @@
+use std::ops::Range;
+
+#[cfg(feature = "gui")]
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+#[cfg(feature = "gui")]
+use winit::window::Window;
+
+// Part of the window the grid is centered in, in clip space: above the skew track and the
+// sequence strip, with a small margin on the other sides
+const AREA_MIN: [f32; 2] = [-0.95, -0.6];
+const AREA_MAX: [f32; 2] = [0.95, 0.95];
+// Share of a cell's pitch taken by the gap to the next cell, unless set otherwise
+const DEFAULT_PADDING: f32 = 0.2;
+// Largest padding and fixed cell size (in logical pixels) that can be set
+pub const MAX_PADDING: f32 = 0.9;
+pub const MAX_CELL_SIZE: u32 = 256;
+
+// Cells beyond the window edge that still get geometry
+const CULL_MARGIN: u32 = 2;
+
+// How the grid is scaled into its area of the window
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Fit {
+    // Square cells, the whole grid visible
+    #[default]
+    Contain,
+    // Square cells, the area filled and the grid cropped along one axis
+    Cover,
+    // The area filled, cells stretched to its aspect ratio
+    Stretch,
+}
+
+impl std::str::FromStr for Fit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "contain" => Ok(Fit::Contain),
+            "cover" => Ok(Fit::Cover),
+            "stretch" => Ok(Fit::Stretch),
+            _ => Err(format!("Unknown fit '{s}', expected 'contain', 'cover' or 'stretch'")),
+        }
+    }
+}
+
+impl std::fmt::Display for Fit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Fit::Contain => write!(f, "contain"),
+            Fit::Cover => write!(f, "cover"),
+            Fit::Stretch => write!(f, "stretch"),
+        }
+    }
+}
+
+// How the cells are sized, adjustable while running: scaled into the grid area by `fit`, or
+// a fixed size that overrides it, with the gap between cells as a share of their pitch, and
+// how far the grid is moved from the middle of the area
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CellLayout {
+    pub fit: Fit,
+    pub padding: f32,
+    // Cell side in logical pixels; None fits the cells to the grid area
+    pub cell_size: Option<u32>,
+    // Right and up, in logical pixels
+    pub pan: [i32; 2],
+}
+
+impl Default for CellLayout {
+    fn default() -> Self {
+        Self { fit: Fit::default(), padding: DEFAULT_PADDING, cell_size: None, pan: [0, 0] }
+    }
+}
+
+// A window's drawable area in physical pixels. Everything is laid out in clip space and
+// only converted here, so cursor positions (physical in winit) and vertex positions agree
+// on HiDPI monitors.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+    // Physical pixels per logical pixel of the monitor the window is on
+    pub scale_factor: f64,
+}
+
+impl Viewport {
+    #[cfg(feature = "gui")]
+    pub fn new(size: PhysicalSize<u32>, scale_factor: f64) -> Self {
+        Self { width: size.width, height: size.height, scale_factor }
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn of(window: &Window) -> Self {
+        Self::new(window.inner_size(), window.scale_factor())
+    }
+
+    // Clip-space position of a point in physical pixels, e.g. the cursor
+    #[cfg(feature = "gui")]
+    pub fn to_clip(&self, position: PhysicalPosition<f64>) -> [f32; 2] {
+        let x = (position.x as f32 / self.width.max(1) as f32) * 2.0 - 1.0;
+        let y = (position.y as f32 / self.height.max(1) as f32) * -2.0 + 1.0;
+        [x, y]
+    }
+
+    // Clip-space size of one physical pixel along x and y
+    pub fn pixel(&self) -> [f32; 2] {
+        [2.0 / self.width as f32, 2.0 / self.height as f32]
+    }
+
+    // Layout of a `rows`x`cols` grid centered in the grid area, scaled by the layout's fit or
+    // at its fixed cell size. Sizes are worked out in physical pixels, so cells stay square on
+    // non-square windows and every cell edge is on a pixel boundary, with no blurred or
+    // uneven cells. The gap between cells is kept at least one logical pixel wide. A fitted
+    // grid with more cells than that leaves room for instead gets cells less than two pixels
+    // apart, not on pixel boundaries and with no gap, which are drawn in blocks (lod.rs);
+    // at a fixed cell size it overflows the area and is cropped.
+    pub fn grid_geometry(&self, rows: u32, cols: u32, layout: &CellLayout) -> GridGeometry {
+        // Minimized windows have no pixels; any layout will do
+        let size = [self.width.max(1) as f32, self.height.max(1) as f32];
+        let pixel = size.map(|size| 2.0 / size);
+        let min_gap = self.scale_factor.round().max(1.0) as f32;
+
+        let counts = [cols.max(1) as f32, rows.max(1) as f32];
+        let fitted = [0, 1].map(|axis| (AREA_MAX[axis] - AREA_MIN[axis]) / pixel[axis] / counts[axis]);
+        let padding = layout.padding.clamp(0.0, MAX_PADDING);
+        let pitch = match (layout.cell_size, layout.fit) {
+            (Some(size), _) => [(size as f64 * self.scale_factor) as f32 / (1.0 - padding); 2],
+            (None, Fit::Contain) => [fitted[0].min(fitted[1]); 2],
+            (None, Fit::Cover) => [fitted[0].max(fitted[1]); 2],
+            (None, Fit::Stretch) => fitted,
+        };
+        let subpixel = [0, 1].map(|axis| layout.cell_size.is_none() && pitch[axis] < 1.0 + min_gap);
+        let cell = [0, 1].map(|axis| if subpixel[axis] { pitch[axis] } else { (pitch[axis] * (1.0 - padding)).round().max(1.0) });
+        let pitch = [0, 1].map(|axis| if subpixel[axis] { pitch[axis] } else { pitch[axis].floor().max(cell[axis] + min_gap) });
+
+        // In f64, as a grid can be a billion cells long and panned as many pixels, where f32
+        // no longer has whole pixels
+        let origin = [0, 1].map(|axis| {
+            let extent = pitch[axis] as f64 * counts[axis] as f64 - (pitch[axis] - cell[axis]) as f64;
+            let center = ((AREA_MIN[axis] + AREA_MAX[axis]) / 2.0 + 1.0) as f64 / pixel[axis] as f64 + layout.pan[axis] as f64 * self.scale_factor;
+            ((center - extent / 2.0).round() * pixel[axis] as f64 - 1.0) as f32
+        });
+        GridGeometry {
+            origin,
+            cell: [0, 1].map(|axis| cell[axis] * pixel[axis]),
+            pitch: [0, 1].map(|axis| pitch[axis] * pixel[axis]),
+        }
+    }
+}
+
+// Where each cell of the grid is drawn in clip space. Row 0 is at the bottom. Cell indices
+// are converted in f64, since f32 stops telling neighboring cells apart past 2^24 of them.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GridGeometry {
+    pub origin: [f32; 2],
+    // Cell width and height
+    pub cell: [f32; 2],
+    // Distance between neighboring cells: cell size plus padding
+    pub pitch: [f32; 2],
+}
+
+impl GridGeometry {
+    // Start of cell `index` along an axis
+    fn start(&self, index: u32, axis: usize) -> f64 {
+        self.origin[axis] as f64 + index as f64 * self.pitch[axis] as f64
+    }
+
+    // Cell a clip-space coordinate falls in along an axis, counting its padding; negative
+    // before the first
+    fn index(&self, value: f32, axis: usize) -> f64 {
+        ((value as f64 - self.origin[axis] as f64) / self.pitch[axis] as f64).floor()
+    }
+
+    // Bottom-left corner of a cell
+    pub fn cell_origin(&self, row: u32, col: u32) -> [f32; 2] {
+        [self.start(col, 0) as f32, self.start(row, 1) as f32]
+    }
+
+    // Right (axis 0) or top (axis 1) edge of the last of `count` cells
+    pub fn end(&self, count: u32, axis: usize) -> f32 {
+        (self.start(count, axis) - (self.pitch[axis] - self.cell[axis]) as f64) as f32
+    }
+
+    // Rows (axis 1) or columns (axis 0) of cells at least partly inside the window, plus
+    // CULL_MARGIN on each side. Grids larger than the window only get geometry for this part.
+    pub fn visible_range(&self, count: u32, axis: usize) -> Range<u32> {
+        let start = self.index(-1.0, axis) - CULL_MARGIN as f64;
+        let end = ((1.0 - self.origin[axis] as f64) / self.pitch[axis] as f64).ceil() + CULL_MARGIN as f64;
+        (start.max(0.0) as u32).min(count)..(end.max(0.0) as u32).min(count)
+    }
+
+    // Inverse of `cell_origin`: the (row, col) under a clip-space point, if it is on a cell
+    // rather than the padding between cells. This is the same mapping the texture renderer's
+    // fragment shader uses, so clicks land on the cells as drawn.
+    pub fn cell_at(&self, rows: u32, cols: u32, x: f32, y: f32) -> Option<(u32, u32)> {
+        let (col, row) = (self.index(x, 0), self.index(y, 1));
+        if col < 0.0 || row < 0.0 || col >= cols as f64 || row >= rows as f64 {
+            return None;
+        }
+        let (row, col) = (row as u32, col as u32);
+        (x as f64 <= self.start(col, 0) + self.cell[0] as f64 && y as f64 <= self.start(row, 1) + self.cell[1] as f64).then_some((row, col))
+    }
+
+    // The cell whose pitch holds a clip-space point, clamped to the grid: padding counts for
+    // the cell before it, and points off the grid for the nearest edge cell. Dragging a
+    // selection uses this, so it keeps following the cursor over gaps and past the edges.
+    pub fn nearest_cell(&self, rows: u32, cols: u32, x: f32, y: f32) -> (u32, u32) {
+        let index = |value: f32, axis: usize, count: u32| self.index(value, axis).clamp(0.0, count.saturating_sub(1) as f64) as u32;
+        (index(y, 1, rows), index(x, 0, cols))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snaps_cells_to_whole_pixels() {
+        // The grid area of an 801x600 window is 760.95x465 pixels
+        let viewport = Viewport { width: 801, height: 600, scale_factor: 1.0 };
+        let pixel = viewport.pixel();
+        let pixels = |value: f32, axis: usize| value / pixel[axis];
+        let whole = |value: f32, axis: usize| (pixels(value, axis).round() - pixels(value, axis)).abs() < 1e-3;
+        for fit in [Fit::Contain, Fit::Cover, Fit::Stretch] {
+            let geometry = viewport.grid_geometry(10, 10, &CellLayout { fit, ..CellLayout::default() });
+            for axis in 0..2 {
+                assert!(whole(geometry.origin[axis] + 1.0, axis));
+                assert!(whole(geometry.cell[axis], axis));
+                assert!(whole(geometry.pitch[axis], axis));
+            }
+        }
+
+        // At 2x the gap is at least two physical pixels, even in a tiny window
+        let tiny = Viewport { width: 40, height: 40, scale_factor: 2.0 }.grid_geometry(10, 10, &CellLayout::default());
+        assert_eq!(((tiny.pitch[0] - tiny.cell[0]) / 0.05).round(), 2.0);
+    }
+
+    #[test]
+    fn centers_square_cells_in_the_grid_area() {
+        let viewport = Viewport { width: 801, height: 600, scale_factor: 1.0 };
+        let pixel = viewport.pixel();
+        let pixels = |value: f32, axis: usize| (value / pixel[axis]).round();
+
+        // 465 / 10 rows limits the pitch to 46 pixels, 37 of them cell
+        let contain = viewport.grid_geometry(10, 10, &CellLayout::default());
+        assert_eq!([pixels(contain.cell[0], 0), pixels(contain.cell[1], 1)], [37.0, 37.0]);
+        let margins = |geometry: &GridGeometry, axis: usize| {
+            [pixels(geometry.origin[axis] - AREA_MIN[axis], axis), pixels(AREA_MAX[axis] - geometry.end(10, axis), axis)]
+        };
+        let [left, right] = margins(&contain, 0);
+        assert!((left - right).abs() <= 1.0 && left > 100.0);
+        let [bottom, top] = margins(&contain, 1);
+        assert!((bottom - top).abs() <= 1.0 && bottom < 10.0);
+
+        // Cover fills the width and crops rows, stretch fills both with wide cells
+        let cover = viewport.grid_geometry(10, 10, &CellLayout { fit: Fit::Cover, ..CellLayout::default() });
+        assert_eq!(pixels(cover.pitch[0], 0), pixels(cover.pitch[1], 1));
+        assert!(margins(&cover, 0)[0] >= 0.0 && margins(&cover, 1)[0] < -100.0);
+        let stretch = viewport.grid_geometry(10, 10, &CellLayout { fit: Fit::Stretch, ..CellLayout::default() });
+        assert_eq!([pixels(stretch.pitch[0], 0), pixels(stretch.pitch[1], 1)], [76.0, 46.0]);
+    }
+
+    #[test]
+    fn huge_grids_fit_with_subpixel_cells() {
+        let viewport = Viewport { width: 801, height: 600, scale_factor: 1.0 };
+        let pixel = viewport.pixel();
+        let geometry = viewport.grid_geometry(4096, 4096, &CellLayout::default());
+        // 465 pixels for 4096 rows, with no gap
+        assert!((geometry.pitch[1] / pixel[1] - 465.0 / 4096.0).abs() < 1e-4);
+        assert_eq!(geometry.cell, geometry.pitch);
+        assert!(geometry.origin[1] >= AREA_MIN[1] - pixel[1] && geometry.end(4096, 1) <= AREA_MAX[1] + pixel[1]);
+        assert_eq!(geometry.visible_range(4096, 1), 0..4096);
+        // At a fixed size the cells stay whole pixels and overflow
+        let fixed = viewport.grid_geometry(4096, 4096, &CellLayout { cell_size: Some(1), ..CellLayout::default() });
+        assert_eq!(((fixed.pitch[0] - fixed.cell[0]) / pixel[0]).round(), 1.0);
+        assert!(fixed.visible_range(4096, 0).len() < 4096);
+    }
+
+    #[test]
+    fn long_grids_keep_cells_apart_past_f32_precision() {
+        // 3 * 2^24 columns of 5 pixels, centered: the columns in view are past 2^24, where
+        // f32 steps by 2, and 120 million pixels from the grid's left edge
+        let viewport = Viewport { width: 800, height: 600, scale_factor: 1.0 };
+        let cols = 3 << 24;
+        let geometry = viewport.grid_geometry(2, cols, &CellLayout { cell_size: Some(4), ..CellLayout::default() });
+        let pitch = geometry.pitch[0];
+        assert!((pitch / viewport.pixel()[0] - 5.0).abs() < 1e-3);
+        let visible = geometry.visible_range(cols, 0);
+        assert!(visible.start > 1 << 24 && visible.len() < 200, "{visible:?}");
+        for col in visible.clone().skip(2).take(visible.len() - 4) {
+            let [left, bottom] = geometry.cell_origin(0, col);
+            assert!((geometry.cell_origin(0, col + 1)[0] - left - pitch).abs() < 1e-5);
+            assert_eq!(geometry.cell_at(2, cols, left + geometry.cell[0] / 2.0, bottom + geometry.cell[1] / 2.0), Some((0, col)));
+        }
+        // Above the grid, the nearest cell is in the top row
+        let col = visible.start + 10;
+        assert_eq!(geometry.nearest_cell(2, cols, geometry.cell_origin(0, col)[0] + geometry.cell[0] / 2.0, 1.0), (1, col));
+    }
+
+    #[test]
+    fn fixed_cell_size_and_padding() {
+        let viewport = Viewport { width: 800, height: 600, scale_factor: 2.0 };
+        let layout = CellLayout { cell_size: Some(12), padding: 0.5, ..CellLayout::default() };
+        let geometry = viewport.grid_geometry(10, 10, &layout);
+        let pixel = viewport.pixel();
+        // 12 logical pixels are 24 physical ones, as much gap again at padding 0.5
+        assert_eq!([(geometry.cell[0] / pixel[0]).round(), (geometry.pitch[1] / pixel[1]).round()], [24.0, 48.0]);
+        // No padding still leaves the minimum gap
+        let tight = viewport.grid_geometry(10, 10, &CellLayout { padding: 0.0, ..layout });
+        assert_eq!(((tight.pitch[0] - tight.cell[0]) / pixel[0]).round(), 2.0);
+        // Panning moves the whole grid by as many logical pixels
+        let panned = viewport.grid_geometry(10, 10, &CellLayout { pan: [30, -5], ..layout });
+        assert_eq!([0, 1].map(|axis| ((panned.origin[axis] - geometry.origin[axis]) / pixel[axis]).round()), [60.0, -10.0]);
+    }
+
+    #[test]
+    #[cfg(feature = "gui")]
+    fn cursor_maps_back_to_its_cell() {
+        let viewport = Viewport { width: 1600, height: 1200, scale_factor: 2.0 };
+        let geometry = viewport.grid_geometry(10, 10, &CellLayout::default());
+        let [x, y] = geometry.cell_origin(3, 5);
+        // A physical pixel just inside the cell's bottom-left corner
+        let cursor = PhysicalPosition::new(((x + 1.0) / 2.0 * 1600.0 + 1.0) as f64, ((1.0 - y) / 2.0 * 1200.0 - 1.0) as f64);
+        let [x, y] = viewport.to_clip(cursor);
+        assert_eq!(geometry.cell_at(10, 10, x, y), Some((3, 5)));
+        // The padding right of the cell belongs to no cell
+        assert_eq!(geometry.cell_at(10, 10, x + geometry.cell[0] + 0.005, y), None);
+        assert_eq!(geometry.cell_at(10, 10, -0.99, -0.99), None);
+        assert_eq!(geometry.nearest_cell(10, 10, x + geometry.cell[0] + 0.005, y), (3, 5));
+        assert_eq!(geometry.nearest_cell(10, 10, -0.99, 0.99), (9, 0));
+    }
+
+    #[test]
+    fn every_drawn_cell_is_hit_where_it_is_drawn() {
+        for (viewport, rows, cols) in [(Viewport { width: 801, height: 600, scale_factor: 1.0 }, 37, 53), (Viewport { width: 2560, height: 1440, scale_factor: 1.5 }, 64, 64)] {
+            let layouts = [CellLayout::default(), CellLayout { fit: Fit::Stretch, padding: 0.0, ..CellLayout::default() }, CellLayout { cell_size: Some(5), ..CellLayout::default() }];
+            for layout in layouts {
+                let geometry = viewport.grid_geometry(rows, cols, &layout);
+                let pixel = viewport.pixel();
+                for row in geometry.visible_range(rows, 1) {
+                    for col in geometry.visible_range(cols, 0) {
+                        let [left, bottom] = geometry.cell_origin(row, col);
+                        // Centers of the cell's first and last physical pixels
+                        for [x, y] in [[left + pixel[0] / 2.0, bottom + pixel[1] / 2.0], [left + geometry.cell[0] - pixel[0] / 2.0, bottom + geometry.cell[1] - pixel[1] / 2.0]] {
+                            assert_eq!(geometry.cell_at(rows, cols, x, y), Some((row, col)), "{layout:?}");
+                            assert_eq!(geometry.nearest_cell(rows, cols, x, y), (row, col));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}